@@ -0,0 +1,795 @@
+use std::{
+  collections::{ BTreeMap, BTreeSet, HashSet },
+  fmt::Write,
+};
+use crate::{
+  backend::{
+    Diagnostics,
+    SaturatingCast,
+    binary_op,
+    flatten_swizzle,
+    float_literal,
+    if_chain,
+    padding::{ Packing, PaddedMember, PaddedStruct, PaddedStructs },
+    parenthesize_target,
+    saturating_cast,
+    struct_name,
+  },
+  model::{
+    BinaryOpModel,
+    BufferAccessMode,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LiteralModel,
+    SWIZZLE_LETTERS,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructTypeModel,
+    TypeModel,
+    UnaryOpModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * Lower an entrypoint of a checked shader file to a GLSL 4.50 compute
+ * shader.  A GLSL shader has a single entrypoint, so each is generated
+ * on its own, along with all of the structs, resources and functions
+ * of the file.
+ *
+ * Buffers are `std430` blocks and the uniforms a `std140` block.  A
+ * buffer of an array is an anonymous block holding the array, and a
+ * buffer of a struct is a block of its fields.  Resources in group 0
+ * are declared without a set, so that the output also suits OpenGL;
+ * other groups need Vulkan GLSL.
+ *
+ * GLSL can't align or size struct members, so fields placed by
+ * `@align`, `@size` or `@packed` are reached with padding members.
+ * Fields that GLSL would align further than their declared offset are
+ * reported.  `f16` and 64-bit integers enable the explicit arithmetic
+ * types extensions.
+ */
+pub fn generate_glsl(model: &ShaderFileModel, entrypoint: &str)
+  -> Result<String, Vec<Diagnostic>>
+{
+  let mut gen = GlslGen {
+    out: String::new(),
+    diagnostics: Diagnostics::default(),
+    structs: PaddedStructs::new("GLSL"),
+    rem_types: BTreeSet::new(),
+    cast_helpers: BTreeMap::new(),
+    uses_f16: false,
+    uses_int64: false,
+  };
+  let Some(entrypoint) = model.entrypoints().iter().find(|e| e.name() == entrypoint) else {
+    gen.diagnostics.error(format!("No entrypoint named `{}`.", entrypoint));
+    return Err(gen.diagnostics.into_vec());
+  };
+
+  let mut declared = HashSet::new();
+  for ty in model.structs() {
+    let TypeModel::Struct(struct_ty) = &**ty else {
+      unreachable!("Non-struct type in shader file structs");
+    };
+    gen.write_struct(struct_ty, &mut declared);
+  }
+  gen.write_resources(model);
+  let decls = std::mem::take(&mut gen.out);
+  if !model.funcs().is_empty() {
+    writeln!(gen.out).unwrap();
+    for func in model.funcs() {
+      let signature = gen.signature(func);
+      writeln!(gen.out, "{};", signature).unwrap();
+    }
+  }
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  gen.write_main(entrypoint);
+  if !gen.diagnostics.is_empty() {
+    return Err(gen.diagnostics.into_vec());
+  }
+
+  let mut out = String::new();
+  writeln!(out,
+    "// Generated by dubgsl from `{}`, entrypoint `{}`.  Do not edit.",
+    model.path(), entrypoint.name()
+  ).unwrap();
+  writeln!(out, "#version 450").unwrap();
+  if gen.uses_f16 {
+    writeln!(out, "#extension GL_EXT_shader_explicit_arithmetic_types_float16 : require")
+      .unwrap();
+    writeln!(out, "#extension GL_EXT_shader_16bit_storage : require").unwrap();
+  }
+  if gen.uses_int64 {
+    writeln!(out, "#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require")
+      .unwrap();
+  }
+  let [x, y, z] = entrypoint.workgroup_size();
+  writeln!(out).unwrap();
+  writeln!(out,
+    "layout(local_size_x = {}, local_size_y = {}, local_size_z = {}) in;", x, y, z
+  ).unwrap();
+  out.push_str(&decls);
+  for (ty, is_float) in &gen.rem_types {
+    // GLSL's `%` is undefined for negative operands, and `mod` of floats
+    // rounds the quotient down, where the remainder truncates it.
+    let quotient = if *is_float { "trunc(a / b)" } else { "(a / b)" };
+    writeln!(out).unwrap();
+    writeln!(out, "{} {}({} a, {} b) {{", ty, REM, ty, ty).unwrap();
+    writeln!(out, "  return a - b * {};", quotient).unwrap();
+    writeln!(out, "}}").unwrap();
+  }
+  for helper in gen.cast_helpers.values() {
+    writeln!(out).unwrap();
+    out.push_str(helper);
+  }
+  out.push_str(&gen.out);
+  Ok(out)
+}
+
+/**
+ * The state of GLSL generation.  Diagnostics are collected rather than
+ * stopping at the first, and the extensions and helper functions used
+ * by the output are noted so that they can be declared ahead of it.
+ */
+struct GlslGen {
+  out: String,
+  diagnostics: Diagnostics,
+  /** Structs with their padding members. */
+  structs: PaddedStructs,
+  /** The types needing a remainder helper, and whether they are float. */
+  rem_types: BTreeSet<(String, bool)>,
+  /**
+   * Saturating cast helpers, by component count and name, so that the
+   * scalar helpers come before the vector helpers calling them.
+   */
+  cast_helpers: BTreeMap<(u8, String), String>,
+  uses_f16: bool,
+  uses_int64: bool,
+}
+
+impl GlslGen {
+  /**
+   * Write a struct after the structs it contains, as GLSL requires.  A
+   * struct ending in a runtime-sized array can only be a buffer block,
+   * so it is padded but not declared.
+   */
+  fn write_struct(&mut self, struct_ty: &StructTypeModel, declared: &mut HashSet<String>) {
+    let name = struct_name(struct_ty, ident);
+    if !declared.insert(name.clone()) {
+      return;
+    }
+    for field in &struct_ty.fields {
+      let mut ty = &field.ty;
+      while let TypeModel::Array(array_ty) = &**ty {
+        ty = &array_ty.elem;
+      }
+      if let TypeModel::Struct(field_struct) = &**ty {
+        self.write_struct(field_struct, declared);
+      }
+    }
+    let Some(padded) = self.pad(struct_ty, Packing::Std430) else {
+      return;
+    };
+    if !struct_ty.fields.last().is_some_and(|field| field.ty.is_runtime_sized()) {
+      let members = self.members(struct_ty, &padded);
+      writeln!(self.out).unwrap();
+      writeln!(self.out, "struct {} {{", name).unwrap();
+      for member in members {
+        writeln!(self.out, "  {};", member).unwrap();
+      }
+      writeln!(self.out, "}};").unwrap();
+    }
+    self.structs.insert(struct_ty, padded);
+  }
+
+  /**
+   * Pad a struct for a GLSL packing, reporting why it can't be.
+   */
+  fn pad(&mut self, struct_ty: &StructTypeModel, packing: Packing) -> Option<PaddedStruct> {
+    match self.structs.pad(struct_ty, packing) {
+      Ok(padded) => Some(padded),
+      Err(errors) => {
+        for error in errors {
+          self.diagnostics.error(error);
+        }
+        None
+      },
+    }
+  }
+
+  /**
+   * The member declarations of a padded struct.
+   */
+  fn members(&mut self, struct_ty: &StructTypeModel, padded: &PaddedStruct) -> Vec<String> {
+    padded.members(struct_ty)
+      .map(|member| match member {
+        PaddedMember::Field(field) => self.declaration(&field.ty, &ident(field.name())),
+        PaddedMember::Pad(pad) => self.declaration(&pad.ty, pad.name()),
+      })
+      .collect()
+  }
+
+  /**
+   * Write the uniforms block and a block for each buffer.
+   */
+  fn write_resources(&mut self, model: &ShaderFileModel) {
+    if let Some(uniforms) = model.uniforms() {
+      let TypeModel::Struct(struct_ty) = &**uniforms.ty() else {
+        unreachable!("Non-struct uniforms type");
+      };
+      if let Some(padded) = self.pad(struct_ty, Packing::Std140) {
+        let binding = uniforms.binding();
+        let members = self.members(struct_ty, &padded);
+        writeln!(self.out).unwrap();
+        writeln!(self.out,
+          "layout({}) uniform {} {{",
+          layout_qualifier("std140", binding.group(), binding.binding()), UNIFORMS_BLOCK
+        ).unwrap();
+        for member in members {
+          writeln!(self.out, "  {};", member).unwrap();
+        }
+        writeln!(self.out, "}} uniforms;").unwrap();
+      }
+    }
+
+    for buffer in model.buffers() {
+      let (members, instance) = match &**buffer.ty() {
+        TypeModel::Struct(struct_ty) => {
+          let Some(padded) = self.structs.get(struct_ty).cloned() else {
+            continue;
+          };
+          let members = self.members(struct_ty, &padded);
+          (members, format!(" {}", ident(buffer.name())))
+        },
+        ty => (vec![self.declaration(ty, &ident(buffer.name()))], String::new()),
+      };
+      let binding = buffer.binding();
+      let access = match buffer.mode() {
+        BufferAccessMode::Read => "readonly ",
+        BufferAccessMode::Write => "writeonly ",
+        BufferAccessMode::ReadWrite => "",
+      };
+      writeln!(self.out).unwrap();
+      writeln!(self.out,
+        "layout({}) {}buffer dubgsl_buffer_{} {{",
+        layout_qualifier("std430", binding.group(), binding.binding()),
+        access,
+        buffer.name()
+      ).unwrap();
+      for member in members {
+        writeln!(self.out, "  {};", member).unwrap();
+      }
+      writeln!(self.out, "}}{};", instance).unwrap();
+    }
+  }
+
+  fn signature(&mut self, func: &FuncModel) -> String {
+    let args = func.args.iter()
+      .map(|arg| self.declaration(&arg.ty, &ident(&arg.name.name)))
+      .collect::<Vec<_>>();
+    let return_ty = if func.return_ty.is_void() {
+      "void".to_string()
+    } else {
+      self.type_name(&func.return_ty)
+    };
+    format!("{} {}({})", return_ty, ident(&func.name.name), args.join(", "))
+  }
+
+  fn write_func(&mut self, func: &FuncModel) {
+    let signature = self.signature(func);
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "{} {{", signature).unwrap();
+    self.write_block(&func.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Write an entrypoint as the shader's `main`.  The invocation id
+   * argument is the global invocation id, narrowed to the entrypoint's
+   * dimensions.
+   */
+  fn write_main(&mut self, entrypoint: &EntrypointModel) {
+    let (ty, components) = match entrypoint.dims {
+      EntrypointDims::D1 => ("uint", ".x"),
+      EntrypointDims::D2 => ("uvec2", ".xy"),
+      EntrypointDims::D3 => ("uvec3", ""),
+    };
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "void main() {{").unwrap();
+    writeln!(self.out,
+      "  {} {} = gl_GlobalInvocationID{};", ty, ident(&entrypoint.arg_name.name), components
+    ).unwrap();
+    self.write_block(&entrypoint.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_block(&mut self, statements: &[StatementModel], depth: usize) {
+    for stmt in statements {
+      self.write_stmt(stmt, depth);
+    }
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let decl = self.declaration(&let_stmt.value.ty, &ident(&let_stmt.name.name));
+        let value = self.expr(&let_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, decl, value).unwrap();
+      },
+      StatementModel::Var(var_stmt) => {
+        let decl = self.declaration(&var_stmt.value.ty, &ident(&var_stmt.name.name));
+        let value = self.expr(&var_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, decl, value).unwrap();
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        // GLSL assigns swizzles directly, so only nesting is removed.
+        let (place, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        let mut lvalue = self.expr(place);
+        if !components.is_empty() {
+          lvalue.push('.');
+          lvalue.extend(components.iter().map(|&c| SWIZZLE_LETTERS[c as usize]));
+        }
+        let value = self.expr(&mutate_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, lvalue, value).unwrap();
+      },
+      StatementModel::Exec(exec_stmt) => {
+        let expr = self.expr(&exec_stmt.expr);
+        writeln!(self.out, "{}{};", indent, expr).unwrap();
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          writeln!(self.out, "{}return {};", indent, value).unwrap();
+        },
+        None => writeln!(self.out, "{}return;", indent).unwrap(),
+      },
+      StatementModel::If(if_stmt) => {
+        for (i, (cond, block)) in if_chain(if_stmt).into_iter().enumerate() {
+          let close = if i == 0 { "" } else { "} else " };
+          match cond {
+            Some(cond) => {
+              let cond = self.expr(cond);
+              writeln!(self.out, "{}{}if ({}) {{", indent, close, cond).unwrap();
+            },
+            None => writeln!(self.out, "{}}} else {{", indent).unwrap(),
+          }
+          self.write_block(block, depth + 1);
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Loop(loop_stmt) => {
+        writeln!(self.out, "{}while (true) {{", indent).unwrap();
+        self.write_block(&loop_stmt.block, depth + 1);
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> String {
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => self.literal(literal),
+      ExpressionModelKind::Local(name) => ident(&name.name),
+      ExpressionModelKind::Uniforms => {
+        // The uniforms are a block, which has no type to copy it into.
+        self.diagnostics.error(
+          "The uniforms cannot be used as a whole value in GLSL, only their fields."
+            .to_string()
+        );
+        "uniforms".to_string()
+      },
+      ExpressionModelKind::Buffer(name) => ident(&name.name),
+      ExpressionModelKind::Index(index_expr) => {
+        let target = self.postfix_target(&index_expr.target);
+        let mut index = self.expr(&index_expr.index);
+        // GLSL only indexes by 32-bit integers.
+        match index_expr.index.ty.as_numeric_scalar() {
+          Some(ScalarNumericTypeModel::I64) => index = format!("int({})", index),
+          Some(ScalarNumericTypeModel::U64) => index = format!("uint({})", index),
+          _ => {},
+        }
+        format!("{}[{}]", target, index)
+      },
+      ExpressionModelKind::ArrayLength(length_expr) =>
+        format!("uint({}.length())", self.postfix_target(&length_expr.target)),
+      ExpressionModelKind::Field(field_expr) => {
+        let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+          unreachable!("Field access on non-struct type");
+        };
+        let field = &struct_ty.fields[field_expr.field as usize];
+        let target = if matches!(field_expr.target.kind, ExpressionModelKind::Uniforms) {
+          "uniforms".to_string()
+        } else {
+          self.postfix_target(&field_expr.target)
+        };
+        format!("{}.{}", target, ident(field.name()))
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => format!("{}.{}",
+        self.postfix_target(&swizzle_expr.target),
+        swizzle_expr.component_letters()
+      ),
+      ExpressionModelKind::Construct(construct_expr) => {
+        let ty = self.type_name(&expr.ty);
+        let mut args = construct_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        // Padding members of structs are constructed as zero.
+        if let TypeModel::Struct(struct_ty) = &*expr.ty {
+          if let Some(padded) = self.structs.get(struct_ty) {
+            let mut fields = args.into_iter();
+            args = padded.members(struct_ty)
+              .map(|member| match member {
+                PaddedMember::Field(_) =>
+                  fields.next().expect("Struct is constructed from each field"),
+                PaddedMember::Pad(pad)
+                  if pad.ty.as_numeric_scalar() == Some(ScalarNumericTypeModel::F16) =>
+                  "float16_t(0.0)".to_string(),
+                PaddedMember::Pad(_) => "0u".to_string(),
+              })
+              .collect();
+          }
+        }
+        format!("{}({})", ty, args.join(", "))
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let args = call_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        format!("{}({})", ident(&call_expr.func.name), args.join(", "))
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let op = match unary_expr.op {
+          UnaryOpModel::Negate => "-",
+          UnaryOpModel::Not => "!",
+          UnaryOpModel::Complement => "~",
+        };
+        let subexpr = self.expr(&unary_expr.subexpr);
+        if matches!(unary_expr.subexpr.kind,
+          ExpressionModelKind::Unary(_) | ExpressionModelKind::Binary(_)
+        ) || subexpr.starts_with('-') {
+          format!("{}({})", op, subexpr)
+        } else {
+          format!("{}{}", op, subexpr)
+        }
+      },
+      ExpressionModelKind::Binary(binary_expr) => {
+        if is_rem_call(expr) {
+          return self.rem(expr, &binary_expr.lhs, &binary_expr.rhs);
+        }
+        let lhs = self.operand(&binary_expr.lhs);
+        let rhs = self.operand(&binary_expr.rhs);
+        format!("{} {} {}", lhs, binary_op(binary_expr.op), rhs)
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        if let Some(cast) = saturating_cast(expr) {
+          return self.saturating_cast(expr, &cast_expr.subexpr, cast);
+        }
+        let ty = self.type_name(&expr.ty);
+        let subexpr = self.expr(&cast_expr.subexpr);
+        format!("{}({})", ty, subexpr)
+      },
+    }
+  }
+
+  /**
+   * An expression that is indexed or has a member selected.
+   */
+  fn postfix_target(&mut self, target: &ExpressionModel) -> String {
+    let target_expr = self.expr(target);
+    parenthesize_target(target, target_expr)
+  }
+
+  /**
+   * An operand of a binary operation.  Nested operations are always
+   * parenthesized, for clarity.
+   */
+  fn operand(&mut self, operand: &ExpressionModel) -> String {
+    let operand_expr = self.expr(operand);
+    if matches!(operand.kind, ExpressionModelKind::Binary(_)) && !is_rem_call(operand) {
+      format!("({})", operand_expr)
+    } else {
+      operand_expr
+    }
+  }
+
+  /**
+   * The truncated remainder of signed or float operands, through a
+   * helper function of the result type.  A scalar operand of a vector
+   * remainder is splatted to match.
+   */
+  fn rem(&mut self, expr: &ExpressionModel, lhs: &ExpressionModel, rhs: &ExpressionModel)
+    -> String
+  {
+    let ty = self.type_name(&expr.ty);
+    let is_float = expr.ty.numeric_element().is_some_and(|e| e.is_float());
+    self.rem_types.insert((ty.clone(), is_float));
+    let mut operand = |operand: &ExpressionModel| {
+      let operand_expr = self.expr(operand);
+      if operand.ty == expr.ty {
+        operand_expr
+      } else {
+        format!("{}({})", ty, operand_expr)
+      }
+    };
+    let lhs = operand(lhs);
+    let rhs = operand(rhs);
+    format!("{}({}, {})", REM, lhs, rhs)
+  }
+
+  /**
+   * A cast from floats to integers, through a helper function of the
+   * result type, as GLSL's conversions are undefined out of range.
+   */
+  fn saturating_cast(&mut self,
+    expr: &ExpressionModel,
+    subexpr: &ExpressionModel,
+    cast: SaturatingCast,
+  ) -> String {
+    let scalar_ty = self.type_name(&TypeModel::new_scalar(cast.to));
+    let scalar_helper = format!("{}_{}", CAST, scalar_ty);
+    if !self.cast_helpers.contains_key(&(1, scalar_helper.clone())) {
+      let [zero, min, max] = [cast.zero, cast.min, cast.max].map(|l| self.literal(&l));
+      let mut body = String::new();
+      writeln!(body, "{} {}(float a) {{", scalar_ty, scalar_helper).unwrap();
+      writeln!(body,
+        "  return isnan(a) ? {} : a <= {} ? {} : a >= {} ? {} : {}(a);",
+        zero, float_literal(cast.lo, FROM_BITS), min, float_literal(cast.hi, FROM_BITS), max,
+        scalar_ty
+      ).unwrap();
+      writeln!(body, "}}").unwrap();
+      self.cast_helpers.insert((1, scalar_helper.clone()), body);
+    }
+
+    let ty = self.type_name(&expr.ty);
+    let float_ty = match &*expr.ty {
+      TypeModel::Vector(vector_ty) =>
+        self.type_name(&TypeModel::new_vector(ScalarNumericTypeModel::F32, vector_ty.dims)),
+      _ => "float".to_string(),
+    };
+    let helper = match &*expr.ty {
+      TypeModel::Vector(vector_ty) => {
+        let dims = vector_ty.dims as u8;
+        let helper = format!("{}_{}", CAST, ty);
+        self.cast_helpers.entry((dims, helper.clone())).or_insert_with(|| {
+          let components = SWIZZLE_LETTERS[..dims as usize].iter()
+            .map(|c| format!("{}(a.{})", scalar_helper, c))
+            .collect::<Vec<_>>();
+          format!(
+            "{} {}({} a) {{\n  return {}({});\n}}\n",
+            ty, helper, float_ty, ty, components.join(", ")
+          )
+        });
+        helper
+      },
+      _ => scalar_helper,
+    };
+    let mut subexpr = self.expr(subexpr);
+    if cast.from_f16 {
+      subexpr = format!("{}({})", float_ty, subexpr);
+    }
+    format!("{}({})", helper, subexpr)
+  }
+
+  fn literal(&mut self, literal: &LiteralModel) -> String {
+    match *literal {
+      LiteralModel::Bool(value) => value.to_string(),
+      // The magnitudes of the minimum integers are out of range for
+      // their literals.
+      LiteralModel::I32(i32::MIN) => "(-2147483647 - 1)".to_string(),
+      LiteralModel::I32(value) => value.to_string(),
+      LiteralModel::U32(value) => format!("{}u", value),
+      LiteralModel::F32(bits) => float_literal(f32::from_bits(bits), FROM_BITS),
+      LiteralModel::F16(bits) => {
+        self.uses_f16 = true;
+        let value = f32::from_bits(bits);
+        if value.is_finite() {
+          format!("{:?}hf", value)
+        } else {
+          format!("float16_t({})", float_literal(value, FROM_BITS))
+        }
+      },
+      LiteralModel::I64(i64::MIN) => {
+        self.uses_int64 = true;
+        "(-9223372036854775807l - 1l)".to_string()
+      },
+      LiteralModel::I64(value) => {
+        self.uses_int64 = true;
+        format!("{}l", value)
+      },
+      LiteralModel::U64(value) => {
+        self.uses_int64 = true;
+        format!("{}ul", value)
+      },
+    }
+  }
+
+  /**
+   * A declaration of a name with a type.  GLSL declares runtime-sized
+   * arrays with the dimension after the name.
+   */
+  fn declaration(&mut self, ty: &TypeModel, name: &str) -> String {
+    match ty {
+      TypeModel::Array(array_ty) if array_ty.len.is_none() =>
+        format!("{} {}[]", self.type_name(&array_ty.elem), name),
+      _ => format!("{} {}", self.type_name(ty), name),
+    }
+  }
+
+  fn type_name(&mut self, ty: &TypeModel) -> String {
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) =>
+        "bool".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) =>
+        "void".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => {
+        self.note_scalar(*scalar);
+        match scalar {
+          ScalarNumericTypeModel::F16 => "float16_t",
+          ScalarNumericTypeModel::F32 => "float",
+          ScalarNumericTypeModel::I32 => "int",
+          ScalarNumericTypeModel::U32 => "uint",
+          ScalarNumericTypeModel::I64 => "int64_t",
+          ScalarNumericTypeModel::U64 => "uint64_t",
+        }.to_string()
+      },
+      TypeModel::Vector(vector_ty) => {
+        self.note_scalar(vector_ty.scalar);
+        format!("{}vec{}", vector_prefix(vector_ty.scalar), vector_ty.dims as u8)
+      },
+      TypeModel::Matrix(matrix_ty) => {
+        self.note_scalar(matrix_ty.scalar);
+        format!(
+          "{}mat{}x{}",
+          vector_prefix(matrix_ty.scalar),
+          matrix_ty.cols as u8,
+          matrix_ty.rows as u8
+        )
+      },
+      TypeModel::Array(array_ty) => {
+        // GLSL lists the dimensions of nested arrays outermost first.
+        let elem = self.type_name(&array_ty.elem);
+        let split = elem.find('[').unwrap_or(elem.len());
+        let len = array_ty.len.map(|len| len.to_string()).unwrap_or_default();
+        format!("{}[{}]{}", &elem[..split], len, &elem[split..])
+      },
+      TypeModel::Struct(struct_ty) => struct_name(struct_ty, ident),
+    }
+  }
+
+  fn note_scalar(&mut self, scalar: ScalarNumericTypeModel) {
+    match scalar {
+      ScalarNumericTypeModel::F16 => self.uses_f16 = true,
+      ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 => self.uses_int64 = true,
+      _ => {},
+    }
+  }
+}
+
+/**
+ * The name of the uniforms block.
+ */
+const UNIFORMS_BLOCK: &str = "dubgsl_uniforms_block";
+
+/**
+ * The name of the remainder helper functions.
+ */
+const REM: &str = "dubgsl_rem";
+
+/**
+ * The prefix of the saturating cast helper functions.
+ */
+const CAST: &str = "dubgsl_cast";
+
+/**
+ * The function making a float from its bits, for non-finite literals.
+ */
+const FROM_BITS: &str = "uintBitsToFloat";
+
+/**
+ * Whether an expression is a remainder written as a call to a helper,
+ * which is needed for signed and float operands.
+ */
+fn is_rem_call(expr: &ExpressionModel) -> bool {
+  matches!(&expr.kind, ExpressionModelKind::Binary(binary_expr)
+    if binary_expr.op == BinaryOpModel::Mod &&
+      expr.ty.numeric_element().is_some_and(|e| e.is_signed())
+  )
+}
+
+fn layout_qualifier(packing: &str, group: u32, binding: u32) -> String {
+  if group == 0 {
+    format!("{}, binding = {}", packing, binding)
+  } else {
+    format!("{}, set = {}, binding = {}", packing, group, binding)
+  }
+}
+
+fn vector_prefix(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::F16 => "f16",
+    ScalarNumericTypeModel::F32 => "",
+    ScalarNumericTypeModel::I32 => "i",
+    ScalarNumericTypeModel::U32 => "u",
+    ScalarNumericTypeModel::I64 => "i64",
+    ScalarNumericTypeModel::U64 => "u64",
+  }
+}
+
+/**
+ * A name as a GLSL identifier.  Names that GLSL reserves or that start
+ * with `gl_`, and names starting with the `dubgsl_` prefix of generated
+ * names, get the prefix.
+ */
+fn ident(name: &str) -> String {
+  if name.starts_with("gl_") || name.starts_with("dubgsl_") || RESERVED.contains(&name) {
+    format!("dubgsl_{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/**
+ * GLSL keywords and reserved words, the types of the extensions the
+ * output may enable, and the built-in functions, which user functions
+ * could otherwise overload.
+ */
+const RESERVED: &[&str] = &[
+  // Keywords.
+  "active", "asm", "atomic_uint", "attribute", "bool", "break", "buffer",
+  "bvec2", "bvec3", "bvec4", "case", "cast", "centroid", "class", "coherent",
+  "common", "const", "continue", "default", "discard", "dmat2", "dmat2x2",
+  "dmat2x3", "dmat2x4", "dmat3", "dmat3x2", "dmat3x3", "dmat3x4", "dmat4",
+  "dmat4x2", "dmat4x3", "dmat4x4", "do", "double", "dvec2", "dvec3", "dvec4",
+  "else", "enum", "extern", "external", "false", "filter", "fixed", "flat",
+  "float", "for", "fvec2", "fvec3", "fvec4", "goto", "half", "highp", "hvec2",
+  "hvec3", "hvec4", "if", "in", "inline", "inout", "input", "int",
+  "interface", "invariant", "isampler2D", "isampler3D", "ivec2", "ivec3",
+  "ivec4", "layout", "long", "lowp", "mat2", "mat2x2", "mat2x3", "mat2x4",
+  "mat3", "mat3x2", "mat3x3", "mat3x4", "mat4", "mat4x2", "mat4x3", "mat4x4",
+  "mediump", "namespace", "noinline", "noperspective", "out", "output",
+  "partition", "patch", "precise", "precision", "public", "readonly",
+  "resource", "restrict", "return", "sample", "sampler1D", "sampler2D",
+  "sampler3D", "samplerCube", "shared", "short", "sizeof", "smooth", "static",
+  "struct", "subroutine", "superp", "switch", "template", "this", "true",
+  "typedef", "uint", "uniform", "union", "unsigned", "using", "usampler2D",
+  "usampler3D", "uvec2", "uvec3", "uvec4", "varying", "vec2", "vec3", "vec4",
+  "void", "volatile", "while", "writeonly",
+
+  // Extension types.
+  "float16_t", "f16vec2", "f16vec3", "f16vec4", "f16mat2x2", "f16mat2x3",
+  "f16mat2x4", "f16mat3x2", "f16mat3x3", "f16mat3x4", "f16mat4x2",
+  "f16mat4x3", "f16mat4x4", "int64_t", "i64vec2", "i64vec3", "i64vec4",
+  "uint64_t", "u64vec2", "u64vec3", "u64vec4",
+
+  // Built-in functions.
+  "abs", "acos", "acosh", "all", "any", "asin", "asinh", "atan", "atanh",
+  "atomicAdd", "atomicAnd", "atomicCompSwap", "atomicExchange", "atomicMax",
+  "atomicMin", "atomicOr", "atomicXor", "barrier", "bitCount",
+  "bitfieldExtract", "bitfieldInsert", "bitfieldReverse", "ceil", "clamp",
+  "cos", "cosh", "cross", "degrees", "determinant", "distance", "dot",
+  "equal", "exp", "exp2", "faceforward", "findLSB", "findMSB",
+  "floatBitsToInt", "floatBitsToUint", "floor", "fma", "fract", "frexp",
+  "greaterThan", "greaterThanEqual", "groupMemoryBarrier", "imulExtended",
+  "intBitsToFloat", "inverse", "inversesqrt", "isinf", "isnan", "ldexp",
+  "length", "lessThan", "lessThanEqual", "log", "log2", "matrixCompMult",
+  "max", "memoryBarrier", "memoryBarrierBuffer", "memoryBarrierShared", "min",
+  "mix", "mod", "modf", "normalize", "not", "notEqual", "outerProduct",
+  "packHalf2x16", "packSnorm2x16", "packSnorm4x8", "packUnorm2x16",
+  "packUnorm4x8", "pow", "radians", "reflect", "refract", "round",
+  "roundEven", "sign", "sin", "sinh", "smoothstep", "sqrt", "step", "tan",
+  "tanh", "transpose", "trunc", "uaddCarry", "uintBitsToFloat",
+  "umulExtended", "unpackHalf2x16", "unpackSnorm2x16", "unpackSnorm4x8",
+  "unpackUnorm2x16", "unpackUnorm4x8", "usubBorrow",
+
+  // Names used by the output.
+  "main", "uniforms",
+];
@@ -0,0 +1,974 @@
+use std::{
+  collections::{ BTreeMap, HashSet },
+  fmt::{ self, Write },
+};
+use crate::{
+  backend::{
+    Diagnostics,
+    SaturatingCast,
+    binary_op,
+    flatten_swizzle,
+    float_literal,
+    if_chain,
+    padding::{ Packing, PaddedMember, PaddedStruct, PaddedStructs },
+    parenthesize_target,
+    saturating_cast,
+    struct_name,
+  },
+  model::{
+    BinaryOpModel,
+    BufferAccessMode,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LayoutRules,
+    LiteralModel,
+    ResourceBindingModel,
+    SWIZZLE_LETTERS,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructTypeModel,
+    TypeLayoutKind,
+    TypeModel,
+    UnaryOpModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * Lower a checked shader file to HLSL compute shaders, one kernel for
+ * each entrypoint, for Shader Model 6.2 with 16-bit types enabled.
+ *
+ * The uniforms are a `cbuffer` holding a `Uniforms` struct.  A buffer
+ * of an array is a `StructuredBuffer`, or a `RWStructuredBuffer` if it
+ * can be written.  A structured buffer only holds an array, so a buffer
+ * of a struct is a byte address buffer instead, whose data is loaded
+ * and stored at the offsets of its layout.  Resources in group `g` are
+ * in register space `g`.
+ *
+ * HLSL can't align or size struct members, so fields placed by
+ * `@align`, `@size` or `@packed` are reached with padding members.
+ * Fields that HLSL packs elsewhere are reported.  Matrices are
+ * declared row-major with a row for each column, so that they are laid
+ * out as columns are, and are multiplied in the opposite order.
+ */
+pub fn generate_hlsl(model: &ShaderFileModel) -> Result<String, Vec<Diagnostic>> {
+  let mut gen = HlslGen {
+    out: String::new(),
+    diagnostics: Diagnostics::default(),
+    structs: PaddedStructs::new("HLSL"),
+    typedefs: Vec::new(),
+    helpers: BTreeMap::new(),
+    uses_matrices: false,
+  };
+
+  let mut declared = HashSet::new();
+  for ty in model.structs() {
+    let TypeModel::Struct(struct_ty) = &**ty else {
+      unreachable!("Non-struct type in shader file structs");
+    };
+    gen.write_struct(struct_ty, &mut declared);
+  }
+  let structs = std::mem::take(&mut gen.out);
+  gen.write_resources(model);
+  let resources = std::mem::take(&mut gen.out);
+  if !model.funcs().is_empty() {
+    writeln!(gen.out).unwrap();
+    for func in model.funcs() {
+      let signature = gen.signature(func);
+      writeln!(gen.out, "{};", signature).unwrap();
+    }
+  }
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  for entrypoint in model.entrypoints() {
+    gen.write_entrypoint(entrypoint);
+  }
+  if !gen.diagnostics.is_empty() {
+    return Err(gen.diagnostics.into_vec());
+  }
+
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path()).unwrap();
+  if gen.uses_matrices {
+    writeln!(out).unwrap();
+    writeln!(out, "#pragma pack_matrix(row_major)").unwrap();
+  }
+  out.push_str(&structs);
+  if !gen.typedefs.is_empty() {
+    writeln!(out).unwrap();
+    for typedef in &gen.typedefs {
+      writeln!(out, "{}", typedef).unwrap();
+    }
+  }
+  out.push_str(&resources);
+  for helper in gen.helpers.values() {
+    writeln!(out).unwrap();
+    out.push_str(helper);
+  }
+  out.push_str(&gen.out);
+  Ok(out)
+}
+
+/**
+ * The state of HLSL generation.  Diagnostics are collected rather than
+ * stopping at the first, and the typedefs and helper functions used by
+ * the output are noted so that they can be declared ahead of it.
+ */
+struct HlslGen {
+  out: String,
+  diagnostics: Diagnostics,
+  /** Structs with their padding members. */
+  structs: PaddedStructs,
+  /** Typedefs of array types, which HLSL can't otherwise name. */
+  typedefs: Vec<String>,
+  /** Helper functions, by name. */
+  helpers: BTreeMap<String, String>,
+  uses_matrices: bool,
+}
+
+impl HlslGen {
+  /**
+   * Write a struct after the structs it contains, as HLSL requires.  A
+   * struct ending in a runtime-sized array can only be a buffer, so it
+   * is padded but not declared.
+   */
+  fn write_struct(&mut self, struct_ty: &StructTypeModel, declared: &mut HashSet<String>) {
+    let name = struct_name(struct_ty, ident);
+    if !declared.insert(name.clone()) {
+      return;
+    }
+    for field in &struct_ty.fields {
+      let mut ty = &field.ty;
+      while let TypeModel::Array(array_ty) = &**ty {
+        ty = &array_ty.elem;
+      }
+      if let TypeModel::Struct(field_struct) = &**ty {
+        self.write_struct(field_struct, declared);
+      }
+    }
+    let Some(padded) = self.pad(struct_ty, Packing::Structured) else {
+      return;
+    };
+    if !struct_ty.fields.last().is_some_and(|field| field.ty.is_runtime_sized()) {
+      self.write_struct_decl(&name, struct_ty, &padded);
+    }
+    self.structs.insert(struct_ty, padded);
+  }
+
+  fn write_struct_decl(&mut self,
+    name: &str,
+    struct_ty: &StructTypeModel,
+    padded: &PaddedStruct,
+  ) {
+    let members = padded.members(struct_ty)
+      .map(|member| match member {
+        PaddedMember::Field(field) => self.declaration(&field.ty, &ident(field.name())),
+        PaddedMember::Pad(pad) => self.declaration(&pad.ty, pad.name()),
+      })
+      .collect::<Vec<_>>();
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "struct {} {{", name).unwrap();
+    for member in members {
+      writeln!(self.out, "  {};", member).unwrap();
+    }
+    writeln!(self.out, "}};").unwrap();
+  }
+
+  /**
+   * Pad a struct for an HLSL packing, reporting why it can't be.
+   */
+  fn pad(&mut self, struct_ty: &StructTypeModel, packing: Packing) -> Option<PaddedStruct> {
+    match self.structs.pad(struct_ty, packing) {
+      Ok(padded) => Some(padded),
+      Err(errors) => {
+        for error in errors {
+          self.diagnostics.error(error);
+        }
+        None
+      },
+    }
+  }
+
+  /**
+   * Write the uniforms struct and its constant buffer, and a resource
+   * for each buffer.
+   */
+  fn write_resources(&mut self, model: &ShaderFileModel) {
+    if let Some(uniforms) = model.uniforms() {
+      let TypeModel::Struct(struct_ty) = &**uniforms.ty() else {
+        unreachable!("Non-struct uniforms type");
+      };
+      if let Some(padded) = self.pad(struct_ty, Packing::Constant) {
+        let name = struct_name(struct_ty, ident);
+        self.write_struct_decl(&name, struct_ty, &padded);
+        writeln!(self.out).unwrap();
+        writeln!(self.out,
+          "cbuffer {} : {} {{", UNIFORMS_BLOCK, register('b', uniforms.binding())
+        ).unwrap();
+        writeln!(self.out, "  {} uniforms;", name).unwrap();
+        writeln!(self.out, "}};").unwrap();
+      }
+    }
+
+    if !model.buffers().is_empty() {
+      writeln!(self.out).unwrap();
+    }
+    for buffer in model.buffers() {
+      let (prefix, class) = match buffer.mode() {
+        BufferAccessMode::Read => ("", 't'),
+        BufferAccessMode::Write | BufferAccessMode::ReadWrite => ("RW", 'u'),
+      };
+      let register = register(class, buffer.binding());
+      let name = ident(buffer.name());
+      match &**buffer.ty() {
+        TypeModel::Array(array_ty) => {
+          if !self.structs.same_layout(buffer.ty(), Packing::Structured) {
+            self.diagnostics.error(format!(
+              "Buffer `{}` of type `{}` cannot be expressed in HLSL, as {} \
+               places its contents differently.",
+              buffer.name(), **buffer.ty(), Packing::Structured
+            ));
+          }
+          let elem = self.type_name(&array_ty.elem);
+          writeln!(self.out,
+            "{}StructuredBuffer<{}> {} : {};", prefix, elem, name, register
+          ).unwrap();
+        },
+        _ => writeln!(self.out,
+          "{}ByteAddressBuffer {} : {};", prefix, name, register
+        ).unwrap(),
+      }
+    }
+  }
+
+  fn signature(&mut self, func: &FuncModel) -> String {
+    let args = func.args.iter()
+      .map(|arg| self.declaration(&arg.ty, &ident(&arg.name.name)))
+      .collect::<Vec<_>>();
+    let return_ty = self.type_name(&func.return_ty);
+    format!("{} {}({})", return_ty, ident(&func.name.name), args.join(", "))
+  }
+
+  fn write_func(&mut self, func: &FuncModel) {
+    let signature = self.signature(func);
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "{} {{", signature).unwrap();
+    self.write_block(&func.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Write an entrypoint as a kernel, whose invocation id argument is
+   * the dispatch thread id, with a component for each dimension.
+   */
+  fn write_entrypoint(&mut self, entrypoint: &EntrypointModel) {
+    let ty = match entrypoint.dims {
+      EntrypointDims::D1 => "uint",
+      EntrypointDims::D2 => "uint2",
+      EntrypointDims::D3 => "uint3",
+    };
+    let [x, y, z] = entrypoint.workgroup_size();
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "[numthreads({}, {}, {})]", x, y, z).unwrap();
+    writeln!(self.out,
+      "void {}({} {} : SV_DispatchThreadID) {{",
+      ident(entrypoint.name()), ty, ident(&entrypoint.arg_name.name)
+    ).unwrap();
+    self.write_block(&entrypoint.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_block(&mut self, statements: &[StatementModel], depth: usize) {
+    for stmt in statements {
+      self.write_stmt(stmt, depth);
+    }
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let decl = self.declaration(&let_stmt.value.ty, &ident(&let_stmt.name.name));
+        let value = self.expr(&let_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, decl, value).unwrap();
+      },
+      StatementModel::Var(var_stmt) => {
+        let decl = self.declaration(&var_stmt.value.ty, &ident(&var_stmt.name.name));
+        let value = self.expr(&var_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, decl, value).unwrap();
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        let (place, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        if let Some(address) = self.byte_address(place) {
+          self.write_store(address, place, &components, &mutate_stmt.value, depth);
+          return;
+        }
+        // HLSL assigns swizzles directly, so only nesting is removed.
+        let mut lvalue = self.expr(place);
+        if !components.is_empty() {
+          lvalue.push('.');
+          lvalue.extend(components.iter().map(|&c| SWIZZLE_LETTERS[c as usize]));
+        }
+        let value = self.expr(&mutate_stmt.value);
+        writeln!(self.out, "{}{} = {};", indent, lvalue, value).unwrap();
+      },
+      StatementModel::Exec(exec_stmt) => {
+        let expr = self.expr(&exec_stmt.expr);
+        writeln!(self.out, "{}{};", indent, expr).unwrap();
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          writeln!(self.out, "{}return {};", indent, value).unwrap();
+        },
+        None => writeln!(self.out, "{}return;", indent).unwrap(),
+      },
+      StatementModel::If(if_stmt) => {
+        for (i, (cond, block)) in if_chain(if_stmt).into_iter().enumerate() {
+          let close = if i == 0 { "" } else { "} else " };
+          match cond {
+            Some(cond) => {
+              let cond = self.expr(cond);
+              writeln!(self.out, "{}{}if ({}) {{", indent, close, cond).unwrap();
+            },
+            None => writeln!(self.out, "{}}} else {{", indent).unwrap(),
+          }
+          self.write_block(block, depth + 1);
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Loop(loop_stmt) => {
+        writeln!(self.out, "{}while (true) {{", indent).unwrap();
+        self.write_block(&loop_stmt.block, depth + 1);
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  /**
+   * Store a value into a byte address buffer.  Each component assigned
+   * by a swizzle is stored on its own, from a copy of the value.
+   */
+  fn write_store(&mut self,
+    mut address: ByteAddress,
+    place: &ExpressionModel,
+    components: &[u32],
+    value: &ExpressionModel,
+    depth: usize,
+  ) {
+    let indent = "  ".repeat(depth);
+    let value_expr = self.expr(value);
+    let scalar_size = place.ty.numeric_element().map(|scalar| scalar.size()).unwrap_or(0);
+    match components {
+      [] => {
+        let ty = self.type_name(&place.ty);
+        writeln!(self.out,
+          "{}{}.Store<{}>({}, {});", indent, address.buffer, ty, address, value_expr
+        ).unwrap();
+      },
+      [component] => {
+        let ty = self.type_name(&value.ty);
+        address.offset += component * scalar_size;
+        writeln!(self.out,
+          "{}{}.Store<{}>({}, {});", indent, address.buffer, ty, address, value_expr
+        ).unwrap();
+      },
+      _ => {
+        let ty = self.type_name(&value.ty);
+        let scalar_ty = self.type_name(
+          &TypeModel::new_scalar(place.ty.numeric_element().expect("Swizzle of a vector"))
+        );
+        writeln!(self.out, "{}{{", indent).unwrap();
+        writeln!(self.out, "{}  uint {} = {};", indent, STORE_ADDRESS, address).unwrap();
+        writeln!(self.out, "{}  {} {} = {};", indent, ty, STORE_VALUE, value_expr).unwrap();
+        for (i, &component) in components.iter().enumerate() {
+          let offset = component * scalar_size;
+          let component_address = if offset == 0 {
+            STORE_ADDRESS.to_string()
+          } else {
+            format!("{} + {}u", STORE_ADDRESS, offset)
+          };
+          writeln!(self.out, "{}  {}.Store<{}>({}, {}.{});",
+            indent,
+            address.buffer,
+            scalar_ty,
+            component_address,
+            STORE_VALUE,
+            SWIZZLE_LETTERS[i],
+          ).unwrap();
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+    }
+  }
+
+  /**
+   * The address of a place within a byte address buffer, if it is one.
+   * Offsets come from the buffer's storage layout.
+   */
+  fn byte_address(&mut self, place: &ExpressionModel) -> Option<ByteAddress> {
+    match &place.kind {
+      ExpressionModelKind::Field(field_expr) => {
+        let mut address = match &field_expr.target.kind {
+          ExpressionModelKind::Buffer(name) => ByteAddress {
+            buffer: ident(&name.name),
+            offset: 0,
+            terms: Vec::new(),
+          },
+          _ => self.byte_address(&field_expr.target)?,
+        };
+        let layout = field_expr.target.ty.layout(LayoutRules::Storage)
+          .expect("Buffer contents have a storage layout");
+        let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+          unreachable!("Field access on non-struct layout");
+        };
+        address.offset += struct_layout.fields[field_expr.field as usize].offset;
+        Some(address)
+      },
+      ExpressionModelKind::Index(index_expr) => {
+        let mut address = self.byte_address(&index_expr.target)?;
+        let layout = index_expr.target.ty.layout(LayoutRules::Storage)
+          .expect("Buffer contents have a storage layout");
+        let TypeLayoutKind::Array(array_layout) = &layout.kind else {
+          unreachable!("Index into non-array layout");
+        };
+        match index_expr.index.kind {
+          ExpressionModelKind::Literal(LiteralModel::U32(index)) =>
+            address.offset += index * array_layout.stride,
+          ExpressionModelKind::Literal(LiteralModel::I32(index)) if index >= 0 =>
+            address.offset += index as u32 * array_layout.stride,
+          _ => {
+            let index = self.index(&index_expr.index);
+            let index = if matches!(index_expr.index.kind, ExpressionModelKind::Binary(_)) {
+              format!("({})", index)
+            } else {
+              index
+            };
+            address.terms.push(format!("{} * {}u", index, array_layout.stride));
+          },
+        }
+        Some(address)
+      },
+      _ => None,
+    }
+  }
+
+  /**
+   * The number of elements of a buffer's runtime-sized array, through
+   * a helper function querying the buffer's size.
+   */
+  fn array_length(&mut self, target: &ExpressionModel) -> String {
+    let (name, body) = match &target.kind {
+      ExpressionModelKind::Buffer(name) => (ident(&name.name), format!(
+        "  uint count;\n  uint stride;\n  {}.GetDimensions(count, stride);\n  return count;\n",
+        ident(&name.name)
+      )),
+      ExpressionModelKind::Field(field_expr) => {
+        let ExpressionModelKind::Buffer(name) = &field_expr.target.kind else {
+          unreachable!("Runtime-sized array outside of a buffer");
+        };
+        let layout = field_expr.target.ty.layout(LayoutRules::Storage)
+          .expect("Buffer has a storage layout");
+        let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+          unreachable!("Field access on non-struct layout");
+        };
+        let field_layout = &struct_layout.fields[field_expr.field as usize];
+        let TypeLayoutKind::Array(array_layout) = &field_layout.layout.kind else {
+          unreachable!("Array length of non-array layout");
+        };
+        (ident(&name.name), format!(
+          "  uint size;\n  {}.GetDimensions(size);\n  return (size - {}u) / {}u;\n",
+          ident(&name.name), field_layout.offset, array_layout.stride
+        ))
+      },
+      _ => unreachable!("Runtime-sized array outside of a buffer"),
+    };
+    let helper = format!("{}_{}", LENGTH, name);
+    self.helpers.entry(helper.clone())
+      .or_insert_with(|| format!("uint {}() {{\n{}}}\n", helper, body));
+    format!("{}()", helper)
+  }
+
+  /**
+   * A construction of a struct, through a helper function taking its
+   * fields, as HLSL has no struct constructors.  Padding members are
+   * zero.
+   */
+  fn construct_struct(&mut self, struct_ty: &StructTypeModel, args: Vec<String>) -> String {
+    let name = struct_name(struct_ty, ident);
+    let helper = format!("{}_{}", CONSTRUCT, name);
+    if !self.helpers.contains_key(&helper) {
+      let params = struct_ty.fields.iter()
+        .map(|field| self.declaration(&field.ty, &ident(field.name())))
+        .collect::<Vec<_>>();
+      let mut body = String::new();
+      writeln!(body, "{} {}({}) {{", name, helper, params.join(", ")).unwrap();
+      writeln!(body, "  {} {} = ({})0;", name, STORE_VALUE, name).unwrap();
+      for field in &struct_ty.fields {
+        let field = ident(field.name());
+        writeln!(body, "  {}.{} = {};", STORE_VALUE, field, field).unwrap();
+      }
+      writeln!(body, "  return {};", STORE_VALUE).unwrap();
+      writeln!(body, "}}").unwrap();
+      self.helpers.insert(helper.clone(), body);
+    }
+    format!("{}({})", helper, args.join(", "))
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> String {
+    if let Some(address) = self.byte_address(expr) {
+      let ty = self.type_name(&expr.ty);
+      return format!("{}.Load<{}>({})", address.buffer, ty, address);
+    }
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => self.literal(literal),
+      ExpressionModelKind::Local(name) => ident(&name.name),
+      ExpressionModelKind::Uniforms => "uniforms".to_string(),
+      ExpressionModelKind::Buffer(name) => ident(&name.name),
+      ExpressionModelKind::Index(index_expr) => {
+        let target = self.postfix_target(&index_expr.target);
+        let index = self.index(&index_expr.index);
+        format!("{}[{}]", target, index)
+      },
+      ExpressionModelKind::ArrayLength(length_expr) => self.array_length(&length_expr.target),
+      ExpressionModelKind::Field(field_expr) => {
+        let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+          unreachable!("Field access on non-struct type");
+        };
+        let field = &struct_ty.fields[field_expr.field as usize];
+        format!("{}.{}", self.postfix_target(&field_expr.target), ident(field.name()))
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => format!("{}.{}",
+        self.postfix_target(&swizzle_expr.target),
+        swizzle_expr.component_letters()
+      ),
+      ExpressionModelKind::Construct(construct_expr) => {
+        let args = construct_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        match &*expr.ty {
+          TypeModel::Struct(struct_ty) => self.construct_struct(struct_ty, args),
+          ty => format!("{}({})", self.type_name(ty), args.join(", ")),
+        }
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let args = call_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        format!("{}({})", ident(&call_expr.func.name), args.join(", "))
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let op = match unary_expr.op {
+          UnaryOpModel::Negate => "-",
+          UnaryOpModel::Not => "!",
+          UnaryOpModel::Complement => "~",
+        };
+        let subexpr = self.expr(&unary_expr.subexpr);
+        if matches!(unary_expr.subexpr.kind,
+          ExpressionModelKind::Unary(_) | ExpressionModelKind::Binary(_)
+        ) || subexpr.starts_with('-') {
+          format!("{}({})", op, subexpr)
+        } else {
+          format!("{}{}", op, subexpr)
+        }
+      },
+      ExpressionModelKind::Binary(binary_expr) => {
+        let lhs = self.operand(&binary_expr.lhs);
+        let rhs = self.operand(&binary_expr.rhs);
+        // Matrices are transposed, so products are taken in the opposite
+        // order.
+        if is_matrix_product(expr) {
+          format!("mul({}, {})", rhs, lhs)
+        } else {
+          format!("{} {} {}", lhs, binary_op(binary_expr.op), rhs)
+        }
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        if let Some(cast) = saturating_cast(expr) {
+          return self.saturating_cast(expr, &cast_expr.subexpr, cast);
+        }
+        let ty = self.type_name(&expr.ty);
+        let subexpr = self.expr(&cast_expr.subexpr);
+        format!("{}({})", ty, subexpr)
+      },
+    }
+  }
+
+  /**
+   * An array index.  HLSL only indexes by 32-bit integers.
+   */
+  fn index(&mut self, index: &ExpressionModel) -> String {
+    let index_expr = self.expr(index);
+    match index.ty.as_numeric_scalar() {
+      Some(ScalarNumericTypeModel::I64) => format!("int({})", index_expr),
+      Some(ScalarNumericTypeModel::U64) => format!("uint({})", index_expr),
+      _ => index_expr,
+    }
+  }
+
+  /**
+   * An expression that is indexed or has a member selected.  Matrix
+   * products are calls to `mul`, and need no parentheses.
+   */
+  fn postfix_target(&mut self, target: &ExpressionModel) -> String {
+    let target_expr = self.expr(target);
+    if is_matrix_product(target) {
+      target_expr
+    } else {
+      parenthesize_target(target, target_expr)
+    }
+  }
+
+  /**
+   * An operand of a binary operation.  Nested operations are always
+   * parenthesized, for clarity.
+   */
+  fn operand(&mut self, operand: &ExpressionModel) -> String {
+    let operand_expr = self.expr(operand);
+    if matches!(operand.kind, ExpressionModelKind::Binary(_)) && !is_matrix_product(operand) {
+      format!("({})", operand_expr)
+    } else {
+      operand_expr
+    }
+  }
+
+  /**
+   * A cast from floats to integers, through a helper function of the
+   * result type, as HLSL's conversions are undefined out of range.  The
+   * helpers of vectors are named after the helpers of their scalars, so
+   * they follow them.
+   */
+  fn saturating_cast(&mut self,
+    expr: &ExpressionModel,
+    subexpr: &ExpressionModel,
+    cast: SaturatingCast,
+  ) -> String {
+    let scalar_ty = scalar_name(cast.to);
+    let scalar_helper = format!("{}_{}", CAST, scalar_ty);
+    if !self.helpers.contains_key(&scalar_helper) {
+      let [zero, min, max] = [cast.zero, cast.min, cast.max].map(|l| self.literal(&l));
+      let mut body = String::new();
+      writeln!(body, "{} {}(float a) {{", scalar_ty, scalar_helper).unwrap();
+      writeln!(body,
+        "  return isnan(a) ? {} : a <= {} ? {} : a >= {} ? {} : {}(a);",
+        zero, float_literal(cast.lo, FROM_BITS), min, float_literal(cast.hi, FROM_BITS), max,
+        scalar_ty
+      ).unwrap();
+      writeln!(body, "}}").unwrap();
+      self.helpers.insert(scalar_helper.clone(), body);
+    }
+
+    let ty = self.type_name(&expr.ty);
+    let float_ty = match &*expr.ty {
+      TypeModel::Vector(vector_ty) =>
+        self.type_name(&TypeModel::new_vector(ScalarNumericTypeModel::F32, vector_ty.dims)),
+      _ => "float".to_string(),
+    };
+    let helper = match &*expr.ty {
+      TypeModel::Vector(vector_ty) => {
+        let helper = format!("{}_{}", CAST, ty);
+        self.helpers.entry(helper.clone()).or_insert_with(|| {
+          let components = SWIZZLE_LETTERS[..vector_ty.dims as usize].iter()
+            .map(|c| format!("{}(a.{})", scalar_helper, c))
+            .collect::<Vec<_>>();
+          format!(
+            "{} {}({} a) {{\n  return {}({});\n}}\n",
+            ty, helper, float_ty, ty, components.join(", ")
+          )
+        });
+        helper
+      },
+      _ => scalar_helper,
+    };
+    let mut subexpr = self.expr(subexpr);
+    if cast.from_f16 {
+      subexpr = format!("{}({})", float_ty, subexpr);
+    }
+    format!("{}({})", helper, subexpr)
+  }
+
+  fn literal(&mut self, literal: &LiteralModel) -> String {
+    match *literal {
+      LiteralModel::Bool(value) => value.to_string(),
+      // The magnitudes of the minimum integers are out of range for
+      // their literals.
+      LiteralModel::I32(i32::MIN) => "(-2147483647 - 1)".to_string(),
+      LiteralModel::I32(value) => value.to_string(),
+      LiteralModel::U32(value) => format!("{}u", value),
+      LiteralModel::F32(bits) => float_literal(f32::from_bits(bits), FROM_BITS),
+      LiteralModel::F16(bits) => {
+        let value = f32::from_bits(bits);
+        if value.is_finite() {
+          format!("{:?}h", value)
+        } else {
+          format!("float16_t({})", float_literal(value, FROM_BITS))
+        }
+      },
+      LiteralModel::I64(i64::MIN) => "(-9223372036854775807l - 1l)".to_string(),
+      LiteralModel::I64(value) => format!("{}l", value),
+      LiteralModel::U64(value) => format!("{}ul", value),
+    }
+  }
+
+  /**
+   * A declaration of a name with a type.  HLSL declares arrays with
+   * their dimensions after the name, outermost first.
+   */
+  fn declaration(&mut self, ty: &TypeModel, name: &str) -> String {
+    let mut dims = String::new();
+    let mut ty = ty;
+    while let TypeModel::Array(array_ty) = ty {
+      write!(dims, "[{}]", array_ty.len.map(|len| len.to_string()).unwrap_or_default())
+        .unwrap();
+      ty = &array_ty.elem;
+    }
+    format!("{} {}{}", self.type_name(ty), name, dims)
+  }
+
+  fn type_name(&mut self, ty: &TypeModel) -> String {
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) =>
+        "bool".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) =>
+        "void".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => scalar_name(*scalar).to_string(),
+      TypeModel::Vector(vector_ty) =>
+        format!("{}{}", scalar_name(vector_ty.scalar), vector_ty.dims as u8),
+      // A row for each column.
+      TypeModel::Matrix(matrix_ty) => {
+        self.uses_matrices = true;
+        format!(
+          "{}{}x{}",
+          scalar_name(matrix_ty.scalar),
+          matrix_ty.cols as u8,
+          matrix_ty.rows as u8
+        )
+      },
+      // Array types are named by typedefs, which are declared after the
+      // structs they may contain.
+      TypeModel::Array(array_ty) => {
+        let elem = self.type_name(&array_ty.elem);
+        let len = array_ty.len.expect("Only sized arrays are named");
+        let name = format!("{}_{}_{}", ARRAY, elem, len);
+        let typedef = format!("typedef {} {}[{}];", elem, name, len);
+        if !self.typedefs.contains(&typedef) {
+          self.typedefs.push(typedef);
+        }
+        name
+      },
+      TypeModel::Struct(struct_ty) => struct_name(struct_ty, ident),
+    }
+  }
+}
+
+/**
+ * A byte offset into a byte address buffer: a constant offset plus
+ * any terms for indices.
+ */
+struct ByteAddress {
+  buffer: String,
+  offset: u32,
+  terms: Vec<String>,
+}
+impl fmt::Display for ByteAddress {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.terms.is_empty() {
+      return write!(f, "{}u", self.offset);
+    }
+    if self.offset != 0 {
+      write!(f, "{}u + ", self.offset)?;
+    }
+    write!(f, "{}", self.terms.join(" + "))
+  }
+}
+
+/**
+ * The name of the uniforms constant buffer.
+ */
+const UNIFORMS_BLOCK: &str = "dubgsl_uniforms_block";
+
+/**
+ * The prefixes of the names of array typedefs and helper functions.
+ */
+const ARRAY: &str = "dubgsl_array";
+const CAST: &str = "dubgsl_cast";
+
+/**
+ * The function making a float from its bits, for non-finite literals.
+ */
+const FROM_BITS: &str = "asfloat";
+const CONSTRUCT: &str = "dubgsl_construct";
+const LENGTH: &str = "dubgsl_length";
+
+/**
+ * Temporaries of stores of several components to byte address buffers,
+ * and of struct construction.
+ */
+const STORE_ADDRESS: &str = "dubgsl_address";
+const STORE_VALUE: &str = "dubgsl_value";
+
+/**
+ * Whether an expression is a product involving a matrix, other than
+ * scaling, which is written as a call to `mul` as `*` of matrices is
+ * componentwise.
+ */
+fn is_matrix_product(expr: &ExpressionModel) -> bool {
+  let ExpressionModelKind::Binary(binary_expr) = &expr.kind else {
+    return false;
+  };
+  let operands = [&binary_expr.lhs, &binary_expr.rhs];
+  binary_expr.op == BinaryOpModel::Mul &&
+    operands.iter().any(|operand| matches!(*operand.ty, TypeModel::Matrix(_))) &&
+    operands.iter().all(|operand| !matches!(*operand.ty, TypeModel::Scalar(_)))
+}
+
+/**
+ * The register of a resource, in the space of its group.
+ */
+fn register(class: char, binding: ResourceBindingModel) -> String {
+  if binding.group() == 0 {
+    format!("register({}{})", class, binding.binding())
+  } else {
+    format!("register({}{}, space{})", class, binding.binding(), binding.group())
+  }
+}
+
+fn scalar_name(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::F16 => "float16_t",
+    ScalarNumericTypeModel::F32 => "float",
+    ScalarNumericTypeModel::I32 => "int",
+    ScalarNumericTypeModel::U32 => "uint",
+    ScalarNumericTypeModel::I64 => "int64_t",
+    ScalarNumericTypeModel::U64 => "uint64_t",
+  }
+}
+
+/**
+ * A name as an HLSL identifier.  Names that HLSL reserves, including
+ * the names of its vector and matrix types, and names starting with
+ * the `dubgsl_` prefix of generated names, get the prefix.
+ */
+fn ident(name: &str) -> String {
+  if name.starts_with("dubgsl_") || RESERVED.contains(&name) || is_type_name(name) {
+    format!("dubgsl_{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/**
+ * Whether a name is a scalar, vector or matrix type, such as `uint`,
+ * `half3` or `float4x4`.
+ */
+fn is_type_name(name: &str) -> bool {
+  TYPE_PREFIXES.iter().any(|prefix| {
+    let Some(dims) = name.strip_prefix(prefix) else {
+      return false;
+    };
+    let dims = dims.as_bytes();
+    let is_dim = |c: &u8| (b'1'..=b'4').contains(c);
+    match dims {
+      [] => true,
+      [n] => is_dim(n),
+      [rows, b'x', cols] => is_dim(rows) && is_dim(cols),
+      _ => false,
+    }
+  })
+}
+
+const TYPE_PREFIXES: &[&str] = &[
+  "bool", "int", "uint", "dword", "half", "float", "double", "min16float",
+  "min10float", "min16int", "min12int", "min16uint", "int16_t", "uint16_t",
+  "int32_t", "uint32_t", "int64_t", "uint64_t", "float16_t", "float32_t",
+  "float64_t",
+];
+
+/**
+ * HLSL keywords and reserved words, resource types, and the intrinsic
+ * functions, which user functions could otherwise overload.
+ */
+const RESERVED: &[&str] = &[
+  // Keywords.
+  "AppendStructuredBuffer", "asm", "asm_fragment", "BlendState", "break",
+  "Buffer", "ByteAddressBuffer", "case", "catch", "cbuffer", "centroid",
+  "char", "class", "column_major", "compile", "compile_fragment",
+  "CompileShader", "const", "const_cast", "ConsumeStructuredBuffer",
+  "continue", "default", "delete", "DepthStencilState", "DepthStencilView",
+  "discard", "do", "dynamic_cast", "else", "enum", "explicit", "export",
+  "extern", "false", "FeedbackTexture2D", "FeedbackTexture2DArray", "for",
+  "friend", "fxgroup", "GeometryShader", "globallycoherent", "goto",
+  "groupshared", "if", "in", "indices", "inline", "inout", "InputPatch",
+  "interface", "line", "lineadj", "linear", "LineStream", "long", "matrix",
+  "mutable", "namespace", "new", "nointerpolation", "noperspective",
+  "operator", "out", "OutputPatch", "packoffset", "pass", "payload",
+  "pixelfragment", "PixelShader", "point", "PointStream", "precise",
+  "primitives", "private", "protected", "public", "RasterizerOrderedBuffer",
+  "RasterizerOrderedByteAddressBuffer", "RasterizerOrderedStructuredBuffer",
+  "RasterizerOrderedTexture1D", "RasterizerOrderedTexture1DArray",
+  "RasterizerOrderedTexture2D", "RasterizerOrderedTexture2DArray",
+  "RasterizerOrderedTexture3D", "RasterizerState", "RayDesc",
+  "RaytracingAccelerationStructure", "register", "reinterpret_cast",
+  "RenderTargetView", "return", "row_major", "RWBuffer",
+  "RWByteAddressBuffer", "RWStructuredBuffer", "RWTexture1D",
+  "RWTexture1DArray", "RWTexture2D", "RWTexture2DArray", "RWTexture3D",
+  "sample", "sampler", "sampler1D", "sampler2D", "sampler3D", "samplerCUBE",
+  "SamplerComparisonState", "SamplerState", "shared", "short", "signed",
+  "sizeof", "snorm", "stateblock", "stateblock_state", "static",
+  "static_cast", "string", "struct", "StructuredBuffer", "switch", "tbuffer",
+  "technique", "technique10", "technique11", "template", "texture",
+  "Texture1D", "Texture1DArray", "Texture2D", "Texture2DArray", "Texture2DMS",
+  "Texture2DMSArray", "Texture3D", "textureCUBE", "TextureCube",
+  "TextureCubeArray", "this", "throw", "triangle", "triangleadj",
+  "TriangleStream", "true", "try", "typedef", "typename", "uniform", "union",
+  "unorm", "unsigned", "using", "vector", "vertexfragment", "VertexShader",
+  "vertices", "virtual", "void", "volatile", "while",
+
+  // Intrinsic functions.
+  "abort", "abs", "acos", "all", "AllMemoryBarrier",
+  "AllMemoryBarrierWithGroupSync", "any", "asdouble", "asfloat", "asfloat16",
+  "asin", "asint", "asint16", "asuint", "asuint16", "atan", "atan2", "ceil",
+  "CheckAccessFullyMapped", "clamp", "clip", "cos", "cosh", "countbits",
+  "cross", "D3DCOLORtoUBYTE4", "ddx", "ddx_coarse", "ddx_fine", "ddy",
+  "ddy_coarse", "ddy_fine", "degrees", "determinant",
+  "DeviceMemoryBarrier", "DeviceMemoryBarrierWithGroupSync", "distance",
+  "dot", "dst", "errorf", "EvaluateAttributeAtCentroid",
+  "EvaluateAttributeAtSample", "EvaluateAttributeSnapped", "exp", "exp2",
+  "f16tof32", "f32tof16", "faceforward", "firstbithigh", "firstbitlow",
+  "floor", "fma", "fmod", "frac", "frexp", "fwidth", "GetRenderTargetSampleCount",
+  "GetRenderTargetSamplePosition", "GroupMemoryBarrier",
+  "GroupMemoryBarrierWithGroupSync", "InterlockedAdd",
+  "InterlockedCompareExchange", "InterlockedCompareStore",
+  "InterlockedExchange", "InterlockedMax", "InterlockedMin", "InterlockedOr",
+  "InterlockedXor", "InterlockedAnd", "isfinite", "isinf", "isnan", "ldexp",
+  "length", "lerp", "lit", "log", "log10", "log2", "mad", "max", "min",
+  "modf", "msad4", "mul", "noise", "normalize", "pow", "printf",
+  "Process2DQuadTessFactorsAvg", "Process2DQuadTessFactorsMax",
+  "Process2DQuadTessFactorsMin", "ProcessIsolineTessFactors",
+  "ProcessQuadTessFactorsAvg", "ProcessQuadTessFactorsMax",
+  "ProcessQuadTessFactorsMin", "ProcessTriTessFactorsAvg",
+  "ProcessTriTessFactorsMax", "ProcessTriTessFactorsMin", "radians", "rcp",
+  "reflect", "refract", "reversebits", "round", "rsqrt", "saturate", "select",
+  "sign", "sin", "sincos", "sinh", "smoothstep", "sqrt", "step", "tan",
+  "tanh", "tex1D", "tex1Dbias", "tex1Dgrad", "tex1Dlod", "tex1Dproj",
+  "tex2D", "tex2Dbias", "tex2Dgrad", "tex2Dlod", "tex2Dproj", "tex3D",
+  "tex3Dbias", "tex3Dgrad", "tex3Dlod", "tex3Dproj", "texCUBE",
+  "texCUBEbias", "texCUBEgrad", "texCUBElod", "texCUBEproj", "transpose",
+  "trunc", "WaveActiveAllEqual", "WaveActiveAllTrue", "WaveActiveAnyTrue",
+  "WaveActiveBallot", "WaveActiveBitAnd", "WaveActiveBitOr",
+  "WaveActiveBitXor", "WaveActiveCountBits", "WaveActiveMax",
+  "WaveActiveMin", "WaveActiveProduct", "WaveActiveSum", "WaveGetLaneCount",
+  "WaveGetLaneIndex", "WaveIsFirstLane", "WavePrefixCountBits",
+  "WavePrefixProduct", "WavePrefixSum", "WaveReadLaneAt", "WaveReadLaneFirst",
+
+  // Names the output declares.
+  "main", "uniforms",
+];
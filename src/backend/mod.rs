@@ -0,0 +1,224 @@
+mod glsl;
+mod hlsl;
+mod msl;
+mod padding;
+mod rust_cpu;
+mod spirv;
+mod wgsl;
+
+pub use self::{
+  glsl::generate_glsl,
+  hlsl::generate_hlsl,
+  msl::generate_msl,
+  rust_cpu::generate_rust_cpu,
+  spirv::{ DisassembleError, disassemble_spirv, generate_spirv },
+  wgsl::generate_wgsl,
+};
+
+use crate::{
+  model::{
+    BinaryOpModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    IfStmtModel,
+    LiteralModel,
+    ScalarNumericTypeModel,
+    StatementModel,
+    StructTypeModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * The errors of generating code.  Generation carries on past an error,
+ * so that all of them are reported, and each message is reported once.
+ */
+#[derive(Default)]
+pub(crate) struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+  pub(crate) fn error(&mut self, message: String) {
+    if !self.0.iter().any(|diagnostic| diagnostic.message == message) {
+      self.0.push(Diagnostic::new(message));
+    }
+  }
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub(crate) fn into_vec(self) -> Vec<Diagnostic> {
+    self.0
+  }
+}
+
+/**
+ * The name of a struct in the output, its path joined by underscores
+ * and made an identifier by the backend's `ident`.
+ */
+pub(crate) fn struct_name(struct_ty: &StructTypeModel, ident: fn(&str) -> String) -> String {
+  let parts = struct_ty.name.path.iter()
+    .map(|part| part.name.as_str())
+    .collect::<Vec<_>>();
+  ident(&parts.join("_"))
+}
+
+/**
+ * The conditions and blocks of an `if` statement, followed by its final
+ * `else` block with no condition.  Chains of `else { if ... }` are
+ * flattened, so that they can be written as `else if`.
+ */
+pub(crate) fn if_chain(if_stmt: &IfStmtModel)
+  -> Vec<(Option<&ExpressionModel>, &[StatementModel])>
+{
+  let mut chain = vec![(Some(&if_stmt.cond), &if_stmt.if_block[..])];
+  let mut else_block = if_stmt.else_block.as_deref();
+  while let Some([StatementModel::If(else_if)]) = else_block {
+    chain.push((Some(&else_if.cond), &else_if.if_block[..]));
+    else_block = else_if.else_block.as_deref();
+  }
+  if let Some(else_block) = else_block {
+    chain.push((None, else_block));
+  }
+  chain
+}
+
+/**
+ * The generated text of the target of an index, field access or
+ * swizzle, parenthesized if it is an operation or starts with a minus
+ * sign, which would otherwise bind looser than the postfix.
+ */
+pub(crate) fn parenthesize_target(target: &ExpressionModel, target_expr: String) -> String {
+  if matches!(target.kind,
+    ExpressionModelKind::Unary(_) | ExpressionModelKind::Binary(_)
+  ) || target_expr.starts_with('-') {
+    format!("({})", target_expr)
+  } else {
+    target_expr
+  }
+}
+
+/**
+ * A float literal for the C-like targets, with a non-finite value made
+ * from its bits by the target's `from_bits` function.  Debug formatting
+ * gives the shortest digits that read back exactly.
+ */
+pub(crate) fn float_literal(value: f32, from_bits: &str) -> String {
+  if value.is_finite() {
+    format!("{:?}", value)
+  } else {
+    format!("{}(0x{:08X}u)", from_bits, value.to_bits())
+  }
+}
+
+/**
+ * Split an assignment target into the place being assigned and the
+ * vector components selected by any swizzles of it.  Nested swizzles
+ * are composed, so `v.zyx.x` selects component 2 of `v`.
+ */
+pub(crate) fn flatten_swizzle(lvalue: &ExpressionModel) -> (&ExpressionModel, Vec<u32>) {
+  match &lvalue.kind {
+    ExpressionModelKind::Swizzle(swizzle_expr) => {
+      let (place, inner) = flatten_swizzle(&swizzle_expr.target);
+      let components = swizzle_expr.components.iter()
+        .map(|&component| inner.get(component as usize).copied().unwrap_or(component))
+        .collect();
+      (place, components)
+    },
+    _ => (lvalue, Vec::new()),
+  }
+}
+
+/**
+ * Whether a place can be evaluated repeatedly without side effects,
+ * and at little cost.
+ */
+pub(crate) fn is_simple_place(place: &ExpressionModel) -> bool {
+  match &place.kind {
+    ExpressionModelKind::Local(_) |
+    ExpressionModelKind::Uniforms |
+    ExpressionModelKind::Buffer(_) => true,
+    ExpressionModelKind::Field(field_expr) => is_simple_place(&field_expr.target),
+    ExpressionModelKind::Index(index_expr) =>
+      is_simple_place(&index_expr.target) && matches!(index_expr.index.kind,
+        ExpressionModelKind::Literal(_) | ExpressionModelKind::Local(_)
+      ),
+    _ => false,
+  }
+}
+
+/**
+ * A cast from floats to integers, which saturates to the integer's
+ * range with NaN becoming zero, where the conversions of GPU targets
+ * are undefined out of range.  Floats at most `lo` become `min`, and
+ * floats at least `hi` become `max`; the bounds are exact in `f32`,
+ * which `f16` operands are converted to first.
+ */
+pub(crate) struct SaturatingCast {
+  pub(crate) to: ScalarNumericTypeModel,
+  pub(crate) from_f16: bool,
+  pub(crate) lo: f32,
+  pub(crate) hi: f32,
+  pub(crate) zero: LiteralModel,
+  pub(crate) min: LiteralModel,
+  pub(crate) max: LiteralModel,
+}
+
+/**
+ * The saturating cast made by a cast expression, if it casts floats to
+ * integers.
+ */
+pub(crate) fn saturating_cast(expr: &ExpressionModel) -> Option<SaturatingCast> {
+  let ExpressionModelKind::Cast(cast_expr) = &expr.kind else {
+    return None;
+  };
+  let from = cast_expr.subexpr.ty.numeric_element()?;
+  let to = expr.ty.numeric_element()?;
+  if !from.is_float() || to.is_float() {
+    return None;
+  }
+  let (lo, hi, min, max) = match to {
+    ScalarNumericTypeModel::I32 =>
+      (-2147483648.0, 2147483648.0, LiteralModel::I32(i32::MIN), LiteralModel::I32(i32::MAX)),
+    ScalarNumericTypeModel::U32 =>
+      (0.0, 4294967296.0, LiteralModel::U32(0), LiteralModel::U32(u32::MAX)),
+    ScalarNumericTypeModel::I64 =>
+      (-9223372036854775808.0, 9223372036854775808.0,
+        LiteralModel::I64(i64::MIN), LiteralModel::I64(i64::MAX)),
+    ScalarNumericTypeModel::U64 =>
+      (0.0, 18446744073709551616.0, LiteralModel::U64(0), LiteralModel::U64(u64::MAX)),
+    ScalarNumericTypeModel::F32 | ScalarNumericTypeModel::F16 => unreachable!("Float target"),
+  };
+  Some(SaturatingCast {
+    to,
+    from_f16: from == ScalarNumericTypeModel::F16,
+    lo,
+    hi,
+    zero: LiteralModel::U32(0).cast(to).expect("Cast of a numeric scalar"),
+    min,
+    max,
+  })
+}
+
+pub(crate) fn binary_op(op: BinaryOpModel) -> &'static str {
+  match op {
+    BinaryOpModel::Add => "+",
+    BinaryOpModel::Sub => "-",
+    BinaryOpModel::Mul => "*",
+    BinaryOpModel::Div => "/",
+    BinaryOpModel::Mod => "%",
+    BinaryOpModel::BitAnd => "&",
+    BinaryOpModel::BitOr => "|",
+    BinaryOpModel::BitXor => "^",
+    BinaryOpModel::Shl => "<<",
+    BinaryOpModel::Shr => ">>",
+    BinaryOpModel::LessThan => "<",
+    BinaryOpModel::LessThanOrEqual => "<=",
+    BinaryOpModel::GreaterThan => ">",
+    BinaryOpModel::GreaterThanOrEqual => ">=",
+    BinaryOpModel::Equal => "==",
+    BinaryOpModel::NotEqual => "!=",
+    BinaryOpModel::LogicalAnd => "&&",
+    BinaryOpModel::LogicalOr => "||",
+  }
+}
@@ -0,0 +1,1020 @@
+use std::{
+  collections::{ BTreeMap, BTreeSet, HashMap, HashSet },
+  fmt::Write,
+};
+use crate::{
+  backend::{
+    Diagnostics,
+    SaturatingCast,
+    binary_op,
+    flatten_swizzle,
+    float_literal,
+    if_chain,
+    is_simple_place,
+    padding::{ Packing, PaddedMember, PaddedStruct, PaddedStructs, is_packed_member },
+    parenthesize_target,
+    saturating_cast,
+    struct_name,
+  },
+  model::{
+    BinaryOpModel,
+    BufferAccessMode,
+    BufferModel,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FieldExprModel,
+    FuncModel,
+    LayoutRules,
+    LiteralModel,
+    SWIZZLE_LETTERS,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructTypeModel,
+    TypeLayoutKind,
+    TypeModel,
+    UnaryOpModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * Lower a checked shader file to Metal Shading Language, with a kernel
+ * for each entrypoint.
+ *
+ * Metal has no global resources, so the uniforms and buffers are
+ * arguments of each kernel, and of each function that uses them.  Their
+ * `[[buffer(n)]]` indices number them in order of group and binding.
+ * The uniforms and buffers that are only read are in the `constant`
+ * address space, and buffers that are written in `device`.  Metal
+ * can't query the size of a buffer, so if an array length is used, the
+ * byte size of each buffer is passed in a `dubgsl_buffer_sizes` struct,
+ * after the resources.
+ *
+ * Metal lays out structs as C does, but with 16-byte 3-component
+ * vectors, so those are packed, and padding members place the fields.
+ * Kernels don't declare their threadgroup size, which the host must
+ * dispatch with, so it is noted in a comment.
+ */
+pub fn generate_msl(model: &ShaderFileModel) -> Result<String, Vec<Diagnostic>> {
+  let mut resources = Vec::new();
+  if let Some(uniforms) = model.uniforms() {
+    resources.push((uniforms.binding(), Resource::Uniforms));
+  }
+  for buffer in model.buffers() {
+    resources.push((buffer.binding(), Resource::Buffer(buffer)));
+  }
+  resources.sort_by_key(|&(binding, _)| binding);
+
+  let mut gen = MslGen {
+    out: String::new(),
+    diagnostics: Diagnostics::default(),
+    structs: PaddedStructs::new("MSL"),
+    helpers: BTreeMap::new(),
+    resources: resources.into_iter().map(|(_, resource)| resource).collect(),
+    uses: resource_uses(model),
+  };
+  // The sizes are passed to every kernel if any array length is used.
+  let mut uses_sizes = gen.uses.values().any(|uses| uses.contains(&Resource::Sizes));
+  for entrypoint in model.entrypoints() {
+    for_each_expr(&entrypoint.body, &mut |expr| {
+      uses_sizes |= matches!(expr.kind, ExpressionModelKind::ArrayLength(_));
+    });
+  }
+
+  let mut declared = HashSet::new();
+  for ty in model.structs() {
+    let TypeModel::Struct(struct_ty) = &**ty else {
+      unreachable!("Non-struct type in shader file structs");
+    };
+    gen.write_struct(struct_ty, &mut declared);
+  }
+  if let Some(uniforms) = model.uniforms() {
+    let TypeModel::Struct(struct_ty) = &**uniforms.ty() else {
+      unreachable!("Non-struct uniforms type");
+    };
+    if let Some(padded) = gen.pad(struct_ty, Packing::MetalUniforms) {
+      gen.write_struct_decl(struct_ty, &padded);
+    }
+  }
+  for buffer in model.buffers() {
+    if matches!(**buffer.ty(), TypeModel::Array(_)) &&
+      !gen.structs.same_layout(buffer.ty(), Packing::Metal)
+    {
+      gen.diagnostics.error(format!(
+        "Buffer `{}` of type `{}` cannot be expressed in MSL, as Metal layout \
+         places its contents differently.",
+        buffer.name(), **buffer.ty()
+      ));
+    }
+  }
+  if uses_sizes {
+    writeln!(gen.out).unwrap();
+    writeln!(gen.out, "struct {} {{", SIZES_STRUCT).unwrap();
+    for buffer in model.buffers() {
+      writeln!(gen.out, "  uint {};", ident(buffer.name())).unwrap();
+    }
+    writeln!(gen.out, "}};").unwrap();
+  }
+  let decls = std::mem::take(&mut gen.out);
+
+  if !model.funcs().is_empty() {
+    writeln!(gen.out).unwrap();
+    for func in model.funcs() {
+      let signature = gen.signature(func);
+      writeln!(gen.out, "{};", signature).unwrap();
+    }
+  }
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  for entrypoint in model.entrypoints() {
+    gen.write_kernel(entrypoint, uses_sizes);
+  }
+  if !gen.diagnostics.is_empty() {
+    return Err(gen.diagnostics.into_vec());
+  }
+
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path()).unwrap();
+  writeln!(out, "#include <metal_stdlib>").unwrap();
+  writeln!(out).unwrap();
+  writeln!(out, "using namespace metal;").unwrap();
+  out.push_str(&decls);
+  for helper in gen.helpers.values() {
+    writeln!(out).unwrap();
+    out.push_str(helper);
+  }
+  out.push_str(&gen.out);
+  Ok(out)
+}
+
+/**
+ * A resource passed to kernels and the functions that use it.
+ */
+#[derive(Debug, Clone, Copy)]
+enum Resource<'a> {
+  Uniforms,
+  Buffer(&'a BufferModel),
+  /** The byte sizes of the buffers. */
+  Sizes,
+}
+
+impl Resource<'_> {
+  /**
+   * The key resources are compared by, as buffers are named uniquely.
+   */
+  fn key(&self) -> (u8, &str) {
+    match self {
+      Resource::Uniforms => (0, ""),
+      Resource::Buffer(buffer) => (1, buffer.name()),
+      Resource::Sizes => (2, ""),
+    }
+  }
+}
+
+impl PartialEq for Resource<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl Eq for Resource<'_> {}
+
+impl PartialOrd for Resource<'_> {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Resource<'_> {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    self.key().cmp(&other.key())
+  }
+}
+
+/**
+ * The state of MSL generation.  Diagnostics are collected rather than
+ * stopping at the first, and the helper functions used by the output
+ * are noted so that they can be declared ahead of it.
+ */
+struct MslGen<'a> {
+  out: String,
+  diagnostics: Diagnostics,
+  /** Structs with their padding members. */
+  structs: PaddedStructs,
+  /** Helper functions, by name. */
+  helpers: BTreeMap<String, String>,
+  /** The uniforms and buffers, in order of their indices. */
+  resources: Vec<Resource<'a>>,
+  /** The resources each function uses, by name. */
+  uses: HashMap<String, BTreeSet<Resource<'a>>>,
+}
+
+impl<'a> MslGen<'a> {
+  /**
+   * Write a struct after the structs it contains, as MSL requires.
+   */
+  fn write_struct(&mut self, struct_ty: &StructTypeModel, declared: &mut HashSet<String>) {
+    if !declared.insert(struct_name(struct_ty, ident)) {
+      return;
+    }
+    for field in &struct_ty.fields {
+      let mut ty = &field.ty;
+      while let TypeModel::Array(array_ty) = &**ty {
+        ty = &array_ty.elem;
+      }
+      if let TypeModel::Struct(field_struct) = &**ty {
+        self.write_struct(field_struct, declared);
+      }
+    }
+    if let Some(padded) = self.pad(struct_ty, Packing::Metal) {
+      self.write_struct_decl(struct_ty, &padded);
+      self.structs.insert(struct_ty, padded);
+    }
+  }
+
+  /**
+   * Write the declaration of a padded struct.  A runtime-sized array
+   * is declared with a single element, and indexed past it.
+   */
+  fn write_struct_decl(&mut self, struct_ty: &StructTypeModel, padded: &PaddedStruct) {
+    let members = padded.members(struct_ty)
+      .map(|member| match member {
+        PaddedMember::Field(field) if is_packed_member(&field.ty) => {
+          let TypeModel::Vector(vector_ty) = &*field.ty else {
+            unreachable!("Packed member of non-vector type");
+          };
+          format!("packed_{}3 {}", scalar_name(vector_ty.scalar), ident(field.name()))
+        },
+        PaddedMember::Field(field) => match &*field.ty {
+          TypeModel::Array(array_ty) if array_ty.len.is_none() =>
+            format!("{} {}[1]", self.type_name(&array_ty.elem), ident(field.name())),
+          ty => format!("{} {}", self.type_name(ty), ident(field.name())),
+        },
+        PaddedMember::Pad(pad) => format!("{} {}", self.type_name(&pad.ty), pad.name()),
+      })
+      .collect::<Vec<_>>();
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "struct {} {{", struct_name(struct_ty, ident)).unwrap();
+    for member in members {
+      writeln!(self.out, "  {};", member).unwrap();
+    }
+    writeln!(self.out, "}};").unwrap();
+  }
+
+  /**
+   * Pad a struct for Metal, reporting why it can't be.
+   */
+  fn pad(&mut self, struct_ty: &StructTypeModel, packing: Packing) -> Option<PaddedStruct> {
+    match self.structs.pad(struct_ty, packing) {
+      Ok(padded) => Some(padded),
+      Err(errors) => {
+        for error in errors {
+          self.diagnostics.error(error);
+        }
+        None
+      },
+    }
+  }
+
+  /**
+   * The parameter declaring a resource.
+   */
+  fn resource_param(&mut self, resource: Resource) -> String {
+    match resource {
+      Resource::Uniforms => "constant Uniforms& uniforms".to_string(),
+      Resource::Buffer(buffer) => {
+        let space = match buffer.mode() {
+          BufferAccessMode::Read => "constant",
+          BufferAccessMode::Write | BufferAccessMode::ReadWrite => "device",
+        };
+        match &**buffer.ty() {
+          TypeModel::Array(array_ty) => format!(
+            "{} {}* {}", space, self.type_name(&array_ty.elem), ident(buffer.name())
+          ),
+          ty => format!("{} {}& {}", space, self.type_name(ty), ident(buffer.name())),
+        }
+      },
+      Resource::Sizes => format!("constant {}& {}", SIZES_STRUCT, SIZES),
+    }
+  }
+
+  fn resource_name(resource: Resource) -> String {
+    match resource {
+      Resource::Uniforms => "uniforms".to_string(),
+      Resource::Buffer(buffer) => ident(buffer.name()),
+      Resource::Sizes => SIZES.to_string(),
+    }
+  }
+
+  /**
+   * The resources a function uses, in order of their indices.
+   */
+  fn func_resources(&self, func: &str) -> Vec<Resource<'a>> {
+    let uses = &self.uses[func];
+    self.resources.iter()
+      .copied()
+      .chain([Resource::Sizes])
+      .filter(|resource| uses.contains(resource))
+      .collect()
+  }
+
+  fn signature(&mut self, func: &FuncModel) -> String {
+    let mut params = self.func_resources(&func.name.name).into_iter()
+      .map(|resource| self.resource_param(resource))
+      .collect::<Vec<_>>();
+    for arg in &func.args {
+      params.push(format!("{} {}", self.type_name(&arg.ty), ident(&arg.name.name)));
+    }
+    let return_ty = self.type_name(&func.return_ty);
+    format!("{} {}({})", return_ty, ident(&func.name.name), params.join(", "))
+  }
+
+  fn write_func(&mut self, func: &FuncModel) {
+    let signature = self.signature(func);
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "{} {{", signature).unwrap();
+    self.write_block(&func.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Write an entrypoint as a kernel taking every resource, whose
+   * invocation id argument is the thread position in the grid, with a
+   * component for each dimension.
+   */
+  fn write_kernel(&mut self, entrypoint: &EntrypointModel, uses_sizes: bool) {
+    let mut params = Vec::new();
+    let resources = self.resources.clone();
+    let sizes = uses_sizes.then_some(Resource::Sizes);
+    for (index, resource) in resources.into_iter().chain(sizes).enumerate() {
+      let param = self.resource_param(resource);
+      params.push(format!("{} [[buffer({})]]", param, index));
+    }
+    let ty = match entrypoint.dims {
+      EntrypointDims::D1 => "uint",
+      EntrypointDims::D2 => "uint2",
+      EntrypointDims::D3 => "uint3",
+    };
+    params.push(format!(
+      "{} {} [[thread_position_in_grid]]", ty, ident(&entrypoint.arg_name.name)
+    ));
+    let [x, y, z] = entrypoint.workgroup_size();
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "// Threadgroup size {}x{}x{}.", x, y, z).unwrap();
+    writeln!(self.out, "kernel void {}(", ident(entrypoint.name())).unwrap();
+    for (i, param) in params.iter().enumerate() {
+      let separator = if i + 1 < params.len() { "," } else { "" };
+      writeln!(self.out, "  {}{}", param, separator).unwrap();
+    }
+    writeln!(self.out, ") {{").unwrap();
+    self.write_block(&entrypoint.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_block(&mut self, statements: &[StatementModel], depth: usize) {
+    for stmt in statements {
+      self.write_stmt(stmt, depth);
+    }
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let ty = self.type_name(&let_stmt.value.ty);
+        let value = self.expr(&let_stmt.value);
+        writeln!(self.out,
+          "{}{} {} = {};", indent, ty, ident(&let_stmt.name.name), value
+        ).unwrap();
+      },
+      StatementModel::Var(var_stmt) => {
+        let ty = self.type_name(&var_stmt.value.ty);
+        let value = self.expr(&var_stmt.value);
+        writeln!(self.out,
+          "{}{} {} = {};", indent, ty, ident(&var_stmt.name.name), value
+        ).unwrap();
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        let (place, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        let place_expr = self.place(place);
+        let value = self.expr(&mutate_stmt.value);
+        if components.is_empty() {
+          writeln!(self.out, "{}{} = {};", indent, place_expr, value).unwrap();
+        } else if !is_packed_place(place) {
+          // Unpacked vectors assign swizzles directly.
+          let components = components.iter()
+            .map(|&c| SWIZZLE_LETTERS[c as usize])
+            .collect::<String>();
+          writeln!(self.out, "{}{}.{} = {};", indent, place_expr, components, value).unwrap();
+        } else if let [component] = components[..] {
+          writeln!(self.out, "{}{}[{}] = {};", indent, place_expr, component, value).unwrap();
+        } else {
+          // Packed vectors are assigned a component at a time, through a
+          // reference when the place may have side effects.
+          writeln!(self.out, "{}{{", indent).unwrap();
+          let target = if is_simple_place(place) {
+            place_expr
+          } else {
+            let ty = self.type_name(&place.ty);
+            writeln!(self.out,
+              "{}  {} packed_{}& {} = {};",
+              indent, place_space(place), ty, SWIZZLE_TARGET, place_expr
+            ).unwrap();
+            SWIZZLE_TARGET.to_string()
+          };
+          let ty = self.type_name(&mutate_stmt.value.ty);
+          writeln!(self.out, "{}  {} {} = {};", indent, ty, SWIZZLE_VALUE, value).unwrap();
+          for (i, &component) in components.iter().enumerate() {
+            writeln!(self.out, "{}  {}[{}] = {}.{};",
+              indent, target, component, SWIZZLE_VALUE, SWIZZLE_LETTERS[i]
+            ).unwrap();
+          }
+          writeln!(self.out, "{}}}", indent).unwrap();
+        }
+      },
+      StatementModel::Exec(exec_stmt) => {
+        let expr = self.expr(&exec_stmt.expr);
+        writeln!(self.out, "{}{};", indent, expr).unwrap();
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          writeln!(self.out, "{}return {};", indent, value).unwrap();
+        },
+        None => writeln!(self.out, "{}return;", indent).unwrap(),
+      },
+      StatementModel::If(if_stmt) => {
+        for (i, (cond, block)) in if_chain(if_stmt).into_iter().enumerate() {
+          let close = if i == 0 { "" } else { "} else " };
+          match cond {
+            Some(cond) => {
+              let cond = self.expr(cond);
+              writeln!(self.out, "{}{}if ({}) {{", indent, close, cond).unwrap();
+            },
+            None => writeln!(self.out, "{}}} else {{", indent).unwrap(),
+          }
+          self.write_block(block, depth + 1);
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Loop(loop_stmt) => {
+        writeln!(self.out, "{}while (true) {{", indent).unwrap();
+        self.write_block(&loop_stmt.block, depth + 1);
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  /**
+   * A place being assigned.  Unlike a read of it, a packed vector is
+   * left packed.
+   */
+  fn place(&mut self, place: &ExpressionModel) -> String {
+    match &place.kind {
+      ExpressionModelKind::Field(field_expr) => self.field(field_expr),
+      _ => self.expr(place),
+    }
+  }
+
+  fn field(&mut self, field_expr: &FieldExprModel) -> String {
+    let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+      unreachable!("Field access on non-struct type");
+    };
+    let field = &struct_ty.fields[field_expr.field as usize];
+    format!("{}.{}", self.postfix_target(&field_expr.target), ident(field.name()))
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> String {
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => literal_expr(literal),
+      ExpressionModelKind::Local(name) => ident(&name.name),
+      ExpressionModelKind::Uniforms => "uniforms".to_string(),
+      ExpressionModelKind::Buffer(name) => ident(&name.name),
+      ExpressionModelKind::Index(index_expr) => {
+        let target = self.postfix_target(&index_expr.target);
+        let index = self.expr(&index_expr.index);
+        format!("{}[{}]", target, index)
+      },
+      ExpressionModelKind::ArrayLength(length_expr) => array_length(&length_expr.target),
+      ExpressionModelKind::Field(field_expr) => {
+        // Packed vectors are unpacked to be read.
+        let field = self.field(field_expr);
+        if is_packed_place(expr) {
+          format!("{}({})", self.type_name(&expr.ty), field)
+        } else {
+          field
+        }
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => format!("{}.{}",
+        self.postfix_target(&swizzle_expr.target),
+        swizzle_expr.component_letters()
+      ),
+      ExpressionModelKind::Construct(construct_expr) => {
+        let ty = self.type_name(&expr.ty);
+        let mut args = construct_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        match &*expr.ty {
+          // Structs are aggregates, with zero padding members.
+          TypeModel::Struct(struct_ty) => {
+            if let Some(padded) = self.structs.get(struct_ty) {
+              let mut fields = args.into_iter();
+              args = padded.members(struct_ty)
+                .map(|member| match member {
+                  PaddedMember::Field(_) =>
+                    fields.next().expect("Struct is constructed from each field"),
+                  PaddedMember::Pad(_) => "{}".to_string(),
+                })
+                .collect();
+            }
+            format!("{}{{{}}}", ty, args.join(", "))
+          },
+          _ => format!("{}({})", ty, args.join(", ")),
+        }
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let mut args = self.func_resources(&call_expr.func.name).into_iter()
+          .map(Self::resource_name)
+          .collect::<Vec<_>>();
+        for arg in &call_expr.args {
+          args.push(self.expr(arg));
+        }
+        format!("{}({})", ident(&call_expr.func.name), args.join(", "))
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let op = match unary_expr.op {
+          UnaryOpModel::Negate => "-",
+          UnaryOpModel::Not => "!",
+          UnaryOpModel::Complement => "~",
+        };
+        let subexpr = self.expr(&unary_expr.subexpr);
+        if matches!(unary_expr.subexpr.kind,
+          ExpressionModelKind::Unary(_) | ExpressionModelKind::Binary(_)
+        ) || subexpr.starts_with('-') {
+          format!("{}({})", op, subexpr)
+        } else {
+          format!("{}{}", op, subexpr)
+        }
+      },
+      ExpressionModelKind::Binary(binary_expr) => {
+        if is_fmod_call(expr) {
+          return self.fmod(expr, &binary_expr.lhs, &binary_expr.rhs);
+        }
+        let lhs = self.operand(&binary_expr.lhs);
+        let rhs = self.operand(&binary_expr.rhs);
+        format!("{} {} {}", lhs, binary_op(binary_expr.op), rhs)
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        if let Some(cast) = saturating_cast(expr) {
+          return self.saturating_cast(expr, &cast_expr.subexpr, cast);
+        }
+        let ty = self.type_name(&expr.ty);
+        let subexpr = self.expr(&cast_expr.subexpr);
+        format!("static_cast<{}>({})", ty, subexpr)
+      },
+    }
+  }
+
+  /**
+   * A cast from floats to integers, through a helper function of the
+   * result type, as Metal's conversions are undefined out of range.
+   * The helpers of vectors are named after the helpers of their
+   * scalars, so they follow them.
+   */
+  fn saturating_cast(&mut self,
+    expr: &ExpressionModel,
+    subexpr: &ExpressionModel,
+    cast: SaturatingCast,
+  ) -> String {
+    let scalar_ty = scalar_name(cast.to);
+    let scalar_helper = format!("{}_{}", CAST, scalar_ty);
+    self.helpers.entry(scalar_helper.clone()).or_insert_with(|| {
+      let [zero, min, max] = [cast.zero, cast.min, cast.max].map(|l| literal_expr(&l));
+      let mut body = String::new();
+      writeln!(body, "{} {}(float a) {{", scalar_ty, scalar_helper).unwrap();
+      writeln!(body,
+        "  return isnan(a) ? {} : a <= {} ? {} : a >= {} ? {} : static_cast<{}>(a);",
+        zero, float_literal(cast.lo, FROM_BITS), min, float_literal(cast.hi, FROM_BITS), max,
+        scalar_ty
+      ).unwrap();
+      writeln!(body, "}}").unwrap();
+      body
+    });
+
+    let ty = self.type_name(&expr.ty);
+    let float_ty = match &*expr.ty {
+      TypeModel::Vector(vector_ty) =>
+        self.type_name(&TypeModel::new_vector(ScalarNumericTypeModel::F32, vector_ty.dims)),
+      _ => "float".to_string(),
+    };
+    let helper = match &*expr.ty {
+      TypeModel::Vector(vector_ty) => {
+        let helper = format!("{}_{}", CAST, ty);
+        self.helpers.entry(helper.clone()).or_insert_with(|| {
+          let components = SWIZZLE_LETTERS[..vector_ty.dims as usize].iter()
+            .map(|c| format!("{}(a.{})", scalar_helper, c))
+            .collect::<Vec<_>>();
+          format!(
+            "{} {}({} a) {{\n  return {}({});\n}}\n",
+            ty, helper, float_ty, ty, components.join(", ")
+          )
+        });
+        helper
+      },
+      _ => scalar_helper,
+    };
+    let mut subexpr = self.expr(subexpr);
+    if cast.from_f16 {
+      subexpr = format!("static_cast<{}>({})", float_ty, subexpr);
+    }
+    format!("{}({})", helper, subexpr)
+  }
+
+  /**
+   * An expression that is indexed or has a member selected.
+   */
+  fn postfix_target(&mut self, target: &ExpressionModel) -> String {
+    let target_expr = self.expr(target);
+    parenthesize_target(target, target_expr)
+  }
+
+  /**
+   * An operand of a binary operation.  Nested operations are always
+   * parenthesized, for clarity.
+   */
+  fn operand(&mut self, operand: &ExpressionModel) -> String {
+    let operand_expr = self.expr(operand);
+    if matches!(operand.kind, ExpressionModelKind::Binary(_)) && !is_fmod_call(operand) {
+      format!("({})", operand_expr)
+    } else {
+      operand_expr
+    }
+  }
+
+  /**
+   * The truncated remainder of float operands, which `%` doesn't take.
+   * A scalar operand of a vector remainder is splatted to match.
+   */
+  fn fmod(&mut self, expr: &ExpressionModel, lhs: &ExpressionModel, rhs: &ExpressionModel)
+    -> String
+  {
+    let ty = self.type_name(&expr.ty);
+    let mut operand = |operand: &ExpressionModel| {
+      let operand_expr = self.expr(operand);
+      if operand.ty == expr.ty {
+        operand_expr
+      } else {
+        format!("{}({})", ty, operand_expr)
+      }
+    };
+    let lhs = operand(lhs);
+    let rhs = operand(rhs);
+    format!("fmod({}, {})", lhs, rhs)
+  }
+
+  fn type_name(&mut self, ty: &TypeModel) -> String {
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) =>
+        "bool".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) =>
+        "void".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => scalar_name(*scalar).to_string(),
+      TypeModel::Vector(vector_ty) =>
+        format!("{}{}", scalar_name(vector_ty.scalar), vector_ty.dims as u8),
+      TypeModel::Matrix(matrix_ty) => format!(
+        "{}{}x{}",
+        scalar_name(matrix_ty.scalar),
+        matrix_ty.cols as u8,
+        matrix_ty.rows as u8
+      ),
+      TypeModel::Array(array_ty) => {
+        let elem = self.type_name(&array_ty.elem);
+        let len = array_ty.len.expect("Only sized arrays are named");
+        format!("array<{}, {}>", elem, len)
+      },
+      TypeModel::Struct(struct_ty) => struct_name(struct_ty, ident),
+    }
+  }
+}
+
+/**
+ * The resources that each function uses, directly or through the
+ * functions it calls.
+ */
+fn resource_uses(model: &ShaderFileModel) -> HashMap<String, BTreeSet<Resource<'_>>> {
+  let mut direct = HashMap::new();
+  let mut calls = HashMap::new();
+  for func in model.funcs() {
+    let mut uses = BTreeSet::new();
+    let mut callees = BTreeSet::new();
+    for_each_expr(&func.body, &mut |expr| match &expr.kind {
+      ExpressionModelKind::Uniforms => {
+        uses.insert(Resource::Uniforms);
+      },
+      ExpressionModelKind::Buffer(name) => {
+        let buffer = model.buffers().iter()
+          .find(|buffer| buffer.name() == name.name)
+          .expect("Checked buffer is declared");
+        uses.insert(Resource::Buffer(buffer));
+      },
+      ExpressionModelKind::ArrayLength(_) => {
+        uses.insert(Resource::Sizes);
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        callees.insert(call_expr.func.name.clone());
+      },
+      _ => {},
+    });
+    direct.insert(func.name.name.clone(), uses);
+    calls.insert(func.name.name.clone(), callees);
+  }
+
+  let mut uses = direct;
+  loop {
+    let mut changed = false;
+    for (func, callees) in &calls {
+      let mut func_uses = uses[func].clone();
+      for callee in callees {
+        func_uses.extend(uses[callee].iter().copied());
+      }
+      if func_uses.len() != uses[func].len() {
+        uses.insert(func.clone(), func_uses);
+        changed = true;
+      }
+    }
+    if !changed {
+      break;
+    }
+  }
+  uses
+}
+
+/**
+ * Visit each expression of a block, and each of its subexpressions.
+ */
+fn for_each_expr(statements: &[StatementModel], f: &mut impl FnMut(&ExpressionModel)) {
+  for stmt in statements {
+    match stmt {
+      StatementModel::Let(let_stmt) => visit_expr(&let_stmt.value, f),
+      StatementModel::Var(var_stmt) => visit_expr(&var_stmt.value, f),
+      StatementModel::Mutate(mutate_stmt) => {
+        visit_expr(&mutate_stmt.lvalue, f);
+        visit_expr(&mutate_stmt.value, f);
+      },
+      StatementModel::Exec(exec_stmt) => visit_expr(&exec_stmt.expr, f),
+      StatementModel::Ret(ret_stmt) => {
+        if let Some(value) = &ret_stmt.value {
+          visit_expr(value, f);
+        }
+      },
+      StatementModel::If(if_stmt) => {
+        visit_expr(&if_stmt.cond, f);
+        for_each_expr(&if_stmt.if_block, f);
+        if let Some(else_block) = &if_stmt.else_block {
+          for_each_expr(else_block, f);
+        }
+      },
+      StatementModel::Loop(loop_stmt) => for_each_expr(&loop_stmt.block, f),
+      StatementModel::Assert(assert_stmt) => visit_expr(&assert_stmt.cond, f),
+    }
+  }
+}
+
+fn visit_expr(expr: &ExpressionModel, f: &mut impl FnMut(&ExpressionModel)) {
+  f(expr);
+  match &expr.kind {
+    ExpressionModelKind::Index(index_expr) => {
+      visit_expr(&index_expr.target, f);
+      visit_expr(&index_expr.index, f);
+    },
+    ExpressionModelKind::ArrayLength(length_expr) => visit_expr(&length_expr.target, f),
+    ExpressionModelKind::Field(field_expr) => visit_expr(&field_expr.target, f),
+    ExpressionModelKind::Swizzle(swizzle_expr) => visit_expr(&swizzle_expr.target, f),
+    ExpressionModelKind::Construct(construct_expr) =>
+      construct_expr.args.iter().for_each(|arg| visit_expr(arg, f)),
+    ExpressionModelKind::Call(call_expr) =>
+      call_expr.args.iter().for_each(|arg| visit_expr(arg, f)),
+    ExpressionModelKind::Unary(unary_expr) => visit_expr(&unary_expr.subexpr, f),
+    ExpressionModelKind::Binary(binary_expr) => {
+      visit_expr(&binary_expr.lhs, f);
+      visit_expr(&binary_expr.rhs, f);
+    },
+    ExpressionModelKind::Cast(cast_expr) => visit_expr(&cast_expr.subexpr, f),
+    ExpressionModelKind::Literal(_) |
+    ExpressionModelKind::Local(_) |
+    ExpressionModelKind::Uniforms |
+    ExpressionModelKind::Buffer(_) => {},
+  }
+}
+
+/**
+ * The number of elements of a buffer's runtime-sized array, from the
+ * byte size of the buffer.
+ */
+fn array_length(target: &ExpressionModel) -> String {
+  let (name, offset, layout) = match &target.kind {
+    ExpressionModelKind::Buffer(name) =>
+      (name, 0, target.ty.layout(LayoutRules::Storage).expect("Buffer has a storage layout")),
+    ExpressionModelKind::Field(field_expr) => {
+      let ExpressionModelKind::Buffer(name) = &field_expr.target.kind else {
+        unreachable!("Runtime-sized array outside of a buffer");
+      };
+      let layout = field_expr.target.ty.layout(LayoutRules::Storage)
+        .expect("Buffer has a storage layout");
+      let TypeLayoutKind::Struct(mut struct_layout) = layout.kind else {
+        unreachable!("Field access on non-struct layout");
+      };
+      let field_layout = struct_layout.fields.swap_remove(field_expr.field as usize);
+      (name, field_layout.offset, field_layout.layout)
+    },
+    _ => unreachable!("Runtime-sized array outside of a buffer"),
+  };
+  let TypeLayoutKind::Array(array_layout) = &layout.kind else {
+    unreachable!("Array length of non-array layout");
+  };
+  if offset == 0 {
+    format!("({}.{} / {}u)", SIZES, ident(&name.name), array_layout.stride)
+  } else {
+    format!("(({}.{} - {}u) / {}u)", SIZES, ident(&name.name), offset, array_layout.stride)
+  }
+}
+
+/**
+ * Whether an expression is a struct field declared as a packed vector.
+ */
+fn is_packed_place(expr: &ExpressionModel) -> bool {
+  matches!(expr.kind, ExpressionModelKind::Field(_)) && is_packed_member(&expr.ty)
+}
+
+/**
+ * The address space of a place: a buffer's, or the invocation's own.
+ */
+fn place_space(place: &ExpressionModel) -> &'static str {
+  match &place.kind {
+    ExpressionModelKind::Buffer(_) => "device",
+    ExpressionModelKind::Field(field_expr) => place_space(&field_expr.target),
+    ExpressionModelKind::Index(index_expr) => place_space(&index_expr.target),
+    _ => "thread",
+  }
+}
+
+/**
+ * Whether an expression is a remainder of floats, written as a call
+ * to `fmod`.
+ */
+fn is_fmod_call(expr: &ExpressionModel) -> bool {
+  matches!(&expr.kind, ExpressionModelKind::Binary(binary_expr)
+    if binary_expr.op == BinaryOpModel::Mod &&
+      expr.ty.numeric_element().is_some_and(|e| e.is_float())
+  )
+}
+
+fn literal_expr(literal: &LiteralModel) -> String {
+  match *literal {
+    LiteralModel::Bool(value) => value.to_string(),
+    // The magnitudes of the minimum integers are out of range for their
+    // literals.
+    LiteralModel::I32(i32::MIN) => "(-2147483647 - 1)".to_string(),
+    LiteralModel::I32(value) => value.to_string(),
+    LiteralModel::U32(value) => format!("{}u", value),
+    LiteralModel::F32(bits) => float_literal(f32::from_bits(bits), FROM_BITS),
+    LiteralModel::F16(bits) => {
+      let value = f32::from_bits(bits);
+      if value.is_finite() {
+        format!("{:?}h", value)
+      } else {
+        format!("half({})", float_literal(value, FROM_BITS))
+      }
+    },
+    LiteralModel::I64(i64::MIN) => "(-9223372036854775807l - 1l)".to_string(),
+    LiteralModel::I64(value) => format!("{}l", value),
+    LiteralModel::U64(value) => format!("{}ul", value),
+  }
+}
+
+fn scalar_name(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::F16 => "half",
+    ScalarNumericTypeModel::F32 => "float",
+    ScalarNumericTypeModel::I32 => "int",
+    ScalarNumericTypeModel::U32 => "uint",
+    ScalarNumericTypeModel::I64 => "long",
+    ScalarNumericTypeModel::U64 => "ulong",
+  }
+}
+
+/**
+ * The prefix of the saturating cast helper functions.
+ */
+const CAST: &str = "dubgsl_cast";
+
+/**
+ * The function making a float from its bits, for non-finite literals.
+ */
+const FROM_BITS: &str = "as_type<float>";
+
+/**
+ * The struct holding the byte size of each buffer, and its parameter.
+ */
+const SIZES_STRUCT: &str = "dubgsl_buffer_sizes";
+const SIZES: &str = "dubgsl_sizes";
+
+/**
+ * Temporaries of assignments to several components of packed vectors.
+ */
+const SWIZZLE_TARGET: &str = "dubgsl_target";
+const SWIZZLE_VALUE: &str = "dubgsl_value";
+
+/**
+ * A name as an MSL identifier.  Names that MSL or C++ reserve,
+ * including the names of vector and matrix types, and names starting
+ * with the `dubgsl_` prefix of generated names, get the prefix.
+ */
+fn ident(name: &str) -> String {
+  if name.starts_with("dubgsl_") || RESERVED.contains(&name) || is_type_name(name) {
+    format!("dubgsl_{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/**
+ * Whether a name is a scalar, vector or matrix type, such as `uint`,
+ * `packed_half3` or `float4x4`.
+ */
+fn is_type_name(name: &str) -> bool {
+  let name = name.strip_prefix("packed_").unwrap_or(name);
+  TYPE_PREFIXES.iter().any(|prefix| {
+    let Some(dims) = name.strip_prefix(prefix) else {
+      return false;
+    };
+    let is_dim = |c: &u8| (b'2'..=b'4').contains(c);
+    match dims.as_bytes() {
+      [] => true,
+      [n] => is_dim(n),
+      [cols, b'x', rows] => is_dim(cols) && is_dim(rows),
+      _ => false,
+    }
+  })
+}
+
+const TYPE_PREFIXES: &[&str] = &[
+  "bool", "char", "uchar", "short", "ushort", "int", "uint", "long", "ulong",
+  "half", "float", "double", "bfloat", "size_t", "ptrdiff_t", "int8_t",
+  "uint8_t", "int16_t", "uint16_t", "int32_t", "uint32_t", "int64_t",
+  "uint64_t",
+];
+
+/**
+ * C++ and MSL keywords, the types and functions of the Metal standard
+ * library, which the output brings into scope, and names the output
+ * declares.
+ */
+const RESERVED: &[&str] = &[
+  // Keywords.
+  "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor",
+  "break", "case", "catch", "class", "compl", "const", "const_cast",
+  "constant", "constexpr", "continue", "decltype", "default", "delete",
+  "device", "do", "dynamic_cast", "else", "enum", "explicit", "export",
+  "extern", "false", "fragment", "for", "friend", "goto", "if", "inline",
+  "kernel", "mutable", "namespace", "new", "noexcept", "not", "not_eq",
+  "nullptr", "object_data", "operator", "or", "or_eq", "private",
+  "protected", "public", "ray_data", "register", "reinterpret_cast",
+  "return", "signed", "sizeof", "static", "static_assert", "static_cast",
+  "struct", "switch", "template", "this", "thread", "thread_local",
+  "threadgroup", "threadgroup_imageblock", "throw", "true", "try",
+  "typedef", "typeid", "typename", "union", "unsigned", "using", "vertex",
+  "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+
+  // Standard library types.
+  "array", "array_ref", "atomic", "atomic_bool", "atomic_float",
+  "atomic_int", "atomic_uint", "depth2d", "metal", "packed", "sampler",
+  "texture1d", "texture2d", "texture3d", "texturecube", "vec",
+
+  // Standard library functions.
+  "abs", "absdiff", "acos", "acosh", "addsat", "all", "any", "as_type",
+  "asin", "asinh", "atan", "atan2", "atanh", "ceil", "clamp", "clz",
+  "copysign", "cos", "cosh", "cospi", "cross", "ctz", "degrees",
+  "determinant", "distance", "distance_squared", "divide", "dot", "exp",
+  "exp10", "exp2", "extract_bits", "fabs", "fdim", "floor", "fma", "fmax",
+  "fmax3", "fmedian3", "fmin", "fmin3", "fmod", "fract", "frexp",
+  "insert_bits", "isfinite", "isinf", "isnan", "isnormal", "isordered",
+  "isunordered", "ldexp", "length", "length_squared", "log", "log10",
+  "log2", "mad", "madhi", "madsat", "max", "max3", "median3", "min", "min3",
+  "mix", "modf", "mulhi", "nextafter", "normalize", "popcount", "pow",
+  "powr", "precise", "radians", "reflect", "refract", "reverse_bits",
+  "rint", "rotate", "round", "rsqrt", "saturate", "select", "sign",
+  "signbit", "sin", "sincos", "sinh", "sinpi", "smoothstep", "sqrt", "step",
+  "subsat", "tan", "tanh", "tanpi", "transpose", "trunc",
+  "threadgroup_barrier",
+
+  // Names the output declares.
+  "main", "uniforms",
+];
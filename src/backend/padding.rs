@@ -0,0 +1,507 @@
+use std::{
+  collections::HashMap,
+  fmt,
+};
+use crate::model::{
+  ArrayLayout,
+  FieldLayout,
+  LayoutRules,
+  MatrixLayout,
+  ModelHandle,
+  NameModel,
+  NamePathModel,
+  ScalarNumericTypeModel,
+  ScalarTypeModel,
+  StructFieldModel,
+  StructLayout,
+  StructTypeModel,
+  TypeLayout,
+  TypeLayoutKind,
+  TypeModel,
+  VecDims,
+};
+
+/**
+ * The prefix of padding member names.
+ */
+pub(crate) const PAD: &str = "dubgsl_pad";
+
+/**
+ * How a target language lays out the structs it declares, which have
+ * no alignment or size attributes of their own.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Packing {
+  /** GLSL `std430` blocks, which follow storage rules. */
+  Std430,
+
+  /** GLSL `std140` blocks, which follow uniform rules. */
+  Std140,
+
+  /** HLSL structured buffers, which align data to its scalars. */
+  Structured,
+
+  /**
+   * HLSL constant buffers, which align data to its scalars but keep
+   * vectors within 16-byte rows, and start aggregates on a new row.
+   */
+  Constant,
+
+  /**
+   * Metal structs, which are laid out as C structs are.  Members that
+   * are 3-component vectors are packed, as `float3` is 16 bytes.
+   */
+  Metal,
+
+  /** Metal structs in the constant address space, for the uniforms. */
+  MetalUniforms,
+}
+impl Packing {
+  /**
+   * The rules of the layout that the packing must reproduce.
+   */
+  pub(crate) fn rules(self) -> LayoutRules {
+    match self {
+      Packing::Std430 | Packing::Structured | Packing::Metal => LayoutRules::Storage,
+      Packing::Std140 | Packing::Constant | Packing::MetalUniforms => LayoutRules::Uniform,
+    }
+  }
+
+  /**
+   * Lay out a host-shareable type without alignment or size attributes.
+   */
+  pub(crate) fn layout(self, ty: &TypeModel) -> TypeLayout {
+    match self {
+      Packing::Std430 | Packing::Std140 =>
+        ty.layout(self.rules()).expect("Padded type has a layout"),
+      Packing::Structured | Packing::Constant => self.hlsl_layout(ty),
+      Packing::Metal | Packing::MetalUniforms => metal_layout(ty, false),
+    }
+  }
+
+  fn hlsl_layout(self, ty: &TypeModel) -> TypeLayout {
+    let is_constant = self == Packing::Constant;
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => {
+        let size = scalar.size();
+        TypeLayout { size, align: size, kind: TypeLayoutKind::Scalar }
+      },
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(_)) =>
+        unreachable!("Padded type is host-shareable"),
+      TypeModel::Vector(vector_ty) => TypeLayout {
+        size: vector_ty.scalar.size() * vector_ty.dims as u32,
+        align: vector_ty.scalar.size(),
+        kind: TypeLayoutKind::Vector,
+      },
+      // Matrices are row-major with a row for each column, so that
+      // their rows are laid out as columns are.
+      TypeModel::Matrix(matrix_ty) => {
+        let column_size = matrix_ty.scalar.size() * matrix_ty.rows as u32;
+        let column_stride = if is_constant { 16 } else { column_size };
+        TypeLayout {
+          size: column_stride * (matrix_ty.cols as u32 - 1) + column_size,
+          align: if is_constant { 16 } else { matrix_ty.scalar.size() },
+          kind: TypeLayoutKind::Matrix(MatrixLayout { column_stride }),
+        }
+      },
+      // The last element of an array in a constant buffer isn't padded
+      // out to its row.
+      TypeModel::Array(array_ty) => {
+        let elem = self.hlsl_layout(&array_ty.elem);
+        let stride = if is_constant { elem.size.next_multiple_of(16) } else { elem.size };
+        let len = array_ty.len.unwrap_or(0);
+        let size = match len {
+          0 => 0,
+          len if is_constant => stride * (len - 1) + elem.size,
+          len => stride * len,
+        };
+        TypeLayout {
+          size,
+          align: if is_constant { 16 } else { elem.align },
+          kind: TypeLayoutKind::Array(ArrayLayout {
+            elem: Box::new(elem),
+            stride,
+            len: array_ty.len,
+          }),
+        }
+      },
+      TypeModel::Struct(struct_ty) => {
+        let mut fields = Vec::new();
+        let mut offset = 0u32;
+        let mut align = if is_constant { 16 } else { 1 };
+        for field in &struct_ty.fields {
+          let layout = self.hlsl_layout(&field.ty);
+          let mut field_offset = offset.next_multiple_of(layout.align);
+          if is_constant && field_offset % 16 + layout.size > 16 {
+            field_offset = field_offset.next_multiple_of(16);
+          }
+          offset = field_offset + layout.size;
+          align = align.max(layout.align);
+          fields.push(FieldLayout {
+            name: field.name.clone(),
+            offset: field_offset,
+            padding: 0,
+            layout,
+          });
+        }
+        TypeLayout {
+          size: if is_constant { offset } else { offset.next_multiple_of(align) },
+          align,
+          kind: TypeLayoutKind::Struct(StructLayout { fields }),
+        }
+      },
+    }
+  }
+}
+impl fmt::Display for Packing {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Packing::Std430 => write!(f, "std430 layout"),
+      Packing::Std140 => write!(f, "std140 layout"),
+      Packing::Structured => write!(f, "structured buffer packing"),
+      Packing::Constant => write!(f, "constant buffer packing"),
+      Packing::Metal | Packing::MetalUniforms => write!(f, "Metal layout"),
+    }
+  }
+}
+
+/**
+ * The layout of a type in Metal, as a struct member or otherwise.
+ */
+fn metal_layout(ty: &TypeModel, is_member: bool) -> TypeLayout {
+  match ty {
+    TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => {
+      let size = scalar.size();
+      TypeLayout { size, align: size, kind: TypeLayoutKind::Scalar }
+    },
+    TypeModel::Scalar(ScalarTypeModel::Symbolic(_)) =>
+      unreachable!("Padded type is host-shareable"),
+    TypeModel::Vector(vector_ty) if is_member && is_packed_member(ty) => TypeLayout {
+      size: vector_ty.scalar.size() * 3,
+      align: vector_ty.scalar.size(),
+      kind: TypeLayoutKind::Vector,
+    },
+    TypeModel::Vector(vector_ty) => {
+      let size = vector_ty.scalar.size() * (vector_ty.dims as u32).next_power_of_two();
+      TypeLayout { size, align: size, kind: TypeLayoutKind::Vector }
+    },
+    TypeModel::Matrix(matrix_ty) => {
+      let column_stride =
+        matrix_ty.scalar.size() * (matrix_ty.rows as u32).next_power_of_two();
+      TypeLayout {
+        size: column_stride * matrix_ty.cols as u32,
+        align: column_stride,
+        kind: TypeLayoutKind::Matrix(MatrixLayout { column_stride }),
+      }
+    },
+    TypeModel::Array(array_ty) => {
+      let elem = metal_layout(&array_ty.elem, false);
+      let stride = elem.size.next_multiple_of(elem.align);
+      TypeLayout {
+        size: stride * array_ty.len.unwrap_or(0),
+        align: elem.align,
+        kind: TypeLayoutKind::Array(ArrayLayout {
+          elem: Box::new(elem),
+          stride,
+          len: array_ty.len,
+        }),
+      }
+    },
+    TypeModel::Struct(struct_ty) => {
+      let mut fields = Vec::new();
+      let mut offset = 0u32;
+      let mut align = 1;
+      for field in &struct_ty.fields {
+        let layout = metal_layout(&field.ty, true);
+        let field_offset = offset.next_multiple_of(layout.align);
+        offset = field_offset + layout.size;
+        align = align.max(layout.align);
+        fields.push(FieldLayout {
+          name: field.name.clone(),
+          offset: field_offset,
+          padding: 0,
+          layout,
+        });
+      }
+      TypeLayout {
+        size: offset.next_multiple_of(align),
+        align,
+        kind: TypeLayoutKind::Struct(StructLayout { fields }),
+      }
+    },
+  }
+}
+
+/**
+ * Whether a struct member of a type is declared as a packed vector in
+ * Metal.  Metal has packed vectors of 16 and 32-bit scalars.
+ */
+pub(crate) fn is_packed_member(ty: &TypeModel) -> bool {
+  matches!(ty, TypeModel::Vector(vector_ty)
+    if vector_ty.dims == VecDims::Vec3 && vector_ty.scalar.size() <= 4
+  )
+}
+
+/**
+ * A struct with padding members placing its fields where the struct's
+ * layout has them, for a target that can't align or size members.
+ * Nested structs are padded in turn, so that `padded` lays out as the
+ * target's struct does.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct PaddedStruct {
+  pub(crate) padded: StructTypeModel,
+  /** The index in `padded` of each field of the struct. */
+  pub(crate) field_members: Vec<usize>,
+}
+impl PaddedStruct {
+  /**
+   * The members of the padded struct, with each field of the struct
+   * given as it was declared.
+   */
+  pub(crate) fn members<'a>(&'a self, struct_ty: &'a StructTypeModel)
+    -> impl Iterator<Item = PaddedMember<'a>>
+  {
+    let mut fields = struct_ty.fields.iter().zip(&self.field_members).peekable();
+    self.padded.fields.iter().enumerate().map(move |(i, member)| {
+      match fields.next_if(|&(_, &field_member)| field_member == i) {
+        Some((field, _)) => PaddedMember::Field(field),
+        None => PaddedMember::Pad(member),
+      }
+    })
+  }
+}
+
+pub(crate) enum PaddedMember<'a> {
+  Field(&'a StructFieldModel),
+  Pad(&'a StructFieldModel),
+}
+
+/**
+ * The padded structs of a shader file, for a target.
+ */
+pub(crate) struct PaddedStructs {
+  target: &'static str,
+  structs: HashMap<NamePathModel, PaddedStruct>,
+}
+impl PaddedStructs {
+  pub(crate) fn new(target: &'static str) -> PaddedStructs {
+    PaddedStructs { target, structs: HashMap::new() }
+  }
+
+  pub(crate) fn get(&self, struct_ty: &StructTypeModel) -> Option<&PaddedStruct> {
+    self.structs.get(&struct_ty.name)
+  }
+
+  /**
+   * Record a padded struct, so that structs containing it can be
+   * padded.
+   */
+  pub(crate) fn insert(&mut self, struct_ty: &StructTypeModel, padded: PaddedStruct) {
+    self.structs.insert(struct_ty.name.clone(), padded);
+  }
+
+  /**
+   * Pad a struct so that the packing lays it out as the packing's rules
+   * do, or give the reasons it can't be.  Padding runs from the end of
+   * each field's data to the next field's offset, so it covers any
+   * `@size` bytes.  Structs that aren't host-shareable are never laid
+   * out in memory, and are left as they are.
+   */
+  pub(crate) fn pad(&self, struct_ty: &StructTypeModel, packing: Packing)
+    -> Result<PaddedStruct, Vec<String>>
+  {
+    let rules = packing.rules();
+    let Ok(layout) = TypeModel::Struct(struct_ty.clone()).layout(rules) else {
+      return Ok(PaddedStruct {
+        padded: struct_ty.clone(),
+        field_members: (0..struct_ty.fields.len()).collect(),
+      });
+    };
+    let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+      unreachable!("Struct has non-struct layout");
+    };
+
+    let mut padded = StructTypeModel {
+      name: struct_ty.name.clone(),
+      fields: Vec::new(),
+      packed: false,
+    };
+    let mut field_members = Vec::new();
+    for (field, field_layout) in struct_ty.fields.iter().zip(&struct_layout.fields) {
+      let ty = self.padded_type(&field.ty);
+      let end = data_end(&packing.layout(&TypeModel::Struct(padded.clone())));
+      padded.fields.push(StructFieldModel {
+        name: field.name.clone(),
+        ty: ModelHandle::new(ty.clone()),
+        align: None,
+        size: None,
+      });
+      let offset = last_offset(&packing.layout(&TypeModel::Struct(padded.clone())));
+      if offset != field_layout.offset {
+        padded.fields.pop();
+        pad(&mut padded, end, field_layout.offset, layout.align);
+        padded.fields.push(StructFieldModel {
+          name: field.name.clone(),
+          ty: ModelHandle::new(ty),
+          align: None,
+          size: None,
+        });
+        let offset = last_offset(&packing.layout(&TypeModel::Struct(padded.clone())));
+        if offset != field_layout.offset {
+          return Err(vec![format!(
+            "{} at offset {} cannot be expressed in {}, as {} places it at \
+             offset {}.",
+            describe_field(struct_ty, field, rules), field_layout.offset,
+            self.target, packing, offset
+          )]);
+        }
+      }
+      field_members.push(padded.fields.len() - 1);
+    }
+
+    let mut padded_layout = packing.layout(&TypeModel::Struct(padded.clone()));
+    if !layout.is_runtime_sized() {
+      if padded_layout.size < layout.size {
+        pad(&mut padded, data_end(&padded_layout), layout.size, layout.align);
+        padded_layout = packing.layout(&TypeModel::Struct(padded.clone()));
+      }
+      if padded_layout.size != layout.size {
+        return Err(vec![format!(
+          "Struct `{}` cannot be expressed in {}, as {} gives it a size of {} \
+           bytes rather than {}.",
+          struct_ty.name, self.target, packing, padded_layout.size, layout.size
+        )]);
+      }
+    }
+    let TypeLayoutKind::Struct(padded_struct_layout) = &padded_layout.kind else {
+      unreachable!("Struct has non-struct layout");
+    };
+
+    // The fields are in place, but nested types may still be laid out
+    // differently within.
+    let errors = struct_ty.fields.iter()
+      .zip(&struct_layout.fields)
+      .zip(&field_members)
+      .filter(|((field, field_layout), &member)| {
+        let member_layout = &padded_struct_layout.fields[member].layout;
+        !self.same_inner_layout(&field.ty, &field_layout.layout, member_layout)
+      })
+      .map(|((field, _), _)| format!(
+        "{} of type `{}` cannot be expressed in {}, as {} places its contents \
+         differently.",
+        describe_field(struct_ty, field, rules), *field.ty, self.target, packing
+      ))
+      .collect::<Vec<_>>();
+    if !errors.is_empty() {
+      return Err(errors);
+    }
+    Ok(PaddedStruct { padded, field_members })
+  }
+
+  /**
+   * A type as the target declares it, with structs replaced by their
+   * padded forms.
+   */
+  pub(crate) fn padded_type(&self, ty: &TypeModel) -> TypeModel {
+    match ty {
+      TypeModel::Array(array_ty) => TypeModel::new_array(
+        ModelHandle::new(self.padded_type(&array_ty.elem)), array_ty.len
+      ),
+      TypeModel::Struct(struct_ty) => match self.get(struct_ty) {
+        Some(padded) => TypeModel::Struct(padded.padded.clone()),
+        None => ty.clone(),
+      },
+      _ => ty.clone(),
+    }
+  }
+
+  /**
+   * Whether the packing lays out everything within a type as the
+   * packing's rules do, though not necessarily its own size.
+   */
+  pub(crate) fn same_layout(&self, ty: &TypeModel, packing: Packing) -> bool {
+    let layout = ty.layout(packing.rules()).expect("Buffer type has a layout");
+    let padded_layout = packing.layout(&self.padded_type(ty));
+    self.same_inner_layout(ty, &layout, &padded_layout)
+  }
+
+  fn same_inner_layout(&self, ty: &TypeModel, layout: &TypeLayout, padded: &TypeLayout)
+    -> bool
+  {
+    match (ty, &layout.kind, &padded.kind) {
+      (_, TypeLayoutKind::Matrix(a), TypeLayoutKind::Matrix(b)) =>
+        a.column_stride == b.column_stride,
+      (TypeModel::Array(array_ty), TypeLayoutKind::Array(a), TypeLayoutKind::Array(b)) =>
+        a.stride == b.stride && self.same_inner_layout(&array_ty.elem, &a.elem, &b.elem),
+      (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(a), TypeLayoutKind::Struct(b)) =>
+        match self.get(struct_ty) {
+          Some(padded_struct) => struct_ty.fields.iter()
+            .zip(&a.fields)
+            .zip(&padded_struct.field_members)
+            .all(|((field, a), &member)| {
+              let b = &b.fields[member];
+              a.offset == b.offset && self.same_inner_layout(&field.ty, &a.layout, &b.layout)
+            }),
+          None => true,
+        },
+      _ => true,
+    }
+  }
+}
+
+/**
+ * Append padding members to a struct covering the bytes from `start` to
+ * `end`.  Members are `u32` where aligned, and `f16` around `f16` data,
+ * which is all a struct aligned to 2 bytes may hold.
+ */
+fn pad(padded: &mut StructTypeModel, start: u32, end: u32, struct_align: u32) {
+  let mut offset = start;
+  while offset < end {
+    let scalar = if struct_align >= 4 && offset.is_multiple_of(4) && end - offset >= 4 {
+      ScalarNumericTypeModel::U32
+    } else {
+      ScalarNumericTypeModel::F16
+    };
+    let count = padded.fields.iter()
+      .filter(|field| field.name().starts_with(PAD))
+      .count();
+    padded.fields.push(StructFieldModel {
+      name: NameModel::new(format!("{}{}", PAD, count)),
+      ty: ModelHandle::new(TypeModel::new_scalar(scalar)),
+      align: None,
+      size: None,
+    });
+    offset += scalar.size();
+  }
+}
+
+/**
+ * The end of the data of a struct's last field.
+ */
+fn data_end(layout: &TypeLayout) -> u32 {
+  struct_fields(layout).last()
+    .map(|field| field.offset + field.layout.size)
+    .unwrap_or(0)
+}
+
+fn last_offset(layout: &TypeLayout) -> u32 {
+  struct_fields(layout).last().map(|field| field.offset).unwrap_or(0)
+}
+
+fn struct_fields(layout: &TypeLayout) -> &[FieldLayout] {
+  let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+    unreachable!("Struct has non-struct layout");
+  };
+  &struct_layout.fields
+}
+
+fn describe_field(struct_ty: &StructTypeModel, field: &StructFieldModel, rules: LayoutRules)
+  -> String
+{
+  match rules {
+    LayoutRules::Storage => format!("Field `{}::{}`", struct_ty.name, field.name()),
+    LayoutRules::Uniform => format!("Uniforms field `{}`", field.name()),
+  }
+}
+
@@ -0,0 +1,1022 @@
+use std::fmt::Write;
+use crate::{
+  backend::{
+    Diagnostics,
+    binary_op,
+    flatten_swizzle,
+    if_chain,
+    parenthesize_target,
+    struct_name,
+  },
+  host_gen::{ pascal_case, rust_ident },
+  model::{
+    BinaryExprModel,
+    BinaryOpModel,
+    BufferModel,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LiteralModel,
+    MutateStmtModel,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructTypeModel,
+    TypeModel,
+    UnaryOpModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * Lower a checked shader file to Rust source that runs its entrypoints
+ * natively, in parallel with `rayon`.
+ *
+ * Structs become plain Rust structs rather than copies of their layout,
+ * with vectors as arrays of components and matrices as arrays of
+ * columns.  The uniforms and buffers are bound in a `Resources` struct:
+ * buffers that are only read as slices, and buffers that are written as
+ * `Shared` slices, whose elements are reached through raw pointers so
+ * that invocations running in parallel may write them.  A buffer of a
+ * struct ending in a runtime-sized array is bound as the struct's other
+ * fields, and a slice of the array.
+ *
+ * Each entrypoint is a method of `Resources` running one invocation,
+ * and `Resources::dispatch` runs an entrypoint over a grid of
+ * workgroups.  Dispatch is `unsafe`, as nothing checks that parallel
+ * invocations don't write the same elements.  Arithmetic follows the
+ * language as the interpreter does: integers wrap, and integer division
+ * by zero, shifts by at least the bit width and indices out of bounds
+ * panic.  The value of an assignment is evaluated before the place it
+ * is assigned to.
+ *
+ * Shaders using `f16` are reported, as Rust has no stable `f16`.
+ */
+pub fn generate_rust_cpu(model: &ShaderFileModel) -> Result<String, Vec<Diagnostic>> {
+  let mut gen = RustCpuGen {
+    out: String::new(),
+    diagnostics: Diagnostics::default(),
+    buffers: model.buffers().iter().map(BoundBuffer::of).collect(),
+    in_unsafe: false,
+  };
+  for ty in model.structs() {
+    let TypeModel::Struct(struct_ty) = &**ty else {
+      unreachable!("Non-struct type in shader file structs");
+    };
+    gen.write_struct(struct_ty);
+  }
+  if let Some(uniforms) = model.uniforms() {
+    let TypeModel::Struct(struct_ty) = &**uniforms.ty() else {
+      unreachable!("Non-struct uniforms type");
+    };
+    gen.write_struct(struct_ty);
+  }
+  gen.write_resources(model);
+  gen.write_entrypoint_enum(model);
+
+  let lifetime = if has_resources(model) { "<'_>" } else { "" };
+  writeln!(gen.out).unwrap();
+  writeln!(gen.out, "impl Resources{} {{", lifetime).unwrap();
+  gen.write_dispatch(model);
+  for entrypoint in model.entrypoints() {
+    gen.write_entrypoint(entrypoint);
+  }
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  writeln!(gen.out, "}}").unwrap();
+  if !gen.diagnostics.is_empty() {
+    return Err(gen.diagnostics.into_vec());
+  }
+
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path()).unwrap();
+  writeln!(out, "// Requires the `rayon` crate.").unwrap();
+  out.push_str(&gen.out);
+  out.push_str(RUNTIME_DECL);
+  Ok(out)
+}
+
+/**
+ * How a buffer is bound in `Resources`.
+ */
+struct BoundBuffer {
+  name: String,
+  /** Whether the buffer is written, and so bound as `Shared`. */
+  shared: bool,
+  /**
+   * The position and name of the runtime-sized array ending the
+   * buffer's struct, which is bound separately.
+   */
+  tail: Option<(u32, String)>,
+}
+impl BoundBuffer {
+  fn of(buffer: &BufferModel) -> BoundBuffer {
+    let tail = match &**buffer.ty() {
+      TypeModel::Struct(struct_ty) => {
+        let field = struct_ty.fields.last().expect("Runtime-sized struct has fields");
+        Some(((struct_ty.fields.len() - 1) as u32, field.name().to_string()))
+      },
+      _ => None,
+    };
+    BoundBuffer {
+      name: buffer.name().to_string(),
+      shared: buffer.mode().is_writable(),
+      tail,
+    }
+  }
+
+  /**
+   * The name of the `Resources` field binding the runtime-sized array
+   * of the buffer's struct.
+   */
+  fn tail_field(&self) -> Option<String> {
+    self.tail.as_ref().map(|(_, name)| ident(&format!("{}_{}", self.name, name)))
+  }
+}
+
+/**
+ * The state of Rust generation.  Diagnostics are collected rather than
+ * stopping at the first.
+ */
+struct RustCpuGen {
+  out: String,
+  diagnostics: Diagnostics,
+  buffers: Vec<BoundBuffer>,
+  /** Whether the expression being written is within an `unsafe` block. */
+  in_unsafe: bool,
+}
+impl RustCpuGen {
+  fn buffer(&self, name: &str) -> &BoundBuffer {
+    self.buffers.iter()
+      .find(|buffer| buffer.name == name)
+      .expect("Checked buffer is declared")
+  }
+
+  /**
+   * Write a struct.  The runtime-sized array ending a buffer's struct
+   * is left out, as it is bound separately.
+   */
+  fn write_struct(&mut self, struct_ty: &StructTypeModel) {
+    let mut fields = &struct_ty.fields[..];
+    writeln!(self.out).unwrap();
+    if let Some((last, sized)) = fields.split_last() {
+      if last.ty.is_runtime_sized() {
+        writeln!(self.out,
+          "/** The fields of `{}` before its runtime-sized `{}`. */",
+          struct_ty.name, last.name()
+        ).unwrap();
+        fields = sized;
+      }
+    }
+    let fields = fields.iter()
+      .map(|field| format!("{}: {}", ident(field.name()), self.type_name(&field.ty)))
+      .collect::<Vec<_>>();
+    writeln!(self.out, "#[derive(Clone, Copy, Debug, PartialEq)]").unwrap();
+    writeln!(self.out, "pub struct {} {{", struct_name(struct_ty, ident)).unwrap();
+    for field in fields {
+      writeln!(self.out, "  pub {},", field).unwrap();
+    }
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_resources(&mut self, model: &ShaderFileModel) {
+    self.out.push_str(RESOURCES_DOC);
+    if !has_resources(model) {
+      writeln!(self.out, "pub struct Resources;").unwrap();
+      return;
+    }
+    let mut fields = Vec::new();
+    if model.uniforms().is_some() {
+      fields.push("uniforms: &'a Uniforms".to_string());
+    }
+    for buffer in model.buffers() {
+      let bound = BoundBuffer::of(buffer);
+      let binding = |ty: String| if bound.shared {
+        format!("dubgsl_rt::Shared<'a, {}>", ty)
+      } else {
+        format!("&'a {}", ty)
+      };
+      match &**buffer.ty() {
+        TypeModel::Array(array_ty) => {
+          let elem = self.type_name(&array_ty.elem);
+          fields.push(format!("{}: {}", ident(&bound.name), binding(format!("[{}]", elem))));
+        },
+        TypeModel::Struct(struct_ty) => {
+          let TypeModel::Array(tail_ty) = &*struct_ty.fields.last().unwrap().ty else {
+            unreachable!("Runtime-sized struct does not end in an array");
+          };
+          let elem = self.type_name(&tail_ty.elem);
+          let name = struct_name(struct_ty, ident);
+          fields.push(format!("{}: {}", ident(&bound.name), binding(name)));
+          fields.push(format!(
+            "{}: {}", bound.tail_field().unwrap(), binding(format!("[{}]", elem))
+          ));
+        },
+        _ => unreachable!("Buffer of a sized type"),
+      }
+    }
+    writeln!(self.out, "pub struct Resources<'a> {{").unwrap();
+    for field in fields {
+      writeln!(self.out, "  pub {},", field).unwrap();
+    }
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_entrypoint_enum(&mut self, model: &ShaderFileModel) {
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]").unwrap();
+    writeln!(self.out, "pub enum Entrypoint {{").unwrap();
+    for entrypoint in model.entrypoints() {
+      writeln!(self.out, "  {},", pascal_case(entrypoint.name())).unwrap();
+    }
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Write the method running an entrypoint over a grid of workgroups.
+   */
+  fn write_dispatch(&mut self, model: &ShaderFileModel) {
+    self.out.push_str(DISPATCH_DOC);
+    writeln!(self.out,
+      "  pub unsafe fn dispatch(&self, entrypoint: Entrypoint, workgroups: [u32; 3]) {{"
+    ).unwrap();
+    writeln!(self.out, "    let resources = dubgsl_rt::AssertSync(self);").unwrap();
+    writeln!(self.out, "    match entrypoint {{").unwrap();
+    for entrypoint in model.entrypoints() {
+      let [x, y, z] = entrypoint.workgroup_size();
+      let name = ident(entrypoint.name());
+      let invocation = match entrypoint.dims {
+        EntrypointDims::D1 => format!("|[x, _, _]| resources.get().{}(x)", name),
+        EntrypointDims::D2 => format!("|[x, y, _]| resources.get().{}([x, y])", name),
+        EntrypointDims::D3 => format!("|id| resources.get().{}(id)", name),
+      };
+      writeln!(self.out,
+        "      Entrypoint::{} => dubgsl_rt::dispatch([{}, {}, {}], workgroups, {}),",
+        pascal_case(entrypoint.name()), x, y, z, invocation
+      ).unwrap();
+    }
+    writeln!(self.out, "    }}").unwrap();
+    writeln!(self.out, "  }}").unwrap();
+  }
+
+  /**
+   * Write an entrypoint as a method running one invocation, whose
+   * invocation id has a component for each dimension.
+   */
+  fn write_entrypoint(&mut self, entrypoint: &EntrypointModel) {
+    let ty = match entrypoint.dims {
+      EntrypointDims::D1 => "u32",
+      EntrypointDims::D2 => "[u32; 2]",
+      EntrypointDims::D3 => "[u32; 3]",
+    };
+    writeln!(self.out).unwrap();
+    writeln!(self.out,
+      "  pub fn {}(&self, {}: {}) {{",
+      ident(entrypoint.name()), ident(&entrypoint.arg_name.name), ty
+    ).unwrap();
+    self.write_block(&entrypoint.body, 2);
+    writeln!(self.out, "  }}").unwrap();
+  }
+
+  /**
+   * Write a function as a private method.  A function that may reach
+   * its end without returning its value panics there, as the
+   * interpreter stops.
+   */
+  fn write_func(&mut self, func: &FuncModel) {
+    let args = func.args.iter()
+      .map(|arg| format!("{}: {}", ident(&arg.name.name), self.type_name(&arg.ty)))
+      .collect::<Vec<_>>();
+    let return_ty = if func.return_ty.is_void() {
+      String::new()
+    } else {
+      format!(" -> {}", self.type_name(&func.return_ty))
+    };
+    writeln!(self.out).unwrap();
+    writeln!(self.out,
+      "  fn {}(&self, {}){} {{", ident(&func.name.name), args.join(", "), return_ty
+    ).unwrap();
+    self.write_block(&func.body, 2);
+    writeln!(self.out, "  }}").unwrap();
+  }
+
+  fn write_block(&mut self, statements: &[StatementModel], depth: usize) {
+    for stmt in statements {
+      self.write_stmt(stmt, depth);
+    }
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let value = self.expr(&let_stmt.value);
+        writeln!(self.out, "{}let {} = {};", indent, ident(&let_stmt.name.name), value)
+          .unwrap();
+      },
+      StatementModel::Var(var_stmt) => {
+        let value = self.expr(&var_stmt.value);
+        writeln!(self.out, "{}let mut {} = {};", indent, ident(&var_stmt.name.name), value)
+          .unwrap();
+      },
+      StatementModel::Mutate(mutate_stmt) => self.write_mutate(mutate_stmt, depth),
+      StatementModel::Exec(exec_stmt) => {
+        let expr = self.expr(&exec_stmt.expr);
+        if matches!(exec_stmt.expr.kind, ExpressionModelKind::Call(_)) {
+          writeln!(self.out, "{}{};", indent, expr).unwrap();
+        } else {
+          writeln!(self.out, "{}let _ = {};", indent, expr).unwrap();
+        }
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          writeln!(self.out, "{}return {};", indent, value).unwrap();
+        },
+        None => writeln!(self.out, "{}return;", indent).unwrap(),
+      },
+      StatementModel::If(if_stmt) => {
+        for (i, (cond, block)) in if_chain(if_stmt).into_iter().enumerate() {
+          let close = if i == 0 { "" } else { "} else " };
+          match cond {
+            Some(cond) => {
+              let cond = self.expr(cond);
+              writeln!(self.out, "{}{}if {} {{", indent, close, cond).unwrap();
+            },
+            None => writeln!(self.out, "{}}} else {{", indent).unwrap(),
+          }
+          self.write_block(block, depth + 1);
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Loop(loop_stmt) => {
+        writeln!(self.out, "{}loop {{", indent).unwrap();
+        self.write_block(&loop_stmt.block, depth + 1);
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  /**
+   * Write an assignment.  A swizzle of several components is assigned
+   * component by component, through a reference to the vector, or a
+   * pointer to it in a shared buffer.  Places in shared buffers are
+   * assigned in `unsafe` blocks, which also hold the value.
+   */
+  fn write_mutate(&mut self, mutate_stmt: &MutateStmtModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let (place, components) = flatten_swizzle(&mutate_stmt.lvalue);
+    let shared = self.is_shared(place);
+    if components.len() <= 1 {
+      self.in_unsafe = shared;
+      let value = self.expr(&mutate_stmt.value);
+      let mut place_expr = self.place(place);
+      self.in_unsafe = false;
+      if let [component] = components[..] {
+        write!(place_expr, "[{}]", component).unwrap();
+      }
+      if shared {
+        writeln!(self.out, "{}unsafe {{ {} = {}; }}", indent, place_expr, value).unwrap();
+      } else {
+        writeln!(self.out, "{}{} = {};", indent, place_expr, value).unwrap();
+      }
+      return;
+    }
+
+    let value = self.expr(&mutate_stmt.value);
+    self.in_unsafe = shared;
+    let place_expr = self.place(place);
+    self.in_unsafe = false;
+    writeln!(self.out, "{}{{", indent).unwrap();
+    writeln!(self.out, "{}  let {} = {};", indent, SWIZZLE_VALUE, value).unwrap();
+    if shared {
+      writeln!(self.out,
+        "{}  let {} = std::ptr::addr_of_mut!({});",
+        indent, SWIZZLE_TARGET, place_expr
+      ).unwrap();
+    } else {
+      writeln!(self.out, "{}  let {} = &mut {};", indent, SWIZZLE_TARGET, place_expr).unwrap();
+    }
+    for (i, &component) in components.iter().enumerate() {
+      if shared {
+        writeln!(self.out,
+          "{}  unsafe {{ (*{})[{}] = {}[{}]; }}",
+          indent, SWIZZLE_TARGET, component, SWIZZLE_VALUE, i
+        ).unwrap();
+      } else {
+        writeln!(self.out,
+          "{}  {}[{}] = {}[{}];", indent, SWIZZLE_TARGET, component, SWIZZLE_VALUE, i
+        ).unwrap();
+      }
+    }
+    writeln!(self.out, "{}}}", indent).unwrap();
+  }
+
+  /**
+   * Whether a place is within a buffer bound as `Shared`, which is
+   * only accessed through its pointer.
+   */
+  fn is_shared(&self, place: &ExpressionModel) -> bool {
+    match &place.kind {
+      ExpressionModelKind::Buffer(name) => self.buffer(&name.name).shared,
+      ExpressionModelKind::Field(field_expr) => self.is_shared(&field_expr.target),
+      ExpressionModelKind::Index(index_expr) => self.is_shared(&index_expr.target),
+      _ => false,
+    }
+  }
+
+  /**
+   * A place expression.  The runtime-sized array of a buffer's struct
+   * is its own binding, and elements of shared arrays are reached
+   * through bounds-checked pointers, so that no reference covers the
+   * elements other invocations write.
+   */
+  fn place(&mut self, place: &ExpressionModel) -> String {
+    match &place.kind {
+      ExpressionModelKind::Local(name) => ident(&name.name),
+      ExpressionModelKind::Uniforms => "self.uniforms".to_string(),
+      ExpressionModelKind::Buffer(name) => {
+        let buffer = self.buffer(&name.name);
+        binding(&ident(&buffer.name), buffer.shared)
+      },
+      ExpressionModelKind::Field(field_expr) => {
+        if let ExpressionModelKind::Buffer(name) = &field_expr.target.kind {
+          let buffer = self.buffer(&name.name);
+          if buffer.tail.as_ref().is_some_and(|(field, _)| *field == field_expr.field) {
+            return binding(&buffer.tail_field().unwrap(), buffer.shared);
+          }
+        }
+        let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+          unreachable!("Field access on non-struct type");
+        };
+        let field = &struct_ty.fields[field_expr.field as usize];
+        format!("{}.{}", self.place(&field_expr.target), ident(field.name()))
+      },
+      ExpressionModelKind::Index(index_expr) => {
+        if let Some(field) = self.shared_array_binding(&index_expr.target) {
+          let index = self.index(&index_expr.index);
+          return format!("(*self.{}.elem({}))", field, index);
+        }
+        let target = self.place(&index_expr.target);
+        let index = self.index(&index_expr.index);
+        format!("{}[{}]", target, index)
+      },
+      _ => self.postfix_target(place),
+    }
+  }
+
+  /**
+   * An index, as a `usize`.  Negative indices wrap to be out of bounds.
+   */
+  fn index(&mut self, index: &ExpressionModel) -> String {
+    match index.kind {
+      ExpressionModelKind::Literal(LiteralModel::U32(value)) => value.to_string(),
+      ExpressionModelKind::Literal(LiteralModel::I32(value @ 0..)) => value.to_string(),
+      _ => format!("{} as usize", self.postfix_target(index)),
+    }
+  }
+
+  /**
+   * The `Resources` field binding a place, if it is the runtime-sized
+   * array of a shared buffer.
+   */
+  fn shared_array_binding(&self, place: &ExpressionModel) -> Option<String> {
+    let is_array = matches!(&*place.ty, TypeModel::Array(array_ty) if array_ty.len.is_none());
+    (is_array && self.is_shared(place)).then(|| self.array_binding(place))
+  }
+
+  /**
+   * The `Resources` field binding the runtime-sized array whose length
+   * is taken.
+   */
+  fn array_binding(&self, target: &ExpressionModel) -> String {
+    match &target.kind {
+      ExpressionModelKind::Buffer(name) => ident(&self.buffer(&name.name).name),
+      ExpressionModelKind::Field(field_expr) => {
+        let ExpressionModelKind::Buffer(name) = &field_expr.target.kind else {
+          unreachable!("Runtime-sized array outside of a buffer");
+        };
+        self.buffer(&name.name).tail_field().expect("Buffer struct ends in an array")
+      },
+      _ => unreachable!("Runtime-sized array outside of a buffer"),
+    }
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> String {
+    if is_place(expr) {
+      if self.is_shared(expr) && !self.in_unsafe {
+        self.in_unsafe = true;
+        let place = self.place(expr);
+        self.in_unsafe = false;
+        return format!("unsafe {{ {} }}", place);
+      }
+      return self.place(expr);
+    }
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => self.literal(literal),
+      ExpressionModelKind::ArrayLength(length_expr) =>
+        format!("self.{}.len() as u32", self.array_binding(&length_expr.target)),
+      ExpressionModelKind::Field(field_expr) => {
+        let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+          unreachable!("Field access on non-struct type");
+        };
+        let field = &struct_ty.fields[field_expr.field as usize];
+        format!("{}.{}", self.postfix_target(&field_expr.target), ident(field.name()))
+      },
+      ExpressionModelKind::Index(_) => self.place(expr),
+      ExpressionModelKind::Swizzle(swizzle_expr) => match &swizzle_expr.components[..] {
+        [component] => format!("{}[{}]", self.postfix_target(&swizzle_expr.target), component),
+        components => {
+          let target = self.expr(&swizzle_expr.target);
+          let components = components.iter()
+            .map(|component| component.to_string())
+            .collect::<Vec<_>>();
+          format!("dubgsl_rt::swizzle({}, [{}])", target, components.join(", "))
+        },
+      },
+      ExpressionModelKind::Construct(construct_expr) => {
+        let args = construct_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        match &*expr.ty {
+          TypeModel::Struct(struct_ty) => {
+            let fields = struct_ty.fields.iter()
+              .zip(args)
+              .map(|(field, arg)| format!("{}: {}", ident(field.name()), arg))
+              .collect::<Vec<_>>();
+            format!("{} {{ {} }}", struct_name(struct_ty, ident), fields.join(", "))
+          },
+          _ if construct_expr.args.iter().all(|arg| matches!(*arg.ty, TypeModel::Scalar(_))) =>
+            format!("[{}]", args.join(", ")),
+          // Vector arguments are split into their components, after
+          // every argument is evaluated in order.
+          _ => {
+            let mut block = String::from("{ ");
+            let mut components = Vec::new();
+            for (i, (arg, arg_expr)) in construct_expr.args.iter().zip(args).enumerate() {
+              write!(block, "let {}{} = {}; ", CONSTRUCT_ARG, i, arg_expr).unwrap();
+              match &*arg.ty {
+                TypeModel::Vector(vector_ty) => for c in 0..vector_ty.dims as u8 {
+                  components.push(format!("{}{}[{}]", CONSTRUCT_ARG, i, c));
+                },
+                _ => components.push(format!("{}{}", CONSTRUCT_ARG, i)),
+              }
+            }
+            write!(block, "[{}] }}", components.join(", ")).unwrap();
+            block
+          },
+        }
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let args = call_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        format!("self.{}({})", ident(&call_expr.func.name), args.join(", "))
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let scalar = unary_expr.subexpr.ty.numeric_element();
+        let apply = |operand: &str| match (unary_expr.op, scalar) {
+          (UnaryOpModel::Negate, Some(scalar)) if scalar.is_integer() =>
+            format!("{}.wrapping_neg()", operand),
+          (UnaryOpModel::Negate, _) => format!("-{}", operand),
+          (UnaryOpModel::Not | UnaryOpModel::Complement, _) => format!("!{}", operand),
+        };
+        match &*unary_expr.subexpr.ty {
+          TypeModel::Vector(_) => {
+            let subexpr = self.expr(&unary_expr.subexpr);
+            format!("dubgsl_rt::map({}, |a| {})", subexpr, apply("a"))
+          },
+          _ => {
+            let subexpr = self.postfix_target(&unary_expr.subexpr);
+            apply(&subexpr)
+          },
+        }
+      },
+      ExpressionModelKind::Binary(binary_expr) => self.binary(binary_expr),
+      ExpressionModelKind::Cast(cast_expr) => {
+        let scalar = expr.ty.numeric_element().expect("Cast to a numeric type");
+        let to = self.scalar_name(scalar);
+        match &*expr.ty {
+          TypeModel::Vector(_) => {
+            let subexpr = self.expr(&cast_expr.subexpr);
+            format!("dubgsl_rt::map({}, |a| a as {})", subexpr, to)
+          },
+          _ => format!("{} as {}", self.postfix_target(&cast_expr.subexpr), to),
+        }
+      },
+      ExpressionModelKind::Local(_) |
+      ExpressionModelKind::Uniforms |
+      ExpressionModelKind::Buffer(_) => unreachable!("Place is written above"),
+    }
+  }
+
+  /**
+   * A binary operation.  Operations on vectors apply to each component,
+   * with a scalar operand splatted, and those on matrices use helpers
+   * that sum in the interpreter's order.
+   */
+  fn binary(&mut self, binary_expr: &BinaryExprModel) -> String {
+    let op = binary_expr.op;
+    let scalar = binary_expr.lhs.ty.numeric_element();
+    let (lhs_ty, rhs_ty) = (&*binary_expr.lhs.ty, &*binary_expr.rhs.ty);
+    match (lhs_ty, rhs_ty) {
+      (TypeModel::Matrix(_), _) | (_, TypeModel::Matrix(_)) => {
+        let lhs = self.expr(&binary_expr.lhs);
+        let rhs = self.expr(&binary_expr.rhs);
+        match (op, lhs_ty, rhs_ty) {
+          (BinaryOpModel::Mul, TypeModel::Matrix(_), TypeModel::Matrix(_)) =>
+            format!("dubgsl_rt::mat_mat({}, {})", lhs, rhs),
+          (BinaryOpModel::Mul, TypeModel::Matrix(_), TypeModel::Vector(_)) =>
+            format!("dubgsl_rt::mat_vec({}, {})", lhs, rhs),
+          (BinaryOpModel::Mul, TypeModel::Vector(_), TypeModel::Matrix(_)) =>
+            format!("dubgsl_rt::vec_mat({}, {})", lhs, rhs),
+          (BinaryOpModel::Mul, TypeModel::Matrix(_), _) =>
+            format!("dubgsl_rt::mat_scalar({}, {})", lhs, rhs),
+          (BinaryOpModel::Mul, _, TypeModel::Matrix(_)) =>
+            format!("dubgsl_rt::scalar_mat({}, {})", lhs, rhs),
+          _ => format!(
+            "dubgsl_rt::zip({}, {}, |a, b| dubgsl_rt::zip(a, b, |a, b| {}))",
+            lhs, rhs, scalar_op(op, scalar, "a", "b")
+          ),
+        }
+      },
+      (TypeModel::Vector(vector_ty), _) | (_, TypeModel::Vector(vector_ty)) => {
+        let dims = vector_ty.dims as u8;
+        let mut operand = |operand: &ExpressionModel| {
+          let operand_expr = self.expr(operand);
+          match &*operand.ty {
+            TypeModel::Vector(_) => operand_expr,
+            _ => format!("[{}; {}]", operand_expr, dims),
+          }
+        };
+        let lhs = operand(&binary_expr.lhs);
+        let rhs = operand(&binary_expr.rhs);
+        format!(
+          "dubgsl_rt::zip({}, {}, |a, b| {})",
+          lhs, rhs, scalar_op(op, scalar, "a", "b")
+        )
+      },
+      _ => {
+        let lhs = self.postfix_target(&binary_expr.lhs);
+        let rhs = match op {
+          // Method arguments need no parentheses.
+          _ if is_method_op(op, lhs_ty) => self.expr(&binary_expr.rhs),
+          _ => self.postfix_target(&binary_expr.rhs),
+        };
+        scalar_op(op, scalar, &lhs, &rhs)
+      },
+    }
+  }
+  /**
+   * An expression that is indexed, has a member selected or a method
+   * called, or is an operand.  Casts, which array lengths are written
+   * with, bind looser than postfixes too.
+   */
+  fn postfix_target(&mut self, target: &ExpressionModel) -> String {
+    let target_expr = self.expr(target);
+    if matches!(target.kind,
+      ExpressionModelKind::Cast(_) | ExpressionModelKind::ArrayLength(_)
+    ) {
+      format!("({})", target_expr)
+    } else {
+      parenthesize_target(target, target_expr)
+    }
+  }
+
+  fn literal(&mut self, literal: &LiteralModel) -> String {
+    match *literal {
+      LiteralModel::Bool(value) => value.to_string(),
+      LiteralModel::I32(value) => format!("{}_i32", value),
+      LiteralModel::U32(value) => format!("{}_u32", value),
+      LiteralModel::I64(value) => format!("{}_i64", value),
+      LiteralModel::U64(value) => format!("{}_u64", value),
+      LiteralModel::F32(bits) => {
+        let value = f32::from_bits(bits);
+        // Debug formatting gives the shortest digits that read back
+        // exactly, and values without a literal are built from their bits.
+        if value.is_finite() {
+          format!("{:?}_f32", value)
+        } else {
+          format!("f32::from_bits(0x{:08X})", bits)
+        }
+      },
+      LiteralModel::F16(_) => self.unsupported_scalar(ScalarNumericTypeModel::F16),
+    }
+  }
+
+  fn type_name(&mut self, ty: &TypeModel) -> String {
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) =>
+        "bool".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) =>
+        "()".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => self.scalar_name(*scalar),
+      TypeModel::Vector(vector_ty) =>
+        format!("[{}; {}]", self.scalar_name(vector_ty.scalar), vector_ty.dims as u8),
+      TypeModel::Matrix(matrix_ty) => format!(
+        "[[{}; {}]; {}]",
+        self.scalar_name(matrix_ty.scalar), matrix_ty.rows as u8, matrix_ty.cols as u8
+      ),
+      TypeModel::Array(array_ty) => {
+        let elem = self.type_name(&array_ty.elem);
+        let len = array_ty.len.expect("Only sized arrays are named");
+        format!("[{}; {}]", elem, len)
+      },
+      TypeModel::Struct(struct_ty) => struct_name(struct_ty, ident),
+    }
+  }
+
+  fn scalar_name(&mut self, scalar: ScalarNumericTypeModel) -> String {
+    match scalar {
+      ScalarNumericTypeModel::F16 => self.unsupported_scalar(scalar),
+      _ => scalar.to_string(),
+    }
+  }
+
+  fn unsupported_scalar(&mut self, scalar: ScalarNumericTypeModel) -> String {
+    self.diagnostics.error(format!("Type `{}` is not supported by the Rust backend.", scalar));
+    scalar.to_string()
+  }
+}
+
+/**
+ * An operation on scalars of the given type, or on the components of
+ * vectors of it.
+ */
+fn scalar_op(op: BinaryOpModel, scalar: Option<ScalarNumericTypeModel>, a: &str, b: &str)
+  -> String
+{
+  if scalar.is_some_and(|scalar| scalar.is_integer()) {
+    let method = match op {
+      BinaryOpModel::Add => Some("wrapping_add"),
+      BinaryOpModel::Sub => Some("wrapping_sub"),
+      BinaryOpModel::Mul => Some("wrapping_mul"),
+      BinaryOpModel::Div => Some("wrapping_div"),
+      BinaryOpModel::Mod => Some("wrapping_rem"),
+      _ => None,
+    };
+    if let Some(method) = method {
+      return format!("{}.{}({})", a, method, b);
+    }
+    match op {
+      BinaryOpModel::Shl =>
+        return format!("{}.checked_shl({}).expect(\"Shift out of range\")", a, b),
+      BinaryOpModel::Shr =>
+        return format!("{}.checked_shr({}).expect(\"Shift out of range\")", a, b),
+      _ => {},
+    }
+  }
+  format!("{} {} {}", a, binary_op(op), b)
+}
+
+fn has_resources(model: &ShaderFileModel) -> bool {
+  model.uniforms().is_some() || !model.buffers().is_empty()
+}
+
+/**
+ * Whether a scalar operation is written as a method call.
+ */
+fn is_method_op(op: BinaryOpModel, operand_ty: &TypeModel) -> bool {
+  operand_ty.is_integer_scalar() && matches!(op,
+    BinaryOpModel::Add | BinaryOpModel::Sub | BinaryOpModel::Mul |
+    BinaryOpModel::Div | BinaryOpModel::Mod | BinaryOpModel::Shl |
+    BinaryOpModel::Shr
+  )
+}
+
+/**
+ * Whether an expression is a place, which is read without copying the
+ * whole of its local, uniforms or buffer.
+ */
+fn is_place(expr: &ExpressionModel) -> bool {
+  match &expr.kind {
+    ExpressionModelKind::Local(_) |
+    ExpressionModelKind::Uniforms |
+    ExpressionModelKind::Buffer(_) => true,
+    ExpressionModelKind::Field(field_expr) => is_place(&field_expr.target),
+    ExpressionModelKind::Index(index_expr) => is_place(&index_expr.target),
+    _ => false,
+  }
+}
+
+/**
+ * A `Resources` field binding a buffer, as a place.  Shared buffers are
+ * reached through their pointers.  Shared arrays are only indexed, with
+ * `Shared::elem`.
+ */
+fn binding(field: &str, shared: bool) -> String {
+  if shared {
+    format!("(*self.{}.as_ptr())", field)
+  } else {
+    format!("self.{}", field)
+  }
+}
+
+const RESOURCES_DOC: &str = "
+/**
+ * The resources bound for a dispatch.  Entrypoint methods run a single
+ * invocation, and `dispatch` runs many in parallel.
+ */
+";
+
+const DISPATCH_DOC: &str = "  /**
+   * Run an entrypoint over a grid of workgroups.  Workgroups run in
+   * parallel, and the invocations of each in order of their ids.
+   *
+   * # Safety
+   *
+   * As on a GPU, an invocation must not write a buffer element that
+   * another invocation reads or writes.
+   *
+   * # Panics
+   *
+   * If the invocations along an axis don't fit in a `u32`, or if an
+   * invocation panics, e.g. indexing out of bounds.
+   */
+";
+
+/**
+ * The names of the temporaries used to assign a swizzle, and to
+ * construct a vector from vectors.
+ */
+const SWIZZLE_TARGET: &str = "dubgsl_target";
+const SWIZZLE_VALUE: &str = "dubgsl_value";
+const CONSTRUCT_ARG: &str = "dubgsl_arg";
+
+/**
+ * The support code for generated kernels: shared buffers, componentwise
+ * operations, and the parallel dispatch.
+ */
+const RUNTIME_DECL: &str = "
+#[allow(dead_code)]
+pub mod dubgsl_rt {
+  use rayon::prelude::*;
+  use std::{ marker::PhantomData, ops::{ Add, Mul } };
+
+  /**
+   * A buffer written by invocations, borrowed for the lifetime of the
+   * resources.  It is not `Sync`: invocations only run in parallel
+   * through the `unsafe` dispatch, and only reach it through pointers.
+   */
+  pub struct Shared<'a, T: ?Sized> {
+    ptr: *mut T,
+    _borrow: PhantomData<&'a mut T>,
+  }
+  unsafe impl<T: ?Sized + Send> Send for Shared<'_, T> {}
+  impl<'a, T: ?Sized> Shared<'a, T> {
+    pub fn new(value: &'a mut T) -> Self {
+      Shared { ptr: value, _borrow: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut T {
+      self.ptr
+    }
+  }
+  impl<T> Shared<'_, [T]> {
+    pub fn len(&self) -> usize {
+      self.ptr.len()
+    }
+
+    /** A pointer to an element, which must be in bounds. */
+    pub fn elem(&self, i: usize) -> *mut T {
+      let len = self.len();
+      assert!(i < len, \"Index {} is out of bounds for a buffer of length {}\", i, len);
+      unsafe { self.ptr.cast::<T>().add(i) }
+    }
+  }
+
+  /**
+   * Resources shared with the invocations of a dispatch, whose caller
+   * promises that they don't race.
+   */
+  pub struct AssertSync<'a, T>(pub &'a T);
+  unsafe impl<T> Sync for AssertSync<'_, T> {}
+  impl<'a, T> AssertSync<'a, T> {
+    pub fn get(&self) -> &'a T {
+      self.0
+    }
+  }
+
+  pub fn map<A: Copy, U, const N: usize>(a: [A; N], f: impl Fn(A) -> U) -> [U; N] {
+    std::array::from_fn(|i| f(a[i]))
+  }
+
+  pub fn zip<A: Copy, B: Copy, U, const N: usize>(
+    a: [A; N],
+    b: [B; N],
+    f: impl Fn(A, B) -> U,
+  ) -> [U; N] {
+    std::array::from_fn(|i| f(a[i], b[i]))
+  }
+
+  pub fn swizzle<T: Copy, const N: usize, const M: usize>(v: [T; N], components: [usize; M])
+    -> [T; M]
+  {
+    components.map(|c| v[c])
+  }
+
+  /** The sum of the matrix's columns, each scaled by the vector's component. */
+  pub fn mat_vec<F, const C: usize, const R: usize>(m: [[F; R]; C], v: [F; C]) -> [F; R]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    let mut sum = m[0].map(|a| a * v[0]);
+    for c in 1..C {
+      sum = zip(sum, m[c], |s, a| s + a * v[c]);
+    }
+    sum
+  }
+
+  /** The dot products of the vector with each of the matrix's columns. */
+  pub fn vec_mat<F, const C: usize, const R: usize>(v: [F; R], m: [[F; R]; C]) -> [F; C]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    m.map(|column| {
+      let mut dot = v[0] * column[0];
+      for r in 1..R {
+        dot = dot + v[r] * column[r];
+      }
+      dot
+    })
+  }
+
+  pub fn mat_mat<F, const K: usize, const C: usize, const R: usize>(
+    a: [[F; R]; C],
+    b: [[F; C]; K],
+  ) -> [[F; R]; K]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    b.map(|column| mat_vec(a, column))
+  }
+
+  pub fn mat_scalar<F, const C: usize, const R: usize>(m: [[F; R]; C], s: F) -> [[F; R]; C]
+    where F: Copy + Mul<Output = F>
+  {
+    m.map(|column| column.map(|a| a * s))
+  }
+
+  pub fn scalar_mat<F, const C: usize, const R: usize>(s: F, m: [[F; R]; C]) -> [[F; R]; C]
+    where F: Copy + Mul<Output = F>
+  {
+    mat_scalar(m, s)
+  }
+
+  /**
+   * Run an invocation for each id in a grid of workgroups.  Workgroups
+   * run in parallel, and the invocations of each in order of their ids,
+   * with `x` varying fastest.
+   *
+   * # Panics
+   *
+   * If the invocations along an axis don't fit in a `u32`, before any
+   * are run.
+   */
+  pub fn dispatch(
+    workgroup_size: [u32; 3],
+    workgroups: [u32; 3],
+    invocation: impl Fn([u32; 3]) + Sync,
+  ) {
+    for axis in 0..3 {
+      workgroups[axis].checked_mul(workgroup_size[axis]).expect(\"Dispatch is too large\");
+    }
+    let [sx, sy, sz] = workgroup_size;
+    let [wx, wy, wz] = workgroups;
+    (0..wz).into_par_iter().for_each(|gz| {
+      (0..wy).into_par_iter().for_each(|gy| {
+        (0..wx).into_par_iter().for_each(|gx| {
+          for z in gz * sz..(gz + 1) * sz {
+            for y in gy * sy..(gy + 1) * sy {
+              for x in gx * sx..(gx + 1) * sx {
+                invocation([x, y, z]);
+              }
+            }
+          }
+        });
+      });
+    });
+  }
+}
+";
+
+/**
+ * A name as a Rust identifier.  Names starting with the `dubgsl_`
+ * prefix of generated names, and names the output declares, get a `_`
+ * suffix.
+ */
+fn ident(name: &str) -> String {
+  if name.starts_with("dubgsl_") {
+    format!("{}_", name)
+  } else {
+    rust_ident(name, RESERVED)
+  }
+}
+
+/**
+ * The names the output declares.
+ */
+const RESERVED: &[&str] = &["Entrypoint", "Resources", "dispatch"];
@@ -0,0 +1,215 @@
+use std::{
+  collections::HashMap,
+  fmt::{ self, Write },
+};
+use crate::{
+  backend::spirv::{
+    EnumKind,
+    MAGIC,
+    Op,
+    Operand,
+    decoration_info,
+    enum_name,
+    op_info,
+  },
+  model::f32_from_f16_bits,
+};
+
+/**
+ * Disassemble a SPIR-V module to text, one instruction per line in the
+ * style of `spirv-dis`, with ids numbered rather than named.  Only the
+ * instructions that `generate_spirv` emits are understood.
+ */
+pub fn disassemble_spirv(words: &[u32]) -> Result<String, DisassembleError> {
+  let [magic, version, generator, bound, schema] = words.get(..5)
+    .and_then(|header| <[u32; 5]>::try_from(header).ok())
+    .ok_or(DisassembleError::Truncated { offset: words.len() })?;
+  if magic != MAGIC {
+    return Err(DisassembleError::BadMagic(magic));
+  }
+
+  let mut out = String::new();
+  writeln!(out, "; SPIR-V").unwrap();
+  writeln!(out, "; Version: {}.{}", version >> 16 & 0xFF, version >> 8 & 0xFF).unwrap();
+  writeln!(out, "; Generator: {}", generator).unwrap();
+  writeln!(out, "; Bound: {}", bound).unwrap();
+  writeln!(out, "; Schema: {}", schema).unwrap();
+
+  // Results are right-aligned, so that the opcodes line up.
+  let width = format!("%{}", bound.saturating_sub(1)).len();
+  let mut disassembler = Disassembler { types: HashMap::new() };
+  let mut offset = 5;
+  while offset < words.len() {
+    let count = (words[offset] >> 16) as usize;
+    let code = (words[offset] & 0xFFFF) as u16;
+    if count == 0 || offset + count > words.len() {
+      return Err(DisassembleError::Truncated { offset });
+    }
+    let (result, text) = disassembler.instruction(code, &words[offset + 1..offset + count])
+      .ok_or(DisassembleError::BadInstruction { offset, opcode: code })?;
+    match result {
+      Some(result) => writeln!(out, "{:>width$} = {}", format!("%{}", result), text).unwrap(),
+      None => writeln!(out, "{:width$}   {}", "", text).unwrap(),
+    }
+    offset += count;
+  }
+  Ok(out)
+}
+
+/**
+ * An error disassembling a SPIR-V module.  Offsets are in words.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisassembleError {
+  BadMagic(u32),
+  Truncated { offset: usize },
+
+  /** An unknown opcode, or an instruction with the wrong operands. */
+  BadInstruction { offset: usize, opcode: u16 },
+}
+impl fmt::Display for DisassembleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DisassembleError::BadMagic(magic) =>
+        write!(f, "Not a SPIR-V module: the magic number is 0x{:08X}.", magic),
+      DisassembleError::Truncated { offset } =>
+        write!(f, "The module is truncated at word {}.", offset),
+      DisassembleError::BadInstruction { offset, opcode } =>
+        write!(f, "Cannot disassemble opcode {} at word {}.", opcode, offset),
+    }
+  }
+}
+
+/**
+ * The numeric types declared so far, whose constants are written by
+ * value.
+ */
+#[derive(Debug, Clone, Copy)]
+enum NumericType {
+  Int { width: u32, signed: bool },
+  Float { width: u32 },
+}
+
+struct Disassembler {
+  types: HashMap<u32, NumericType>,
+}
+
+impl Disassembler {
+  /**
+   * Disassemble an instruction's operands, returning its result id and
+   * text.
+   */
+  fn instruction(&mut self, code: u16, words: &[u32]) -> Option<(Option<u32>, String)> {
+    let (name, operands) = op_info(code)?;
+    match code {
+      c if c == Op::TypeInt as u16 => {
+        let signed = *words.get(2)? != 0;
+        self.types.insert(words[0], NumericType::Int { width: words[1], signed });
+      },
+      c if c == Op::TypeFloat as u16 => {
+        self.types.insert(words[0], NumericType::Float { width: *words.get(1)? });
+      },
+      _ => {},
+    }
+
+    let mut words = words.iter().copied();
+    let mut result = None;
+    let mut result_type = None;
+    let mut text = name.to_string();
+    for &operand in operands {
+      match operand {
+        Operand::ResultType => {
+          let id = words.next()?;
+          result_type = Some(id);
+          write!(text, " %{}", id).unwrap();
+        },
+        Operand::Result => result = Some(words.next()?),
+        Operand::Id => write!(text, " %{}", words.next()?).unwrap(),
+        Operand::OptionalId => {
+          if let Some(id) = words.next() {
+            write!(text, " %{}", id).unwrap();
+          }
+        },
+        Operand::Ids => {
+          for id in words.by_ref() {
+            write!(text, " %{}", id).unwrap();
+          }
+        },
+        Operand::Literal => write!(text, " {}", words.next()?).unwrap(),
+        Operand::Literals => {
+          for literal in words.by_ref() {
+            write!(text, " {}", literal).unwrap();
+          }
+        },
+        Operand::String => write!(text, " {}", string(&mut words)?).unwrap(),
+        Operand::TypedLiteral => {
+          let literal = self.typed_literal(result_type?, &mut words)?;
+          write!(text, " {}", literal).unwrap();
+        },
+        Operand::Enum(kind) => write!(text, " {}", enumerant(kind, words.next()?)).unwrap(),
+        Operand::Decoration => {
+          let decoration = words.next()?;
+          let (name, kind) = decoration_info(decoration)?;
+          write!(text, " {}", name).unwrap();
+          for literal in words.by_ref() {
+            match kind {
+              Some(kind) => write!(text, " {}", enumerant(kind, literal)).unwrap(),
+              None => write!(text, " {}", literal).unwrap(),
+            }
+          }
+        },
+      }
+    }
+    if words.next().is_some() {
+      return None;
+    }
+    Some((result, text))
+  }
+
+  /**
+   * A constant's value, as its type reads it.
+   */
+  fn typed_literal(&self, ty: u32, words: &mut impl Iterator<Item = u32>) -> Option<String> {
+    let low = words.next()?;
+    let literal = match *self.types.get(&ty)? {
+      NumericType::Int { width: 64, signed } => {
+        let value = (words.next()? as u64) << 32 | low as u64;
+        if signed { (value as i64).to_string() } else { value.to_string() }
+      },
+      NumericType::Int { width: 32, signed: true } => (low as i32).to_string(),
+      NumericType::Int { .. } => low.to_string(),
+      NumericType::Float { width: 16 } => format!("{:?}", f32_from_f16_bits(low as u16)),
+      NumericType::Float { width: 32 } => format!("{:?}", f32::from_bits(low)),
+      NumericType::Float { width: 64 } => {
+        let bits = (words.next()? as u64) << 32 | low as u64;
+        format!("{:?}", f64::from_bits(bits))
+      },
+      NumericType::Float { .. } => return None,
+    };
+    Some(literal)
+  }
+}
+
+/**
+ * A literal string: UTF-8, nul-terminated and padded to a whole word.
+ */
+fn string(words: &mut impl Iterator<Item = u32>) -> Option<String> {
+  let mut bytes = Vec::new();
+  'words: for word in words {
+    for byte in word.to_le_bytes() {
+      if byte == 0 {
+        break 'words;
+      }
+      bytes.push(byte);
+    }
+  }
+  let string = String::from_utf8(bytes).ok()?;
+  Some(format!("{:?}", string))
+}
+
+fn enumerant(kind: EnumKind, value: u32) -> String {
+  match enum_name(kind, value) {
+    Some(name) => name.to_string(),
+    None => value.to_string(),
+  }
+}
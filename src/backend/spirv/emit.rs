@@ -0,0 +1,1190 @@
+use std::collections::{ BTreeSet, HashMap, HashSet };
+use crate::{
+  backend::{
+    SaturatingCast,
+    flatten_swizzle,
+    saturating_cast,
+    spirv::{
+      ADDRESSING_LOGICAL,
+      BUILT_IN_GLOBAL_INVOCATION_ID,
+      CAPABILITY_FLOAT16,
+      CAPABILITY_INT64,
+      CAPABILITY_SHADER,
+      CAPABILITY_STORAGE_BUFFER_16BIT,
+      CAPABILITY_UNIFORM_16BIT,
+      DECORATION_ARRAY_STRIDE,
+      DECORATION_BINDING,
+      DECORATION_BLOCK,
+      DECORATION_BUILT_IN,
+      DECORATION_COL_MAJOR,
+      DECORATION_DESCRIPTOR_SET,
+      DECORATION_MATRIX_STRIDE,
+      DECORATION_NON_READABLE,
+      DECORATION_NON_WRITABLE,
+      DECORATION_OFFSET,
+      EXECUTION_MODEL_GLCOMPUTE,
+      EXECUTION_MODE_LOCAL_SIZE,
+      MAGIC,
+      MEMORY_MODEL_GLSL450,
+      Op,
+      STORAGE_FUNCTION,
+      STORAGE_INPUT,
+      STORAGE_STORAGE_BUFFER,
+      STORAGE_UNIFORM,
+      VERSION,
+    },
+  },
+  model::{
+    BinaryOpModel,
+    BufferModel,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LayoutRules,
+    LiteralModel,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    TypeLayout,
+    TypeLayoutKind,
+    TypeModel,
+    UnaryOpModel,
+    VecDims,
+    f16_bits_from_f32,
+  },
+};
+
+/**
+ * Lower a checked shader file to a SPIR-V module for Vulkan, with a
+ * `GLCompute` entry point for each entrypoint.
+ *
+ * The uniforms are a `Uniform` block and buffers are `StorageBuffer`
+ * blocks, decorated with their descriptor set and binding.  Buffers
+ * that are only read are `NonWritable`, and those only written are
+ * `NonReadable`.  Types keep the model's layout through `Offset`,
+ * `ArrayStride` and `MatrixStride` decorations, so structs and arrays
+ * inside the uniforms are distinct types from those elsewhere, and are
+ * converted member by member when loaded.
+ *
+ * Any checked shader can be expressed, though packed structs need
+ * `VK_EXT_scalar_block_layout` where their fields are not aligned as
+ * std430 requires.
+ */
+pub fn generate_spirv(model: &ShaderFileModel) -> Vec<u32> {
+  let mut gen = SpirvGen {
+    next_id: 1,
+    capabilities: BTreeSet::new(),
+    entry_points: Vec::new(),
+    execution_modes: Vec::new(),
+    names: Vec::new(),
+    annotations: Vec::new(),
+    globals: Vec::new(),
+    functions: Vec::new(),
+    types: HashMap::new(),
+    pointer_types: HashMap::new(),
+    function_types: HashMap::new(),
+    constants: HashMap::new(),
+    blocks: HashSet::new(),
+    uniforms: None,
+    buffers: HashMap::new(),
+    invocation_id: None,
+    funcs: HashMap::new(),
+    func: FuncState::default(),
+  };
+  gen.capabilities.insert(CAPABILITY_SHADER);
+
+  if let Some(uniforms) = model.uniforms() {
+    let ty = gen.type_id(uniforms.ty(), LayoutRules::Uniform);
+    gen.decorate(ty, DECORATION_BLOCK, &[]);
+    if contains_f16(uniforms.ty()) {
+      gen.capabilities.insert(CAPABILITY_UNIFORM_16BIT);
+    }
+    let binding = uniforms.binding();
+    let var = gen.global_var(STORAGE_UNIFORM, ty, "uniforms");
+    gen.decorate(var, DECORATION_DESCRIPTOR_SET, &[binding.group()]);
+    gen.decorate(var, DECORATION_BINDING, &[binding.binding()]);
+    gen.uniforms = Some(var);
+  }
+  for buffer in model.buffers() {
+    gen.write_buffer(buffer);
+  }
+  for func in model.funcs() {
+    let id = gen.id();
+    gen.funcs.insert(func.name.name.clone(), id);
+  }
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  for entrypoint in model.entrypoints() {
+    gen.write_entrypoint(entrypoint);
+  }
+
+  let mut words = vec![MAGIC, VERSION, 0, gen.next_id, 0];
+  for &capability in &gen.capabilities {
+    inst(&mut words, Op::Capability, &[capability]);
+  }
+  inst(&mut words, Op::MemoryModel, &[ADDRESSING_LOGICAL, MEMORY_MODEL_GLSL450]);
+  words.extend(gen.entry_points);
+  words.extend(gen.execution_modes);
+  words.extend(gen.names);
+  words.extend(gen.annotations);
+  words.extend(gen.globals);
+  words.extend(gen.functions);
+  words
+}
+
+/**
+ * The state of SPIR-V generation.  Each section of the module is
+ * written separately, and they are joined in the order SPIR-V
+ * requires.
+ */
+struct SpirvGen {
+  next_id: u32,
+  capabilities: BTreeSet<u32>,
+  entry_points: Vec<u32>,
+  execution_modes: Vec<u32>,
+  names: Vec<u32>,
+  annotations: Vec<u32>,
+  /** Types, constants and global variables. */
+  globals: Vec<u32>,
+  functions: Vec<u32>,
+
+  /** Types by their model and layout rules. */
+  types: HashMap<(TypeModel, LayoutRules), u32>,
+  /** Pointer types by storage class and pointee. */
+  pointer_types: HashMap<(u32, u32), u32>,
+  /** Function types by return type and parameter types. */
+  function_types: HashMap<Vec<u32>, u32>,
+  /** Constants by type and value. */
+  constants: HashMap<(u32, Vec<u32>), u32>,
+  /** Struct types decorated as blocks. */
+  blocks: HashSet<u32>,
+
+  uniforms: Option<u32>,
+  /** Buffer variables, and whether their type is wrapped in a block. */
+  buffers: HashMap<String, (u32, bool)>,
+  /** The `GlobalInvocationId` input, shared by the entry points. */
+  invocation_id: Option<u32>,
+  funcs: HashMap<String, u32>,
+  func: FuncState,
+}
+
+/**
+ * The state of the function being written.
+ */
+#[derive(Default)]
+struct FuncState {
+  /** The variables, which SPIR-V declares in the first block. */
+  vars: Vec<u32>,
+  body: Vec<u32>,
+  /** The block being written. */
+  label: u32,
+  /** Whether the block being written has ended. */
+  terminated: bool,
+  /** The locals in scope, latest last. */
+  locals: Vec<(String, Local)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Local {
+  Value(u32),
+  /** A `Function` variable holding the local. */
+  Variable(u32),
+}
+
+/**
+ * A pointer into a variable, before its access chain is made.
+ */
+struct Place {
+  base: u32,
+  storage: u32,
+  rules: LayoutRules,
+  indices: Vec<u32>,
+}
+
+impl SpirvGen {
+  fn id(&mut self) -> u32 {
+    let id = self.next_id;
+    self.next_id += 1;
+    id
+  }
+
+  fn name(&mut self, id: u32, name: &str) {
+    let mut operands = vec![id];
+    operands.extend(string_words(name));
+    inst(&mut self.names, Op::Name, &operands);
+  }
+
+  fn decorate(&mut self, id: u32, decoration: u32, operands: &[u32]) {
+    let mut all = vec![id, decoration];
+    all.extend_from_slice(operands);
+    inst(&mut self.annotations, Op::Decorate, &all);
+  }
+
+  fn global_var(&mut self, storage: u32, ty: u32, name: &str) -> u32 {
+    let pointer = self.pointer_type(storage, ty);
+    let var = self.id();
+    inst(&mut self.globals, Op::Variable, &[pointer, var, storage]);
+    self.name(var, name);
+    var
+  }
+
+  /**
+   * Declare a buffer.  A runtime-sized array is wrapped in a block
+   * struct, as the block must be a struct.
+   */
+  fn write_buffer(&mut self, buffer: &BufferModel) {
+    let ty = self.type_id(buffer.ty(), LayoutRules::Storage);
+    let wrapped = matches!(**buffer.ty(), TypeModel::Array(_));
+    let block = if wrapped {
+      let block = self.id();
+      inst(&mut self.globals, Op::TypeStruct, &[block, ty]);
+      self.name(block, &format!("dubgsl_buffer_{}", buffer.name()));
+      self.member_name(block, 0, buffer.name());
+      inst(&mut self.annotations, Op::MemberDecorate, &[block, 0, DECORATION_OFFSET, 0]);
+      self.decorate_matrix_member(block, 0, &buffer.layout().expect("Buffer has a layout"));
+      block
+    } else {
+      ty
+    };
+    if self.blocks.insert(block) {
+      self.decorate(block, DECORATION_BLOCK, &[]);
+    }
+    if contains_f16(buffer.ty()) {
+      self.capabilities.insert(CAPABILITY_STORAGE_BUFFER_16BIT);
+    }
+
+    let var = self.global_var(STORAGE_STORAGE_BUFFER, block, buffer.name());
+    let binding = buffer.binding();
+    self.decorate(var, DECORATION_DESCRIPTOR_SET, &[binding.group()]);
+    self.decorate(var, DECORATION_BINDING, &[binding.binding()]);
+    if !buffer.mode().is_writable() {
+      self.decorate(var, DECORATION_NON_WRITABLE, &[]);
+    }
+    if !buffer.mode().is_readable() {
+      self.decorate(var, DECORATION_NON_READABLE, &[]);
+    }
+    self.buffers.insert(buffer.name().to_string(), (var, wrapped));
+  }
+
+  fn member_name(&mut self, id: u32, member: u32, name: &str) {
+    let mut operands = vec![id, member];
+    operands.extend(string_words(name));
+    inst(&mut self.names, Op::MemberName, &operands);
+  }
+
+  /**
+   * Decorate a struct member holding matrices, or arrays of them, with
+   * their column stride.
+   */
+  fn decorate_matrix_member(&mut self, id: u32, member: u32, layout: &TypeLayout) {
+    let mut layout = layout;
+    while let TypeLayoutKind::Array(array_layout) = &layout.kind {
+      layout = &array_layout.elem;
+    }
+    if let TypeLayoutKind::Matrix(matrix_layout) = &layout.kind {
+      inst(&mut self.annotations, Op::MemberDecorate, &[id, member, DECORATION_COL_MAJOR]);
+      inst(&mut self.annotations, Op::MemberDecorate, &[
+        id, member, DECORATION_MATRIX_STRIDE, matrix_layout.column_stride,
+      ]);
+    }
+  }
+
+  /**
+   * The type of a model type laid out by the given rules.  Only arrays
+   * and structs differ between the rules.
+   */
+  fn type_id(&mut self, ty: &TypeModel, rules: LayoutRules) -> u32 {
+    let rules = match ty {
+      TypeModel::Array(_) | TypeModel::Struct(_) => rules,
+      _ => LayoutRules::Storage,
+    };
+    if let Some(&id) = self.types.get(&(ty.clone(), rules)) {
+      return id;
+    }
+    let id = match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) => {
+        let id = self.id();
+        inst(&mut self.globals, Op::TypeBool, &[id]);
+        id
+      },
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) => {
+        let id = self.id();
+        inst(&mut self.globals, Op::TypeVoid, &[id]);
+        id
+      },
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => {
+        let id = self.id();
+        let width = scalar.size() * 8;
+        match scalar {
+          ScalarNumericTypeModel::F16 => {
+            self.capabilities.insert(CAPABILITY_FLOAT16);
+          },
+          ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 => {
+            self.capabilities.insert(CAPABILITY_INT64);
+          },
+          _ => {},
+        }
+        if scalar.is_float() {
+          inst(&mut self.globals, Op::TypeFloat, &[id, width]);
+        } else {
+          inst(&mut self.globals, Op::TypeInt, &[id, width, scalar.is_signed() as u32]);
+        }
+        id
+      },
+      TypeModel::Vector(vector_ty) => {
+        let scalar = self.type_id(&TypeModel::new_scalar(vector_ty.scalar), rules);
+        let id = self.id();
+        inst(&mut self.globals, Op::TypeVector, &[id, scalar, vector_ty.dims as u32]);
+        id
+      },
+      TypeModel::Matrix(matrix_ty) => {
+        let column = TypeModel::new_vector(matrix_ty.scalar, matrix_ty.rows);
+        let column = self.type_id(&column, rules);
+        let id = self.id();
+        inst(&mut self.globals, Op::TypeMatrix, &[id, column, matrix_ty.cols as u32]);
+        id
+      },
+      TypeModel::Array(array_ty) => {
+        let elem = self.type_id(&array_ty.elem, rules);
+        let id = match array_ty.len {
+          Some(len) => {
+            let len = self.constant(LiteralModel::U32(len));
+            let id = self.id();
+            inst(&mut self.globals, Op::TypeArray, &[id, elem, len]);
+            id
+          },
+          None => {
+            let id = self.id();
+            inst(&mut self.globals, Op::TypeRuntimeArray, &[id, elem]);
+            id
+          },
+        };
+        // Arrays of types without a layout, such as `bool`, are only
+        // ever local.
+        if let Ok(layout) = ty.layout(rules) {
+          let TypeLayoutKind::Array(array_layout) = &layout.kind else {
+            unreachable!("Array type without an array layout");
+          };
+          self.decorate(id, DECORATION_ARRAY_STRIDE, &[array_layout.stride]);
+        }
+        id
+      },
+      TypeModel::Struct(struct_ty) => {
+        let mut members = vec![0];
+        for field in &struct_ty.fields {
+          members.push(self.type_id(&field.ty, rules));
+        }
+        let id = self.id();
+        members[0] = id;
+        inst(&mut self.globals, Op::TypeStruct, &members);
+        self.name(id, &struct_ty.name.to_string());
+        for (i, field) in struct_ty.fields.iter().enumerate() {
+          self.member_name(id, i as u32, field.name());
+        }
+        if let Ok(layout) = ty.layout(rules) {
+          let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+            unreachable!("Struct type without a struct layout");
+          };
+          for (i, field) in struct_layout.fields.iter().enumerate() {
+            inst(&mut self.annotations, Op::MemberDecorate, &[
+              id, i as u32, DECORATION_OFFSET, field.offset,
+            ]);
+            self.decorate_matrix_member(id, i as u32, &field.layout);
+          }
+        }
+        id
+      },
+    };
+    self.types.insert((ty.clone(), rules), id);
+    id
+  }
+
+  fn pointer_type(&mut self, storage: u32, pointee: u32) -> u32 {
+    if let Some(&id) = self.pointer_types.get(&(storage, pointee)) {
+      return id;
+    }
+    let id = self.id();
+    inst(&mut self.globals, Op::TypePointer, &[id, storage, pointee]);
+    self.pointer_types.insert((storage, pointee), id);
+    id
+  }
+
+  fn function_type(&mut self, signature: Vec<u32>) -> u32 {
+    if let Some(&id) = self.function_types.get(&signature) {
+      return id;
+    }
+    let id = self.id();
+    let mut operands = vec![id];
+    operands.extend_from_slice(&signature);
+    inst(&mut self.globals, Op::TypeFunction, &operands);
+    self.function_types.insert(signature, id);
+    id
+  }
+
+  fn constant(&mut self, literal: LiteralModel) -> u32 {
+    let (ty, words) = match literal {
+      LiteralModel::Bool(value) => (TypeModel::new_bool(), vec![value as u32]),
+      LiteralModel::I32(value) => (TypeModel::new_i32(), vec![value as u32]),
+      LiteralModel::U32(value) => (TypeModel::new_u32(), vec![value]),
+      LiteralModel::F32(bits) => (TypeModel::new_f32(), vec![bits]),
+      LiteralModel::F16(bits) => (
+        TypeModel::new_scalar(ScalarNumericTypeModel::F16),
+        vec![f16_bits_from_f32(f32::from_bits(bits)) as u32],
+      ),
+      LiteralModel::I64(value) => (
+        TypeModel::new_scalar(ScalarNumericTypeModel::I64),
+        vec![value as u32, (value >> 32) as u32],
+      ),
+      LiteralModel::U64(value) => (
+        TypeModel::new_scalar(ScalarNumericTypeModel::U64),
+        vec![value as u32, (value >> 32) as u32],
+      ),
+    };
+    let ty = self.type_id(&ty, LayoutRules::Storage);
+    if let Some(&id) = self.constants.get(&(ty, words.clone())) {
+      return id;
+    }
+    let id = self.id();
+    match literal {
+      LiteralModel::Bool(true) => inst(&mut self.globals, Op::ConstantTrue, &[ty, id]),
+      LiteralModel::Bool(false) => inst(&mut self.globals, Op::ConstantFalse, &[ty, id]),
+      _ => {
+        let mut operands = vec![ty, id];
+        operands.extend_from_slice(&words);
+        inst(&mut self.globals, Op::Constant, &operands);
+      },
+    }
+    self.constants.insert((ty, words), id);
+    id
+  }
+
+  /**
+   * Write an instruction to the function body, returning its result.
+   */
+  fn op(&mut self, op: Op, ty: &TypeModel, operands: &[u32]) -> u32 {
+    let ty = self.type_id(ty, LayoutRules::Storage);
+    self.op_with_type_id(op, ty, operands)
+  }
+
+  fn op_with_type_id(&mut self, op: Op, ty: u32, operands: &[u32]) -> u32 {
+    let id = self.id();
+    let mut all = vec![ty, id];
+    all.extend_from_slice(operands);
+    inst(&mut self.func.body, op, &all);
+    id
+  }
+
+  fn write_func(&mut self, func: &FuncModel) {
+    let return_ty = self.type_id(&func.return_ty, LayoutRules::Storage);
+    let mut signature = vec![return_ty];
+    for arg in &func.args {
+      signature.push(self.type_id(&arg.ty, LayoutRules::Storage));
+    }
+    let function_ty = self.function_type(signature.clone());
+    let id = self.funcs[&func.name.name];
+    self.name(id, &func.name.name);
+
+    let mut header = Vec::new();
+    inst(&mut header, Op::Function, &[return_ty, id, 0, function_ty]);
+    self.func = FuncState::default();
+    for (arg, &ty) in func.args.iter().zip(&signature[1..]) {
+      let param = self.id();
+      inst(&mut header, Op::FunctionParameter, &[ty, param]);
+      self.name(param, &arg.name.name);
+      self.func.locals.push((arg.name.name.clone(), Local::Value(param)));
+    }
+    self.write_body(header, &func.body);
+  }
+
+  /**
+   * Write an entrypoint as a function without parameters, reading its
+   * invocation id from the `GlobalInvocationId` built-in.
+   */
+  fn write_entrypoint(&mut self, entrypoint: &EntrypointModel) {
+    let void = self.type_id(&TypeModel::new_void(), LayoutRules::Storage);
+    let function_ty = self.function_type(vec![void]);
+    let id = self.id();
+    self.name(id, entrypoint.name());
+
+    let uvec3 = TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec3);
+    let invocation_id = match self.invocation_id {
+      Some(invocation_id) => invocation_id,
+      None => {
+        let uvec3_id = self.type_id(&uvec3, LayoutRules::Storage);
+        let invocation_id = self.global_var(STORAGE_INPUT, uvec3_id, "gl_GlobalInvocationID");
+        self.decorate(invocation_id, DECORATION_BUILT_IN, &[BUILT_IN_GLOBAL_INVOCATION_ID]);
+        self.invocation_id = Some(invocation_id);
+        invocation_id
+      },
+    };
+
+    let mut operands = vec![EXECUTION_MODEL_GLCOMPUTE, id];
+    operands.extend(string_words(entrypoint.name()));
+    operands.push(invocation_id);
+    inst(&mut self.entry_points, Op::EntryPoint, &operands);
+    let [x, y, z] = entrypoint.workgroup_size();
+    inst(&mut self.execution_modes, Op::ExecutionMode, &[
+      id, EXECUTION_MODE_LOCAL_SIZE, x, y, z,
+    ]);
+
+    let mut header = Vec::new();
+    inst(&mut header, Op::Function, &[void, id, 0, function_ty]);
+    self.func = FuncState::default();
+    let value = self.op(Op::Load, &uvec3, &[invocation_id]);
+    let value = match entrypoint.dims {
+      EntrypointDims::D1 => self.op(Op::CompositeExtract, &TypeModel::new_u32(), &[value, 0]),
+      EntrypointDims::D2 => {
+        let uvec2 = TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec2);
+        self.op(Op::VectorShuffle, &uvec2, &[value, value, 0, 1])
+      },
+      EntrypointDims::D3 => value,
+    };
+    self.name(value, &entrypoint.arg_name.name);
+    self.func.locals.push((entrypoint.arg_name.name.clone(), Local::Value(value)));
+    self.write_body(header, &entrypoint.body);
+  }
+
+  /**
+   * Write a function's body after its header, and end it.  Only void
+   * functions reach their end, since checked functions with a return
+   * type return on every path.
+   */
+  fn write_body(&mut self, header: Vec<u32>, body: &[StatementModel]) {
+    let entry = self.id();
+    self.func.label = entry;
+    self.write_block(body);
+    if !self.func.terminated {
+      inst(&mut self.func.body, Op::Return, &[]);
+    }
+    let func = std::mem::take(&mut self.func);
+    self.functions.extend(header);
+    inst(&mut self.functions, Op::Label, &[entry]);
+    self.functions.extend(func.vars);
+    self.functions.extend(func.body);
+    inst(&mut self.functions, Op::FunctionEnd, &[]);
+  }
+
+  /**
+   * Start a new block.
+   */
+  fn label(&mut self, label: u32) {
+    inst(&mut self.func.body, Op::Label, &[label]);
+    self.func.label = label;
+    self.func.terminated = false;
+  }
+
+  /**
+   * End the block being written with a terminator.
+   */
+  fn terminate(&mut self, op: Op, operands: &[u32]) {
+    inst(&mut self.func.body, op, operands);
+    self.func.terminated = true;
+  }
+
+  /**
+   * Write a block of statements in its own scope.  Statements after
+   * the block ends, by returning, are unreachable and not written.
+   */
+  fn write_block(&mut self, statements: &[StatementModel]) {
+    let scope = self.func.locals.len();
+    for stmt in statements {
+      if self.func.terminated {
+        break;
+      }
+      self.write_stmt(stmt);
+    }
+    self.func.locals.truncate(scope);
+  }
+
+  /**
+   * Declare a `Function` variable in the first block.
+   */
+  fn local_var(&mut self, ty: &TypeModel) -> u32 {
+    let ty = self.type_id(ty, LayoutRules::Storage);
+    let pointer = self.pointer_type(STORAGE_FUNCTION, ty);
+    let var = self.id();
+    inst(&mut self.func.vars, Op::Variable, &[pointer, var, STORAGE_FUNCTION]);
+    var
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel) {
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let value = self.expr(&let_stmt.value);
+        self.func.locals.push((let_stmt.name.name.clone(), Local::Value(value)));
+      },
+      StatementModel::Var(var_stmt) => {
+        let value = self.expr(&var_stmt.value);
+        let var = self.local_var(&var_stmt.value.ty);
+        self.name(var, &var_stmt.name.name);
+        inst(&mut self.func.body, Op::Store, &[var, value]);
+        self.func.locals.push((var_stmt.name.name.clone(), Local::Variable(var)));
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        let (place_expr, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        let mut place = self.place(place_expr).expect("Mutated place has a pointer");
+        match components[..] {
+          [] => {
+            let pointer = self.access(place, &place_expr.ty);
+            let value = self.expr(&mutate_stmt.value);
+            inst(&mut self.func.body, Op::Store, &[pointer, value]);
+          },
+          // A single component is stored through its own pointer.
+          [component] => {
+            place.indices.push(self.constant(LiteralModel::U32(component)));
+            let pointer = self.access(place, &mutate_stmt.value.ty);
+            let value = self.expr(&mutate_stmt.value);
+            inst(&mut self.func.body, Op::Store, &[pointer, value]);
+          },
+          // Several components are shuffled into the vector.
+          _ => {
+            let TypeModel::Vector(vector_ty) = &*place_expr.ty else {
+              unreachable!("Swizzle of non-vector type");
+            };
+            let dims = vector_ty.dims as u32;
+            let pointer = self.access(place, &place_expr.ty);
+            let value = self.expr(&mutate_stmt.value);
+            let old = self.op(Op::Load, &place_expr.ty, &[pointer]);
+            let mut operands = vec![old, value];
+            for c in 0..dims {
+              operands.push(match components.iter().position(|&s| s == c) {
+                Some(i) => dims + i as u32,
+                None => c,
+              });
+            }
+            let new = self.op(Op::VectorShuffle, &place_expr.ty, &operands);
+            inst(&mut self.func.body, Op::Store, &[pointer, new]);
+          },
+        }
+      },
+      StatementModel::Exec(exec_stmt) => {
+        self.expr(&exec_stmt.expr);
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          self.terminate(Op::ReturnValue, &[value]);
+        },
+        None => self.terminate(Op::Return, &[]),
+      },
+      StatementModel::If(if_stmt) => {
+        let cond = self.expr(&if_stmt.cond);
+        let then_label = self.id();
+        let merge = self.id();
+        let else_label = match if_stmt.else_block {
+          Some(_) => self.id(),
+          None => merge,
+        };
+        inst(&mut self.func.body, Op::SelectionMerge, &[merge, 0]);
+        self.terminate(Op::BranchConditional, &[cond, then_label, else_label]);
+
+        self.label(then_label);
+        self.write_block(&if_stmt.if_block);
+        let mut all_terminated = self.func.terminated;
+        if !self.func.terminated {
+          self.terminate(Op::Branch, &[merge]);
+        }
+        match &if_stmt.else_block {
+          Some(else_block) => {
+            self.label(else_label);
+            self.write_block(else_block);
+            all_terminated &= self.func.terminated;
+            if !self.func.terminated {
+              self.terminate(Op::Branch, &[merge]);
+            }
+          },
+          None => all_terminated = false,
+        }
+
+        self.label(merge);
+        if all_terminated {
+          self.terminate(Op::Unreachable, &[]);
+        }
+      },
+      // Loops are left only by returning, so the merge block is
+      // unreachable.
+      StatementModel::Loop(loop_stmt) => {
+        let header = self.id();
+        let body = self.id();
+        let continue_label = self.id();
+        let merge = self.id();
+        self.terminate(Op::Branch, &[header]);
+        self.label(header);
+        inst(&mut self.func.body, Op::LoopMerge, &[merge, continue_label, 0]);
+        self.terminate(Op::Branch, &[body]);
+
+        self.label(body);
+        self.write_block(&loop_stmt.block);
+        if !self.func.terminated {
+          self.terminate(Op::Branch, &[continue_label]);
+        }
+        self.label(continue_label);
+        self.terminate(Op::Branch, &[header]);
+        self.label(merge);
+        self.terminate(Op::Unreachable, &[]);
+      },
+      // Assertions are only allowed in tests, which are not generated.
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  fn local(&self, name: &str) -> Local {
+    self.func.locals.iter()
+      .rev()
+      .find(|(local, _)| local == name)
+      .map(|&(_, local)| local)
+      .expect("Checked local is in scope")
+  }
+
+  /**
+   * The place an expression refers to, if it is within a variable.
+   * Indices are evaluated from the root of the place outward.
+   */
+  fn place(&mut self, expr: &ExpressionModel) -> Option<Place> {
+    match &expr.kind {
+      ExpressionModelKind::Local(name) => match self.local(&name.name) {
+        Local::Variable(var) => Some(Place {
+          base: var,
+          storage: STORAGE_FUNCTION,
+          rules: LayoutRules::Storage,
+          indices: Vec::new(),
+        }),
+        Local::Value(_) => None,
+      },
+      ExpressionModelKind::Uniforms => Some(Place {
+        base: self.uniforms.expect("Checked uniforms are declared"),
+        storage: STORAGE_UNIFORM,
+        rules: LayoutRules::Uniform,
+        indices: Vec::new(),
+      }),
+      ExpressionModelKind::Buffer(name) => {
+        let (var, wrapped) = self.buffers[&name.name];
+        let indices = if wrapped {
+          vec![self.constant(LiteralModel::U32(0))]
+        } else {
+          Vec::new()
+        };
+        Some(Place {
+          base: var,
+          storage: STORAGE_STORAGE_BUFFER,
+          rules: LayoutRules::Storage,
+          indices,
+        })
+      },
+      ExpressionModelKind::Index(index_expr) => {
+        let mut place = self.place(&index_expr.target)?;
+        let index = self.expr(&index_expr.index);
+        place.indices.push(index);
+        Some(place)
+      },
+      ExpressionModelKind::Field(field_expr) => {
+        let mut place = self.place(&field_expr.target)?;
+        place.indices.push(self.constant(LiteralModel::U32(field_expr.field)));
+        Some(place)
+      },
+      _ => None,
+    }
+  }
+
+  /**
+   * A pointer to a place of the given type.
+   */
+  fn access(&mut self, place: Place, ty: &TypeModel) -> u32 {
+    if place.indices.is_empty() {
+      return place.base;
+    }
+    let ty = self.type_id(ty, place.rules);
+    let pointer = self.pointer_type(place.storage, ty);
+    let mut operands = vec![place.base];
+    operands.extend(place.indices);
+    self.op_with_type_id(Op::AccessChain, pointer, &operands)
+  }
+
+  /**
+   * Load the value at a place, converting it from the uniform layout.
+   */
+  fn load(&mut self, place: Place, ty: &TypeModel) -> u32 {
+    let rules = place.rules;
+    let pointer = self.access(place, ty);
+    let loaded_ty = self.type_id(ty, rules);
+    let value = self.op_with_type_id(Op::Load, loaded_ty, &[pointer]);
+    self.convert_to_storage(value, ty, rules)
+  }
+
+  /**
+   * Convert a value of a struct or array type laid out by other rules
+   * to the storage layout used for values, a member at a time.
+   */
+  fn convert_to_storage(&mut self, value: u32, ty: &TypeModel, rules: LayoutRules) -> u32 {
+    if self.type_id(ty, rules) == self.type_id(ty, LayoutRules::Storage) {
+      return value;
+    }
+    let members = match ty {
+      TypeModel::Struct(struct_ty) => struct_ty.fields.iter()
+        .map(|field| field.ty.clone())
+        .collect::<Vec<_>>(),
+      TypeModel::Array(array_ty) => {
+        let len = array_ty.len.expect("Runtime-sized arrays are not values");
+        vec![array_ty.elem.clone(); len as usize]
+      },
+      _ => return value,
+    };
+    let mut converted = Vec::new();
+    for (i, member_ty) in members.iter().enumerate() {
+      let member_id = self.type_id(member_ty, rules);
+      let member = self.op_with_type_id(Op::CompositeExtract, member_id, &[value, i as u32]);
+      converted.push(self.convert_to_storage(member, member_ty, rules));
+    }
+    self.op(Op::CompositeConstruct, ty, &converted)
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> u32 {
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => self.constant(*literal),
+      ExpressionModelKind::Local(name) => match self.local(&name.name) {
+        Local::Value(value) => value,
+        Local::Variable(var) => self.op(Op::Load, &expr.ty, &[var]),
+      },
+      ExpressionModelKind::Uniforms | ExpressionModelKind::Buffer(_) => {
+        let place = self.place(expr).expect("Resource has a pointer");
+        self.load(place, &expr.ty)
+      },
+      ExpressionModelKind::Index(index_expr) => {
+        if let Some(place) = self.place(expr) {
+          return self.load(place, &expr.ty);
+        }
+        let target = self.expr(&index_expr.target);
+        match index_expr.index.kind {
+          ExpressionModelKind::Literal(LiteralModel::U32(index)) =>
+            self.op(Op::CompositeExtract, &expr.ty, &[target, index]),
+          ExpressionModelKind::Literal(LiteralModel::I32(index)) if index >= 0 =>
+            self.op(Op::CompositeExtract, &expr.ty, &[target, index as u32]),
+          // Values can't be indexed dynamically, so the array is copied
+          // to a variable.
+          _ => {
+            let index = self.expr(&index_expr.index);
+            let var = self.local_var(&index_expr.target.ty);
+            inst(&mut self.func.body, Op::Store, &[var, target]);
+            let place = Place {
+              base: var,
+              storage: STORAGE_FUNCTION,
+              rules: LayoutRules::Storage,
+              indices: vec![index],
+            };
+            self.load(place, &expr.ty)
+          },
+        }
+      },
+      ExpressionModelKind::ArrayLength(length_expr) => {
+        let (var, member) = match &length_expr.target.kind {
+          ExpressionModelKind::Buffer(name) => (self.buffers[&name.name].0, 0),
+          ExpressionModelKind::Field(field_expr) => {
+            let ExpressionModelKind::Buffer(name) = &field_expr.target.kind else {
+              unreachable!("Runtime-sized array outside of a buffer");
+            };
+            (self.buffers[&name.name].0, field_expr.field)
+          },
+          _ => unreachable!("Runtime-sized array outside of a buffer"),
+        };
+        self.op(Op::ArrayLength, &expr.ty, &[var, member])
+      },
+      ExpressionModelKind::Field(field_expr) => {
+        if let Some(place) = self.place(expr) {
+          return self.load(place, &expr.ty);
+        }
+        let target = self.expr(&field_expr.target);
+        self.op(Op::CompositeExtract, &expr.ty, &[target, field_expr.field])
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => {
+        let target = self.expr(&swizzle_expr.target);
+        match swizzle_expr.components[..] {
+          [component] => self.op(Op::CompositeExtract, &expr.ty, &[target, component]),
+          _ => {
+            let mut operands = vec![target, target];
+            operands.extend_from_slice(&swizzle_expr.components);
+            self.op(Op::VectorShuffle, &expr.ty, &operands)
+          },
+        }
+      },
+      ExpressionModelKind::Construct(construct_expr) => {
+        let args = construct_expr.args.iter()
+          .map(|arg| self.expr(arg))
+          .collect::<Vec<_>>();
+        self.op(Op::CompositeConstruct, &expr.ty, &args)
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let mut operands = vec![self.funcs[&call_expr.func.name]];
+        for arg in &call_expr.args {
+          operands.push(self.expr(arg));
+        }
+        self.op(Op::FunctionCall, &expr.ty, &operands)
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let subexpr = self.expr(&unary_expr.subexpr);
+        let op = match unary_expr.op {
+          UnaryOpModel::Negate if is_float(&expr.ty) => Op::FNegate,
+          UnaryOpModel::Negate => Op::SNegate,
+          UnaryOpModel::Not => Op::LogicalNot,
+          UnaryOpModel::Complement => Op::Not,
+        };
+        self.op(op, &expr.ty, &[subexpr])
+      },
+      ExpressionModelKind::Binary(binary_expr) => match binary_expr.op {
+        BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr =>
+          self.logical(binary_expr.op, &binary_expr.lhs, &binary_expr.rhs),
+        op => {
+          let lhs = self.expr(&binary_expr.lhs);
+          let rhs = self.expr(&binary_expr.rhs);
+          self.binary(expr, op, (lhs, &binary_expr.lhs.ty), (rhs, &binary_expr.rhs.ty))
+        },
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        let subexpr = self.expr(&cast_expr.subexpr);
+        if let Some(cast) = saturating_cast(expr) {
+          return self.saturating_cast(subexpr, &expr.ty, cast);
+        }
+        let from = cast_expr.subexpr.ty.numeric_element().expect("Cast from a numeric type");
+        let to = expr.ty.numeric_element().expect("Cast to a numeric type");
+        let op = match (from.is_float(), to.is_float()) {
+          _ if from == to => return subexpr,
+          (true, true) => Op::FConvert,
+          (true, false) if to.is_signed() => Op::ConvertFToS,
+          (true, false) => Op::ConvertFToU,
+          (false, true) if from.is_signed() => Op::ConvertSToF,
+          (false, true) => Op::ConvertUToF,
+          (false, false) if from.size() == to.size() => Op::Bitcast,
+          // The source's signedness decides whether the value is
+          // sign-extended.
+          (false, false) if from.is_signed() => Op::SConvert,
+          (false, false) => Op::UConvert,
+        };
+        self.op(op, &expr.ty, &[subexpr])
+      },
+    }
+  }
+
+  /**
+   * A cast from floats to integers, made a component at a time, as the
+   * conversions are undefined out of range: the bounds are selected for
+   * values out of range, and zero for NaN.
+   */
+  fn saturating_cast(&mut self, value: u32, ty: &TypeModel, cast: SaturatingCast) -> u32 {
+    let TypeModel::Vector(vector_ty) = ty else {
+      return self.saturating_cast_scalar(value, &cast);
+    };
+    let from_ty = TypeModel::new_scalar(if cast.from_f16 {
+      ScalarNumericTypeModel::F16
+    } else {
+      ScalarNumericTypeModel::F32
+    });
+    let components = (0..vector_ty.dims as u32)
+      .map(|i| {
+        let component = self.op(Op::CompositeExtract, &from_ty, &[value, i]);
+        self.saturating_cast_scalar(component, &cast)
+      })
+      .collect::<Vec<_>>();
+    self.op(Op::CompositeConstruct, ty, &components)
+  }
+
+  fn saturating_cast_scalar(&mut self, value: u32, cast: &SaturatingCast) -> u32 {
+    let bool_ty = TypeModel::new_bool();
+    let to_ty = TypeModel::new_scalar(cast.to);
+    let value = if cast.from_f16 {
+      self.op(Op::FConvert, &TypeModel::new_f32(), &[value])
+    } else {
+      value
+    };
+    let convert = if cast.to.is_signed() { Op::ConvertFToS } else { Op::ConvertFToU };
+    let converted = self.op(convert, &to_ty, &[value]);
+    let [lo, hi] = [cast.lo, cast.hi].map(|bound| self.constant(LiteralModel::new_f32(bound)));
+    let [zero, min, max] = [cast.zero, cast.min, cast.max].map(|bound| self.constant(bound));
+    let below = self.op(Op::FOrdLessThanEqual, &bool_ty, &[value, lo]);
+    let above = self.op(Op::FOrdGreaterThanEqual, &bool_ty, &[value, hi]);
+    let is_nan = self.op(Op::IsNan, &bool_ty, &[value]);
+    let result = self.op(Op::Select, &to_ty, &[below, min, converted]);
+    let result = self.op(Op::Select, &to_ty, &[above, max, result]);
+    self.op(Op::Select, &to_ty, &[is_nan, zero, result])
+  }
+
+  /**
+   * A logical operation.  The right operand is evaluated only when
+   * needed if it may have side effects, through a call.
+   */
+  fn logical(&mut self, op: BinaryOpModel, lhs: &ExpressionModel, rhs: &ExpressionModel)
+    -> u32
+  {
+    let bool_ty = TypeModel::new_bool();
+    let lhs = self.expr(lhs);
+    if !rhs.has_call() {
+      let rhs = self.expr(rhs);
+      let op = match op {
+        BinaryOpModel::LogicalAnd => Op::LogicalAnd,
+        _ => Op::LogicalOr,
+      };
+      return self.op(op, &bool_ty, &[lhs, rhs]);
+    }
+    let lhs_label = self.func.label;
+    let rhs_label = self.id();
+    let merge = self.id();
+    let (if_true, if_false) = match op {
+      BinaryOpModel::LogicalAnd => (rhs_label, merge),
+      _ => (merge, rhs_label),
+    };
+    inst(&mut self.func.body, Op::SelectionMerge, &[merge, 0]);
+    self.terminate(Op::BranchConditional, &[lhs, if_true, if_false]);
+    self.label(rhs_label);
+    let rhs = self.expr(rhs);
+    let rhs_label = self.func.label;
+    self.terminate(Op::Branch, &[merge]);
+    self.label(merge);
+    self.op(Op::Phi, &bool_ty, &[lhs, lhs_label, rhs, rhs_label])
+  }
+
+  /**
+   * An arithmetic, bitwise or comparison operation.  A scalar operand
+   * of a vector operation is splatted, except in scaling a float
+   * vector, which SPIR-V does directly.
+   */
+  fn binary(&mut self,
+    expr: &ExpressionModel,
+    op: BinaryOpModel,
+    (lhs, lhs_ty): (u32, &TypeModel),
+    (rhs, rhs_ty): (u32, &TypeModel),
+  ) -> u32 {
+    let ty = &*expr.ty;
+    match (lhs_ty, rhs_ty) {
+      (TypeModel::Matrix(_), TypeModel::Matrix(_)) if op == BinaryOpModel::Mul =>
+        return self.op(Op::MatrixTimesMatrix, ty, &[lhs, rhs]),
+      (TypeModel::Matrix(_), TypeModel::Vector(_)) =>
+        return self.op(Op::MatrixTimesVector, ty, &[lhs, rhs]),
+      (TypeModel::Vector(_), TypeModel::Matrix(_)) =>
+        return self.op(Op::VectorTimesMatrix, ty, &[lhs, rhs]),
+      (TypeModel::Matrix(_), TypeModel::Scalar(_)) =>
+        return self.op(Op::MatrixTimesScalar, ty, &[lhs, rhs]),
+      (TypeModel::Scalar(_), TypeModel::Matrix(_)) =>
+        return self.op(Op::MatrixTimesScalar, ty, &[rhs, lhs]),
+      // Matrices are added and subtracted a column at a time.
+      (TypeModel::Matrix(matrix_ty), TypeModel::Matrix(_)) => {
+        let column_ty = TypeModel::new_vector(matrix_ty.scalar, matrix_ty.rows);
+        let op = if op == BinaryOpModel::Add { Op::FAdd } else { Op::FSub };
+        let mut columns = Vec::new();
+        for i in 0..matrix_ty.cols as u32 {
+          let lhs_column = self.op(Op::CompositeExtract, &column_ty, &[lhs, i]);
+          let rhs_column = self.op(Op::CompositeExtract, &column_ty, &[rhs, i]);
+          columns.push(self.op(op, &column_ty, &[lhs_column, rhs_column]));
+        }
+        return self.op(Op::CompositeConstruct, ty, &columns);
+      },
+      (TypeModel::Vector(_), TypeModel::Scalar(_))
+        if op == BinaryOpModel::Mul && is_float(ty) =>
+        return self.op(Op::VectorTimesScalar, ty, &[lhs, rhs]),
+      (TypeModel::Scalar(_), TypeModel::Vector(_))
+        if op == BinaryOpModel::Mul && is_float(ty) =>
+        return self.op(Op::VectorTimesScalar, ty, &[rhs, lhs]),
+      _ => {},
+    }
+
+    let (lhs, rhs) = match (lhs_ty, rhs_ty) {
+      (TypeModel::Vector(vector_ty), TypeModel::Scalar(_)) =>
+        (lhs, self.splat(rhs, vector_ty.dims as usize, lhs_ty)),
+      (TypeModel::Scalar(_), TypeModel::Vector(vector_ty)) =>
+        (self.splat(lhs, vector_ty.dims as usize, rhs_ty), rhs),
+      _ => (lhs, rhs),
+    };
+    let elem = lhs_ty.numeric_element();
+    let is_float = elem.is_some_and(|e| e.is_float());
+    let is_signed = elem.is_some_and(|e| e.is_signed());
+    let op = match op {
+      BinaryOpModel::Add if is_float => Op::FAdd,
+      BinaryOpModel::Add => Op::IAdd,
+      BinaryOpModel::Sub if is_float => Op::FSub,
+      BinaryOpModel::Sub => Op::ISub,
+      BinaryOpModel::Mul if is_float => Op::FMul,
+      BinaryOpModel::Mul => Op::IMul,
+      BinaryOpModel::Div if is_float => Op::FDiv,
+      BinaryOpModel::Div if is_signed => Op::SDiv,
+      BinaryOpModel::Div => Op::UDiv,
+      // Remainders truncate, taking the sign of the dividend.
+      BinaryOpModel::Mod if is_float => Op::FRem,
+      BinaryOpModel::Mod if is_signed => Op::SRem,
+      BinaryOpModel::Mod => Op::UMod,
+      BinaryOpModel::BitAnd => Op::BitwiseAnd,
+      BinaryOpModel::BitOr => Op::BitwiseOr,
+      BinaryOpModel::BitXor => Op::BitwiseXor,
+      BinaryOpModel::Shl => Op::ShiftLeftLogical,
+      BinaryOpModel::Shr if is_signed => Op::ShiftRightArithmetic,
+      BinaryOpModel::Shr => Op::ShiftRightLogical,
+      BinaryOpModel::Equal if elem.is_none() => Op::LogicalEqual,
+      BinaryOpModel::NotEqual if elem.is_none() => Op::LogicalNotEqual,
+      BinaryOpModel::Equal if is_float => Op::FOrdEqual,
+      BinaryOpModel::Equal => Op::IEqual,
+      // Unordered, so that NaN is unequal to everything.
+      BinaryOpModel::NotEqual if is_float => Op::FUnordNotEqual,
+      BinaryOpModel::NotEqual => Op::INotEqual,
+      BinaryOpModel::LessThan if is_float => Op::FOrdLessThan,
+      BinaryOpModel::LessThan if is_signed => Op::SLessThan,
+      BinaryOpModel::LessThan => Op::ULessThan,
+      BinaryOpModel::LessThanOrEqual if is_float => Op::FOrdLessThanEqual,
+      BinaryOpModel::LessThanOrEqual if is_signed => Op::SLessThanEqual,
+      BinaryOpModel::LessThanOrEqual => Op::ULessThanEqual,
+      BinaryOpModel::GreaterThan if is_float => Op::FOrdGreaterThan,
+      BinaryOpModel::GreaterThan if is_signed => Op::SGreaterThan,
+      BinaryOpModel::GreaterThan => Op::UGreaterThan,
+      BinaryOpModel::GreaterThanOrEqual if is_float => Op::FOrdGreaterThanEqual,
+      BinaryOpModel::GreaterThanOrEqual if is_signed => Op::SGreaterThanEqual,
+      BinaryOpModel::GreaterThanOrEqual => Op::UGreaterThanEqual,
+      BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr =>
+        unreachable!("Logical operations are written separately"),
+    };
+    self.op(op, ty, &[lhs, rhs])
+  }
+
+  /**
+   * A vector with each component a scalar.
+   */
+  fn splat(&mut self, scalar: u32, dims: usize, vector_ty: &TypeModel) -> u32 {
+    self.op(Op::CompositeConstruct, vector_ty, &vec![scalar; dims])
+  }
+}
+
+/**
+ * Write an instruction: its word count and opcode, then its operands.
+ */
+fn inst(out: &mut Vec<u32>, op: Op, operands: &[u32]) {
+  out.push((operands.len() as u32 + 1) << 16 | op as u32);
+  out.extend_from_slice(operands);
+}
+
+/**
+ * A literal string: UTF-8, nul-terminated and padded to a whole word.
+ */
+fn string_words(string: &str) -> Vec<u32> {
+  let mut bytes = string.as_bytes().to_vec();
+  bytes.push(0);
+  bytes.resize(bytes.len().next_multiple_of(4), 0);
+  bytes.chunks(4)
+    .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+    .collect()
+}
+
+fn is_float(ty: &TypeModel) -> bool {
+  match ty {
+    TypeModel::Matrix(_) => true,
+    _ => ty.numeric_element().is_some_and(|e| e.is_float()),
+  }
+}
+
+fn contains_f16(ty: &TypeModel) -> bool {
+  match ty {
+    TypeModel::Scalar(_) | TypeModel::Vector(_) =>
+      ty.numeric_element() == Some(ScalarNumericTypeModel::F16),
+    TypeModel::Matrix(matrix_ty) => matrix_ty.scalar == ScalarNumericTypeModel::F16,
+    TypeModel::Array(array_ty) => contains_f16(&array_ty.elem),
+    TypeModel::Struct(struct_ty) => struct_ty.fields.iter().any(|field| contains_f16(&field.ty)),
+  }
+}
@@ -0,0 +1,373 @@
+mod disassemble;
+mod emit;
+
+pub use self::{
+  disassemble::{ DisassembleError, disassemble_spirv },
+  emit::generate_spirv,
+};
+
+/**
+ * The first word of a SPIR-V module.
+ */
+const MAGIC: u32 = 0x0723_0203;
+
+/**
+ * The SPIR-V version emitted, 1.3, which Vulkan 1.1 consumes.
+ */
+const VERSION: u32 = 0x0001_0300;
+
+/**
+ * The opcodes emitted.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+  Name = 5,
+  MemberName = 6,
+  MemoryModel = 14,
+  EntryPoint = 15,
+  ExecutionMode = 16,
+  Capability = 17,
+  TypeVoid = 19,
+  TypeBool = 20,
+  TypeInt = 21,
+  TypeFloat = 22,
+  TypeVector = 23,
+  TypeMatrix = 24,
+  TypeArray = 28,
+  TypeRuntimeArray = 29,
+  TypeStruct = 30,
+  TypePointer = 32,
+  TypeFunction = 33,
+  ConstantTrue = 41,
+  ConstantFalse = 42,
+  Constant = 43,
+  Function = 54,
+  FunctionParameter = 55,
+  FunctionEnd = 56,
+  FunctionCall = 57,
+  Variable = 59,
+  Load = 61,
+  Store = 62,
+  AccessChain = 65,
+  ArrayLength = 68,
+  Decorate = 71,
+  MemberDecorate = 72,
+  VectorShuffle = 79,
+  CompositeConstruct = 80,
+  CompositeExtract = 81,
+  ConvertFToU = 109,
+  ConvertFToS = 110,
+  ConvertSToF = 111,
+  ConvertUToF = 112,
+  UConvert = 113,
+  SConvert = 114,
+  FConvert = 115,
+  Bitcast = 124,
+  SNegate = 126,
+  FNegate = 127,
+  IAdd = 128,
+  FAdd = 129,
+  ISub = 130,
+  FSub = 131,
+  IMul = 132,
+  FMul = 133,
+  UDiv = 134,
+  SDiv = 135,
+  FDiv = 136,
+  UMod = 137,
+  SRem = 138,
+  FRem = 140,
+  VectorTimesScalar = 142,
+  MatrixTimesScalar = 143,
+  VectorTimesMatrix = 144,
+  MatrixTimesVector = 145,
+  MatrixTimesMatrix = 146,
+  IsNan = 156,
+  LogicalEqual = 164,
+  LogicalNotEqual = 165,
+  LogicalOr = 166,
+  LogicalAnd = 167,
+  LogicalNot = 168,
+  Select = 169,
+  IEqual = 170,
+  INotEqual = 171,
+  UGreaterThan = 172,
+  SGreaterThan = 173,
+  UGreaterThanEqual = 174,
+  SGreaterThanEqual = 175,
+  ULessThan = 176,
+  SLessThan = 177,
+  ULessThanEqual = 178,
+  SLessThanEqual = 179,
+  FOrdEqual = 180,
+  FUnordNotEqual = 183,
+  FOrdLessThan = 184,
+  FOrdGreaterThan = 186,
+  FOrdLessThanEqual = 188,
+  FOrdGreaterThanEqual = 190,
+  ShiftRightLogical = 194,
+  ShiftRightArithmetic = 195,
+  ShiftLeftLogical = 196,
+  BitwiseOr = 197,
+  BitwiseXor = 198,
+  BitwiseAnd = 199,
+  Not = 200,
+  Phi = 245,
+  LoopMerge = 246,
+  SelectionMerge = 247,
+  Label = 248,
+  Branch = 249,
+  BranchConditional = 250,
+  Return = 253,
+  ReturnValue = 254,
+  Unreachable = 255,
+}
+
+/**
+ * The kinds of operands of an instruction, for disassembly.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+  ResultType,
+  Result,
+  Id,
+  /** Ids to the end of the instruction. */
+  Ids,
+  Literal,
+  /** Literal numbers to the end of the instruction. */
+  Literals,
+  String,
+  /** A literal of the instruction's result type. */
+  TypedLiteral,
+  Enum(EnumKind),
+  /** A decoration, followed by its own operands. */
+  Decoration,
+  /** An id, if the instruction has one more word. */
+  OptionalId,
+}
+
+/**
+ * The kinds of named values an operand can be.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnumKind {
+  AddressingModel,
+  MemoryModel,
+  ExecutionModel,
+  ExecutionMode,
+  Capability,
+  StorageClass,
+  BuiltIn,
+  /** Function, selection, loop and memory access controls. */
+  Control,
+}
+
+use self::Operand::{
+  Decoration as Deco,
+  Enum,
+  Id,
+  Ids,
+  Literal,
+  Literals,
+  OptionalId,
+  Result,
+  ResultType,
+  String as Str,
+  TypedLiteral,
+};
+
+const UNARY: &[Operand] = &[ResultType, Result, Id];
+const BINARY: &[Operand] = &[ResultType, Result, Id, Id];
+
+/**
+ * The name and operands of each opcode.
+ */
+const OPS: &[(Op, &str, &[Operand])] = &[
+  (Op::Name, "OpName", &[Id, Str]),
+  (Op::MemberName, "OpMemberName", &[Id, Literal, Str]),
+  (Op::MemoryModel, "OpMemoryModel", &[
+    Enum(EnumKind::AddressingModel), Enum(EnumKind::MemoryModel),
+  ]),
+  (Op::EntryPoint, "OpEntryPoint", &[Enum(EnumKind::ExecutionModel), Id, Str, Ids]),
+  (Op::ExecutionMode, "OpExecutionMode", &[Id, Enum(EnumKind::ExecutionMode), Literals]),
+  (Op::Capability, "OpCapability", &[Enum(EnumKind::Capability)]),
+  (Op::TypeVoid, "OpTypeVoid", &[Result]),
+  (Op::TypeBool, "OpTypeBool", &[Result]),
+  (Op::TypeInt, "OpTypeInt", &[Result, Literal, Literal]),
+  (Op::TypeFloat, "OpTypeFloat", &[Result, Literal]),
+  (Op::TypeVector, "OpTypeVector", &[Result, Id, Literal]),
+  (Op::TypeMatrix, "OpTypeMatrix", &[Result, Id, Literal]),
+  (Op::TypeArray, "OpTypeArray", &[Result, Id, Id]),
+  (Op::TypeRuntimeArray, "OpTypeRuntimeArray", &[Result, Id]),
+  (Op::TypeStruct, "OpTypeStruct", &[Result, Ids]),
+  (Op::TypePointer, "OpTypePointer", &[Result, Enum(EnumKind::StorageClass), Id]),
+  (Op::TypeFunction, "OpTypeFunction", &[Result, Id, Ids]),
+  (Op::ConstantTrue, "OpConstantTrue", &[ResultType, Result]),
+  (Op::ConstantFalse, "OpConstantFalse", &[ResultType, Result]),
+  (Op::Constant, "OpConstant", &[ResultType, Result, TypedLiteral]),
+  (Op::Function, "OpFunction", &[ResultType, Result, Enum(EnumKind::Control), Id]),
+  (Op::FunctionParameter, "OpFunctionParameter", &[ResultType, Result]),
+  (Op::FunctionEnd, "OpFunctionEnd", &[]),
+  (Op::FunctionCall, "OpFunctionCall", &[ResultType, Result, Id, Ids]),
+  (Op::Variable, "OpVariable", &[
+    ResultType, Result, Enum(EnumKind::StorageClass), OptionalId,
+  ]),
+  (Op::Load, "OpLoad", &[ResultType, Result, Id]),
+  (Op::Store, "OpStore", &[Id, Id]),
+  (Op::AccessChain, "OpAccessChain", &[ResultType, Result, Id, Ids]),
+  (Op::ArrayLength, "OpArrayLength", &[ResultType, Result, Id, Literal]),
+  (Op::Decorate, "OpDecorate", &[Id, Deco]),
+  (Op::MemberDecorate, "OpMemberDecorate", &[Id, Literal, Deco]),
+  (Op::VectorShuffle, "OpVectorShuffle", &[ResultType, Result, Id, Id, Literals]),
+  (Op::CompositeConstruct, "OpCompositeConstruct", &[ResultType, Result, Ids]),
+  (Op::CompositeExtract, "OpCompositeExtract", &[ResultType, Result, Id, Literals]),
+  (Op::ConvertFToU, "OpConvertFToU", UNARY),
+  (Op::ConvertFToS, "OpConvertFToS", UNARY),
+  (Op::ConvertSToF, "OpConvertSToF", UNARY),
+  (Op::ConvertUToF, "OpConvertUToF", UNARY),
+  (Op::UConvert, "OpUConvert", UNARY),
+  (Op::SConvert, "OpSConvert", UNARY),
+  (Op::FConvert, "OpFConvert", UNARY),
+  (Op::Bitcast, "OpBitcast", UNARY),
+  (Op::SNegate, "OpSNegate", UNARY),
+  (Op::FNegate, "OpFNegate", UNARY),
+  (Op::IAdd, "OpIAdd", BINARY),
+  (Op::FAdd, "OpFAdd", BINARY),
+  (Op::ISub, "OpISub", BINARY),
+  (Op::FSub, "OpFSub", BINARY),
+  (Op::IMul, "OpIMul", BINARY),
+  (Op::FMul, "OpFMul", BINARY),
+  (Op::UDiv, "OpUDiv", BINARY),
+  (Op::SDiv, "OpSDiv", BINARY),
+  (Op::FDiv, "OpFDiv", BINARY),
+  (Op::UMod, "OpUMod", BINARY),
+  (Op::SRem, "OpSRem", BINARY),
+  (Op::FRem, "OpFRem", BINARY),
+  (Op::VectorTimesScalar, "OpVectorTimesScalar", BINARY),
+  (Op::MatrixTimesScalar, "OpMatrixTimesScalar", BINARY),
+  (Op::VectorTimesMatrix, "OpVectorTimesMatrix", BINARY),
+  (Op::MatrixTimesVector, "OpMatrixTimesVector", BINARY),
+  (Op::MatrixTimesMatrix, "OpMatrixTimesMatrix", BINARY),
+  (Op::IsNan, "OpIsNan", UNARY),
+  (Op::LogicalEqual, "OpLogicalEqual", BINARY),
+  (Op::LogicalNotEqual, "OpLogicalNotEqual", BINARY),
+  (Op::LogicalOr, "OpLogicalOr", BINARY),
+  (Op::LogicalAnd, "OpLogicalAnd", BINARY),
+  (Op::LogicalNot, "OpLogicalNot", UNARY),
+  (Op::Select, "OpSelect", &[ResultType, Result, Id, Id, Id]),
+  (Op::IEqual, "OpIEqual", BINARY),
+  (Op::INotEqual, "OpINotEqual", BINARY),
+  (Op::UGreaterThan, "OpUGreaterThan", BINARY),
+  (Op::SGreaterThan, "OpSGreaterThan", BINARY),
+  (Op::UGreaterThanEqual, "OpUGreaterThanEqual", BINARY),
+  (Op::SGreaterThanEqual, "OpSGreaterThanEqual", BINARY),
+  (Op::ULessThan, "OpULessThan", BINARY),
+  (Op::SLessThan, "OpSLessThan", BINARY),
+  (Op::ULessThanEqual, "OpULessThanEqual", BINARY),
+  (Op::SLessThanEqual, "OpSLessThanEqual", BINARY),
+  (Op::FOrdEqual, "OpFOrdEqual", BINARY),
+  (Op::FUnordNotEqual, "OpFUnordNotEqual", BINARY),
+  (Op::FOrdLessThan, "OpFOrdLessThan", BINARY),
+  (Op::FOrdGreaterThan, "OpFOrdGreaterThan", BINARY),
+  (Op::FOrdLessThanEqual, "OpFOrdLessThanEqual", BINARY),
+  (Op::FOrdGreaterThanEqual, "OpFOrdGreaterThanEqual", BINARY),
+  (Op::ShiftRightLogical, "OpShiftRightLogical", BINARY),
+  (Op::ShiftRightArithmetic, "OpShiftRightArithmetic", BINARY),
+  (Op::ShiftLeftLogical, "OpShiftLeftLogical", BINARY),
+  (Op::BitwiseOr, "OpBitwiseOr", BINARY),
+  (Op::BitwiseXor, "OpBitwiseXor", BINARY),
+  (Op::BitwiseAnd, "OpBitwiseAnd", BINARY),
+  (Op::Not, "OpNot", UNARY),
+  (Op::Phi, "OpPhi", &[ResultType, Result, Ids]),
+  (Op::LoopMerge, "OpLoopMerge", &[Id, Id, Enum(EnumKind::Control)]),
+  (Op::SelectionMerge, "OpSelectionMerge", &[Id, Enum(EnumKind::Control)]),
+  (Op::Label, "OpLabel", &[Result]),
+  (Op::Branch, "OpBranch", &[Id]),
+  (Op::BranchConditional, "OpBranchConditional", &[Id, Id, Id, Literals]),
+  (Op::Return, "OpReturn", &[]),
+  (Op::ReturnValue, "OpReturnValue", &[Id]),
+  (Op::Unreachable, "OpUnreachable", &[]),
+];
+
+/**
+ * The name and operands of an opcode, if it is one emitted.
+ */
+fn op_info(code: u16) -> Option<(&'static str, &'static [Operand])> {
+  OPS.iter()
+    .find(|(op, _, _)| *op as u16 == code)
+    .map(|&(_, name, operands)| (name, operands))
+}
+
+const CAPABILITY_SHADER: u32 = 1;
+const CAPABILITY_FLOAT16: u32 = 9;
+const CAPABILITY_INT64: u32 = 11;
+const CAPABILITY_STORAGE_BUFFER_16BIT: u32 = 4433;
+const CAPABILITY_UNIFORM_16BIT: u32 = 4434;
+
+const ADDRESSING_LOGICAL: u32 = 0;
+const MEMORY_MODEL_GLSL450: u32 = 1;
+const EXECUTION_MODEL_GLCOMPUTE: u32 = 5;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_FUNCTION: u32 = 7;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_COL_MAJOR: u32 = 5;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_NON_WRITABLE: u32 = 24;
+const DECORATION_NON_READABLE: u32 = 25;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const BUILT_IN_GLOBAL_INVOCATION_ID: u32 = 28;
+
+/**
+ * The names of the enumerants used, for disassembly.
+ */
+fn enum_name(kind: EnumKind, value: u32) -> Option<&'static str> {
+  let name = match (kind, value) {
+    (EnumKind::AddressingModel, ADDRESSING_LOGICAL) => "Logical",
+    (EnumKind::MemoryModel, MEMORY_MODEL_GLSL450) => "GLSL450",
+    (EnumKind::ExecutionModel, EXECUTION_MODEL_GLCOMPUTE) => "GLCompute",
+    (EnumKind::ExecutionMode, EXECUTION_MODE_LOCAL_SIZE) => "LocalSize",
+    (EnumKind::Capability, CAPABILITY_SHADER) => "Shader",
+    (EnumKind::Capability, CAPABILITY_FLOAT16) => "Float16",
+    (EnumKind::Capability, CAPABILITY_INT64) => "Int64",
+    (EnumKind::Capability, CAPABILITY_STORAGE_BUFFER_16BIT) => "StorageBuffer16BitAccess",
+    (EnumKind::Capability, CAPABILITY_UNIFORM_16BIT) => "UniformAndStorageBuffer16BitAccess",
+    (EnumKind::StorageClass, STORAGE_INPUT) => "Input",
+    (EnumKind::StorageClass, STORAGE_UNIFORM) => "Uniform",
+    (EnumKind::StorageClass, STORAGE_FUNCTION) => "Function",
+    (EnumKind::StorageClass, STORAGE_STORAGE_BUFFER) => "StorageBuffer",
+    (EnumKind::BuiltIn, BUILT_IN_GLOBAL_INVOCATION_ID) => "GlobalInvocationId",
+    (EnumKind::Control, 0) => "None",
+    _ => return None,
+  };
+  Some(name)
+}
+
+/**
+ * The names of the decorations used, and the kinds of their operands.
+ */
+fn decoration_info(value: u32) -> Option<(&'static str, Option<EnumKind>)> {
+  let info = match value {
+    DECORATION_BLOCK => ("Block", None),
+    DECORATION_COL_MAJOR => ("ColMajor", None),
+    DECORATION_ARRAY_STRIDE => ("ArrayStride", None),
+    DECORATION_MATRIX_STRIDE => ("MatrixStride", None),
+    DECORATION_BUILT_IN => ("BuiltIn", Some(EnumKind::BuiltIn)),
+    DECORATION_NON_WRITABLE => ("NonWritable", None),
+    DECORATION_NON_READABLE => ("NonReadable", None),
+    DECORATION_BINDING => ("Binding", None),
+    DECORATION_DESCRIPTOR_SET => ("DescriptorSet", None),
+    DECORATION_OFFSET => ("Offset", None),
+    _ => return None,
+  };
+  Some(info)
+}
@@ -0,0 +1,618 @@
+use std::fmt::Write;
+use crate::{
+  backend::{
+    Diagnostics,
+    binary_op,
+    flatten_swizzle,
+    if_chain,
+    is_simple_place,
+    parenthesize_target,
+    struct_name,
+  },
+  model::{
+    BinaryOpModel,
+    EntrypointDims,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LayoutRules,
+    LiteralModel,
+    MutateStmtModel,
+    SWIZZLE_LETTERS,
+    ScalarNumericTypeModel,
+    ScalarSymbolicTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructTypeModel,
+    TypeLayout,
+    TypeLayoutKind,
+    TypeModel,
+    UnaryOpModel,
+  },
+  transform::Diagnostic,
+};
+
+/**
+ * Lower a checked shader file to WGSL source.
+ *
+ * Structs keep their storage layout, and the uniforms are a `Uniforms`
+ * struct with explicit `@align` and `@size` where the uniform layout
+ * differs.  Resources are declared in binding order of the model: the
+ * uniforms first, then buffers in declaration order.  Write-only
+ * buffers are `read_write`, as WGSL has no write-only storage.
+ *
+ * Shaders that WGSL cannot express are reported: 64-bit integers,
+ * packed structs whose fields are not naturally aligned, and uniforms
+ * whose layout WGSL cannot reproduce.
+ */
+pub fn generate_wgsl(model: &ShaderFileModel) -> Result<String, Vec<Diagnostic>> {
+  let mut gen = WgslGen {
+    out: String::new(),
+    diagnostics: Diagnostics::default(),
+    uses_f16: false,
+  };
+  for ty in model.structs() {
+    let TypeModel::Struct(struct_ty) = &**ty else {
+      unreachable!("Non-struct type in shader file structs");
+    };
+    gen.write_struct(struct_ty);
+  }
+  if let Some(uniforms) = model.uniforms() {
+    let TypeModel::Struct(struct_ty) = &**uniforms.ty() else {
+      unreachable!("Non-struct uniforms type");
+    };
+    gen.write_uniforms_struct(struct_ty);
+  }
+  gen.write_resources(model);
+  for func in model.funcs() {
+    gen.write_func(func);
+  }
+  for entrypoint in model.entrypoints() {
+    gen.write_entrypoint(entrypoint);
+  }
+  if !gen.diagnostics.is_empty() {
+    return Err(gen.diagnostics.into_vec());
+  }
+
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path())
+    .unwrap();
+  if gen.uses_f16 {
+    writeln!(out).unwrap();
+    writeln!(out, "enable f16;").unwrap();
+  }
+  out.push_str(&gen.out);
+  Ok(out)
+}
+
+/**
+ * The state of WGSL generation.  Diagnostics are collected rather than
+ * stopping at the first, and features used by the output are noted so
+ * that they can be enabled ahead of it.
+ */
+struct WgslGen {
+  out: String,
+  diagnostics: Diagnostics,
+  uses_f16: bool,
+}
+impl WgslGen {
+  fn write_struct(&mut self, struct_ty: &StructTypeModel) {
+    if struct_ty.packed && !self.check_packed(struct_ty) {
+      return;
+    }
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "struct {} {{", struct_name(struct_ty, ident)).unwrap();
+    for field in &struct_ty.fields {
+      let mut attributes = String::new();
+      if let Some(align) = field.align {
+        write!(attributes, "@align({}) ", align).unwrap();
+      }
+      if let Some(size) = field.size {
+        write!(attributes, "@size({}) ", size).unwrap();
+      }
+      let ty = self.type_name(&field.ty);
+      writeln!(self.out, "  {}{}: {},", attributes, ident(field.name()), ty).unwrap();
+    }
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Check that a packed struct lays out as if it were not packed, so
+   * that it can be declared as a plain WGSL struct.
+   */
+  fn check_packed(&mut self, struct_ty: &StructTypeModel) -> bool {
+    let unpacked = TypeModel::Struct(StructTypeModel {
+      packed: false,
+      ..struct_ty.clone()
+    });
+    let packed = TypeModel::Struct(struct_ty.clone());
+    if packed.layout(LayoutRules::Storage) != unpacked.layout(LayoutRules::Storage) {
+      self.diagnostics.error(format!(
+        "Packed struct `{}` cannot be expressed in WGSL, as its fields are \
+         not naturally aligned.",
+        struct_ty.name
+      ));
+      return false;
+    }
+    true
+  }
+
+  /**
+   * Write the uniforms struct.  WGSL lays out every struct by storage
+   * rules, so fields whose uniform alignment or size differs are given
+   * explicit attributes.  The layout within each field's type can't be
+   * adjusted, so it must already agree with the uniform rules.
+   */
+  fn write_uniforms_struct(&mut self, struct_ty: &StructTypeModel) {
+    let layout = TypeModel::Struct(struct_ty.clone())
+      .layout(LayoutRules::Uniform)
+      .expect("Checked uniforms have a uniform layout");
+    let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+      unreachable!("Struct has non-struct layout");
+    };
+
+    let mut fields = Vec::new();
+    for (field, field_layout) in struct_ty.fields.iter().zip(&struct_layout.fields) {
+      let storage_layout = field.ty.layout(LayoutRules::Storage)
+        .expect("Uniform fields have a storage layout");
+      if !same_inner_layout(&field_layout.layout, &storage_layout) {
+        self.diagnostics.error(format!(
+          "Uniforms field `{}` of type `{}` cannot be expressed in WGSL, as \
+           its uniform layout differs from its storage layout.",
+          field.name(), *field.ty
+        ));
+        continue;
+      }
+      let align = field.align.unwrap_or(field_layout.layout.align);
+      let size = field.size.unwrap_or(field_layout.layout.size);
+      let mut attributes = String::new();
+      if field.align.is_some() || align != storage_layout.align {
+        write!(attributes, "@align({}) ", align).unwrap();
+      }
+      if field.size.is_some() || size != storage_layout.size {
+        write!(attributes, "@size({}) ", size).unwrap();
+      }
+      let ty = self.type_name(&field.ty);
+      fields.push(format!("{}{}: {}", attributes, ident(field.name()), ty));
+    }
+
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "struct {} {{", struct_name(struct_ty, ident)).unwrap();
+    for field in fields {
+      writeln!(self.out, "  {},", field).unwrap();
+    }
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_resources(&mut self, model: &ShaderFileModel) {
+    if model.uniforms().is_none() && model.buffers().is_empty() {
+      return;
+    }
+    writeln!(self.out).unwrap();
+    if let Some(uniforms) = model.uniforms() {
+      let binding = uniforms.binding();
+      let ty = self.type_name(uniforms.ty());
+      writeln!(self.out,
+        "@group({}) @binding({}) var<uniform> uniforms: {};",
+        binding.group(), binding.binding(), ty
+      ).unwrap();
+    }
+    for buffer in model.buffers() {
+      let binding = buffer.binding();
+      let access = if buffer.mode().is_writable() { "read_write" } else { "read" };
+      let ty = self.type_name(buffer.ty());
+      writeln!(self.out,
+        "@group({}) @binding({}) var<storage, {}> {}: {};",
+        binding.group(), binding.binding(), access, ident(buffer.name()), ty
+      ).unwrap();
+    }
+  }
+
+  fn write_func(&mut self, func: &FuncModel) {
+    let args = func.args.iter()
+      .map(|arg| format!("{}: {}", ident(&arg.name.name), self.type_name(&arg.ty)))
+      .collect::<Vec<_>>();
+    let return_ty = if func.return_ty.is_void() {
+      String::new()
+    } else {
+      format!(" -> {}", self.type_name(&func.return_ty))
+    };
+    writeln!(self.out).unwrap();
+    writeln!(self.out,
+      "fn {}({}){} {{", ident(&func.name.name), args.join(", "), return_ty
+    ).unwrap();
+    self.write_block(&func.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  /**
+   * Write an entrypoint as a compute shader.  The invocation id
+   * argument is bound from the global invocation id, narrowed to the
+   * entrypoint's dimensions.
+   */
+  fn write_entrypoint(&mut self, entrypoint: &EntrypointModel) {
+    let [x, y, z] = entrypoint.workgroup_size();
+    let components = match entrypoint.dims {
+      EntrypointDims::D1 => ".x",
+      EntrypointDims::D2 => ".xy",
+      EntrypointDims::D3 => "",
+    };
+    writeln!(self.out).unwrap();
+    writeln!(self.out, "@compute @workgroup_size({}, {}, {})", x, y, z).unwrap();
+    writeln!(self.out,
+      "fn {}(@builtin(global_invocation_id) {}: vec3<u32>) {{",
+      ident(entrypoint.name()), GLOBAL_ID
+    ).unwrap();
+    writeln!(self.out,
+      "  let {} = {}{};", ident(&entrypoint.arg_name.name), GLOBAL_ID, components
+    ).unwrap();
+    self.write_block(&entrypoint.body, 1);
+    writeln!(self.out, "}}").unwrap();
+  }
+
+  fn write_block(&mut self, statements: &[StatementModel], depth: usize) {
+    for stmt in statements {
+      self.write_stmt(stmt, depth);
+    }
+  }
+
+  fn write_stmt(&mut self, stmt: &StatementModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let value = self.expr(&let_stmt.value);
+        writeln!(self.out, "{}let {} = {};", indent, ident(&let_stmt.name.name), value)
+          .unwrap();
+      },
+      StatementModel::Var(var_stmt) => {
+        let value = self.expr(&var_stmt.value);
+        writeln!(self.out, "{}var {} = {};", indent, ident(&var_stmt.name.name), value)
+          .unwrap();
+      },
+      StatementModel::Mutate(mutate_stmt) => self.write_mutate(mutate_stmt, depth),
+      StatementModel::Exec(exec_stmt) => {
+        // WGSL only allows calls as expression statements, so other
+        // values are discarded with a phony assignment.
+        let expr = self.expr(&exec_stmt.expr);
+        if matches!(exec_stmt.expr.kind, ExpressionModelKind::Call(_)) {
+          writeln!(self.out, "{}{};", indent, expr).unwrap();
+        } else {
+          writeln!(self.out, "{}_ = {};", indent, expr).unwrap();
+        }
+      },
+      StatementModel::Ret(ret_stmt) => match &ret_stmt.value {
+        Some(value) => {
+          let value = self.expr(value);
+          writeln!(self.out, "{}return {};", indent, value).unwrap();
+        },
+        None => writeln!(self.out, "{}return;", indent).unwrap(),
+      },
+      StatementModel::If(if_stmt) => {
+        for (i, (cond, block)) in if_chain(if_stmt).into_iter().enumerate() {
+          let close = if i == 0 { "" } else { "} else " };
+          match cond {
+            Some(cond) => {
+              let cond = self.expr(cond);
+              writeln!(self.out, "{}{}if {} {{", indent, close, cond).unwrap();
+            },
+            None => writeln!(self.out, "{}}} else {{", indent).unwrap(),
+          }
+          self.write_block(block, depth + 1);
+        }
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      StatementModel::Loop(loop_stmt) => {
+        writeln!(self.out, "{}loop {{", indent).unwrap();
+        self.write_block(&loop_stmt.block, depth + 1);
+        writeln!(self.out, "{}}}", indent).unwrap();
+      },
+      // Assertions are only allowed in tests, which are not generated.
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  /**
+   * Write an assignment.  WGSL can only assign single vector
+   * components, so a swizzle of several is assigned component by
+   * component from a copy of the value.  A target that may have side
+   * effects is evaluated once, through a pointer.
+   */
+  fn write_mutate(&mut self, mutate_stmt: &MutateStmtModel, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let (place, components) = flatten_swizzle(&mutate_stmt.lvalue);
+    if components.len() <= 1 {
+      let mut lvalue = self.expr(place);
+      if let [component] = components[..] {
+        write!(lvalue, ".{}", SWIZZLE_LETTERS[component as usize]).unwrap();
+      }
+      let value = self.expr(&mutate_stmt.value);
+      writeln!(self.out, "{}{} = {};", indent, lvalue, value).unwrap();
+      return;
+    }
+
+    let place_expr = self.expr(place);
+    let value = self.expr(&mutate_stmt.value);
+    writeln!(self.out, "{}{{", indent).unwrap();
+    let target = if is_simple_place(place) {
+      place_expr
+    } else {
+      writeln!(self.out, "{}  let {} = &{};", indent, SWIZZLE_TARGET, place_expr)
+        .unwrap();
+      format!("(*{})", SWIZZLE_TARGET)
+    };
+    writeln!(self.out, "{}  let {} = {};", indent, SWIZZLE_VALUE, value).unwrap();
+    for (i, &component) in components.iter().enumerate() {
+      writeln!(self.out, "{}  {}.{} = {}.{};",
+        indent,
+        target,
+        SWIZZLE_LETTERS[component as usize],
+        SWIZZLE_VALUE,
+        SWIZZLE_LETTERS[i],
+      ).unwrap();
+    }
+    writeln!(self.out, "{}}}", indent).unwrap();
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> String {
+    match &expr.kind {
+      ExpressionModelKind::Literal(literal) => self.literal(literal),
+      ExpressionModelKind::Local(name) => ident(&name.name),
+      ExpressionModelKind::Uniforms => "uniforms".to_string(),
+      ExpressionModelKind::Buffer(name) => ident(&name.name),
+      ExpressionModelKind::Index(index_expr) => {
+        let target = self.postfix_target(&index_expr.target);
+        let index = self.expr(&index_expr.index);
+        format!("{}[{}]", target, index)
+      },
+      ExpressionModelKind::ArrayLength(length_expr) =>
+        format!("arrayLength(&{})", self.expr(&length_expr.target)),
+      ExpressionModelKind::Field(field_expr) => {
+        let TypeModel::Struct(struct_ty) = &*field_expr.target.ty else {
+          unreachable!("Field access on non-struct type");
+        };
+        let field = &struct_ty.fields[field_expr.field as usize];
+        format!("{}.{}", self.postfix_target(&field_expr.target), ident(field.name()))
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => format!("{}.{}",
+        self.postfix_target(&swizzle_expr.target),
+        swizzle_expr.component_letters()
+      ),
+      ExpressionModelKind::Construct(construct_expr) => {
+        let ty = self.type_name(&expr.ty);
+        let args = self.args(&construct_expr.args);
+        format!("{}({})", ty, args)
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let args = self.args(&call_expr.args);
+        format!("{}({})", ident(&call_expr.func.name), args)
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let op = match unary_expr.op {
+          UnaryOpModel::Negate => "-",
+          UnaryOpModel::Not => "!",
+          UnaryOpModel::Complement => "~",
+        };
+        let subexpr = self.expr(&unary_expr.subexpr);
+        if matches!(unary_expr.subexpr.kind,
+          ExpressionModelKind::Unary(_) | ExpressionModelKind::Binary(_)
+        ) || subexpr.starts_with('-') {
+          format!("{}({})", op, subexpr)
+        } else {
+          format!("{}{}", op, subexpr)
+        }
+      },
+      ExpressionModelKind::Binary(binary_expr) => {
+        let lhs = self.operand(&binary_expr.lhs, &binary_expr.rhs, binary_expr.op);
+        let rhs = self.operand(&binary_expr.rhs, &binary_expr.lhs, binary_expr.op);
+        format!("{} {} {}", lhs, binary_op(binary_expr.op), rhs)
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        let ty = self.type_name(&expr.ty);
+        let subexpr = self.expr(&cast_expr.subexpr);
+        format!("{}({})", ty, subexpr)
+      },
+    }
+  }
+
+  fn args(&mut self, args: &[ExpressionModel]) -> String {
+    args.iter()
+      .map(|arg| self.expr(arg))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  /**
+   * An expression that is indexed or has a member selected.
+   */
+  fn postfix_target(&mut self, target: &ExpressionModel) -> String {
+    let target_expr = self.expr(target);
+    parenthesize_target(target, target_expr)
+  }
+
+  /**
+   * An operand of a binary operation.  Nested operations are always
+   * parenthesized, as WGSL rejects many mixes of operators without
+   * them.  WGSL bitwise operations need operands of the same type, so
+   * a scalar combined with a vector is splatted.
+   */
+  fn operand(&mut self,
+    operand: &ExpressionModel,
+    other: &ExpressionModel,
+    op: BinaryOpModel,
+  ) -> String {
+    let operand_expr = self.expr(operand);
+    let is_bitwise = matches!(op,
+      BinaryOpModel::BitAnd | BinaryOpModel::BitOr | BinaryOpModel::BitXor
+    );
+    if is_bitwise &&
+      matches!(*operand.ty, TypeModel::Scalar(_)) &&
+      matches!(*other.ty, TypeModel::Vector(_))
+    {
+      return format!("{}({})", self.type_name(&other.ty), operand_expr);
+    }
+    if matches!(operand.kind, ExpressionModelKind::Binary(_)) {
+      format!("({})", operand_expr)
+    } else {
+      operand_expr
+    }
+  }
+
+  fn literal(&mut self, literal: &LiteralModel) -> String {
+    match *literal {
+      LiteralModel::Bool(value) => value.to_string(),
+      // The magnitude of `i32::MIN` is out of range for an `i32` literal.
+      LiteralModel::I32(i32::MIN) => "i32(-2147483648)".to_string(),
+      LiteralModel::I32(value) => format!("{}i", value),
+      LiteralModel::U32(value) => format!("{}u", value),
+      LiteralModel::F32(bits) => self.float_literal(f32::from_bits(bits), "f"),
+      LiteralModel::F16(bits) => {
+        self.uses_f16 = true;
+        let value = f32::from_bits(bits);
+        self.float_literal(value, "h")
+      },
+      LiteralModel::I64(_) => self.unsupported_scalar(ScalarNumericTypeModel::I64),
+      LiteralModel::U64(_) => self.unsupported_scalar(ScalarNumericTypeModel::U64),
+    }
+  }
+
+  fn float_literal(&mut self, value: f32, suffix: &str) -> String {
+    if !value.is_finite() {
+      self.diagnostics.error(format!(
+        "Float literal `{}` cannot be expressed in WGSL, as it is not finite.", value
+      ));
+    }
+    // Debug formatting gives the shortest digits that read back exactly.
+    format!("{:?}{}", value, suffix)
+  }
+
+  fn type_name(&mut self, ty: &TypeModel) -> String {
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool)) =>
+        "bool".to_string(),
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void)) =>
+        unreachable!("Void type has no WGSL name"),
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => self.scalar_name(*scalar),
+      TypeModel::Vector(vector_ty) => format!(
+        "vec{}<{}>", vector_ty.dims as u8, self.scalar_name(vector_ty.scalar)
+      ),
+      TypeModel::Matrix(matrix_ty) => format!(
+        "mat{}x{}<{}>",
+        matrix_ty.cols as u8, matrix_ty.rows as u8, self.scalar_name(matrix_ty.scalar)
+      ),
+      TypeModel::Array(array_ty) => {
+        let elem = self.type_name(&array_ty.elem);
+        match array_ty.len {
+          Some(len) => format!("array<{}, {}>", elem, len),
+          None => format!("array<{}>", elem),
+        }
+      },
+      TypeModel::Struct(struct_ty) => struct_name(struct_ty, ident),
+    }
+  }
+
+  fn scalar_name(&mut self, scalar: ScalarNumericTypeModel) -> String {
+    match scalar {
+      ScalarNumericTypeModel::F16 => {
+        self.uses_f16 = true;
+        scalar.to_string()
+      },
+      ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 =>
+        self.unsupported_scalar(scalar),
+      _ => scalar.to_string(),
+    }
+  }
+
+  fn unsupported_scalar(&mut self, scalar: ScalarNumericTypeModel) -> String {
+    self.diagnostics.error(format!("Type `{}` is not supported by WGSL.", scalar));
+    scalar.to_string()
+  }
+}
+
+/**
+ * The name of the invocation id parameter of entrypoints.
+ */
+const GLOBAL_ID: &str = "dubgsl_global_id";
+
+/**
+ * The names of the temporaries used to assign a swizzle.
+ */
+const SWIZZLE_TARGET: &str = "dubgsl_target";
+const SWIZZLE_VALUE: &str = "dubgsl_value";
+
+/**
+ * Whether two layouts of a type agree on everything within it, though
+ * not necessarily on its own size and alignment.
+ */
+fn same_inner_layout(a: &TypeLayout, b: &TypeLayout) -> bool {
+  match (&a.kind, &b.kind) {
+    (TypeLayoutKind::Matrix(a), TypeLayoutKind::Matrix(b)) =>
+      a.column_stride == b.column_stride,
+    (TypeLayoutKind::Array(a), TypeLayoutKind::Array(b)) =>
+      a.stride == b.stride && same_inner_layout(&a.elem, &b.elem),
+    (TypeLayoutKind::Struct(a), TypeLayoutKind::Struct(b)) =>
+      a.fields.iter().zip(&b.fields).all(|(a, b)| {
+        a.offset == b.offset && same_inner_layout(&a.layout, &b.layout)
+      }),
+    _ => true,
+  }
+}
+
+/**
+ * A name as a WGSL identifier.  Names that WGSL reserves, and names
+ * starting with the `dubgsl_` prefix of generated names, get a `_`
+ * suffix.  WGSL forbids `_` and names starting with `__`, so these get
+ * the prefix.
+ */
+fn ident(name: &str) -> String {
+  if name == "_" || name.starts_with("__") {
+    format!("dubgsl{}", name)
+  } else if name.starts_with("dubgsl_") || RESERVED.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/**
+ * WGSL keywords and reserved words, and the predeclared types and
+ * functions that the output refers to.
+ */
+const RESERVED: &[&str] = &[
+  // Keywords.
+  "alias", "break", "case", "const", "const_assert", "continue", "continuing",
+  "default", "diagnostic", "discard", "else", "enable", "false", "fn", "for",
+  "if", "let", "loop", "override", "requires", "return", "struct", "switch",
+  "true", "var", "while",
+
+  // Reserved words.
+  "NULL", "Self", "abstract", "active", "alignas", "alignof", "as", "asm",
+  "asm_fragment", "async", "attribute", "auto", "await", "become", "cast",
+  "catch", "class", "co_await", "co_return", "co_yield", "coherent",
+  "column_major", "common", "compile", "compile_fragment", "concept",
+  "const_cast", "consteval", "constexpr", "constinit", "crate", "debugger",
+  "decltype", "delete", "demote", "demote_to_helper", "do", "dynamic_cast",
+  "enum", "explicit", "export", "extends", "extern", "external",
+  "fallthrough", "filter", "final", "finally", "friend", "from", "fxgroup",
+  "get", "goto", "groupshared", "highp", "impl", "implements", "import",
+  "inline", "instanceof", "interface", "layout", "lowp", "macro",
+  "macro_rules", "match", "mediump", "meta", "mod", "module", "move", "mut",
+  "mutable", "namespace", "new", "nil", "noexcept", "noinline",
+  "nointerpolation", "non_coherent", "noncoherent", "noperspective", "null",
+  "nullptr", "of", "operator", "package", "packoffset", "partition", "pass",
+  "patch", "pixelfragment", "precise", "precision", "premerge", "priv",
+  "protected", "pub", "public", "readonly", "ref", "regardless", "register",
+  "reinterpret_cast", "require", "resource", "restrict", "self", "set",
+  "shared", "sizeof", "smooth", "snorm", "static", "static_assert",
+  "static_cast", "std", "subroutine", "super", "target", "template", "this",
+  "thread_local", "throw", "trait", "try", "type", "typedef", "typeid",
+  "typename", "typeof", "union", "unless", "unorm", "unsafe", "unsized",
+  "use", "using", "varying", "virtual", "volatile", "wgsl", "where", "with",
+  "writeonly", "yield",
+
+  // Predeclared types and functions used by the output.
+  "array", "arrayLength", "bool", "f16", "f32", "i32", "u32", "vec2", "vec3",
+  "vec4", "mat2x2", "mat2x3", "mat2x4", "mat3x2", "mat3x3", "mat3x4",
+  "mat4x2", "mat4x3", "mat4x4", "ptr", "uniforms",
+];
@@ -0,0 +1,422 @@
+use std::fmt;
+use crate::{
+  data::value::{ Value, ValuePath, ValuePathSegment },
+  model::{
+    LayoutError,
+    LayoutRules,
+    LiteralModel,
+    ScalarNumericTypeModel,
+    TypeLayout,
+    TypeLayoutKind,
+    TypeModel,
+    f16_bits_from_f32,
+    f32_from_f16_bits,
+  },
+};
+
+/**
+ * Encode a value of the given type into bytes, laid out under the given
+ * rules.  A runtime-sized array takes its length from the value.
+ */
+pub fn encode(ty: &TypeModel, rules: LayoutRules, value: &Value)
+  -> Result<Vec<u8>, CodecError>
+{
+  let layout = TypeLayout::of(ty, rules).map_err(layout_error)?;
+  let len = runtime_len(ty, value);
+  let count = u32::try_from(len)
+    .map_err(|_| layout_error(LayoutError::TooManyElements(len)))?;
+  let size = layout.size_with_elements(count).map_err(layout_error)?;
+  let mut bytes = vec![0; size as usize];
+  Encoder { bytes: &mut bytes, path: Vec::new() }.write(ty, &layout, 0, value)?;
+  Ok(bytes)
+}
+
+/**
+ * Decode bytes laid out under the given rules into a value of the given
+ * type.  A runtime-sized array takes as many elements as fit.
+ */
+pub fn decode(ty: &TypeModel, rules: LayoutRules, bytes: &[u8])
+  -> Result<Value, CodecError>
+{
+  let layout = TypeLayout::of(ty, rules).map_err(layout_error)?;
+  check_size(&layout, bytes.len())?;
+  Ok(Decoder { bytes }.read(ty, &layout, 0))
+}
+
+/**
+ * Check that bytes of the given length can hold a value of a layout.
+ * A runtime-sized array must fill the bytes with whole elements.
+ */
+pub(crate) fn check_size(layout: &TypeLayout, len: usize) -> Result<(), CodecError> {
+  let expected = match runtime_tail(layout) {
+    Some((offset, stride)) => {
+      let count = len.saturating_sub(offset as usize) / stride as usize;
+      let count = u32::try_from(count)
+        .map_err(|_| layout_error(LayoutError::TooManyElements(count)))?;
+      layout.size_with_elements(count).map_err(layout_error)? as usize
+    },
+    None => layout.size as usize,
+  };
+  if len != expected {
+    return Err(CodecError::new(Vec::new(), CodecErrorKind::SizeMismatch {
+      expected,
+      found: len,
+    }));
+  }
+  Ok(())
+}
+
+/**
+ * An error in the layout of the value as a whole.
+ */
+fn layout_error(err: LayoutError) -> CodecError {
+  CodecError::new(Vec::new(), CodecErrorKind::Layout(err))
+}
+
+/**
+ * Read a value of the given type and layout at an offset into bytes,
+ * which must be large enough to hold it.
+ */
+pub(crate) fn read_at(ty: &TypeModel, layout: &TypeLayout, bytes: &[u8], offset: usize)
+  -> Value
+{
+  Decoder { bytes }.read(ty, layout, offset)
+}
+
+/**
+ * Write a value of the given type and layout at an offset into bytes,
+ * which must be large enough to hold it.  Padding is left as it is.
+ */
+pub(crate) fn write_at(
+  ty: &TypeModel,
+  layout: &TypeLayout,
+  bytes: &mut [u8],
+  offset: usize,
+  value: &Value,
+) -> Result<(), CodecError> {
+  Encoder { bytes, path: Vec::new() }.write(ty, layout, offset, value)
+}
+
+/**
+ * An error encoding or decoding a value, at the path of the value in
+ * error.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError {
+  pub(crate) path: Vec<ValuePathSegment>,
+  pub(crate) kind: CodecErrorKind,
+}
+impl CodecError {
+  fn new(path: Vec<ValuePathSegment>, kind: CodecErrorKind) -> CodecError {
+    CodecError { path, kind }
+  }
+
+  pub fn path(&self) -> &[ValuePathSegment] {
+    &self.path
+  }
+
+  pub fn kind(&self) -> &CodecErrorKind {
+    &self.kind
+  }
+}
+impl fmt::Display for CodecError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "At `{}`: {}", ValuePath(&self.path), self.kind)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecErrorKind {
+  /** The type has no layout. */
+  Layout(LayoutError),
+
+  /** The value does not have the shape or scalar type of its type. */
+  TypeMismatch { expected: String, found: String },
+
+  /** A vector, matrix or fixed-size array has the wrong length. */
+  LengthMismatch { expected: usize, found: usize },
+
+  MissingField(String),
+  UnknownField(String),
+  DuplicateField(String),
+
+  /** The bytes to decode are not the size of the type. */
+  SizeMismatch { expected: usize, found: usize },
+}
+impl fmt::Display for CodecErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CodecErrorKind::Layout(err) => write!(f, "{}", err),
+      CodecErrorKind::TypeMismatch { expected, found } =>
+        write!(f, "Expected a value of type `{}`, found {}.", expected, found),
+      CodecErrorKind::LengthMismatch { expected, found } =>
+        write!(f, "Expected {} elements, found {}.", expected, found),
+      CodecErrorKind::MissingField(name) => write!(f, "Missing field `{}`.", name),
+      CodecErrorKind::UnknownField(name) => write!(f, "Unknown field `{}`.", name),
+      CodecErrorKind::DuplicateField(name) => write!(f, "Duplicate field `{}`.", name),
+      CodecErrorKind::SizeMismatch { expected, found } =>
+        write!(f, "Expected {} bytes, found {}.", expected, found),
+    }
+  }
+}
+
+struct Encoder<'b> {
+  bytes: &'b mut [u8],
+  path: Vec<ValuePathSegment>,
+}
+impl<'b> Encoder<'b> {
+  fn error<T>(&self, kind: CodecErrorKind) -> Result<T, CodecError> {
+    Err(CodecError::new(self.path.clone(), kind))
+  }
+
+  fn mismatch<T>(&self, ty: &TypeModel, value: &Value) -> Result<T, CodecError> {
+    self.error(CodecErrorKind::TypeMismatch {
+      expected: ty.to_string(),
+      found: value.describe(),
+    })
+  }
+
+  fn write(&mut self, ty: &TypeModel, layout: &TypeLayout, offset: usize, value: &Value)
+    -> Result<(), CodecError>
+  {
+    match (ty, &layout.kind) {
+      (TypeModel::Scalar(_), _) => {
+        let scalar = ty.as_numeric_scalar().expect("Host-shareable scalar is numeric");
+        let Value::Scalar(literal) = value else {
+          return self.mismatch(ty, value);
+        };
+        self.write_scalar(scalar, offset, literal)
+      },
+      (TypeModel::Vector(vector_ty), _) => {
+        self.write_vector(vector_ty.scalar, vector_ty.dims as usize, offset, ty, value)
+      },
+      (TypeModel::Matrix(matrix_ty), TypeLayoutKind::Matrix(matrix_layout)) => {
+        let Value::Array(columns) = value else {
+          return self.mismatch(ty, value);
+        };
+        self.check_len(matrix_ty.cols as usize, columns.len())?;
+        let column_ty = TypeModel::new_vector(matrix_ty.scalar, matrix_ty.rows);
+        for (i, column) in columns.iter().enumerate() {
+          self.path.push(ValuePathSegment::Index(i));
+          self.write_vector(
+            matrix_ty.scalar,
+            matrix_ty.rows as usize,
+            offset + i * matrix_layout.column_stride as usize,
+            &column_ty,
+            column,
+          )?;
+          self.path.pop();
+        }
+        Ok(())
+      },
+      (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) => {
+        let Value::Array(elems) = value else {
+          return self.mismatch(ty, value);
+        };
+        if let Some(len) = array_ty.len {
+          self.check_len(len as usize, elems.len())?;
+        }
+        for (i, elem) in elems.iter().enumerate() {
+          self.path.push(ValuePathSegment::Index(i));
+          self.write(
+            &array_ty.elem,
+            &array_layout.elem,
+            offset + i * array_layout.stride as usize,
+            elem,
+          )?;
+          self.path.pop();
+        }
+        Ok(())
+      },
+      (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(struct_layout)) => {
+        let Value::Struct(members) = value else {
+          return self.mismatch(ty, value);
+        };
+        for (i, (name, _)) in members.iter().enumerate() {
+          if struct_ty.field(name).is_none() {
+            return self.error(CodecErrorKind::UnknownField(name.clone()));
+          }
+          if members[.. i].iter().any(|(other, _)| other == name) {
+            return self.error(CodecErrorKind::DuplicateField(name.clone()));
+          }
+        }
+        for (field, field_layout) in struct_ty.fields.iter().zip(&struct_layout.fields) {
+          let Some(member) = value.field(&field.name.name) else {
+            return self.error(CodecErrorKind::MissingField(field.name.name.clone()));
+          };
+          self.path.push(ValuePathSegment::Field(field.name.name.clone()));
+          self.write(
+            &field.ty,
+            &field_layout.layout,
+            offset + field_layout.offset as usize,
+            member,
+          )?;
+          self.path.pop();
+        }
+        Ok(())
+      },
+      _ => panic!("Layout does not match type `{}`", ty),
+    }
+  }
+
+  fn write_vector(&mut self,
+    scalar: ScalarNumericTypeModel,
+    dims: usize,
+    offset: usize,
+    ty: &TypeModel,
+    value: &Value,
+  ) -> Result<(), CodecError> {
+    let Value::Vector(components) = value else {
+      return self.mismatch(ty, value);
+    };
+    self.check_len(dims, components.len())?;
+    for (i, component) in components.iter().enumerate() {
+      self.path.push(ValuePathSegment::Index(i));
+      let component_offset = offset + i * scalar.size() as usize;
+      self.write_scalar(scalar, component_offset, component)?;
+      self.path.pop();
+    }
+    Ok(())
+  }
+
+  fn write_scalar(&mut self,
+    scalar: ScalarNumericTypeModel,
+    offset: usize,
+    literal: &LiteralModel,
+  ) -> Result<(), CodecError> {
+    let bytes = match (scalar, literal) {
+      (ScalarNumericTypeModel::I32, LiteralModel::I32(v)) => v.to_le_bytes().to_vec(),
+      (ScalarNumericTypeModel::U32, LiteralModel::U32(v)) => v.to_le_bytes().to_vec(),
+      (ScalarNumericTypeModel::F32, LiteralModel::F32(bits)) => bits.to_le_bytes().to_vec(),
+      (ScalarNumericTypeModel::F16, LiteralModel::F16(bits)) =>
+        f16_bits_from_f32(f32::from_bits(*bits)).to_le_bytes().to_vec(),
+      (ScalarNumericTypeModel::I64, LiteralModel::I64(v)) => v.to_le_bytes().to_vec(),
+      (ScalarNumericTypeModel::U64, LiteralModel::U64(v)) => v.to_le_bytes().to_vec(),
+      _ => return self.error(CodecErrorKind::TypeMismatch {
+        expected: scalar.to_string(),
+        found: Value::Scalar(*literal).describe(),
+      }),
+    };
+    self.bytes[offset .. offset + bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+  }
+
+  fn check_len(&self, expected: usize, found: usize) -> Result<(), CodecError> {
+    if expected != found {
+      return self.error(CodecErrorKind::LengthMismatch { expected, found });
+    }
+    Ok(())
+  }
+}
+
+struct Decoder<'b> {
+  bytes: &'b [u8],
+}
+impl<'b> Decoder<'b> {
+  /**
+   * Read a value.  The bytes have been checked to be large enough.
+   */
+  fn read(&self, ty: &TypeModel, layout: &TypeLayout, offset: usize) -> Value {
+    match (ty, &layout.kind) {
+      (TypeModel::Scalar(_), _) => {
+        let scalar = ty.as_numeric_scalar().expect("Host-shareable scalar is numeric");
+        Value::Scalar(self.read_scalar(scalar, offset))
+      },
+      (TypeModel::Vector(vector_ty), _) =>
+        self.read_vector(vector_ty.scalar, vector_ty.dims as usize, offset),
+      (TypeModel::Matrix(matrix_ty), TypeLayoutKind::Matrix(matrix_layout)) => Value::Array(
+        (0 .. matrix_ty.cols as usize)
+          .map(|i| self.read_vector(
+            matrix_ty.scalar,
+            matrix_ty.rows as usize,
+            offset + i * matrix_layout.column_stride as usize,
+          ))
+          .collect()
+      ),
+      (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) => {
+        let stride = array_layout.stride as usize;
+        let len = match array_ty.len {
+          Some(len) => len as usize,
+          None => (self.bytes.len() - offset) / stride,
+        };
+        Value::Array(
+          (0 .. len)
+            .map(|i| self.read(&array_ty.elem, &array_layout.elem, offset + i * stride))
+            .collect()
+        )
+      },
+      (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(struct_layout)) => Value::Struct(
+        struct_ty.fields.iter().zip(&struct_layout.fields)
+          .map(|(field, field_layout)| (
+            field.name.name.clone(),
+            self.read(
+              &field.ty,
+              &field_layout.layout,
+              offset + field_layout.offset as usize,
+            ),
+          ))
+          .collect()
+      ),
+      _ => panic!("Layout does not match type `{}`", ty),
+    }
+  }
+
+  fn read_vector(&self, scalar: ScalarNumericTypeModel, dims: usize, offset: usize)
+    -> Value
+  {
+    Value::Vector(
+      (0 .. dims)
+        .map(|i| self.read_scalar(scalar, offset + i * scalar.size() as usize))
+        .collect()
+    )
+  }
+
+  fn read_scalar(&self, scalar: ScalarNumericTypeModel, offset: usize) -> LiteralModel {
+    let bytes = &self.bytes[offset .. offset + scalar.size() as usize];
+    match scalar {
+      ScalarNumericTypeModel::I32 =>
+        LiteralModel::I32(i32::from_le_bytes(bytes.try_into().unwrap())),
+      ScalarNumericTypeModel::U32 =>
+        LiteralModel::U32(u32::from_le_bytes(bytes.try_into().unwrap())),
+      ScalarNumericTypeModel::F32 =>
+        LiteralModel::F32(u32::from_le_bytes(bytes.try_into().unwrap())),
+      ScalarNumericTypeModel::F16 => LiteralModel::new_f16(
+        f32_from_f16_bits(u16::from_le_bytes(bytes.try_into().unwrap()))
+      ),
+      ScalarNumericTypeModel::I64 =>
+        LiteralModel::I64(i64::from_le_bytes(bytes.try_into().unwrap())),
+      ScalarNumericTypeModel::U64 =>
+        LiteralModel::U64(u64::from_le_bytes(bytes.try_into().unwrap())),
+    }
+  }
+}
+
+/**
+ * The element count of the runtime-sized array in a value, or 0 if the
+ * type has none or the value doesn't match it.
+ */
+fn runtime_len(ty: &TypeModel, value: &Value) -> usize {
+  match (ty, value) {
+    (TypeModel::Array(array_ty), Value::Array(elems)) if array_ty.len.is_none() =>
+      elems.len(),
+    (TypeModel::Struct(struct_ty), Value::Struct(_)) => struct_ty.fields.last()
+      .and_then(|field| Some(runtime_len(&field.ty, value.field(&field.name.name)?)))
+      .unwrap_or(0),
+    _ => 0,
+  }
+}
+
+/**
+ * The offset and stride of the runtime-sized array ending a layout.
+ */
+fn runtime_tail(layout: &TypeLayout) -> Option<(u32, u32)> {
+  match &layout.kind {
+    TypeLayoutKind::Array(array) if array.len.is_none() => Some((0, array.stride)),
+    TypeLayoutKind::Struct(struct_layout) => {
+      let last = struct_layout.fields.last()?;
+      let (offset, stride) = runtime_tail(&last.layout)?;
+      Some((last.offset + offset, stride))
+    },
+    _ => None,
+  }
+}
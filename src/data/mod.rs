@@ -0,0 +1,8 @@
+mod codec;
+mod value;
+
+pub use self::{
+  codec::{ CodecError, CodecErrorKind, decode, encode },
+  value::{ Value, ValuePathSegment },
+};
+pub(crate) use self::codec::{ check_size, read_at, write_at };
@@ -0,0 +1,80 @@
+use std::fmt;
+use crate::model::LiteralModel;
+
+/**
+ * A dynamically typed value of a host-shareable type.  Matrices are
+ * arrays of their column vectors, and struct members are named.
+ *
+ * E.g. a `Particle { position: vec3xf32, mass: f32 }` is
+ * `Struct([("position", Vector([F32, F32, F32])), ("mass", Scalar(F32))])`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Scalar(LiteralModel),
+  Vector(Vec<LiteralModel>),
+  Array(Vec<Value>),
+  Struct(Vec<(String, Value)>),
+}
+impl Value {
+  /**
+   * Look up a struct member by name.
+   */
+  pub fn field(&self, name: &str) -> Option<&Value> {
+    match self {
+      Value::Struct(members) => members.iter()
+        .find(|(member_name, _)| member_name == name)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  /**
+   * A short description of the value's shape, for error messages.
+   */
+  pub(crate) fn describe(&self) -> String {
+    match self {
+      Value::Scalar(literal) => format!("a `{}` scalar", literal_type_name(literal)),
+      Value::Vector(components) => format!("a vector of {} components", components.len()),
+      Value::Array(elems) => format!("an array of {} elements", elems.len()),
+      Value::Struct(_) => "a struct".to_string(),
+    }
+  }
+}
+
+pub(crate) fn literal_type_name(literal: &LiteralModel) -> &'static str {
+  match literal {
+    LiteralModel::Bool(_) => "bool",
+    LiteralModel::I32(_) => "i32",
+    LiteralModel::U32(_) => "u32",
+    LiteralModel::F32(_) => "f32",
+    LiteralModel::F16(_) => "f16",
+    LiteralModel::I64(_) => "i64",
+    LiteralModel::U64(_) => "u64",
+  }
+}
+
+/**
+ * A step in the path from a root value to a nested value.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValuePathSegment {
+  Field(String),
+  Index(usize),
+}
+
+/**
+ * Formats a path as e.g. `value.particles[3].mass`.
+ */
+pub(crate) struct ValuePath<'a>(pub(crate) &'a [ValuePathSegment]);
+impl<'a> fmt::Display for ValuePath<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "value")?;
+    for segment in self.0 {
+      match segment {
+        ValuePathSegment::Field(name) => write!(f, ".{}", name)?,
+        ValuePathSegment::Index(index) => write!(f, "[{}]", index)?,
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,226 @@
+use std::{
+  collections::HashSet,
+  fmt::Write,
+};
+use crate::{
+  host_gen::{ HostStruct, HostType, HostTypes, pascal_case, screaming_snake_case },
+  model::{ ScalarNumericTypeModel, ShaderFileModel },
+};
+
+/**
+ * Generate a C11 header declaring padded structs for the structs and
+ * uniforms of a shader file, and the element type of each buffer.
+ * Static assertions check that the sizes and field offsets match the
+ * shader's layout.
+ *
+ * `f16` values are held as their `uint16_t` bit patterns.
+ */
+pub fn generate_c_header(model: &ShaderFileModel) -> String {
+  let host_types = HostTypes::of(model);
+  let guard = include_guard(model.path());
+  let mut gen = CGen { out: String::new(), padded: HashSet::new() };
+
+  for host_struct in &host_types.structs {
+    gen.write_struct(host_struct);
+  }
+  for element in &host_types.buffer_elements {
+    let name = format!("{}Element", pascal_case(&element.buffer));
+    let elem_decl =
+      gen.elem_decl(&element.elem, element.elem_size, element.stride, &name);
+    writeln!(gen.out).unwrap();
+    writeln!(gen.out, "/* The element type of buffer `{}`. */", element.buffer).unwrap();
+    writeln!(gen.out, "typedef {};", elem_decl).unwrap();
+    writeln!(gen.out, "#define {}_STRIDE {}", screaming_snake_case(&name), element.stride)
+      .unwrap();
+    writeln!(gen.out,
+      "_Static_assert(sizeof({}) == {}, \"{} stride\");", name, element.stride, name
+    ).unwrap();
+  }
+
+  let mut out = String::new();
+  writeln!(out, "/* Generated by dubgsl from `{}`.  Do not edit. */", model.path())
+    .unwrap();
+  writeln!(out, "#ifndef {}", guard).unwrap();
+  writeln!(out, "#define {}", guard).unwrap();
+  writeln!(out).unwrap();
+  writeln!(out, "#include <stddef.h>").unwrap();
+  writeln!(out, "#include <stdint.h>").unwrap();
+  out.push_str(&gen.out);
+  writeln!(out).unwrap();
+  writeln!(out, "#endif /* {} */", guard).unwrap();
+  out
+}
+
+/**
+ * The state of C header generation.  Array elements that are smaller
+ * than their stride are wrapped in padded structs, which are declared
+ * once, before their first use.
+ */
+struct CGen {
+  out: String,
+  padded: HashSet<String>,
+}
+impl CGen {
+  fn write_struct(&mut self, host_struct: &HostStruct) {
+    let name = &host_struct.name;
+    // The member declarations are built first, so that any padded
+    // element structs they use are declared before this struct.
+    let mut members = Vec::new();
+    let mut pad_count = 0;
+    for (i, field) in host_struct.fields.iter().enumerate() {
+      let decl = self.decl(&field.ty, &field_name(&field.name));
+      if i == 0 && !host_struct.packed {
+        members.push(format!("_Alignas({}) {}", host_struct.align, decl));
+      } else {
+        members.push(decl);
+      }
+      if field.padding > 0 {
+        members.push(format!("uint8_t _pad{}[{}]", pad_count, field.padding));
+        pad_count += 1;
+      }
+    }
+    let tail_decl = host_struct.tail.as_ref().map(|tail| {
+      let declarator = format!("{}[]", field_name(&tail.name));
+      self.elem_decl(&tail.elem, tail.elem_size, tail.stride, &declarator)
+    });
+
+    writeln!(self.out).unwrap();
+    if let Some(tail) = &host_struct.tail {
+      let tail_name = format!("{}{}", name, pascal_case(&tail.name));
+      let tail_const = screaming_snake_case(&tail_name);
+      writeln!(self.out,
+        "/* The offset of the runtime-sized `{}` array of `{}`. */", tail.name, name
+      ).unwrap();
+      writeln!(self.out, "#define {}_OFFSET {}", tail_const, tail.offset).unwrap();
+      writeln!(self.out, "#define {}_STRIDE {}", tail_const, tail.stride).unwrap();
+      // C structs need a member before a flexible array member.
+      if host_struct.fields.is_empty() {
+        return;
+      }
+    }
+
+    if host_struct.packed {
+      writeln!(self.out, "#pragma pack(push, 1)").unwrap();
+    }
+    writeln!(self.out, "typedef struct {} {{", name).unwrap();
+    for member in &members {
+      writeln!(self.out, "  {};", member).unwrap();
+    }
+    if let Some(tail_decl) = &tail_decl {
+      writeln!(self.out, "  {};", tail_decl).unwrap();
+    }
+    writeln!(self.out, "}} {};", name).unwrap();
+    if host_struct.packed {
+      writeln!(self.out, "#pragma pack(pop)").unwrap();
+    }
+
+    if host_struct.tail.is_none() {
+      writeln!(self.out,
+        "_Static_assert(sizeof({}) == {}, \"{} size\");", name, host_struct.size, name
+      ).unwrap();
+    }
+    for field in &host_struct.fields {
+      writeln!(self.out,
+        "_Static_assert(offsetof({}, {}) == {}, \"{}.{} offset\");",
+        name, field_name(&field.name), field.offset, name, field.name
+      ).unwrap();
+    }
+    if let Some(tail) = &host_struct.tail {
+      writeln!(self.out,
+        "_Static_assert(offsetof({}, {}) == {}, \"{}.{} offset\");",
+        name, field_name(&tail.name), tail.offset, name, tail.name
+      ).unwrap();
+    }
+  }
+
+  /**
+   * A declaration of `declarator` with the given type, e.g.
+   * `float position[3]`.
+   */
+  fn decl(&mut self, ty: &HostType, declarator: &str) -> String {
+    match ty {
+      HostType::Scalar(scalar) => format!("{} {}", scalar_type(*scalar), declarator),
+      HostType::Vector(scalar, len) =>
+        format!("{} {}[{}]", scalar_type(*scalar), declarator, len),
+      HostType::Array { elem, elem_size, stride, len } =>
+        self.elem_decl(elem, *elem_size, *stride, &format!("{}[{}]", declarator, len)),
+      HostType::Struct(name) => format!("{} {}", name, declarator),
+    }
+  }
+
+  /**
+   * A declaration of an array element type, declaring a padded struct
+   * for it if the stride is larger than the element.
+   */
+  fn elem_decl(&mut self, elem: &HostType, elem_size: u32, stride: u32, declarator: &str)
+    -> String
+  {
+    if stride <= elem_size {
+      return self.decl(elem, declarator);
+    }
+    let pad = stride - elem_size;
+    let name = format!("dubgsl_padded_{}_{}", type_tag(elem), pad);
+    if self.padded.insert(name.clone()) {
+      let value_decl = self.decl(elem, "value");
+      writeln!(self.out).unwrap();
+      writeln!(self.out, "typedef struct {} {{", name).unwrap();
+      writeln!(self.out, "  {};", value_decl).unwrap();
+      writeln!(self.out, "  uint8_t _pad[{}];", pad).unwrap();
+      writeln!(self.out, "}} {};", name).unwrap();
+    }
+    format!("{} {}", name, declarator)
+  }
+}
+
+/**
+ * A name for a type, for use in the names of padded structs.
+ */
+fn type_tag(ty: &HostType) -> String {
+  match ty {
+    HostType::Scalar(scalar) => scalar.to_string(),
+    HostType::Vector(scalar, len) => format!("{}x{}", scalar, len),
+    HostType::Array { elem, elem_size, stride, len } if stride > elem_size =>
+      format!("{}_{}_{}", type_tag(elem), stride - elem_size, len),
+    HostType::Array { elem, len, .. } => format!("{}_{}", type_tag(elem), len),
+    HostType::Struct(name) => name.clone(),
+  }
+}
+
+fn scalar_type(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::I32 => "int32_t",
+    ScalarNumericTypeModel::U32 => "uint32_t",
+    ScalarNumericTypeModel::F32 => "float",
+    ScalarNumericTypeModel::F16 => "uint16_t",
+    ScalarNumericTypeModel::I64 => "int64_t",
+    ScalarNumericTypeModel::U64 => "uint64_t",
+  }
+}
+
+/**
+ * A field name, with an underscore appended if it is a C keyword.
+ */
+fn field_name(name: &str) -> String {
+  const KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do",
+    "double", "else", "enum", "extern", "float", "for", "goto", "if", "inline",
+    "int", "long", "register", "restrict", "return", "short", "signed",
+    "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned",
+    "void", "volatile", "while",
+  ];
+  if KEYWORDS.contains(&name) {
+    format!("{}_", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/**
+ * An include guard derived from the shader file path.
+ */
+fn include_guard(path: &str) -> String {
+  let sanitized = path.chars()
+    .map(|ch| if ch.is_ascii_alphanumeric() { ch.to_ascii_uppercase() } else { '_' })
+    .collect::<String>();
+  format!("DUBGSL_{}_H", sanitized)
+}
@@ -0,0 +1,298 @@
+mod c_gen;
+mod rust_gen;
+mod ts_gen;
+
+pub use self::{
+  c_gen::generate_c_header,
+  rust_gen::generate_rust,
+  ts_gen::generate_typescript,
+};
+
+use std::collections::HashSet;
+use crate::model::{
+  LayoutRules,
+  NamePathModel,
+  ScalarNumericTypeModel,
+  ShaderFileModel,
+  StructTypeModel,
+  TypeLayout,
+  TypeLayoutKind,
+  TypeModel,
+};
+
+/**
+ * A type as it is laid out in host memory.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HostType {
+  Scalar(ScalarNumericTypeModel),
+
+  /** A vector of scalars, without any trailing padding. */
+  Vector(ScalarNumericTypeModel, u32),
+
+  /**
+   * A fixed-size array whose elements of `elem_size` bytes are padded
+   * to `stride` bytes.  Matrices are arrays of their column vectors.
+   */
+  Array { elem: Box<HostType>, elem_size: u32, stride: u32, len: u32 },
+
+  /** A generated struct, by name. */
+  Struct(String),
+}
+
+/**
+ * A struct to generate, with explicit padding between its fields.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct HostStruct {
+  pub(crate) name: String,
+  pub(crate) size: u32,
+  pub(crate) align: u32,
+  pub(crate) packed: bool,
+  pub(crate) fields: Vec<HostField>,
+  pub(crate) tail: Option<HostTailArray>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HostField {
+  pub(crate) name: String,
+  pub(crate) offset: u32,
+  pub(crate) padding: u32,
+  pub(crate) ty: HostType,
+}
+
+/**
+ * The runtime-sized array ending a struct.  It is not part of the
+ * generated struct, which holds only the fields before it.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct HostTailArray {
+  pub(crate) name: String,
+  pub(crate) offset: u32,
+  pub(crate) elem: HostType,
+  pub(crate) elem_size: u32,
+  pub(crate) stride: u32,
+}
+
+/**
+ * The element type of a buffer.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct HostBufferElement {
+  pub(crate) buffer: String,
+  pub(crate) elem: HostType,
+  pub(crate) elem_size: u32,
+  pub(crate) stride: u32,
+}
+
+/**
+ * The host types of a shader file: every struct that can be shared with
+ * the host, the uniforms, and the element type of each buffer.  Structs
+ * come before the structs that contain them.
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HostTypes {
+  pub(crate) structs: Vec<HostStruct>,
+  pub(crate) buffer_elements: Vec<HostBufferElement>,
+  added: HashSet<String>,
+}
+impl HostTypes {
+  pub(crate) fn of(model: &ShaderFileModel) -> HostTypes {
+    let mut host_types = HostTypes::default();
+    for ty in &model.structs {
+      if let TypeModel::Struct(struct_ty) = &**ty {
+        // Structs that can't be shared with the host are left out.
+        if ty.layout(LayoutRules::Storage).is_ok() {
+          host_types.struct_name(struct_ty, LayoutRules::Storage);
+        }
+      }
+    }
+    if let Some(uniforms) = &model.uniforms {
+      if let TypeModel::Struct(struct_ty) = &*uniforms.ty {
+        let layout = uniforms.layout().expect("Checked uniforms have a layout");
+        host_types.add_struct(
+          "Uniforms".to_string(),
+          struct_ty,
+          &layout,
+          LayoutRules::Uniform,
+        );
+      }
+    }
+    for buffer in model.reflect().buffers() {
+      let (elem_ty, elem_layout) = buffer.element();
+      let elem = host_types.host_type(elem_ty, elem_layout, LayoutRules::Storage);
+      host_types.buffer_elements.push(HostBufferElement {
+        buffer: buffer.name().to_string(),
+        elem,
+        elem_size: elem_layout.size,
+        stride: buffer.element_stride(),
+      });
+    }
+    host_types
+  }
+
+  /**
+   * The name of the host struct for a struct type under the given
+   * rules, adding it if needed.  A struct laid out differently under
+   * uniform rules gets a separate `Uniform`-suffixed struct.
+   */
+  fn struct_name(&mut self, struct_ty: &StructTypeModel, rules: LayoutRules) -> String {
+    let ty = TypeModel::Struct(struct_ty.clone());
+    let storage_layout = ty.layout(LayoutRules::Storage)
+      .expect("Host-shareable struct has a layout");
+    let (name, layout) = match rules {
+      LayoutRules::Storage => (host_name(&struct_ty.name), storage_layout),
+      LayoutRules::Uniform => {
+        let layout = ty.layout(LayoutRules::Uniform)
+          .expect("Host-shareable struct has a layout");
+        if layout == storage_layout {
+          return self.struct_name(struct_ty, LayoutRules::Storage);
+        }
+        (format!("{}Uniform", host_name(&struct_ty.name)), layout)
+      },
+    };
+    if !self.added.contains(&name) {
+      self.add_struct(name.clone(), struct_ty, &layout, rules);
+    }
+    name
+  }
+
+  fn add_struct(&mut self,
+    name: String,
+    struct_ty: &StructTypeModel,
+    layout: &TypeLayout,
+    rules: LayoutRules,
+  ) {
+    self.added.insert(name.clone());
+    let TypeLayoutKind::Struct(struct_layout) = &layout.kind else {
+      panic!("Struct `{}` has a non-struct layout", name);
+    };
+    let mut fields = Vec::new();
+    let mut tail = None;
+    for (field, field_layout) in struct_ty.fields.iter().zip(&struct_layout.fields) {
+      match (&*field.ty, &field_layout.layout.kind) {
+        (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout))
+          if array_ty.len.is_none() =>
+        {
+          tail = Some(HostTailArray {
+            name: field.name.name.clone(),
+            offset: field_layout.offset,
+            elem: self.host_type(&array_ty.elem, &array_layout.elem, rules),
+            elem_size: array_layout.elem.size,
+            stride: array_layout.stride,
+          });
+        },
+        _ => fields.push(HostField {
+          name: field.name.name.clone(),
+          offset: field_layout.offset,
+          padding: field_layout.padding,
+          ty: self.host_type(&field.ty, &field_layout.layout, rules),
+        }),
+      }
+    }
+    self.structs.push(HostStruct {
+      name,
+      size: layout.size,
+      align: layout.align,
+      packed: struct_ty.packed,
+      fields,
+      tail,
+    });
+  }
+
+  fn host_type(&mut self, ty: &TypeModel, layout: &TypeLayout, rules: LayoutRules)
+    -> HostType
+  {
+    match (ty, &layout.kind) {
+      (TypeModel::Vector(vector_ty), _) =>
+        HostType::Vector(vector_ty.scalar, vector_ty.dims as u32),
+      (TypeModel::Matrix(matrix_ty), TypeLayoutKind::Matrix(matrix_layout)) => {
+        let rows = matrix_ty.rows as u32;
+        HostType::Array {
+          elem: Box::new(HostType::Vector(matrix_ty.scalar, rows)),
+          elem_size: matrix_ty.scalar.size() * rows,
+          stride: matrix_layout.column_stride,
+          len: matrix_ty.cols as u32,
+        }
+      },
+      (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) => HostType::Array {
+        elem: Box::new(self.host_type(&array_ty.elem, &array_layout.elem, rules)),
+        elem_size: array_layout.elem.size,
+        stride: array_layout.stride,
+        len: array_ty.len.expect("Runtime-sized arrays are struct tails"),
+      },
+      (TypeModel::Struct(struct_ty), _) => HostType::Struct(self.struct_name(struct_ty, rules)),
+      _ => HostType::Scalar(
+        ty.as_numeric_scalar().expect("Host-shareable type is numeric")
+      ),
+    }
+  }
+}
+
+/**
+ * The host name of a struct, joining the parts of its path.
+ */
+fn host_name(path: &NamePathModel) -> String {
+  path.path.iter()
+    .map(|part| part.name.as_str())
+    .collect::<Vec<_>>()
+    .join("_")
+}
+
+/**
+ * Convert a `snake_case` name to `PascalCase`.
+ */
+pub(crate) fn pascal_case(name: &str) -> String {
+  name.split('_')
+    .filter(|part| !part.is_empty())
+    .map(|part| {
+      let mut chars = part.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+        None => String::new(),
+      }
+    })
+    .collect()
+}
+
+/**
+ * Convert a `PascalCase` or `snake_case` name to `SCREAMING_SNAKE_CASE`.
+ */
+pub(crate) fn screaming_snake_case(name: &str) -> String {
+  let mut result = String::new();
+  let mut prev: Option<char> = None;
+  for ch in name.chars() {
+    if ch.is_uppercase() && prev.is_some_and(|prev| prev.is_lowercase() || prev.is_ascii_digit()) {
+      result.push('_');
+    }
+    result.extend(ch.to_uppercase());
+    prev = Some(ch);
+  }
+  result
+}
+
+/**
+ * A name as a Rust identifier, raw if it is a keyword.  Keywords that
+ * can't be raw identifiers, and the names in `reserved`, get a `_`
+ * suffix instead.
+ */
+pub(crate) fn rust_ident(name: &str, reserved: &[&str]) -> String {
+  if RUST_NON_RAW_KEYWORDS.contains(&name) || reserved.contains(&name) {
+    format!("{}_", name)
+  } else if RUST_KEYWORDS.contains(&name) {
+    format!("r#{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+  "as", "async", "await", "box", "break", "const", "continue", "do", "dyn",
+  "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+  "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+  "override", "priv", "pub", "ref", "return", "static", "struct", "trait",
+  "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+  "where", "while", "yield",
+];
+
+const RUST_NON_RAW_KEYWORDS: &[&str] = &["_", "crate", "self", "Self", "super"];
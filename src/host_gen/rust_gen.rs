@@ -0,0 +1,159 @@
+use std::fmt::Write;
+use crate::{
+  host_gen::{ HostStruct, HostType, HostTypes, pascal_case, rust_ident },
+  model::{ ScalarNumericTypeModel, ShaderFileModel },
+};
+
+/**
+ * Generate Rust source declaring `#[repr(C)]` host types for the structs,
+ * uniforms and buffer elements of a shader file.  Padding is explicit,
+ * and compile-time assertions check that the sizes and field offsets
+ * match the shader's layout.
+ *
+ * `f16` values are held as their `u16` bit patterns.
+ */
+pub fn generate_rust(model: &ShaderFileModel) -> String {
+  let host_types = HostTypes::of(model);
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path())
+    .unwrap();
+  if uses_padded(&host_types) {
+    out.push_str(PADDED_DECL);
+  }
+  for host_struct in &host_types.structs {
+    out.push('\n');
+    write_struct(&mut out, host_struct);
+  }
+  for element in &host_types.buffer_elements {
+    let name = format!("{}Element", pascal_case(&element.buffer));
+    writeln!(out).unwrap();
+    writeln!(out, "/** The element type of buffer `{}`. */", element.buffer).unwrap();
+    writeln!(out, "pub type {} = {};", name, padded_type(
+      &element.elem, element.elem_size, element.stride
+    )).unwrap();
+    writeln!(out,
+      "const _: () = assert!(std::mem::size_of::<{}>() == {});",
+      name, element.stride
+    ).unwrap();
+  }
+  out
+}
+
+/**
+ * A value padded with trailing bytes, for array elements whose stride
+ * is larger than their size.
+ */
+const PADDED_DECL: &str = "
+/** A value followed by `PAD` bytes of padding. */
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Padded<T: Copy, const PAD: usize> {
+  pub value: T,
+  pub _pad: [u8; PAD],
+}
+";
+
+fn write_struct(out: &mut String, host_struct: &HostStruct) {
+  let name = &host_struct.name;
+  if host_struct.packed {
+    writeln!(out, "#[repr(C, packed)]").unwrap();
+  } else {
+    writeln!(out, "#[repr(C, align({}))]", host_struct.align).unwrap();
+  }
+  writeln!(out, "#[derive(Clone, Copy, Debug, PartialEq)]").unwrap();
+  writeln!(out, "pub struct {} {{", name).unwrap();
+  let mut pad_count = 0;
+  for field in &host_struct.fields {
+    writeln!(out, "  pub {}: {},", rust_ident(&field.name, &[]), rust_type(&field.ty))
+      .unwrap();
+    if field.padding > 0 {
+      writeln!(out, "  pub _pad{}: [u8; {}],", pad_count, field.padding).unwrap();
+      pad_count += 1;
+    }
+  }
+  writeln!(out, "}}").unwrap();
+
+  if let Some(tail) = &host_struct.tail {
+    let const_name = tail.name.to_uppercase();
+    writeln!(out, "impl {} {{", name).unwrap();
+    writeln!(out,
+      "  /** The offset of the runtime-sized `{}` array, which follows these fields. */",
+      tail.name
+    ).unwrap();
+    writeln!(out, "  pub const {}_OFFSET: usize = {};", const_name, tail.offset).unwrap();
+    writeln!(out, "  pub const {}_STRIDE: usize = {};", const_name, tail.stride).unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "pub type {}{} = {};",
+      name,
+      pascal_case(&tail.name),
+      padded_type(&tail.elem, tail.elem_size, tail.stride)
+    ).unwrap();
+  }
+
+  writeln!(out, "const _: () = {{").unwrap();
+  // The fields before a runtime-sized array may be followed by less
+  // padding than Rust adds, so only the offsets are checked.
+  if host_struct.tail.is_none() {
+    writeln!(out,
+      "  assert!(std::mem::size_of::<{}>() == {});", name, host_struct.size
+    ).unwrap();
+  }
+  for field in &host_struct.fields {
+    writeln!(out,
+      "  assert!(std::mem::offset_of!({}, {}) == {});",
+      name, rust_ident(&field.name, &[]), field.offset
+    ).unwrap();
+  }
+  writeln!(out, "}};").unwrap();
+}
+
+fn rust_type(ty: &HostType) -> String {
+  match ty {
+    HostType::Scalar(scalar) => scalar_type(*scalar).to_string(),
+    HostType::Vector(scalar, len) => format!("[{}; {}]", scalar_type(*scalar), len),
+    HostType::Array { elem, elem_size, stride, len } =>
+      format!("[{}; {}]", padded_type(elem, *elem_size, *stride), len),
+    HostType::Struct(name) => name.clone(),
+  }
+}
+
+/**
+ * The type of an array element, wrapped in `Padded` if the stride is
+ * larger than the element.
+ */
+fn padded_type(elem: &HostType, elem_size: u32, stride: u32) -> String {
+  if stride > elem_size {
+    format!("Padded<{}, {}>", rust_type(elem), stride - elem_size)
+  } else {
+    rust_type(elem)
+  }
+}
+
+fn uses_padded(host_types: &HostTypes) -> bool {
+  fn type_uses_padded(ty: &HostType) -> bool {
+    match ty {
+      HostType::Array { elem, elem_size, stride, .. } =>
+        stride > elem_size || type_uses_padded(elem),
+      _ => false,
+    }
+  }
+  host_types.structs.iter().any(|host_struct| {
+    host_struct.fields.iter().any(|field| type_uses_padded(&field.ty)) ||
+      host_struct.tail.as_ref().is_some_and(|tail| {
+        tail.stride > tail.elem_size || type_uses_padded(&tail.elem)
+      })
+  }) || host_types.buffer_elements.iter().any(|element| {
+    element.stride > element.elem_size || type_uses_padded(&element.elem)
+  })
+}
+
+fn scalar_type(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::I32 => "i32",
+    ScalarNumericTypeModel::U32 => "u32",
+    ScalarNumericTypeModel::F32 => "f32",
+    ScalarNumericTypeModel::F16 => "u16",
+    ScalarNumericTypeModel::I64 => "i64",
+    ScalarNumericTypeModel::U64 => "u64",
+  }
+}
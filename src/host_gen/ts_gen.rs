@@ -0,0 +1,216 @@
+use std::fmt::Write;
+use crate::{
+  host_gen::{ HostStruct, HostType, HostTypes, pascal_case, screaming_snake_case },
+  model::{ ScalarNumericTypeModel, ShaderFileModel },
+};
+
+/**
+ * Generate TypeScript source declaring an interface for each struct and
+ * the uniforms of a shader file, with functions to pack and unpack them
+ * at an offset in a `DataView`.  Each buffer gets the same for its
+ * element type.  Values are little-endian.
+ *
+ * `i64` and `u64` values are `bigint`s, and `f16` values are held as
+ * their `u16` bit patterns.
+ */
+pub fn generate_typescript(model: &ShaderFileModel) -> String {
+  let host_types = HostTypes::of(model);
+  let mut out = String::new();
+  writeln!(out, "// Generated by dubgsl from `{}`.  Do not edit.", model.path())
+    .unwrap();
+  for host_struct in &host_types.structs {
+    out.push('\n');
+    write_struct(&mut out, host_struct);
+  }
+  for element in &host_types.buffer_elements {
+    out.push('\n');
+    writeln!(out, "/** The element type of buffer `{}`. */", element.buffer).unwrap();
+    write_element(
+      &mut out,
+      &format!("{}Element", pascal_case(&element.buffer)),
+      &element.elem,
+      element.stride,
+    );
+  }
+  out
+}
+
+fn write_struct(out: &mut String, host_struct: &HostStruct) {
+  let name = &host_struct.name;
+  let const_name = screaming_snake_case(name);
+  writeln!(out, "export interface {} {{", name).unwrap();
+  for field in &host_struct.fields {
+    writeln!(out, "  {}: {};", field.name, ts_type(&field.ty)).unwrap();
+  }
+  writeln!(out, "}}").unwrap();
+  writeln!(out, "export const {}_SIZE = {};", const_name, host_struct.size).unwrap();
+  writeln!(out, "export const {}_ALIGN = {};", const_name, host_struct.align).unwrap();
+
+  writeln!(out,
+    "export function unpack{}(view: DataView, offset: number): {} {{", name, name
+  ).unwrap();
+  writeln!(out, "  return {{").unwrap();
+  for field in &host_struct.fields {
+    let field_offset = offset_expr("offset", field.offset);
+    writeln!(out, "    {}: {},", field.name, unpack_expr(&field.ty, &field_offset, 0))
+      .unwrap();
+  }
+  writeln!(out, "  }};").unwrap();
+  writeln!(out, "}}").unwrap();
+
+  writeln!(out,
+    "export function pack{}(view: DataView, offset: number, value: {}): void {{",
+    name, name
+  ).unwrap();
+  for field in &host_struct.fields {
+    let field_offset = offset_expr("offset", field.offset);
+    let field_value = format!("value.{}", field.name);
+    write_pack(out, &field.ty, &field_offset, &field_value, 1);
+  }
+  writeln!(out, "}}").unwrap();
+
+  if let Some(tail) = &host_struct.tail {
+    let tail_name = format!("{}{}", name, pascal_case(&tail.name));
+    writeln!(out,
+      "/** The offset of the runtime-sized `{}` array of `{}`. */", tail.name, name
+    ).unwrap();
+    writeln!(out,
+      "export const {}_OFFSET = {};", screaming_snake_case(&tail_name), tail.offset
+    ).unwrap();
+    write_element(out, &tail_name, &tail.elem, tail.stride);
+  }
+}
+
+/**
+ * Write the type, stride and pack and unpack functions for the element
+ * of a runtime-sized array.
+ */
+fn write_element(out: &mut String, name: &str, elem: &HostType, stride: u32) {
+  writeln!(out, "export type {} = {};", name, ts_type(elem)).unwrap();
+  writeln!(out, "export const {}_STRIDE = {};", screaming_snake_case(name), stride)
+    .unwrap();
+  writeln!(out,
+    "export function unpack{}(view: DataView, offset: number): {} {{", name, name
+  ).unwrap();
+  writeln!(out, "  return {};", unpack_expr(elem, "offset", 0)).unwrap();
+  writeln!(out, "}}").unwrap();
+  writeln!(out,
+    "export function pack{}(view: DataView, offset: number, value: {}): void {{",
+    name, name
+  ).unwrap();
+  write_pack(out, elem, "offset", "value", 1);
+  writeln!(out, "}}").unwrap();
+}
+
+fn ts_type(ty: &HostType) -> String {
+  match ty {
+    HostType::Scalar(scalar) => scalar_type(*scalar).to_string(),
+    HostType::Vector(scalar, len) => {
+      let parts = vec![scalar_type(*scalar); *len as usize];
+      format!("[{}]", parts.join(", "))
+    },
+    HostType::Array { elem, .. } => format!("{}[]", ts_type(elem)),
+    HostType::Struct(name) => name.clone(),
+  }
+}
+
+/**
+ * An expression reading a value of the given type at `offset`.  Array
+ * indices are named by nesting `depth`.
+ */
+fn unpack_expr(ty: &HostType, offset: &str, depth: usize) -> String {
+  match ty {
+    HostType::Scalar(scalar) =>
+      format!("view.get{}({}, true)", accessor(*scalar), offset),
+    HostType::Vector(scalar, len) => {
+      let parts = (0 .. *len)
+        .map(|i| format!(
+          "view.get{}({}, true)",
+          accessor(*scalar),
+          offset_expr(offset, i * scalar.size())
+        ))
+        .collect::<Vec<_>>();
+      format!("[{}]", parts.join(", "))
+    },
+    HostType::Array { elem, stride, len, .. } => {
+      let index = format!("i{}", depth);
+      let elem_offset = format!("{} + {} * {}", offset, index, stride);
+      format!(
+        "Array.from({{ length: {} }}, (_, {}): {} => {})",
+        len, index, ts_type(elem), unpack_expr(elem, &elem_offset, depth + 1)
+      )
+    },
+    HostType::Struct(name) => format!("unpack{}(view, {})", name, offset),
+  }
+}
+
+/**
+ * Write statements storing `value`, of the given type, at `offset`.
+ */
+fn write_pack(out: &mut String, ty: &HostType, offset: &str, value: &str, depth: usize) {
+  let indent = "  ".repeat(depth);
+  match ty {
+    HostType::Scalar(scalar) => {
+      writeln!(out, "{}view.set{}({}, {}, true);",
+        indent, accessor(*scalar), offset, value
+      ).unwrap();
+    },
+    HostType::Vector(scalar, len) => {
+      for i in 0 .. *len {
+        writeln!(out, "{}view.set{}({}, {}[{}], true);",
+          indent,
+          accessor(*scalar),
+          offset_expr(offset, i * scalar.size()),
+          value,
+          i
+        ).unwrap();
+      }
+    },
+    HostType::Array { elem, stride, len, .. } => {
+      let index = format!("i{}", depth - 1);
+      writeln!(out, "{}for (let {} = 0; {} < {}; {}++) {{",
+        indent, index, index, len, index
+      ).unwrap();
+      write_pack(
+        out,
+        elem,
+        &format!("{} + {} * {}", offset, index, stride),
+        &format!("{}[{}]", value, index),
+        depth + 1,
+      );
+      writeln!(out, "{}}}", indent).unwrap();
+    },
+    HostType::Struct(name) => {
+      writeln!(out, "{}pack{}(view, {}, {});", indent, name, offset, value).unwrap();
+    },
+  }
+}
+
+fn offset_expr(base: &str, offset: u32) -> String {
+  if offset == 0 {
+    base.to_string()
+  } else {
+    format!("{} + {}", base, offset)
+  }
+}
+
+fn scalar_type(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 => "bigint",
+    _ => "number",
+  }
+}
+
+/**
+ * The name of the `DataView` accessor for a scalar, after `get` or `set`.
+ */
+fn accessor(scalar: ScalarNumericTypeModel) -> &'static str {
+  match scalar {
+    ScalarNumericTypeModel::I32 => "Int32",
+    ScalarNumericTypeModel::U32 => "Uint32",
+    ScalarNumericTypeModel::F32 => "Float32",
+    ScalarNumericTypeModel::F16 => "Uint16",
+    ScalarNumericTypeModel::I64 => "BigInt64",
+    ScalarNumericTypeModel::U64 => "BigUint64",
+  }
+}
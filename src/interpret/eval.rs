@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use crate::{
+  backend::flatten_swizzle,
+  data::{ Value, read_at, write_at },
+  interpret::InterpretErrorKind,
+  model::{
+    BinaryExprModel,
+    BinaryOpModel,
+    BufferModel,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    LiteralModel,
+    StatementModel,
+    TestModel,
+    TypeLayout,
+    TypeLayoutKind,
+    TypeModel,
+    const_eval as scalar,
+  },
+};
+
+/**
+ * The state of a dispatch or test: the bound resources, and the locals
+ * of the running function.  Using a resource that is not bound is an
+ * error, which a dispatch rules out before it starts.
+ */
+pub(super) struct Executor<'a> {
+  pub(super) funcs: HashMap<&'a str, &'a FuncModel>,
+  // Only the buffers that are bound.
+  pub(super) buffers: Vec<BoundBuffer<'a>>,
+  pub(super) uniforms: Option<&'a Value>,
+  pub(super) locals: Vec<(&'a str, Value)>,
+}
+
+pub(super) struct BoundBuffer<'a> {
+  pub(super) model: &'a BufferModel,
+  pub(super) layout: &'a TypeLayout,
+  pub(super) bytes: &'a mut [u8],
+}
+
+/**
+ * How control leaves a block.
+ */
+enum Flow {
+  Next,
+  Return(Option<Value>),
+}
+
+/**
+ * A place that can be read and mutated.
+ */
+enum Place<'a> {
+  /**
+   * A part of a local or the uniforms, by the positions of the nested
+   * struct members and array elements.
+   */
+  Value { root: Root, path: Vec<usize> },
+
+  /** The bytes of a buffer holding a value of the given type. */
+  Bytes { buffer: usize, ty: &'a TypeModel, layout: &'a TypeLayout, offset: usize },
+}
+
+#[derive(Clone, Copy)]
+enum Root {
+  Local(usize),
+  Uniforms,
+}
+
+type Result<T> = std::result::Result<T, InterpretErrorKind>;
+
+impl<'a> Executor<'a> {
+  /**
+   * Run an entrypoint for one invocation.
+   */
+  pub(super) fn run(&mut self, entrypoint: &'a EntrypointModel, id: Value) -> Result<()> {
+    self.locals = vec![(entrypoint.arg_name.name.as_str(), id)];
+    self.block(&entrypoint.body)?;
+    Ok(())
+  }
+
+  /**
+   * Run the body of a test.
+   */
+  pub(super) fn test(&mut self, test: &'a TestModel) -> Result<()> {
+    self.locals = Vec::new();
+    self.block(&test.body)?;
+    Ok(())
+  }
+
+  fn block(&mut self, block: &'a [StatementModel]) -> Result<Flow> {
+    let scope = self.locals.len();
+    for stmt in block {
+      if let Flow::Return(value) = self.stmt(stmt)? {
+        return Ok(Flow::Return(value));
+      }
+    }
+    self.locals.truncate(scope);
+    Ok(Flow::Next)
+  }
+
+  fn stmt(&mut self, stmt: &'a StatementModel) -> Result<Flow> {
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let value = self.expr(&let_stmt.value)?;
+        self.locals.push((&let_stmt.name.name, value));
+      },
+      StatementModel::Var(var_stmt) => {
+        let value = self.expr(&var_stmt.value)?;
+        self.locals.push((&var_stmt.name.name, value));
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        let (place_expr, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        let place = self.place(place_expr)?;
+        let value = self.expr(&mutate_stmt.value)?;
+        if components.is_empty() {
+          self.write(&place, &value);
+        } else {
+          let mut vector = self.read(&place);
+          let Value::Vector(old) = &mut vector else {
+            unreachable!("Swizzle of a non-vector");
+          };
+          match value {
+            Value::Scalar(literal) => old[components[0] as usize] = literal,
+            Value::Vector(new) => for (&component, literal) in components.iter().zip(new) {
+              old[component as usize] = literal;
+            },
+            _ => unreachable!("Swizzle of a non-vector"),
+          }
+          self.write(&place, &vector);
+        }
+      },
+      StatementModel::Exec(exec_stmt) => match &exec_stmt.expr.kind {
+        ExpressionModelKind::Call(call_expr) => {
+          let args = self.exprs(&call_expr.args)?;
+          self.call(&call_expr.func.name, args)?;
+        },
+        _ => {
+          self.expr(&exec_stmt.expr)?;
+        },
+      },
+      StatementModel::Ret(ret_stmt) => {
+        let value = match &ret_stmt.value {
+          Some(value) => Some(self.expr(value)?),
+          None => None,
+        };
+        return Ok(Flow::Return(value));
+      },
+      StatementModel::If(if_stmt) => {
+        if self.bool(&if_stmt.cond)? {
+          return self.block(&if_stmt.if_block);
+        } else if let Some(else_block) = &if_stmt.else_block {
+          return self.block(else_block);
+        }
+      },
+      StatementModel::Loop(loop_stmt) => loop {
+        if let Flow::Return(value) = self.block(&loop_stmt.block)? {
+          return Ok(Flow::Return(value));
+        }
+      },
+      StatementModel::Assert(assert_stmt) => {
+        if !self.bool(&assert_stmt.cond)? {
+          return Err(InterpretErrorKind::AssertionFailed { span: assert_stmt.span.clone() });
+        }
+      },
+    }
+    Ok(Flow::Next)
+  }
+
+  fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Option<Value>> {
+    let func = self.funcs[name];
+    let locals = func.args.iter()
+      .map(|arg| arg.name.name.as_str())
+      .zip(args)
+      .collect();
+    let caller = std::mem::replace(&mut self.locals, locals);
+    let flow = self.block(&func.body);
+    self.locals = caller;
+    match flow? {
+      Flow::Return(value) => Ok(value),
+      Flow::Next => Ok(None),
+    }
+  }
+
+  fn exprs(&mut self, exprs: &'a [ExpressionModel]) -> Result<Vec<Value>> {
+    exprs.iter().map(|expr| self.expr(expr)).collect()
+  }
+
+  fn bool(&mut self, expr: &'a ExpressionModel) -> Result<bool> {
+    match self.expr(expr)? {
+      Value::Scalar(LiteralModel::Bool(value)) => Ok(value),
+      _ => unreachable!("Condition is not a bool"),
+    }
+  }
+
+  fn expr(&mut self, expr: &'a ExpressionModel) -> Result<Value> {
+    if is_place(expr) {
+      let place = self.place(expr)?;
+      return Ok(self.read(&place));
+    }
+    let value = match &expr.kind {
+      ExpressionModelKind::Literal(literal) => Value::Scalar(*literal),
+      ExpressionModelKind::Field(field_expr) => match self.expr(&field_expr.target)? {
+        Value::Struct(mut members) => members.swap_remove(field_expr.field as usize).1,
+        _ => unreachable!("Field of a non-struct"),
+      },
+      ExpressionModelKind::Index(index_expr) => {
+        let Value::Array(mut elems) = self.expr(&index_expr.target)? else {
+          unreachable!("Index of a non-array");
+        };
+        let index = self.index(&index_expr.index, elems.len())?;
+        elems.swap_remove(index)
+      },
+      ExpressionModelKind::ArrayLength(length_expr) => {
+        let Place::Bytes { buffer, layout, offset, .. } = self.place(&length_expr.target)? else {
+          unreachable!("Length of a sized array");
+        };
+        let len = runtime_len(layout, self.buffers[buffer].bytes.len() - offset);
+        Value::Scalar(LiteralModel::U32(len as u32))
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => {
+        let Value::Vector(components) = self.expr(&swizzle_expr.target)? else {
+          unreachable!("Swizzle of a non-vector");
+        };
+        match &swizzle_expr.components[..] {
+          [component] => Value::Scalar(components[*component as usize]),
+          selected => Value::Vector(
+            selected.iter().map(|&component| components[component as usize]).collect()
+          ),
+        }
+      },
+      ExpressionModelKind::Construct(construct_expr) => {
+        let args = self.exprs(&construct_expr.args)?;
+        match &*expr.ty {
+          TypeModel::Struct(struct_ty) => Value::Struct(
+            struct_ty.fields.iter()
+              .map(|field| field.name.name.clone())
+              .zip(args)
+              .collect()
+          ),
+          TypeModel::Vector(_) => Value::Vector(
+            args.into_iter()
+              .flat_map(|arg| match arg {
+                Value::Scalar(literal) => vec![literal],
+                Value::Vector(components) => components,
+                _ => unreachable!("Vector constructed from a non-vector"),
+              })
+              .collect()
+          ),
+          _ => Value::Array(args),
+        }
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        let args = self.exprs(&call_expr.args)?;
+        let name = &call_expr.func.name;
+        self.call(name, args)?
+          .ok_or_else(|| InterpretErrorKind::MissingReturn(name.to_string()))?
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        let operand = self.expr(&unary_expr.subexpr)?;
+        map(operand, |literal| scalar::unary(unary_expr.op, literal))
+      },
+      ExpressionModelKind::Cast(cast_expr) => {
+        let to = expr.ty.numeric_element().expect("Cast to a numeric type");
+        let operand = self.expr(&cast_expr.subexpr)?;
+        map(operand, |literal| scalar::cast(literal, to))
+      },
+      ExpressionModelKind::Binary(binary_expr) => self.binary(binary_expr)?,
+      ExpressionModelKind::Local(_) |
+      ExpressionModelKind::Uniforms |
+      ExpressionModelKind::Buffer(_) => unreachable!("Place is read above"),
+    };
+    Ok(value)
+  }
+
+  fn binary(&mut self, binary_expr: &'a BinaryExprModel) -> Result<Value> {
+    let op = binary_expr.op;
+    match op {
+      BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr => {
+        // The right operand is only evaluated if it decides the result.
+        let lhs = self.bool(&binary_expr.lhs)?;
+        let result = if lhs == (op == BinaryOpModel::LogicalOr) {
+          lhs
+        } else {
+          self.bool(&binary_expr.rhs)?
+        };
+        return Ok(Value::Scalar(LiteralModel::Bool(result)));
+      },
+      _ => {},
+    }
+
+    let lhs = self.expr(&binary_expr.lhs)?;
+    let rhs = self.expr(&binary_expr.rhs)?;
+    let is_matrix = |expr: &ExpressionModel| matches!(&*expr.ty, TypeModel::Matrix(_));
+    match (is_matrix(&binary_expr.lhs), is_matrix(&binary_expr.rhs)) {
+      (false, false) => componentwise(op, lhs, rhs),
+      (true, true) if op != BinaryOpModel::Mul => {
+        let (Value::Array(a), Value::Array(b)) = (lhs, rhs) else {
+          unreachable!("Matrix is not an array of columns");
+        };
+        let columns = a.into_iter().zip(b)
+          .map(|(a, b)| componentwise(op, a, b))
+          .collect::<Result<_>>()?;
+        Ok(Value::Array(columns))
+      },
+      // The product of matrices is the left matrix times each column
+      // of the right.
+      (true, true) => {
+        let Value::Array(columns) = rhs else {
+          unreachable!("Matrix is not an array of columns");
+        };
+        let columns = columns.into_iter()
+          .map(|column| matrix_times_vector(&lhs, column))
+          .collect::<Result<_>>()?;
+        Ok(Value::Array(columns))
+      },
+      (true, false) => match rhs {
+        Value::Scalar(_) => scale(lhs, rhs),
+        _ => matrix_times_vector(&lhs, rhs),
+      },
+      (false, true) => match lhs {
+        Value::Scalar(_) => scale(rhs, lhs),
+        _ => vector_times_matrix(lhs, rhs),
+      },
+    }
+  }
+
+  /**
+   * Evaluate an index into an array of the given length.
+   */
+  fn index(&mut self, expr: &'a ExpressionModel, len: usize) -> Result<usize> {
+    let Value::Scalar(literal) = self.expr(expr)? else {
+      unreachable!("Index is not a scalar");
+    };
+    let index = scalar::int_value(literal);
+    if index < 0 || index >= len as i128 {
+      return Err(InterpretErrorKind::IndexOutOfBounds { index, len });
+    }
+    Ok(index as usize)
+  }
+
+  /**
+   * Resolve a place expression, evaluating its indices.
+   */
+  fn place(&mut self, expr: &'a ExpressionModel) -> Result<Place<'a>> {
+    let place = match &expr.kind {
+      ExpressionModelKind::Local(name) => {
+        let local = self.locals.iter()
+          .rposition(|(local, _)| *local == name.name)
+          .expect("Local is bound");
+        Place::Value { root: Root::Local(local), path: Vec::new() }
+      },
+      ExpressionModelKind::Uniforms => {
+        if self.uniforms.is_none() {
+          return Err(InterpretErrorKind::UnboundUniforms);
+        }
+        Place::Value { root: Root::Uniforms, path: Vec::new() }
+      },
+      ExpressionModelKind::Buffer(name) => {
+        let Some(buffer) = self.buffers.iter()
+          .position(|buffer| buffer.model.name.name == name.name) else
+        {
+          return Err(InterpretErrorKind::UnboundBuffer(name.name.to_string()));
+        };
+        let bound = &self.buffers[buffer];
+        Place::Bytes { buffer, ty: &bound.model.ty, layout: bound.layout, offset: 0 }
+      },
+      ExpressionModelKind::Field(field_expr) => {
+        let field = field_expr.field as usize;
+        match self.place(&field_expr.target)? {
+          Place::Value { root, mut path } => {
+            path.push(field);
+            Place::Value { root, path }
+          },
+          Place::Bytes { buffer, ty, layout, offset } => {
+            let (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(struct_layout)) =
+              (ty, &layout.kind) else
+            {
+              unreachable!("Field of a non-struct");
+            };
+            let field_layout = &struct_layout.fields[field];
+            Place::Bytes {
+              buffer,
+              ty: &struct_ty.fields[field].ty,
+              layout: &field_layout.layout,
+              offset: offset + field_layout.offset as usize,
+            }
+          },
+        }
+      },
+      ExpressionModelKind::Index(index_expr) => match self.place(&index_expr.target)? {
+        Place::Value { root, mut path } => {
+          let Value::Array(elems) = self.value_at(root, &path) else {
+            unreachable!("Index of a non-array");
+          };
+          let len = elems.len();
+          path.push(self.index(&index_expr.index, len)?);
+          Place::Value { root, path }
+        },
+        Place::Bytes { buffer, ty, layout, offset } => {
+          let (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) =
+            (ty, &layout.kind) else
+          {
+            unreachable!("Index of a non-array");
+          };
+          let len = match array_layout.len {
+            Some(len) => len as usize,
+            None => runtime_len(layout, self.buffers[buffer].bytes.len() - offset),
+          };
+          let index = self.index(&index_expr.index, len)?;
+          Place::Bytes {
+            buffer,
+            ty: &array_ty.elem,
+            layout: &array_layout.elem,
+            offset: offset + index * array_layout.stride as usize,
+          }
+        },
+      },
+      _ => unreachable!("Expression is not a place"),
+    };
+    Ok(place)
+  }
+
+  fn value_at(&self, root: Root, path: &[usize]) -> &Value {
+    let mut value = match root {
+      Root::Local(local) => &self.locals[local].1,
+      Root::Uniforms => self.uniforms.expect("Uniforms are bound"),
+    };
+    for &position in path {
+      value = match value {
+        Value::Array(elems) => &elems[position],
+        Value::Struct(members) => &members[position].1,
+        _ => unreachable!("Path into a non-aggregate"),
+      };
+    }
+    value
+  }
+
+  fn read(&self, place: &Place<'a>) -> Value {
+    match *place {
+      Place::Value { root, ref path } => self.value_at(root, path).clone(),
+      Place::Bytes { buffer, ty, layout, offset } =>
+        read_at(ty, layout, self.buffers[buffer].bytes, offset),
+    }
+  }
+
+  fn write(&mut self, place: &Place<'a>, new: &Value) {
+    match *place {
+      Place::Value { root: Root::Local(local), ref path } => {
+        let mut value = &mut self.locals[local].1;
+        for &position in path {
+          value = match value {
+            Value::Array(elems) => &mut elems[position],
+            Value::Struct(members) => &mut members[position].1,
+            _ => unreachable!("Path into a non-aggregate"),
+          };
+        }
+        *value = new.clone();
+      },
+      Place::Value { root: Root::Uniforms, .. } => unreachable!("Uniforms are immutable"),
+      Place::Bytes { buffer, ty, layout, offset } => {
+        write_at(ty, layout, self.buffers[buffer].bytes, offset, new)
+          .expect("Checked value matches its type");
+      },
+    }
+  }
+}
+
+/**
+ * Whether an expression is a place, which is read without evaluating
+ * the whole of its local, uniforms or buffer.
+ */
+fn is_place(expr: &ExpressionModel) -> bool {
+  match &expr.kind {
+    ExpressionModelKind::Local(_) |
+    ExpressionModelKind::Uniforms |
+    ExpressionModelKind::Buffer(_) => true,
+    ExpressionModelKind::Field(field_expr) => is_place(&field_expr.target),
+    ExpressionModelKind::Index(index_expr) => is_place(&index_expr.target),
+    _ => false,
+  }
+}
+
+/**
+ * The element count of a runtime-sized array, or struct ending in one,
+ * that takes up the given number of bytes.
+ */
+fn runtime_len(layout: &TypeLayout, size: usize) -> usize {
+  match &layout.kind {
+    TypeLayoutKind::Array(array_layout) => size / array_layout.stride as usize,
+    TypeLayoutKind::Struct(struct_layout) => {
+      let last = struct_layout.fields.last().expect("Runtime-sized struct has fields");
+      runtime_len(&last.layout, size - last.offset as usize)
+    },
+    _ => unreachable!("Layout is not runtime-sized"),
+  }
+}
+
+fn map(value: Value, f: impl Fn(LiteralModel) -> LiteralModel) -> Value {
+  match value {
+    Value::Scalar(literal) => Value::Scalar(f(literal)),
+    Value::Vector(components) => Value::Vector(components.into_iter().map(f).collect()),
+    _ => unreachable!("Operand is not a scalar or vector"),
+  }
+}
+
+/**
+ * Apply an operation to scalars or to the components of vectors.  A
+ * scalar operand with a vector is applied to each of its components.
+ */
+fn componentwise(op: BinaryOpModel, lhs: Value, rhs: Value) -> Result<Value> {
+  let value = match (lhs, rhs) {
+    (Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(binary(op, a, b)?),
+    (Value::Vector(a), Value::Vector(b)) => Value::Vector(
+      a.into_iter().zip(b)
+        .map(|(a, b)| binary(op, a, b))
+        .collect::<Result<_>>()?
+    ),
+    (Value::Vector(a), Value::Scalar(b)) => Value::Vector(
+      a.into_iter()
+        .map(|a| binary(op, a, b))
+        .collect::<Result<_>>()?
+    ),
+    (Value::Scalar(a), Value::Vector(b)) => Value::Vector(
+      b.into_iter()
+        .map(|b| binary(op, a, b))
+        .collect::<Result<_>>()?
+    ),
+    _ => unreachable!("Operands are not scalars or vectors"),
+  };
+  Ok(value)
+}
+
+fn binary(op: BinaryOpModel, lhs: LiteralModel, rhs: LiteralModel) -> Result<LiteralModel> {
+  Ok(scalar::binary(op, lhs, rhs)?)
+}
+
+fn scale(matrix: Value, factor: Value) -> Result<Value> {
+  let Value::Array(columns) = matrix else {
+    unreachable!("Matrix is not an array of columns");
+  };
+  let columns = columns.into_iter()
+    .map(|column| componentwise(BinaryOpModel::Mul, column, factor.clone()))
+    .collect::<Result<_>>()?;
+  Ok(Value::Array(columns))
+}
+
+/**
+ * The sum of the matrix's columns, each scaled by the vector's
+ * corresponding component.
+ */
+fn matrix_times_vector(matrix: &Value, vector: Value) -> Result<Value> {
+  let (Value::Array(columns), Value::Vector(components)) = (matrix, vector) else {
+    unreachable!("Operands are not a matrix and a vector");
+  };
+  let mut sum: Option<Value> = None;
+  for (column, component) in columns.iter().zip(components) {
+    let term = componentwise(BinaryOpModel::Mul, column.clone(), Value::Scalar(component))?;
+    sum = Some(match sum {
+      Some(sum) => componentwise(BinaryOpModel::Add, sum, term)?,
+      None => term,
+    });
+  }
+  Ok(sum.expect("Matrix has columns"))
+}
+
+/**
+ * The dot products of the vector with each of the matrix's columns.
+ */
+fn vector_times_matrix(vector: Value, matrix: Value) -> Result<Value> {
+  let (Value::Vector(components), Value::Array(columns)) = (vector, matrix) else {
+    unreachable!("Operands are not a vector and a matrix");
+  };
+  let dots = columns.into_iter()
+    .map(|column| {
+      let Value::Vector(column) = column else {
+        unreachable!("Matrix column is not a vector");
+      };
+      let mut dot: Option<LiteralModel> = None;
+      for (&a, b) in components.iter().zip(column) {
+        let term = binary(BinaryOpModel::Mul, a, b)?;
+        dot = Some(match dot {
+          Some(dot) => binary(BinaryOpModel::Add, dot, term)?,
+          None => term,
+        });
+      }
+      Ok(dot.expect("Vector has components"))
+    })
+    .collect::<Result<_>>()?;
+  Ok(Value::Vector(dots))
+}
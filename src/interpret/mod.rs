@@ -0,0 +1,323 @@
+mod eval;
+
+use std::{ fmt, ops::Range };
+use crate::{
+  data::{ CodecError, Value, check_size, decode, encode },
+  interpret::eval::{ BoundBuffer, Executor },
+  model::{
+    EntrypointDims,
+    EvalError,
+    LayoutRules,
+    LiteralModel,
+    ShaderFileModel,
+    TypeLayout,
+  },
+};
+
+/**
+ * A reference interpreter, which runs the entrypoints of a checked
+ * shader file on the CPU.  Buffers are bound as bytes in their storage
+ * layout, and the uniforms as a value, as a host would bind them for
+ * a GPU.
+ *
+ * Invocations run one after another, so every result is one that a GPU
+ * could produce.  Arithmetic follows the language exactly: integers
+ * wrap, and `f16` values are rounded after every operation.  Where
+ * targets disagree, e.g. on integer division by zero, the interpreter
+ * stops with an error rather than pick one.
+ *
+ * The interpreter also runs the tests of a file, which may use any
+ * resources that are bound.
+ */
+pub struct Interpreter<'m> {
+  model: &'m ShaderFileModel,
+  layouts: Vec<TypeLayout>,
+  buffers: Vec<Option<Vec<u8>>>,
+  uniforms: Option<Value>,
+}
+impl<'m> Interpreter<'m> {
+  pub fn new(model: &'m ShaderFileModel) -> Interpreter<'m> {
+    Interpreter {
+      model,
+      layouts: model.buffers.iter()
+        .map(|buffer| buffer.layout().expect("Checked buffer has a layout"))
+        .collect(),
+      buffers: vec![None; model.buffers.len()],
+      uniforms: None,
+    }
+  }
+
+  /**
+   * Bind the bytes of a buffer, replacing any bound before.  A buffer
+   * whose storage type is runtime-sized holds as many elements as fit.
+   */
+  pub fn bind_buffer(&mut self, name: &str, bytes: Vec<u8>) -> Result<(), InterpretError> {
+    let Some(index) = self.model.buffers.iter().position(|buffer| buffer.name() == name) else {
+      return Err(InterpretErrorKind::UnknownBuffer(name.to_string()).into());
+    };
+    check_size(&self.layouts[index], bytes.len()).map_err(InterpretErrorKind::Codec)?;
+    self.buffers[index] = Some(bytes);
+    Ok(())
+  }
+
+  /**
+   * Bind the uniforms.  Values are rounded to their types as they would
+   * be when encoded, e.g. `f16` members to the nearest `f16`.
+   */
+  pub fn bind_uniforms(&mut self, value: &Value) -> Result<(), InterpretError> {
+    let Some(uniforms) = &self.model.uniforms else {
+      return Err(InterpretErrorKind::NoUniforms.into());
+    };
+    let bytes = encode(uniforms.ty(), LayoutRules::Uniform, value)
+      .map_err(InterpretErrorKind::Codec)?;
+    let value = decode(uniforms.ty(), LayoutRules::Uniform, &bytes)
+      .map_err(InterpretErrorKind::Codec)?;
+    self.uniforms = Some(value);
+    Ok(())
+  }
+
+  /**
+   * The bytes bound to a buffer, with any changes made by dispatches.
+   */
+  pub fn buffer(&self, name: &str) -> Option<&[u8]> {
+    let index = self.model.buffers.iter().position(|buffer| buffer.name() == name)?;
+    self.buffers[index].as_deref()
+  }
+
+  /**
+   * Run an entrypoint over a grid of workgroups, as a GPU dispatch
+   * does.  Every buffer, and the uniforms if declared, must be bound.
+   * Invocations run in order of their ids, with `x` varying fastest.
+   */
+  pub fn dispatch(&mut self, entrypoint: &str, workgroups: [u32; 3])
+    -> Result<(), InterpretError>
+  {
+    let model = self.model;
+    let Some(entrypoint) = model.entrypoints.iter().find(|e| e.name() == entrypoint) else {
+      return Err(InterpretErrorKind::UnknownEntrypoint(entrypoint.to_string()).into());
+    };
+    if model.uniforms.is_some() && self.uniforms.is_none() {
+      return Err(InterpretErrorKind::UnboundUniforms.into());
+    }
+    if let Some(index) = self.buffers.iter().position(Option::is_none) {
+      let name = model.buffers[index].name().to_string();
+      return Err(InterpretErrorKind::UnboundBuffer(name).into());
+    }
+    let workgroup_size = entrypoint.workgroup_size();
+    let counts = [0, 1, 2].map(|axis| workgroups[axis].checked_mul(workgroup_size[axis]));
+    let [Some(x_count), Some(y_count), Some(z_count)] = counts else {
+      return Err(InterpretErrorKind::DispatchTooLarge { workgroups, workgroup_size }.into());
+    };
+    let mut executor = self.executor();
+
+    for z in 0..z_count {
+      for y in 0..y_count {
+        for x in 0..x_count {
+          let id = match entrypoint.dims() {
+            EntrypointDims::D1 => Value::Scalar(LiteralModel::U32(x)),
+            EntrypointDims::D2 => Value::Vector(vec![
+              LiteralModel::U32(x),
+              LiteralModel::U32(y),
+            ]),
+            EntrypointDims::D3 => Value::Vector(vec![
+              LiteralModel::U32(x),
+              LiteralModel::U32(y),
+              LiteralModel::U32(z),
+            ]),
+          };
+          executor.run(entrypoint, id)
+            .map_err(|kind| InterpretError { invocation: Some([x, y, z]), kind })?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /**
+   * Run a test.  Changes it makes to bound buffers are kept.
+   */
+  pub fn run_test(&mut self, name: &str) -> Result<(), InterpretError> {
+    let model = self.model;
+    let Some(test) = model.tests.iter().find(|test| test.name() == name) else {
+      return Err(InterpretErrorKind::UnknownTest(name.to_string()).into());
+    };
+    self.executor().test(test)?;
+    Ok(())
+  }
+
+  /**
+   * Run every test in declaration order, reporting the result of each.
+   */
+  pub fn run_tests(&mut self) -> Vec<TestReport> {
+    let model = self.model;
+    model.tests.iter()
+      .map(|test| TestReport {
+        name: test.name().to_string(),
+        result: self.executor().test(test).map_err(InterpretError::from),
+      })
+      .collect()
+  }
+
+  fn executor(&mut self) -> Executor<'_> {
+    let model = self.model;
+    let buffers = model.buffers.iter()
+      .zip(&self.layouts)
+      .zip(&mut self.buffers)
+      .filter_map(|((buffer, layout), bytes)| {
+        let bytes = bytes.as_deref_mut()?;
+        Some(BoundBuffer { model: buffer, layout, bytes })
+      })
+      .collect();
+    Executor {
+      funcs: model.funcs.iter().map(|func| (func.name.name.as_str(), func)).collect(),
+      buffers,
+      uniforms: self.uniforms.as_ref(),
+      locals: Vec::new(),
+    }
+  }
+}
+
+/**
+ * The result of running a test.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+  pub(crate) name: String,
+  pub(crate) result: Result<(), InterpretError>,
+}
+impl TestReport {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn result(&self) -> &Result<(), InterpretError> {
+    &self.result
+  }
+
+  pub fn passed(&self) -> bool {
+    self.result.is_ok()
+  }
+
+  /**
+   * The span in the source of the assertion that failed, if the test
+   * failed on one rather than with another error.
+   */
+  pub fn failed_assertion(&self) -> Option<Range<usize>> {
+    match &self.result {
+      Err(InterpretError { kind: InterpretErrorKind::AssertionFailed { span }, .. }) =>
+        Some(span.clone()),
+      _ => None,
+    }
+  }
+}
+impl fmt::Display for TestReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.result {
+      Ok(()) => write!(f, "test {} ... ok", self.name),
+      Err(err) => write!(f, "test {} ... FAILED: {}", self.name, err),
+    }
+  }
+}
+
+/**
+ * An error binding resources or running an entrypoint.  Errors while
+ * running are reported with the id of the invocation in error.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpretError {
+  pub(crate) invocation: Option<[u32; 3]>,
+  pub(crate) kind: InterpretErrorKind,
+}
+impl InterpretError {
+  pub fn invocation(&self) -> Option<[u32; 3]> {
+    self.invocation
+  }
+
+  pub fn kind(&self) -> &InterpretErrorKind {
+    &self.kind
+  }
+}
+impl From<InterpretErrorKind> for InterpretError {
+  fn from(kind: InterpretErrorKind) -> InterpretError {
+    InterpretError { invocation: None, kind }
+  }
+}
+impl fmt::Display for InterpretError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.invocation {
+      Some([x, y, z]) => write!(f, "In invocation ({}, {}, {}): {}", x, y, z, self.kind),
+      None => write!(f, "{}", self.kind),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpretErrorKind {
+  UnknownEntrypoint(String),
+  UnknownTest(String),
+  UnknownBuffer(String),
+  UnboundBuffer(String),
+
+  /** Uniforms were bound for a shader file that declares none. */
+  NoUniforms,
+  UnboundUniforms,
+
+  /** The invocations of a dispatch along an axis don't fit in a `u32`. */
+  DispatchTooLarge { workgroups: [u32; 3], workgroup_size: [u32; 3] },
+
+  /** Bound bytes or uniforms do not match their type. */
+  Codec(CodecError),
+
+  IndexOutOfBounds { index: i128, len: usize },
+  DivisionByZero,
+
+  /** A function with a return type ended without returning a value. */
+  MissingReturn(String),
+
+  /** An integer of `bits` bits was shifted by at least its width. */
+  ShiftOutOfRange { amount: u32, bits: u32 },
+
+  /** An assertion in a test was false.  The span is its byte range in the source. */
+  AssertionFailed { span: Range<usize> },
+}
+impl From<EvalError> for InterpretErrorKind {
+  fn from(err: EvalError) -> InterpretErrorKind {
+    match err {
+      EvalError::DivisionByZero => InterpretErrorKind::DivisionByZero,
+      EvalError::ShiftOutOfRange { amount, bits } =>
+        InterpretErrorKind::ShiftOutOfRange { amount, bits },
+    }
+  }
+}
+impl fmt::Display for InterpretErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      InterpretErrorKind::UnknownEntrypoint(name) =>
+        write!(f, "No entrypoint named `{}`.", name),
+      InterpretErrorKind::UnknownTest(name) => write!(f, "No test named `{}`.", name),
+      InterpretErrorKind::UnknownBuffer(name) => write!(f, "No buffer named `{}`.", name),
+      InterpretErrorKind::UnboundBuffer(name) => write!(f, "Buffer `{}` is not bound.", name),
+      InterpretErrorKind::NoUniforms => write!(f, "The shader file declares no uniforms."),
+      InterpretErrorKind::UnboundUniforms => write!(f, "The uniforms are not bound."),
+      InterpretErrorKind::DispatchTooLarge {
+        workgroups: [x, y, z],
+        workgroup_size: [sx, sy, sz],
+      } =>
+        write!(f,
+          "A dispatch of {}x{}x{} workgroups of {}x{}x{} invocations is too large; the \
+           invocations along each axis must fit in a `u32`.",
+          x, y, z, sx, sy, sz
+        ),
+      InterpretErrorKind::Codec(err) => write!(f, "{}", err),
+      InterpretErrorKind::IndexOutOfBounds { index, len } =>
+        write!(f, "Index {} is out of bounds for an array of length {}.", index, len),
+      InterpretErrorKind::DivisionByZero => write!(f, "Integer division by zero."),
+      InterpretErrorKind::MissingReturn(name) =>
+        write!(f, "Function `{}` ended without returning a value.", name),
+      InterpretErrorKind::ShiftOutOfRange { amount, bits } =>
+        write!(f, "Cannot shift a {}-bit integer by {}.", bits, amount),
+      InterpretErrorKind::AssertionFailed { span } =>
+        write!(f, "Assertion failed at {}..{}.", span.start, span.end),
+    }
+  }
+}
@@ -0,0 +1,508 @@
+use crate::{
+  backend::flatten_swizzle,
+  ir::{
+    IrAccess,
+    IrBlock,
+    IrBlockId,
+    IrBuffer,
+    IrFunction,
+    IrFunctionKind,
+    IrInst,
+    IrLocal,
+    IrLocalId,
+    IrMerge,
+    IrModule,
+    IrOp,
+    IrParam,
+    IrPhi,
+    IrPlace,
+    IrPlaceRoot,
+    IrTerminator,
+    IrValue,
+  },
+  model::{
+    BinaryOpModel,
+    CallExprModel,
+    EntrypointModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FuncModel,
+    ScalarNumericTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    TypeModel,
+    TypeModelHandle,
+    VecDims,
+  },
+};
+
+/**
+ * Lower the functions and entrypoints of a checked shader file to IR.
+ * Tests run only on the interpreter, and are not lowered.
+ *
+ * `let` bindings become the values they are bound to, and each `var`
+ * a local that is stored to and loaded from.  Operands are evaluated
+ * left to right, and the right operand of `&&` or `||` only when it
+ * is needed, if it calls a function.
+ */
+pub fn lower_shader(model: &ShaderFileModel) -> IrModule {
+  let mut structs = Vec::new();
+  let uniforms = model.uniforms.as_ref().map(|uniforms| uniforms.ty.clone());
+  for ty in model.structs.iter().chain(&uniforms) {
+    add_struct(&mut structs, ty);
+  }
+  for buffer in &model.buffers {
+    add_struct(&mut structs, &buffer.ty);
+  }
+
+  let mut functions = Vec::new();
+  for func in &model.funcs {
+    functions.push(FunctionLowering::lower_func(func));
+  }
+  for entrypoint in &model.entrypoints {
+    functions.push(FunctionLowering::lower_entrypoint(entrypoint));
+  }
+  IrModule {
+    structs,
+    uniforms,
+    buffers: model.buffers.iter()
+      .map(|buffer| IrBuffer {
+        name: buffer.name.name.clone(),
+        mode: buffer.mode,
+        ty: buffer.ty.clone(),
+      })
+      .collect(),
+    functions,
+  }
+}
+
+/**
+ * Add the struct types within a type, each after those it contains.
+ */
+fn add_struct(structs: &mut Vec<TypeModelHandle>, ty: &TypeModelHandle) {
+  match &**ty {
+    TypeModel::Struct(struct_ty) => {
+      if structs.contains(ty) {
+        return;
+      }
+      for field in &struct_ty.fields {
+        add_struct(structs, &field.ty);
+      }
+      structs.push(ty.clone());
+    },
+    TypeModel::Array(array_ty) => add_struct(structs, &array_ty.elem),
+    _ => {},
+  }
+}
+
+/**
+ * How a name in scope is bound.
+ */
+#[derive(Debug, Clone, Copy)]
+enum Binding {
+  Value(IrValue),
+  Local(IrLocalId),
+}
+
+struct FunctionLowering {
+  function: IrFunction,
+  scope: Vec<(String, Binding)>,
+  next_value: u32,
+  current: IrBlockId,
+  terminated: bool,
+}
+
+impl FunctionLowering {
+  fn new(name: &str, kind: IrFunctionKind, return_ty: &TypeModelHandle) -> FunctionLowering {
+    FunctionLowering {
+      function: IrFunction {
+        name: name.to_string(),
+        kind,
+        params: Vec::new(),
+        return_ty: return_ty.clone(),
+        locals: Vec::new(),
+        blocks: vec![IrBlock::new()],
+      },
+      scope: Vec::new(),
+      next_value: 0,
+      current: IrBlockId(0),
+      terminated: false,
+    }
+  }
+
+  fn lower_func(func: &FuncModel) -> IrFunction {
+    let kind = IrFunctionKind::Func;
+    let mut lowering = FunctionLowering::new(&func.name.name, kind, &func.return_ty);
+    for arg in &func.args {
+      lowering.param(&arg.name.name, &arg.ty);
+    }
+    lowering.finish(&func.body)
+  }
+
+  fn lower_entrypoint(entrypoint: &EntrypointModel) -> IrFunction {
+    let void = TypeModelHandle::new(TypeModel::new_void());
+    let kind = IrFunctionKind::Entrypoint(entrypoint.dims);
+    let mut lowering = FunctionLowering::new(entrypoint.name(), kind, &void);
+    lowering.param(&entrypoint.arg_name.name, &invocation_id_ty(entrypoint));
+    lowering.finish(&entrypoint.body)
+  }
+
+  fn param(&mut self, name: &str, ty: &TypeModelHandle) {
+    let value = self.value();
+    self.function.params.push(IrParam { value, ty: ty.clone() });
+    self.scope.push((name.to_string(), Binding::Value(value)));
+  }
+
+  /**
+   * Lower the body, ending it with a return if control reaches its end
+   * in a function without a result.
+   */
+  fn finish(mut self, body: &[StatementModel]) -> IrFunction {
+    self.lower_block(body);
+    if !self.terminated {
+      let terminator = if self.function.return_ty.is_void() {
+        IrTerminator::Return(None)
+      } else {
+        IrTerminator::Unreachable
+      };
+      self.terminate(terminator);
+    }
+    self.function
+  }
+
+  fn value(&mut self) -> IrValue {
+    let value = IrValue(self.next_value);
+    self.next_value += 1;
+    value
+  }
+
+  fn block_mut(&mut self, id: IrBlockId) -> &mut IrBlock {
+    &mut self.function.blocks[id.0 as usize]
+  }
+
+  /**
+   * Start a new block, after the current one has been terminated.
+   */
+  fn new_block(&mut self) -> IrBlockId {
+    let id = IrBlockId(self.function.blocks.len() as u32);
+    self.function.blocks.push(IrBlock::new());
+    self.current = id;
+    self.terminated = false;
+    id
+  }
+
+  fn terminate(&mut self, terminator: IrTerminator) {
+    self.block_mut(self.current).terminator = terminator;
+    self.terminated = true;
+  }
+
+  /**
+   * Terminate the current block, if it falls through, with a branch.
+   * Returns the block if it did.
+   */
+  fn fall_through(&mut self, target: IrBlockId) -> Option<IrBlockId> {
+    if self.terminated {
+      return None;
+    }
+    self.terminate(IrTerminator::Branch(target));
+    Some(self.current)
+  }
+
+  fn inst(&mut self, ty: &TypeModelHandle, op: IrOp) -> IrValue {
+    let value = self.value();
+    self.block_mut(self.current).insts.push(IrInst { result: Some((value, ty.clone())), op });
+    value
+  }
+
+  fn inst_without_result(&mut self, op: IrOp) {
+    self.block_mut(self.current).insts.push(IrInst { result: None, op });
+  }
+
+  /**
+   * Lower a block of statements in its own scope.  Statements after
+   * the block ends, by returning, are unreachable and not lowered.
+   */
+  fn lower_block(&mut self, statements: &[StatementModel]) {
+    let scope = self.scope.len();
+    for stmt in statements {
+      if self.terminated {
+        break;
+      }
+      self.lower_stmt(stmt);
+    }
+    self.scope.truncate(scope);
+  }
+
+  fn lower_stmt(&mut self, stmt: &StatementModel) {
+    match stmt {
+      StatementModel::Let(let_stmt) => {
+        let value = self.expr(&let_stmt.value);
+        self.scope.push((let_stmt.name.name.clone(), Binding::Value(value)));
+      },
+      StatementModel::Var(var_stmt) => {
+        let value = self.expr(&var_stmt.value);
+        let local = IrLocalId(self.function.locals.len() as u32);
+        self.function.locals.push(IrLocal {
+          name: var_stmt.name.name.clone(),
+          ty: var_stmt.value.ty.clone(),
+        });
+        let place = IrPlace { root: IrPlaceRoot::Local(local), path: Vec::new() };
+        self.inst_without_result(IrOp::Store(place, value));
+        self.scope.push((var_stmt.name.name.clone(), Binding::Local(local)));
+      },
+      StatementModel::Mutate(mutate_stmt) => {
+        let (place_expr, components) = flatten_swizzle(&mutate_stmt.lvalue);
+        let mut place = self.place(place_expr).expect("Mutated place is in a local or buffer");
+        let value = self.expr(&mutate_stmt.value);
+        match components[..] {
+          [] => self.inst_without_result(IrOp::Store(place, value)),
+          [component] => {
+            place.path.push(IrAccess::Component(component));
+            self.inst_without_result(IrOp::Store(place, value));
+          },
+          // Several components are stored one at a time.
+          _ => {
+            let TypeModel::Vector(vector_ty) = &*mutate_stmt.value.ty else {
+              unreachable!("Swizzle of non-vector type");
+            };
+            let scalar_ty = TypeModelHandle::new(TypeModel::new_scalar(vector_ty.scalar));
+            for (i, &component) in components.iter().enumerate() {
+              let part = self.inst(&scalar_ty, IrOp::Swizzle(value, vec![i as u32]));
+              let mut component_place = place.clone();
+              component_place.path.push(IrAccess::Component(component));
+              self.inst_without_result(IrOp::Store(component_place, part));
+            }
+          },
+        }
+      },
+      StatementModel::Exec(exec_stmt) => match &exec_stmt.expr.kind {
+        ExpressionModelKind::Call(call_expr) => {
+          self.call(call_expr, &exec_stmt.expr.ty);
+        },
+        _ => {
+          self.expr(&exec_stmt.expr);
+        },
+      },
+      StatementModel::Ret(ret_stmt) => {
+        let value = ret_stmt.value.as_ref().map(|value| self.expr(value));
+        self.terminate(IrTerminator::Return(value));
+      },
+      StatementModel::If(if_stmt) => {
+        let cond = self.expr(&if_stmt.cond);
+        let header = self.current;
+
+        let if_true = self.new_block();
+        self.lower_block(&if_stmt.if_block);
+        let mut ends = vec![(self.current, self.terminated)];
+        let if_false = match &if_stmt.else_block {
+          Some(else_block) => {
+            let if_false = self.new_block();
+            self.lower_block(else_block);
+            ends.push((self.current, self.terminated));
+            Some(if_false)
+          },
+          None => None,
+        };
+
+        let merge = IrBlockId(self.function.blocks.len() as u32);
+        for &(end, terminated) in &ends {
+          if !terminated {
+            self.block_mut(end).terminator = IrTerminator::Branch(merge);
+          }
+        }
+        let header_block = self.block_mut(header);
+        header_block.merge = Some(IrMerge::Selection { merge });
+        header_block.terminator = IrTerminator::BranchIf {
+          cond,
+          if_true,
+          if_false: if_false.unwrap_or(merge),
+        };
+
+        // When both branches return, the merge block is unreachable.
+        self.new_block();
+        if if_false.is_some() && ends.iter().all(|&(_, terminated)| terminated) {
+          self.terminate(IrTerminator::Unreachable);
+        }
+      },
+      // Loops are left only by returning, so the merge block is
+      // unreachable.
+      StatementModel::Loop(loop_stmt) => {
+        let header = IrBlockId(self.function.blocks.len() as u32);
+        self.terminate(IrTerminator::Branch(header));
+        self.new_block();
+        let body = IrBlockId(header.0 + 1);
+        self.terminate(IrTerminator::Branch(body));
+
+        self.new_block();
+        self.lower_block(&loop_stmt.block);
+        let continue_block = IrBlockId(self.function.blocks.len() as u32);
+        self.fall_through(continue_block);
+        self.new_block();
+        self.terminate(IrTerminator::Branch(header));
+
+        let merge = self.new_block();
+        self.block_mut(header).merge = Some(IrMerge::Loop { merge, continue_block });
+        self.terminate(IrTerminator::Unreachable);
+      },
+      // Assertions are only allowed in tests, which are not lowered.
+      StatementModel::Assert(_) => unreachable!("Assertion outside a test"),
+    }
+  }
+
+  fn lookup(&self, name: &str) -> Binding {
+    self.scope.iter()
+      .rev()
+      .find(|(local, _)| local == name)
+      .map(|&(_, binding)| binding)
+      .expect("Checked local is in scope")
+  }
+
+  /**
+   * The place an expression refers to, if it is within a mutable local
+   * or a resource.  Indices are evaluated from the root of the place
+   * outward.
+   */
+  fn place(&mut self, expr: &ExpressionModel) -> Option<IrPlace> {
+    let root = match &expr.kind {
+      ExpressionModelKind::Local(name) => match self.lookup(&name.name) {
+        Binding::Local(local) => IrPlaceRoot::Local(local),
+        Binding::Value(_) => return None,
+      },
+      ExpressionModelKind::Uniforms => IrPlaceRoot::Uniforms,
+      ExpressionModelKind::Buffer(name) => IrPlaceRoot::Buffer(name.name.clone()),
+      ExpressionModelKind::Index(index_expr) => {
+        let mut place = self.place(&index_expr.target)?;
+        let index = self.expr(&index_expr.index);
+        place.path.push(IrAccess::Index(index));
+        return Some(place);
+      },
+      ExpressionModelKind::Field(field_expr) => {
+        let mut place = self.place(&field_expr.target)?;
+        place.path.push(IrAccess::Field(field_expr.field));
+        return Some(place);
+      },
+      _ => return None,
+    };
+    Some(IrPlace { root, path: Vec::new() })
+  }
+
+  fn expr(&mut self, expr: &ExpressionModel) -> IrValue {
+    let op = match &expr.kind {
+      ExpressionModelKind::Literal(literal) => IrOp::Const(*literal),
+      ExpressionModelKind::Local(name) => match self.lookup(&name.name) {
+        Binding::Value(value) => return value,
+        Binding::Local(local) =>
+          IrOp::Load(IrPlace { root: IrPlaceRoot::Local(local), path: Vec::new() }),
+      },
+      ExpressionModelKind::Uniforms |
+      ExpressionModelKind::Buffer(_) => {
+        IrOp::Load(self.place(expr).expect("Resource is a place"))
+      },
+      ExpressionModelKind::Index(index_expr) => match self.place(expr) {
+        Some(place) => IrOp::Load(place),
+        None => {
+          let target = self.expr(&index_expr.target);
+          let index = self.expr(&index_expr.index);
+          IrOp::Index(target, index)
+        },
+      },
+      ExpressionModelKind::ArrayLength(length_expr) => {
+        let place = self.place(&length_expr.target)
+          .expect("Runtime-sized array is in a buffer");
+        IrOp::ArrayLength(place)
+      },
+      ExpressionModelKind::Field(field_expr) => match self.place(expr) {
+        Some(place) => IrOp::Load(place),
+        None => IrOp::Field(self.expr(&field_expr.target), field_expr.field),
+      },
+      ExpressionModelKind::Swizzle(swizzle_expr) => {
+        let target = self.expr(&swizzle_expr.target);
+        IrOp::Swizzle(target, swizzle_expr.components.clone())
+      },
+      ExpressionModelKind::Construct(construct_expr) => {
+        IrOp::Construct(construct_expr.args.iter().map(|arg| self.expr(arg)).collect())
+      },
+      ExpressionModelKind::Call(call_expr) => {
+        return self.call(call_expr, &expr.ty).expect("Called function has a result");
+      },
+      ExpressionModelKind::Unary(unary_expr) => {
+        IrOp::Unary(unary_expr.op, self.expr(&unary_expr.subexpr))
+      },
+      ExpressionModelKind::Binary(binary_expr) => match binary_expr.op {
+        op @ (BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr)
+          if binary_expr.rhs.has_call() =>
+        {
+          return self.logical(op, &binary_expr.lhs, &binary_expr.rhs, &expr.ty);
+        },
+        op => {
+          let lhs = self.expr(&binary_expr.lhs);
+          let rhs = self.expr(&binary_expr.rhs);
+          IrOp::Binary(op, lhs, rhs)
+        },
+      },
+      ExpressionModelKind::Cast(cast_expr) => IrOp::Cast(self.expr(&cast_expr.subexpr)),
+    };
+    self.inst(&expr.ty, op)
+  }
+
+  /**
+   * A call, whose result is `None` for a function without one.
+   */
+  fn call(&mut self, call_expr: &CallExprModel, ty: &TypeModelHandle) -> Option<IrValue> {
+    let args = call_expr.args.iter().map(|arg| self.expr(arg)).collect();
+    let op = IrOp::Call(call_expr.func.name.clone(), args);
+    if ty.is_void() {
+      self.inst_without_result(op);
+      return None;
+    }
+    Some(self.inst(ty, op))
+  }
+
+  /**
+   * A logical operation whose right operand is evaluated in its own
+   * block, only when needed, with the result chosen by a phi.
+   */
+  fn logical(&mut self,
+    op: BinaryOpModel,
+    lhs: &ExpressionModel,
+    rhs: &ExpressionModel,
+    ty: &TypeModelHandle,
+  ) -> IrValue {
+    let lhs = self.expr(lhs);
+    let header = self.current;
+    let rhs_block = self.new_block();
+    let rhs = self.expr(rhs);
+    let merge = IrBlockId(self.function.blocks.len() as u32);
+    let rhs_end = self.fall_through(merge).expect("Expression falls through");
+
+    let (if_true, if_false) = match op {
+      BinaryOpModel::LogicalAnd => (rhs_block, merge),
+      _ => (merge, rhs_block),
+    };
+    let header_block = self.block_mut(header);
+    header_block.merge = Some(IrMerge::Selection { merge });
+    header_block.terminator = IrTerminator::BranchIf { cond: lhs, if_true, if_false };
+
+    self.new_block();
+    let result = self.value();
+    self.block_mut(merge).phis.push(IrPhi {
+      result,
+      ty: ty.clone(),
+      incoming: vec![(lhs, header), (rhs, rhs_end)],
+    });
+    result
+  }
+}
+
+/**
+ * The type of an entrypoint's invocation id, with a component for each
+ * of its dimensions.
+ */
+fn invocation_id_ty(entrypoint: &EntrypointModel) -> TypeModelHandle {
+  let u32_ty = ScalarNumericTypeModel::U32;
+  let ty = match VecDims::from_count(entrypoint.dims as usize) {
+    Some(dims) => TypeModel::new_vector(u32_ty, dims),
+    None => TypeModel::new_scalar(u32_ty),
+  };
+  TypeModelHandle::new(ty)
+}
@@ -0,0 +1,297 @@
+mod lower;
+mod parse;
+mod print;
+mod verify;
+
+pub use self::{
+  lower::lower_shader,
+  parse::parse_ir,
+  verify::verify_ir,
+};
+
+use std::fmt;
+use crate::model::{
+  BinaryOpModel,
+  BufferAccessMode,
+  EntrypointDims,
+  LiteralModel,
+  TypeModelHandle,
+  UnaryOpModel,
+};
+
+/**
+ * A shader file in static single assignment form, lowered from the
+ * checked model.  Each function is a graph of basic blocks,
+ * whose instructions define each value once.  Mutable locals and
+ * resources are reached only through explicit loads and stores.
+ *
+ * Control flow stays structured: a block that branches to several
+ * successors is marked with the block where they join, and a loop
+ * header with its merge and continue blocks, as SPIR-V requires.
+ *
+ * The textual form is written by `Display` and read by `parse_ir`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrModule {
+  /** The struct types used, each after the structs it contains. */
+  pub(crate) structs: Vec<TypeModelHandle>,
+  pub(crate) uniforms: Option<TypeModelHandle>,
+  pub(crate) buffers: Vec<IrBuffer>,
+  /** The functions, then the entrypoints, in declaration order. */
+  pub(crate) functions: Vec<IrFunction>,
+}
+impl IrModule {
+  pub fn function(&self, name: &str) -> Option<&IrFunction> {
+    self.functions.iter().find(|function| function.name == name)
+  }
+
+  pub(crate) fn buffer(&self, name: &str) -> Option<&IrBuffer> {
+    self.buffers.iter().find(|buffer| buffer.name == name)
+  }
+}
+
+/**
+ * A buffer, with its runtime-sized storage type.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrBuffer {
+  pub(crate) name: String,
+  pub(crate) mode: BufferAccessMode,
+  pub(crate) ty: TypeModelHandle,
+}
+
+/**
+ * A function or entrypoint.  The entry block is `bb0`, and blocks are
+ * numbered by their position.  An entrypoint has a single parameter,
+ * its invocation id.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrFunction {
+  pub(crate) name: String,
+  pub(crate) kind: IrFunctionKind,
+  pub(crate) params: Vec<IrParam>,
+  pub(crate) return_ty: TypeModelHandle,
+  pub(crate) locals: Vec<IrLocal>,
+  pub(crate) blocks: Vec<IrBlock>,
+}
+impl IrFunction {
+  pub fn blocks(&self) -> &[IrBlock] {
+    &self.blocks
+  }
+
+  pub fn block(&self, id: IrBlockId) -> Option<&IrBlock> {
+    self.blocks.get(id.0 as usize)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrFunctionKind {
+  Func,
+  Entrypoint(EntrypointDims),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrParam {
+  pub(crate) value: IrValue,
+  pub(crate) ty: TypeModelHandle,
+}
+
+/**
+ * A mutable local variable, named after the `var` it holds.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrLocal {
+  pub(crate) name: String,
+  pub(crate) ty: TypeModelHandle,
+}
+
+/**
+ * A value defined by a parameter, phi or instruction, e.g. `%3`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IrValue(pub(crate) u32);
+impl fmt::Display for IrValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "%{}", self.0)
+  }
+}
+
+/**
+ * A basic block, by its position in the function, e.g. `bb2`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IrBlockId(pub(crate) u32);
+impl fmt::Display for IrBlockId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "bb{}", self.0)
+  }
+}
+
+/**
+ * A mutable local, by its position in the function, e.g. `$0`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IrLocalId(pub(crate) u32);
+impl fmt::Display for IrLocalId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "${}", self.0)
+  }
+}
+
+/**
+ * A basic block: its phis, its instructions, and the terminator that
+ * ends it, with any structured control-flow marker.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrBlock {
+  pub(crate) phis: Vec<IrPhi>,
+  pub(crate) insts: Vec<IrInst>,
+  pub(crate) merge: Option<IrMerge>,
+  pub(crate) terminator: IrTerminator,
+}
+impl IrBlock {
+  pub(crate) fn new() -> IrBlock {
+    IrBlock {
+      phis: Vec::new(),
+      insts: Vec::new(),
+      merge: None,
+      terminator: IrTerminator::Unreachable,
+    }
+  }
+
+  pub fn insts(&self) -> &[IrInst] {
+    &self.insts
+  }
+}
+
+/**
+ * A value chosen by the predecessor that control came from.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrPhi {
+  pub(crate) result: IrValue,
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) incoming: Vec<(IrValue, IrBlockId)>,
+}
+
+/**
+ * An instruction, defining a value unless it is a store or a call of a
+ * function without one.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrInst {
+  pub(crate) result: Option<(IrValue, TypeModelHandle)>,
+  pub(crate) op: IrOp,
+}
+impl IrInst {
+  pub fn op(&self) -> &IrOp {
+    &self.op
+  }
+}
+
+/**
+ * The operations of instructions.  Operands and results have the types
+ * of the model's expressions, so e.g. a scalar operand of a vector
+ * `Binary` applies to every component.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrOp {
+  Const(LiteralModel),
+  Load(IrPlace),
+  Store(IrPlace, IrValue),
+
+  /** The element count of a runtime-sized array in a buffer. */
+  ArrayLength(IrPlace),
+
+  Field(IrValue, u32),
+  Index(IrValue, IrValue),
+  Swizzle(IrValue, Vec<u32>),
+  Construct(Vec<IrValue>),
+  Call(String, Vec<IrValue>),
+  Unary(UnaryOpModel, IrValue),
+  Binary(BinaryOpModel, IrValue, IrValue),
+  Cast(IrValue),
+}
+
+/**
+ * A location in a mutable local or a resource, e.g.
+ * `particles[%2].1.x`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrPlace {
+  pub(crate) root: IrPlaceRoot,
+  pub(crate) path: Vec<IrAccess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrPlaceRoot {
+  Local(IrLocalId),
+  Uniforms,
+  Buffer(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrAccess {
+  Field(u32),
+  Index(IrValue),
+  Component(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrTerminator {
+  Branch(IrBlockId),
+  BranchIf { cond: IrValue, if_true: IrBlockId, if_false: IrBlockId },
+  Return(Option<IrValue>),
+  Unreachable,
+}
+impl IrTerminator {
+  pub fn successors(&self) -> Vec<IrBlockId> {
+    match *self {
+      IrTerminator::Branch(target) => vec![target],
+      IrTerminator::BranchIf { if_true, if_false, .. } => vec![if_true, if_false],
+      IrTerminator::Return(_) | IrTerminator::Unreachable => Vec::new(),
+    }
+  }
+}
+
+/**
+ * The structured control-flow marker of a block.  A selection header
+ * branches to blocks that all reach its merge block, unless they
+ * return.  A loop header's body branches back to it only through its
+ * continue block.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrMerge {
+  Selection { merge: IrBlockId },
+  Loop { merge: IrBlockId, continue_block: IrBlockId },
+}
+
+/**
+ * The names of operators in the textual form.
+ */
+pub(crate) const UNARY_OPS: [(UnaryOpModel, &str); 3] = [
+  (UnaryOpModel::Negate, "neg"),
+  (UnaryOpModel::Not, "not"),
+  (UnaryOpModel::Complement, "complement"),
+];
+
+pub(crate) const BINARY_OPS: [(BinaryOpModel, &str); 18] = [
+  (BinaryOpModel::Add, "add"),
+  (BinaryOpModel::Sub, "sub"),
+  (BinaryOpModel::Mul, "mul"),
+  (BinaryOpModel::Div, "div"),
+  (BinaryOpModel::Mod, "mod"),
+  (BinaryOpModel::BitAnd, "bit_and"),
+  (BinaryOpModel::BitOr, "bit_or"),
+  (BinaryOpModel::BitXor, "bit_xor"),
+  (BinaryOpModel::Shl, "shl"),
+  (BinaryOpModel::Shr, "shr"),
+  (BinaryOpModel::LessThan, "lt"),
+  (BinaryOpModel::LessThanOrEqual, "le"),
+  (BinaryOpModel::GreaterThan, "gt"),
+  (BinaryOpModel::GreaterThanOrEqual, "ge"),
+  (BinaryOpModel::Equal, "eq"),
+  (BinaryOpModel::NotEqual, "ne"),
+  (BinaryOpModel::LogicalAnd, "and"),
+  (BinaryOpModel::LogicalOr, "or"),
+];
@@ -0,0 +1,775 @@
+use std::fmt;
+use crate::{
+  ir::{
+    BINARY_OPS,
+    IrAccess,
+    IrBlock,
+    IrBlockId,
+    IrBuffer,
+    IrFunction,
+    IrFunctionKind,
+    IrInst,
+    IrLocal,
+    IrLocalId,
+    IrMerge,
+    IrModule,
+    IrOp,
+    IrParam,
+    IrPhi,
+    IrPlace,
+    IrPlaceRoot,
+    IrTerminator,
+    IrValue,
+    UNARY_OPS,
+  },
+  model::{
+    BufferAccessMode,
+    EntrypointDims,
+    LiteralModel,
+    NameModel,
+    NameModelHandle,
+    NamePathModel,
+    ScalarNumericTypeModel,
+    StructFieldModel,
+    StructTypeModel,
+    SwizzleExprModel,
+    TypeModel,
+    TypeModelHandle,
+    VecDims,
+  },
+};
+
+/**
+ * Parse a module from the textual form written by its `Display`.
+ * Structs must be declared before they are used.  The module is only
+ * parsed, and may need to be verified.
+ */
+pub fn parse_ir(text: &str) -> Result<IrModule, IrParseError> {
+  let mut parser = Parser {
+    lines: text.lines().enumerate().collect(),
+    pos: 0,
+    module: IrModule {
+      structs: Vec::new(),
+      uniforms: None,
+      buffers: Vec::new(),
+      functions: Vec::new(),
+    },
+  };
+  while let Some((line, text)) = parser.next_line() {
+    let mut cursor = Cursor::new(line, text)?;
+    match cursor.peek() {
+      Some(Token::Punct("@")) | Some(Token::Ident("struct")) => parser.parse_struct(cursor)?,
+      Some(Token::Ident("uniforms")) => {
+        cursor.next();
+        cursor.expect(":")?;
+        let ty = parser.parse_type(&mut cursor)?;
+        cursor.end()?;
+        if !matches!(*ty, TypeModel::Struct(_)) {
+          return Err(cursor.error(format!("Uniforms must be a struct, found `{}`.", *ty)));
+        }
+        parser.module.uniforms = Some(ty);
+      },
+      Some(Token::Ident("buffer")) => parser.parse_buffer(cursor)?,
+      Some(Token::Ident("func")) | Some(Token::Ident("entrypoint")) =>
+        parser.parse_function(cursor)?,
+      _ => return Err(cursor.error(format!("Unexpected {}.", cursor.describe()))),
+    }
+  }
+  Ok(parser.module)
+}
+
+/**
+ * An error parsing IR text, at a line numbered from 1.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrParseError {
+  line: usize,
+  message: String,
+}
+impl fmt::Display for IrParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Line {}: {}", self.line, self.message)
+  }
+}
+
+struct Parser<'t> {
+  lines: Vec<(usize, &'t str)>,
+  pos: usize,
+  module: IrModule,
+}
+
+impl<'t> Parser<'t> {
+  /**
+   * The next line that is not blank, numbered from 1.
+   */
+  fn next_line(&mut self) -> Option<(usize, &'t str)> {
+    while let Some(&(index, text)) = self.lines.get(self.pos) {
+      self.pos += 1;
+      if !text.trim().is_empty() {
+        return Some((index + 1, text));
+      }
+    }
+    None
+  }
+
+  fn parse_struct(&mut self, mut cursor: Cursor) -> Result<(), IrParseError> {
+    let packed = cursor.eat("@");
+    if packed {
+      cursor.expect_keyword("packed")?;
+    }
+    cursor.expect_keyword("struct")?;
+    let name = cursor.name_path()?;
+    cursor.expect("{")?;
+    let mut fields = Vec::new();
+    while !cursor.eat("}") {
+      if !fields.is_empty() {
+        cursor.expect(",")?;
+      }
+      let (mut align, mut size) = (None, None);
+      while cursor.eat("@") {
+        let attribute = cursor.ident()?;
+        cursor.expect("(")?;
+        let value = Some(cursor.int()? as u32);
+        cursor.expect(")")?;
+        match attribute.as_str() {
+          "align" => align = value,
+          "size" => size = value,
+          _ => return Err(cursor.error(format!("Unknown attribute `@{}`.", attribute))),
+        }
+      }
+      let field_name = cursor.ident()?;
+      cursor.expect(":")?;
+      let ty = self.parse_type(&mut cursor)?;
+      fields.push(StructFieldModel { name: NameModel::new(field_name), ty, align, size });
+    }
+    cursor.end()?;
+    let ty = TypeModelHandle::new(TypeModel::Struct(StructTypeModel { name, fields, packed }));
+    self.module.structs.push(ty);
+    Ok(())
+  }
+
+  fn parse_buffer(&mut self, mut cursor: Cursor) -> Result<(), IrParseError> {
+    cursor.expect_keyword("buffer")?;
+    cursor.expect("(")?;
+    let mode = match cursor.ident()?.as_str() {
+      "r" => BufferAccessMode::Read,
+      "w" => BufferAccessMode::Write,
+      "rw" => BufferAccessMode::ReadWrite,
+      mode => return Err(cursor.error(format!("Unknown buffer mode `{}`.", mode))),
+    };
+    cursor.expect(")")?;
+    let name = cursor.ident()?;
+    cursor.expect(":")?;
+    let ty = self.parse_type(&mut cursor)?;
+    cursor.end()?;
+    self.module.buffers.push(IrBuffer { name, mode, ty });
+    Ok(())
+  }
+
+  fn parse_type(&self, cursor: &mut Cursor) -> Result<TypeModelHandle, IrParseError> {
+    if cursor.eat("[") {
+      let elem = self.parse_type(cursor)?;
+      let len = match cursor.eat(";") {
+        true => Some(cursor.int()? as u32),
+        false => None,
+      };
+      cursor.expect("]")?;
+      return Ok(TypeModelHandle::new(TypeModel::new_array(elem, len)));
+    }
+    let name = cursor.name_path()?;
+    let text = name.to_string();
+    if let Some(ty) = builtin_type(&text) {
+      return Ok(TypeModelHandle::new(ty));
+    }
+    self.module.structs.iter()
+      .find(|ty| matches!(&***ty, TypeModel::Struct(struct_ty) if struct_ty.name == name))
+      .cloned()
+      .ok_or_else(|| cursor.error(format!("Unknown type `{}`.", text)))
+  }
+
+  fn parse_function(&mut self, mut cursor: Cursor) -> Result<(), IrParseError> {
+    let kind = match cursor.ident()?.as_str() {
+      "entrypoint" => {
+        cursor.expect("(")?;
+        let dims = match (cursor.int()?, cursor.ident()?.as_str()) {
+          (1, "d") => EntrypointDims::D1,
+          (2, "d") => EntrypointDims::D2,
+          (3, "d") => EntrypointDims::D3,
+          _ => return Err(cursor.error("Expected `1d`, `2d` or `3d`.")),
+        };
+        cursor.expect(")")?;
+        IrFunctionKind::Entrypoint(dims)
+      },
+      _ => IrFunctionKind::Func,
+    };
+    let name = cursor.ident()?;
+    cursor.expect("(")?;
+    let mut params = Vec::new();
+    while !cursor.eat(")") {
+      if !params.is_empty() {
+        cursor.expect(",")?;
+      }
+      let value = cursor.value()?;
+      cursor.expect(":")?;
+      params.push(IrParam { value, ty: self.parse_type(&mut cursor)? });
+    }
+    let return_ty = match cursor.eat("->") {
+      true => self.parse_type(&mut cursor)?,
+      false => TypeModelHandle::new(TypeModel::new_void()),
+    };
+    cursor.expect("{")?;
+    cursor.end()?;
+
+    let mut function = IrFunction {
+      name,
+      kind,
+      params,
+      return_ty,
+      locals: Vec::new(),
+      blocks: Vec::new(),
+    };
+    // Whether the last block is still waiting for its terminator.
+    let mut open = false;
+    loop {
+      let Some((line, text)) = self.next_line() else {
+        return Err(IrParseError {
+          line: self.lines.len(),
+          message: format!("Function `{}` is not closed with `}}`.", function.name),
+        });
+      };
+      // Literals are not split into tokens, as floats and negative
+      // numbers only appear there.
+      let (text, literal) = match text.split_once(" = const ") {
+        Some((head, literal)) => (head, Some(literal.trim())),
+        None => (text, None),
+      };
+      let mut cursor = Cursor::new(line, text)?;
+      if let Some(literal) = literal {
+        let value = cursor.value()?;
+        cursor.expect(":")?;
+        let ty = self.parse_type(&mut cursor)?;
+        cursor.end()?;
+        let literal = parse_literal(&ty, literal)
+          .ok_or_else(|| cursor.error(format!("Invalid `{}` literal `{}`.", *ty, literal)))?;
+        let block = open_block(&mut function, open, &cursor)?;
+        block.insts.push(IrInst { result: Some((value, ty)), op: IrOp::Const(literal) });
+        continue;
+      }
+      match cursor.peek() {
+        Some(Token::Punct("}")) => {
+          cursor.next();
+          cursor.end()?;
+          if open {
+            return Err(cursor.error("Expected a terminator before `}`."));
+          }
+          self.module.functions.push(function);
+          return Ok(());
+        },
+        Some(Token::Ident("var")) => {
+          cursor.next();
+          if !function.blocks.is_empty() {
+            return Err(cursor.error("Locals must be declared before the first block."));
+          }
+          let local = cursor.local()?;
+          if local.0 as usize != function.locals.len() {
+            return Err(cursor.error(format!("Expected local `${}`.", function.locals.len())));
+          }
+          let name = cursor.ident()?;
+          cursor.expect(":")?;
+          let ty = self.parse_type(&mut cursor)?;
+          cursor.end()?;
+          function.locals.push(IrLocal { name, ty });
+        },
+        Some(Token::Ident(label))
+          if is_block_label(label) && cursor.peek_at(1) == Some(Token::Punct(":")) =>
+        {
+          if open {
+            return Err(cursor.error("Expected a terminator before the next block."));
+          }
+          let id = cursor.block()?;
+          if id.0 as usize != function.blocks.len() {
+            return Err(cursor.error(format!("Expected block `bb{}`.", function.blocks.len())));
+          }
+          cursor.expect(":")?;
+          cursor.end()?;
+          function.blocks.push(IrBlock::new());
+          open = true;
+        },
+        _ => {
+          let block = open_block(&mut function, open, &cursor)?;
+          if self.parse_block_line(block, &mut cursor)? {
+            open = false;
+          }
+        },
+      }
+    }
+  }
+
+  /**
+   * Parse a line within a block, returning whether it terminates it.
+   */
+  fn parse_block_line(&self, block: &mut IrBlock, cursor: &mut Cursor)
+    -> Result<bool, IrParseError>
+  {
+    let keyword = match cursor.peek() {
+      Some(Token::Ident(keyword)) => keyword.to_string(),
+      Some(Token::Value(_)) => {
+        let (value, ty) = self.parse_result(cursor)?;
+        if cursor.eat_keyword("phi") {
+          if !block.insts.is_empty() {
+            return Err(cursor.error("Phis must come before the other instructions of a block."));
+          }
+          let mut incoming = Vec::new();
+          loop {
+            cursor.expect("[")?;
+            let value = cursor.value()?;
+            cursor.expect(",")?;
+            incoming.push((value, cursor.block()?));
+            cursor.expect("]")?;
+            if !cursor.eat(",") {
+              break;
+            }
+          }
+          cursor.end()?;
+          block.phis.push(IrPhi { result: value, ty, incoming });
+        } else {
+          let op = self.parse_op(cursor)?;
+          block.insts.push(IrInst { result: Some((value, ty)), op });
+        }
+        return Ok(false);
+      },
+      _ => return Err(cursor.error(format!("Unexpected {}.", cursor.describe()))),
+    };
+    if block.merge.is_some() && !matches!(keyword.as_str(), "br" | "br_if") {
+      return Err(cursor.error("Expected a branch after a merge marker."));
+    }
+    cursor.next();
+    match keyword.as_str() {
+      "store" => {
+        let place = self.parse_place(cursor)?;
+        cursor.expect(",")?;
+        let value = cursor.value()?;
+        cursor.end()?;
+        block.insts.push(IrInst { result: None, op: IrOp::Store(place, value) });
+        Ok(false)
+      },
+      "call" => {
+        let op = self.parse_call(cursor)?;
+        block.insts.push(IrInst { result: None, op });
+        Ok(false)
+      },
+      "selection_merge" => {
+        let merge = cursor.block()?;
+        cursor.end()?;
+        block.merge = Some(IrMerge::Selection { merge });
+        Ok(false)
+      },
+      "loop_merge" => {
+        let merge = cursor.block()?;
+        cursor.expect(",")?;
+        let continue_block = cursor.block()?;
+        cursor.end()?;
+        block.merge = Some(IrMerge::Loop { merge, continue_block });
+        Ok(false)
+      },
+      "br" => {
+        block.terminator = IrTerminator::Branch(cursor.block()?);
+        cursor.end()?;
+        Ok(true)
+      },
+      "br_if" => {
+        let cond = cursor.value()?;
+        cursor.expect(",")?;
+        let if_true = cursor.block()?;
+        cursor.expect(",")?;
+        let if_false = cursor.block()?;
+        cursor.end()?;
+        block.terminator = IrTerminator::BranchIf { cond, if_true, if_false };
+        Ok(true)
+      },
+      "ret" => {
+        let value = match cursor.peek() {
+          Some(_) => Some(cursor.value()?),
+          None => None,
+        };
+        cursor.end()?;
+        block.terminator = IrTerminator::Return(value);
+        Ok(true)
+      },
+      "unreachable" => {
+        cursor.end()?;
+        block.terminator = IrTerminator::Unreachable;
+        Ok(true)
+      },
+      _ => Err(cursor.error(format!("Unknown instruction `{}`.", keyword))),
+    }
+  }
+
+  /**
+   * Parse the `%N: type =` that starts an instruction with a result.
+   */
+  fn parse_result(&self, cursor: &mut Cursor) -> Result<(IrValue, TypeModelHandle), IrParseError> {
+    let value = cursor.value()?;
+    cursor.expect(":")?;
+    let ty = self.parse_type(cursor)?;
+    cursor.expect("=")?;
+    Ok((value, ty))
+  }
+
+  fn parse_op(&self, cursor: &mut Cursor) -> Result<IrOp, IrParseError> {
+    let name = cursor.ident()?;
+    let op = match name.as_str() {
+      "load" => IrOp::Load(self.parse_place(cursor)?),
+      "array_length" => IrOp::ArrayLength(self.parse_place(cursor)?),
+      "field" => {
+        let value = cursor.value()?;
+        cursor.expect(",")?;
+        IrOp::Field(value, cursor.int()? as u32)
+      },
+      "index" => {
+        let value = cursor.value()?;
+        cursor.expect(",")?;
+        IrOp::Index(value, cursor.value()?)
+      },
+      "swizzle" => {
+        let value = cursor.value()?;
+        cursor.expect(",")?;
+        let letters = cursor.ident()?;
+        let components = SwizzleExprModel::parse_components(&letters)
+          .ok_or_else(|| cursor.error(format!("Invalid swizzle `{}`.", letters)))?;
+        IrOp::Swizzle(value, components)
+      },
+      "construct" => IrOp::Construct(cursor.values()?),
+      "call" => return self.parse_call(cursor),
+      "cast" => IrOp::Cast(cursor.value()?),
+      _ => {
+        if let Some(&(op, _)) = UNARY_OPS.iter().find(|(_, n)| *n == name) {
+          IrOp::Unary(op, cursor.value()?)
+        } else if let Some(&(op, _)) = BINARY_OPS.iter().find(|(_, n)| *n == name) {
+          let lhs = cursor.value()?;
+          cursor.expect(",")?;
+          IrOp::Binary(op, lhs, cursor.value()?)
+        } else {
+          return Err(cursor.error(format!("Unknown instruction `{}`.", name)));
+        }
+      },
+    };
+    cursor.end()?;
+    Ok(op)
+  }
+
+  fn parse_call(&self, cursor: &mut Cursor) -> Result<IrOp, IrParseError> {
+    let name = cursor.ident()?;
+    cursor.expect("(")?;
+    let args = match cursor.peek() {
+      Some(Token::Punct(")")) => Vec::new(),
+      _ => cursor.values()?,
+    };
+    cursor.expect(")")?;
+    cursor.end()?;
+    Ok(IrOp::Call(name, args))
+  }
+
+  fn parse_place(&self, cursor: &mut Cursor) -> Result<IrPlace, IrParseError> {
+    let root = match cursor.peek() {
+      Some(Token::Local(_)) => IrPlaceRoot::Local(cursor.local()?),
+      _ => match cursor.ident()?.as_str() {
+        "uniforms" => IrPlaceRoot::Uniforms,
+        name => IrPlaceRoot::Buffer(name.to_string()),
+      },
+    };
+    let mut path = Vec::new();
+    loop {
+      if cursor.eat("[") {
+        path.push(IrAccess::Index(cursor.value()?));
+        cursor.expect("]")?;
+      } else if cursor.eat(".") {
+        let access = match cursor.peek() {
+          Some(Token::Int(_)) => IrAccess::Field(cursor.int()? as u32),
+          _ => {
+            let letter = cursor.ident()?;
+            match SwizzleExprModel::parse_components(&letter).as_deref() {
+              Some(&[component]) => IrAccess::Component(component),
+              _ => return Err(cursor.error(format!("Invalid component `{}`.", letter))),
+            }
+          },
+        };
+        path.push(access);
+      } else {
+        return Ok(IrPlace { root, path });
+      }
+    }
+  }
+}
+
+/**
+ * The block that instructions are being added to.
+ */
+fn open_block<'f>(function: &'f mut IrFunction, open: bool, cursor: &Cursor)
+  -> Result<&'f mut IrBlock, IrParseError>
+{
+  match function.blocks.last_mut() {
+    Some(block) if open => Ok(block),
+    _ => Err(cursor.error("Expected a block label.")),
+  }
+}
+
+fn is_block_label(ident: &str) -> bool {
+  ident.strip_prefix("bb").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/**
+ * A scalar, vector or matrix type by name, e.g. `mat3x4xf32`.
+ */
+fn builtin_type(name: &str) -> Option<TypeModel> {
+  match name {
+    "bool" => return Some(TypeModel::new_bool()),
+    "void" => return Some(TypeModel::new_void()),
+    _ => {},
+  }
+  if let Some(scalar) = ScalarNumericTypeModel::from_name(name) {
+    return Some(TypeModel::new_scalar(scalar));
+  }
+  let dims = |digit: &str| VecDims::from_count(digit.parse().ok()?);
+  if let Some(rest) = name.strip_prefix("vec") {
+    let (size, scalar) = rest.split_once('x')?;
+    return Some(TypeModel::new_vector(ScalarNumericTypeModel::from_name(scalar)?, dims(size)?));
+  }
+  let rest = name.strip_prefix("mat")?;
+  let (cols, rest) = rest.split_once('x')?;
+  let (rows, scalar) = rest.split_once('x')?;
+  Some(TypeModel::new_matrix(
+    ScalarNumericTypeModel::from_name(scalar)?,
+    dims(cols)?,
+    dims(rows)?,
+  ))
+}
+
+/**
+ * Parse a literal of a scalar type.  Floats may be given by their bits
+ * in hex, e.g. `0x7FC00000`.
+ */
+fn parse_literal(ty: &TypeModel, text: &str) -> Option<LiteralModel> {
+  if ty.is_bool() {
+    return text.parse().ok().map(LiteralModel::Bool);
+  }
+  let float_bits = || match text.strip_prefix("0x") {
+    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+    None => text.parse::<f32>().ok().map(f32::to_bits),
+  };
+  match ty.as_numeric_scalar()? {
+    ScalarNumericTypeModel::I32 => text.parse().ok().map(LiteralModel::I32),
+    ScalarNumericTypeModel::U32 => text.parse().ok().map(LiteralModel::U32),
+    ScalarNumericTypeModel::I64 => text.parse().ok().map(LiteralModel::I64),
+    ScalarNumericTypeModel::U64 => text.parse().ok().map(LiteralModel::U64),
+    ScalarNumericTypeModel::F32 => float_bits().map(LiteralModel::F32),
+    ScalarNumericTypeModel::F16 => float_bits().map(LiteralModel::F16),
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'t> {
+  Ident(&'t str),
+  Int(u64),
+  Value(u32),
+  Local(u32),
+  Punct(&'static str),
+}
+impl fmt::Display for Token<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Token::Ident(ident) => write!(f, "`{}`", ident),
+      Token::Int(int) => write!(f, "`{}`", int),
+      Token::Value(value) => write!(f, "`%{}`", value),
+      Token::Local(local) => write!(f, "`${}`", local),
+      Token::Punct(punct) => write!(f, "`{}`", punct),
+    }
+  }
+}
+
+const PUNCTUATION: [&str; 14] = [
+  "::", "->", "(", ")", "{", "}", "[", "]", ":", ",", "=", ".", ";", "@",
+];
+
+/**
+ * The tokens of a line, and the position of the next.
+ */
+struct Cursor<'t> {
+  line: usize,
+  tokens: Vec<Token<'t>>,
+  pos: usize,
+}
+
+impl<'t> Cursor<'t> {
+  fn new(line: usize, text: &'t str) -> Result<Cursor<'t>, IrParseError> {
+    let error = |message: String| IrParseError { line, message };
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+      let word_len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+      let (token, len) = if c.is_ascii_alphabetic() || c == '_' {
+        (Token::Ident(&rest[..word_len]), word_len)
+      } else if c.is_ascii_digit() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let int = rest[..digits].parse()
+          .map_err(|_| error(format!("Invalid number `{}`.", &rest[..digits])))?;
+        (Token::Int(int), digits)
+      } else if c == '%' || c == '$' {
+        let digits = rest[1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - 1);
+        let n = rest[1..1 + digits].parse()
+          .map_err(|_| error(format!("Expected a number after `{}`.", c)))?;
+        let token = if c == '%' { Token::Value(n) } else { Token::Local(n) };
+        (token, 1 + digits)
+      } else {
+        let punct = PUNCTUATION.iter()
+          .find(|punct| rest.starts_with(**punct))
+          .ok_or_else(|| error(format!("Unexpected character `{}`.", c)))?;
+        (Token::Punct(punct), punct.len())
+      };
+      tokens.push(token);
+      rest = rest[len..].trim_start();
+    }
+    Ok(Cursor { line, tokens, pos: 0 })
+  }
+
+  fn error(&self, message: impl Into<String>) -> IrParseError {
+    IrParseError { line: self.line, message: message.into() }
+  }
+
+  fn peek(&self) -> Option<Token<'t>> {
+    self.peek_at(0)
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<Token<'t>> {
+    self.tokens.get(self.pos + offset).cloned()
+  }
+
+  fn next(&mut self) -> Option<Token<'t>> {
+    let token = self.peek();
+    self.pos += 1;
+    token
+  }
+
+  /**
+   * A description of the next token, for errors.
+   */
+  fn describe(&self) -> String {
+    match self.peek() {
+      Some(token) => format!("{}", token),
+      None => "end of line".to_string(),
+    }
+  }
+
+  fn eat(&mut self, punct: &str) -> bool {
+    if matches!(self.peek(), Some(Token::Punct(p)) if p == punct) {
+      self.pos += 1;
+      return true;
+    }
+    false
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    if self.peek() == Some(Token::Ident(keyword)) {
+      self.pos += 1;
+      return true;
+    }
+    false
+  }
+
+  fn expect(&mut self, punct: &str) -> Result<(), IrParseError> {
+    match self.eat(punct) {
+      true => Ok(()),
+      false => Err(self.error(format!("Expected `{}`, found {}.", punct, self.describe()))),
+    }
+  }
+
+  fn expect_keyword(&mut self, keyword: &str) -> Result<(), IrParseError> {
+    match self.eat_keyword(keyword) {
+      true => Ok(()),
+      false => Err(self.error(format!("Expected `{}`, found {}.", keyword, self.describe()))),
+    }
+  }
+
+  fn end(&self) -> Result<(), IrParseError> {
+    match self.peek() {
+      None => Ok(()),
+      Some(token) => Err(self.error(format!("Unexpected {} at end of line.", token))),
+    }
+  }
+
+  fn ident(&mut self) -> Result<String, IrParseError> {
+    match self.peek() {
+      Some(Token::Ident(ident)) => {
+        self.pos += 1;
+        Ok(ident.to_string())
+      },
+      _ => Err(self.error(format!("Expected a name, found {}.", self.describe()))),
+    }
+  }
+
+  fn name_path(&mut self) -> Result<NamePathModel, IrParseError> {
+    let mut path = vec![self.ident()?];
+    while self.eat("::") {
+      path.push(self.ident()?);
+    }
+    Ok(NamePathModel::new(
+      path.into_iter()
+        .map(|name| NameModelHandle::new(NameModel::new(name)))
+        .collect()
+    ))
+  }
+
+  fn int(&mut self) -> Result<u64, IrParseError> {
+    match self.peek() {
+      Some(Token::Int(int)) => {
+        self.pos += 1;
+        Ok(int)
+      },
+      _ => Err(self.error(format!("Expected a number, found {}.", self.describe()))),
+    }
+  }
+
+  fn value(&mut self) -> Result<IrValue, IrParseError> {
+    match self.peek() {
+      Some(Token::Value(value)) => {
+        self.pos += 1;
+        Ok(IrValue(value))
+      },
+      _ => Err(self.error(format!("Expected a value, found {}.", self.describe()))),
+    }
+  }
+
+  /**
+   * One or more values separated by commas.
+   */
+  fn values(&mut self) -> Result<Vec<IrValue>, IrParseError> {
+    let mut values = vec![self.value()?];
+    while self.eat(",") {
+      values.push(self.value()?);
+    }
+    Ok(values)
+  }
+
+  fn local(&mut self) -> Result<IrLocalId, IrParseError> {
+    match self.peek() {
+      Some(Token::Local(local)) => {
+        self.pos += 1;
+        Ok(IrLocalId(local))
+      },
+      _ => Err(self.error(format!("Expected a local, found {}.", self.describe()))),
+    }
+  }
+
+  fn block(&mut self) -> Result<IrBlockId, IrParseError> {
+    match self.peek() {
+      Some(Token::Ident(label)) if is_block_label(label) => {
+        self.pos += 1;
+        label[2..].parse()
+          .map(IrBlockId)
+          .map_err(|_| self.error(format!("Invalid block `{}`.", label)))
+      },
+      _ => Err(self.error(format!("Expected a block, found {}.", self.describe()))),
+    }
+  }
+}
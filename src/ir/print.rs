@@ -0,0 +1,218 @@
+use std::fmt;
+use crate::{
+  ir::{
+    BINARY_OPS,
+    IrAccess,
+    IrBlock,
+    IrFunction,
+    IrFunctionKind,
+    IrInst,
+    IrMerge,
+    IrModule,
+    IrOp,
+    IrPlace,
+    IrPlaceRoot,
+    IrTerminator,
+    IrValue,
+    UNARY_OPS,
+  },
+  model::{ BufferAccessMode, LiteralModel, SWIZZLE_LETTERS, TypeModel },
+};
+
+/**
+ * Write the module in its textual form, e.g.
+ *
+ * ```text
+ * func half(%0: f32) -> f32 {
+ *   bb0:
+ *     %1: f32 = const 0.5
+ *     %2: f32 = mul %0, %1
+ *     ret %2
+ * }
+ * ```
+ */
+impl fmt::Display for IrModule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for ty in &self.structs {
+      let TypeModel::Struct(struct_ty) = &**ty else {
+        unreachable!("Module struct is not a struct type");
+      };
+      if struct_ty.packed {
+        write!(f, "@packed ")?;
+      }
+      write!(f, "struct {} {{", struct_ty.name)?;
+      for (i, field) in struct_ty.fields.iter().enumerate() {
+        write!(f, "{} ", if i == 0 { "" } else { "," })?;
+        if let Some(align) = field.align {
+          write!(f, "@align({}) ", align)?;
+        }
+        if let Some(size) = field.size {
+          write!(f, "@size({}) ", size)?;
+        }
+        write!(f, "{}: {}", field.name, *field.ty)?;
+      }
+      writeln!(f, " }}")?;
+    }
+    if let Some(uniforms) = &self.uniforms {
+      writeln!(f, "uniforms: {}", **uniforms)?;
+    }
+    for buffer in &self.buffers {
+      let mode = match buffer.mode {
+        BufferAccessMode::Read => "r",
+        BufferAccessMode::Write => "w",
+        BufferAccessMode::ReadWrite => "rw",
+      };
+      writeln!(f, "buffer({}) {}: {}", mode, buffer.name, *buffer.ty)?;
+    }
+    for function in &self.functions {
+      writeln!(f)?;
+      write!(f, "{}", function)?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for IrFunction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.kind {
+      IrFunctionKind::Func => write!(f, "func ")?,
+      IrFunctionKind::Entrypoint(dims) => write!(f, "entrypoint({}d) ", dims as u8)?,
+    }
+    write!(f, "{}(", self.name)?;
+    for (i, param) in self.params.iter().enumerate() {
+      write!(f, "{}{}: {}", if i == 0 { "" } else { ", " }, param.value, *param.ty)?;
+    }
+    write!(f, ")")?;
+    if !self.return_ty.is_void() {
+      write!(f, " -> {}", *self.return_ty)?;
+    }
+    writeln!(f, " {{")?;
+    for (i, local) in self.locals.iter().enumerate() {
+      writeln!(f, "  var ${} {}: {}", i, local.name, *local.ty)?;
+    }
+    for (i, block) in self.blocks.iter().enumerate() {
+      writeln!(f, "  bb{}:", i)?;
+      write!(f, "{}", block)?;
+    }
+    writeln!(f, "}}")
+  }
+}
+
+impl fmt::Display for IrBlock {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for phi in &self.phis {
+      write!(f, "    {}: {} = phi", phi.result, *phi.ty)?;
+      for (i, (value, block)) in phi.incoming.iter().enumerate() {
+        write!(f, "{} [{}, {}]", if i == 0 { "" } else { "," }, value, block)?;
+      }
+      writeln!(f)?;
+    }
+    for inst in &self.insts {
+      writeln!(f, "    {}", inst)?;
+    }
+    match self.merge {
+      Some(IrMerge::Selection { merge }) => writeln!(f, "    selection_merge {}", merge)?,
+      Some(IrMerge::Loop { merge, continue_block }) =>
+        writeln!(f, "    loop_merge {}, {}", merge, continue_block)?,
+      None => {},
+    }
+    match &self.terminator {
+      IrTerminator::Branch(target) => writeln!(f, "    br {}", target),
+      IrTerminator::BranchIf { cond, if_true, if_false } =>
+        writeln!(f, "    br_if {}, {}, {}", cond, if_true, if_false),
+      IrTerminator::Return(Some(value)) => writeln!(f, "    ret {}", value),
+      IrTerminator::Return(None) => writeln!(f, "    ret"),
+      IrTerminator::Unreachable => writeln!(f, "    unreachable"),
+    }
+  }
+}
+
+impl fmt::Display for IrInst {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some((value, ty)) = &self.result {
+      write!(f, "{}: {} = ", value, **ty)?;
+    }
+    match &self.op {
+      IrOp::Const(literal) => write!(f, "const {}", LiteralText(*literal)),
+      IrOp::Load(place) => write!(f, "load {}", place),
+      IrOp::Store(place, value) => write!(f, "store {}, {}", place, value),
+      IrOp::ArrayLength(place) => write!(f, "array_length {}", place),
+      IrOp::Field(value, field) => write!(f, "field {}, {}", value, field),
+      IrOp::Index(value, index) => write!(f, "index {}, {}", value, index),
+      IrOp::Swizzle(value, components) => {
+        let letters = components.iter()
+          .map(|&component| SWIZZLE_LETTERS[component as usize])
+          .collect::<String>();
+        write!(f, "swizzle {}, {}", value, letters)
+      },
+      IrOp::Construct(args) => write!(f, "construct {}", ValueList(args)),
+      IrOp::Call(name, args) => write!(f, "call {}({})", name, ValueList(args)),
+      IrOp::Unary(op, value) => {
+        let name = UNARY_OPS.iter().find(|(o, _)| o == op).map(|(_, name)| name).unwrap();
+        write!(f, "{} {}", name, value)
+      },
+      IrOp::Binary(op, lhs, rhs) => {
+        let name = BINARY_OPS.iter().find(|(o, _)| o == op).map(|(_, name)| name).unwrap();
+        write!(f, "{} {}, {}", name, lhs, rhs)
+      },
+      IrOp::Cast(value) => write!(f, "cast {}", value),
+    }
+  }
+}
+
+impl fmt::Display for IrPlace {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.root {
+      IrPlaceRoot::Local(local) => write!(f, "{}", local)?,
+      IrPlaceRoot::Uniforms => write!(f, "uniforms")?,
+      IrPlaceRoot::Buffer(name) => write!(f, "{}", name)?,
+    }
+    for access in &self.path {
+      match access {
+        IrAccess::Field(field) => write!(f, ".{}", field)?,
+        IrAccess::Index(index) => write!(f, "[{}]", index)?,
+        IrAccess::Component(component) =>
+          write!(f, ".{}", SWIZZLE_LETTERS[*component as usize])?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/**
+ * Values separated by commas.
+ */
+struct ValueList<'a>(&'a [IrValue]);
+impl fmt::Display for ValueList<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, value) in self.0.iter().enumerate() {
+      write!(f, "{}{}", if i == 0 { "" } else { ", " }, value)?;
+    }
+    Ok(())
+  }
+}
+
+/**
+ * A literal, whose type is given by the instruction's result.  Floats
+ * that are not finite are written by their bits, e.g. `0x7FC00000`.
+ */
+struct LiteralText(LiteralModel);
+impl fmt::Display for LiteralText {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.0 {
+      LiteralModel::Bool(value) => write!(f, "{}", value),
+      LiteralModel::I32(value) => write!(f, "{}", value),
+      LiteralModel::U32(value) => write!(f, "{}", value),
+      LiteralModel::I64(value) => write!(f, "{}", value),
+      LiteralModel::U64(value) => write!(f, "{}", value),
+      LiteralModel::F32(bits) | LiteralModel::F16(bits) => {
+        let value = f32::from_bits(bits);
+        if value.is_finite() {
+          write!(f, "{:?}", value)
+        } else {
+          write!(f, "0x{:08X}", bits)
+        }
+      },
+    }
+  }
+}
@@ -0,0 +1,653 @@
+use std::{ collections::HashMap, fmt };
+use crate::{
+  ir::{
+    IrAccess,
+    IrBlockId,
+    IrFunction,
+    IrFunctionKind,
+    IrInst,
+    IrMerge,
+    IrModule,
+    IrOp,
+    IrPlace,
+    IrPlaceRoot,
+    IrTerminator,
+    IrValue,
+  },
+  model::{
+    BinaryOpModel,
+    LiteralModel,
+    ScalarNumericTypeModel,
+    TypeModel,
+    TypeModelHandle,
+    UnaryOpModel,
+    VecDims,
+  },
+};
+
+/**
+ * Check that a module is well formed: that every value is defined once
+ * and only used where its definition dominates, that phis have a value
+ * for each predecessor, that branches and merge markers name blocks of
+ * the function, and that operands have the types their operations
+ * need.
+ */
+pub fn verify_ir(module: &IrModule) -> Result<(), Vec<IrVerifyError>> {
+  let mut errors = Vec::new();
+  for (i, function) in module.functions.iter().enumerate() {
+    if module.functions[..i].iter().any(|other| other.name == function.name) {
+      errors.push(IrVerifyError {
+        function: function.name.clone(),
+        block: None,
+        message: "The function is defined more than once.".to_string(),
+      });
+    }
+    errors.extend(FunctionVerifier::verify(module, function));
+  }
+  match errors.is_empty() {
+    true => Ok(()),
+    false => Err(errors),
+  }
+}
+
+/**
+ * A problem found in a function, and the block it is in.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrVerifyError {
+  function: String,
+  block: Option<IrBlockId>,
+  message: String,
+}
+impl fmt::Display for IrVerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.block {
+      Some(block) => write!(f, "In `{}`, {}: {}", self.function, block, self.message),
+      None => write!(f, "In `{}`: {}", self.function, self.message),
+    }
+  }
+}
+
+/**
+ * Where a value is defined.  Within a block, phis are at position 0
+ * and each instruction after them.
+ */
+#[derive(Debug, Clone, Copy)]
+enum Definition {
+  Param,
+  Block(IrBlockId, usize),
+}
+
+/**
+ * The position of the terminator, after every instruction.
+ */
+const END: usize = usize::MAX;
+
+struct FunctionVerifier<'m> {
+  module: &'m IrModule,
+  function: &'m IrFunction,
+  errors: Vec<IrVerifyError>,
+  block: Option<IrBlockId>,
+  definitions: HashMap<IrValue, (Definition, TypeModelHandle)>,
+  predecessors: Vec<Vec<IrBlockId>>,
+
+  /** For each block, whether each block dominates it. */
+  dominators: Vec<Vec<bool>>,
+}
+
+impl<'m> FunctionVerifier<'m> {
+  fn verify(module: &'m IrModule, function: &'m IrFunction) -> Vec<IrVerifyError> {
+    let mut verifier = FunctionVerifier {
+      module,
+      function,
+      errors: Vec::new(),
+      block: None,
+      definitions: HashMap::new(),
+      predecessors: vec![Vec::new(); function.blocks.len()],
+      dominators: Vec::new(),
+    };
+    if function.blocks.is_empty() {
+      verifier.error("The function has no blocks.");
+      return verifier.errors;
+    }
+    verifier.check_params();
+    verifier.collect_definitions();
+    verifier.collect_predecessors();
+    verifier.compute_dominators();
+    for (i, block) in function.blocks.iter().enumerate() {
+      verifier.block = Some(IrBlockId(i as u32));
+      verifier.check_phis(IrBlockId(i as u32));
+      for (position, inst) in block.insts.iter().enumerate() {
+        verifier.check_inst(position + 1, inst);
+      }
+      verifier.check_terminator(IrBlockId(i as u32));
+    }
+    verifier.errors
+  }
+
+  fn error(&mut self, message: impl Into<String>) {
+    self.errors.push(IrVerifyError {
+      function: self.function.name.clone(),
+      block: self.block,
+      message: message.into(),
+    });
+  }
+
+  /**
+   * An entrypoint takes only its invocation id, with a component for
+   * each dimension.
+   */
+  fn check_params(&mut self) {
+    let IrFunctionKind::Entrypoint(dims) = self.function.kind else {
+      return;
+    };
+    let u32_ty = ScalarNumericTypeModel::U32;
+    let expected = match VecDims::from_count(dims as usize) {
+      Some(dims) => TypeModel::new_vector(u32_ty, dims),
+      None => TypeModel::new_scalar(u32_ty),
+    };
+    match &self.function.params[..] {
+      [param] if *param.ty == expected => {},
+      _ => self.error(format!("An entrypoint takes only an invocation id of type `{}`.", expected)),
+    }
+    if !self.function.return_ty.is_void() {
+      self.error("An entrypoint cannot return a value.");
+    }
+  }
+
+  fn collect_definitions(&mut self) {
+    let function = self.function;
+    let define = |verifier: &mut Self, value: IrValue, definition, ty: &TypeModelHandle| {
+      if verifier.definitions.insert(value, (definition, ty.clone())).is_some() {
+        verifier.error(format!("Value {} is defined more than once.", value));
+      }
+    };
+    for param in &function.params {
+      define(self, param.value, Definition::Param, &param.ty);
+    }
+    for (i, block) in function.blocks.iter().enumerate() {
+      self.block = Some(IrBlockId(i as u32));
+      for phi in &block.phis {
+        define(self, phi.result, Definition::Block(IrBlockId(i as u32), 0), &phi.ty);
+      }
+      for (position, inst) in block.insts.iter().enumerate() {
+        if let Some((value, ty)) = &inst.result {
+          define(self, *value, Definition::Block(IrBlockId(i as u32), position + 1), ty);
+        }
+      }
+    }
+    self.block = None;
+  }
+
+  fn collect_predecessors(&mut self) {
+    let function = self.function;
+    for (i, block) in function.blocks.iter().enumerate() {
+      let id = IrBlockId(i as u32);
+      self.block = Some(id);
+      let mut targets = block.terminator.successors();
+      match block.merge {
+        Some(IrMerge::Selection { merge }) => targets.push(merge),
+        Some(IrMerge::Loop { merge, continue_block }) => targets.extend([merge, continue_block]),
+        None => {},
+      }
+      for &target in &targets {
+        if function.block(target).is_none() {
+          self.error(format!("Block {} does not exist.", target));
+        }
+      }
+      for target in block.terminator.successors() {
+        if target == IrBlockId(0) {
+          self.error("The entry block cannot be branched to.");
+        } else if let Some(predecessors) = self.predecessors.get_mut(target.0 as usize) {
+          if !predecessors.contains(&id) {
+            predecessors.push(id);
+          }
+        }
+      }
+    }
+    self.block = None;
+  }
+
+  /**
+   * Find the blocks that dominate each block, by iterating to a fixed
+   * point.  Unreachable blocks are dominated by every block.
+   */
+  fn compute_dominators(&mut self) {
+    let count = self.function.blocks.len();
+    self.dominators = vec![vec![true; count]; count];
+    self.dominators[0] = (0..count).map(|i| i == 0).collect();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for block in 1..count {
+        let mut dominators = vec![true; count];
+        for predecessor in &self.predecessors[block] {
+          for (i, dominator) in dominators.iter_mut().enumerate() {
+            *dominator &= self.dominators[predecessor.0 as usize][i];
+          }
+        }
+        dominators[block] = true;
+        if dominators != self.dominators[block] {
+          self.dominators[block] = dominators;
+          changed = true;
+        }
+      }
+    }
+  }
+
+  /**
+   * The type of a value used at a position in a block, if it is
+   * defined where it is used.
+   */
+  fn use_value(&mut self, value: IrValue, block: IrBlockId, position: usize)
+    -> Option<TypeModelHandle>
+  {
+    let Some((definition, ty)) = self.definitions.get(&value).cloned() else {
+      self.error(format!("Value {} is not defined.", value));
+      return None;
+    };
+    let available = match definition {
+      Definition::Param => true,
+      Definition::Block(def_block, def_position) if def_block == block =>
+        def_position < position,
+      Definition::Block(def_block, _) =>
+        self.dominators[block.0 as usize][def_block.0 as usize],
+    };
+    if !available {
+      self.error(format!("Value {} is used where its definition does not dominate.", value));
+    }
+    Some(ty)
+  }
+
+  fn check_phis(&mut self, id: IrBlockId) {
+    let block = &self.function.blocks[id.0 as usize];
+    let predecessors = self.predecessors[id.0 as usize].clone();
+    for phi in &block.phis {
+      for predecessor in &predecessors {
+        let count = phi.incoming.iter().filter(|(_, from)| from == predecessor).count();
+        if count != 1 {
+          self.error(format!(
+            "Phi {} needs one value from predecessor {}, but has {}.",
+            phi.result, predecessor, count
+          ));
+        }
+      }
+      for &(value, from) in &phi.incoming {
+        if !predecessors.contains(&from) {
+          self.error(format!(
+            "Phi {} has a value from {}, which is not a predecessor.", phi.result, from
+          ));
+          continue;
+        }
+        if let Some(ty) = self.use_value(value, from, END) {
+          if ty != phi.ty {
+            self.error(format!(
+              "Phi {} has type `{}`, but its value {} from {} has type `{}`.",
+              phi.result, *phi.ty, value, from, *ty
+            ));
+          }
+        }
+      }
+    }
+  }
+
+  fn check_inst(&mut self, position: usize, inst: &IrInst) {
+    let block = self.block.expect("Instruction is in a block");
+    let result_ty = inst.result.as_ref().map(|(_, ty)| ty.clone());
+    let expected = match self.op_type(&inst.op, result_ty.as_deref(), block, position) {
+      Ok(expected) => expected,
+      // An operand that is not defined has been reported already.
+      Err(message) => {
+        if !message.is_empty() {
+          self.error(message);
+        }
+        return;
+      },
+    };
+    match (&inst.result, expected) {
+      (Some((value, ty)), Some(expected)) if **ty != expected => self.error(format!(
+        "Value {} has type `{}`, but its instruction produces `{}`.", value, **ty, expected
+      )),
+      (Some((value, _)), None) =>
+        self.error(format!("Value {} is defined by an instruction without a result.", value)),
+      (None, Some(expected)) =>
+        self.error(format!("An instruction producing `{}` has no result.", expected)),
+      _ => {},
+    }
+  }
+
+  /**
+   * The type an operation produces, after checking its operands, or
+   * `None` if it produces nothing.
+   */
+  fn op_type(&mut self,
+    op: &IrOp,
+    result_ty: Option<&TypeModel>,
+    block: IrBlockId,
+    position: usize,
+  ) -> Result<Option<TypeModel>, String> {
+    let operand = |verifier: &mut Self, value: IrValue| {
+      verifier.use_value(value, block, position).ok_or_else(String::new)
+    };
+    let ty = match op {
+      IrOp::Const(literal) => literal_type(*literal),
+      IrOp::Load(place) => {
+        let ty = self.place_type(place, block, position)?;
+        if ty.is_runtime_sized() {
+          return Err(format!("A runtime-sized `{}` cannot be loaded.", *ty));
+        }
+        if let IrPlaceRoot::Buffer(name) = &place.root {
+          if self.module.buffer(name).is_some_and(|buffer| !buffer.mode.is_readable()) {
+            return Err(format!("Buffer `{}` is not readable.", name));
+          }
+        }
+        (*ty).clone()
+      },
+      IrOp::Store(place, value) => {
+        let ty = self.place_type(place, block, position)?;
+        let value_ty = operand(self, *value)?;
+        match &place.root {
+          IrPlaceRoot::Uniforms => return Err("The uniforms are not writable.".to_string()),
+          IrPlaceRoot::Buffer(name) if self.module.buffer(name)
+            .is_some_and(|buffer| !buffer.mode.is_writable()) =>
+          {
+            return Err(format!("Buffer `{}` is not writable.", name));
+          },
+          _ => {},
+        }
+        if value_ty != ty {
+          return Err(format!(
+            "Cannot store {} of type `{}` to `{}`, of type `{}`.", value, *value_ty, place, *ty
+          ));
+        }
+        return Ok(None);
+      },
+      IrOp::ArrayLength(place) => {
+        let ty = self.place_type(place, block, position)?;
+        let in_buffer = matches!(place.root, IrPlaceRoot::Buffer(_));
+        if !in_buffer || !matches!(&*ty, TypeModel::Array(array_ty) if array_ty.len.is_none()) {
+          return Err(format!("`{}` is not a runtime-sized array in a buffer.", place));
+        }
+        TypeModel::new_u32()
+      },
+      IrOp::Field(value, field) => {
+        let ty = operand(self, *value)?;
+        match &*ty {
+          TypeModel::Struct(struct_ty) => struct_ty.fields.get(*field as usize)
+            .map(|field| (*field.ty).clone())
+            .ok_or_else(|| format!("`{}` has no field {}.", *ty, field))?,
+          _ => return Err(format!("Cannot take field {} of `{}`.", field, *ty)),
+        }
+      },
+      IrOp::Index(value, index) => {
+        let ty = operand(self, *value)?;
+        let index_ty = operand(self, *index)?;
+        if !index_ty.is_integer_scalar() {
+          return Err(format!("Index {} has type `{}`, not an integer.", index, *index_ty));
+        }
+        match &*ty {
+          TypeModel::Array(array_ty) => (*array_ty.elem).clone(),
+          _ => return Err(format!("Cannot index into `{}`.", *ty)),
+        }
+      },
+      IrOp::Swizzle(value, components) => {
+        let ty = operand(self, *value)?;
+        let TypeModel::Vector(vector_ty) = &*ty else {
+          return Err(format!("Cannot swizzle `{}`.", *ty));
+        };
+        if components.iter().any(|&component| component >= vector_ty.dims as u32) {
+          return Err(format!("Swizzle is out of range for `{}`.", *ty));
+        }
+        match VecDims::from_count(components.len()) {
+          Some(dims) => TypeModel::new_vector(vector_ty.scalar, dims),
+          None if components.len() == 1 => TypeModel::new_scalar(vector_ty.scalar),
+          None => return Err(format!("Cannot swizzle {} components.", components.len())),
+        }
+      },
+      IrOp::Construct(args) => {
+        let mut arg_tys = Vec::new();
+        for &arg in args {
+          arg_tys.push(operand(self, arg)?);
+        }
+        let Some(ty) = result_ty else {
+          return Err("A construction has no result.".to_string());
+        };
+        check_construct(ty, &arg_tys)?;
+        ty.clone()
+      },
+      IrOp::Call(name, args) => {
+        let Some(callee) = self.module.function(name) else {
+          return Err(format!("Function `{}` does not exist.", name));
+        };
+        if callee.kind != IrFunctionKind::Func {
+          return Err(format!("Entrypoint `{}` cannot be called.", name));
+        }
+        if args.len() != callee.params.len() {
+          return Err(format!(
+            "Function `{}` takes {} arguments, but is given {}.",
+            name, callee.params.len(), args.len()
+          ));
+        }
+        for (&arg, param) in args.iter().zip(&callee.params) {
+          let ty = operand(self, arg)?;
+          if ty != param.ty {
+            return Err(format!(
+              "Argument {} to `{}` has type `{}`, but `{}` is expected.",
+              arg, name, *ty, *param.ty
+            ));
+          }
+        }
+        if callee.return_ty.is_void() {
+          return Ok(None);
+        }
+        (*callee.return_ty).clone()
+      },
+      IrOp::Unary(op, value) => {
+        let ty = operand(self, *value)?;
+        let valid = match op {
+          UnaryOpModel::Negate => ty.numeric_element().is_some_and(|s| s.is_signed()),
+          UnaryOpModel::Not => ty.is_bool(),
+          UnaryOpModel::Complement => ty.numeric_element().is_some_and(|s| s.is_integer()),
+        };
+        if !valid {
+          return Err(format!("Cannot apply `{:?}` to `{}`.", op, *ty));
+        }
+        (*ty).clone()
+      },
+      IrOp::Binary(op, lhs, rhs) => {
+        let lhs_ty = operand(self, *lhs)?;
+        let rhs_ty = operand(self, *rhs)?;
+        binary_type(*op, &lhs_ty, &rhs_ty).ok_or_else(|| format!(
+          "Cannot apply `{:?}` to `{}` and `{}`.", op, *lhs_ty, *rhs_ty
+        ))?
+      },
+      IrOp::Cast(value) => {
+        let ty = operand(self, *value)?;
+        let Some(result_ty) = result_ty else {
+          return Err("A cast has no result.".to_string());
+        };
+        let valid = match (&*ty, result_ty) {
+          (TypeModel::Scalar(_), TypeModel::Scalar(_)) =>
+            ty.as_numeric_scalar().is_some() && result_ty.as_numeric_scalar().is_some(),
+          (TypeModel::Vector(from), TypeModel::Vector(to)) => from.dims == to.dims,
+          _ => false,
+        };
+        if !valid {
+          return Err(format!("Cannot cast `{}` to `{}`.", *ty, result_ty));
+        }
+        result_ty.clone()
+      },
+    };
+    Ok(Some(ty))
+  }
+
+  /**
+   * The type of a place, after checking its root and path.
+   */
+  fn place_type(&mut self, place: &IrPlace, block: IrBlockId, position: usize)
+    -> Result<TypeModelHandle, String>
+  {
+    let mut ty = match &place.root {
+      IrPlaceRoot::Local(local) => self.function.locals.get(local.0 as usize)
+        .map(|local| local.ty.clone())
+        .ok_or_else(|| format!("Local {} is not declared.", local))?,
+      IrPlaceRoot::Uniforms => self.module.uniforms.clone()
+        .ok_or_else(|| "The module has no uniforms.".to_string())?,
+      IrPlaceRoot::Buffer(name) => self.module.buffer(name)
+        .map(|buffer| buffer.ty.clone())
+        .ok_or_else(|| format!("Buffer `{}` does not exist.", name))?,
+    };
+    for access in &place.path {
+      ty = match (access, &*ty) {
+        (IrAccess::Field(field), TypeModel::Struct(struct_ty)) =>
+          struct_ty.fields.get(*field as usize).map(|field| field.ty.clone()),
+        (IrAccess::Index(index), TypeModel::Array(array_ty)) => {
+          let index_ty = self.use_value(*index, block, position).ok_or_else(String::new)?;
+          if !index_ty.is_integer_scalar() {
+            return Err(format!("Index {} has type `{}`, not an integer.", index, *index_ty));
+          }
+          Some(array_ty.elem.clone())
+        },
+        (IrAccess::Component(component), TypeModel::Vector(vector_ty))
+          if *component < vector_ty.dims as u32 =>
+        {
+          Some(TypeModelHandle::new(TypeModel::new_scalar(vector_ty.scalar)))
+        },
+        _ => None,
+      }.ok_or_else(|| format!("`{}` is not a valid place.", place))?;
+    }
+    Ok(ty)
+  }
+
+  fn check_terminator(&mut self, id: IrBlockId) {
+    let block = &self.function.blocks[id.0 as usize];
+    match block.terminator {
+      IrTerminator::BranchIf { cond, .. } => {
+        if let Some(ty) = self.use_value(cond, id, END) {
+          if !ty.is_bool() {
+            self.error(format!("Branch condition {} has type `{}`, not `bool`.", cond, *ty));
+          }
+        }
+      },
+      IrTerminator::Return(value) => {
+        let return_ty = &self.function.return_ty;
+        match value {
+          Some(value) => if let Some(ty) = self.use_value(value, id, END) {
+            if ty != *return_ty {
+              self.error(format!(
+                "Returned value {} has type `{}`, but the function returns `{}`.",
+                value, *ty, **return_ty
+              ));
+            }
+          },
+          None if !return_ty.is_void() =>
+            self.error(format!("Return without a value of type `{}`.", **return_ty)),
+          None => {},
+        }
+      },
+      IrTerminator::Branch(_) | IrTerminator::Unreachable => {},
+    }
+    match (block.merge, &block.terminator) {
+      (Some(IrMerge::Selection { .. }), IrTerminator::BranchIf { .. }) |
+      (Some(IrMerge::Loop { .. }), IrTerminator::Branch(_) | IrTerminator::BranchIf { .. }) |
+      (None, _) => {},
+      (Some(_), _) => self.error("A block with a merge marker must end in a branch."),
+    }
+  }
+}
+
+fn literal_type(literal: LiteralModel) -> TypeModel {
+  match literal {
+    LiteralModel::Bool(_) => TypeModel::new_bool(),
+    LiteralModel::I32(_) => TypeModel::new_i32(),
+    LiteralModel::U32(_) => TypeModel::new_u32(),
+    LiteralModel::F32(_) => TypeModel::new_f32(),
+    LiteralModel::F16(_) => TypeModel::new_scalar(ScalarNumericTypeModel::F16),
+    LiteralModel::I64(_) => TypeModel::new_scalar(ScalarNumericTypeModel::I64),
+    LiteralModel::U64(_) => TypeModel::new_scalar(ScalarNumericTypeModel::U64),
+  }
+}
+
+/**
+ * Check the arguments of a construction: the fields of a struct in
+ * order, or scalars and vectors of a vector's element type that make
+ * up its components.
+ */
+fn check_construct(ty: &TypeModel, arg_tys: &[TypeModelHandle]) -> Result<(), String> {
+  match ty {
+    TypeModel::Struct(struct_ty) => {
+      let matches = arg_tys.len() == struct_ty.fields.len() &&
+        arg_tys.iter().zip(&struct_ty.fields).all(|(arg_ty, field)| *arg_ty == field.ty);
+      if !matches {
+        return Err(format!("The arguments do not match the fields of `{}`.", ty));
+      }
+    },
+    TypeModel::Vector(vector_ty) => {
+      let mut count = 0;
+      for arg_ty in arg_tys {
+        count += match &**arg_ty {
+          TypeModel::Vector(arg) if arg.scalar == vector_ty.scalar => arg.dims as u32,
+          _ if arg_ty.as_numeric_scalar() == Some(vector_ty.scalar) => 1,
+          _ => return Err(format!("Cannot construct `{}` from `{}`.", ty, **arg_ty)),
+        };
+      }
+      if count != vector_ty.dims as u32 {
+        return Err(format!("`{}` is constructed from {} components.", ty, count));
+      }
+    },
+    _ => return Err(format!("Cannot construct `{}`.", ty)),
+  }
+  Ok(())
+}
+
+/**
+ * The type of a binary operation's result, if its operands suit it.
+ * A scalar operand of a vector or matrix operation applies to each
+ * component.
+ */
+fn binary_type(op: BinaryOpModel, lhs: &TypeModel, rhs: &TypeModel) -> Option<TypeModel> {
+  if matches!(op, BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr) {
+    return (lhs.is_bool() && rhs.is_bool()).then(TypeModel::new_bool);
+  }
+  if op.is_comparison() {
+    let ordered = !matches!(op, BinaryOpModel::Equal | BinaryOpModel::NotEqual);
+    let valid = lhs == rhs && (lhs.as_numeric_scalar().is_some() || !ordered && lhs.is_bool());
+    return valid.then(TypeModel::new_bool);
+  }
+  if matches!(op, BinaryOpModel::Shl | BinaryOpModel::Shr) {
+    lhs.numeric_element().filter(|scalar| scalar.is_integer())?;
+    let amount = match (lhs, rhs) {
+      (TypeModel::Vector(l), TypeModel::Vector(r)) => l.dims == r.dims &&
+        r.scalar == ScalarNumericTypeModel::U32,
+      _ => rhs.as_numeric_scalar() == Some(ScalarNumericTypeModel::U32),
+    };
+    return amount.then(|| lhs.clone());
+  }
+  match (lhs, rhs) {
+    (TypeModel::Matrix(l), TypeModel::Matrix(r)) if op == BinaryOpModel::Mul =>
+      (l.scalar == r.scalar && l.cols == r.rows)
+        .then(|| TypeModel::new_matrix(l.scalar, r.cols, l.rows)),
+    (TypeModel::Matrix(m), TypeModel::Vector(v)) if op == BinaryOpModel::Mul =>
+      (m.scalar == v.scalar && m.cols == v.dims).then(|| TypeModel::new_vector(m.scalar, m.rows)),
+    (TypeModel::Vector(v), TypeModel::Matrix(m)) if op == BinaryOpModel::Mul =>
+      (m.scalar == v.scalar && m.rows == v.dims).then(|| TypeModel::new_vector(m.scalar, m.cols)),
+    (TypeModel::Matrix(m), _) | (_, TypeModel::Matrix(m)) if lhs == rhs ||
+      op == BinaryOpModel::Mul &&
+        [lhs, rhs].iter().any(|ty| ty.as_numeric_scalar() == Some(m.scalar)) =>
+    {
+      Some(TypeModel::Matrix(m.clone()))
+    },
+    (TypeModel::Matrix(_), _) | (_, TypeModel::Matrix(_)) => None,
+    _ => {
+      let lhs_scalar = lhs.numeric_element()?;
+      if lhs_scalar != rhs.numeric_element()? {
+        return None;
+      }
+      match (lhs, rhs) {
+        (TypeModel::Vector(l), TypeModel::Vector(r)) => (l.dims == r.dims).then(|| lhs.clone()),
+        (TypeModel::Vector(_), _) => Some(lhs.clone()),
+        _ => Some(rhs.clone()),
+      }
+    },
+  }
+}
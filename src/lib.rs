@@ -1,6 +1,18 @@
 pub mod syntax;
 pub mod transform;
 pub mod model;
+pub mod reflect;
+pub mod host_gen;
+pub mod data;
+pub mod backend;
+pub mod interpret;
+/**
+ * A static single assignment form of checked shaders, with its lowering,
+ * verifier and textual form.  No backend is built on it yet, so it is a
+ * debugging aid for tests only, and not part of the public API.
+ */
+#[cfg(test)]
+mod ir;
 
 #[cfg(test)]
 mod tests;
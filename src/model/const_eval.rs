@@ -0,0 +1,224 @@
+use std::fmt;
+use crate::model::{
+  BinaryOpModel,
+  ExpressionModel,
+  ExpressionModelKind,
+  LiteralModel,
+  ScalarNumericTypeModel,
+  TypeModel,
+  UnaryOpModel,
+};
+
+/**
+ * An operation whose result differs between targets, which is an error
+ * both when folding constants and when interpreting.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EvalError {
+  DivisionByZero,
+
+  /** An integer of `bits` bits was shifted by at least its width. */
+  ShiftOutOfRange { amount: u32, bits: u32 },
+}
+impl fmt::Display for EvalError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      EvalError::DivisionByZero => write!(f, "Integer division by zero."),
+      EvalError::ShiftOutOfRange { amount, bits } =>
+        write!(f, "Cannot shift a {}-bit integer by {}.", bits, amount),
+    }
+  }
+}
+
+/**
+ * Evaluate an expression at compile time, if it is a scalar built only
+ * from literals, e.g. `(1 << 4) * 3`.  Returns `None` for expressions
+ * that depend on anything else.
+ *
+ * Evaluation follows the language exactly, as the interpreter does, so
+ * folding never changes a result.  Operations whose results differ
+ * between targets, e.g. integer division by zero, are errors.
+ */
+pub(crate) fn const_eval(expr: &ExpressionModel)
+  -> Result<Option<LiteralModel>, EvalError>
+{
+  if !matches!(&*expr.ty, TypeModel::Scalar(_)) {
+    return Ok(None);
+  }
+  let value = match &expr.kind {
+    ExpressionModelKind::Literal(literal) => *literal,
+    ExpressionModelKind::Unary(unary_expr) => {
+      let Some(operand) = const_eval(&unary_expr.subexpr)? else {
+        return Ok(None);
+      };
+      unary(unary_expr.op, operand)
+    },
+    ExpressionModelKind::Binary(binary_expr) => {
+      let Some(lhs) = const_eval(&binary_expr.lhs)? else {
+        return Ok(None);
+      };
+      let Some(rhs) = const_eval(&binary_expr.rhs)? else {
+        return Ok(None);
+      };
+      binary(binary_expr.op, lhs, rhs)?
+    },
+    ExpressionModelKind::Cast(cast_expr) => {
+      let Some(operand) = const_eval(&cast_expr.subexpr)? else {
+        return Ok(None);
+      };
+      let to = expr.ty.as_numeric_scalar().expect("Cast to a numeric scalar");
+      cast(operand, to)
+    },
+    _ => return Ok(None),
+  };
+  Ok(Some(value))
+}
+
+/**
+ * A numeric scalar, widened so that every operation on its type can be
+ * done exactly and then narrowed back.
+ */
+#[derive(Debug, Clone, Copy)]
+enum Number {
+  Int(i128),
+  Float(f32),
+}
+
+fn number(literal: LiteralModel) -> (ScalarNumericTypeModel, Number) {
+  match literal {
+    LiteralModel::Bool(_) => panic!("Expected a numeric scalar, found a bool"),
+    LiteralModel::I32(v) => (ScalarNumericTypeModel::I32, Number::Int(v as i128)),
+    LiteralModel::U32(v) => (ScalarNumericTypeModel::U32, Number::Int(v as i128)),
+    LiteralModel::I64(v) => (ScalarNumericTypeModel::I64, Number::Int(v as i128)),
+    LiteralModel::U64(v) => (ScalarNumericTypeModel::U64, Number::Int(v as i128)),
+    LiteralModel::F32(bits) => (ScalarNumericTypeModel::F32, Number::Float(f32::from_bits(bits))),
+    LiteralModel::F16(bits) => (ScalarNumericTypeModel::F16, Number::Float(f32::from_bits(bits))),
+  }
+}
+
+/**
+ * Narrow a number to a scalar type.  Integers wrap, keeping their low
+ * bits, and `f16` values are rounded.
+ */
+fn literal(scalar: ScalarNumericTypeModel, number: Number) -> LiteralModel {
+  match (scalar, number) {
+    (ScalarNumericTypeModel::I32, Number::Int(v)) => LiteralModel::I32(v as i32),
+    (ScalarNumericTypeModel::U32, Number::Int(v)) => LiteralModel::U32(v as u32),
+    (ScalarNumericTypeModel::I64, Number::Int(v)) => LiteralModel::I64(v as i64),
+    (ScalarNumericTypeModel::U64, Number::Int(v)) => LiteralModel::U64(v as u64),
+    (ScalarNumericTypeModel::F32, Number::Float(v)) => LiteralModel::new_f32(v),
+    (ScalarNumericTypeModel::F16, Number::Float(v)) => LiteralModel::new_f16(v),
+    _ => panic!("Number does not match type `{}`", scalar),
+  }
+}
+
+/**
+ * Convert a numeric scalar as an `as` cast does.
+ */
+pub(crate) fn cast(literal: LiteralModel, to: ScalarNumericTypeModel) -> LiteralModel {
+  literal.cast(to).expect("Cast of a numeric scalar")
+}
+
+/**
+ * The value of an integer scalar, e.g. an index.
+ */
+pub(crate) fn int_value(literal: LiteralModel) -> i128 {
+  match number(literal) {
+    (_, Number::Int(v)) => v,
+    (scalar, Number::Float(_)) => panic!("Expected an integer, found `{}`", scalar),
+  }
+}
+
+pub(crate) fn unary(op: UnaryOpModel, operand: LiteralModel) -> LiteralModel {
+  if let LiteralModel::Bool(v) = operand {
+    assert_eq!(op, UnaryOpModel::Not, "Unary {:?} of a bool", op);
+    return LiteralModel::Bool(!v);
+  }
+  let (scalar, value) = number(operand);
+  let result = match (op, value) {
+    (UnaryOpModel::Negate, Number::Int(v)) => Number::Int(-v),
+    (UnaryOpModel::Negate, Number::Float(v)) => Number::Float(-v),
+    (UnaryOpModel::Complement, Number::Int(v)) => Number::Int(!v),
+    _ => panic!("Cannot apply unary {:?} to `{}`", op, scalar),
+  };
+  literal(scalar, result)
+}
+
+/**
+ * Apply a binary operation to scalars.  Integer arithmetic wraps, and
+ * remainders truncate, taking the sign of the dividend.  Integer
+ * division by zero and shifts by at least the bit width are errors,
+ * since their results differ between targets.
+ */
+pub(crate) fn binary(op: BinaryOpModel, lhs: LiteralModel, rhs: LiteralModel)
+  -> Result<LiteralModel, EvalError>
+{
+  if let (LiteralModel::Bool(a), LiteralModel::Bool(b)) = (lhs, rhs) {
+    let result = match op {
+      BinaryOpModel::Equal => a == b,
+      BinaryOpModel::NotEqual => a != b,
+      BinaryOpModel::LogicalAnd => a && b,
+      BinaryOpModel::LogicalOr => a || b,
+      _ => panic!("Cannot apply {:?} to bools", op),
+    };
+    return Ok(LiteralModel::Bool(result));
+  }
+
+  let (scalar, a) = number(lhs);
+  let (_, b) = number(rhs);
+  if op.is_comparison() {
+    let result = match (a, b) {
+      (Number::Int(a), Number::Int(b)) => compare(op, a, b),
+      (Number::Float(a), Number::Float(b)) => compare(op, a, b),
+      _ => panic!("Mismatched operands of {:?}", op),
+    };
+    return Ok(LiteralModel::Bool(result));
+  }
+  let result = match (a, b) {
+    (Number::Int(a), Number::Int(b)) => Number::Int(match op {
+      BinaryOpModel::Add => a + b,
+      BinaryOpModel::Sub => a - b,
+      // The low bits of a wrapped product are still exact.
+      BinaryOpModel::Mul => a.wrapping_mul(b),
+      BinaryOpModel::Div | BinaryOpModel::Mod if b == 0 =>
+        return Err(EvalError::DivisionByZero),
+      BinaryOpModel::Div => a / b,
+      BinaryOpModel::Mod => a % b,
+      BinaryOpModel::BitAnd => a & b,
+      BinaryOpModel::BitOr => a | b,
+      BinaryOpModel::BitXor => a ^ b,
+      BinaryOpModel::Shl | BinaryOpModel::Shr => {
+        let bits = scalar.size() * 8;
+        if b >= bits as i128 {
+          return Err(EvalError::ShiftOutOfRange { amount: b as u32, bits });
+        }
+        // Signed values are sign-extended, so this shift is arithmetic
+        // for them and logical for unsigned values.
+        if op == BinaryOpModel::Shl { a << b } else { a >> b }
+      },
+      _ => panic!("Cannot apply {:?} to integers", op),
+    }),
+    (Number::Float(a), Number::Float(b)) => Number::Float(match op {
+      BinaryOpModel::Add => a + b,
+      BinaryOpModel::Sub => a - b,
+      BinaryOpModel::Mul => a * b,
+      BinaryOpModel::Div => a / b,
+      BinaryOpModel::Mod => a % b,
+      _ => panic!("Cannot apply {:?} to floats", op),
+    }),
+    _ => panic!("Mismatched operands of {:?}", op),
+  };
+  Ok(literal(scalar, result))
+}
+
+fn compare<T: PartialOrd>(op: BinaryOpModel, a: T, b: T) -> bool {
+  match op {
+    BinaryOpModel::LessThan => a < b,
+    BinaryOpModel::LessThanOrEqual => a <= b,
+    BinaryOpModel::GreaterThan => a > b,
+    BinaryOpModel::GreaterThanOrEqual => a >= b,
+    BinaryOpModel::Equal => a == b,
+    BinaryOpModel::NotEqual => a != b,
+    _ => unreachable!("{:?} is not a comparison", op),
+  }
+}
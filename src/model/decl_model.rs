@@ -0,0 +1,180 @@
+use crate::{
+  model::{
+    EntrypointDims,
+    LayoutError,
+    LayoutRules,
+    NameModelHandle,
+    StatementModel,
+    TypeLayout,
+    TypeModelHandle,
+  },
+  syntax::declaration::BufferDeclMode,
+};
+
+/**
+ * A type-checked entrypoint.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntrypointModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) dims: EntrypointDims,
+  pub(crate) workgroup_size: [u32; 3],
+  pub(crate) arg_name: NameModelHandle,
+  pub(crate) body: Vec<StatementModel>,
+}
+impl EntrypointModel {
+  pub fn name(&self) -> &str {
+    &self.name.name
+  }
+
+  pub fn dims(&self) -> EntrypointDims {
+    self.dims
+  }
+
+  /**
+   * The number of invocations in each workgroup along each axis.
+   */
+  pub fn workgroup_size(&self) -> [u32; 3] {
+    self.workgroup_size
+  }
+}
+
+/**
+ * A type-checked test.  Its body runs on the interpreter, and is not
+ * part of any generated shader.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) body: Vec<StatementModel>,
+}
+impl TestModel {
+  pub fn name(&self) -> &str {
+    &self.name.name
+  }
+}
+
+/**
+ * A buffer.  The storage type is runtime-sized: either a runtime-sized
+ * array, or a struct whose last field is one.  A buffer declared with
+ * a sized type `T` is stored as `[T]`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BufferModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) mode: BufferAccessMode,
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) binding: ResourceBindingModel,
+}
+impl BufferModel {
+  pub fn name(&self) -> &str {
+    &self.name.name
+  }
+
+  pub fn mode(&self) -> BufferAccessMode {
+    self.mode
+  }
+
+  /**
+   * The storage type of the buffer.
+   */
+  pub fn ty(&self) -> &TypeModelHandle {
+    &self.ty
+  }
+
+  /**
+   * The memory layout of the buffer, under storage rules.
+   */
+  pub fn layout(&self) -> Result<TypeLayout, LayoutError> {
+    self.ty.layout(LayoutRules::Storage)
+  }
+
+  pub fn binding(&self) -> ResourceBindingModel {
+    self.binding
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferAccessMode {
+  Read,
+  Write,
+  ReadWrite,
+}
+impl BufferAccessMode {
+  pub fn from_decl_mode(decl_mode: BufferDeclMode) -> Self {
+    match decl_mode {
+      BufferDeclMode::Read => Self::Read,
+      BufferDeclMode::Write => Self::Write,
+      BufferDeclMode::ReadWrite => Self::ReadWrite,
+    }
+  }
+
+  pub fn is_readable(self) -> bool {
+    matches!(self, Self::Read | Self::ReadWrite)
+  }
+
+  pub fn is_writable(self) -> bool {
+    matches!(self, Self::Write | Self::ReadWrite)
+  }
+}
+
+/**
+ * The uniforms of a shader file, held as an implicitly named struct.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UniformsModel {
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) binding: ResourceBindingModel,
+}
+impl UniformsModel {
+  pub fn ty(&self) -> &TypeModelHandle {
+    &self.ty
+  }
+
+  /**
+   * The memory layout of the uniforms, under uniform rules.
+   */
+  pub fn layout(&self) -> Result<TypeLayout, LayoutError> {
+    self.ty.layout(LayoutRules::Uniform)
+  }
+
+  pub fn binding(&self) -> ResourceBindingModel {
+    self.binding
+  }
+}
+
+/**
+ * The bind group and binding slot of a buffer or the uniforms, either
+ * declared with `@group(g) @binding(b)` or assigned automatically.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceBindingModel {
+  pub(crate) group: u32,
+  pub(crate) binding: u32,
+}
+impl ResourceBindingModel {
+  pub fn group(&self) -> u32 {
+    self.group
+  }
+
+  pub fn binding(&self) -> u32 {
+    self.binding
+  }
+}
+
+/**
+ * A type-checked function.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) args: Vec<FuncArgModel>,
+  pub(crate) return_ty: TypeModelHandle,
+  pub(crate) body: Vec<StatementModel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncArgModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) ty: TypeModelHandle,
+}
@@ -15,6 +15,18 @@ impl EntrypointDims {
       EntrypointDeclDims::D3 => Self::D3,
     }
   }
+
+  /**
+   * The default workgroup size of entrypoints of these dimensions, with
+   * 64 invocations per workgroup spread over the used axes.
+   */
+  pub fn workgroup_size(self) -> [u32; 3] {
+    match self {
+      Self::D1 => [64, 1, 1],
+      Self::D2 => [8, 8, 1],
+      Self::D3 => [4, 4, 4],
+    }
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -24,3 +36,13 @@ pub enum VecDims {
   Vec3 = 3,
   Vec4 = 4,
 }
+impl VecDims {
+  pub fn from_count(count: usize) -> Option<Self> {
+    match count {
+      2 => Some(Self::Vec2),
+      3 => Some(Self::Vec3),
+      4 => Some(Self::Vec4),
+      _ => None,
+    }
+  }
+}
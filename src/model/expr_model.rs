@@ -0,0 +1,328 @@
+use crate::model::{
+  NameModelHandle,
+  ScalarNumericTypeModel,
+  TypeModelHandle,
+  f16_bits_from_f32,
+  f32_from_f16_bits,
+};
+
+/**
+ * A type-checked expression.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExpressionModel {
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) kind: ExpressionModelKind,
+}
+impl ExpressionModel {
+  pub(crate) fn new(ty: TypeModelHandle, kind: ExpressionModelKind)
+    -> ExpressionModel
+  {
+    ExpressionModel { ty, kind }
+  }
+
+  pub(crate) fn boxed(self) -> Box<Self> {
+    Box::new(self)
+  }
+
+  /**
+   * Whether evaluating the expression calls a function.
+   */
+  pub(crate) fn has_call(&self) -> bool {
+    match &self.kind {
+      ExpressionModelKind::Call(_) => true,
+      ExpressionModelKind::Literal(_) |
+      ExpressionModelKind::Local(_) |
+      ExpressionModelKind::Uniforms |
+      ExpressionModelKind::Buffer(_) => false,
+      ExpressionModelKind::Index(index_expr) =>
+        index_expr.target.has_call() || index_expr.index.has_call(),
+      ExpressionModelKind::ArrayLength(length_expr) => length_expr.target.has_call(),
+      ExpressionModelKind::Field(field_expr) => field_expr.target.has_call(),
+      ExpressionModelKind::Swizzle(swizzle_expr) => swizzle_expr.target.has_call(),
+      ExpressionModelKind::Construct(construct_expr) =>
+        construct_expr.args.iter().any(ExpressionModel::has_call),
+      ExpressionModelKind::Unary(unary_expr) => unary_expr.subexpr.has_call(),
+      ExpressionModelKind::Binary(binary_expr) =>
+        binary_expr.lhs.has_call() || binary_expr.rhs.has_call(),
+      ExpressionModelKind::Cast(cast_expr) => cast_expr.subexpr.has_call(),
+    }
+  }
+}
+
+/**
+ * The kinds of type-checked expressions.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExpressionModelKind {
+  Literal(LiteralModel),
+  Local(NameModelHandle),
+  Uniforms,
+  Buffer(NameModelHandle),
+  Index(IndexExprModel),
+  ArrayLength(ArrayLengthExprModel),
+  Field(FieldExprModel),
+  Swizzle(SwizzleExprModel),
+  Construct(ConstructExprModel),
+  Call(CallExprModel),
+  Unary(UnaryExprModel),
+  Binary(BinaryExprModel),
+  Cast(CastExprModel),
+}
+
+/**
+ * A literal value.  Floats are held by their bit pattern so that
+ * the model remains `Eq` and `Hash`.  `f16` values are held as the
+ * bits of the equivalent `f32`, rounded to an `f16` by `new_f16`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiteralModel {
+  Bool(bool),
+  I32(i32),
+  U32(u32),
+  F32(u32),
+  F16(u32),
+  I64(i64),
+  U64(u64),
+}
+impl LiteralModel {
+  pub fn new_f32(value: f32) -> LiteralModel {
+    LiteralModel::F32(value.to_bits())
+  }
+
+  /**
+   * An `f16` literal of the nearest `f16` to a value.  Arithmetic on
+   * `f16` is done in `f32` and then rounded, which is exact for the basic
+   * operations since `f32` has more than twice the precision.
+   */
+  pub fn new_f16(value: f32) -> LiteralModel {
+    LiteralModel::F16(f32_from_f16_bits(f16_bits_from_f32(value)).to_bits())
+  }
+
+  /**
+   * Whether the literal is not an infinite or NaN float.
+   */
+  pub fn is_finite(self) -> bool {
+    match self {
+      LiteralModel::F32(bits) | LiteralModel::F16(bits) =>
+        f32::from_bits(bits).is_finite(),
+      _ => true,
+    }
+  }
+
+  /**
+   * Convert a numeric literal to another numeric scalar type, following
+   * the semantics of `as` casts:
+   *
+   * - Between integer types the bits are truncated or sign-extended, so
+   *   sign changes wrap (e.g. `-1_i32 as u32` is `0xFFFF_FFFF`).
+   * - From a float to an integer the value is truncated toward zero and
+   *   saturated to the integer's range, and NaN becomes zero.
+   * - From an integer to a float the value is rounded to nearest.
+   *
+   * Returns `None` for boolean literals.
+   */
+  pub fn cast(self, to: ScalarNumericTypeModel) -> Option<LiteralModel> {
+    // Every integer fits in an `i128`, and every float in an `f64`, so
+    // Rust's `as` from these gives the same result as a direct cast.
+    enum Value { Int(i128), Float(f64) }
+    let value = match self {
+      LiteralModel::Bool(_) => return None,
+      LiteralModel::I32(v) => Value::Int(v as i128),
+      LiteralModel::U32(v) => Value::Int(v as i128),
+      LiteralModel::I64(v) => Value::Int(v as i128),
+      LiteralModel::U64(v) => Value::Int(v as i128),
+      LiteralModel::F32(bits) | LiteralModel::F16(bits) =>
+        Value::Float(f32::from_bits(bits) as f64),
+    };
+    let result = match (value, to) {
+      (Value::Int(v), ScalarNumericTypeModel::I32) => LiteralModel::I32(v as i32),
+      (Value::Int(v), ScalarNumericTypeModel::U32) => LiteralModel::U32(v as u32),
+      (Value::Int(v), ScalarNumericTypeModel::I64) => LiteralModel::I64(v as i64),
+      (Value::Int(v), ScalarNumericTypeModel::U64) => LiteralModel::U64(v as u64),
+      (Value::Int(v), ScalarNumericTypeModel::F32) =>
+        LiteralModel::new_f32(v as f32),
+      (Value::Int(v), ScalarNumericTypeModel::F16) =>
+        LiteralModel::new_f16(v as f32),
+
+      // Rust's float-to-int `as` truncates, saturates and maps NaN to 0.
+      (Value::Float(v), ScalarNumericTypeModel::I32) => LiteralModel::I32(v as i32),
+      (Value::Float(v), ScalarNumericTypeModel::U32) => LiteralModel::U32(v as u32),
+      (Value::Float(v), ScalarNumericTypeModel::I64) => LiteralModel::I64(v as i64),
+      (Value::Float(v), ScalarNumericTypeModel::U64) => LiteralModel::U64(v as u64),
+      (Value::Float(v), ScalarNumericTypeModel::F32) =>
+        LiteralModel::new_f32(v as f32),
+      (Value::Float(v), ScalarNumericTypeModel::F16) =>
+        LiteralModel::new_f16(v as f32),
+    };
+    Some(result)
+  }
+}
+
+/**
+ * An indexed access of an array element.  Buffers are indexed through
+ * their storage array.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexExprModel {
+  pub(crate) target: Box<ExpressionModel>,
+  pub(crate) index: Box<ExpressionModel>,
+}
+
+/**
+ * The element count of a runtime-sized array, e.g. `birds.length`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArrayLengthExprModel {
+  pub(crate) target: Box<ExpressionModel>,
+}
+
+/**
+ * A struct field access, by field position.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldExprModel {
+  pub(crate) target: Box<ExpressionModel>,
+  pub(crate) field: u32,
+}
+
+/**
+ * A vector swizzle, selecting components by position.  A single
+ * component yields a scalar, several yield a vector of that size.
+ *
+ * E.g. `v.0`, `v.y` or `v.zyx`
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SwizzleExprModel {
+  pub(crate) target: Box<ExpressionModel>,
+  pub(crate) components: Vec<u32>,
+}
+impl SwizzleExprModel {
+  /**
+   * The swizzle's components as letters, e.g. `zyx`.
+   */
+  pub fn component_letters(&self) -> String {
+    self.components.iter()
+      .map(|&component| SWIZZLE_LETTERS[component as usize])
+      .collect()
+  }
+
+  /**
+   * Parse swizzle letters into component positions.
+   */
+  pub fn parse_components(letters: &str) -> Option<Vec<u32>> {
+    if letters.is_empty() || letters.len() > 4 {
+      return None;
+    }
+    letters.chars()
+      .map(|letter| {
+        SWIZZLE_LETTERS.iter()
+          .position(|&l| l == letter)
+          .map(|position| position as u32)
+      })
+      .collect()
+  }
+
+  /**
+   * Whether any component is selected more than once.
+   */
+  pub fn has_repeats(&self) -> bool {
+    self.components.iter()
+      .enumerate()
+      .any(|(i, component)| self.components[..i].contains(component))
+  }
+}
+
+pub(crate) const SWIZZLE_LETTERS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/**
+ * Construction of a struct or vector value.  Struct arguments are in
+ * field declaration order.  Vector arguments are scalars or vectors of
+ * the element type whose components add up to the vector's size.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConstructExprModel {
+  pub(crate) args: Vec<ExpressionModel>,
+}
+
+/**
+ * A function call.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallExprModel {
+  pub(crate) func: NameModelHandle,
+  pub(crate) args: Vec<ExpressionModel>,
+}
+
+/**
+ * A unary operation.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnaryExprModel {
+  pub(crate) op: UnaryOpModel,
+  pub(crate) subexpr: Box<ExpressionModel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOpModel {
+  Negate,
+  Not,
+  Complement,
+}
+
+/**
+ * A numeric conversion to the expression's type.  The source and
+ * target are both scalars, or both vectors of the same size, and each
+ * component converts as described by `LiteralModel::cast`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CastExprModel {
+  pub(crate) subexpr: Box<ExpressionModel>,
+}
+
+/**
+ * A binary operation.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BinaryExprModel {
+  pub(crate) lhs: Box<ExpressionModel>,
+  pub(crate) op: BinaryOpModel,
+  pub(crate) rhs: Box<ExpressionModel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOpModel {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
+  BitAnd,
+  BitOr,
+  BitXor,
+  Shl,
+  Shr,
+  LessThan,
+  LessThanOrEqual,
+  GreaterThan,
+  GreaterThanOrEqual,
+  Equal,
+  NotEqual,
+  LogicalAnd,
+  LogicalOr,
+}
+impl BinaryOpModel {
+  /**
+   * Whether the operation produces a boolean from its operands.
+   */
+  pub fn is_comparison(self) -> bool {
+    matches!(self,
+      BinaryOpModel::LessThan |
+      BinaryOpModel::LessThanOrEqual |
+      BinaryOpModel::GreaterThan |
+      BinaryOpModel::GreaterThanOrEqual |
+      BinaryOpModel::Equal |
+      BinaryOpModel::NotEqual
+    )
+  }
+}
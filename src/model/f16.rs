@@ -0,0 +1,53 @@
+/**
+ * Convert an `f32` to the bits of the nearest `f16`, rounding ties to
+ * even.  Values too large for `f16` become infinite.
+ */
+pub(crate) fn f16_bits_from_f32(value: f32) -> u16 {
+  let bits = value.to_bits();
+  let sign = ((bits >> 16) & 0x8000) as u16;
+  let exp = ((bits >> 23) & 0xff) as i32;
+  let mant = bits & 0x7f_ffff;
+  if exp == 0xff {
+    let nan = if mant != 0 { 0x200 } else { 0 };
+    return sign | 0x7c00 | nan;
+  }
+
+  // Round away the low `shift` bits of the mantissa, with its implicit
+  // leading bit if normal.  A carry out of the mantissa correctly bumps
+  // the exponent, up to infinity.
+  let round = |mant: u32, shift: u32| {
+    let kept = mant >> shift;
+    let rest = mant & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    if rest > half || (rest == half && kept & 1 == 1) { kept + 1 } else { kept }
+  };
+  let exp = exp - 127 + 15;
+  if exp >= 0x1f {
+    sign | 0x7c00
+  } else if exp <= 0 {
+    // Subnormal, as a multiple of 2^-24.
+    if exp < -10 {
+      return sign;
+    }
+    sign | round(mant | 0x80_0000, (14 - exp) as u32) as u16
+  } else {
+    sign | round(((exp as u32) << 23) | mant, 13) as u16
+  }
+}
+
+/**
+ * Convert the bits of an `f16` to an `f32`, which is exact.
+ */
+pub(crate) fn f32_from_f16_bits(bits: u16) -> f32 {
+  let sign = ((bits & 0x8000) as u32) << 16;
+  let exp = ((bits >> 10) & 0x1f) as u32;
+  let mant = (bits & 0x3ff) as u32;
+  match exp {
+    0 => {
+      let magnitude = mant as f32 * f32::powi(2.0, -24);
+      if sign != 0 { -magnitude } else { magnitude }
+    },
+    0x1f => f32::from_bits(sign | 0x7f80_0000 | (mant << 13)),
+    _ => f32::from_bits(sign | ((exp + 127 - 15) << 23) | (mant << 13)),
+  }
+}
@@ -0,0 +1,387 @@
+use std::fmt;
+use crate::model::{
+  NameModel,
+  ScalarNumericTypeModel,
+  ScalarTypeModel,
+  TypeModel,
+  VecDims,
+};
+
+/**
+ * The rules used to lay out host-shareable data in memory.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayoutRules {
+  /** Storage buffer rules (std430). */
+  Storage,
+
+  /**
+   * Uniform buffer rules (std140).  Array strides and struct alignments
+   * are rounded up to 16 bytes, and matrices are laid out as arrays of
+   * their column vectors.
+   */
+  Uniform,
+}
+impl LayoutRules {
+  /**
+   * Round an array stride or struct alignment up as the rules require.
+   */
+  fn round_aggregate_align(self, align: u32) -> Option<u32> {
+    match self {
+      LayoutRules::Storage => Some(align),
+      LayoutRules::Uniform => round_up(align, 16),
+    }
+  }
+}
+
+/**
+ * The memory layout of a type.  Runtime-sized arrays contribute no
+ * elements to the size; see `size_with_elements`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout {
+  pub(crate) size: u32,
+  pub(crate) align: u32,
+  pub(crate) kind: TypeLayoutKind,
+}
+impl TypeLayout {
+  /**
+   * Compute the layout of a type under the given rules.  Sizes and
+   * offsets must fit in a `u32`.
+   */
+  pub fn of(ty: &TypeModel, rules: LayoutRules) -> Result<TypeLayout, LayoutError> {
+    let too_large = || LayoutError::TooLarge(ty.to_string());
+    match ty {
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => {
+        let size = scalar.size();
+        Ok(TypeLayout { size, align: size, kind: TypeLayoutKind::Scalar })
+      },
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(_)) =>
+        Err(LayoutError::NotHostShareable(ty.to_string())),
+      TypeModel::Vector(vector_ty) => Ok(Self::vector(vector_ty.scalar, vector_ty.dims)),
+      TypeModel::Matrix(matrix_ty) => {
+        let column = Self::vector(matrix_ty.scalar, matrix_ty.rows);
+        let column_stride = match rules {
+          LayoutRules::Storage => column.align,
+          LayoutRules::Uniform => round_up(column.align, 16).ok_or_else(too_large)?,
+        };
+        Ok(TypeLayout {
+          size: column_stride * matrix_ty.cols as u32,
+          align: column_stride,
+          kind: TypeLayoutKind::Matrix(MatrixLayout { column_stride }),
+        })
+      },
+      TypeModel::Array(array_ty) => {
+        let elem = TypeLayout::of(&array_ty.elem, rules)?;
+        let align = rules.round_aggregate_align(elem.align).ok_or_else(too_large)?;
+        let stride = round_up(elem.size, align).ok_or_else(too_large)?;
+        Ok(TypeLayout {
+          size: stride.checked_mul(array_ty.len.unwrap_or(0)).ok_or_else(too_large)?,
+          align,
+          kind: TypeLayoutKind::Array(ArrayLayout {
+            elem: Box::new(elem),
+            stride,
+            len: array_ty.len,
+          }),
+        })
+      },
+      TypeModel::Struct(struct_ty) => {
+        let mut fields: Vec<FieldLayout> = Vec::new();
+        let mut offset = 0;
+        let mut align = 1;
+        for field in &struct_ty.fields {
+          let layout = TypeLayout::of(&field.ty, rules)?;
+          let required_align = if struct_ty.packed {
+            min_align(&field.ty)
+          } else {
+            layout.align
+          };
+          let field_align = match field.align {
+            Some(field_align) if field_align % required_align != 0 =>
+              return Err(LayoutError::AlignTooSmall {
+                field: format!("{}::{}", struct_ty.name, field.name),
+                align: field_align,
+                required: required_align,
+              }),
+            Some(field_align) => field_align,
+            None => required_align,
+          };
+          let field_size = match field.size {
+            Some(field_size) if field_size < layout.size =>
+              return Err(LayoutError::SizeTooSmall {
+                field: format!("{}::{}", struct_ty.name, field.name),
+                size: field_size,
+                required: layout.size,
+              }),
+            Some(field_size) => field_size,
+            None => layout.size,
+          };
+
+          // Padding runs from the end of the previous field's data, so
+          // it includes any extra bytes from `@size`.
+          let field_offset = round_up(offset, field_align).ok_or_else(too_large)?;
+          if let Some(prev) = fields.last_mut() {
+            prev.padding = field_offset - (prev.offset + prev.layout.size);
+          }
+          offset = field_offset.checked_add(field_size).ok_or_else(too_large)?;
+          align = align.max(field_align);
+          fields.push(FieldLayout {
+            name: field.name.clone(),
+            offset: field_offset,
+            padding: 0,
+            layout,
+          });
+        }
+        let align = rules.round_aggregate_align(align).ok_or_else(too_large)?;
+        let size = round_up(offset, align).ok_or_else(too_large)?;
+        if let Some(last) = fields.last_mut() {
+          last.padding = size - (last.offset + last.layout.size);
+        }
+        Ok(TypeLayout {
+          size,
+          align,
+          kind: TypeLayoutKind::Struct(StructLayout { fields }),
+        })
+      },
+    }
+  }
+
+  fn vector(scalar: ScalarNumericTypeModel, dims: VecDims) -> TypeLayout {
+    let scalar_size = scalar.size();
+    let align = match dims {
+      VecDims::Vec2 => 2 * scalar_size,
+      VecDims::Vec3 | VecDims::Vec4 => 4 * scalar_size,
+    };
+    TypeLayout {
+      size: scalar_size * dims as u32,
+      align,
+      kind: TypeLayoutKind::Vector,
+    }
+  }
+
+  /**
+   * The size in bytes.
+   */
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  /**
+   * The alignment in bytes.
+   */
+  pub fn align(&self) -> u32 {
+    self.align
+  }
+
+  pub fn kind(&self) -> &TypeLayoutKind {
+    &self.kind
+  }
+
+  /**
+   * The size in bytes when the runtime-sized array at the end of this
+   * type, if any, holds `count` elements.
+   */
+  pub fn size_with_elements(&self, count: u32) -> Result<u32, LayoutError> {
+    let too_many = || LayoutError::TooManyElements(count as usize);
+    match &self.kind {
+      TypeLayoutKind::Array(array) if array.len.is_none() =>
+        array.stride.checked_mul(count).ok_or_else(too_many),
+      TypeLayoutKind::Struct(struct_layout) => match struct_layout.fields.last() {
+        Some(last) if last.layout.is_runtime_sized() => {
+          let end = last.offset.checked_add(last.layout.size_with_elements(count)?);
+          end.and_then(|end| round_up(end, self.align)).ok_or_else(too_many)
+        },
+        _ => Ok(self.size),
+      },
+      _ => Ok(self.size),
+    }
+  }
+
+  /**
+   * Whether this is the layout of a runtime-sized array, or of a struct
+   * ending in one.
+   */
+  pub fn is_runtime_sized(&self) -> bool {
+    match &self.kind {
+      TypeLayoutKind::Array(array) => array.len.is_none(),
+      TypeLayoutKind::Struct(struct_layout) => struct_layout.fields.last()
+        .is_some_and(|field| field.layout.is_runtime_sized()),
+      _ => false,
+    }
+  }
+}
+
+/**
+ * The kind-specific parts of a type layout.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeLayoutKind {
+  Scalar,
+  Vector,
+  Matrix(MatrixLayout),
+  Array(ArrayLayout),
+  Struct(StructLayout),
+}
+
+/**
+ * The layout of a matrix, as consecutive column vectors.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixLayout {
+  pub(crate) column_stride: u32,
+}
+impl MatrixLayout {
+  /**
+   * The distance in bytes between the starts of consecutive columns.
+   */
+  pub fn column_stride(&self) -> u32 {
+    self.column_stride
+  }
+}
+
+/**
+ * The layout of an array.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayLayout {
+  pub(crate) elem: Box<TypeLayout>,
+  pub(crate) stride: u32,
+  pub(crate) len: Option<u32>,
+}
+impl ArrayLayout {
+  pub fn elem(&self) -> &TypeLayout {
+    &self.elem
+  }
+
+  /**
+   * The distance in bytes between the starts of consecutive elements.
+   */
+  pub fn stride(&self) -> u32 {
+    self.stride
+  }
+
+  /**
+   * The element count, or `None` for a runtime-sized array.
+   */
+  pub fn elem_count(&self) -> Option<u32> {
+    self.len
+  }
+}
+
+/**
+ * The layout of a struct's fields, in declaration order.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+  pub(crate) fields: Vec<FieldLayout>,
+}
+impl StructLayout {
+  pub fn fields(&self) -> &[FieldLayout] {
+    &self.fields
+  }
+}
+
+/**
+ * The layout of a struct field.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldLayout {
+  pub(crate) name: NameModel,
+  pub(crate) offset: u32,
+  pub(crate) padding: u32,
+  pub(crate) layout: TypeLayout,
+}
+impl FieldLayout {
+  pub fn name(&self) -> &str {
+    &self.name.name
+  }
+
+  /**
+   * The offset in bytes from the start of the struct.
+   */
+  pub fn offset(&self) -> u32 {
+    self.offset
+  }
+
+  /**
+   * The padding in bytes between the end of this field and the start
+   * of the next field, or the end of the struct.
+   */
+  pub fn padding(&self) -> u32 {
+    self.padding
+  }
+
+  pub fn layout(&self) -> &TypeLayout {
+    &self.layout
+  }
+}
+
+/**
+ * An error computing a layout.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+  /** The named type cannot be shared with the host, e.g. `bool`. */
+  NotHostShareable(String),
+
+  /** A field's `@align` is not a multiple of its required alignment. */
+  AlignTooSmall { field: String, align: u32, required: u32 },
+
+  /** A field's `@size` is smaller than its type. */
+  SizeTooSmall { field: String, size: u32, required: u32 },
+
+  /** The size of the named type does not fit in a `u32`. */
+  TooLarge(String),
+
+  /** A runtime-sized array of this many elements does not fit in a `u32`. */
+  TooManyElements(usize),
+}
+impl fmt::Display for LayoutError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LayoutError::NotHostShareable(ty) =>
+        write!(f, "Type `{}` cannot be shared with the host.", ty),
+      LayoutError::AlignTooSmall { field, align, required } => write!(f,
+        "Field `{}` has `@align({})`, but must be aligned to a multiple of {}.",
+        field, align, required
+      ),
+      LayoutError::SizeTooSmall { field, size, required } => write!(f,
+        "Field `{}` has `@size({})`, but its type needs at least {} bytes.",
+        field, size, required
+      ),
+      LayoutError::TooLarge(ty) =>
+        write!(f, "Type `{}` is too large; sizes must fit in a `u32`.", ty),
+      LayoutError::TooManyElements(count) => write!(f,
+        "A runtime-sized array of {} elements is too large; sizes must fit in a `u32`.",
+        count
+      ),
+    }
+  }
+}
+
+/**
+ * The minimum alignment of a type's data, which is the size of its
+ * largest scalar component.  Fields of packed structs use this
+ * alignment.
+ */
+fn min_align(ty: &TypeModel) -> u32 {
+  match ty {
+    TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => scalar.size(),
+    TypeModel::Scalar(ScalarTypeModel::Symbolic(_)) => 1,
+    TypeModel::Vector(vector_ty) => vector_ty.scalar.size(),
+    TypeModel::Matrix(matrix_ty) => matrix_ty.scalar.size(),
+    TypeModel::Array(array_ty) => min_align(&array_ty.elem),
+    TypeModel::Struct(struct_ty) => struct_ty.fields.iter()
+      .map(|field| field.align.unwrap_or_else(|| min_align(&field.ty)))
+      .max()
+      .unwrap_or(1),
+  }
+}
+
+/**
+ * Round a value up to a multiple of an alignment, or `None` if the
+ * result does not fit in a `u32`.
+ */
+fn round_up(value: u32, align: u32) -> Option<u32> {
+  value.checked_next_multiple_of(align)
+}
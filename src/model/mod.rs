@@ -1,13 +1,55 @@
+pub(crate) mod const_eval;
+mod decl_model;
 mod dims;
+mod expr_model;
+mod f16;
+mod layout;
 mod model_handle;
 mod model_space;
 mod name_model;
 mod shader_file_model;
+mod stmt_model;
 mod string_model;
 mod type_model;
 
 pub use self::{
+  decl_model::{
+    BufferAccessMode,
+    BufferModel,
+    EntrypointModel,
+    FuncArgModel,
+    FuncModel,
+    ResourceBindingModel,
+    TestModel,
+    UniformsModel,
+  },
   dims::{ EntrypointDims, VecDims },
+  expr_model::{
+    BinaryExprModel,
+    BinaryOpModel,
+    IndexExprModel,
+    ArrayLengthExprModel,
+    CallExprModel,
+    CastExprModel,
+    ConstructExprModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FieldExprModel,
+    LiteralModel,
+    SwizzleExprModel,
+    UnaryExprModel,
+    UnaryOpModel,
+  },
+  layout::{
+    ArrayLayout,
+    FieldLayout,
+    LayoutError,
+    LayoutRules,
+    MatrixLayout,
+    StructLayout,
+    TypeLayout,
+    TypeLayoutKind,
+  },
   model_handle::ModelHandle,
   model_space::ModelSpace,
   name_model::{
@@ -17,6 +59,17 @@ pub use self::{
     NamePathModelHandle,
   },
   shader_file_model::{ ShaderFileModel, ShaderFileModelHandle },
+  stmt_model::{
+    StatementModel,
+    LetStmtModel,
+    VarStmtModel,
+    MutateStmtModel,
+    ExecStmtModel,
+    RetStmtModel,
+    IfStmtModel,
+    LoopStmtModel,
+    AssertStmtModel,
+  },
   string_model::{ StringModel, StringModelHandle },
   type_model::{
     TypeModel,
@@ -24,12 +77,20 @@ pub use self::{
     ScalarNumericTypeModel,
     ScalarSymbolicTypeModel,
     VectorTypeModel,
+    MatrixTypeModel,
     StructTypeModel,
     StructFieldModel,
+    ArrayTypeModel,
     TypeModelHandle,
   },
 };
 
+pub(crate) use self::{
+  const_eval::{ EvalError, const_eval },
+  expr_model::SWIZZLE_LETTERS,
+  f16::{ f16_bits_from_f32, f32_from_f16_bits },
+};
+
 use std::{
   fmt::Debug,
   hash::Hash,
@@ -3,6 +3,7 @@ use std::{
   hash::{ Hash, Hasher },
   sync::Arc,
   borrow::Borrow,
+  ops::Deref,
 };
 use crate::model::Model;
 
@@ -45,3 +46,9 @@ impl<M: Model> Borrow<M> for ModelHandle<M> {
     self.0.as_ref()
   }
 }
+impl<M: Model> Deref for ModelHandle<M> {
+  type Target = M;
+  fn deref(&self) -> &M {
+    self.0.as_ref()
+  }
+}
@@ -1,4 +1,4 @@
-use std::borrow::Borrow;
+use std::{ borrow::Borrow, fmt };
 
 use crate::model::{ Model, ModelHandle };
 
@@ -16,6 +16,11 @@ impl NameModel {
 }
 impl Model for NameModel {
 }
+impl fmt::Display for NameModel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name)
+  }
+}
 
 pub type NameModelHandle = ModelHandle<NameModel>;
 
@@ -39,6 +44,17 @@ impl NamePathModel {
 }
 impl Model for NamePathModel {
 }
+impl fmt::Display for NamePathModel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, part) in self.path.iter().enumerate() {
+      if i > 0 {
+        write!(f, "::")?;
+      }
+      write!(f, "{}", part.name)?;
+    }
+    Ok(())
+  }
+}
 
 pub type NamePathModelHandle = ModelHandle<NamePathModel>;
 
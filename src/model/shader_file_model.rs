@@ -1,4 +1,17 @@
-use crate::model::{ Model, ModelHandle, StringModel };
+use crate::{
+  model::{
+    BufferModel,
+    EntrypointModel,
+    FuncModel,
+    Model,
+    ModelHandle,
+    StringModel,
+    TestModel,
+    TypeModelHandle,
+    UniformsModel,
+  },
+  reflect::ShaderReflection,
+};
 
 /**
  * An internal representation of the shader file.
@@ -6,10 +19,68 @@ use crate::model::{ Model, ModelHandle, StringModel };
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ShaderFileModel {
     pub(crate) path: ModelHandle<StringModel>,
+    pub(crate) structs: Vec<TypeModelHandle>,
+    pub(crate) buffers: Vec<BufferModel>,
+    pub(crate) uniforms: Option<UniformsModel>,
+    pub(crate) funcs: Vec<FuncModel>,
+    pub(crate) entrypoints: Vec<EntrypointModel>,
+    pub(crate) tests: Vec<TestModel>,
 }
 impl ShaderFileModel {
   pub(crate) fn new(path: ModelHandle<StringModel>) -> ShaderFileModel {
-    ShaderFileModel { path }
+    ShaderFileModel {
+      path,
+      structs: Vec::new(),
+      buffers: Vec::new(),
+      uniforms: None,
+      funcs: Vec::new(),
+      entrypoints: Vec::new(),
+      tests: Vec::new(),
+    }
+  }
+
+  pub fn path(&self) -> &str {
+    &self.path.name
+  }
+
+  /**
+   * The struct types declared in the file, in declaration order.
+   */
+  pub fn structs(&self) -> &[TypeModelHandle] {
+    &self.structs
+  }
+
+  pub fn buffers(&self) -> &[BufferModel] {
+    &self.buffers
+  }
+
+  pub fn uniforms(&self) -> Option<&UniformsModel> {
+    self.uniforms.as_ref()
+  }
+
+  /**
+   * The checked functions, in declaration order.
+   */
+  pub fn funcs(&self) -> &[FuncModel] {
+    &self.funcs
+  }
+
+  pub fn entrypoints(&self) -> &[EntrypointModel] {
+    &self.entrypoints
+  }
+
+  /**
+   * The checked tests, in declaration order.
+   */
+  pub fn tests(&self) -> &[TestModel] {
+    &self.tests
+  }
+
+  /**
+   * Describe the entrypoints and resources of the file for the host.
+   */
+  pub fn reflect(&self) -> ShaderReflection {
+    ShaderReflection::of(self)
   }
 }
 impl Model for ShaderFileModel {
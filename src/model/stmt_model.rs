@@ -0,0 +1,76 @@
+use std::ops::Range;
+use crate::model::{ ExpressionModel, NameModelHandle };
+
+/**
+ * A type-checked statement.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StatementModel {
+  Let(LetStmtModel),
+  Var(VarStmtModel),
+  Mutate(MutateStmtModel),
+  Exec(ExecStmtModel),
+  Ret(RetStmtModel),
+  If(IfStmtModel),
+  Loop(LoopStmtModel),
+  Assert(AssertStmtModel),
+}
+
+/**
+ * An immutable local binding.  Each piece of a syntactic `let` becomes
+ * its own statement.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LetStmtModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) value: ExpressionModel,
+}
+
+/**
+ * A mutable local binding.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VarStmtModel {
+  pub(crate) name: NameModelHandle,
+  pub(crate) value: ExpressionModel,
+}
+
+/**
+ * An assignment to a place expression.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MutateStmtModel {
+  pub(crate) lvalue: ExpressionModel,
+  pub(crate) value: ExpressionModel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecStmtModel {
+  pub(crate) expr: ExpressionModel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetStmtModel {
+  pub(crate) value: Option<ExpressionModel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IfStmtModel {
+  pub(crate) cond: ExpressionModel,
+  pub(crate) if_block: Vec<StatementModel>,
+  pub(crate) else_block: Option<Vec<StatementModel>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoopStmtModel {
+  pub(crate) block: Vec<StatementModel>,
+}
+
+/**
+ * An assertion in a test, with its span in the source for reporting.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssertStmtModel {
+  pub(crate) cond: ExpressionModel,
+  pub(crate) span: Range<usize>,
+}
@@ -1,5 +1,17 @@
-use std::hash::{ Hash, Hasher };
-use crate::model::{ Model, ModelHandle, NameModel, NamePathModel, VecDims };
+use std::{
+  fmt,
+  hash::{ Hash, Hasher },
+};
+use crate::model::{
+  LayoutError,
+  LayoutRules,
+  Model,
+  ModelHandle,
+  NameModel,
+  NamePathModel,
+  TypeLayout,
+  VecDims,
+};
 
 /**
  * Type model representation.
@@ -8,7 +20,9 @@ use crate::model::{ Model, ModelHandle, NameModel, NamePathModel, VecDims };
 pub enum TypeModel {
   Scalar(ScalarTypeModel),
   Vector(VectorTypeModel),
+  Matrix(MatrixTypeModel),
   Struct(StructTypeModel),
+  Array(ArrayTypeModel),
 }
 impl TypeModel {
   pub fn new_i32() -> TypeModel {
@@ -26,9 +40,99 @@ impl TypeModel {
   pub fn new_void() -> TypeModel {
     TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void))
   }
+  pub fn new_scalar(scalar: ScalarNumericTypeModel) -> TypeModel {
+    TypeModel::Scalar(ScalarTypeModel::Numeric(scalar))
+  }
   pub fn new_vector(scalar: ScalarNumericTypeModel, dims: VecDims) -> TypeModel {
     TypeModel::Vector(VectorTypeModel { scalar, dims })
   }
+  pub fn new_matrix(scalar: ScalarNumericTypeModel, cols: VecDims, rows: VecDims)
+    -> TypeModel
+  {
+    TypeModel::Matrix(MatrixTypeModel { scalar, cols, rows })
+  }
+  pub fn new_array(elem: TypeModelHandle, len: Option<u32>) -> TypeModel {
+    TypeModel::Array(ArrayTypeModel { elem, len })
+  }
+
+  pub fn is_bool(&self) -> bool {
+    matches!(self,
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Bool))
+    )
+  }
+
+  pub fn is_void(&self) -> bool {
+    matches!(self,
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(ScalarSymbolicTypeModel::Void))
+    )
+  }
+
+  /**
+   * Get the numeric scalar type, if this is a numeric scalar.
+   */
+  pub fn as_numeric_scalar(&self) -> Option<ScalarNumericTypeModel> {
+    match self {
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => Some(*scalar),
+      _ => None,
+    }
+  }
+
+  /**
+   * Get the element type of a numeric scalar or vector.
+   */
+  pub fn numeric_element(&self) -> Option<ScalarNumericTypeModel> {
+    match self {
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => Some(*scalar),
+      TypeModel::Vector(vector) => Some(vector.scalar),
+      _ => None,
+    }
+  }
+
+  pub fn is_integer_scalar(&self) -> bool {
+    self.as_numeric_scalar().is_some_and(|s| s.is_integer())
+  }
+
+  /**
+   * Compute the memory layout of this type under the given rules.
+   */
+  pub fn layout(&self, rules: LayoutRules) -> Result<TypeLayout, LayoutError> {
+    TypeLayout::of(self, rules)
+  }
+
+  /**
+   * Whether this is a runtime-sized array, or a struct ending in one.
+   * Such types may only be the storage type of a buffer.
+   */
+  pub fn is_runtime_sized(&self) -> bool {
+    match self {
+      TypeModel::Array(array_ty) => array_ty.len.is_none(),
+      TypeModel::Struct(struct_ty) => struct_ty.fields.last()
+        .is_some_and(|field| field.ty.is_runtime_sized()),
+      _ => false,
+    }
+  }
+}
+impl fmt::Display for TypeModel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TypeModel::Scalar(ScalarTypeModel::Symbolic(symbolic)) => match symbolic {
+        ScalarSymbolicTypeModel::Bool => write!(f, "bool"),
+        ScalarSymbolicTypeModel::Void => write!(f, "void"),
+      },
+      TypeModel::Scalar(ScalarTypeModel::Numeric(numeric)) =>
+        write!(f, "{}", numeric),
+      TypeModel::Vector(vector) =>
+        write!(f, "vec{}x{}", vector.dims as u8, vector.scalar),
+      TypeModel::Matrix(matrix) => write!(f, "mat{}x{}x{}",
+        matrix.cols as u8, matrix.rows as u8, matrix.scalar
+      ),
+      TypeModel::Struct(struct_ty) => write!(f, "{}", struct_ty.name),
+      TypeModel::Array(array_ty) => match array_ty.len {
+        Some(len) => write!(f, "[{}; {}]", *array_ty.elem, len),
+        None => write!(f, "[{}]", *array_ty.elem),
+      },
+    }
+  }
 }
 impl Model for TypeModel {}
 
@@ -45,13 +149,71 @@ pub enum ScalarTypeModel {
  * Scalar symbolic types.
  */
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy)]
 pub enum ScalarSymbolicTypeModel { Bool, Void }
 
 /**
  * A scalar type model.
  */
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum ScalarNumericTypeModel { I32, U32, F32 }
+#[derive(Copy)]
+pub enum ScalarNumericTypeModel { I32, U32, F32, F16, I64, U64 }
+impl ScalarNumericTypeModel {
+  pub fn is_integer(self) -> bool {
+    matches!(self,
+      ScalarNumericTypeModel::I32 | ScalarNumericTypeModel::U32 |
+      ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64
+    )
+  }
+
+  pub fn is_float(self) -> bool {
+    matches!(self, ScalarNumericTypeModel::F32 | ScalarNumericTypeModel::F16)
+  }
+
+  pub fn is_signed(self) -> bool {
+    !matches!(self, ScalarNumericTypeModel::U32 | ScalarNumericTypeModel::U64)
+  }
+
+  /**
+   * The size in bytes.
+   */
+  pub fn size(self) -> u32 {
+    match self {
+      ScalarNumericTypeModel::F16 => 2,
+      ScalarNumericTypeModel::I32 |
+      ScalarNumericTypeModel::U32 |
+      ScalarNumericTypeModel::F32 => 4,
+      ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 => 8,
+    }
+  }
+
+  /**
+   * Parse a scalar numeric type name, e.g. `f32`.
+   */
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "i32" => Some(ScalarNumericTypeModel::I32),
+      "u32" => Some(ScalarNumericTypeModel::U32),
+      "f32" => Some(ScalarNumericTypeModel::F32),
+      "f16" => Some(ScalarNumericTypeModel::F16),
+      "i64" => Some(ScalarNumericTypeModel::I64),
+      "u64" => Some(ScalarNumericTypeModel::U64),
+      _ => None,
+    }
+  }
+}
+impl fmt::Display for ScalarNumericTypeModel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ScalarNumericTypeModel::I32 => write!(f, "i32"),
+      ScalarNumericTypeModel::U32 => write!(f, "u32"),
+      ScalarNumericTypeModel::F32 => write!(f, "f32"),
+      ScalarNumericTypeModel::F16 => write!(f, "f16"),
+      ScalarNumericTypeModel::I64 => write!(f, "i64"),
+      ScalarNumericTypeModel::U64 => write!(f, "u64"),
+    }
+  }
+}
 
 /**
  * A vector type model.
@@ -63,12 +225,57 @@ pub struct VectorTypeModel {
 }
 
 /**
- * A struct type model.
+ * A matrix type model, with `cols` column vectors of `rows` components.
+ *
+ * E.g. `mat3x4xf32` has 3 columns of `vec4xf32`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatrixTypeModel {
+  pub(crate) scalar: ScalarNumericTypeModel,
+  pub(crate) cols: VecDims,
+  pub(crate) rows: VecDims,
+}
+
+/**
+ * An array type model.  Arrays without a length are runtime-sized.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArrayTypeModel {
+  pub(crate) elem: TypeModelHandle,
+  pub(crate) len: Option<u32>,
+}
+
+/**
+ * A struct type model.  Fields of a packed struct are aligned only to
+ * their scalar components, with no other padding.
  */
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructTypeModel {
   pub(crate) name: NamePathModel,
   pub(crate) fields: Vec<StructFieldModel>,
+  pub(crate) packed: bool,
+}
+impl StructTypeModel {
+  pub fn name(&self) -> &NamePathModel {
+    &self.name
+  }
+
+  pub fn fields(&self) -> &[StructFieldModel] {
+    &self.fields
+  }
+
+  pub fn packed(&self) -> bool {
+    self.packed
+  }
+
+  /**
+   * Find a field by name, returning its position and model.
+   */
+  pub fn field(&self, name: &str) -> Option<(usize, &StructFieldModel)> {
+    self.fields.iter()
+      .enumerate()
+      .find(|(_, field)| field.name.name == name)
+  }
 }
 impl Hash for StructTypeModel {
   fn hash<H: Hasher>(&self, state: &mut H) {
@@ -77,12 +284,31 @@ impl Hash for StructTypeModel {
 }
 
 /**
- * A struct field model.
+ * A struct field model, with any explicit `@align` and `@size`.
  */
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct StructFieldModel {
   pub(crate) name: NameModel,
   pub(crate) ty: TypeModelHandle,
+  pub(crate) align: Option<u32>,
+  pub(crate) size: Option<u32>,
+}
+impl StructFieldModel {
+  pub fn name(&self) -> &str {
+    &self.name.name
+  }
+
+  pub fn ty(&self) -> &TypeModelHandle {
+    &self.ty
+  }
+
+  pub fn align(&self) -> Option<u32> {
+    self.align
+  }
+
+  pub fn size(&self) -> Option<u32> {
+    self.size
+  }
 }
 
 pub type TypeModelHandle = ModelHandle<TypeModel>;
@@ -0,0 +1,140 @@
+use std::fmt::{ self, Write };
+
+/**
+ * A JSON value.  Object members keep their insertion order, so that
+ * the output is stable.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonValue {
+  Null,
+  Bool(bool),
+  Number(i64),
+  String(String),
+  Array(Vec<JsonValue>),
+  Object(Vec<(String, JsonValue)>),
+}
+impl JsonValue {
+  /**
+   * Build an object from its members, in order.
+   */
+  pub fn object<'k>(members: impl IntoIterator<Item = (&'k str, JsonValue)>)
+    -> JsonValue
+  {
+    JsonValue::Object(
+      members.into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    )
+  }
+
+  pub fn string(value: impl Into<String>) -> JsonValue {
+    JsonValue::String(value.into())
+  }
+
+  /**
+   * Write the value with two-space indentation, starting at the given
+   * indentation depth.
+   */
+  fn write_pretty(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+    let indent = |f: &mut fmt::Formatter<'_>, depth: usize| {
+      (0 .. depth).try_for_each(|_| f.write_str("  "))
+    };
+    match self {
+      JsonValue::Array(items) if !items.is_empty() => {
+        f.write_str("[\n")?;
+        for (i, item) in items.iter().enumerate() {
+          indent(f, depth + 1)?;
+          item.write_pretty(f, depth + 1)?;
+          f.write_str(if i + 1 < items.len() { ",\n" } else { "\n" })?;
+        }
+        indent(f, depth)?;
+        f.write_char(']')
+      },
+      JsonValue::Object(members) if !members.is_empty() => {
+        f.write_str("{\n")?;
+        for (i, (key, value)) in members.iter().enumerate() {
+          indent(f, depth + 1)?;
+          write_json_string(f, key)?;
+          f.write_str(": ")?;
+          value.write_pretty(f, depth + 1)?;
+          f.write_str(if i + 1 < members.len() { ",\n" } else { "\n" })?;
+        }
+        indent(f, depth)?;
+        f.write_char('}')
+      },
+      _ => write!(f, "{}", self),
+    }
+  }
+}
+
+/**
+ * Formats as compact JSON, or indented JSON with `{:#}`.
+ */
+impl fmt::Display for JsonValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+      return self.write_pretty(f, 0);
+    }
+    match self {
+      JsonValue::Null => f.write_str("null"),
+      JsonValue::Bool(value) => write!(f, "{}", value),
+      JsonValue::Number(value) => write!(f, "{}", value),
+      JsonValue::String(value) => write_json_string(f, value),
+      JsonValue::Array(items) => {
+        f.write_char('[')?;
+        for (i, item) in items.iter().enumerate() {
+          if i > 0 {
+            f.write_char(',')?;
+          }
+          write!(f, "{}", item)?;
+        }
+        f.write_char(']')
+      },
+      JsonValue::Object(members) => {
+        f.write_char('{')?;
+        for (i, (key, value)) in members.iter().enumerate() {
+          if i > 0 {
+            f.write_char(',')?;
+          }
+          write_json_string(f, key)?;
+          write!(f, ":{}", value)?;
+        }
+        f.write_char('}')
+      },
+    }
+  }
+}
+
+impl From<bool> for JsonValue {
+  fn from(value: bool) -> JsonValue {
+    JsonValue::Bool(value)
+  }
+}
+
+impl From<u32> for JsonValue {
+  fn from(value: u32) -> JsonValue {
+    JsonValue::Number(value.into())
+  }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+  fn from(value: Option<T>) -> JsonValue {
+    value.map_or(JsonValue::Null, Into::into)
+  }
+}
+
+fn write_json_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+  f.write_char('"')?;
+  for ch in value.chars() {
+    match ch {
+      '"' => f.write_str("\\\"")?,
+      '\\' => f.write_str("\\\\")?,
+      '\n' => f.write_str("\\n")?,
+      '\r' => f.write_str("\\r")?,
+      '\t' => f.write_str("\\t")?,
+      ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+      ch => f.write_char(ch)?,
+    }
+  }
+  f.write_char('"')
+}
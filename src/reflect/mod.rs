@@ -0,0 +1,299 @@
+mod json;
+
+pub use self::json::JsonValue;
+
+use crate::model::{
+  BufferAccessMode,
+  EntrypointDims,
+  FieldLayout,
+  ResourceBindingModel,
+  ShaderFileModel,
+  StructFieldModel,
+  TypeLayout,
+  TypeLayoutKind,
+  TypeModel,
+  TypeModelHandle,
+};
+
+/**
+ * The version of the JSON schema written by `ShaderReflection::to_json`.
+ * It changes only when existing members change meaning or are removed.
+ */
+pub const REFLECTION_SCHEMA_VERSION: u32 = 1;
+
+/**
+ * The host-facing description of a checked shader file: the
+ * entrypoints and resources needed to set up pipelines and buffers.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderReflection {
+  pub(crate) entrypoints: Vec<EntrypointReflection>,
+  pub(crate) buffers: Vec<BufferReflection>,
+  pub(crate) uniforms: Option<UniformsReflection>,
+}
+impl ShaderReflection {
+  /**
+   * Reflect a checked shader file.
+   */
+  pub fn of(model: &ShaderFileModel) -> ShaderReflection {
+    let entrypoints = model.entrypoints.iter()
+      .map(|entrypoint| EntrypointReflection {
+        name: entrypoint.name().to_string(),
+        dims: entrypoint.dims(),
+        workgroup_size: entrypoint.workgroup_size(),
+      })
+      .collect();
+    let buffers = model.buffers.iter()
+      .map(|buffer| BufferReflection {
+        name: buffer.name().to_string(),
+        mode: buffer.mode(),
+        binding: buffer.binding(),
+        ty: buffer.ty().clone(),
+        layout: buffer.layout().expect("Checked buffer has a layout"),
+      })
+      .collect();
+    let uniforms = model.uniforms.as_ref()
+      .map(|uniforms| UniformsReflection {
+        binding: uniforms.binding(),
+        ty: uniforms.ty().clone(),
+        layout: uniforms.layout().expect("Checked uniforms have a layout"),
+      });
+    ShaderReflection { entrypoints, buffers, uniforms }
+  }
+
+  pub fn entrypoints(&self) -> &[EntrypointReflection] {
+    &self.entrypoints
+  }
+
+  pub fn buffers(&self) -> &[BufferReflection] {
+    &self.buffers
+  }
+
+  pub fn uniforms(&self) -> Option<&UniformsReflection> {
+    self.uniforms.as_ref()
+  }
+
+  /**
+   * Describe the shader file as JSON.  Members are written in a fixed
+   * order, so the output only changes when the shader does.
+   */
+  pub fn to_json(&self) -> JsonValue {
+    JsonValue::object([
+      ("version", REFLECTION_SCHEMA_VERSION.into()),
+      ("entrypoints", JsonValue::Array(
+        self.entrypoints.iter().map(EntrypointReflection::to_json).collect()
+      )),
+      ("buffers", JsonValue::Array(
+        self.buffers.iter().map(BufferReflection::to_json).collect()
+      )),
+      ("uniforms", self.uniforms.as_ref()
+        .map_or(JsonValue::Null, UniformsReflection::to_json)),
+    ])
+  }
+}
+
+/**
+ * An entrypoint and the size of its workgroups.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrypointReflection {
+  pub(crate) name: String,
+  pub(crate) dims: EntrypointDims,
+  pub(crate) workgroup_size: [u32; 3],
+}
+impl EntrypointReflection {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn dims(&self) -> EntrypointDims {
+    self.dims
+  }
+
+  pub fn workgroup_size(&self) -> [u32; 3] {
+    self.workgroup_size
+  }
+
+  fn to_json(&self) -> JsonValue {
+    JsonValue::object([
+      ("name", JsonValue::string(&self.name)),
+      ("dims", (self.dims as u32).into()),
+      ("workgroup_size", JsonValue::Array(
+        self.workgroup_size.iter().map(|&size| size.into()).collect()
+      )),
+    ])
+  }
+}
+
+/**
+ * A buffer, with its storage layout under storage rules.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferReflection {
+  pub(crate) name: String,
+  pub(crate) mode: BufferAccessMode,
+  pub(crate) binding: ResourceBindingModel,
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) layout: TypeLayout,
+}
+impl BufferReflection {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn mode(&self) -> BufferAccessMode {
+    self.mode
+  }
+
+  pub fn binding(&self) -> ResourceBindingModel {
+    self.binding
+  }
+
+  /**
+   * The storage type, which ends in a runtime-sized array.
+   */
+  pub fn ty(&self) -> &TypeModelHandle {
+    &self.ty
+  }
+
+  pub fn layout(&self) -> &TypeLayout {
+    &self.layout
+  }
+
+  /**
+   * The type and layout of the elements of the runtime-sized array at
+   * the end of the storage type.
+   */
+  pub fn element(&self) -> (&TypeModel, &TypeLayout) {
+    let (array_ty, array_layout) = match (&*self.ty, &self.layout.kind) {
+      (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(struct_layout)) => (
+        &*struct_ty.fields.last().expect("Runtime-sized struct has fields").ty,
+        &struct_layout.fields.last().expect("Runtime-sized struct has fields").layout,
+      ),
+      _ => (&*self.ty, &self.layout),
+    };
+    match (array_ty, &array_layout.kind) {
+      (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) =>
+        (&*array_ty.elem, &*array_layout.elem),
+      _ => panic!("Buffer type `{}` is not runtime-sized", *self.ty),
+    }
+  }
+
+  /**
+   * The distance in bytes between the starts of consecutive elements.
+   */
+  pub fn element_stride(&self) -> u32 {
+    let (_, element_layout) = self.element();
+    element_layout.size.div_ceil(element_layout.align) * element_layout.align
+  }
+
+  fn to_json(&self) -> JsonValue {
+    let (element_ty, element_layout) = self.element();
+    JsonValue::object([
+      ("name", JsonValue::string(&self.name)),
+      ("access", JsonValue::string(match self.mode {
+        BufferAccessMode::Read => "read",
+        BufferAccessMode::Write => "write",
+        BufferAccessMode::ReadWrite => "read_write",
+      })),
+      ("group", self.binding.group.into()),
+      ("binding", self.binding.binding.into()),
+      ("layout", layout_to_json(&self.ty, &self.layout)),
+      ("element", layout_to_json(element_ty, element_layout)),
+    ])
+  }
+}
+
+/**
+ * The uniforms, with their layout under uniform rules.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformsReflection {
+  pub(crate) binding: ResourceBindingModel,
+  pub(crate) ty: TypeModelHandle,
+  pub(crate) layout: TypeLayout,
+}
+impl UniformsReflection {
+  pub fn binding(&self) -> ResourceBindingModel {
+    self.binding
+  }
+
+  pub fn ty(&self) -> &TypeModelHandle {
+    &self.ty
+  }
+
+  pub fn layout(&self) -> &TypeLayout {
+    &self.layout
+  }
+
+  /**
+   * The layouts of the uniform fields, in declaration order.
+   */
+  pub fn fields(&self) -> &[FieldLayout] {
+    match &self.layout.kind {
+      TypeLayoutKind::Struct(struct_layout) => &struct_layout.fields,
+      _ => &[],
+    }
+  }
+
+  fn to_json(&self) -> JsonValue {
+    let fields = match &*self.ty {
+      TypeModel::Struct(struct_ty) => fields_to_json(&struct_ty.fields, self.fields()),
+      _ => JsonValue::Array(Vec::new()),
+    };
+    JsonValue::object([
+      ("group", self.binding.group.into()),
+      ("binding", self.binding.binding.into()),
+      ("size", self.layout.size.into()),
+      ("align", self.layout.align.into()),
+      ("fields", fields),
+    ])
+  }
+}
+
+/**
+ * Describe a type and its layout as JSON.
+ */
+fn layout_to_json(ty: &TypeModel, layout: &TypeLayout) -> JsonValue {
+  let mut members = vec![
+    ("type", JsonValue::string(ty.to_string())),
+    ("size", layout.size.into()),
+    ("align", layout.align.into()),
+  ];
+  match (ty, &layout.kind) {
+    (_, TypeLayoutKind::Scalar) => members.push(("kind", JsonValue::string("scalar"))),
+    (_, TypeLayoutKind::Vector) => members.push(("kind", JsonValue::string("vector"))),
+    (_, TypeLayoutKind::Matrix(matrix_layout)) => members.extend([
+      ("kind", JsonValue::string("matrix")),
+      ("column_stride", matrix_layout.column_stride.into()),
+    ]),
+    (TypeModel::Array(array_ty), TypeLayoutKind::Array(array_layout)) => members.extend([
+      ("kind", JsonValue::string("array")),
+      ("len", array_layout.len.into()),
+      ("stride", array_layout.stride.into()),
+      ("element", layout_to_json(&array_ty.elem, &array_layout.elem)),
+    ]),
+    (TypeModel::Struct(struct_ty), TypeLayoutKind::Struct(struct_layout)) => members.extend([
+      ("kind", JsonValue::string("struct")),
+      ("fields", fields_to_json(&struct_ty.fields, &struct_layout.fields)),
+    ]),
+    _ => panic!("Layout does not match type `{}`", ty),
+  }
+  JsonValue::object(members)
+}
+
+fn fields_to_json(
+  fields: &[StructFieldModel],
+  layouts: &[FieldLayout],
+) -> JsonValue {
+  JsonValue::Array(
+    fields.iter().zip(layouts)
+      .map(|(field, field_layout)| JsonValue::object([
+        ("name", JsonValue::string(field_layout.name())),
+        ("offset", field_layout.offset.into()),
+        ("padding", field_layout.padding.into()),
+        ("layout", layout_to_json(&field.ty, &field_layout.layout)),
+      ]))
+      .collect()
+  )
+}
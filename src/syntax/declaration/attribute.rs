@@ -0,0 +1,43 @@
+use chumsky::{
+  Parser,
+  extra::ParserExtra,
+};
+use crate::syntax::{
+  expression::{ ConstExpr, Expression },
+  name::Name,
+  util::whitespace_parser,
+};
+
+/**
+ * An attribute on a declaration, with an optional constant argument.
+ *
+ * E.g. `@packed` or `@align(16)`
+ */
+#[derive(Debug, Clone)]
+pub struct Attribute<'a> {
+  pub name: Name<'a>,
+  pub arg: Option<ConstExpr<'a>>,
+}
+
+/**
+ * Parser for a sequence of attributes, each followed by whitespace.
+ */
+pub(crate) fn attributes_parser<'a, E>()
+  -> impl Clone + Parser<'a, &'a str, Vec<Attribute<'a>>, E>
+  where E: ParserExtra<'a, &'a str>
+{
+  use chumsky::prelude::*;
+
+  just('@')
+    .ignore_then(Name::parser())
+    .then(
+      ConstExpr::parser(Expression::parser())
+        .padded_by(whitespace_parser())
+        .delimited_by(just('('), just(')'))
+        .or_not()
+    )
+    .then_ignore(whitespace_parser())
+    .map(|(name, arg)| Attribute { name, arg })
+    .repeated()
+    .collect::<Vec<_>>()
+}
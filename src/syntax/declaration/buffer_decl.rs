@@ -3,6 +3,7 @@ use chumsky::{
   extra::ParserExtra,
 };
 use crate::syntax::{
+  declaration::attribute::{ Attribute, attributes_parser },
   name::Name,
   types::TypeName,
   util::{ terminal_semicolon_parser, whitespace_parser },
@@ -10,9 +11,12 @@ use crate::syntax::{
 
 /**
  * A buffer declaration.
+ *
+ * E.g. `@group(0) @binding(1) buffer(rw) birds: [BirdInfo];`
  */
 #[derive(Debug, Clone)]
 pub struct BufferDecl<'a> {
+  pub attributes: Vec<Attribute<'a>>,
   pub name: Name<'a>,
   pub mode: BufferDeclMode,
   pub ty: TypeName<'a>,
@@ -31,8 +35,9 @@ pub(crate) fn buffer_decl_parser<'a, E>()
 {
   use chumsky::prelude::*;
 
-  text::keyword("buffer").then(whitespace_parser())
-    .ignore_then(
+  attributes_parser()
+    .then_ignore(text::keyword("buffer").then(whitespace_parser()))
+    .then(
       choice((
         just("rw").map(|_| BufferDeclMode::ReadWrite),
         just("r").map(|_| BufferDeclMode::Read),
@@ -48,8 +53,8 @@ pub(crate) fn buffer_decl_parser<'a, E>()
     .then_ignore(just(':').padded_by(whitespace_parser()))
     .then(TypeName::parser())
     .then_ignore(terminal_semicolon_parser())
-    .map(|((mode, name), ty)| {
-      BufferDecl { name, mode, ty }
+    .map(|(((attributes, mode), name), ty)| {
+      BufferDecl { attributes, name, mode, ty }
     })
     .boxed()
 }
@@ -3,6 +3,7 @@ use chumsky::{
   extra::ParserExtra,
 };
 use crate::syntax::{
+  declaration::attribute::{ Attribute, attributes_parser },
   name::Name,
   statement::{ Statement, StatementBlock },
   util::whitespace_parser,
@@ -13,6 +14,7 @@ use crate::syntax::{
  */
 #[derive(Debug, Clone)]
 pub struct EntrypointDecl<'a> {
+  pub attributes: Vec<Attribute<'a>>,
   pub name: Name<'a>,
   pub dims: EntrypointDeclDims,
   pub arg_name: Name<'a>,
@@ -33,8 +35,9 @@ pub(crate) fn entrypoint_decl_parser<'a, E>()
 {
   use chumsky::prelude::*;
 
-  text::keyword("entrypoint").then(whitespace_parser())
-    .ignore_then(
+  attributes_parser()
+    .then_ignore(text::keyword("entrypoint").then(whitespace_parser()))
+    .then(
       choice((
         just("1d").map(|_| EntrypointDeclDims::D1),
         just("2d").map(|_| EntrypointDeclDims::D2),
@@ -54,8 +57,8 @@ pub(crate) fn entrypoint_decl_parser<'a, E>()
         )
     )
     .then(StatementBlock::parser(Statement::parser()))
-    .map(|(((dims, name), arg_name), body)| {
-      EntrypointDecl { dims, name, arg_name, body }
+    .map(|((((attributes, dims), name), arg_name), body)| {
+      EntrypointDecl { attributes, dims, name, arg_name, body }
     })
     .boxed()
 }
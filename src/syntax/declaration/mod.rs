@@ -1,3 +1,4 @@
+mod attribute;
 mod buffer_decl;
 mod entrypoint_decl;
 mod func_decl;
@@ -5,17 +6,20 @@ mod import_decl;
 mod instance_decl;
 mod module_decl;
 mod struct_decl;
+mod test_decl;
 
 use crate::syntax::util::whitespace_parser;
 
 pub use self::{
-  buffer_decl::BufferDecl,
+  attribute::Attribute,
+  buffer_decl::{ BufferDecl, BufferDeclMode },
   entrypoint_decl::{ EntrypointDecl, EntrypointDeclDims },
   func_decl::FuncDecl,
   import_decl::ImportDecl,
   instance_decl::InstanceDecl,
   module_decl::ModuleDecl,
-  struct_decl::{ StructDecl, UniformsDecl },
+  struct_decl::{ StructDecl, StructDeclField, UniformsDecl },
+  test_decl::TestDecl,
 };
 pub(crate) use self::{
   buffer_decl::buffer_decl_parser,
@@ -25,6 +29,7 @@ pub(crate) use self::{
   instance_decl::instance_decl_parser,
   module_decl::module_decl_parser,
   struct_decl::{ struct_decl_parser, uniforms_decl_parser },
+  test_decl::test_decl_parser,
 };
 
 use chumsky::{
@@ -3,6 +3,7 @@ use chumsky::{
   extra::ParserExtra,
 };
 use crate::syntax::{
+  declaration::attribute::{ Attribute, attributes_parser },
   name::Name,
   types::TypeName,
   util::whitespace_parser,
@@ -10,9 +11,12 @@ use crate::syntax::{
 
 /**
  * A struct declaration.
+ *
+ * E.g. `@packed struct Header { magic: u32, @align(8) len: u64 }`
  */
 #[derive(Debug, Clone)]
 pub struct StructDecl<'a> {
+  pub attributes: Vec<Attribute<'a>>,
   pub name: Name<'a>,
   pub fields: Vec<StructDeclField<'a>>,
 }
@@ -20,14 +24,18 @@ pub struct StructDecl<'a> {
 /**
  * A uniforms declaration is effectively an implicitly named struct declaration
  * and an instance of it.
+ *
+ * E.g. `@group(0) @binding(2) uniforms { scale: f32 }`
  */
 #[derive(Debug, Clone)]
 pub struct UniformsDecl<'a> {
+  pub attributes: Vec<Attribute<'a>>,
   pub fields: Vec<StructDeclField<'a>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct StructDeclField<'a> {
+  pub attributes: Vec<Attribute<'a>>,
   pub name: Name<'a>,
   pub ty: TypeName<'a>,
 }
@@ -38,10 +46,13 @@ pub(crate) fn struct_decl_parser<'a, E>()
 {
   use chumsky::prelude::*;
 
-  text::keyword("struct").then(whitespace_parser())
-    .ignore_then(Name::parser())
+  attributes_parser()
+    .then_ignore(text::keyword("struct").then(whitespace_parser()))
+    .then(Name::parser())
     .then(struct_decl_body_parser())
-    .map(|(name, fields)| { StructDecl { name, fields } })
+    .map(|((attributes, name), fields)| {
+      StructDecl { attributes, name, fields }
+    })
     .boxed()
 }
 
@@ -51,9 +62,10 @@ pub(crate) fn uniforms_decl_parser<'a, E>()
 {
   use chumsky::prelude::*;
 
-  text::keyword("uniforms").then(whitespace_parser())
-    .ignore_then(struct_decl_body_parser())
-    .map(|fields| { UniformsDecl { fields } })
+  attributes_parser()
+    .then_ignore(text::keyword("uniforms").then(whitespace_parser()))
+    .then(struct_decl_body_parser())
+    .map(|(attributes, fields)| { UniformsDecl { attributes, fields } })
     .boxed()
 }
 
@@ -80,8 +92,9 @@ pub(crate) fn struct_decl_field_parser<'a, E>()
 {
   use chumsky::prelude::*;
 
-  Name::parser()
+  attributes_parser()
+    .then(Name::parser())
     .then_ignore(just(':').padded_by(whitespace_parser()))
     .then(TypeName::parser())
-    .map(|(name, ty)| StructDeclField { name, ty })
+    .map(|((attributes, name), ty)| StructDeclField { attributes, name, ty })
 }
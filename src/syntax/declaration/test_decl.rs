@@ -0,0 +1,31 @@
+use chumsky::{
+  Parser,
+  extra::ParserExtra,
+};
+use crate::syntax::{
+  name::Name,
+  statement::{ Statement, StatementBlock },
+  util::whitespace_parser,
+};
+
+/**
+ * A test declaration, whose body runs on the interpreter.
+ */
+#[derive(Debug, Clone)]
+pub struct TestDecl<'a> {
+  pub name: Name<'a>,
+  pub body: StatementBlock<'a>,
+}
+
+pub(crate) fn test_decl_parser<'a, E>()
+  -> impl Clone + Parser<'a, &'a str, TestDecl<'a>, E>
+  where E: ParserExtra<'a, &'a str>
+{
+  use chumsky::prelude::*;
+
+  text::keyword("test").then(whitespace_parser())
+    .ignore_then(Name::parser())
+    .then(StatementBlock::parser(Statement::parser()))
+    .map(|(name, body)| TestDecl { name, body })
+    .boxed()
+}
@@ -0,0 +1,35 @@
+use chumsky::{
+  Parser,
+  extra::ParserExtra,
+};
+use crate::syntax::{
+  expression::Expression,
+  types::TypeName,
+  util::whitespace_parser,
+};
+
+/**
+ * A numeric cast expression.
+ *
+ * E.g. `i as f32` or `v as vec2xi32`
+ */
+#[derive(Debug, Clone)]
+pub struct CastExpr<'a> {
+  pub subexpr: Box<Expression<'a>>,
+  pub ty: TypeName<'a>,
+}
+
+/**
+ * Parser for the `as TYPE` suffix of a cast.  The expression parser is
+ * used for array lengths within the type.
+ */
+pub(crate) fn cast_suffix_parser<'a, E>(
+  expr_parser: impl 'a + Clone + Parser<'a, &'a str, Expression<'a>, E>
+) -> impl 'a + Clone + Parser<'a, &'a str, TypeName<'a>, E>
+  where E: ParserExtra<'a, &'a str>
+{
+  use chumsky::prelude::*;
+
+  text::keyword("as").padded_by(whitespace_parser())
+    .ignore_then(TypeName::parser_with(expr_parser))
+}
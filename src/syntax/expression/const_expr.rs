@@ -0,0 +1,37 @@
+use std::fmt;
+use chumsky::{
+  Parser,
+  extra::ParserExtra,
+};
+use crate::syntax::expression::Expression;
+
+/**
+ * An expression whose value is needed at compile time, kept with its
+ * source text for diagnostics.
+ *
+ * E.g. the `1 << 4` in `[f32; 1 << 4]`
+ */
+#[derive(Debug, Clone)]
+pub struct ConstExpr<'a> {
+  pub expr: Box<Expression<'a>>,
+  pub text: &'a str,
+}
+impl<'a> ConstExpr<'a> {
+  /**
+   * Parse a constant expression with the given expression parser, so
+   * that it can be used within expressions, e.g. in the type of a cast.
+   */
+  pub fn parser<E>(
+    expr_parser: impl 'a + Clone + Parser<'a, &'a str, Expression<'a>, E>
+  ) -> impl 'a + Clone + Parser<'a, &'a str, ConstExpr<'a>, E>
+    where E: ParserExtra<'a, &'a str>
+  {
+    expr_parser.map_with(|expr, e| ConstExpr { expr: expr.boxed(), text: e.slice() })
+  }
+}
+
+impl<'a> fmt::Display for ConstExpr<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.text)
+  }
+}
@@ -7,17 +7,33 @@ mod add;
 mod shift;
 mod relational;
 mod logical;
+mod cast;
+mod const_expr;
 
 pub use self::{
-  primary::{ CallExpr, DotExpr, DotExprSuffix },
-  terminal::{ IntLiteralExpr, NameExpr, ParenExpr },
+  primary::{ CallExpr, DotExpr, DotExprSuffix, IndexExpr },
+  terminal::{
+    IntLiteralExpr,
+    IntLiteralExprSign,
+    IntLiteralExprBase,
+    IntLiteralExprType,
+    IntLiteralValue,
+    IntLiteralRangeError,
+    FloatLiteralExpr,
+    NameExpr,
+    ParenExpr,
+    StructLiteralExpr,
+    StructLiteralExprField,
+  },
   unary::{ UnaryExpr, UnaryExprOp },
   bit::{ BitExpr, BitExprOp },
   mul::{ MulExpr, MulExprOp },
   add::{ AddExpr, AddExprOp },
   shift::{ ShiftExpr, ShiftExprOp },
   relational::{ RelationalExpr, RelationalExprOp },
-  logical::{ LogicalExpr, LogicalExprOp }
+  logical::{ LogicalExpr, LogicalExprOp },
+  cast::CastExpr,
+  const_expr::ConstExpr,
 };
 pub(crate) use self::{
   primary::primary_expr_parser,
@@ -29,6 +45,7 @@ pub(crate) use self::{
   shift::shift_expr_parser,
   relational::relational_expr_parser,
   logical::logical_expr_parser,
+  cast::cast_suffix_parser,
 };
 
 use chumsky::{
@@ -36,7 +53,10 @@ use chumsky::{
   extra::ParserExtra,
   Boxed,
 };
-use crate::syntax::util::whitespace_parser;
+use crate::syntax::{
+  types::TypeName,
+  util::whitespace_parser,
+};
 
 /**
  * An expression in the language.
@@ -45,8 +65,11 @@ use crate::syntax::util::whitespace_parser;
 pub enum Expression<'a> {
   Name(NameExpr<'a>),
   IntLiteral(IntLiteralExpr<'a>),
+  FloatLiteral(FloatLiteralExpr<'a>),
   Paren(ParenExpr<'a>),
+  StructLiteral(StructLiteralExpr<'a>),
   Dot(DotExpr<'a>),
+  Index(IndexExpr<'a>),
   Call(CallExpr<'a>),
   Unary(UnaryExpr<'a>),
   Bit(BitExpr<'a>),
@@ -54,7 +77,8 @@ pub enum Expression<'a> {
   Add(AddExpr<'a>),
   Shift(ShiftExpr<'a>),
   Relational(RelationalExpr<'a>),
-  Logical(LogicalExpr<'a>)
+  Logical(LogicalExpr<'a>),
+  Cast(CastExpr<'a>),
 }
 impl<'a> Expression<'a> {
   pub fn boxed(self) -> Box<Self> {
@@ -68,15 +92,18 @@ impl<'a> Expression<'a> {
     Self::shift_reduce_parser()
   }
 
+  /**
+   * Parse an lvalue.  The lvalue itself is restricted to primary
+   * expressions, but index and argument subexpressions are full
+   * expressions (e.g. `birds[i + 1].position`).
+   */
   pub fn lvalue_parser<E>()
     -> Boxed<'a, 'a, &'a str, Expression<'a>, E>
     where E: ParserExtra<'a, &'a str>,
   {
     use chumsky::prelude::*;
 
-    recursive(|expr_parser| {
-      primary_expr_parser(expr_parser)
-    }).boxed()
+    primary_expr_parser(Self::parser()).boxed()
   }
 
   pub fn shift_reduce_parser<E>()
@@ -85,20 +112,34 @@ impl<'a> Expression<'a> {
   {
     use chumsky::prelude::*;
 
+    enum ShiftReduceTail<'a> {
+      Op(ExpressionPrecedenceContext, Expression<'a>),
+      Cast(TypeName<'a>),
+    }
+
     recursive(|expr_parser| {
       unary_expr_parser(expr_parser.clone())
         .map(ShiftReduceExpressionState::new)
         .then(
-          Self::binary_op_parser().padded_by(whitespace_parser())
-            .then(unary_expr_parser(expr_parser))
+          choice((
+            cast_suffix_parser(expr_parser.clone()).map(ShiftReduceTail::Cast),
+            Self::binary_op_parser().padded_by(whitespace_parser())
+              .then(unary_expr_parser(expr_parser))
+              .map(|(op, expr)| ShiftReduceTail::Op(op, expr)),
+          ))
             .repeated()
             .collect::<Vec<_>>()
         )
-        .try_map(|(state, op_expr_vec), span| {
-          let result = op_expr_vec.into_iter().fold(
+        .try_map(|(state, tails), span| {
+          let result = tails.into_iter().fold(
             state,
-            |mut state, (next_op, next_expr)| {
-              state.push_op_expr(next_op, next_expr);
+            |mut state, tail| {
+              match tail {
+                ShiftReduceTail::Op(next_op, next_expr) =>
+                  state.push_op_expr(next_op, next_expr),
+                ShiftReduceTail::Cast(ty) =>
+                  state.push_cast(ty),
+              }
               state
             }
           ).finalize();
@@ -158,6 +199,7 @@ pub enum ExpressionPrecedenceContext {
   Add(AddExprOp),
   Mul(MulExprOp),
   Bit(BitExprOp),
+  Cast,
 }
 impl ExpressionPrecedenceContext {
   fn name(self) -> &'static str {
@@ -170,6 +212,7 @@ impl ExpressionPrecedenceContext {
       ExpressionPrecedenceContext::Add(_) => "add",
       ExpressionPrecedenceContext::Mul(_) => "mul",
       ExpressionPrecedenceContext::Bit(_) => "bit",
+      ExpressionPrecedenceContext::Cast => "cast",
     }
   }
 
@@ -202,6 +245,8 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => make_err_result(op),
         ExpressionPrecedenceContext::Mul(_) => make_err_result(op),
         ExpressionPrecedenceContext::Bit(_) => make_err_result(op),
+        // Casts bind tighter than any binary operator.
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Logical(LogicalExprOp::Or) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -215,6 +260,7 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => make_err_result(op),
         ExpressionPrecedenceContext::Mul(_) => make_err_result(op),
         ExpressionPrecedenceContext::Bit(_) => make_err_result(op),
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Relational(_) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -228,6 +274,7 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => ShiftReduceResult::Shift,
         ExpressionPrecedenceContext::Mul(_) => ShiftReduceResult::Shift,
         ExpressionPrecedenceContext::Bit(_) => ShiftReduceResult::Shift,
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Shift(_) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -241,6 +288,7 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => make_err_result(op),
         ExpressionPrecedenceContext::Mul(_) => make_err_result(op),
         ExpressionPrecedenceContext::Bit(_) => ShiftReduceResult::Shift,
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Add(_) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -254,6 +302,7 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => ShiftReduceResult::Reduce,
         ExpressionPrecedenceContext::Mul(_) => ShiftReduceResult::Shift,
         ExpressionPrecedenceContext::Bit(_) => make_err_result(op),
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Mul(_) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -267,6 +316,7 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => ShiftReduceResult::Reduce,
         ExpressionPrecedenceContext::Mul(_) => make_err_result(op),
         ExpressionPrecedenceContext::Bit(_) => ShiftReduceResult::Shift,
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
       ExpressionPrecedenceContext::Bit(_) => match op {
         ExpressionPrecedenceContext::Top => make_err_result(op),
@@ -280,7 +330,12 @@ impl ExpressionPrecedenceContext {
         ExpressionPrecedenceContext::Add(_) => make_err_result(op),
         ExpressionPrecedenceContext::Mul(_) => make_err_result(op),
         ExpressionPrecedenceContext::Bit(_) => ShiftReduceResult::Shift,
+        ExpressionPrecedenceContext::Cast => ShiftReduceResult::Shift,
       },
+      // Casts are applied to the top of the stack immediately, and are
+      // never pushed as an operator context.
+      ExpressionPrecedenceContext::Cast =>
+        unreachable!("Cast is never an operator context"),
     }
   }
 
@@ -293,6 +348,9 @@ impl ExpressionPrecedenceContext {
       ExpressionPrecedenceContext::Top => {
         panic!("Cannot make binary expression with top precedence context")
       },
+      ExpressionPrecedenceContext::Cast => {
+        panic!("Cannot make binary expression with cast precedence context")
+      },
       ExpressionPrecedenceContext::Logical(op) =>
         Expression::Logical(LogicalExpr { lhs, op, rhs }),
       ExpressionPrecedenceContext::Relational(op) =>
@@ -355,6 +413,31 @@ impl<'a> ShiftReduceExpressionState<'a> {
     }
   }
 
+  fn push_cast(&mut self, ty: TypeName<'a>) {
+    if self.error.is_some() {
+      return;
+    }
+
+    loop {
+      let current_prec = self.current_prec();
+      match current_prec.shift_reduce(ExpressionPrecedenceContext::Cast) {
+        ShiftReduceResult::Error(err) => {
+          self.error = Some(err);
+          return;
+        },
+        ShiftReduceResult::Shift => {
+          let subexpr = self.stack.pop().expect("Empty stack").boxed();
+          self.stack.push(Expression::Cast(CastExpr { subexpr, ty }));
+          return;
+        },
+        ShiftReduceResult::Reduce => {
+          self.reduce();
+          continue;
+        },
+      }
+    }
+  }
+
   fn finalize(mut self) -> Result<Expression<'a>, String> {
     if self.error.is_some() {
       return Err(self.error.take().unwrap());
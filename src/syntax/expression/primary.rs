@@ -14,8 +14,9 @@ use crate::syntax::{
 /**
  * A dot-expression accesses a component of a value expression.
  * 
- * Either `struct_value.field` or `vec_value.i`.
- * E.g. `unit.health` or `point_vec.0`
+ * Either `struct_value.field`, `vec_value.i` or a swizzle of vector
+ * components `vec_value.xyzw`.
+ * E.g. `unit.health`, `point_vec.0` or `color.zyx`
  */
 #[derive(Debug, Clone)]
 pub struct DotExpr<'a> {
@@ -29,6 +30,17 @@ pub enum DotExprSuffix<'a> {
   Number(u32),
 }
 
+/**
+ * An index expression accesses an element of an array or buffer.
+ *
+ * E.g. `birds[i]`
+ */
+#[derive(Debug, Clone)]
+pub struct IndexExpr<'a> {
+  pub target: Box<Expression<'a>>,
+  pub index: Box<Expression<'a>>,
+}
+
 /**
  * A function call expression.
  */
@@ -65,11 +77,18 @@ pub(crate) fn primary_expr_parser<'a, E>(
   use chumsky::prelude::*;
   enum PrimaryTail<'a> {
     Dot(DotExprSuffix<'a>),
+    Index(Expression<'a>),
     Call(Vec<Expression<'a>>),
   }
 
   let dot_tail_parser = make_dot_tail_parser().map(PrimaryTail::Dot);
 
+  let index_tail_parser =
+    just("[").padded_by(whitespace_parser())
+      .ignore_then(base_expr.clone())
+      .then_ignore(just("]").padded_by(whitespace_parser()))
+      .map(PrimaryTail::Index);
+
   let call_tail_parser =
     just("(").padded_by(whitespace_parser())
       .ignore_then(
@@ -83,7 +102,7 @@ pub(crate) fn primary_expr_parser<'a, E>(
 
   terminal_expr_parser(base_expr)
     .then(
-      choice((call_tail_parser, dot_tail_parser))
+      choice((call_tail_parser, index_tail_parser, dot_tail_parser))
         .repeated()
         .collect::<Vec<_>>()
     ).map(|(target, tails)| {
@@ -91,6 +110,12 @@ pub(crate) fn primary_expr_parser<'a, E>(
         PrimaryTail::Dot(name) =>
           Expression::Dot(DotExpr { target: Box::new(target), name }),
 
+        PrimaryTail::Index(index) =>
+          Expression::Index(IndexExpr {
+            target: Box::new(target),
+            index: Box::new(index),
+          }),
+
         PrimaryTail::Call(args) =>
           Expression::Call(CallExpr { callee: Box::new(target), args }),
       })
@@ -2,10 +2,11 @@
  * Terminal expressions.
  */
 
+use std::fmt;
 use chumsky::{ Parser, extra::ParserExtra };
 use crate::syntax::{
   expression::Expression,
-  name::NamePath,
+  name::{ Name, NamePath },
   util::{
     dec_digit_parser,
     bin_digit_parser,
@@ -58,13 +59,74 @@ pub enum IntLiteralExprBase {
   Octal,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum IntLiteralExprType {
   U32,
   I32,
+  U64,
+  I64,
+}
+
+/**
+ * The checked value of an integer literal.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntLiteralValue {
+  U32(u32),
+  I32(i32),
+  U64(u64),
+  I64(i64),
+}
+
+/**
+ * An integer literal whose value does not fit its type.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntLiteralRangeError {
+  pub ty: IntLiteralExprType,
 }
 
 impl<'a> IntLiteralExpr<'a> {
+  /**
+   * Get the value of the literal.  The literal's own suffix, if any,
+   * takes precedence over the type given by context.
+   */
+  pub fn value(&self, context_ty: IntLiteralExprType)
+    -> Result<IntLiteralValue, IntLiteralRangeError>
+  {
+    let ty = self.ty.unwrap_or(context_ty);
+    let range_error = IntLiteralRangeError { ty };
+    let radix = match self.base {
+      None | Some(IntLiteralExprBase::Decimal) => 10,
+      Some(IntLiteralExprBase::Hexadecimal) => 16,
+      Some(IntLiteralExprBase::Binary) => 2,
+      Some(IntLiteralExprBase::Octal) => 8,
+    };
+
+    // Accumulate the magnitude, which is at most 2^64 for any valid
+    // literal, so u128 overflow is itself a range error.
+    let mut magnitude: u128 = 0;
+    for digit in self.value.chars().filter(|&c| c != '_') {
+      let digit = digit.to_digit(radix).expect("Invalid digit in literal");
+      magnitude = magnitude.checked_mul(radix as u128)
+        .and_then(|m| m.checked_add(digit as u128))
+        .ok_or(range_error)?;
+    }
+    let signed = match self.sign {
+      Some(IntLiteralExprSign::Negative) =>
+        i128::try_from(magnitude).map(|m| -m).map_err(|_| range_error)?,
+      _ => i128::try_from(magnitude).map_err(|_| range_error)?,
+    };
+
+    let value = match ty {
+      IntLiteralExprType::U32 => u32::try_from(signed).map(IntLiteralValue::U32),
+      IntLiteralExprType::I32 => i32::try_from(signed).map(IntLiteralValue::I32),
+      IntLiteralExprType::U64 => u64::try_from(signed).map(IntLiteralValue::U64),
+      IntLiteralExprType::I64 => i64::try_from(signed).map(IntLiteralValue::I64),
+    };
+    value.map_err(|_| range_error)
+  }
+
   fn digits_run_parser<X, E>(
     digit: impl Clone + Parser<'a, &'a str, X, E>
   ) -> impl Clone + Parser<'a, &'a str, &'a str, E>
@@ -92,7 +154,7 @@ impl<'a> IntLiteralExpr<'a> {
         .ignore_then(
           Self::digits_run_parser(dec_digit_parser())
         )
-        .map(|digits| (Some(IntLiteralExprBase::Hexadecimal), digits));
+        .map(|digits| (Some(IntLiteralExprBase::Decimal), digits));
     let bin_main_parser =
       just("0b")
         .ignore_then(
@@ -118,6 +180,8 @@ impl<'a> IntLiteralExpr<'a> {
     let ty_parser = choice((
       just("_u32").map(|_| IntLiteralExprType::U32),
       just("_i32").map(|_| IntLiteralExprType::I32),
+      just("_u64").map(|_| IntLiteralExprType::U64),
+      just("_i64").map(|_| IntLiteralExprType::I64),
     ));
 
     sign_parser.padded_by(whitespace_parser()).or_not()
@@ -135,6 +199,75 @@ impl<'a> IntLiteralExpr<'a> {
   }
 }
 
+impl<'a> fmt::Display for IntLiteralExpr<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.sign {
+      Some(IntLiteralExprSign::Negative) => write!(f, "-")?,
+      Some(IntLiteralExprSign::Positive) => write!(f, "+")?,
+      None => {},
+    }
+    match self.base {
+      Some(IntLiteralExprBase::Decimal) => write!(f, "0d")?,
+      Some(IntLiteralExprBase::Hexadecimal) => write!(f, "0x")?,
+      Some(IntLiteralExprBase::Binary) => write!(f, "0b")?,
+      Some(IntLiteralExprBase::Octal) => write!(f, "0o")?,
+      None => {},
+    }
+    write!(f, "{}", self.value)?;
+    match self.ty {
+      Some(IntLiteralExprType::U32) => write!(f, "_u32"),
+      Some(IntLiteralExprType::I32) => write!(f, "_i32"),
+      Some(IntLiteralExprType::U64) => write!(f, "_u64"),
+      Some(IntLiteralExprType::I64) => write!(f, "_i64"),
+      None => Ok(()),
+    }
+  }
+}
+
+/**
+ * Floating point literal expression.
+ *
+ * E.g. `1.0` or `-0.25`
+ */
+#[derive(Clone, Debug)]
+pub struct FloatLiteralExpr<'a> {
+  pub sign: Option<IntLiteralExprSign>,
+  pub value: &'a str,
+}
+impl<'a> FloatLiteralExpr<'a> {
+  pub fn parser<E>() -> impl Clone + Parser<'a, &'a str, FloatLiteralExpr<'a>, E>
+    where E: ParserExtra<'a, &'a str>
+  {
+    use chumsky::prelude::*;
+
+    let sign_parser = choice((
+      just('-').map(|_| IntLiteralExprSign::Negative),
+      just('+').map(|_| IntLiteralExprSign::Positive),
+    ));
+
+    sign_parser.padded_by(whitespace_parser()).or_not()
+      .then(
+        IntLiteralExpr::digits_run_parser(dec_digit_parser())
+          .then(just('.'))
+          .then(IntLiteralExpr::digits_run_parser(dec_digit_parser()))
+          .to_slice()
+      )
+      .map(|(sign, value)| FloatLiteralExpr { sign, value })
+  }
+
+  /**
+   * Get the value of the literal as an f32.
+   */
+  pub fn value(&self) -> f32 {
+    let digits = self.value.replace('_', "");
+    let magnitude = digits.parse::<f32>().expect("Invalid float literal");
+    match self.sign {
+      Some(IntLiteralExprSign::Negative) => -magnitude,
+      _ => magnitude,
+    }
+  }
+}
+
 /**
  * Parentheses-enclosed expression.
  * 
@@ -158,6 +291,56 @@ impl<'a> ParenExpr<'a> {
   }
 }
 
+/**
+ * Struct literal expression.
+ *
+ * E.g. `Position { x: 0, y: 0 }`
+ *
+ * At least one field is required, so that a name followed by a block
+ * (e.g. `if done { ... }`) is not mistaken for a literal.
+ */
+#[derive(Clone, Debug)]
+pub struct StructLiteralExpr<'a> {
+  pub name: NamePath<'a>,
+  pub fields: Vec<StructLiteralExprField<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StructLiteralExprField<'a> {
+  pub name: Name<'a>,
+  pub value: Expression<'a>,
+}
+
+impl<'a> StructLiteralExpr<'a> {
+  pub fn parser<E>(
+    base_expr: impl Clone + Parser<'a, &'a str, Expression<'a>, E>
+  ) -> impl Clone + Parser<'a, &'a str, StructLiteralExpr<'a>, E>
+    where E: ParserExtra<'a, &'a str>
+  {
+    use chumsky::prelude::*;
+
+    let field_parser =
+      Name::parser()
+        .then_ignore(just(':').padded_by(whitespace_parser()))
+        .then(base_expr)
+        .map(|(name, value)| StructLiteralExprField { name, value });
+
+    NamePath::parser()
+      .then(
+        field_parser
+          .separated_by(just(',').padded_by(whitespace_parser()))
+          .at_least(1)
+          .allow_trailing()
+          .collect::<Vec<_>>()
+          .delimited_by(
+            just('{').padded_by(whitespace_parser()),
+            just('}').padded_by(whitespace_parser()),
+          )
+      )
+      .map(|(name, fields)| StructLiteralExpr { name, fields })
+  }
+}
+
 pub(crate) fn terminal_expr_parser<'a, E>(
   base_expr: impl Clone + Parser<'a, &'a str, Expression<'a>, E>
 ) -> impl Clone + Parser<'a, &'a str, Expression<'a>, E>
@@ -165,7 +348,9 @@ pub(crate) fn terminal_expr_parser<'a, E>(
 {
   use chumsky::prelude::*;
   choice((
+    StructLiteralExpr::parser(base_expr.clone()).map(Expression::StructLiteral),
     NameExpr::parser().map(Expression::Name),
+    FloatLiteralExpr::parser().map(Expression::FloatLiteral),
     IntLiteralExpr::parser().map(Expression::IntLiteral),
     ParenExpr::parser(base_expr).map(Expression::Paren),
   ))
@@ -9,10 +9,12 @@ use crate::syntax::{
     FuncDecl,
     ModuleDecl,
     StructDecl,
+    TestDecl,
     import_decl_parser,
     func_decl_parser,
     module_decl_parser,
     struct_decl_parser,
+    test_decl_parser,
   },
   util::whitespace_parser,
 };
@@ -52,6 +54,7 @@ pub enum LibraryFileDeclaration<'a> {
   Func(FuncDecl<'a>),
   Module(ModuleDecl<'a>),
   Struct(StructDecl<'a>),
+  Test(TestDecl<'a>),
 }
 impl<'a> LibraryFileDeclaration<'a> {
   pub fn parser<E>() -> impl Clone + Parser<'a, &'a str, Self, E>
@@ -65,6 +68,7 @@ impl<'a> LibraryFileDeclaration<'a> {
       module_decl_parser(Declaration::parser_for_module())
         .map(LibraryFileDeclaration::Module),
       struct_decl_parser().map(LibraryFileDeclaration::Struct),
+      test_decl_parser().map(LibraryFileDeclaration::Test),
     ))
     .boxed()
   }
@@ -13,6 +13,7 @@ use crate::syntax::{
     ModuleDecl,
     StructDecl,
     UniformsDecl,
+    TestDecl,
     entrypoint_decl_parser,
     buffer_decl_parser,
     import_decl_parser,
@@ -21,6 +22,7 @@ use crate::syntax::{
     module_decl_parser,
     struct_decl_parser,
     uniforms_decl_parser,
+    test_decl_parser,
   },
   util::whitespace_parser,
 };
@@ -63,6 +65,7 @@ pub enum ShaderFileDeclaration<'a> {
   Module(ModuleDecl<'a>),
   Struct(StructDecl<'a>),
   Uniforms(UniformsDecl<'a>),
+  Test(TestDecl<'a>),
 }
 impl<'a> ShaderFileDeclaration<'a> {
   pub fn parser<E>() -> impl Clone + Parser<'a, &'a str, Self, E>
@@ -80,6 +83,7 @@ impl<'a> ShaderFileDeclaration<'a> {
         .map(ShaderFileDeclaration::Module),
       struct_decl_parser().map(ShaderFileDeclaration::Struct),
       uniforms_decl_parser().map(ShaderFileDeclaration::Uniforms),
+      test_decl_parser().map(ShaderFileDeclaration::Test),
     ))
     .boxed()
   }
@@ -0,0 +1,41 @@
+use std::ops::Range;
+use chumsky::{
+  Parser,
+  extra::ParserExtra,
+};
+use crate::syntax::{
+  expression::Expression,
+  util::{ terminal_semicolon_parser, whitespace_parser },
+};
+
+/**
+ * An assertion, only allowed in tests.  The span covers the assertion
+ * up to its closing parenthesis, as byte offsets into the parsed
+ * source.
+ */
+#[derive(Debug, Clone)]
+pub struct AssertStmt<'a> {
+  pub cond: Box<Expression<'a>>,
+  pub span: Range<usize>,
+}
+
+pub(crate) fn assert_stmt_parser<'a, E>()
+  -> impl Clone + Parser<'a, &'a str, AssertStmt<'a>, E>
+  where E: ParserExtra<'a, &'a str>
+{
+  use chumsky::prelude::*;
+
+  text::keyword("assert")
+    .ignore_then(
+      Expression::parser()
+        .delimited_by(
+          just('(').padded_by(whitespace_parser()),
+          whitespace_parser().then(just(')')),
+        )
+    )
+    .map_with(|cond, e| {
+      let span: SimpleSpan = e.span();
+      AssertStmt { cond: cond.boxed(), span: span.into_range() }
+    })
+    .then_ignore(terminal_semicolon_parser())
+}
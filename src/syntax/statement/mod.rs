@@ -5,6 +5,7 @@ mod var_stmt;
 mod ret_stmt;
 mod if_stmt;
 mod loop_stmt;
+mod assert_stmt;
 
 use crate::syntax::util::whitespace_parser;
 
@@ -16,6 +17,7 @@ pub use self::{
   ret_stmt::RetStmt,
   if_stmt::IfStmt,
   loop_stmt::LoopStmt,
+  assert_stmt::AssertStmt,
 };
 pub(crate) use self::{
   mutate_stmt::mutate_stmt_parser,
@@ -25,6 +27,7 @@ pub(crate) use self::{
   ret_stmt::ret_stmt_parser,
   if_stmt::if_stmt_parser,
   loop_stmt::loop_stmt_parser,
+  assert_stmt::assert_stmt_parser,
 };
 
 use chumsky::{
@@ -45,6 +48,7 @@ pub enum Statement<'a> {
   Ret(RetStmt<'a>),
   If(IfStmt<'a>),
   Loop(LoopStmt<'a>),
+  Assert(AssertStmt<'a>),
 }
 impl<'a> Statement<'a> {
   pub fn boxed(self) -> Box<Self> {
@@ -66,6 +70,7 @@ impl<'a> Statement<'a> {
         ret_stmt_parser().map(Statement::Ret),
         if_stmt_parser(stmt_parser.clone()).map(Statement::If),
         loop_stmt_parser(stmt_parser).map(Statement::Loop),
+        assert_stmt_parser().map(Statement::Assert),
       ))
     }).boxed()
   }
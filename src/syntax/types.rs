@@ -2,15 +2,23 @@
  * Parsers for types.
  */
 
+use std::fmt;
 use chumsky::{ Parser, extra::ParserExtra };
-use crate::syntax::name::NamePath;
+use crate::syntax::{
+  expression::{ ConstExpr, Expression },
+  name::NamePath,
+  util::whitespace_parser,
+};
 
 /**
  * A type name.
+ *
+ * E.g. `BirdInfo`, `[f32; 16]` or `[BirdInfo]`
  */
 #[derive(Debug, Clone)]
-pub struct TypeName<'a> {
-  pub name: NamePath<'a>
+pub enum TypeName<'a> {
+  Path(NamePath<'a>),
+  Array(ArrayTypeName<'a>),
 }
 impl<'a> TypeName<'a> {
   /**
@@ -19,10 +27,70 @@ impl<'a> TypeName<'a> {
   pub fn parser<E>()
     -> impl 'a + Clone + Parser<'a, &'a str, TypeName<'a>, E>
     where E: ParserExtra<'a, &'a str>
+  {
+    Self::parser_with(Expression::parser())
+  }
+
+  /**
+   * Parse a type name, using the given parser for array lengths.
+   * Expression parsers pass themselves, since casts contain types.
+   */
+  pub fn parser_with<E>(
+    expr_parser: impl 'a + Clone + Parser<'a, &'a str, Expression<'a>, E>
+  ) -> impl 'a + Clone + Parser<'a, &'a str, TypeName<'a>, E>
+    where E: ParserExtra<'a, &'a str>
   {
     use chumsky::prelude::*;
 
-    NamePath::parser()
-      .map(|name| TypeName { name })
+    recursive(|type_name| {
+      let array_parser =
+        type_name
+          .then(
+            just(';').padded_by(whitespace_parser())
+              .ignore_then(ConstExpr::parser(expr_parser))
+              .or_not()
+          )
+          .delimited_by(
+            just('[').padded_by(whitespace_parser()),
+            just(']').padded_by(whitespace_parser()),
+          )
+          .map(|(elem, len)| {
+            TypeName::Array(ArrayTypeName { elem: Box::new(elem), len })
+          });
+
+      choice((
+        array_parser,
+        NamePath::parser().map(TypeName::Path),
+      ))
+    })
   }
 }
+
+impl<'a> fmt::Display for TypeName<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TypeName::Path(path) => {
+        let parts = path.parts.iter()
+          .map(|part| part.contents)
+          .collect::<Vec<_>>();
+        write!(f, "{}", parts.join("::"))
+      },
+      TypeName::Array(array) => match &array.len {
+        Some(len) => write!(f, "[{}; {}]", array.elem, len),
+        None => write!(f, "[{}]", array.elem),
+      },
+    }
+  }
+}
+
+/**
+ * An array type name.  Arrays without a length are runtime-sized, and
+ * the length of others is a constant expression.
+ *
+ * E.g. `[f32; 16]`, `[u32; 4 * 4]` or `[BirdInfo]`
+ */
+#[derive(Debug, Clone)]
+pub struct ArrayTypeName<'a> {
+  pub elem: Box<TypeName<'a>>,
+  pub len: Option<ConstExpr<'a>>,
+}
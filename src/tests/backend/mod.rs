@@ -0,0 +1,73 @@
+mod test_glsl;
+mod test_hlsl;
+mod test_msl;
+mod test_rust_cpu;
+mod test_spirv;
+mod test_wgsl;
+
+/** Generated by the Rust backend from `test_rust_cpu::KERNEL_SHADER`. */
+#[allow(clippy::all, unused_parens)]
+mod rust_cpu_kernel;
+
+const BACKEND_SHADER: &str = "
+  struct Particle { position: vec3xf32, velocity: vec3xf32, mass: f32 }
+  struct Grid { width: u32, @align(16) cells: [vec2xi32] }
+  uniforms { gravity: vec3xf32, dt: f32, steps: u32, tint: Tint }
+  struct Tint { color: vec4xf32, scale: f32 }
+  @group(1) buffer(rw) particles: Particle;
+  buffer(r) grid: Grid;
+  buffer(w) sums: f32;
+  buffer(r) weights: f16;
+
+  func energy(p: Particle) -> f32 {
+    let v = p.velocity;
+    ret (0.5 * p.mass) * (v.x * v.x + v.y * v.y + v.z * v.z);
+  }
+
+  func clamp_index(i: u32, n: u32) -> u32 {
+    if i < n {
+      ret i;
+    } else {
+      if n == 0 { ret 0; } else { ret n - 1; }
+    }
+  }
+
+  entrypoint(1d) step(i) {
+    var p = particles[i];
+    mutate p.velocity = p.velocity + uniforms.gravity * uniforms.dt;
+    mutate p.position.xz = p.position.zx;
+    mutate p.position.zyx.x = -p.mass;
+    mutate particles[clamp_index(i, particles.length)].velocity.zyx = p.velocity;
+    mutate particles[i] = p;
+    var j = 0_u32;
+    var total = 0.0;
+    loop {
+      if !(j < uniforms.steps) { ret; }
+      mutate total = total - energy(p) * weights[j] as f32;
+      mutate j = j + 1;
+    }
+  }
+
+  entrypoint(2d) fill(id) {
+    let cell = grid.cells[id.x + id.y * grid.width];
+    let mask = ~cell & 3;
+    exec energy(particles[0]);
+    mutate sums[id.x] = (mask.x + -2147483648) as f32 * uniforms.tint.scale + 0.1;
+  }
+";
+
+/**
+ * Casts from floats to integers, which saturate, unlike the conversions
+ * of the targets.
+ */
+const CAST_SHADER: &str = "
+  uniforms { scale: f32, half: f16 }
+  buffer(w) ints: i32;
+  buffer(w) pairs: vec2xu32;
+  buffer(w) wides: i64;
+  entrypoint(1d) run(i) {
+    mutate ints[i] = uniforms.scale as i32;
+    mutate pairs[i] = vec2xf32(uniforms.scale, 1.5) as vec2xu32;
+    mutate wides[i] = uniforms.half as i64;
+  }
+";
@@ -0,0 +1,210 @@
+// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+// Requires the `rayon` crate.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uniforms {
+  pub dt: f32,
+  pub gravity: [f32; 2],
+  pub scale: u32,
+}
+
+/**
+ * The resources bound for a dispatch.  Entrypoint methods run a single
+ * invocation, and `dispatch` runs many in parallel.
+ */
+pub struct Resources<'a> {
+  pub uniforms: &'a Uniforms,
+  pub velocities: &'a [[f32; 2]],
+  pub positions: dubgsl_rt::Shared<'a, [[f32; 2]]>,
+  pub counts: dubgsl_rt::Shared<'a, [u32]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entrypoint {
+  Step,
+}
+
+impl Resources<'_> {
+  /**
+   * Run an entrypoint over a grid of workgroups.  Workgroups run in
+   * parallel, and the invocations of each in order of their ids.
+   *
+   * # Safety
+   *
+   * As on a GPU, an invocation must not write a buffer element that
+   * another invocation reads or writes.
+   *
+   * # Panics
+   *
+   * If the invocations along an axis don't fit in a `u32`, or if an
+   * invocation panics, e.g. indexing out of bounds.
+   */
+  pub unsafe fn dispatch(&self, entrypoint: Entrypoint, workgroups: [u32; 3]) {
+    let resources = dubgsl_rt::AssertSync(self);
+    match entrypoint {
+      Entrypoint::Step => dubgsl_rt::dispatch([64, 1, 1], workgroups, |[x, _, _]| resources.get().step(x)),
+    }
+  }
+
+  pub fn step(&self, i: u32) {
+    if i < (self.positions.len() as u32) {
+      let v = dubgsl_rt::zip(self.velocities[i as usize], dubgsl_rt::zip(self.uniforms.gravity, [self.uniforms.dt; 2], |a, b| a * b), |a, b| a + b);
+      unsafe { (*self.positions.elem(i as usize)) = dubgsl_rt::zip((*self.positions.elem(i as usize)), dubgsl_rt::zip(v, [self.uniforms.dt; 2], |a, b| a * b), |a, b| a + b); }
+      {
+        let dubgsl_value = unsafe { (*self.positions.elem(i as usize)) };
+        let dubgsl_target = std::ptr::addr_of_mut!((*self.positions.elem(i as usize)));
+        unsafe { (*dubgsl_target)[1] = dubgsl_value[0]; }
+        unsafe { (*dubgsl_target)[0] = dubgsl_value[1]; }
+      }
+      unsafe { (*self.counts.elem(i as usize)) = (i.wrapping_mul(self.uniforms.scale)).wrapping_add((v[0] * 100.0_f32) as u32); }
+    }
+  }
+}
+
+#[allow(dead_code)]
+pub mod dubgsl_rt {
+  use rayon::prelude::*;
+  use std::{ marker::PhantomData, ops::{ Add, Mul } };
+
+  /**
+   * A buffer written by invocations, borrowed for the lifetime of the
+   * resources.  It is not `Sync`: invocations only run in parallel
+   * through the `unsafe` dispatch, and only reach it through pointers.
+   */
+  pub struct Shared<'a, T: ?Sized> {
+    ptr: *mut T,
+    _borrow: PhantomData<&'a mut T>,
+  }
+  unsafe impl<T: ?Sized + Send> Send for Shared<'_, T> {}
+  impl<'a, T: ?Sized> Shared<'a, T> {
+    pub fn new(value: &'a mut T) -> Self {
+      Shared { ptr: value, _borrow: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut T {
+      self.ptr
+    }
+  }
+  impl<T> Shared<'_, [T]> {
+    pub fn len(&self) -> usize {
+      self.ptr.len()
+    }
+
+    /** A pointer to an element, which must be in bounds. */
+    pub fn elem(&self, i: usize) -> *mut T {
+      let len = self.len();
+      assert!(i < len, "Index {} is out of bounds for a buffer of length {}", i, len);
+      unsafe { self.ptr.cast::<T>().add(i) }
+    }
+  }
+
+  /**
+   * Resources shared with the invocations of a dispatch, whose caller
+   * promises that they don't race.
+   */
+  pub struct AssertSync<'a, T>(pub &'a T);
+  unsafe impl<T> Sync for AssertSync<'_, T> {}
+  impl<'a, T> AssertSync<'a, T> {
+    pub fn get(&self) -> &'a T {
+      self.0
+    }
+  }
+
+  pub fn map<A: Copy, U, const N: usize>(a: [A; N], f: impl Fn(A) -> U) -> [U; N] {
+    std::array::from_fn(|i| f(a[i]))
+  }
+
+  pub fn zip<A: Copy, B: Copy, U, const N: usize>(
+    a: [A; N],
+    b: [B; N],
+    f: impl Fn(A, B) -> U,
+  ) -> [U; N] {
+    std::array::from_fn(|i| f(a[i], b[i]))
+  }
+
+  pub fn swizzle<T: Copy, const N: usize, const M: usize>(v: [T; N], components: [usize; M])
+    -> [T; M]
+  {
+    components.map(|c| v[c])
+  }
+
+  /** The sum of the matrix's columns, each scaled by the vector's component. */
+  pub fn mat_vec<F, const C: usize, const R: usize>(m: [[F; R]; C], v: [F; C]) -> [F; R]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    let mut sum = m[0].map(|a| a * v[0]);
+    for c in 1..C {
+      sum = zip(sum, m[c], |s, a| s + a * v[c]);
+    }
+    sum
+  }
+
+  /** The dot products of the vector with each of the matrix's columns. */
+  pub fn vec_mat<F, const C: usize, const R: usize>(v: [F; R], m: [[F; R]; C]) -> [F; C]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    m.map(|column| {
+      let mut dot = v[0] * column[0];
+      for r in 1..R {
+        dot = dot + v[r] * column[r];
+      }
+      dot
+    })
+  }
+
+  pub fn mat_mat<F, const K: usize, const C: usize, const R: usize>(
+    a: [[F; R]; C],
+    b: [[F; C]; K],
+  ) -> [[F; R]; K]
+    where F: Copy + Add<Output = F> + Mul<Output = F>
+  {
+    b.map(|column| mat_vec(a, column))
+  }
+
+  pub fn mat_scalar<F, const C: usize, const R: usize>(m: [[F; R]; C], s: F) -> [[F; R]; C]
+    where F: Copy + Mul<Output = F>
+  {
+    m.map(|column| column.map(|a| a * s))
+  }
+
+  pub fn scalar_mat<F, const C: usize, const R: usize>(s: F, m: [[F; R]; C]) -> [[F; R]; C]
+    where F: Copy + Mul<Output = F>
+  {
+    mat_scalar(m, s)
+  }
+
+  /**
+   * Run an invocation for each id in a grid of workgroups.  Workgroups
+   * run in parallel, and the invocations of each in order of their ids,
+   * with `x` varying fastest.
+   *
+   * # Panics
+   *
+   * If the invocations along an axis don't fit in a `u32`, before any
+   * are run.
+   */
+  pub fn dispatch(
+    workgroup_size: [u32; 3],
+    workgroups: [u32; 3],
+    invocation: impl Fn([u32; 3]) + Sync,
+  ) {
+    for axis in 0..3 {
+      workgroups[axis].checked_mul(workgroup_size[axis]).expect("Dispatch is too large");
+    }
+    let [sx, sy, sz] = workgroup_size;
+    let [wx, wy, wz] = workgroups;
+    (0..wz).into_par_iter().for_each(|gz| {
+      (0..wy).into_par_iter().for_each(|gy| {
+        (0..wx).into_par_iter().for_each(|gx| {
+          for z in gz * sz..(gz + 1) * sz {
+            for y in gy * sy..(gy + 1) * sy {
+              for x in gx * sx..(gx + 1) * sx {
+                invocation([x, y, z]);
+              }
+            }
+          }
+        });
+      });
+    });
+  }
+}
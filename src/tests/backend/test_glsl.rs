@@ -0,0 +1,232 @@
+use crate::{ backend::generate_glsl, tests::check_source };
+use super::{ BACKEND_SHADER, CAST_SHADER };
+
+#[test]
+fn test_glsl() {
+  let model = check_source(BACKEND_SHADER);
+  assert_eq!(generate_glsl(&model, "step").unwrap(), EXPECTED_GLSL);
+
+  // Other entrypoints share the declarations, with their own `main`.
+  let fill = generate_glsl(&model, "fill").unwrap();
+  assert!(fill.contains(
+    "layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;"
+  ));
+  assert!(fill.ends_with(
+    "void main() {\n  \
+       uvec2 id = gl_GlobalInvocationID.xy;\n  \
+       ivec2 cell = grid.cells[id.x + (id.y * grid.width)];\n  \
+       ivec2 mask = ~cell & 3;\n  \
+       energy(particles[0u]);\n  \
+       sums[id.x] = (float(mask.x + (-2147483647 - 1)) * uniforms.tint.scale) + 0.1;\n\
+     }\n"
+  ));
+}
+
+#[test]
+fn test_glsl_layout() {
+  let glsl = generate_glsl(&check_source("
+    struct Padded { a: f32, @align(16) b: vec2xf32, @size(8) c: u32 }
+    struct Halves { x: f16, @align(8) y: f16 }
+    struct Outer { @align(16) inner: Padded, weights: [f32; 3] }
+    uniforms { scale: f32, @size(12) count: u32, outer: Outer }
+    buffer(rw) items: Padded;
+    buffer(rw) totals: i64;
+    buffer(rw) halves: Halves;
+    entrypoint(3d) run(id) {
+      let i = id.x;
+      mutate items[i] = Padded { c: 4, a: 1.0, b: vec2xf32(2.0, 3.0) % 2.0 };
+      mutate totals[i] = totals[i] % -3 + 9223372036854775807;
+      mutate items[i].c = items[i].c % 2;
+      mutate halves[i] = Halves { x: 1.5, y: 2.0 };
+    }
+  "), "run").unwrap();
+
+  // Fields placed by attributes are reached with padding members, both
+  // between fields and at the end of the struct.
+  assert!(glsl.contains(
+    "struct Padded {\n  float a;\n  uint dubgsl_pad0;\n  uint dubgsl_pad1;\n  \
+     uint dubgsl_pad2;\n  vec2 b;\n  uint c;\n};"
+  ));
+  assert!(glsl.contains(
+    "struct Halves {\n  float16_t x;\n  float16_t dubgsl_pad0;\n  \
+     uint dubgsl_pad1;\n  float16_t y;\n  float16_t dubgsl_pad2;\n  \
+     uint dubgsl_pad3;\n};"
+  ));
+  assert!(glsl.contains(
+    "layout(std140, binding = 0) uniform dubgsl_uniforms_block {\n  \
+     float scale;\n  uint count;\n  Outer outer;\n} uniforms;"
+  ));
+  assert!(glsl.contains(
+    "#extension GL_EXT_shader_explicit_arithmetic_types_int64 : require\n"
+  ));
+
+  // Signed and float remainders truncate, through helpers.
+  assert!(glsl.contains(
+    "int64_t dubgsl_rem(int64_t a, int64_t b) {\n  return a - b * (a / b);\n}"
+  ));
+  assert!(glsl.contains(
+    "vec2 dubgsl_rem(vec2 a, vec2 b) {\n  return a - b * trunc(a / b);\n}"
+  ));
+  assert!(glsl.contains(
+    "items[i] = Padded(1.0, 0u, 0u, 0u, dubgsl_rem(vec2(2.0, 3.0), vec2(2.0)), 4u);"
+  ));
+  assert!(glsl.contains(
+    "totals[i] = dubgsl_rem(totals[i], -3l) + 9223372036854775807l;"
+  ));
+  assert!(glsl.contains("items[i].c = items[i].c % 2u;"));
+  assert!(glsl.contains(
+    "halves[i] = Halves(1.5hf, float16_t(0.0), 0u, 2.0hf, float16_t(0.0), 0u);"
+  ));
+}
+
+#[test]
+fn test_glsl_casts() {
+  // Casts from floats to integers saturate, with NaN becoming zero,
+  // through helpers, as GLSL's conversions are undefined out of range.
+  let glsl = generate_glsl(&check_source(CAST_SHADER), "run").unwrap();
+  assert!(glsl.contains(
+    "int dubgsl_cast_int(float a) {\n  \
+       return isnan(a) ? 0 : a <= -2147483600.0 ? (-2147483647 - 1) : \
+       a >= 2147483600.0 ? 2147483647 : int(a);\n\
+     }"
+  ));
+  assert!(glsl.contains(
+    "uvec2 dubgsl_cast_uvec2(vec2 a) {\n  \
+       return uvec2(dubgsl_cast_uint(a.x), dubgsl_cast_uint(a.y));\n\
+     }"
+  ));
+  assert!(glsl.contains(
+    "  ints[i] = dubgsl_cast_int(uniforms.scale);\n  \
+       pairs[i] = dubgsl_cast_uvec2(vec2(uniforms.scale, 1.5));\n  \
+       wides[i] = dubgsl_cast_int64_t(float(uniforms.dubgsl_half));\n"
+  ));
+}
+
+#[test]
+fn test_glsl_unsupported() {
+  let glsl_err = |contents: &str, entrypoint: &str| {
+    generate_glsl(&check_source(contents), entrypoint)
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    glsl_err("entrypoint(1d) run(i) {}", "walk"),
+    vec!["No entrypoint named `walk`."]
+  );
+  assert_eq!(
+    glsl_err("
+      @packed struct Record { tag: u32, position: vec3xf32 }
+      entrypoint(1d) run(i) {}", "run"),
+    vec![
+      "Field `Record::position` at offset 4 cannot be expressed in GLSL, as \
+       std430 layout places it at offset 16."
+    ]
+  );
+  assert_eq!(
+    glsl_err("
+      @packed struct Tail { position: vec3xf32, a: f32, b: f32 }
+      entrypoint(1d) run(i) {}", "run"),
+    vec![
+      "Struct `Tail` cannot be expressed in GLSL, as std430 layout gives it a \
+       size of 32 bytes rather than 20."
+    ]
+  );
+  assert_eq!(
+    glsl_err("
+      uniforms { scale: f32 }
+      buffer(w) out: f32;
+      entrypoint(1d) run(i) {
+        let u = uniforms;
+        mutate out[i] = u.scale;
+      }", "run"),
+    vec!["The uniforms cannot be used as a whole value in GLSL, only their fields."]
+  );
+}
+
+// Swizzles are assigned directly, and the struct ending in a runtime
+// array is declared only as a buffer block.
+const EXPECTED_GLSL: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`, entrypoint `step`.  Do not edit.
+#version 450
+#extension GL_EXT_shader_explicit_arithmetic_types_float16 : require
+#extension GL_EXT_shader_16bit_storage : require
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct Particle {
+  vec3 position;
+  vec3 velocity;
+  float mass;
+};
+
+struct Tint {
+  vec4 color;
+  float scale;
+};
+
+layout(std140, binding = 0) uniform dubgsl_uniforms_block {
+  vec3 gravity;
+  float dt;
+  uint steps;
+  Tint tint;
+} uniforms;
+
+layout(std430, set = 1, binding = 0) buffer dubgsl_buffer_particles {
+  Particle particles[];
+};
+
+layout(std430, binding = 1) readonly buffer dubgsl_buffer_grid {
+  uint width;
+  uint dubgsl_pad0;
+  uint dubgsl_pad1;
+  uint dubgsl_pad2;
+  ivec2 cells[];
+} grid;
+
+layout(std430, binding = 2) writeonly buffer dubgsl_buffer_sums {
+  float sums[];
+};
+
+layout(std430, binding = 3) readonly buffer dubgsl_buffer_weights {
+  float16_t weights[];
+};
+
+float energy(Particle p);
+uint clamp_index(uint i, uint n);
+
+float energy(Particle p) {
+  vec3 v = p.velocity;
+  return (0.5 * p.mass) * (((v.x * v.x) + (v.y * v.y)) + (v.z * v.z));
+}
+
+uint clamp_index(uint i, uint n) {
+  if (i < n) {
+    return i;
+  } else if (n == 0u) {
+    return 0u;
+  } else {
+    return n - 1u;
+  }
+}
+
+void main() {
+  uint i = gl_GlobalInvocationID.x;
+  Particle p = particles[i];
+  p.velocity = p.velocity + (uniforms.gravity * uniforms.dt);
+  p.position.xz = p.position.zx;
+  p.position.z = -p.mass;
+  particles[clamp_index(i, uint(particles.length()))].velocity.zyx = p.velocity;
+  particles[i] = p;
+  uint j = 0u;
+  float total = 0.0;
+  while (true) {
+    if (!(j < uniforms.steps)) {
+      return;
+    }
+    total = total - (energy(p) * float(weights[j]));
+    j = j + 1u;
+  }
+}
+"#;
@@ -0,0 +1,214 @@
+use crate::{ backend::generate_hlsl, tests::check_source };
+use super::{ BACKEND_SHADER, CAST_SHADER };
+
+#[test]
+fn test_hlsl() {
+  assert_eq!(generate_hlsl(&check_source(BACKEND_SHADER)).unwrap(), EXPECTED_HLSL);
+}
+
+#[test]
+fn test_hlsl_layout() {
+  let hlsl = generate_hlsl(&check_source("
+    struct Frame { basis: mat2x4xf32, @align(16) weights: [f32; 2] }
+    struct Header { count: u32, @align(16) points: [vec4xf32] }
+    uniforms { scale: f16, frame: Frame }
+    buffer(rw) header: Header;
+    buffer(rw) frames: Frame;
+    buffer(w) totals: i64;
+    func weights(f: Frame) -> [f32; 2] {
+      ret f.weights;
+    }
+    entrypoint(3d) run(id) {
+      let f = frames[id.x];
+      mutate frames[id.y] = Frame {
+        weights: weights(f),
+        basis: f.basis * uniforms.frame.weights[0],
+      };
+      mutate header.points[id.z].wzy = (f.basis * vec2xf32(1.0, 2.0)).xyz;
+      mutate header.points[2].x = uniforms.scale as f32;
+      mutate header.count = header.points.length;
+      mutate totals[id.x] = -9223372036854775807 - 1;
+    }
+  ")).unwrap();
+
+  // Matrices have a row for each column, and arrays are named by
+  // typedefs where HLSL needs a type name.
+  assert!(hlsl.contains("#pragma pack_matrix(row_major)\n"));
+  assert!(hlsl.contains(
+    "struct Frame {\n  float2x4 basis;\n  float weights[2];\n  \
+     uint dubgsl_pad0;\n  uint dubgsl_pad1;\n};"
+  ));
+  assert!(hlsl.contains("typedef float dubgsl_array_float_2[2];"));
+  assert!(hlsl.contains("dubgsl_array_float_2 weights(Frame f) {"));
+  assert!(hlsl.contains(
+    "struct Uniforms {\n  float16_t scale;\n  Frame frame;\n  uint dubgsl_pad0;\n};\n\n\
+     cbuffer dubgsl_uniforms_block : register(b0) {\n  Uniforms uniforms;\n};"
+  ));
+
+  // Structs are built by helpers, and a buffer of a struct is accessed
+  // by byte offset.
+  assert!(hlsl.contains("RWByteAddressBuffer header : register(u1);"));
+  assert!(hlsl.contains(
+    "Frame dubgsl_construct_Frame(float2x4 basis, float weights[2]) {\n  \
+     Frame dubgsl_value = (Frame)0;\n  \
+     dubgsl_value.basis = basis;\n  \
+     dubgsl_value.weights = weights;\n  \
+     return dubgsl_value;\n}"
+  ));
+  assert!(hlsl.contains(
+    "uint dubgsl_length_header() {\n  uint size;\n  header.GetDimensions(size);\n  \
+     return (size - 16u) / 16u;\n}"
+  ));
+  assert!(hlsl.ends_with(
+    "[numthreads(4, 4, 4)]\n\
+     void run(uint3 id : SV_DispatchThreadID) {\n  \
+       Frame f = frames[id.x];\n  \
+       frames[id.y] = dubgsl_construct_Frame(f.basis * uniforms.frame.weights[0u], weights(f));\n  \
+       {\n    \
+         uint dubgsl_address = 16u + id.z * 16u;\n    \
+         float3 dubgsl_value = mul(float2(1.0, 2.0), f.basis).xyz;\n    \
+         header.Store<float>(dubgsl_address + 12u, dubgsl_value.x);\n    \
+         header.Store<float>(dubgsl_address + 8u, dubgsl_value.y);\n    \
+         header.Store<float>(dubgsl_address + 4u, dubgsl_value.z);\n  \
+       }\n  \
+       header.Store<float>(48u, float(uniforms.scale));\n  \
+       header.Store<uint>(0u, dubgsl_length_header());\n  \
+       totals[id.x] = (-9223372036854775807l - 1l);\n\
+     }\n"
+  ));
+}
+
+#[test]
+fn test_hlsl_casts() {
+  // Casts from floats to integers saturate, with NaN becoming zero,
+  // through helpers, as HLSL's conversions are undefined out of range.
+  let hlsl = generate_hlsl(&check_source(CAST_SHADER)).unwrap();
+  assert!(hlsl.contains(
+    "uint dubgsl_cast_uint(float a) {\n  \
+       return isnan(a) ? 0u : a <= 0.0 ? 0u : a >= 4294967300.0 ? 4294967295u : uint(a);\n\
+     }\n\
+     \n\
+     uint2 dubgsl_cast_uint2(float2 a) {\n  \
+       return uint2(dubgsl_cast_uint(a.x), dubgsl_cast_uint(a.y));\n\
+     }"
+  ));
+  assert!(hlsl.contains(
+    "  ints[i] = dubgsl_cast_int(uniforms.scale);\n  \
+       pairs[i] = dubgsl_cast_uint2(float2(uniforms.scale, 1.5));\n  \
+       wides[i] = dubgsl_cast_int64_t(float(uniforms.dubgsl_half));\n"
+  ));
+}
+
+#[test]
+fn test_hlsl_unsupported() {
+  let hlsl_err = |contents: &str| {
+    generate_hlsl(&check_source(contents))
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    hlsl_err("buffer(r) points: vec3xf32;"),
+    vec![
+      "Buffer `points` of type `[vec3xf32]` cannot be expressed in HLSL, as \
+       structured buffer packing places its contents differently."
+    ]
+  );
+  assert_eq!(
+    hlsl_err("struct Basis { scale: f32, rotation: mat3x3xf32 }"),
+    vec![
+      "Field `Basis::rotation` of type `mat3x3xf32` cannot be expressed in \
+       HLSL, as structured buffer packing places its contents differently."
+    ]
+  );
+}
+
+// The buffer of a struct ending in a runtime array is accessed by byte
+// offset, and `step` is renamed as HLSL has an intrinsic of that name.
+const EXPECTED_HLSL: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+
+struct Particle {
+  float3 position;
+  uint dubgsl_pad0;
+  float3 velocity;
+  float mass;
+};
+
+struct Tint {
+  float4 color;
+  float scale;
+  uint dubgsl_pad0;
+  uint dubgsl_pad1;
+  uint dubgsl_pad2;
+};
+
+struct Uniforms {
+  float3 gravity;
+  float dt;
+  uint steps;
+  Tint tint;
+};
+
+cbuffer dubgsl_uniforms_block : register(b0) {
+  Uniforms uniforms;
+};
+
+RWStructuredBuffer<Particle> particles : register(u0, space1);
+ByteAddressBuffer grid : register(t1);
+RWStructuredBuffer<float> sums : register(u2);
+StructuredBuffer<float16_t> weights : register(t3);
+
+uint dubgsl_length_particles() {
+  uint count;
+  uint stride;
+  particles.GetDimensions(count, stride);
+  return count;
+}
+
+float energy(Particle p);
+uint clamp_index(uint i, uint n);
+
+float energy(Particle p) {
+  float3 v = p.velocity;
+  return (0.5 * p.mass) * (((v.x * v.x) + (v.y * v.y)) + (v.z * v.z));
+}
+
+uint clamp_index(uint i, uint n) {
+  if (i < n) {
+    return i;
+  } else if (n == 0u) {
+    return 0u;
+  } else {
+    return n - 1u;
+  }
+}
+
+[numthreads(64, 1, 1)]
+void dubgsl_step(uint i : SV_DispatchThreadID) {
+  Particle p = particles[i];
+  p.velocity = p.velocity + (uniforms.gravity * uniforms.dt);
+  p.position.xz = p.position.zx;
+  p.position.z = -p.mass;
+  particles[clamp_index(i, dubgsl_length_particles())].velocity.zyx = p.velocity;
+  particles[i] = p;
+  uint j = 0u;
+  float total = 0.0;
+  while (true) {
+    if (!(j < uniforms.steps)) {
+      return;
+    }
+    total = total - (energy(p) * float(weights[j]));
+    j = j + 1u;
+  }
+}
+
+[numthreads(8, 8, 1)]
+void fill(uint2 id : SV_DispatchThreadID) {
+  int2 cell = grid.Load<int2>(16u + (id.x + (id.y * grid.Load<uint>(0u))) * 8u);
+  int2 mask = ~cell & 3;
+  energy(particles[0u]);
+  sums[id.x] = (float(mask.x + (-2147483647 - 1)) * uniforms.tint.scale) + 0.1;
+}
+"#;
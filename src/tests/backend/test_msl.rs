@@ -0,0 +1,249 @@
+use crate::{ backend::generate_msl, tests::check_source };
+use super::{ BACKEND_SHADER, CAST_SHADER };
+
+#[test]
+fn test_msl() {
+  assert_eq!(generate_msl(&check_source(BACKEND_SHADER)).unwrap(), EXPECTED_MSL);
+}
+
+#[test]
+fn test_msl_layout() {
+  let msl = generate_msl(&check_source("
+    struct Padded { a: f32, @align(16) b: vec2xf32, @size(8) c: u32 }
+    struct Halves { x: f16, @align(8) y: vec3xf16 }
+    @packed struct Record { tag: u32, position: vec3xf32 }
+    struct Log { count: u32, entries: [vec3xf32] }
+    uniforms { scale: f32, @size(12) count: u32, offset: vec3xf32 }
+    buffer(rw) items: Padded;
+    buffer(rw) halves: Halves;
+    buffer(r) records: Record;
+    buffer(w) log: Log;
+    func record(v: vec3xf32) {
+      mutate log.entries[log.entries.length - 1].zy = v.xy * uniforms.scale;
+    }
+    entrypoint(3d) run(id) {
+      let i = id.x;
+      mutate items[i] = Padded { c: 4, a: 1.0, b: vec2xf32(2.0, 3.0) % 2.0 };
+      mutate halves[i] = Halves { x: 1.5, y: vec3xf16(2.0, 0.25, 1.0) };
+      mutate halves[i].y.xz = halves[i].y.zx;
+      exec record(uniforms.offset + records[i].position);
+    }
+  ")).unwrap();
+
+  // Fields placed by attributes are reached with padding members, and
+  // 3-component vectors in structs are packed, which `@packed` structs
+  // need.
+  assert!(msl.contains(
+    "struct Padded {\n  float a;\n  uint dubgsl_pad0;\n  uint dubgsl_pad1;\n  \
+     uint dubgsl_pad2;\n  float2 b;\n  uint c;\n};"
+  ));
+  assert!(msl.contains(
+    "struct Halves {\n  half x;\n  half dubgsl_pad0;\n  uint dubgsl_pad1;\n  \
+     packed_half3 y;\n};"
+  ));
+  assert!(msl.contains("struct Record {\n  uint tag;\n  packed_float3 position;\n};"));
+  assert!(msl.contains("struct Log {\n  uint count;\n  float3 entries[1];\n};"));
+  assert!(msl.contains(
+    "struct Uniforms {\n  float scale;\n  uint count;\n  uint dubgsl_pad0;\n  \
+     uint dubgsl_pad1;\n  packed_float3 offset;\n  uint dubgsl_pad2;\n};"
+  ));
+
+  // Functions take the resources they use, and the lengths of runtime
+  // arrays come from the buffer sizes.
+  assert!(msl.contains(
+    "void record(constant Uniforms& uniforms, device Log& dubgsl_log, \
+     constant dubgsl_buffer_sizes& dubgsl_sizes, float3 v) {\n  \
+     dubgsl_log.entries[((dubgsl_sizes.dubgsl_log - 16u) / 16u) - 1u].zy = \
+     v.xy * uniforms.scale;\n}"
+  ));
+  assert!(msl.contains(
+    "kernel void run(\n  \
+       constant Uniforms& uniforms [[buffer(0)]],\n  \
+       device Padded* items [[buffer(1)]],\n  \
+       device Halves* halves [[buffer(2)]],\n  \
+       constant Record* records [[buffer(3)]],\n  \
+       device Log& dubgsl_log [[buffer(4)]],\n  \
+       constant dubgsl_buffer_sizes& dubgsl_sizes [[buffer(5)]],\n  \
+       uint3 id [[thread_position_in_grid]]\n\
+     ) {"
+  ));
+
+  // Structs are aggregates, and packed vectors are assigned a component
+  // at a time.
+  assert!(msl.contains(
+    "items[i] = Padded{1.0, {}, {}, {}, fmod(float2(2.0, 3.0), float2(2.0)), 4u};"
+  ));
+  assert!(msl.contains(
+    "halves[i] = Halves{1.5h, {}, {}, half3(2.0h, 0.25h, 1.0h)};"
+  ));
+  assert!(msl.contains(
+    "  {\n    half2 dubgsl_value = half3(halves[i].y).zx;\n    \
+     halves[i].y[0] = dubgsl_value.x;\n    \
+     halves[i].y[2] = dubgsl_value.y;\n  }"
+  ));
+  assert!(msl.contains(
+    "record(uniforms, dubgsl_log, dubgsl_sizes, \
+     float3(uniforms.offset) + float3(records[i].position));"
+  ));
+}
+
+#[test]
+fn test_msl_casts() {
+  // Casts from floats to integers saturate, with NaN becoming zero,
+  // through helpers, as MSL's conversions are undefined out of range.
+  let msl = generate_msl(&check_source(CAST_SHADER)).unwrap();
+  assert!(msl.contains(
+    "long dubgsl_cast_long(float a) {\n  \
+       return isnan(a) ? 0l : a <= -9.223372e18 ? (-9223372036854775807l - 1l) : \
+       a >= 9.223372e18 ? 9223372036854775807l : static_cast<long>(a);\n\
+     }"
+  ));
+  assert!(msl.contains(
+    "  ints[i] = dubgsl_cast_int(uniforms.scale);\n  \
+       pairs[i] = dubgsl_cast_uint2(float2(uniforms.scale, 1.5));\n  \
+       wides[i] = dubgsl_cast_long(static_cast<float>(uniforms.dubgsl_half));\n"
+  ));
+}
+
+#[test]
+fn test_msl_unsupported() {
+  let msl_err = |contents: &str| {
+    generate_msl(&check_source(contents))
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    msl_err("
+      @packed struct Pair { tag: u32, value: vec2xf32 }
+      entrypoint(1d) run(i) {}"),
+    vec![
+      "Field `Pair::value` at offset 4 cannot be expressed in MSL, as \
+       Metal layout places it at offset 8."
+    ]
+  );
+  assert_eq!(
+    msl_err("
+      @packed struct Odd { value: vec2xf32, tag: u32 }
+      entrypoint(1d) run(i) {}"),
+    vec![
+      "Struct `Odd` cannot be expressed in MSL, as Metal layout gives it a \
+       size of 16 bytes rather than 12."
+    ]
+  );
+}
+
+// Packed vectors are unpacked to be read, and entrypoints that clash
+// with Metal functions are renamed.
+const EXPECTED_MSL: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Particle {
+  packed_float3 position;
+  uint dubgsl_pad0;
+  packed_float3 velocity;
+  float mass;
+};
+
+struct Grid {
+  uint width;
+  uint dubgsl_pad0;
+  uint dubgsl_pad1;
+  uint dubgsl_pad2;
+  int2 cells[1];
+};
+
+struct Tint {
+  float4 color;
+  float scale;
+};
+
+struct Uniforms {
+  packed_float3 gravity;
+  float dt;
+  uint steps;
+  Tint tint;
+};
+
+struct dubgsl_buffer_sizes {
+  uint particles;
+  uint grid;
+  uint sums;
+  uint weights;
+};
+
+float energy(Particle p);
+uint clamp_index(uint i, uint n);
+
+float energy(Particle p) {
+  float3 v = float3(p.velocity);
+  return (0.5 * p.mass) * (((v.x * v.x) + (v.y * v.y)) + (v.z * v.z));
+}
+
+uint clamp_index(uint i, uint n) {
+  if (i < n) {
+    return i;
+  } else if (n == 0u) {
+    return 0u;
+  } else {
+    return n - 1u;
+  }
+}
+
+// Threadgroup size 64x1x1.
+kernel void dubgsl_step(
+  constant Uniforms& uniforms [[buffer(0)]],
+  constant Grid& grid [[buffer(1)]],
+  device float* sums [[buffer(2)]],
+  constant half* weights [[buffer(3)]],
+  device Particle* particles [[buffer(4)]],
+  constant dubgsl_buffer_sizes& dubgsl_sizes [[buffer(5)]],
+  uint i [[thread_position_in_grid]]
+) {
+  Particle p = particles[i];
+  p.velocity = float3(p.velocity) + (float3(uniforms.gravity) * uniforms.dt);
+  {
+    float2 dubgsl_value = float3(p.position).zx;
+    p.position[0] = dubgsl_value.x;
+    p.position[2] = dubgsl_value.y;
+  }
+  p.position[2] = -p.mass;
+  {
+    device packed_float3& dubgsl_target = particles[clamp_index(i, (dubgsl_sizes.particles / 32u))].velocity;
+    float3 dubgsl_value = float3(p.velocity);
+    dubgsl_target[2] = dubgsl_value.x;
+    dubgsl_target[1] = dubgsl_value.y;
+    dubgsl_target[0] = dubgsl_value.z;
+  }
+  particles[i] = p;
+  uint j = 0u;
+  float total = 0.0;
+  while (true) {
+    if (!(j < uniforms.steps)) {
+      return;
+    }
+    total = total - (energy(p) * static_cast<float>(weights[j]));
+    j = j + 1u;
+  }
+}
+
+// Threadgroup size 8x8x1.
+kernel void fill(
+  constant Uniforms& uniforms [[buffer(0)]],
+  constant Grid& grid [[buffer(1)]],
+  device float* sums [[buffer(2)]],
+  constant half* weights [[buffer(3)]],
+  device Particle* particles [[buffer(4)]],
+  constant dubgsl_buffer_sizes& dubgsl_sizes [[buffer(5)]],
+  uint2 id [[thread_position_in_grid]]
+) {
+  int2 cell = grid.cells[id.x + (id.y * grid.width)];
+  int2 mask = ~cell & 3;
+  energy(particles[0u]);
+  sums[id.x] = (static_cast<float>(mask.x + (-2147483647 - 1)) * uniforms.tint.scale) + 0.1;
+}
+"#;
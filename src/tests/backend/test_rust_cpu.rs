@@ -0,0 +1,217 @@
+use crate::{
+  backend::generate_rust_cpu,
+  data::Value,
+  interpret::Interpreter,
+  model::LiteralModel,
+  tests::check_source,
+};
+use super::rust_cpu_kernel;
+
+#[test]
+fn test_rust_cpu() {
+  let rust = generate_rust_cpu(&check_source("
+    struct Particle { position: vec3xf32, velocity: vec3xf32, mass: f32 }
+    struct Grid { width: u32, cells: [vec2xi32] }
+    uniforms { gravity: vec3xf32, dt: f32, rot: mat2x2xf32 }
+    buffer(rw) particles: Particle;
+    buffer(r) grid: Grid;
+    buffer(w) sums: f32;
+
+    func clamp_index(i: u32, n: u32) -> u32 {
+      if i < n { ret i; } else { if n == 0 { ret 0; } else { ret n - 1; } }
+    }
+
+    func first(i: u32) -> u32 {
+      if i < 3 { ret 1; }
+      ret 2;
+    }
+
+    entrypoint(1d) step(i) {
+      var p = particles[i];
+      mutate p.velocity = p.velocity + uniforms.gravity * uniforms.dt;
+      mutate p.position.xz = p.position.zx;
+      mutate particles[clamp_index(i, particles.length)] = p;
+      mutate particles[i].velocity.yx = p.velocity.xy;
+    }
+
+    entrypoint(2d) fill(id) {
+      let cell = grid.cells[id.x + id.y * grid.width];
+      let w = vec2xf32(1.0, 2.0) * uniforms.rot;
+      mutate sums[id.x] = (cell.x >> 1) as f32 + w.y;
+    }
+  ")).unwrap();
+
+  // Runtime-sized arrays are bound as slices beside the fields before
+  // them, and written buffers are shared between invocations.
+  assert!(rust.contains(
+    "pub struct Grid {\n  pub width: u32,\n}"
+  ));
+  assert!(rust.contains(
+    "pub struct Uniforms {\n  pub gravity: [f32; 3],\n  pub dt: f32,\n  \
+     pub rot: [[f32; 2]; 2],\n}"
+  ));
+  assert!(rust.contains(
+    "pub struct Resources<'a> {\n  \
+       pub uniforms: &'a Uniforms,\n  \
+       pub particles: dubgsl_rt::Shared<'a, [Particle]>,\n  \
+       pub grid: &'a Grid,\n  \
+       pub grid_cells: &'a [[i32; 2]],\n  \
+       pub sums: dubgsl_rt::Shared<'a, [f32]>,\n\
+     }"
+  ));
+
+  // Dispatch runs each entrypoint over its workgroup size, and is
+  // unsafe, as invocations must not race.
+  assert!(rust.contains(
+    "pub unsafe fn dispatch(&self, entrypoint: Entrypoint, workgroups: [u32; 3]) {\n    \
+       let resources = dubgsl_rt::AssertSync(self);\n"
+  ));
+  assert!(rust.contains(
+    "Entrypoint::Step => dubgsl_rt::dispatch([64, 1, 1], workgroups, \
+     |[x, _, _]| resources.get().step(x)),"
+  ));
+  assert!(rust.contains(
+    "Entrypoint::Fill => dubgsl_rt::dispatch([8, 8, 1], workgroups, \
+     |[x, y, _]| resources.get().fill([x, y])),"
+  ));
+
+  // Integer arithmetic wraps, vectors are arrays, and swizzled writes
+  // are made a component at a time.  Shared elements are reached
+  // through bounds-checked pointers.
+  assert!(rust.contains(
+    "let mut p = unsafe { (*self.particles.elem(i as usize)) };"
+  ));
+  assert!(rust.contains(
+    "p.velocity = dubgsl_rt::zip(p.velocity, dubgsl_rt::zip(\
+     self.uniforms.gravity, [self.uniforms.dt; 3], |a, b| a * b), |a, b| a + b);"
+  ));
+  assert!(rust.contains(
+    "    {\n      \
+           let dubgsl_value = dubgsl_rt::swizzle(p.position, [2, 0]);\n      \
+           let dubgsl_target = &mut p.position;\n      \
+           dubgsl_target[0] = dubgsl_value[0];\n      \
+           dubgsl_target[2] = dubgsl_value[1];\n    \
+         }"
+  ));
+  assert!(rust.contains(
+    "unsafe { (*self.particles.elem(self.clamp_index(i, \
+     self.particles.len() as u32) as usize)) = p; }"
+  ));
+  assert!(rust.contains(
+    "      let dubgsl_target = \
+             std::ptr::addr_of_mut!((*self.particles.elem(i as usize)).velocity);\n      \
+           unsafe { (*dubgsl_target)[1] = dubgsl_value[0]; }\n      \
+           unsafe { (*dubgsl_target)[0] = dubgsl_value[1]; }\n"
+  ));
+  assert!(rust.contains(
+    "let cell = self.grid_cells[(id[0].wrapping_add(\
+     id[1].wrapping_mul(self.grid.width))) as usize];"
+  ));
+  assert!(rust.contains(
+    "let w = dubgsl_rt::vec_mat([1.0_f32, 2.0_f32], self.uniforms.rot);"
+  ));
+  assert!(rust.contains(
+    "(cell[0].checked_shr(1_u32).expect(\"Shift out of range\")) as f32"
+  ));
+
+  assert!(rust.contains(
+    "    } else if n == 0_u32 {\n      return 0_u32;\n    } else {\n      \
+     return n.wrapping_sub(1_u32);\n    }"
+  ));
+  assert!(rust.contains(
+    "      return 1_u32;\n    }\n    return 2_u32;\n  }"
+  ));
+}
+
+#[test]
+fn test_rust_cpu_unsupported() {
+  let rust_err = |contents: &str| {
+    generate_rust_cpu(&check_source(contents))
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    rust_err("
+      buffer(r) weights: f16;
+      buffer(w) sums: f32;
+      entrypoint(1d) run(i) {
+        mutate sums[i] = weights[i] as f32 + weights[i + 1] as f32;
+      }"),
+    vec!["Type `f16` is not supported by the Rust backend."]
+  );
+}
+
+/** The shader `rust_cpu_kernel.rs` is generated from. */
+const KERNEL_SHADER: &str = "
+  uniforms { dt: f32, gravity: vec2xf32, scale: u32 }
+  buffer(r) velocities: vec2xf32;
+  buffer(rw) positions: vec2xf32;
+  buffer(w) counts: u32;
+
+  entrypoint(1d) step(i) {
+    if i < positions.length {
+      let v = velocities[i] + uniforms.gravity * uniforms.dt;
+      mutate positions[i] = positions[i] + v * uniforms.dt;
+      mutate positions[i].yx = positions[i];
+      mutate counts[i] = i * uniforms.scale + (v.x * 100.0) as u32;
+    }
+  }
+";
+
+#[test]
+fn test_rust_cpu_kernel() {
+  // The checked-in kernel is compiled with the tests, so it must be
+  // regenerated when the backend's output changes.
+  assert_eq!(
+    generate_rust_cpu(&check_source(KERNEL_SHADER)).unwrap(),
+    include_str!("rust_cpu_kernel.rs"),
+    "`rust_cpu_kernel.rs` is out of date",
+  );
+
+  // A dispatch of the compiled kernel writes what the interpreter does.
+  const COUNT: usize = 100;
+  let velocities = (0..COUNT)
+    .map(|i| [i as f32 * 0.25 - 10.0, 3.0 - i as f32 * 0.125])
+    .collect::<Vec<_>>();
+  let mut positions = (0..COUNT)
+    .map(|i| [i as f32, -(i as f32) * 0.5])
+    .collect::<Vec<_>>();
+  let mut counts = vec![0_u32; COUNT];
+
+  let model = check_source(KERNEL_SHADER);
+  let mut interpreter = Interpreter::new(&model);
+  interpreter.bind_uniforms(&Value::Struct(vec![
+    ("dt".to_string(), Value::Scalar(LiteralModel::new_f32(0.5))),
+    ("gravity".to_string(), Value::Vector(vec![
+      LiteralModel::new_f32(0.0),
+      LiteralModel::new_f32(-9.75),
+    ])),
+    ("scale".to_string(), Value::Scalar(LiteralModel::U32(3))),
+  ])).unwrap();
+  let to_bytes = |values: &[[f32; 2]]| {
+    values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>()
+  };
+  interpreter.bind_buffer("velocities", to_bytes(&velocities)).unwrap();
+  interpreter.bind_buffer("positions", to_bytes(&positions)).unwrap();
+  interpreter.bind_buffer("counts", vec![0; COUNT * 4]).unwrap();
+  interpreter.dispatch("step", [2, 1, 1]).unwrap();
+
+  let uniforms = rust_cpu_kernel::Uniforms { dt: 0.5, gravity: [0.0, -9.75], scale: 3 };
+  let resources = rust_cpu_kernel::Resources {
+    uniforms: &uniforms,
+    velocities: &velocities,
+    positions: rust_cpu_kernel::dubgsl_rt::Shared::new(&mut positions[..]),
+    counts: rust_cpu_kernel::dubgsl_rt::Shared::new(&mut counts[..]),
+  };
+  // Safety: each invocation only touches the elements at its own id.
+  unsafe { resources.dispatch(rust_cpu_kernel::Entrypoint::Step, [2, 1, 1]) };
+
+  assert_eq!(interpreter.buffer("positions").unwrap(), to_bytes(&positions));
+  assert_eq!(
+    interpreter.buffer("counts").unwrap(),
+    counts.iter().flat_map(|c| c.to_le_bytes()).collect::<Vec<_>>(),
+  );
+}
@@ -0,0 +1,454 @@
+use crate::{
+  backend::{ DisassembleError, disassemble_spirv, generate_spirv },
+  tests::check_source,
+};
+use super::{ BACKEND_SHADER, CAST_SHADER };
+
+#[test]
+fn test_spirv() {
+  let words = generate_spirv(&check_source(BACKEND_SHADER));
+  assert_eq!(&words[..5], &[0x0723_0203, 0x0001_0300, 0, 178, 0]);
+  assert_eq!(disassemble_spirv(&words).unwrap(), EXPECTED_SPIRV);
+}
+
+#[test]
+fn test_spirv_layout() {
+  let spirv = disassemble_spirv(&generate_spirv(&check_source("
+    struct Frame { @align(16) weights: [f32; 2], basis: mat2x4xf32 }
+    uniforms { frame: Frame, scale: f16 }
+    buffer(rw) totals: i64;
+    buffer(rw) frames: Frame;
+    func check(x: u32) -> bool {
+      mutate totals[x] = totals[x] + 1;
+      ret x > 2;
+    }
+    entrypoint(1d) run(i) {
+      let frame = uniforms.frame;
+      let w = frame.weights[i % 2];
+      mutate frames[i] = frame;
+      mutate frames[i].basis = frame.basis + frame.basis * 2.0;
+      let v = frame.basis * vec2xf32(w, 1.0);
+      if (i == 0 && check(i)) || true == (i < 4) {
+        mutate totals[i] = (v.x as i64 << 2) + (uniforms.scale as f32 as u32) as i64;
+      }
+    }
+  "))).unwrap();
+  let lines = spirv.lines().map(str::trim).collect::<Vec<_>>();
+  let has = |line: &str| lines.contains(&line);
+
+  assert!(has("OpCapability Float16"));
+  assert!(has("OpCapability Int64"));
+  assert!(has("OpCapability UniformAndStorageBuffer16BitAccess"));
+
+  // The uniforms keep std140 strides and offsets, so their structs and
+  // arrays are distinct from those in buffers.
+  assert!(has("OpDecorate %4 ArrayStride 16"));
+  assert!(has("OpMemberDecorate %7 1 Offset 32"));
+  assert!(has("OpMemberDecorate %7 1 MatrixStride 16"));
+  assert!(has("OpMemberDecorate %9 1 Offset 64"));
+  assert!(has("OpDecorate %17 ArrayStride 4"));
+  assert!(has("OpMemberDecorate %18 1 Offset 16"));
+  assert!(has("OpDecorate %19 ArrayStride 48"));
+
+  // Loading a uniform struct converts it to the storage layout, and
+  // values are indexed through a variable.
+  assert!(spirv.contains(
+    "%47 = OpLoad %7 %46\n \
+     %48 = OpCompositeExtract %4 %47 0\n \
+     %49 = OpCompositeExtract %1 %48 0\n \
+     %50 = OpCompositeExtract %1 %48 1\n \
+     %51 = OpCompositeConstruct %17 %49 %50\n \
+     %52 = OpCompositeExtract %6 %47 1\n \
+     %53 = OpCompositeConstruct %18 %51 %52\n"
+  ));
+  assert!(has("%57 = OpVariable %56 Function"));
+  assert!(has("%59 = OpAccessChain %58 %57 %55"));
+
+  // Matrices are added a column at a time.
+  assert!(has("%69 = OpMatrixTimesScalar %6 %67 %68"));
+  assert!(has("%72 = OpFAdd %5 %70 %71"));
+  assert!(has("%76 = OpCompositeConstruct %6 %72 %75"));
+  assert!(has("%81 = OpMatrixTimesVector %5 %77 %80"));
+
+  // The right operand of a logical operation is skipped when it calls
+  // a function.
+  assert!(spirv.contains(
+    "OpSelectionMerge %84 None\n       \
+     OpBranchConditional %82 %83 %84\n \
+     %83 = OpLabel\n \
+     %85 = OpFunctionCall %24 %23 %43\n       \
+     OpBranch %84\n \
+     %84 = OpLabel\n \
+     %86 = OpPhi %24 %82 %44 %85 %83\n"
+  ));
+  assert!(has("%90 = OpLogicalEqual %24 %87 %89"));
+  assert!(has("%91 = OpLogicalOr %24 %86 %90"));
+
+  // Casts convert by the signedness of their source.
+  assert!(has("%96 = OpConvertFToS %12 %95"));
+  assert!(has("%112 = OpFConvert %1 %111"));
+  assert!(has("%123 = OpUConvert %12 %122"));
+}
+
+#[test]
+fn test_spirv_casts() {
+  let spirv = disassemble_spirv(&generate_spirv(&check_source(CAST_SHADER))).unwrap();
+  let lines = spirv.lines().map(str::trim).collect::<Vec<_>>();
+  let has = |line: &str| lines.contains(&line);
+
+  // Casts from floats to integers select the bounds of the integer out
+  // of its range and zero for NaN, as conversions are undefined there.
+  assert!(has("%38 = OpConstant %1 -2147483600.0"));
+  assert!(has("%39 = OpConstant %1 2147483600.0"));
+  assert!(spirv.contains(
+    "%37 = OpConvertFToS %6 %36\n\
+     %44 = OpFOrdLessThanEqual %43 %36 %38\n\
+     %45 = OpFOrdGreaterThanEqual %43 %36 %39\n\
+     %46 = OpIsNan %43 %36\n\
+     %47 = OpSelect %6 %44 %41 %37\n\
+     %48 = OpSelect %6 %45 %42 %47\n\
+     %49 = OpSelect %6 %46 %40 %48\n"
+  ));
+
+  // Vectors are cast a component at a time, and `f16` through `f32`.
+  assert!(has("%57 = OpCompositeExtract %1 %56 0"));
+  assert!(has("%68 = OpCompositeExtract %1 %56 1"));
+  assert!(has("%76 = OpCompositeConstruct %12 %67 %75"));
+  assert!(has("%83 = OpFConvert %1 %82"));
+  assert!(has("%84 = OpConvertFToS %17 %83"));
+}
+
+#[test]
+fn test_spirv_disassemble() {
+  let words = generate_spirv(&check_source("
+    buffer(w) out: i64;
+    buffer(w) halves: f16;
+    entrypoint(1d) run(i) {
+      mutate out[i] = -9223372036854775807 - 1;
+      mutate halves[i] = 0.333;
+    }
+  "));
+  let spirv = disassemble_spirv(&words).unwrap();
+  assert!(spirv.contains("OpEntryPoint GLCompute %13 \"run\" %17"));
+  assert!(spirv.contains("= OpConstant %1 -9223372036854775808\n"));
+  assert!(spirv.contains("= OpConstant %6 0.3330078\n"));
+
+  assert_eq!(
+    disassemble_spirv(&[0x0302_2307, 0x0001_0300, 0, 1, 0]),
+    Err(DisassembleError::BadMagic(0x0302_2307))
+  );
+  let mut truncated = words[..5].to_vec();
+  truncated.extend([3 << 16 | 5, 13]);
+  assert_eq!(disassemble_spirv(&truncated), Err(DisassembleError::Truncated { offset: 5 }));
+  let mut unknown = words[..5].to_vec();
+  unknown.push(1 << 16 | 400);
+  assert_eq!(
+    disassemble_spirv(&unknown).unwrap_err().to_string(),
+    "Cannot disassemble opcode 400 at word 5."
+  );
+}
+
+// Buffers of arrays are wrapped in blocks, and locals that are mutated
+// are variables in the first block of their function.
+const EXPECTED_SPIRV: &str = r#"; SPIR-V
+; Version: 1.3
+; Generator: 0
+; Bound: 178
+; Schema: 0
+       OpCapability Shader
+       OpCapability Float16
+       OpCapability StorageBuffer16BitAccess
+       OpMemoryModel Logical GLSL450
+       OpEntryPoint GLCompute %68 "step" %71
+       OpEntryPoint GLCompute %143 "fill" %71
+       OpExecutionMode %68 LocalSize 64 1 1
+       OpExecutionMode %143 LocalSize 8 8 1
+       OpName %5 "Tint"
+       OpMemberName %5 0 "color"
+       OpMemberName %5 1 "scale"
+       OpName %6 "Uniforms"
+       OpMemberName %6 0 "gravity"
+       OpMemberName %6 1 "dt"
+       OpMemberName %6 2 "steps"
+       OpMemberName %6 3 "tint"
+       OpName %8 "uniforms"
+       OpName %9 "Particle"
+       OpMemberName %9 0 "position"
+       OpMemberName %9 1 "velocity"
+       OpMemberName %9 2 "mass"
+       OpName %11 "dubgsl_buffer_particles"
+       OpMemberName %11 0 "particles"
+       OpName %13 "particles"
+       OpName %17 "Grid"
+       OpMemberName %17 0 "width"
+       OpMemberName %17 1 "cells"
+       OpName %19 "grid"
+       OpName %21 "dubgsl_buffer_sums"
+       OpMemberName %21 0 "sums"
+       OpName %23 "sums"
+       OpName %26 "dubgsl_buffer_weights"
+       OpMemberName %26 0 "weights"
+       OpName %28 "weights"
+       OpName %29 "energy"
+       OpName %32 "p"
+       OpName %30 "clamp_index"
+       OpName %51 "i"
+       OpName %52 "n"
+       OpName %68 "step"
+       OpName %71 "gl_GlobalInvocationID"
+       OpName %73 "i"
+       OpName %79 "p"
+       OpName %116 "j"
+       OpName %118 "total"
+       OpName %143 "fill"
+       OpName %146 "id"
+       OpMemberDecorate %5 0 Offset 0
+       OpMemberDecorate %5 1 Offset 16
+       OpMemberDecorate %6 0 Offset 0
+       OpMemberDecorate %6 1 Offset 12
+       OpMemberDecorate %6 2 Offset 16
+       OpMemberDecorate %6 3 Offset 32
+       OpDecorate %6 Block
+       OpDecorate %8 DescriptorSet 0
+       OpDecorate %8 Binding 0
+       OpMemberDecorate %9 0 Offset 0
+       OpMemberDecorate %9 1 Offset 16
+       OpMemberDecorate %9 2 Offset 28
+       OpDecorate %10 ArrayStride 32
+       OpMemberDecorate %11 0 Offset 0
+       OpDecorate %11 Block
+       OpDecorate %13 DescriptorSet 1
+       OpDecorate %13 Binding 0
+       OpDecorate %16 ArrayStride 8
+       OpMemberDecorate %17 0 Offset 0
+       OpMemberDecorate %17 1 Offset 16
+       OpDecorate %17 Block
+       OpDecorate %19 DescriptorSet 0
+       OpDecorate %19 Binding 1
+       OpDecorate %19 NonWritable
+       OpDecorate %20 ArrayStride 4
+       OpMemberDecorate %21 0 Offset 0
+       OpDecorate %21 Block
+       OpDecorate %23 DescriptorSet 0
+       OpDecorate %23 Binding 2
+       OpDecorate %23 NonReadable
+       OpDecorate %25 ArrayStride 2
+       OpMemberDecorate %26 0 Offset 0
+       OpDecorate %26 Block
+       OpDecorate %28 DescriptorSet 0
+       OpDecorate %28 Binding 3
+       OpDecorate %28 NonWritable
+       OpDecorate %71 BuiltIn GlobalInvocationId
+  %1 = OpTypeFloat 32
+  %2 = OpTypeVector %1 3
+  %3 = OpTypeInt 32 0
+  %4 = OpTypeVector %1 4
+  %5 = OpTypeStruct %4 %1
+  %6 = OpTypeStruct %2 %1 %3 %5
+  %7 = OpTypePointer Uniform %6
+  %8 = OpVariable %7 Uniform
+  %9 = OpTypeStruct %2 %2 %1
+ %10 = OpTypeRuntimeArray %9
+ %11 = OpTypeStruct %10
+ %12 = OpTypePointer StorageBuffer %11
+ %13 = OpVariable %12 StorageBuffer
+ %14 = OpTypeInt 32 1
+ %15 = OpTypeVector %14 2
+ %16 = OpTypeRuntimeArray %15
+ %17 = OpTypeStruct %3 %16
+ %18 = OpTypePointer StorageBuffer %17
+ %19 = OpVariable %18 StorageBuffer
+ %20 = OpTypeRuntimeArray %1
+ %21 = OpTypeStruct %20
+ %22 = OpTypePointer StorageBuffer %21
+ %23 = OpVariable %22 StorageBuffer
+ %24 = OpTypeFloat 16
+ %25 = OpTypeRuntimeArray %24
+ %26 = OpTypeStruct %25
+ %27 = OpTypePointer StorageBuffer %26
+ %28 = OpVariable %27 StorageBuffer
+ %31 = OpTypeFunction %1 %9
+ %35 = OpConstant %1 0.5
+ %50 = OpTypeFunction %3 %3 %3
+ %54 = OpTypeBool
+ %59 = OpConstant %3 0
+ %64 = OpConstant %3 1
+ %66 = OpTypeVoid
+ %67 = OpTypeFunction %66
+ %69 = OpTypeVector %3 3
+ %70 = OpTypePointer Input %69
+ %71 = OpVariable %70 Input
+ %75 = OpTypePointer StorageBuffer %9
+ %78 = OpTypePointer Function %9
+ %80 = OpTypePointer Function %2
+ %84 = OpTypePointer Uniform %2
+ %87 = OpTypePointer Uniform %1
+ %95 = OpTypeVector %1 2
+ %99 = OpConstant %3 2
+%100 = OpTypePointer Function %1
+%107 = OpTypePointer StorageBuffer %2
+%115 = OpTypePointer Function %3
+%117 = OpConstant %1 0.0
+%124 = OpTypePointer Uniform %3
+%135 = OpTypePointer StorageBuffer %24
+%145 = OpTypeVector %3 2
+%150 = OpTypePointer StorageBuffer %3
+%155 = OpTypePointer StorageBuffer %15
+%159 = OpConstant %14 3
+%166 = OpTypePointer StorageBuffer %1
+%169 = OpConstant %14 -2147483648
+%172 = OpConstant %3 3
+%176 = OpConstant %1 0.1
+ %29 = OpFunction %1 None %31
+ %32 = OpFunctionParameter %9
+ %33 = OpLabel
+ %34 = OpCompositeExtract %2 %32 1
+ %36 = OpCompositeExtract %1 %32 2
+ %37 = OpFMul %1 %35 %36
+ %38 = OpCompositeExtract %1 %34 0
+ %39 = OpCompositeExtract %1 %34 0
+ %40 = OpFMul %1 %38 %39
+ %41 = OpCompositeExtract %1 %34 1
+ %42 = OpCompositeExtract %1 %34 1
+ %43 = OpFMul %1 %41 %42
+ %44 = OpFAdd %1 %40 %43
+ %45 = OpCompositeExtract %1 %34 2
+ %46 = OpCompositeExtract %1 %34 2
+ %47 = OpFMul %1 %45 %46
+ %48 = OpFAdd %1 %44 %47
+ %49 = OpFMul %1 %37 %48
+       OpReturnValue %49
+       OpFunctionEnd
+ %30 = OpFunction %3 None %50
+ %51 = OpFunctionParameter %3
+ %52 = OpFunctionParameter %3
+ %53 = OpLabel
+ %55 = OpULessThan %54 %51 %52
+       OpSelectionMerge %57 None
+       OpBranchConditional %55 %56 %58
+ %56 = OpLabel
+       OpReturnValue %51
+ %58 = OpLabel
+ %60 = OpIEqual %54 %52 %59
+       OpSelectionMerge %62 None
+       OpBranchConditional %60 %61 %63
+ %61 = OpLabel
+       OpReturnValue %59
+ %63 = OpLabel
+ %65 = OpISub %3 %52 %64
+       OpReturnValue %65
+ %62 = OpLabel
+       OpUnreachable
+ %57 = OpLabel
+       OpUnreachable
+       OpFunctionEnd
+ %68 = OpFunction %66 None %67
+ %74 = OpLabel
+ %79 = OpVariable %78 Function
+%116 = OpVariable %115 Function
+%118 = OpVariable %100 Function
+ %72 = OpLoad %69 %71
+ %73 = OpCompositeExtract %3 %72 0
+ %76 = OpAccessChain %75 %13 %59 %73
+ %77 = OpLoad %9 %76
+       OpStore %79 %77
+ %81 = OpAccessChain %80 %79 %64
+ %82 = OpAccessChain %80 %79 %64
+ %83 = OpLoad %2 %82
+ %85 = OpAccessChain %84 %8 %59
+ %86 = OpLoad %2 %85
+ %88 = OpAccessChain %87 %8 %64
+ %89 = OpLoad %1 %88
+ %90 = OpVectorTimesScalar %2 %86 %89
+ %91 = OpFAdd %2 %83 %90
+       OpStore %81 %91
+ %92 = OpAccessChain %80 %79 %59
+ %93 = OpAccessChain %80 %79 %59
+ %94 = OpLoad %2 %93
+ %96 = OpVectorShuffle %95 %94 %94 2 0
+ %97 = OpLoad %2 %92
+ %98 = OpVectorShuffle %2 %97 %96 3 1 4
+       OpStore %92 %98
+%101 = OpAccessChain %100 %79 %59 %99
+%102 = OpAccessChain %100 %79 %99
+%103 = OpLoad %1 %102
+%104 = OpFNegate %1 %103
+       OpStore %101 %104
+%105 = OpArrayLength %3 %13 0
+%106 = OpFunctionCall %3 %30 %73 %105
+%108 = OpAccessChain %107 %13 %59 %106 %64
+%109 = OpAccessChain %80 %79 %64
+%110 = OpLoad %2 %109
+%111 = OpLoad %2 %108
+%112 = OpVectorShuffle %2 %111 %110 5 4 3
+       OpStore %108 %112
+%113 = OpAccessChain %75 %13 %59 %73
+%114 = OpLoad %9 %79
+       OpStore %113 %114
+       OpStore %116 %59
+       OpStore %118 %117
+       OpBranch %119
+%119 = OpLabel
+       OpLoopMerge %122 %121 None
+       OpBranch %120
+%120 = OpLabel
+%123 = OpLoad %3 %116
+%125 = OpAccessChain %124 %8 %99
+%126 = OpLoad %3 %125
+%127 = OpULessThan %54 %123 %126
+%128 = OpLogicalNot %54 %127
+       OpSelectionMerge %130 None
+       OpBranchConditional %128 %129 %130
+%129 = OpLabel
+       OpReturn
+%130 = OpLabel
+%131 = OpLoad %1 %118
+%132 = OpLoad %9 %79
+%133 = OpFunctionCall %1 %29 %132
+%134 = OpLoad %3 %116
+%136 = OpAccessChain %135 %28 %59 %134
+%137 = OpLoad %24 %136
+%138 = OpFConvert %1 %137
+%139 = OpFMul %1 %133 %138
+%140 = OpFSub %1 %131 %139
+       OpStore %118 %140
+%141 = OpLoad %3 %116
+%142 = OpIAdd %3 %141 %64
+       OpStore %116 %142
+       OpBranch %121
+%121 = OpLabel
+       OpBranch %119
+%122 = OpLabel
+       OpUnreachable
+       OpFunctionEnd
+%143 = OpFunction %66 None %67
+%147 = OpLabel
+%144 = OpLoad %69 %71
+%146 = OpVectorShuffle %145 %144 %144 0 1
+%148 = OpCompositeExtract %3 %146 0
+%149 = OpCompositeExtract %3 %146 1
+%151 = OpAccessChain %150 %19 %59
+%152 = OpLoad %3 %151
+%153 = OpIMul %3 %149 %152
+%154 = OpIAdd %3 %148 %153
+%156 = OpAccessChain %155 %19 %64 %154
+%157 = OpLoad %15 %156
+%158 = OpNot %15 %157
+%160 = OpCompositeConstruct %15 %159 %159
+%161 = OpBitwiseAnd %15 %158 %160
+%162 = OpAccessChain %75 %13 %59 %59
+%163 = OpLoad %9 %162
+%164 = OpFunctionCall %1 %29 %163
+%165 = OpCompositeExtract %3 %146 0
+%167 = OpAccessChain %166 %23 %59 %165
+%168 = OpCompositeExtract %14 %161 0
+%170 = OpIAdd %14 %168 %169
+%171 = OpConvertSToF %1 %170
+%173 = OpAccessChain %87 %8 %172 %64
+%174 = OpLoad %1 %173
+%175 = OpFMul %1 %171 %174
+%177 = OpFAdd %1 %175 %176
+       OpStore %167 %177
+       OpReturn
+       OpFunctionEnd
+"#;
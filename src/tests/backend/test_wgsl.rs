@@ -0,0 +1,146 @@
+use crate::{ backend::generate_wgsl, tests::check_source };
+use super::BACKEND_SHADER;
+
+#[test]
+fn test_wgsl() {
+  assert_eq!(generate_wgsl(&check_source(BACKEND_SHADER)).unwrap(), EXPECTED_WGSL);
+}
+
+#[test]
+fn test_wgsl_unsupported() {
+  let wgsl_err = |contents: &str| {
+    generate_wgsl(&check_source(contents))
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  assert_eq!(
+    wgsl_err("
+      buffer(rw) totals: u64;
+      entrypoint(1d) sum(i) {
+        mutate totals[i] = totals[i] + 1;
+      }"),
+    vec!["Type `u64` is not supported by WGSL."]
+  );
+  assert_eq!(
+    wgsl_err("@packed struct Record { tag: u32, position: vec3xf32 }"),
+    vec![
+      "Packed struct `Record` cannot be expressed in WGSL, as its fields are \
+       not naturally aligned."
+    ]
+  );
+  assert_eq!(
+    wgsl_err("uniforms { weights: [f32; 4], rotation: mat2x2xf32 }"),
+    vec![
+      "Uniforms field `weights` of type `[f32; 4]` cannot be expressed in \
+       WGSL, as its uniform layout differs from its storage layout.",
+      "Uniforms field `rotation` of type `mat2x2xf32` cannot be expressed in \
+       WGSL, as its uniform layout differs from its storage layout.",
+    ]
+  );
+
+  // Naturally aligned packed structs, and uniforms that only need
+  // their own fields aligned, are fine.
+  let wgsl = generate_wgsl(&check_source("
+    @packed struct Pair { a: u32, b: u32 }
+    struct Inner { value: f32 }
+    uniforms { scale: f32, inner: Inner, @size(8) count: u32, last: f32 }
+  ")).unwrap();
+  assert!(wgsl.contains("struct Pair {\n  a: u32,\n  b: u32,\n}"));
+  assert!(wgsl.contains(
+    "struct Uniforms {\n  scale: f32,\n  @align(16) @size(16) inner: Inner,\n  \
+     @size(8) count: u32,\n  last: f32,\n}"
+  ));
+}
+
+// Swizzles of several components are assigned one component at a time,
+// through a pointer when the target may have side effects.
+const EXPECTED_WGSL: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+
+enable f16;
+
+struct Particle {
+  position: vec3<f32>,
+  velocity: vec3<f32>,
+  mass: f32,
+}
+
+struct Grid {
+  width: u32,
+  @align(16) cells: array<vec2<i32>>,
+}
+
+struct Tint {
+  color: vec4<f32>,
+  scale: f32,
+}
+
+struct Uniforms {
+  gravity: vec3<f32>,
+  dt: f32,
+  steps: u32,
+  tint: Tint,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(1) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<storage, read> grid: Grid;
+@group(0) @binding(2) var<storage, read_write> sums: array<f32>;
+@group(0) @binding(3) var<storage, read> weights: array<f16>;
+
+fn energy(p: Particle) -> f32 {
+  let v = p.velocity;
+  return (0.5f * p.mass) * (((v.x * v.x) + (v.y * v.y)) + (v.z * v.z));
+}
+
+fn clamp_index(i: u32, n: u32) -> u32 {
+  if i < n {
+    return i;
+  } else if n == 0u {
+    return 0u;
+  } else {
+    return n - 1u;
+  }
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn step(@builtin(global_invocation_id) dubgsl_global_id: vec3<u32>) {
+  let i = dubgsl_global_id.x;
+  var p = particles[i];
+  p.velocity = p.velocity + (uniforms.gravity * uniforms.dt);
+  {
+    let dubgsl_value = p.position.zx;
+    p.position.x = dubgsl_value.x;
+    p.position.z = dubgsl_value.y;
+  }
+  p.position.z = -p.mass;
+  {
+    let dubgsl_target = &particles[clamp_index(i, arrayLength(&particles))].velocity;
+    let dubgsl_value = p.velocity;
+    (*dubgsl_target).z = dubgsl_value.x;
+    (*dubgsl_target).y = dubgsl_value.y;
+    (*dubgsl_target).x = dubgsl_value.z;
+  }
+  particles[i] = p;
+  var j = 0u;
+  var total = 0.0f;
+  loop {
+    if !(j < uniforms.steps) {
+      return;
+    }
+    total = total - (energy(p) * f32(weights[j]));
+    j = j + 1u;
+  }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn fill(@builtin(global_invocation_id) dubgsl_global_id: vec3<u32>) {
+  let id = dubgsl_global_id.xy;
+  let cell = grid.cells[id.x + (id.y * grid.width)];
+  let mask = ~cell & vec2<i32>(3i);
+  energy(particles[0u]);
+  sums[id.x] = (f32(mask.x + i32(-2147483648)) * uniforms.tint.scale) + 0.1f;
+}
+"#;
@@ -0,0 +1,143 @@
+use crate::{
+  data::{ CodecErrorKind, Value, check_size, decode, encode },
+  model::{
+    LayoutError,
+    LayoutRules,
+    LiteralModel,
+    TypeLayout,
+    TypeModel,
+  },
+  tests::{ check_source, f32s, fields },
+};
+
+const CODEC_SHADER: &str = "
+  struct Particle { position: vec3xf32, mass: f32 }
+  struct Frame { transform: mat2x2xf32, weights: [f16; 3], id: u64 }
+  struct Grid { width: u32, cells: [vec2xi32] }
+";
+
+fn particle(position: [f32; 3], mass: f32) -> Value {
+  fields(vec![
+    ("position", f32s(&position)),
+    ("mass", Value::Scalar(LiteralModel::new_f32(mass))),
+  ])
+}
+
+#[test]
+fn test_codec_round_trip() {
+  let model = check_source(CODEC_SHADER);
+  let particle_ty = &model.structs()[0];
+  let frame_ty = &model.structs()[1];
+  let grid_ty = &model.structs()[2];
+
+  let value = particle([1.0, 2.0, 3.0], 4.0);
+  let bytes = encode(particle_ty, LayoutRules::Storage, &value).unwrap();
+  assert_eq!(bytes.len(), 16);
+  assert_eq!(&bytes[12 .. 16], &4.0f32.to_le_bytes());
+  assert_eq!(decode(particle_ty, LayoutRules::Storage, &bytes), Ok(value));
+
+  let value = fields(vec![
+    ("id", Value::Scalar(LiteralModel::U64(u64::MAX))),
+    ("transform", Value::Array(vec![f32s(&[1.0, 0.0]), f32s(&[0.0, 1.0])])),
+    ("weights", Value::Array(
+      [0.5, -2.0, 65504.0].iter()
+        .map(|&v| Value::Scalar(LiteralModel::new_f16(v)))
+        .collect()
+    )),
+  ]);
+  let bytes = encode(frame_ty, LayoutRules::Uniform, &value).unwrap();
+  // Under uniform rules the matrix columns and each `f16` of the array
+  // take 16 bytes.
+  assert_eq!(bytes.len(), 96);
+  assert_eq!(&bytes[20 .. 24], &1.0f32.to_le_bytes());
+  assert_eq!(&bytes[24 .. 32], &[0; 8]);
+  assert_eq!(&bytes[32 .. 34], &0x3800u16.to_le_bytes());
+  assert_eq!(&bytes[64 .. 66], &0x7bffu16.to_le_bytes());
+  assert_eq!(&bytes[80 .. 88], &u64::MAX.to_le_bytes());
+  let decoded = decode(frame_ty, LayoutRules::Uniform, &bytes).unwrap();
+  assert_eq!(decoded.field("weights"), value.field("weights"));
+  assert_eq!(decoded.field("id"), value.field("id"));
+
+  let cells = |count: i32| Value::Array(
+    (0 .. count)
+      .map(|i| Value::Vector(vec![LiteralModel::I32(i), LiteralModel::I32(-i)]))
+      .collect()
+  );
+  let value = fields(vec![
+    ("width", Value::Scalar(LiteralModel::U32(3))),
+    ("cells", cells(3)),
+  ]);
+  let bytes = encode(grid_ty, LayoutRules::Storage, &value).unwrap();
+  assert_eq!(bytes.len(), 8 + 3 * 8);
+  assert_eq!(decode(grid_ty, LayoutRules::Storage, &bytes), Ok(value));
+  let empty = fields(vec![
+    ("width", Value::Scalar(LiteralModel::U32(0))),
+    ("cells", cells(0)),
+  ]);
+  assert_eq!(decode(grid_ty, LayoutRules::Storage, &[0; 8]), Ok(empty));
+}
+
+#[test]
+fn test_codec_errors() {
+  let model = check_source(CODEC_SHADER);
+  let particle_ty = &model.structs()[0];
+  let grid_ty = &model.structs()[2];
+  let particles_ty = TypeModel::new_array(particle_ty.clone(), Some(2));
+
+  let encode_err = |ty: &TypeModel, value: &Value| {
+    encode(ty, LayoutRules::Storage, value).unwrap_err().to_string()
+  };
+
+  let mut wrong_scalar = particle([0.0; 3], 0.0);
+  if let Value::Struct(members) = &mut wrong_scalar {
+    members[1].1 = Value::Scalar(LiteralModel::U32(1));
+  }
+  assert_eq!(
+    encode_err(&particles_ty, &Value::Array(vec![particle([0.0; 3], 0.0), wrong_scalar])),
+    "At `value[1].mass`: Expected a value of type `f32`, found a `u32` scalar."
+  );
+
+  assert_eq!(
+    encode_err(particle_ty, &fields(vec![
+      ("position", f32s(&[0.0, 0.0])),
+      ("mass", Value::Scalar(LiteralModel::new_f32(0.0))),
+    ])),
+    "At `value.position`: Expected 3 elements, found 2."
+  );
+  assert_eq!(
+    encode_err(particle_ty, &fields(vec![("position", f32s(&[0.0; 3]))])),
+    "At `value`: Missing field `mass`."
+  );
+  assert_eq!(
+    encode_err(particle_ty, &fields(vec![("velocity", f32s(&[0.0; 3]))])),
+    "At `value`: Unknown field `velocity`."
+  );
+  assert_eq!(
+    encode_err(&particles_ty, &Value::Array(vec![particle([0.0; 3], 0.0)])),
+    "At `value`: Expected 2 elements, found 1."
+  );
+  assert_eq!(
+    encode_err(particle_ty, &f32s(&[0.0; 4])),
+    "At `value`: Expected a value of type `Particle`, found a vector of 4 components."
+  );
+  assert_eq!(
+    encode_err(&TypeModel::new_bool(), &Value::Scalar(LiteralModel::Bool(true))),
+    "At `value`: Type `bool` cannot be shared with the host."
+  );
+
+  let err = decode(particle_ty, LayoutRules::Storage, &[0; 12]).unwrap_err();
+  assert_eq!(err.kind(), &CodecErrorKind::SizeMismatch { expected: 16, found: 12 });
+  let err = decode(grid_ty, LayoutRules::Storage, &[0; 12]).unwrap_err();
+  assert_eq!(err.kind(), &CodecErrorKind::SizeMismatch { expected: 8, found: 12 });
+
+  // Runtime-sized arrays whose size doesn't fit in a `u32` are errors,
+  // rather than wrapping to a smaller size.
+  let grid_layout = TypeLayout::of(grid_ty, LayoutRules::Storage).unwrap();
+  let too_many = |count| CodecErrorKind::Layout(LayoutError::TooManyElements(count));
+  let err = check_size(&grid_layout, 8 + (8 << 29)).unwrap_err();
+  assert_eq!(err.kind(), &too_many(1 << 29));
+  if let Ok(len) = usize::try_from(8 + (8_u64 << 32)) {
+    let err = check_size(&grid_layout, len).unwrap_err();
+    assert_eq!(err.kind(), &too_many(1 << 32));
+  }
+}
@@ -0,0 +1,17 @@
+mod test_c_gen;
+mod test_rust_gen;
+mod test_ts_gen;
+
+const HOST_SHADER: &str = "
+  struct Particle { position: vec3xf32, mass: f32 }
+  struct Mixed { a: f32, b: [f32; 2], m: mat2x3xf32 }
+  @packed struct Record { tag: u32, id: u64 }
+  struct Grid { width: u32, cells: [vec3xf32] }
+  struct Flagged { value: f32, flag: bool }
+  uniforms { mixed: Mixed, type: u32 }
+  buffer(r) particles: Particle;
+  buffer(rw) grid: Grid;
+  buffer(w) records: Record;
+  buffer(r) weights: f16;
+";
+
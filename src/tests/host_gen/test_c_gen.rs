@@ -0,0 +1,108 @@
+use crate::{ host_gen::generate_c_header, tests::check_source };
+use super::HOST_SHADER;
+
+#[test]
+fn test_c_gen() {
+  assert_eq!(generate_c_header(&check_source(HOST_SHADER)), EXPECTED_C_HEADER);
+}
+
+const EXPECTED_C_HEADER: &str = r#"/* Generated by dubgsl from `test.dubgsl.shader`.  Do not edit. */
+#ifndef DUBGSL_TEST_DUBGSL_SHADER_H
+#define DUBGSL_TEST_DUBGSL_SHADER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct Particle {
+  _Alignas(16) float position[3];
+  float mass;
+} Particle;
+_Static_assert(sizeof(Particle) == 16, "Particle size");
+_Static_assert(offsetof(Particle, position) == 0, "Particle.position offset");
+_Static_assert(offsetof(Particle, mass) == 12, "Particle.mass offset");
+
+typedef struct dubgsl_padded_f32x3_4 {
+  float value[3];
+  uint8_t _pad[4];
+} dubgsl_padded_f32x3_4;
+
+typedef struct Mixed {
+  _Alignas(16) float a;
+  float b[2];
+  uint8_t _pad0[4];
+  dubgsl_padded_f32x3_4 m[2];
+} Mixed;
+_Static_assert(sizeof(Mixed) == 48, "Mixed size");
+_Static_assert(offsetof(Mixed, a) == 0, "Mixed.a offset");
+_Static_assert(offsetof(Mixed, b) == 4, "Mixed.b offset");
+_Static_assert(offsetof(Mixed, m) == 16, "Mixed.m offset");
+
+#pragma pack(push, 1)
+typedef struct Record {
+  uint32_t tag;
+  uint8_t _pad0[4];
+  uint64_t id;
+} Record;
+#pragma pack(pop)
+_Static_assert(sizeof(Record) == 16, "Record size");
+_Static_assert(offsetof(Record, tag) == 0, "Record.tag offset");
+_Static_assert(offsetof(Record, id) == 8, "Record.id offset");
+
+/* The offset of the runtime-sized `cells` array of `Grid`. */
+#define GRID_CELLS_OFFSET 16
+#define GRID_CELLS_STRIDE 16
+typedef struct Grid {
+  _Alignas(16) uint32_t width;
+  uint8_t _pad0[12];
+  dubgsl_padded_f32x3_4 cells[];
+} Grid;
+_Static_assert(offsetof(Grid, width) == 0, "Grid.width offset");
+_Static_assert(offsetof(Grid, cells) == 16, "Grid.cells offset");
+
+typedef struct dubgsl_padded_f32_12 {
+  float value;
+  uint8_t _pad[12];
+} dubgsl_padded_f32_12;
+
+typedef struct MixedUniform {
+  _Alignas(16) float a;
+  uint8_t _pad0[12];
+  dubgsl_padded_f32_12 b[2];
+  dubgsl_padded_f32x3_4 m[2];
+} MixedUniform;
+_Static_assert(sizeof(MixedUniform) == 80, "MixedUniform size");
+_Static_assert(offsetof(MixedUniform, a) == 0, "MixedUniform.a offset");
+_Static_assert(offsetof(MixedUniform, b) == 16, "MixedUniform.b offset");
+_Static_assert(offsetof(MixedUniform, m) == 48, "MixedUniform.m offset");
+
+typedef struct Uniforms {
+  _Alignas(16) MixedUniform mixed;
+  uint32_t type;
+  uint8_t _pad0[12];
+} Uniforms;
+_Static_assert(sizeof(Uniforms) == 96, "Uniforms size");
+_Static_assert(offsetof(Uniforms, mixed) == 0, "Uniforms.mixed offset");
+_Static_assert(offsetof(Uniforms, type) == 80, "Uniforms.type offset");
+
+/* The element type of buffer `particles`. */
+typedef Particle ParticlesElement;
+#define PARTICLES_ELEMENT_STRIDE 16
+_Static_assert(sizeof(ParticlesElement) == 16, "ParticlesElement stride");
+
+/* The element type of buffer `grid`. */
+typedef dubgsl_padded_f32x3_4 GridElement;
+#define GRID_ELEMENT_STRIDE 16
+_Static_assert(sizeof(GridElement) == 16, "GridElement stride");
+
+/* The element type of buffer `records`. */
+typedef Record RecordsElement;
+#define RECORDS_ELEMENT_STRIDE 16
+_Static_assert(sizeof(RecordsElement) == 16, "RecordsElement stride");
+
+/* The element type of buffer `weights`. */
+typedef uint16_t WeightsElement;
+#define WEIGHTS_ELEMENT_STRIDE 2
+_Static_assert(sizeof(WeightsElement) == 2, "WeightsElement stride");
+
+#endif /* DUBGSL_TEST_DUBGSL_SHADER_H */
+"#;
@@ -0,0 +1,119 @@
+use crate::{ host_gen::generate_rust, tests::check_source };
+use super::HOST_SHADER;
+
+#[test]
+fn test_rust_gen() {
+  assert_eq!(generate_rust(&check_source(HOST_SHADER)), EXPECTED_RUST);
+}
+
+// `Flagged` holds a `bool`, so it has no host type.
+const EXPECTED_RUST: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+
+/** A value followed by `PAD` bytes of padding. */
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Padded<T: Copy, const PAD: usize> {
+  pub value: T,
+  pub _pad: [u8; PAD],
+}
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+  pub position: [f32; 3],
+  pub mass: f32,
+}
+const _: () = {
+  assert!(std::mem::size_of::<Particle>() == 16);
+  assert!(std::mem::offset_of!(Particle, position) == 0);
+  assert!(std::mem::offset_of!(Particle, mass) == 12);
+};
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mixed {
+  pub a: f32,
+  pub b: [f32; 2],
+  pub _pad0: [u8; 4],
+  pub m: [Padded<[f32; 3], 4>; 2],
+}
+const _: () = {
+  assert!(std::mem::size_of::<Mixed>() == 48);
+  assert!(std::mem::offset_of!(Mixed, a) == 0);
+  assert!(std::mem::offset_of!(Mixed, b) == 4);
+  assert!(std::mem::offset_of!(Mixed, m) == 16);
+};
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+  pub tag: u32,
+  pub _pad0: [u8; 4],
+  pub id: u64,
+}
+const _: () = {
+  assert!(std::mem::size_of::<Record>() == 16);
+  assert!(std::mem::offset_of!(Record, tag) == 0);
+  assert!(std::mem::offset_of!(Record, id) == 8);
+};
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grid {
+  pub width: u32,
+  pub _pad0: [u8; 12],
+}
+impl Grid {
+  /** The offset of the runtime-sized `cells` array, which follows these fields. */
+  pub const CELLS_OFFSET: usize = 16;
+  pub const CELLS_STRIDE: usize = 16;
+}
+pub type GridCells = Padded<[f32; 3], 4>;
+const _: () = {
+  assert!(std::mem::offset_of!(Grid, width) == 0);
+};
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MixedUniform {
+  pub a: f32,
+  pub _pad0: [u8; 12],
+  pub b: [Padded<f32, 12>; 2],
+  pub m: [Padded<[f32; 3], 4>; 2],
+}
+const _: () = {
+  assert!(std::mem::size_of::<MixedUniform>() == 80);
+  assert!(std::mem::offset_of!(MixedUniform, a) == 0);
+  assert!(std::mem::offset_of!(MixedUniform, b) == 16);
+  assert!(std::mem::offset_of!(MixedUniform, m) == 48);
+};
+
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uniforms {
+  pub mixed: MixedUniform,
+  pub r#type: u32,
+  pub _pad0: [u8; 12],
+}
+const _: () = {
+  assert!(std::mem::size_of::<Uniforms>() == 96);
+  assert!(std::mem::offset_of!(Uniforms, mixed) == 0);
+  assert!(std::mem::offset_of!(Uniforms, r#type) == 80);
+};
+
+/** The element type of buffer `particles`. */
+pub type ParticlesElement = Particle;
+const _: () = assert!(std::mem::size_of::<ParticlesElement>() == 16);
+
+/** The element type of buffer `grid`. */
+pub type GridElement = Padded<[f32; 3], 4>;
+const _: () = assert!(std::mem::size_of::<GridElement>() == 16);
+
+/** The element type of buffer `records`. */
+pub type RecordsElement = Record;
+const _: () = assert!(std::mem::size_of::<RecordsElement>() == 16);
+
+/** The element type of buffer `weights`. */
+pub type WeightsElement = u16;
+const _: () = assert!(std::mem::size_of::<WeightsElement>() == 2);
+"#;
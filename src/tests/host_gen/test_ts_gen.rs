@@ -0,0 +1,183 @@
+use crate::{ host_gen::generate_typescript, tests::check_source };
+use super::HOST_SHADER;
+
+#[test]
+fn test_ts_gen() {
+  assert_eq!(generate_typescript(&check_source(HOST_SHADER)), EXPECTED_TYPESCRIPT);
+}
+
+const EXPECTED_TYPESCRIPT: &str = r#"// Generated by dubgsl from `test.dubgsl.shader`.  Do not edit.
+
+export interface Particle {
+  position: [number, number, number];
+  mass: number;
+}
+export const PARTICLE_SIZE = 16;
+export const PARTICLE_ALIGN = 16;
+export function unpackParticle(view: DataView, offset: number): Particle {
+  return {
+    position: [view.getFloat32(offset, true), view.getFloat32(offset + 4, true), view.getFloat32(offset + 8, true)],
+    mass: view.getFloat32(offset + 12, true),
+  };
+}
+export function packParticle(view: DataView, offset: number, value: Particle): void {
+  view.setFloat32(offset, value.position[0], true);
+  view.setFloat32(offset + 4, value.position[1], true);
+  view.setFloat32(offset + 8, value.position[2], true);
+  view.setFloat32(offset + 12, value.mass, true);
+}
+
+export interface Mixed {
+  a: number;
+  b: number[];
+  m: [number, number, number][];
+}
+export const MIXED_SIZE = 48;
+export const MIXED_ALIGN = 16;
+export function unpackMixed(view: DataView, offset: number): Mixed {
+  return {
+    a: view.getFloat32(offset, true),
+    b: Array.from({ length: 2 }, (_, i0): number => view.getFloat32(offset + 4 + i0 * 4, true)),
+    m: Array.from({ length: 2 }, (_, i0): [number, number, number] => [view.getFloat32(offset + 16 + i0 * 16, true), view.getFloat32(offset + 16 + i0 * 16 + 4, true), view.getFloat32(offset + 16 + i0 * 16 + 8, true)]),
+  };
+}
+export function packMixed(view: DataView, offset: number, value: Mixed): void {
+  view.setFloat32(offset, value.a, true);
+  for (let i0 = 0; i0 < 2; i0++) {
+    view.setFloat32(offset + 4 + i0 * 4, value.b[i0], true);
+  }
+  for (let i0 = 0; i0 < 2; i0++) {
+    view.setFloat32(offset + 16 + i0 * 16, value.m[i0][0], true);
+    view.setFloat32(offset + 16 + i0 * 16 + 4, value.m[i0][1], true);
+    view.setFloat32(offset + 16 + i0 * 16 + 8, value.m[i0][2], true);
+  }
+}
+
+export interface Record {
+  tag: number;
+  id: bigint;
+}
+export const RECORD_SIZE = 16;
+export const RECORD_ALIGN = 8;
+export function unpackRecord(view: DataView, offset: number): Record {
+  return {
+    tag: view.getUint32(offset, true),
+    id: view.getBigUint64(offset + 8, true),
+  };
+}
+export function packRecord(view: DataView, offset: number, value: Record): void {
+  view.setUint32(offset, value.tag, true);
+  view.setBigUint64(offset + 8, value.id, true);
+}
+
+export interface Grid {
+  width: number;
+}
+export const GRID_SIZE = 16;
+export const GRID_ALIGN = 16;
+export function unpackGrid(view: DataView, offset: number): Grid {
+  return {
+    width: view.getUint32(offset, true),
+  };
+}
+export function packGrid(view: DataView, offset: number, value: Grid): void {
+  view.setUint32(offset, value.width, true);
+}
+/** The offset of the runtime-sized `cells` array of `Grid`. */
+export const GRID_CELLS_OFFSET = 16;
+export type GridCells = [number, number, number];
+export const GRID_CELLS_STRIDE = 16;
+export function unpackGridCells(view: DataView, offset: number): GridCells {
+  return [view.getFloat32(offset, true), view.getFloat32(offset + 4, true), view.getFloat32(offset + 8, true)];
+}
+export function packGridCells(view: DataView, offset: number, value: GridCells): void {
+  view.setFloat32(offset, value[0], true);
+  view.setFloat32(offset + 4, value[1], true);
+  view.setFloat32(offset + 8, value[2], true);
+}
+
+export interface MixedUniform {
+  a: number;
+  b: number[];
+  m: [number, number, number][];
+}
+export const MIXED_UNIFORM_SIZE = 80;
+export const MIXED_UNIFORM_ALIGN = 16;
+export function unpackMixedUniform(view: DataView, offset: number): MixedUniform {
+  return {
+    a: view.getFloat32(offset, true),
+    b: Array.from({ length: 2 }, (_, i0): number => view.getFloat32(offset + 16 + i0 * 16, true)),
+    m: Array.from({ length: 2 }, (_, i0): [number, number, number] => [view.getFloat32(offset + 48 + i0 * 16, true), view.getFloat32(offset + 48 + i0 * 16 + 4, true), view.getFloat32(offset + 48 + i0 * 16 + 8, true)]),
+  };
+}
+export function packMixedUniform(view: DataView, offset: number, value: MixedUniform): void {
+  view.setFloat32(offset, value.a, true);
+  for (let i0 = 0; i0 < 2; i0++) {
+    view.setFloat32(offset + 16 + i0 * 16, value.b[i0], true);
+  }
+  for (let i0 = 0; i0 < 2; i0++) {
+    view.setFloat32(offset + 48 + i0 * 16, value.m[i0][0], true);
+    view.setFloat32(offset + 48 + i0 * 16 + 4, value.m[i0][1], true);
+    view.setFloat32(offset + 48 + i0 * 16 + 8, value.m[i0][2], true);
+  }
+}
+
+export interface Uniforms {
+  mixed: MixedUniform;
+  type: number;
+}
+export const UNIFORMS_SIZE = 96;
+export const UNIFORMS_ALIGN = 16;
+export function unpackUniforms(view: DataView, offset: number): Uniforms {
+  return {
+    mixed: unpackMixedUniform(view, offset),
+    type: view.getUint32(offset + 80, true),
+  };
+}
+export function packUniforms(view: DataView, offset: number, value: Uniforms): void {
+  packMixedUniform(view, offset, value.mixed);
+  view.setUint32(offset + 80, value.type, true);
+}
+
+/** The element type of buffer `particles`. */
+export type ParticlesElement = Particle;
+export const PARTICLES_ELEMENT_STRIDE = 16;
+export function unpackParticlesElement(view: DataView, offset: number): ParticlesElement {
+  return unpackParticle(view, offset);
+}
+export function packParticlesElement(view: DataView, offset: number, value: ParticlesElement): void {
+  packParticle(view, offset, value);
+}
+
+/** The element type of buffer `grid`. */
+export type GridElement = [number, number, number];
+export const GRID_ELEMENT_STRIDE = 16;
+export function unpackGridElement(view: DataView, offset: number): GridElement {
+  return [view.getFloat32(offset, true), view.getFloat32(offset + 4, true), view.getFloat32(offset + 8, true)];
+}
+export function packGridElement(view: DataView, offset: number, value: GridElement): void {
+  view.setFloat32(offset, value[0], true);
+  view.setFloat32(offset + 4, value[1], true);
+  view.setFloat32(offset + 8, value[2], true);
+}
+
+/** The element type of buffer `records`. */
+export type RecordsElement = Record;
+export const RECORDS_ELEMENT_STRIDE = 16;
+export function unpackRecordsElement(view: DataView, offset: number): RecordsElement {
+  return unpackRecord(view, offset);
+}
+export function packRecordsElement(view: DataView, offset: number, value: RecordsElement): void {
+  packRecord(view, offset, value);
+}
+
+/** The element type of buffer `weights`. */
+export type WeightsElement = number;
+export const WEIGHTS_ELEMENT_STRIDE = 2;
+export function unpackWeightsElement(view: DataView, offset: number): WeightsElement {
+  return view.getUint16(offset, true);
+}
+export function packWeightsElement(view: DataView, offset: number, value: WeightsElement): void {
+  view.setUint16(offset, value, true);
+}
+"#;
@@ -0,0 +1,2 @@
+mod test_interpret;
+mod test_tests;
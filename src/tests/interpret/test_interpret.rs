@@ -0,0 +1,271 @@
+use crate::{
+  data::{ Value, decode, encode },
+  interpret::{ InterpretErrorKind, Interpreter },
+  model::{ LayoutRules, LiteralModel },
+  tests::{ check_source, f32s, fields },
+};
+
+const INTERPRET_SHADER: &str = "
+  struct Particle { position: vec2xf32, velocity: vec2xf32, steps: u32 }
+  uniforms { gravity: vec2xf32, dt: f32, max_steps: u32 }
+  buffer(rw) particles: Particle;
+
+  func advance(p: Particle) -> Particle {
+    ret Particle {
+      position: p.position + p.velocity * uniforms.dt,
+      velocity: p.velocity + uniforms.gravity * uniforms.dt,
+      steps: p.steps + 1,
+    };
+  }
+
+  entrypoint(1d) simulate(i) {
+    if i >= particles.length { ret; }
+    var p = particles[i];
+    loop {
+      if p.steps == uniforms.max_steps || p.position.y < 0.0 {
+        mutate particles[i] = p;
+        ret;
+      }
+      mutate p = advance(p);
+    }
+  }
+";
+
+fn particle(position: [f32; 2], velocity: [f32; 2], steps: u32) -> Value {
+  fields(vec![
+    ("position", f32s(&position)),
+    ("velocity", f32s(&velocity)),
+    ("steps", Value::Scalar(LiteralModel::U32(steps))),
+  ])
+}
+
+#[test]
+fn test_interpret() {
+  let model = check_source(INTERPRET_SHADER);
+  let particles_ty = model.buffers()[0].ty();
+  let mut interpreter = Interpreter::new(&model);
+  interpreter.bind_uniforms(&fields(vec![
+    ("gravity", f32s(&[0.0, -10.0])),
+    ("dt", Value::Scalar(LiteralModel::new_f32(0.5))),
+    ("max_steps", Value::Scalar(LiteralModel::U32(3))),
+  ])).unwrap();
+  let particles = Value::Array(vec![
+    particle([0.0, 1.0], [2.0, 0.0], 0),
+    particle([0.0, 100.0], [1.0, 4.0], 1),
+    particle([0.0, -1.0], [0.0, 0.0], 0),
+  ]);
+  let bytes = encode(particles_ty, LayoutRules::Storage, &particles).unwrap();
+  interpreter.bind_buffer("particles", bytes).unwrap();
+
+  // One workgroup of 64 invocations, most of them out of range.
+  interpreter.dispatch("simulate", [1, 1, 1]).unwrap();
+  let bytes = interpreter.buffer("particles").unwrap();
+  assert_eq!(decode(particles_ty, LayoutRules::Storage, bytes), Ok(Value::Array(vec![
+    particle([2.0, -1.5], [2.0, -10.0], 2),
+    particle([1.0, 101.5], [1.0, -6.0], 3),
+    particle([0.0, -1.0], [0.0, 0.0], 0),
+  ])));
+}
+
+#[test]
+fn test_interpret_arithmetic() {
+  let model = check_source("
+    struct Ints { a: i32, b: u32, c: i64, d: u64 }
+    buffer(rw) ints: Ints;
+    buffer(rw) floats: vec4xf32;
+    buffer(rw) halves: f16;
+    buffer(r) matrices: mat2x2xf32;
+
+    entrypoint(1d) run(i) {
+      if i > 0 { ret; }
+      let x = ints[0];
+      mutate ints[1] = Ints {
+        a: x.a + 1,
+        b: x.b * 3,
+        c: x.c / -1,
+        d: x.d >> 60,
+      };
+      mutate ints[2] = Ints {
+        a: x.a % 7 + (x.a >> 28),
+        b: x.b - 4 - (1_u32 << 31),
+        c: x.c % 7,
+        d: ~x.d,
+      };
+      mutate ints[3] = Ints {
+        a: 10000000000.0 as i32,
+        b: -1.5 as u32,
+        c: (x.a + 1) as i64,
+        d: (x.a + 1) as u64,
+      };
+
+      let m = matrices[0];
+      let v = floats[0].xy;
+      mutate floats[1].xy = m * v;
+      mutate floats[1].zw = v * m;
+      let column = (m * m + m * 0.5) * vec2xf32(0.0, 1.0);
+      mutate floats[2] = vec4xf32(column, 7.5 % -2.0, 1.0 / 0.0);
+      mutate floats[3].wx = vec2xf32(halves[0] as f32, (halves[0] * halves[0]) as f32);
+      mutate halves[1] = halves[0] + 0.0001;
+      mutate halves[2] = 100000.0 as f16;
+    }
+  ");
+  let mut interpreter = Interpreter::new(&model);
+  let ints = |a: i32, b: u32, c: i64, d: u64| fields(vec![
+    ("a", Value::Scalar(LiteralModel::I32(a))),
+    ("b", Value::Scalar(LiteralModel::U32(b))),
+    ("c", Value::Scalar(LiteralModel::I64(c))),
+    ("d", Value::Scalar(LiteralModel::U64(d))),
+  ]);
+  let ints_ty = model.buffers()[0].ty();
+  let zeros = ints(0, 0, 0, 0);
+  let value = Value::Array(vec![
+    ints(i32::MAX, 0x8000_0001, i64::MIN + 3, u64::MAX),
+    zeros.clone(),
+    zeros.clone(),
+    zeros,
+  ]);
+  interpreter.bind_buffer("ints", encode(ints_ty, LayoutRules::Storage, &value).unwrap())
+    .unwrap();
+  let floats_ty = model.buffers()[1].ty();
+  let mut value = vec![f32s(&[0.0; 4]); 4];
+  value[0] = f32s(&[1.0, -1.0, 0.0, 0.0]);
+  let value = Value::Array(value);
+  interpreter.bind_buffer("floats", encode(floats_ty, LayoutRules::Storage, &value).unwrap())
+    .unwrap();
+  let matrices_ty = model.buffers()[3].ty();
+  let value = Value::Array(vec![Value::Array(vec![f32s(&[1.0, 2.0]), f32s(&[3.0, 4.0])])]);
+  interpreter.bind_buffer("matrices", encode(matrices_ty, LayoutRules::Storage, &value).unwrap())
+    .unwrap();
+  let halves_ty = model.buffers()[2].ty();
+  let value = Value::Array(
+    [1.0 / 3.0, 0.0, 0.0].iter().map(|&v| Value::Scalar(LiteralModel::new_f16(v))).collect()
+  );
+  interpreter.bind_buffer("halves", encode(halves_ty, LayoutRules::Storage, &value).unwrap())
+    .unwrap();
+  interpreter.dispatch("run", [1, 1, 1]).unwrap();
+
+  let Ok(Value::Array(ints_out)) =
+    decode(ints_ty, LayoutRules::Storage, interpreter.buffer("ints").unwrap())
+  else {
+    panic!("Buffer does not decode to an array");
+  };
+  // Integer arithmetic wraps, remainders take the sign of the dividend,
+  // and signed shifts are arithmetic.
+  assert_eq!(ints_out[1], ints(i32::MIN, 0x8000_0003, i64::MAX - 2, 15));
+  assert_eq!(ints_out[2], ints(1 + 7, 0xFFFF_FFFD, -5, 0));
+  // Float to integer casts truncate and saturate, and integer casts
+  // wrap or sign-extend.
+  assert_eq!(ints_out[3], ints(i32::MAX, 0, i32::MIN as i64, 0xFFFF_FFFF_8000_0000));
+
+  let Ok(Value::Array(floats_out)) =
+    decode(floats_ty, LayoutRules::Storage, interpreter.buffer("floats").unwrap())
+  else {
+    panic!("Buffer does not decode to an array");
+  };
+  assert_eq!(floats_out[1], f32s(&[-2.0, -2.0, -1.0, -1.0]));
+  assert_eq!(floats_out[2], f32s(&[16.5, 24.0, 1.5, f32::INFINITY]));
+
+  // `f16` arithmetic rounds every result to an `f16`, so the small
+  // addend is lost, and large values become infinite.
+  let third = 0.33325195;
+  assert_eq!(floats_out[3], f32s(&[0.111083984, 0.0, 0.0, third]));
+  let Ok(Value::Array(halves_out)) =
+    decode(halves_ty, LayoutRules::Storage, interpreter.buffer("halves").unwrap())
+  else {
+    panic!("Buffer does not decode to an array");
+  };
+  assert_eq!(halves_out[1], Value::Scalar(LiteralModel::new_f16(third)));
+  assert_eq!(halves_out[2], Value::Scalar(LiteralModel::new_f16(f32::INFINITY)));
+}
+
+#[test]
+fn test_interpret_dispatch() {
+  let model = check_source("
+    struct Grid { width: u32, @align(16) cells: [vec2xu32] }
+    buffer(rw) grid: Grid;
+    entrypoint(2d) fill(id) {
+      if (id.x < grid.width) && (id.y * grid.width + id.x < grid.cells.length) {
+        mutate grid.cells[id.y * grid.width + id.x] = id;
+      }
+    }
+  ");
+  let mut interpreter = Interpreter::new(&model);
+  let mut bytes = vec![0; 16 + 20 * 8];
+  bytes[0] = 10;
+  interpreter.bind_buffer("grid", bytes).unwrap();
+  // Workgroups are 8x8, so two along x cover the grid's width.
+  interpreter.dispatch("fill", [2, 1, 1]).unwrap();
+  let cells = match decode(model.buffers()[0].ty(), LayoutRules::Storage,
+    interpreter.buffer("grid").unwrap())
+  {
+    Ok(grid) => grid.field("cells").cloned().unwrap(),
+    Err(err) => panic!("{}", err),
+  };
+  let expected = (0 .. 20)
+    .map(|i| Value::Vector(vec![LiteralModel::U32(i % 10), LiteralModel::U32(i / 10)]))
+    .collect();
+  assert_eq!(cells, Value::Array(expected));
+}
+
+#[test]
+fn test_interpret_errors() {
+  let model = check_source("
+    uniforms { divisor: i32 }
+    buffer(rw) out: i32;
+    entrypoint(1d) run(i) {
+      mutate out[i] = 100 / (uniforms.divisor - i as i32) + (1 << i);
+    }
+  ");
+  let out_ty = model.buffers()[0].ty();
+  let out = |len: usize| {
+    let value = Value::Array(vec![Value::Scalar(LiteralModel::I32(0)); len]);
+    encode(out_ty, LayoutRules::Storage, &value).unwrap()
+  };
+  let divisor = |divisor: i32| {
+    fields(vec![("divisor", Value::Scalar(LiteralModel::I32(divisor)))])
+  };
+  let mut interpreter = Interpreter::new(&model);
+
+  let err = interpreter.dispatch("main", [1, 1, 1]).unwrap_err();
+  assert_eq!(err.kind(), &InterpretErrorKind::UnknownEntrypoint("main".to_string()));
+  let err = interpreter.dispatch("run", [1, 1, 1]).unwrap_err();
+  assert_eq!(err.to_string(), "The uniforms are not bound.");
+  interpreter.bind_uniforms(&divisor(3)).unwrap();
+  let err = interpreter.dispatch("run", [1, 1, 1]).unwrap_err();
+  assert_eq!(err.to_string(), "Buffer `out` is not bound.");
+  let err = interpreter.bind_buffer("out", vec![0; 6]).unwrap_err();
+  assert_eq!(err.to_string(), "At `value`: Expected 4 bytes, found 6.");
+  let err = interpreter.bind_buffer("inputs", out(1)).unwrap_err();
+  assert_eq!(err.kind(), &InterpretErrorKind::UnknownBuffer("inputs".to_string()));
+  let err = interpreter.bind_uniforms(&Value::Scalar(LiteralModel::I32(1))).unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "At `value`: Expected a value of type `Uniforms`, found a `i32` scalar."
+  );
+
+  interpreter.bind_buffer("out", out(64)).unwrap();
+  let err = interpreter.dispatch("run", [1, 1, 1]).unwrap_err();
+  assert_eq!(err.invocation(), Some([3, 0, 0]));
+  assert_eq!(err.to_string(), "In invocation (3, 0, 0): Integer division by zero.");
+
+  interpreter.bind_uniforms(&divisor(-1)).unwrap();
+  let err = interpreter.dispatch("run", [1, 1, 1]).unwrap_err();
+  assert_eq!(err.to_string(), "In invocation (32, 0, 0): Cannot shift a 32-bit integer by 32.");
+  interpreter.bind_buffer("out", out(16)).unwrap();
+  let err = interpreter.dispatch("run", [1, 1, 1]).unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "In invocation (16, 0, 0): Index 16 is out of bounds for an array of length 16."
+  );
+
+  let err = interpreter.dispatch("run", [67108864, 1, 1]).unwrap_err();
+  assert_eq!(err.kind(), &InterpretErrorKind::DispatchTooLarge {
+    workgroups: [67108864, 1, 1],
+    workgroup_size: [64, 1, 1],
+  });
+  assert_eq!(
+    err.to_string(),
+    "A dispatch of 67108864x1x1 workgroups of 64x1x1 invocations is too large; the \
+     invocations along each axis must fit in a `u32`."
+  );
+}
@@ -0,0 +1,140 @@
+use crate::{
+  interpret::{ InterpretErrorKind, Interpreter },
+  tests::{ check_source, session_config },
+  transform::{ SyntaxIngester, TargetCapabilities },
+};
+
+const TESTED_SHADER: &str = "
+  buffer(rw) values: i32;
+
+  func clamp_index(i: i32, len: i32) -> i32 {
+    if i < 0 { ret 0; }
+    if i >= len { ret len - 1; }
+    ret i;
+  }
+
+  entrypoint(1d) double(i) {
+    mutate values[i] = values[i] * 2;
+  }
+
+  test clamps_low {
+    assert(clamp_index(-3, 8) == 0);
+  }
+
+  test clamps_high {
+    let last = clamp_index(12, 8);
+    assert(last == 7);
+    assert(last == 8);
+  }
+
+  test reads_values {
+    assert(values.length == 2);
+    assert(values[1] == 5);
+  }
+";
+
+#[test]
+fn test_run_tests() {
+  let model = check_source(TESTED_SHADER);
+  let mut interpreter = Interpreter::new(&model);
+
+  let reports = interpreter.run_tests();
+  let names = reports.iter().map(|report| report.name()).collect::<Vec<_>>();
+  assert_eq!(names, vec!["clamps_low", "clamps_high", "reads_values"]);
+  assert!(reports[0].passed());
+  assert_eq!(reports[0].to_string(), "test clamps_low ... ok");
+
+  // The span of the failing assertion slices back to it in the source.
+  assert!(!reports[1].passed());
+  let span = reports[1].failed_assertion().expect("Expected a failed assertion");
+  assert_eq!(&TESTED_SHADER[span.clone()], "assert(last == 8)");
+  assert_eq!(
+    reports[1].to_string(),
+    format!("test clamps_high ... FAILED: Assertion failed at {}..{}.", span.start, span.end)
+  );
+
+  // Tests may use bound resources, and fail without an assertion if
+  // they use unbound ones.
+  assert_eq!(reports[2].failed_assertion(), None);
+  assert_eq!(
+    reports[2].result().as_ref().unwrap_err().kind(),
+    &InterpretErrorKind::UnboundBuffer("values".to_string())
+  );
+  let bytes = [4_i32, 5].iter().flat_map(|v| v.to_le_bytes()).collect();
+  interpreter.bind_buffer("values", bytes).unwrap();
+  interpreter.run_test("reads_values").unwrap();
+
+  let err = interpreter.run_test("missing").unwrap_err();
+  assert_eq!(err.to_string(), "No test named `missing`.");
+}
+
+#[test]
+fn test_run_library_tests() {
+  let contents = "
+    struct Range { start: u32, end: u32 }
+
+    func len(r: Range) -> u32 {
+      ret r.end - r.start;
+    }
+
+    test len_of_range {
+      assert(len(Range { start: 3, end: 10 }) == 7);
+    }
+  ";
+  let model = SyntaxIngester::parse_library_file(
+    &session_config(TargetCapabilities::default()),
+    "test.dubgsl.lib",
+    contents,
+  ).expect("Failed to check tested library");
+  let reports = Interpreter::new(&model).run_tests();
+  assert_eq!(reports.len(), 1);
+  assert!(reports[0].passed(), "{}", reports[0]);
+}
+
+#[test]
+fn test_malformed_library_test() {
+  let parse_err = |contents: &str| {
+    SyntaxIngester::parse_library_file(
+      &session_config(TargetCapabilities::default()),
+      "test.dubgsl.lib",
+      contents,
+    )
+      .unwrap_err()
+      .into_iter()
+      .map(|diagnostic| diagnostic.message)
+      .collect::<Vec<_>>()
+  };
+
+  // Syntax errors are reported rather than panicking.
+  assert_eq!(
+    parse_err("test { assert(true); }"),
+    vec!["Failed to parse `test.dubgsl.lib` at 5..6: unexpected `{`."]
+  );
+  assert_eq!(
+    parse_err("test unfinished { assert(1 == 1);"),
+    vec!["Failed to parse `test.dubgsl.lib` at 33..33: unexpected end of file."]
+  );
+}
+
+#[test]
+fn test_missing_return() {
+  // The checker rules out functions that end without a value, but a
+  // model that has one fails the test rather than the interpreter.
+  let mut model = (*check_source("
+    func f(x: i32) -> i32 {
+      if x > 0 { ret 1; }
+      ret 2;
+    }
+    test t { assert(f(0) == 1); }
+  ")).clone();
+  model.funcs[0].body.pop();
+  let reports = Interpreter::new(&model).run_tests();
+  assert_eq!(
+    reports[0].result().as_ref().unwrap_err().kind(),
+    &InterpretErrorKind::MissingReturn("f".to_string())
+  );
+  assert_eq!(
+    reports[0].to_string(),
+    "test t ... FAILED: Function `f` ended without returning a value."
+  );
+}
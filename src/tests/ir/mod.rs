@@ -0,0 +1,2 @@
+mod test_lower;
+mod test_verify;
@@ -0,0 +1,173 @@
+use crate::{
+  ir::{ lower_shader, parse_ir, verify_ir },
+  tests::check_source,
+};
+
+const LOWER_SHADER: &str = "
+  struct Particle { position: vec3xf32, velocity: vec3xf32, mass: f32 }
+  uniforms { gravity: vec3xf32, dt: f32, steps: u32 }
+  buffer(rw) particles: Particle;
+
+  func heavy(p: Particle) -> bool {
+    ret p.mass > 1.0;
+  }
+
+  entrypoint(1d) step(i) {
+    if i >= particles.length || heavy(particles[i]) { ret; }
+    var p = particles[i];
+    var n = 0_u32;
+    loop {
+      if n == uniforms.steps { ret; }
+      mutate p.velocity = p.velocity + uniforms.gravity * uniforms.dt;
+      mutate particles[i].position.zx = p.position.xz;
+      mutate n = n + 1;
+    }
+  }
+";
+
+#[test]
+fn test_lower() {
+  let module = lower_shader(&check_source(LOWER_SHADER));
+  assert_eq!(module.to_string(), EXPECTED_IR);
+  verify_ir(&module).unwrap();
+  assert_eq!(parse_ir(EXPECTED_IR).unwrap(), module);
+}
+
+#[test]
+fn test_lower_expressions() {
+  let module = lower_shader(&check_source("
+    struct Tint { color: vec4xf32, @align(16) scale: f16 }
+    uniforms { rot: mat2x2xf32, tint: Tint }
+    @packed struct Pair { tag: u32, value: vec2xf32 }
+    struct Grid { width: u32, cells: [vec2xi32] }
+    buffer(r) grid: Grid;
+    buffer(w) pairs: Pair;
+
+    func mix(a: vec2xf32, b: f32) -> vec2xf32 {
+      let m = uniforms.rot * uniforms.rot;
+      ret vec2xf32(a.y, b) * m + m * a;
+    }
+
+    entrypoint(2d) fill(id) {
+      let cell = grid.cells[id.x + id.y * grid.width];
+      let mask = ~cell & 3;
+      let far = !(mask.x > 1 && mask.y < -1);
+      let scaled = mix(cell as vec2xf32, uniforms.tint.scale as f32);
+      if far { ret; } else {
+        mutate pairs[id.x] = Pair { tag: (1 << id.y) >> 1, value: scaled };
+      }
+      mutate pairs[id.y].value.y = -1.5 / 0.0;
+    }
+  "));
+  verify_ir(&module).unwrap();
+  let text = module.to_string();
+  assert_eq!(parse_ir(&text).unwrap(), module);
+
+  // Structs come after those they contain, with their attributes.
+  assert!(text.starts_with(
+    "struct Tint { color: vec4xf32, @align(16) scale: f16 }\n\
+     @packed struct Pair { tag: u32, value: vec2xf32 }\n\
+     struct Grid { width: u32, cells: [vec2xi32] }\n\
+     struct Uniforms { rot: mat2x2xf32, tint: Tint }\n\
+     uniforms: Uniforms\n\
+     buffer(r) grid: Grid\n\
+     buffer(w) pairs: [Pair]\n"
+  ));
+
+  // Resources are loaded through places, and values are taken apart
+  // with their own instructions.
+  assert!(text.contains(
+    "    %5: u32 = add %1, %4\n    %6: vec2xi32 = load grid.1[%5]\n"
+  ));
+  assert!(text.contains("    %19: f16 = load uniforms.1.1\n    %20: f32 = cast %19\n"));
+  assert!(text.contains(
+    "    %5: f32 = swizzle %0, y\n    %6: vec2xf32 = construct %5, %1\n    \
+     %7: vec2xf32 = mul %6, %4\n"
+  ));
+
+  // Logical operations without calls are evaluated eagerly.
+  assert!(text.contains("    %16: bool = and %12, %15\n    %17: bool = not %16\n"));
+
+  // Only the branch that doesn't return reaches the merge block.
+  assert!(text.contains(
+    "    store pairs[%22], %28\n    br bb3\n  bb3:\n    %29: u32 = swizzle %0, y\n"
+  ));
+  // Constants are folded, except where the result is not finite.
+  assert!(text.contains(
+    "    %30: f32 = const -1.5\n    %31: f32 = const 0.0\n    %32: f32 = div %30, %31\n"
+  ));
+}
+
+const EXPECTED_IR: &str = "\
+struct Particle { position: vec3xf32, velocity: vec3xf32, mass: f32 }
+struct Uniforms { gravity: vec3xf32, dt: f32, steps: u32 }
+uniforms: Uniforms
+buffer(rw) particles: [Particle]
+
+func heavy(%0: Particle) -> bool {
+  bb0:
+    %1: f32 = field %0, 2
+    %2: f32 = const 1.0
+    %3: bool = gt %1, %2
+    ret %3
+}
+
+entrypoint(1d) step(%0: u32) {
+  var $0 p: Particle
+  var $1 n: u32
+  bb0:
+    %1: u32 = array_length particles
+    %2: bool = ge %0, %1
+    selection_merge bb2
+    br_if %2, bb2, bb1
+  bb1:
+    %3: Particle = load particles[%0]
+    %4: bool = call heavy(%3)
+    br bb2
+  bb2:
+    %5: bool = phi [%2, bb0], [%4, bb1]
+    selection_merge bb4
+    br_if %5, bb3, bb4
+  bb3:
+    ret
+  bb4:
+    %6: Particle = load particles[%0]
+    store $0, %6
+    %7: u32 = const 0
+    store $1, %7
+    br bb5
+  bb5:
+    loop_merge bb10, bb9
+    br bb6
+  bb6:
+    %8: u32 = load $1
+    %9: u32 = load uniforms.2
+    %10: bool = eq %8, %9
+    selection_merge bb8
+    br_if %10, bb7, bb8
+  bb7:
+    ret
+  bb8:
+    %11: vec3xf32 = load $0.1
+    %12: vec3xf32 = load uniforms.0
+    %13: f32 = load uniforms.1
+    %14: vec3xf32 = mul %12, %13
+    %15: vec3xf32 = add %11, %14
+    store $0.1, %15
+    %16: vec3xf32 = load $0.0
+    %17: vec2xf32 = swizzle %16, xz
+    %18: f32 = swizzle %17, x
+    store particles[%0].0.z, %18
+    %19: f32 = swizzle %17, y
+    store particles[%0].0.x, %19
+    %20: u32 = load $1
+    %21: u32 = const 1
+    %22: u32 = add %20, %21
+    store $1, %22
+    br bb9
+  bb9:
+    br bb5
+  bb10:
+    unreachable
+}
+";
@@ -0,0 +1,244 @@
+use crate::{
+  ir::{ IrOp, parse_ir, verify_ir },
+  model::LiteralModel,
+};
+
+fn verify_err(text: &str) -> Vec<String> {
+  verify_ir(&parse_ir(text).unwrap())
+    .unwrap_err()
+    .into_iter()
+    .map(|error| error.to_string())
+    .collect()
+}
+
+fn parse_err(text: &str) -> String {
+  parse_ir(text).unwrap_err().to_string()
+}
+
+#[test]
+fn test_verify() {
+  let text = "\
+    buffer(rw) counts: [u32]
+
+    func pick(%0: bool, %1: u32) -> u32 {
+      bb0:
+        selection_merge bb2
+        br_if %0, bb1, bb2
+      bb1:
+        %2: u32 = const 16
+        br bb2
+      bb2:
+        %3: u32 = phi [%1, bb0], [%2, bb1]
+        ret %3
+    }
+
+    entrypoint(1d) run(%0: u32) {
+      var $0 total: f32
+      bb0:
+        %1: f32 = const 0x7FC00001
+        store $0, %1
+        %2: bool = const true
+        %3: u32 = call pick(%2, %0)
+        store counts[%0], %3
+        ret
+    }
+  ";
+  let module = parse_ir(text).unwrap();
+  verify_ir(&module).unwrap();
+  assert_eq!(parse_ir(&module.to_string()).unwrap(), module);
+
+  // Floats that are not finite keep their bits.
+  let run = module.function("run").unwrap();
+  assert_eq!(
+    run.blocks()[0].insts()[0].to_string(),
+    "%1: f32 = const 0x7FC00001"
+  );
+  assert!(matches!(
+    run.blocks()[0].insts()[0].op(),
+    IrOp::Const(LiteralModel::F32(0x7FC0_0001))
+  ));
+}
+
+#[test]
+fn test_verify_errors() {
+  // Values must be defined once, where they dominate their uses.
+  assert_eq!(
+    verify_err("
+      func f(%0: bool) -> u32 {
+        bb0:
+          selection_merge bb2
+          br_if %0, bb1, bb2
+        bb1:
+          %1: u32 = const 1
+          br bb2
+        bb2:
+          %1: u32 = const 2
+          %2: u32 = add %1, %3
+          ret %1
+      }"),
+    vec![
+      "In `f`, bb2: Value %1 is defined more than once.",
+      "In `f`, bb2: Value %3 is not defined.",
+    ]
+  );
+  assert_eq!(
+    verify_err("
+      func f(%0: bool) -> u32 {
+        bb0:
+          selection_merge bb2
+          br_if %0, bb1, bb2
+        bb1:
+          %1: u32 = const 1
+          br bb2
+        bb2:
+          ret %1
+      }"),
+    vec!["In `f`, bb2: Value %1 is used where its definition does not dominate."]
+  );
+
+  // Phis need one value from each predecessor.
+  assert_eq!(
+    verify_err("
+      func f(%0: bool) -> u32 {
+        bb0:
+          %1: u32 = const 1
+          selection_merge bb2
+          br_if %0, bb1, bb2
+        bb1:
+          br bb2
+        bb2:
+          %2: u32 = phi [%1, bb0], [%0, bb3]
+          ret %2
+        bb3:
+          unreachable
+      }"),
+    vec![
+      "In `f`, bb2: Phi %2 needs one value from predecessor bb1, but has 0.",
+      "In `f`, bb2: Phi %2 has a value from bb3, which is not a predecessor.",
+    ]
+  );
+
+  // Branches and merge markers name blocks of the function.
+  assert_eq!(
+    verify_err("
+      entrypoint(1d) run(%0: u32) {
+        bb0:
+          loop_merge bb4, bb0
+          br bb1
+        bb1:
+          %1: bool = const false
+          selection_merge bb2
+          br bb0
+      }"),
+    vec![
+      "In `run`, bb0: Block bb4 does not exist.",
+      "In `run`, bb1: Block bb2 does not exist.",
+      "In `run`, bb1: The entry block cannot be branched to.",
+      "In `run`, bb1: A block with a merge marker must end in a branch.",
+    ]
+  );
+
+  // Operands must suit their operations.
+  assert_eq!(
+    verify_err("
+      struct Pair { tag: u32, value: vec2xf32 }
+      uniforms: Pair
+      buffer(r) pairs: [Pair]
+
+      func f(%0: u32) -> f32 {
+        var $0 v: vec2xf32
+        bb0:
+          %1: Pair = load pairs[%0]
+          %2: vec2xf32 = field %1, 1
+          %3: f32 = swizzle %2, z
+          %4: f32 = add %0, %2
+          store pairs[%0].0, %0
+          store uniforms.1.x, %3
+          store $0.y, %0
+          %5: u32 = field %1, 1
+          %6: Pair = construct %0, %0
+          %7: u32 = call f(%0)
+          %8: vec2xf32 = load $0.y
+          br_if %0, bb1, bb1
+        bb1:
+          ret %0
+      }"),
+    vec![
+      "In `f`, bb0: Swizzle is out of range for `vec2xf32`.",
+      "In `f`, bb0: Cannot apply `Add` to `u32` and `vec2xf32`.",
+      "In `f`, bb0: Buffer `pairs` is not writable.",
+      "In `f`, bb0: The uniforms are not writable.",
+      "In `f`, bb0: Cannot store %0 of type `u32` to `$0.y`, of type `f32`.",
+      "In `f`, bb0: Value %5 has type `u32`, but its instruction produces `vec2xf32`.",
+      "In `f`, bb0: The arguments do not match the fields of `Pair`.",
+      "In `f`, bb0: Value %7 has type `u32`, but its instruction produces `f32`.",
+      "In `f`, bb0: Value %8 has type `vec2xf32`, but its instruction produces `f32`.",
+      "In `f`, bb0: Branch condition %0 has type `u32`, not `bool`.",
+      "In `f`, bb1: Returned value %0 has type `u32`, but the function returns `f32`.",
+    ]
+  );
+
+  // Entrypoints take their invocation id.
+  assert_eq!(
+    verify_err("
+      entrypoint(2d) run(%0: u32) {
+        bb0:
+          ret
+      }"),
+    vec!["In `run`: An entrypoint takes only an invocation id of type `vec2xu32`."]
+  );
+}
+
+#[test]
+fn test_parse_errors() {
+  assert_eq!(
+    parse_err("struct A { a: B }"),
+    "Line 1: Unknown type `B`."
+  );
+  assert_eq!(
+    parse_err("buffer(x) a: [u32]"),
+    "Line 1: Unknown buffer mode `x`."
+  );
+  assert_eq!(
+    parse_err("
+      func f() {
+        bb0:
+          %0: u32 = const -1
+          ret
+      }"),
+    "Line 4: Invalid `u32` literal `-1`."
+  );
+  assert_eq!(
+    parse_err("
+      func f() {
+        bb1:
+          ret
+      }"),
+    "Line 3: Expected block `bb0`."
+  );
+  assert_eq!(
+    parse_err("
+      func f() {
+        bb0:
+          %0: u32 = frobnicate %1
+      }"),
+    "Line 4: Unknown instruction `frobnicate`."
+  );
+  assert_eq!(
+    parse_err("
+      func f() {
+        bb0:
+          %0: bool = const true
+          ret
+          ret
+      }"),
+    "Line 6: Expected a block label."
+  );
+  assert_eq!(
+    parse_err("
+      func f() {
+        bb0:
+          ret"),
+    "Line 4: Function `f` is not closed with `}`."
+  );
+}
@@ -1,3 +1,78 @@
 
 mod syntax;
 mod transform;
+mod model;
+mod reflect;
+mod host_gen;
+mod data;
+mod backend;
+mod interpret;
+mod ir;
+
+use std::path::PathBuf;
+use crate::{
+  data::Value,
+  model::{ LiteralModel, ShaderFileModelHandle },
+  transform::{
+    Diagnostic,
+    SessionConfig,
+    SessionConfigBuilder,
+    SyntaxIngester,
+    TargetCapabilities,
+  },
+};
+
+/**
+ * The session config of test files, checked for a target with the
+ * given capabilities.
+ */
+fn session_config(capabilities: TargetCapabilities) -> SessionConfig {
+  SessionConfigBuilder::new()
+    .project_root(PathBuf::from("/test"))
+    .capabilities(capabilities)
+    .build()
+}
+
+/**
+ * Check the contents of a shader file for a target with the given
+ * capabilities.
+ */
+fn check_source_with(contents: &str, capabilities: TargetCapabilities)
+  -> Result<ShaderFileModelHandle, Vec<Diagnostic>>
+{
+  SyntaxIngester::parse_shader_file(
+    &session_config(capabilities),
+    "test.dubgsl.shader",
+    contents,
+  )
+}
+
+/**
+ * Check the contents of a shader file for a target with every
+ * capability, which must succeed.
+ */
+fn check_source(contents: &str) -> ShaderFileModelHandle {
+  let all = TargetCapabilities { shader_f16: true, shader_int64: true };
+  match check_source_with(contents, all) {
+    Ok(model) => model,
+    Err(diagnostics) => panic!("Failed to check shader: {:?}", diagnostics),
+  }
+}
+
+/**
+ * A vector value of `f32` components.
+ */
+fn f32s(values: &[f32]) -> Value {
+  Value::Vector(values.iter().map(|&v| LiteralModel::new_f32(v)).collect())
+}
+
+/**
+ * A struct value of named members.
+ */
+fn fields(members: Vec<(&str, Value)>) -> Value {
+  Value::Struct(
+    members.into_iter()
+      .map(|(name, value)| (name.to_string(), value))
+      .collect()
+  )
+}
@@ -0,0 +1,2 @@
+mod test_literal_model;
+mod test_layout;
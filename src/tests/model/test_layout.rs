@@ -0,0 +1,110 @@
+use crate::{
+  model::{ LayoutRules, TypeLayout, TypeLayoutKind },
+  tests::check_source,
+};
+
+const LAYOUT_SHADER: &str = "
+  struct Particle {
+    position: vec3xf32,
+    mass: f32,
+    velocity: vec3xf32,
+    flags: u32,
+  }
+  struct Mixed {
+    a: f32,
+    b: vec2xf32,
+    c: [f32; 3],
+    d: mat3x3xf32,
+  }
+  struct Grid { width: u32, cells: [f32] }
+  struct Wide { half: vec3xf16, count: u64 }
+  @packed struct Record { tag: u32, position: vec3xf32, @align(8) id: u64, flag: f16 }
+  struct Sized { @size(8) a: u32, @align(16) b: f32, c: vec2xf32 }
+  uniforms { mixed: Mixed, scale: f32 }
+  buffer(r) particles: Particle;
+  buffer(rw) grid: Grid;
+  buffer(r) mixed: Mixed;
+  buffer(r) wide: Wide;
+  buffer(r) records: Record;
+  buffer(r) sized: Sized;
+";
+
+/**
+ * Get the (offset, padding) of each field of a struct layout.
+ */
+fn field_offsets(layout: &TypeLayout) -> Vec<(u32, u32)> {
+  let TypeLayoutKind::Struct(struct_layout) = layout.kind() else {
+    panic!("Expected a struct layout, got {:?}", layout);
+  };
+  struct_layout.fields().iter()
+    .map(|field| (field.offset(), field.padding()))
+    .collect()
+}
+
+/**
+ * Get the element layout of an array layout.
+ */
+fn array_elem(layout: &TypeLayout) -> &TypeLayout {
+  let TypeLayoutKind::Array(array_layout) = layout.kind() else {
+    panic!("Expected an array layout, got {:?}", layout);
+  };
+  array_layout.elem()
+}
+
+#[test]
+fn test_storage_layout() {
+  let model = check_source(LAYOUT_SHADER);
+  let buffers = model.buffers();
+
+  // A buffer of a sized type is a runtime-sized array of it.
+  let particles = buffers[0].layout().unwrap();
+  let particle = array_elem(&particles);
+  assert_eq!((particle.size(), particle.align()), (32, 16));
+  assert_eq!(field_offsets(particle), vec![(0, 0), (12, 0), (16, 0), (28, 0)]);
+  assert_eq!(particles.size_with_elements(10), Ok(320));
+
+  let grid = buffers[1].layout().unwrap();
+  assert_eq!(field_offsets(&grid), vec![(0, 0), (4, 0)]);
+  assert_eq!(grid.size_with_elements(3), Ok(16));
+
+  let mixed = buffers[2].layout().unwrap();
+  let mixed = array_elem(&mixed);
+  assert_eq!((mixed.size(), mixed.align()), (80, 16));
+  assert_eq!(field_offsets(mixed), vec![(0, 4), (8, 0), (16, 4), (32, 0)]);
+
+  let wide = buffers[3].layout().unwrap();
+  let wide = array_elem(&wide);
+  assert_eq!((wide.size(), wide.align()), (16, 8));
+  assert_eq!(field_offsets(wide), vec![(0, 2), (8, 0)]);
+
+  // Packed fields are aligned only to their scalars.
+  let records = buffers[4].layout().unwrap();
+  let record = array_elem(&records);
+  assert_eq!((record.size(), record.align()), (32, 8));
+  assert_eq!(field_offsets(record), vec![(0, 0), (4, 0), (16, 0), (24, 6)]);
+
+  // Padding includes the extra bytes of `@size`.
+  let sized = buffers[5].layout().unwrap();
+  let sized = array_elem(&sized);
+  assert_eq!((sized.size(), sized.align()), (32, 16));
+  assert_eq!(field_offsets(sized), vec![(0, 12), (16, 4), (24, 0)]);
+}
+
+#[test]
+fn test_uniform_layout() {
+  let model = check_source(LAYOUT_SHADER);
+  let uniforms = model.uniforms().unwrap().layout().unwrap();
+  assert_eq!((uniforms.size(), uniforms.align()), (128, 16));
+  assert_eq!(field_offsets(&uniforms), vec![(0, 0), (112, 12)]);
+
+  // Array strides and matrix columns are rounded up to 16 bytes.
+  let TypeLayoutKind::Struct(struct_layout) = uniforms.kind() else {
+    panic!("Expected a struct layout");
+  };
+  let mixed = struct_layout.fields()[0].layout();
+  assert_eq!(field_offsets(mixed), vec![(0, 4), (8, 0), (16, 0), (64, 0)]);
+  assert_eq!(mixed.size(), 112);
+
+  let storage = model.uniforms().unwrap().ty().layout(LayoutRules::Storage).unwrap();
+  assert_eq!(storage.size(), 96);
+}
@@ -0,0 +1,43 @@
+use crate::model::{ LiteralModel, ScalarNumericTypeModel };
+
+#[test]
+fn test_literal_casts() {
+  use ScalarNumericTypeModel::*;
+
+  // Sign changes reinterpret bits.
+  check_cast(LiteralModel::I32(-1), U32, LiteralModel::U32(0xFFFF_FFFF));
+  check_cast(LiteralModel::U32(0x8000_0000), I32, LiteralModel::I32(i32::MIN));
+
+  // Float to int truncates toward zero and saturates.
+  check_cast(LiteralModel::new_f32(-1.75), I32, LiteralModel::I32(-1));
+  check_cast(LiteralModel::new_f32(2.5), U32, LiteralModel::U32(2));
+  check_cast(LiteralModel::new_f32(-3.0), U32, LiteralModel::U32(0));
+  check_cast(LiteralModel::new_f32(3.0e10), I32, LiteralModel::I32(i32::MAX));
+  check_cast(LiteralModel::new_f32(f32::NAN), I32, LiteralModel::I32(0));
+
+  // Int to float rounds to nearest.
+  check_cast(LiteralModel::U32(16_777_217), F32, LiteralModel::new_f32(16_777_216.0));
+  check_cast(LiteralModel::I32(-7), F32, LiteralModel::new_f32(-7.0));
+
+  // 64-bit integers sign-extend and truncate.
+  check_cast(LiteralModel::I32(-2), U64, LiteralModel::U64(u64::MAX - 1));
+  check_cast(LiteralModel::U64(0x1_0000_0005), U32, LiteralModel::U32(5));
+  check_cast(LiteralModel::new_f32(-3.0e10), I64, LiteralModel::I64(-30_000_001_024));
+  check_cast(LiteralModel::I64(3), F16, LiteralModel::new_f16(3.0));
+
+  assert_eq!(LiteralModel::Bool(true).cast(I32), None);
+}
+
+fn check_cast(from: LiteralModel, to: ScalarNumericTypeModel, expected: LiteralModel) {
+  assert_eq!(from.cast(to), Some(expected), "{:?} as {:?}", from, to);
+}
+
+#[test]
+fn test_f16_literals() {
+  // `f16` literals hold the nearest `f16`, so equal `f16` values are
+  // equal literals.
+  assert_eq!(LiteralModel::new_f16(1.0 / 3.0), LiteralModel::new_f16(0.33325195));
+  assert_eq!(LiteralModel::new_f16(1.0 + 1.0e-4), LiteralModel::new_f16(1.0));
+  assert_eq!(LiteralModel::new_f16(65520.0), LiteralModel::new_f16(f32::INFINITY));
+  assert_eq!(LiteralModel::new_f16(65504.0), LiteralModel::F16(65504.0_f32.to_bits()));
+}
@@ -0,0 +1,126 @@
+use crate::{
+  model::{ BufferAccessMode, EntrypointDims },
+  reflect::JsonValue,
+  tests::check_source,
+};
+
+const REFLECTION_SHADER: &str = "
+  struct Particle { position: vec3xf32, mass: f32 }
+  struct Grid { width: u32, cells: [f32] }
+  uniforms { scale: f32, offset: vec2xf32 }
+  buffer(r) particles: Particle;
+  @group(1) @binding(2) buffer(rw) grid: Grid;
+  entrypoint(2d) step(point) {
+    let x = point.x;
+  }
+";
+
+#[test]
+fn test_reflection() {
+  let reflection = check_source(REFLECTION_SHADER).reflect();
+
+  let entrypoint = &reflection.entrypoints()[0];
+  assert_eq!(entrypoint.name(), "step");
+  assert_eq!(entrypoint.dims(), EntrypointDims::D2);
+  assert_eq!(entrypoint.workgroup_size(), [8, 8, 1]);
+
+  let grid = &reflection.buffers()[1];
+  assert_eq!(grid.mode(), BufferAccessMode::ReadWrite);
+  assert_eq!((grid.binding().group(), grid.binding().binding()), (1, 2));
+  let (element_ty, element_layout) = grid.element();
+  assert_eq!(element_ty.to_string(), "f32");
+  assert_eq!(element_layout.size(), 4);
+
+  let uniforms = reflection.uniforms().expect("Expected uniforms");
+  let offsets = uniforms.fields().iter()
+    .map(|field| (field.name(), field.offset()))
+    .collect::<Vec<_>>();
+  assert_eq!(offsets, vec![("scale", 0), ("offset", 8)]);
+}
+
+#[test]
+fn test_reflection_json() {
+  let model = check_source("
+    uniforms { scale: f32 }
+    @binding(3) buffer(w) out: f32;
+    entrypoint(1d) fill(i) {
+      let x = i;
+    }
+  ");
+  let expected = r#"{
+  "version": 1,
+  "entrypoints": [
+    {
+      "name": "fill",
+      "dims": 1,
+      "workgroup_size": [
+        64,
+        1,
+        1
+      ]
+    }
+  ],
+  "buffers": [
+    {
+      "name": "out",
+      "access": "write",
+      "group": 0,
+      "binding": 3,
+      "layout": {
+        "type": "[f32]",
+        "size": 0,
+        "align": 4,
+        "kind": "array",
+        "len": null,
+        "stride": 4,
+        "element": {
+          "type": "f32",
+          "size": 4,
+          "align": 4,
+          "kind": "scalar"
+        }
+      },
+      "element": {
+        "type": "f32",
+        "size": 4,
+        "align": 4,
+        "kind": "scalar"
+      }
+    }
+  ],
+  "uniforms": {
+    "group": 0,
+    "binding": 0,
+    "size": 16,
+    "align": 16,
+    "fields": [
+      {
+        "name": "scale",
+        "offset": 0,
+        "padding": 12,
+        "layout": {
+          "type": "f32",
+          "size": 4,
+          "align": 4,
+          "kind": "scalar"
+        }
+      }
+    ]
+  }
+}"#;
+  assert_eq!(format!("{:#}", model.reflect().to_json()), expected);
+}
+
+#[test]
+fn test_json_values() {
+  let value = JsonValue::object([
+    ("name", JsonValue::string("a \"quoted\"\tname\u{1}")),
+    ("len", JsonValue::from(None::<u32>)),
+    ("items", JsonValue::Array(vec![true.into(), 3u32.into()])),
+    ("empty", JsonValue::Object(Vec::new())),
+  ]);
+  assert_eq!(
+    value.to_string(),
+    r#"{"name":"a \"quoted\"\tname\u0001","len":null,"items":[true,3],"empty":{}}"#
+  );
+}
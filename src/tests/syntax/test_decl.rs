@@ -28,10 +28,20 @@ fn test_declarations() {
   }");
 
   test_decl_str("struct Foo { a: int, b: bool }");
+  test_decl_str("struct Table { len: u32, entries: [ [f32; 4] ; 0x10 ] }");
+  test_decl_str("struct Particles { count: u32, items: [Particle] }");
+  test_decl_str("@packed struct Header { magic: u32, @align(8) @size(16) len: u64 }");
+  test_decl_str("@packed
+    struct Header {
+      @align( 0x10 )
+      magic: u32,
+    }");
 
   test_decl_str("buffer(r) B: int;");
   test_decl_str("buffer(rw  ) B: int;");
   test_decl_str("buffer(w) elevations: Terrain::Elevations;");
+  test_decl_str("buffer(r) birds: [BirdInfo];");
+  test_decl_str("@group(1) @binding(0x2) buffer(rw) birds: [BirdInfo];");
 
   test_decl_str("entrypoint(2d) gen_terrain(point) {
     let x = point.x;
@@ -52,6 +62,11 @@ fn test_declarations() {
     b: bool,
     c: vec3xu32,
   }");
+  test_decl_str("@group(0) @binding(3) uniforms { a: f32 }");
+  test_decl_str("uniforms {
+    @align(16) a: f32,
+    @size(8) b: u32,
+  }");
 }
 
 fn test_decl_str(s: &str) {
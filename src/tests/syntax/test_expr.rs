@@ -22,6 +22,13 @@ fn test_terminal_exprs() {
   test_terminal_expr_str("hello");
   test_terminal_expr_str("55");
   test_terminal_expr_str(" (0x30  )");
+  test_terminal_expr_str("1.5");
+  test_terminal_expr_str("Position { x: 0, y: 0 }");
+  test_terminal_expr_str("Color {
+    r: (1.0),
+    g: (0.0),
+    b: (0.5),
+  }");
   test_terminal_expr_str("
   (
       (  - 0b100000000001   )
@@ -51,6 +58,11 @@ fn test_primary_exprs() {
   test_primary_expr_str("hello.there");
   test_primary_expr_str("rgb.0");
   test_primary_expr_str("blend(rgb.0, rgb.1)");
+  test_primary_expr_str("birds[i]");
+  test_primary_expr_str("birds [ i ].position.x");
+  test_primary_expr_str("grid[x][y](3)[0]");
+  test_primary_expr_str("vec3xf32(1.0, 0.0, 0.0)");
+  test_primary_expr_str("Outer { inner: Inner { v: 1 } }.inner.v");
 }
 
 fn test_primary_expr_str(s: &str) {
@@ -240,3 +252,30 @@ fn full_logical_expr_parser<'a>()
     logical_expr_parser(base_expr)
   })
 }
+
+#[test]
+fn test_cast_exprs() {
+  test_expr_str("i as f32");
+  test_expr_str("v as vec2xi32");
+  test_expr_str("-x as u32 as f32");
+  test_expr_str("(a + b) as f32 * 0.5");
+
+  // Casts bind tighter than binary operators.
+  let parsed = Expression::parser::<Default>().parse("a + b as f32 * c");
+  let Ok(Expression::Add(add)) = parsed.into_result() else {
+    panic!("Expected add expression");
+  };
+  assert!(matches!(*add.lhs, Expression::Name(_)));
+  let Expression::Mul(mul) = *add.rhs else {
+    panic!("Expected mul expression");
+  };
+  assert!(matches!(*mul.lhs, Expression::Cast(_)));
+}
+
+fn test_expr_str(s: &str) {
+  let parsed = Expression::parser::<Default>().parse(s);
+  match parsed.into_result() {
+    Ok(_) => {},
+    Err(e) => panic!("Failed to parse: {} - {:?}", s, e),
+  }
+}
@@ -15,6 +15,9 @@ fn test_files() {
     }
     func my_func() {
       ret 5;
+    }
+    test my_func_returns_five {
+      assert(my_func() == 5);
     }");
 
   test_shader_file_str("
@@ -26,6 +29,9 @@ fn test_files() {
     }
     func my_func() {
       ret 5;
+    }
+    test my_func_returns_five {
+      assert(my_func() == 5);
     }");
 }
 
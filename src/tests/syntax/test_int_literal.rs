@@ -2,7 +2,11 @@ use chumsky::{
   Parser,
   extra::Default,
 };
-use crate::syntax::expression::IntLiteralExpr;
+use crate::syntax::expression::{
+  IntLiteralExpr,
+  IntLiteralExprType,
+  IntLiteralValue,
+};
 
 #[test]
 fn test_int_literals() {
@@ -15,6 +19,43 @@ fn test_int_literals() {
 3309");
 }
 
+#[test]
+fn test_int_literal_values() {
+  use IntLiteralExprType::{ I32, I64, U32, U64 };
+  check_int_literal_value("55", I32, Some(IntLiteralValue::I32(55)));
+  check_int_literal_value("55", U32, Some(IntLiteralValue::U32(55)));
+  check_int_literal_value("0d99", I32, Some(IntLiteralValue::I32(99)));
+  check_int_literal_value("0x3F", I32, Some(IntLiteralValue::I32(63)));
+  check_int_literal_value("0b101", I32, Some(IntLiteralValue::I32(5)));
+  check_int_literal_value("0o17", I32, Some(IntLiteralValue::I32(15)));
+  check_int_literal_value("7_u32", I32, Some(IntLiteralValue::U32(7)));
+  check_int_literal_value("0xFFFF_FFFF", U32, Some(IntLiteralValue::U32(u32::MAX)));
+  check_int_literal_value("0xFFFF_FFFF", I32, None);
+  check_int_literal_value("0xFFFF_FFFF_F", U32, None);
+  check_int_literal_value("-2147483648", I32, Some(IntLiteralValue::I32(i32::MIN)));
+  check_int_literal_value("2147483648_i32", U32, None);
+  check_int_literal_value("-1_u32", I32, None);
+  check_int_literal_value("-0_u32", I32, Some(IntLiteralValue::U32(0)));
+  check_int_literal_value("0xFFFF_FFFF_F", U64, Some(IntLiteralValue::U64(0xF_FFFF_FFFF)));
+  check_int_literal_value("18446744073709551615_u64", I32, Some(IntLiteralValue::U64(u64::MAX)));
+  check_int_literal_value("18446744073709551616", U64, None);
+  check_int_literal_value("-9223372036854775808", I64, Some(IntLiteralValue::I64(i64::MIN)));
+  check_int_literal_value("9223372036854775808_i64", I32, None);
+}
+
+fn check_int_literal_value(
+  str: &'static str,
+  context_ty: IntLiteralExprType,
+  expected: Option<IntLiteralValue>,
+) {
+  let parsed = IntLiteralExpr::parser::<Default>().parse(str);
+  let literal = match parsed.into_result() {
+    Ok(literal) => literal,
+    Err(e) => panic!("Failed to parse: {} - {:?}", str, e),
+  };
+  assert_eq!(literal.value(context_ty).ok(), expected, "Value of {}", str);
+}
+
 fn check_int_literal<'a>(str: &'static str) {
   let parsed = IntLiteralExpr::parser::<Default>().parse(str);
   match parsed.into_result() {
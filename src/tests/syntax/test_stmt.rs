@@ -27,6 +27,8 @@ fn test_statements() {
   test_stmt_str("if 3 { let x = 3; } else { let y = 4; }");
   test_stmt_str("if 3 { let x = 3; } else { }");
   test_stmt_str("if x.bang == 3 {} else { let y = 4; }");
+  test_stmt_str("if x == y {} else { let y = 4; }");
+  test_stmt_str("if done { ret; }");
   test_stmt_str("if foo(bar >> 9, 3, abc.def(33)) == abc.q { let x = 3; }");
   test_stmt_str("loop  {
     let a = 9;
@@ -39,6 +41,11 @@ fn test_statements() {
     ");
 
   test_stmt_str("mutate x = y + 9 ;");
+  test_stmt_str("mutate birds[i].position.x = 0;");
+  test_stmt_str("mutate birds[i + 1 * j].color = foo(bar) ;");
+
+  test_stmt_str("assert(x == 3);");
+  test_stmt_str("assert ( !foo(bar) ) ;");
 }
 
 fn test_exec_ret_expr(s: &str) {
@@ -1,2 +1,3 @@
 
 mod test_shader_file;
+mod test_type_checker;
@@ -0,0 +1,672 @@
+use crate::{
+  model::ShaderFileModelHandle,
+  tests::{ check_source, check_source_with },
+  transform::{ Diagnostic, TargetCapabilities },
+};
+
+fn check_shader(contents: &str) -> Result<ShaderFileModelHandle, Vec<Diagnostic>> {
+  check_source_with(contents, TargetCapabilities::default())
+}
+
+fn check_ok(contents: &str) -> ShaderFileModelHandle {
+  match check_shader(contents) {
+    Ok(model) => model,
+    Err(e) => panic!("Failed to check: {} - {:?}", contents, e),
+  }
+}
+
+fn check_err(contents: &str, expected: &str) {
+  match check_shader(contents) {
+    Ok(_) => panic!("Expected error `{}`: {}", expected, contents),
+    Err(diagnostics) => {
+      if !diagnostics.iter().any(|d| d.message.contains(expected)) {
+        panic!("Expected error `{}`, got {:?}", expected, diagnostics);
+      }
+    },
+  }
+}
+
+const INDEX_PRELUDE: &str = "
+  struct Point { x: i32, y: i32 }
+  buffer(r) points: Point;
+  buffer(w) out: i32;
+";
+
+#[test]
+fn test_index_exprs() {
+  check_ok(&format!("{}
+    entrypoint(1d) sum(i) {{
+      let a = points[i].x + points[i + 1].y;
+      mutate out[i] = a;
+      mutate out[0] = points[5_i32].x;
+    }}", INDEX_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate out[i] = points(i).x;
+    }}", INDEX_PRELUDE), "cannot be called");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate out[1.0] = 0;
+    }}", INDEX_PRELUDE), "Index must be an integer");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let p = points[i];
+      mutate out[i] = p[0];
+    }}", INDEX_PRELUDE), "expected an array or buffer");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate out[i] = out[i];
+    }}", INDEX_PRELUDE), "write-only buffer");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate points[i].x = 0;
+    }}", INDEX_PRELUDE), "read-only buffer");
+
+  // Constant indices of sized arrays must be in bounds.
+  const ARRAY_PRELUDE: &str = "
+    struct S { a: [u32; 4] }
+    buffer(rw) s: S;
+  ";
+  check_ok(&format!("{}
+    entrypoint(1d) run(i) {{
+      mutate s[i].a[3] = s[i].a[i + 7];
+    }}", ARRAY_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate s[i].a[0] = s[i].a[7];
+    }}", ARRAY_PRELUDE), "Index 7 is out of bounds for type `[u32; 4]`.");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate s[i].a[2 * 2] = 0;
+    }}", ARRAY_PRELUDE), "Index 4 is out of bounds for type `[u32; 4]`.");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate s[i].a[0] = s[i].a[1_i32 - 2];
+    }}", ARRAY_PRELUDE), "Index -1 is out of bounds for type `[u32; 4]`.");
+}
+
+#[test]
+fn test_func_returns() {
+  // A function with a return type returns on every path, which ends in
+  // a `ret`, an `if` whose branches both return, or a loop.
+  check_ok("
+    func sign(x: i32) -> i32 {
+      if x < 0 { ret -1; } else { if x > 0 { ret 1; } else { ret 0; } }
+    }
+    func forever() -> u32 {
+      loop { }
+    }
+    func nothing() { }
+  ");
+
+  check_err("
+    func f() -> u32 { }
+  ", "Function `f` may end without returning a value.");
+
+  check_err("
+    func f(x: i32) -> i32 {
+      if x > 0 { ret 1; }
+    }
+  ", "Function `f` may end without returning a value.");
+
+  check_err("
+    func f(x: i32) -> i32 {
+      loop { if x > 0 { ret 1; } }
+      let y = x;
+    }
+  ", "Function `f` may end without returning a value.");
+}
+
+#[test]
+fn test_mismatched_types() {
+  check_err("
+    struct Point { x: i32, y: i32 }
+    buffer(rw) points: Point;
+    entrypoint(1d) bad(i) {
+      mutate points[i].x = 1.0;
+    }", "expected `i32`, found `f32`");
+
+  check_err("
+    entrypoint(1d) bad(i) {
+      let a = 3;
+      mutate a = 4;
+    }", "immutable binding");
+
+  check_err("
+    struct A { b: B }
+    struct B { a: A }", "contains itself");
+}
+
+const STRUCT_PRELUDE: &str = "
+  struct Position { x: u32, y: u32 }
+  struct Color { r: f32, g: f32, b: f32 }
+  buffer(w) positions: Position;
+  buffer(w) colors: vec4xf32;
+";
+
+#[test]
+fn test_construct_exprs() {
+  check_ok(&format!("{}
+    entrypoint(1d) init(i) {{
+      mutate positions[i] = Position {{ y: i, x: 0 }};
+      let c = Color {{ r: 1.0, g: 0.0, b: 0.0 }};
+      let rgb = vec3xf32(c.r, c.g, c.b);
+      mutate colors[i] = vec4xf32(rgb, 1.0);
+      mutate colors[i + 1] = vec4xf32(vec2xf32(0.0, 0.5), c.b, 1.0);
+    }}", STRUCT_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate positions[i] = Position {{ x: 0 }};
+    }}", STRUCT_PRELUDE), "Missing field `y`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate positions[i] = Position {{ x: 0, x: 1, y: 2 }};
+    }}", STRUCT_PRELUDE), "given more than once");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate positions[i] = Position {{ x: 0, y: 1, z: 2 }};
+    }}", STRUCT_PRELUDE), "No field `z`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate positions[i] = Position {{ x: 0, y: 1.0 }};
+    }}", STRUCT_PRELUDE), "expected `u32`, found `f32`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate colors[i] = vec4xf32(1.0, 0.0, 0.0);
+    }}", STRUCT_PRELUDE), "needs 4 components, but 3");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate colors[i] = vec4xf32(1, 0, 0, 1);
+    }}", STRUCT_PRELUDE), "Cannot construct `vec4xf32`");
+}
+
+#[test]
+fn test_swizzle_exprs() {
+  check_ok("
+    buffer(rw) colors: vec4xf32;
+    buffer(w) sums: f32;
+    entrypoint(2d) swizzle(id) {
+      let i = id.x + id.1 * 64;
+      let c = colors[i];
+      let bgr = c.zyx;
+      var rg = c.xy;
+      mutate rg.y = c.w;
+      mutate sums[i] = rg.x + bgr.0 + c.wzyx.w;
+      mutate colors[i] = vec4xf32(bgr, 1.0);
+      mutate colors[i].xz = vec2xf32(c.w, c.y);
+      mutate colors[i].wzyx = c;
+    }");
+
+  check_err("
+    buffer(rw) colors: vec4xf32;
+    entrypoint(1d) bad(i) {
+      mutate colors[i].xx = vec2xf32(1.0, 0.0);
+    }", "repeated components");
+
+  check_err("
+    buffer(rw) coords: vec2xf32;
+    entrypoint(1d) bad(i) {
+      mutate coords[i].x = coords[i].z;
+    }", "out of range for type `vec2xf32`");
+
+  check_err("
+    buffer(rw) coords: vec2xf32;
+    entrypoint(1d) bad(i) {
+      mutate coords[i].x = coords[i].xq;
+    }", "expected a swizzle");
+
+  check_err("
+    buffer(rw) coords: vec2xf32;
+    entrypoint(1d) bad(i) {
+      mutate coords[i] = coords[i].yxy;
+    }", "expected `vec2xf32`, found `vec3xf32`");
+}
+
+#[test]
+fn test_cast_exprs() {
+  check_ok("
+    buffer(r) counts: u32;
+    buffer(w) means: vec2xf32;
+    entrypoint(2d) average(id) {
+      let i = id.x;
+      let n = counts[i] as f32;
+      let scaled = id as vec2xf32 * n;
+      mutate means[i] = vec2xf32(n, (counts[i + 1] as i32 - 1) as f32);
+    }");
+
+  check_err("
+    buffer(w) out: vec2xf32;
+    entrypoint(3d) bad(id) {
+      mutate out[0] = id as vec2xf32;
+    }", "Cannot cast a value of type `vec3xu32` to `vec2xf32`");
+
+  check_err("
+    buffer(w) out: u32;
+    entrypoint(1d) bad(i) {
+      mutate out[i] = (i == 0) as u32;
+    }", "Cannot cast a value of type `bool`");
+
+  check_err("
+    struct Point { x: f32 }
+    buffer(w) out: u32;
+    entrypoint(1d) bad(i) {
+      mutate out[i] = i as Point;
+    }", "expected a numeric scalar or vector type");
+}
+
+#[test]
+fn test_int_literal_ranges() {
+  check_ok(&format!("{}
+    entrypoint(1d) literals(i) {{
+      if 0 < i {{ mutate out[i] = -2147483648; }}
+      let mask = 0xFFFF_FFFF & i;
+      let shifted = 1 << (i % 32);
+    }}", INDEX_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let x = 0xFFFF_FFFF_F;
+    }}", INDEX_PRELUDE), "`0xFFFF_FFFF_F` is out of range for `i32`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let x = -1_u32;
+    }}", INDEX_PRELUDE), "`-1_u32` is out of range for `u32`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let x = i + 0x1_0000_0000;
+    }}", INDEX_PRELUDE), "out of range for `u32`");
+}
+
+const ARRAY_PRELUDE: &str = "
+  struct Table { weights: [f32; 4], scale: f32 }
+  struct Grid { width: u32, cells: [f32] }
+  buffer(r) tables: Table;
+  buffer(rw) grid: Grid;
+  buffer(w) sums: [f32];
+";
+
+#[test]
+fn test_array_types() {
+  check_ok(&format!("{}
+    entrypoint(1d) sum(i) {{
+      let table = tables[i];
+      var total = 0.0;
+      var j = 0_u32;
+      loop {{
+        if j == table.weights.length {{ ret; }}
+        mutate total = total + table.weights[j] * grid.cells[i * 4 + j];
+        mutate j = j + 1;
+      }}
+      mutate sums[i] = total * table.scale;
+      mutate grid.cells[i] = grid.width as f32;
+      mutate sums[sums.length - 1] = 0.0;
+    }}", ARRAY_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate sums[i] = sums[0];
+    }}", ARRAY_PRELUDE), "write-only buffer `sums`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let cells = grid.cells;
+    }}", ARRAY_PRELUDE), "runtime-sized type `[f32]`");
+
+  check_err("
+    struct Bad { cells: [f32], width: u32 }
+  ", "last member of a buffer type");
+
+  check_err("
+    struct Grid { width: u32, cells: [f32] }
+    struct Outer { grid: Grid }
+  ", "last member of a buffer type");
+
+  check_err("
+    func f(a: [u32]) {}
+  ", "last member of a buffer type");
+
+  check_err("
+    struct Bad { values: [u32; 0] }
+  ", "Array length `0` must be a positive `u32`");
+}
+
+const MATRIX_PRELUDE: &str = "
+  uniforms {
+    model: mat4x4xf32,
+    project: mat3x4xf32,
+  }
+  struct Particle { position: vec4xf32, normal: vec3xf32 }
+  buffer(r) particles: Particle;
+  buffer(w) out: vec4xf32;
+  buffer(w) rotations: mat2x2xf32;
+";
+
+#[test]
+fn test_matrix_types() {
+  check_ok(&format!("{}
+    entrypoint(1d) transform(i) {{
+      let p = particles[i];
+      let world = uniforms.model * p.position;
+      let back = world * uniforms.model;
+      let projected = uniforms.project * p.normal;
+      let combined = uniforms.model * uniforms.model + uniforms.model;
+      mutate out[i] = (combined * 0.5) * (projected + back);
+    }}", MATRIX_PRELUDE));
+
+  check_ok(&format!("{}
+    func compose(a: mat2x3xf32, b: mat4x2xf32) -> mat4x3xf32 {{
+      ret a * b;
+    }}", MATRIX_PRELUDE));
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      mutate out[i] = uniforms.model * particles[i].normal;
+    }}", MATRIX_PRELUDE), "types `mat4x4xf32` and `vec3xf32`");
+
+  check_err(&format!("{}
+    entrypoint(1d) bad(i) {{
+      let m = uniforms.project * uniforms.project;
+    }}", MATRIX_PRELUDE), "types `mat3x4xf32` and `mat3x4xf32`");
+
+  check_err("
+    struct Bad { m: mat4x5xf32 }
+  ", "Unknown type `mat4x5xf32`");
+}
+
+const EXTENDED_SCALAR_SHADER: &str = "
+  struct Sample { value: f16, weights: vec4xf16, count: u64 }
+  buffer(r) samples: Sample;
+  buffer(w) out: vec2xf16;
+  buffer(rw) totals: i64;
+  entrypoint(1d) reduce(i) {
+    let s = samples[i];
+    let scaled = s.weights * 0.5 + vec4xf16(s.value, 1.0, 2.0, 3.0);
+    mutate out[i] = scaled.xy;
+    mutate totals[i] = totals[i] + (s.count as i64) * -2 + 0x1_0000_0000;
+    mutate totals[0] = 9_223_372_036_854_775_807_i64;
+  }
+";
+
+#[test]
+fn test_extended_scalars() {
+  check_source(EXTENDED_SCALAR_SHADER);
+
+  check_err(EXTENDED_SCALAR_SHADER,
+    "Type `f16` requires the `shader_f16` capability");
+
+  let no_int64 = TargetCapabilities { shader_f16: true, shader_int64: false };
+  let diagnostics = check_source_with(EXTENDED_SCALAR_SHADER, no_int64)
+    .expect_err("Expected int64 errors");
+  assert!(
+    diagnostics.iter().all(|d| d.message.contains("`shader_int64`")),
+    "Expected only int64 errors, got {:?}", diagnostics
+  );
+
+  check_err("
+    entrypoint(1d) bad(i) {
+      let x = i as u64;
+    }", "Type `u64` requires the `shader_int64` capability");
+}
+
+#[test]
+fn test_host_shareable_types() {
+  check_err("
+    struct Flagged { value: f32, flag: bool }
+    buffer(r) flagged: Flagged;
+  ", "Type `bool` cannot be shared with the host");
+
+  check_err("
+    uniforms { enabled: bool }
+  ", "Uniforms cannot be laid out");
+}
+
+#[test]
+fn test_layout_sizes() {
+  check_err("
+    struct T { a: [vec4xf32; 268435456] }
+    buffer(r) t: T;
+  ", "Type `[vec4xf32; 268435456]` is too large; sizes must fit in a `u32`.");
+
+  check_err("
+    buffer(r) t: [[u32; 65536]; 65536];
+  ", "Buffer `t` cannot be laid out: Type `[[u32; 65536]; 65536]` is too large");
+
+  check_err("
+    uniforms { a: u32, b: [f32; 268435456] }
+  ", "Uniforms cannot be laid out: Type `[f32; 268435456]` is too large");
+}
+
+#[test]
+fn test_layout_attributes() {
+  check_ok("
+    @packed struct Header { magic: u32, @align(8) len: u32, @size(8) kind: u32 }
+    struct Padded { @align(32) @size(12) a: vec2xf32 }
+  ");
+
+  check_err("
+    struct Bad { @align(3) a: u32 }
+  ", "`@align` on field `a` in struct `Bad` must be a power of two");
+
+  check_err("
+    struct Bad { @align(2) a: vec4xf32 }
+  ", "`Bad::a` has `@align(2)`, but must be aligned to a multiple of 16");
+
+  check_err("
+    @packed struct Bad { @align(2) a: vec4xf32 }
+  ", "must be aligned to a multiple of 4");
+
+  check_err("
+    struct Bad { @size(2) a: u32 }
+  ", "needs at least 4 bytes");
+
+  check_err("
+    struct Bad { @align(2147483648) a: u32 }
+  ", "`@align` on field `a` in struct `Bad` must be at most 65536");
+
+  check_err("
+    struct Bad { @size(4294967295) a: u32, b: u32 }
+  ", "Type `Bad` is too large");
+
+  check_err("
+    struct Bad { @size(4294967295) a: u32 }
+  ", "Type `Bad` is too large");
+
+  check_err("
+    struct Bad { @offset(2) a: u32 }
+  ", "Unknown attribute `@offset` on field `a` in struct `Bad`");
+
+  check_err("
+    @packed(1) struct Bad { a: u32 }
+  ", "Attribute `@packed` on struct `Bad` takes no argument");
+
+  // Uniform rules require arrays to be 16-byte aligned.
+  check_err("
+    uniforms { scale: f32, @align(8) weights: [f32; 2] }
+  ", "`Uniforms::weights` has `@align(8)`, but must be aligned to a multiple of 16");
+}
+
+#[test]
+fn test_resource_bindings() {
+  let model = check_ok("
+    struct Bird { position: vec2xf32 }
+    buffer(r) birds: Bird;
+    @group(0) @binding(0) buffer(w) out: f32;
+    @group(1) buffer(rw) scratch: u32;
+    @binding(2) buffer(r) weights: f32;
+    @group(1) @binding(0) uniforms { scale: f32 }
+  ");
+  let bindings = model.buffers().iter()
+    .map(|buffer| {
+      let binding = buffer.binding();
+      (buffer.name(), binding.group(), binding.binding())
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(bindings, vec![
+    ("birds", 0, 1),
+    ("out", 0, 0),
+    ("scratch", 1, 1),
+    ("weights", 0, 2),
+  ]);
+  let uniforms = model.uniforms().expect("Expected uniforms").binding();
+  assert_eq!((uniforms.group(), uniforms.binding()), (1, 0));
+
+  // Undeclared bindings do not depend on where the uniforms appear.
+  let model = check_ok("
+    buffer(r) a: f32;
+    uniforms { scale: f32 }
+    buffer(r) b: f32;
+  ");
+  let uniforms = model.uniforms().expect("Expected uniforms").binding();
+  assert_eq!((uniforms.group(), uniforms.binding()), (0, 0));
+  let bindings = model.buffers().iter()
+    .map(|buffer| buffer.binding().binding())
+    .collect::<Vec<_>>();
+  assert_eq!(bindings, vec![1, 2]);
+
+  check_err("
+    @binding(1) buffer(r) a: f32;
+    @group(0) @binding(1) buffer(r) b: f32;
+  ", "Binding `@group(0) @binding(1)` of buffer `b` is already used by buffer `a`");
+
+  check_err("
+    @group(2) @binding(3) uniforms { scale: f32 }
+    @group(2) @binding(3) buffer(r) a: f32;
+  ", "of buffer `a` is already used by uniforms");
+
+  check_err("
+    @binding(1) @binding(2) buffer(r) a: f32;
+  ", "Duplicate attribute `@binding` on buffer `a`");
+
+  check_err("
+    @group buffer(r) a: f32;
+  ", "Attribute `@group` on buffer `a` needs an argument");
+
+  check_err("
+    @align(4) uniforms { scale: f32 }
+  ", "Unknown attribute `@align` on uniforms");
+}
+
+#[test]
+fn test_test_decls() {
+  let model = check_ok("
+    func square(x: i32) -> i32 { ret x * x; }
+    test square_of_three {
+      let x = square(3);
+      assert(x == 9);
+    }
+    test square {
+      assert(square(-2) > 0);
+    }
+  ");
+  let names = model.tests().iter().map(|test| test.name()).collect::<Vec<_>>();
+  assert_eq!(names, vec!["square_of_three", "square"]);
+
+  check_err("
+    entrypoint(1d) run(i) {
+      assert(i < 64);
+    }
+  ", "`assert` is only allowed in tests");
+
+  check_err("
+    test bad {
+      assert(1);
+    }
+  ", "Mismatched types in assertion: expected `bool`");
+
+  check_err("
+    test twice { ret; }
+    test twice { ret; }
+  ", "Duplicate test `twice`");
+}
+
+#[test]
+fn test_const_exprs() {
+  // Operations on constants are folded, wrapping as they would at
+  // runtime, and constants can give array lengths and attributes.
+  let model = check_ok("
+    struct Table { @align(4 * 4) weights: [f32; (1 << 4) * 3] }
+    buffer(r) tables: Table;
+    buffer(w) out: i32;
+    entrypoint(1d) run(i) {
+      mutate out[i] = (7 - 10) / 2 + -(1 << 30) * 4 + (2.9 as i32);
+    }
+  ");
+  let debug = format!("{:?}", model);
+  assert!(debug.contains("len: Some(48)"));
+  assert!(debug.contains("align: Some(16)"));
+  assert!(debug.contains("Literal(I32(1))"));
+  assert!(!debug.contains("Binary"));
+
+  check_err("
+    entrypoint(1d) run(i) {
+      let a = i + 1 / (2 - 2);
+    }
+  ", "Integer division by zero in a constant expression.");
+
+  check_err("
+    entrypoint(1d) run(i) {
+      let a = i + (1_u32 << 32);
+    }
+  ", "Cannot shift a 32-bit integer by 32 in a constant expression.");
+
+  check_err("
+    struct Bad { values: [u32; 2 - 3 + 1] }
+  ", "Array length `2 - 3 + 1` must be a positive `u32`");
+
+  check_err("
+    struct Bad { values: [u32; 1.0 / 0.0] }
+  ", "Expression `1.0 / 0.0` is not a constant.");
+
+  check_err("
+    struct Bad { @align(1 << 40) a: u32 }
+  ", "Cannot shift a 32-bit integer by 40 in a constant expression.");
+}
+
+#[test]
+fn test_workgroup_sizes() {
+  // Constants can size an entrypoint's workgroups along the axes of its
+  // id, and other axes keep their default size.
+  let model = check_ok("
+    entrypoint(1d) wide(i) {}
+    @workgroup_size_x(4 * 8) entrypoint(1d) narrow(i) {}
+    @workgroup_size_y(1 << 4) entrypoint(2d) tall(id) {}
+    @workgroup_size_x(2) @workgroup_size_y(2) @workgroup_size_z(16 / 2)
+    entrypoint(3d) deep(id) {}
+  ");
+  let sizes = model.entrypoints.iter()
+    .map(|entrypoint| entrypoint.workgroup_size())
+    .collect::<Vec<_>>();
+  assert_eq!(sizes, vec![[64, 1, 1], [32, 1, 1], [8, 16, 1], [2, 2, 8]]);
+
+  check_err("
+    @workgroup_size_y(2) entrypoint(1d) run(i) {}
+  ", "Unknown attribute `@workgroup_size_y` on entrypoint `run`.");
+
+  check_err("
+    @workgroup_size_x(2 - 2) entrypoint(1d) run(i) {}
+  ", "`@workgroup_size_x` on entrypoint `run` must be positive.");
+
+  check_err("
+    @workgroup_size_x(32) @workgroup_size_y(16) entrypoint(2d) run(id) {}
+  ", "The workgroups of entrypoint `run` have 32x16x1 invocations, more than the 256 allowed.");
+
+  check_err("
+    @workgroup_size_x(1 / 0) entrypoint(1d) run(i) {}
+  ", "Integer division by zero in a constant expression.");
+}
@@ -0,0 +1,23 @@
+use std::fmt;
+
+/**
+ * A diagnostic reported by a transform.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  /** The human-readable description of the problem. */
+  pub message: String,
+}
+impl Diagnostic {
+  /**
+   * Create a new diagnostic.
+   */
+  pub fn new(message: impl Into<String>) -> Self {
+    Diagnostic { message: message.into() }
+  }
+}
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
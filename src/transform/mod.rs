@@ -1,8 +1,11 @@
 
 mod syntax_ingester;
+mod type_checker;
 mod session_config;
+mod diagnostic;
 
 pub use self::{
   syntax_ingester::SyntaxIngester,
-  session_config::{ SessionConfig, SessionConfigBuilder },
+  session_config::{ SessionConfig, SessionConfigBuilder, TargetCapabilities },
+  diagnostic::Diagnostic,
 };
@@ -7,6 +7,22 @@ use std::path::PathBuf;
 pub struct SessionConfig {
   /** The project root directory. */
   pub project_root: PathBuf,
+
+  /** The optional features of the compile target. */
+  pub capabilities: TargetCapabilities,
+}
+
+/**
+ * Optional features of the compile target.  Types that need a feature
+ * the target lacks are reported as errors.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TargetCapabilities {
+  /** Whether `f16` scalars, vectors and matrices are supported. */
+  pub shader_f16: bool,
+
+  /** Whether `i64` and `u64` scalars and vectors are supported. */
+  pub shader_int64: bool,
 }
 
 
@@ -15,13 +31,17 @@ pub struct SessionConfig {
  */
 pub struct SessionConfigBuilder {
   project_root: Option<PathBuf>,
+  capabilities: TargetCapabilities,
 }
 impl SessionConfigBuilder {
   /**
    * Create a new session configuration builder.
    */
   pub fn new() -> Self {
-    SessionConfigBuilder { project_root: None }
+    SessionConfigBuilder {
+      project_root: None,
+      capabilities: TargetCapabilities::default(),
+    }
   }
 
   /**
@@ -32,12 +52,21 @@ impl SessionConfigBuilder {
     self
   }
 
+  /**
+   * Set the capabilities of the compile target.
+   */
+  pub fn capabilities(mut self, capabilities: TargetCapabilities) -> Self {
+    self.capabilities = capabilities;
+    self
+  }
+
   /**
    * Build the session configuration.
    */
   pub fn build(self) -> SessionConfig {
     SessionConfig {
       project_root: self.project_root.expect("project_root is required"),
+      capabilities: self.capabilities,
     }
   }
 }
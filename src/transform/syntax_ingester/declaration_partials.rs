@@ -1,20 +1,23 @@
-use std::marker::PhantomData;
 use crate::{
   model::{ EntrypointDims, NameModelHandle },
   syntax::{
     declaration::{
+      Attribute,
+      BufferDeclMode,
       InstanceDecl,
       ModuleDecl,
       StructDecl,
       UniformsDecl,
     },
     name::NamePath,
+    statement::Statement,
   },
   transform::syntax_ingester::TypeRefPartial,
 };
 
 #[derive(Debug, Clone)]
 pub struct EntrypointDeclPartial<'a> {
+  pub(crate) attributes: Vec<Attribute<'a>>,
   pub(crate) name: NameModelHandle,
   pub(crate) dims: EntrypointDims,
   pub(crate) arg_name: NameModelHandle,
@@ -23,7 +26,9 @@ pub struct EntrypointDeclPartial<'a> {
 
 #[derive(Debug, Clone)]
 pub struct BufferDeclPartial<'a> {
+  pub(crate) attributes: Vec<Attribute<'a>>,
   pub(crate) name: NameModelHandle,
+  pub(crate) mode: BufferDeclMode,
   pub(crate) ty: TypeRefPartial<'a>,
 }
 
@@ -64,6 +69,12 @@ pub struct UniformsDeclPartial<'a> {
   pub(crate) syntax_decl: UniformsDecl<'a>,
 }
 
+#[derive(Debug, Clone)]
+pub struct TestDeclPartial<'a> {
+  pub(crate) name: NameModelHandle,
+  pub(crate) body: Vec<StatementBodyPartial<'a>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct FuncDeclArgPartial<'a> {
   pub(crate) name: NameModelHandle,
@@ -72,5 +83,5 @@ pub struct FuncDeclArgPartial<'a> {
 
 #[derive(Debug, Clone)]
 pub struct StatementBodyPartial<'a> {
-  _phantom: PhantomData<&'a ()>
+  pub(crate) syntax_stmt: Statement<'a>,
 }
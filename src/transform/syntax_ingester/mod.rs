@@ -3,7 +3,7 @@ mod declaration_partials;
 mod type_partials;
 
 pub(crate) use self::{
-  shader_file_partial::ShaderFilePartial,
+  shader_file_partial::{ ShaderFilePartial, ShaderFileDeclarationPartial },
   declaration_partials::{
     BufferDeclPartial,
     EntrypointDeclPartial,
@@ -12,14 +12,16 @@ pub(crate) use self::{
     ImportDeclPartial,
     InstanceDeclPartial,
     ModuleDeclPartial,
+    StatementBodyPartial,
     StructDeclPartial,
+    TestDeclPartial,
     UniformsDeclPartial,
   },
   type_partials::TypeRefPartial,
 };
 
-use std::str;
-use chumsky::{ Parser, extra };
+use std::{ borrow::Borrow, str };
+use chumsky::{ Parser, error::Rich, extra };
 use crate::{
   model::{
     EntrypointDims,
@@ -30,12 +32,18 @@ use crate::{
   },
   syntax::{
     declaration::{
-      BufferDecl, EntrypointDecl, FuncDecl, ImportDecl, InstanceDecl, ModuleDecl, StructDecl, UniformsDecl
+      BufferDecl, EntrypointDecl, FuncDecl, ImportDecl, InstanceDecl, ModuleDecl, StructDecl,
+      TestDecl, UniformsDecl,
     },
-    file::{ ShaderFile, ShaderFileDeclaration },
+    file::{ LibraryFile, LibraryFileDeclaration, ShaderFile, ShaderFileDeclaration },
+    statement::StatementBlock,
     types::TypeName,
   },
-  transform::SessionConfig,
+  transform::{
+    Diagnostic,
+    SessionConfig,
+    type_checker::TypeChecker,
+  },
 };
 
 pub struct SyntaxIngestionError;
@@ -63,37 +71,99 @@ impl<'a> SyntaxIngester<'a> {
 
   /**
    * Generate a ShaderFileModel for a shader file within a session config.
+   *
+   * The file is type-checked after ingestion, and any syntax or
+   * semantic errors are returned as diagnostics.
    */
   pub fn parse_shader_file<'x: 'a>(
     session_config: &SessionConfig,
     sub_path: &str,
     contents: &'x str,
-  ) -> ShaderFileModelHandle {
+  ) -> Result<ShaderFileModelHandle, Vec<Diagnostic>> {
     let mut ingester = SyntaxIngester::new(&session_config);
 
     let sub_path = ingester.model_space.intern_string(sub_path);
-    let _partial = ingester.ingest_shader_file_contents(&sub_path, contents);
-    ingester.model_space.add_shader_file_model(ShaderFileModel::new(sub_path))
+    let partial = ingester.ingest_shader_file_contents(&sub_path, contents)?;
+    let model = TypeChecker::check_shader_file(
+      &mut ingester.model_space,
+      session_config.capabilities,
+      ShaderFileModel::new(sub_path),
+      &partial,
+    )?;
+    Ok(ingester.model_space.add_shader_file_model(model))
+  }
+
+  /**
+   * Generate a ShaderFileModel for a library file within a session
+   * config.  The model holds the library's structs, functions and
+   * tests, and any syntax or semantic errors are returned as
+   * diagnostics.
+   */
+  pub fn parse_library_file<'x: 'a>(
+    session_config: &SessionConfig,
+    sub_path: &str,
+    contents: &'x str,
+  ) -> Result<ShaderFileModelHandle, Vec<Diagnostic>> {
+    let mut ingester = SyntaxIngester::new(session_config);
+
+    let sub_path = ingester.model_space.intern_string(sub_path);
+    let partial = ingester.ingest_library_file_contents(&sub_path, contents)?;
+    let model = TypeChecker::check_shader_file(
+      &mut ingester.model_space,
+      session_config.capabilities,
+      ShaderFileModel::new(sub_path),
+      &partial,
+    )?;
+    Ok(ingester.model_space.add_shader_file_model(model))
   }
 
   fn ingest_shader_file_contents<'x: 'a>(&mut self,
     sub_path: &StringModelHandle,
     file_contents: &'x str,
-  ) -> ShaderFilePartial<'a> {
+  ) -> Result<ShaderFilePartial<'a>, Vec<Diagnostic>> {
     let shader_file =
-      ShaderFile::parser::<extra::Default>()
+      ShaderFile::parser::<extra::Err<Rich<char>>>()
         .parse(file_contents)
         .into_result()
-        .expect(
-          &format!("Failed to parse file {:?}", sub_path)
-        );
+        .map_err(|errs| parse_diagnostics(sub_path, errs))?;
     let mut shader_file_partial = ShaderFilePartial::new(sub_path.clone());
 
     for decl in shader_file.declarations {
       self.ingest_shader_file_declaration(&mut shader_file_partial, decl);
     }
 
-    shader_file_partial
+    Ok(shader_file_partial)
+  }
+
+  fn ingest_library_file_contents<'x: 'a>(&mut self,
+    sub_path: &StringModelHandle,
+    file_contents: &'x str,
+  ) -> Result<ShaderFilePartial<'a>, Vec<Diagnostic>> {
+    let library_file =
+      LibraryFile::parser::<extra::Err<Rich<char>>>()
+        .parse(file_contents)
+        .into_result()
+        .map_err(|errs| parse_diagnostics(sub_path, errs))?;
+    let mut shader_file_partial = ShaderFilePartial::new(sub_path.clone());
+
+    // A library declaration is ingested as the same shader file one.
+    for decl in library_file.declarations {
+      let decl = match decl {
+        LibraryFileDeclaration::Import(import_decl) =>
+          ShaderFileDeclaration::Import(import_decl),
+        LibraryFileDeclaration::Func(func_decl) =>
+          ShaderFileDeclaration::Func(func_decl),
+        LibraryFileDeclaration::Module(module_decl) =>
+          ShaderFileDeclaration::Module(module_decl),
+        LibraryFileDeclaration::Struct(struct_decl) =>
+          ShaderFileDeclaration::Struct(struct_decl),
+        LibraryFileDeclaration::Test(test_decl) =>
+          ShaderFileDeclaration::Test(test_decl),
+      };
+      self.ingest_shader_file_declaration(&mut shader_file_partial, decl);
+    }
+
+    Ok(shader_file_partial)
   }
 
   /**
@@ -128,6 +198,9 @@ impl<'a> SyntaxIngester<'a> {
       ShaderFileDeclaration::Uniforms(uniforms_decl) => {
         self.ingest_uniforms_decl(partial, uniforms_decl);
       },
+      ShaderFileDeclaration::Test(test_decl) => {
+        self.ingest_test_decl(partial, test_decl);
+      },
     }
   }
 
@@ -142,8 +215,9 @@ impl<'a> SyntaxIngester<'a> {
     let arg_name =
       self.model_space.intern_name(entrypoint_decl.arg_name.contents);
     let dims = EntrypointDims::from_decl_dims(entrypoint_decl.dims);
-    let body = Vec::new();
+    let body = Self::ingest_statement_block(entrypoint_decl.body);
     partial.add_entrypoint_decl(EntrypointDeclPartial {
+      attributes: entrypoint_decl.attributes,
       name,
       dims,
       arg_name,
@@ -159,8 +233,14 @@ impl<'a> SyntaxIngester<'a> {
     buffer_decl: BufferDecl<'a>,
   ) {
     let name = self.model_space.intern_name(buffer_decl.name.contents);
+    let mode = buffer_decl.mode;
     let ty = self.inflate_type_reference(partial, &buffer_decl.ty);
-    partial.add_buffer_decl(BufferDeclPartial { name, ty });
+    partial.add_buffer_decl(BufferDeclPartial {
+      attributes: buffer_decl.attributes,
+      name,
+      mode,
+      ty,
+    });
   }
 
   /**
@@ -211,7 +291,7 @@ impl<'a> SyntaxIngester<'a> {
         }
       })
       .collect();
-    let body = Vec::new();
+    let body = Self::ingest_statement_block(func_decl.body);
     partial.add_func_decl(FuncDeclPartial { name, return_ty, args, body });
   }
 
@@ -251,6 +331,29 @@ impl<'a> SyntaxIngester<'a> {
     );
   }
 
+  /**
+   * Ingest a test declaration.
+   */
+  fn ingest_test_decl(&mut self,
+    partial: &mut ShaderFilePartial<'a>,
+    test_decl: TestDecl<'a>,
+  ) {
+    let name = self.model_space.intern_name(test_decl.name.contents);
+    let body = Self::ingest_statement_block(test_decl.body);
+    partial.add_test_decl(TestDeclPartial { name, body });
+  }
+
+  /**
+   * Ingest the statements of a body.
+   */
+  fn ingest_statement_block(block: StatementBlock<'a>)
+    -> Vec<StatementBodyPartial<'a>>
+  {
+    block.statements.into_iter()
+      .map(|syntax_stmt| StatementBodyPartial { syntax_stmt })
+      .collect()
+  }
+
   /**
    * Ingest a type-reference.
    */
@@ -261,3 +364,23 @@ impl<'a> SyntaxIngester<'a> {
     TypeRefPartial::from_type_name(ty)
   }
 }
+
+/**
+ * The diagnostics of a file that failed to parse, with the byte range
+ * of what was found in place of the expected syntax.
+ */
+fn parse_diagnostics(sub_path: &StringModelHandle, errs: Vec<Rich<char>>) -> Vec<Diagnostic> {
+  let sub_path: &str = sub_path.borrow();
+  errs.into_iter()
+    .map(|err| {
+      let found = match err.found() {
+        Some(c) => format!("`{}`", c),
+        None => "end of file".to_string(),
+      };
+      Diagnostic::new(format!(
+        "Failed to parse `{}` at {}..{}: unexpected {}.",
+        sub_path, err.span().start, err.span().end, found
+      ))
+    })
+    .collect()
+}
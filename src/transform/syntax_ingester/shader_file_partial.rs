@@ -9,6 +9,7 @@ use crate::{
     FuncDeclPartial,
     ModuleDeclPartial,
     StructDeclPartial,
+    TestDeclPartial,
     UniformsDeclPartial,
   },
 };
@@ -22,6 +23,11 @@ pub struct ShaderFilePartial<'a> {
   pub(crate) uniforms: Option<UniformsDeclPartial<'a>>,
   pub(crate) declarations:
     HashMap<NameModelHandle, ShaderFileDeclarationPartial<'a>>,
+  // Declaration names in source order.
+  pub(crate) order: Vec<NameModelHandle>,
+  // Tests in source order.  Their names are not in scope, so they are
+  // kept apart from the declarations.
+  pub(crate) tests: Vec<TestDeclPartial<'a>>,
 }
 impl<'a> ShaderFilePartial<'a> {
   pub(crate) fn new(path: StringModelHandle) -> ShaderFilePartial<'a> {
@@ -29,6 +35,8 @@ impl<'a> ShaderFilePartial<'a> {
       path,
       uniforms: None,
       declarations: HashMap::new(),
+      order: Vec::new(),
+      tests: Vec::new(),
     }
   }
 
@@ -36,9 +44,19 @@ impl<'a> ShaderFilePartial<'a> {
     if self.declarations.contains_key(&name) {
       panic!("Duplicate declaration: {:?}", name);
     }
+    self.order.push(name.clone());
     self.declarations.insert(name, decl);
   }
 
+  /**
+   * Iterate over the declarations in source order.
+   */
+  pub(crate) fn declarations_in_order(&self)
+    -> impl Iterator<Item = &ShaderFileDeclarationPartial<'a>>
+  {
+    self.order.iter().map(|name| &self.declarations[name])
+  }
+
   pub(crate) fn add_entrypoint_decl(&mut self, entrypoint_decl: EntrypointDeclPartial<'a>) {
     self.add(
       entrypoint_decl.name.clone(),
@@ -91,6 +109,10 @@ impl<'a> ShaderFilePartial<'a> {
   pub(crate) fn add_uniforms_decl(&mut self, uniforms_decl: UniformsDeclPartial<'a>) {
     self.uniforms = Some(uniforms_decl);
   }
+
+  pub(crate) fn add_test_decl(&mut self, test_decl: TestDeclPartial<'a>) {
+    self.tests.push(test_decl);
+  }
 }
 
 /**
@@ -1,6 +1,7 @@
 use crate::{
   model::{ ScalarNumericTypeModel, TypeModel, VecDims },
   syntax::{
+    expression::ConstExpr,
     name::NamePath,
     types::TypeName,
   },
@@ -13,46 +14,70 @@ use crate::{
 pub enum TypeRefPartial<'a> {
     Model(TypeModel),
     Path(NamePath<'a>),
+    Array {
+      elem: Box<TypeRefPartial<'a>>,
+      len: Option<ConstExpr<'a>>,
+    },
 }
 impl<'a> TypeRefPartial<'a> {
   pub(crate) fn from_type_name(s: &TypeName<'a>)
     -> TypeRefPartial<'a>
   {
-    let name = s.name.parts.last().expect("Empty type name").contents;
-    match name {
-      "i32" => TypeRefPartial::Model(TypeModel::new_i32()),
-      "u32" => TypeRefPartial::Model(TypeModel::new_u32()),
-      "f32" => TypeRefPartial::Model(TypeModel::new_f32()),
-      "bool" => TypeRefPartial::Model(TypeModel::new_bool()),
-      "void" => TypeRefPartial::Model(TypeModel::new_void()),
-      "vec2xi32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::I32, VecDims::Vec2)
-      ),
-      "vec2xu32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec2)
-      ),
-      "vec2xf32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::F32, VecDims::Vec2)
-      ),
-      "vec3xi32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::I32, VecDims::Vec3)
-      ),
-      "vec3xu32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec3)
-      ),
-      "vec3xf32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::F32, VecDims::Vec3)
-      ),
-      "vec4xi32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::I32, VecDims::Vec4)
-      ),
-      "vec4xu32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec4)
-      ),
-      "vec4xf32" => TypeRefPartial::Model(
-          TypeModel::new_vector(ScalarNumericTypeModel::F32, VecDims::Vec4)
-      ),
-      _ => TypeRefPartial::Path(s.name.clone()),
+    match s {
+      TypeName::Path(path) => Self::from_path(path),
+      TypeName::Array(array) => TypeRefPartial::Array {
+        elem: Box::new(Self::from_type_name(&array.elem)),
+        len: array.len.clone(),
+      },
+    }
+  }
+
+  fn from_path(path: &NamePath<'a>) -> TypeRefPartial<'a> {
+    let name = path.parts.last().expect("Empty type name").contents;
+    let model = match name {
+      "bool" => Some(TypeModel::new_bool()),
+      "void" => Some(TypeModel::new_void()),
+      _ => ScalarNumericTypeModel::from_name(name).map(TypeModel::new_scalar)
+        .or_else(|| Self::vector_from_name(name))
+        .or_else(|| Self::matrix_from_name(name)),
+    };
+    match model {
+      Some(model) => TypeRefPartial::Model(model),
+      None => TypeRefPartial::Path(path.clone()),
+    }
+  }
+
+  /**
+   * Parse a vector type name of the form `vecNxT`, with `N` components
+   * from 2 to 4 of scalar type `T`, e.g. `vec3xf32`.
+   */
+  fn vector_from_name(name: &str) -> Option<TypeModel> {
+    let (dims, scalar) = name.strip_prefix("vec")?.split_once('x')?;
+    Some(TypeModel::new_vector(
+      ScalarNumericTypeModel::from_name(scalar)?,
+      Self::dims_from_digit(dims)?,
+    ))
+  }
+
+  /**
+   * Parse a matrix type name of the form `matCxRxT`, with `C` columns
+   * and `R` rows each from 2 to 4, of float type `T`, e.g. `mat4x4xf32`.
+   */
+  fn matrix_from_name(name: &str) -> Option<TypeModel> {
+    let mut parts = name.strip_prefix("mat")?.splitn(3, 'x');
+    let cols = Self::dims_from_digit(parts.next()?)?;
+    let rows = Self::dims_from_digit(parts.next()?)?;
+    let scalar = ScalarNumericTypeModel::from_name(parts.next()?)
+      .filter(|scalar| scalar.is_float())?;
+    Some(TypeModel::new_matrix(scalar, cols, rows))
+  }
+
+  fn dims_from_digit(digit: &str) -> Option<VecDims> {
+    match digit {
+      "2" => Some(VecDims::Vec2),
+      "3" => Some(VecDims::Vec3),
+      "4" => Some(VecDims::Vec4),
+      _ => None,
     }
   }
 }
@@ -0,0 +1,974 @@
+use crate::{
+  model::{
+    BinaryExprModel,
+    BinaryOpModel,
+    ArrayLengthExprModel,
+    BufferModel,
+    CallExprModel,
+    CastExprModel,
+    ConstructExprModel,
+    ExpressionModel,
+    ExpressionModelKind,
+    FieldExprModel,
+    IndexExprModel,
+    LiteralModel,
+    ScalarNumericTypeModel,
+    SwizzleExprModel,
+    TypeModel,
+    TypeModelHandle,
+    UnaryExprModel,
+    UnaryOpModel,
+    VecDims,
+    const_eval,
+  },
+  syntax::{
+    types::TypeName,
+    expression::{
+      AddExprOp,
+      BitExprOp,
+      CallExpr,
+      CastExpr,
+      ConstExpr,
+      DotExpr,
+      DotExprSuffix,
+      Expression,
+      IndexExpr,
+      IntLiteralExpr,
+      IntLiteralExprSign,
+      IntLiteralExprType,
+      IntLiteralRangeError,
+      IntLiteralValue,
+      LogicalExprOp,
+      MulExprOp,
+      NameExpr,
+      RelationalExprOp,
+      ShiftExprOp,
+      StructLiteralExpr,
+      UnaryExpr,
+      UnaryExprOp,
+    },
+  },
+  transform::{
+    syntax_ingester::TypeRefPartial,
+    type_checker::TypeChecker,
+  },
+};
+
+impl<'s> TypeChecker<'s> {
+  /**
+   * Check an expression in a read context.
+   *
+   * The expected type, if given, is only a hint used to type literals.
+   * Callers are responsible for checking the resulting type.
+   */
+  pub(super) fn check_expr(&mut self,
+    expr: &Expression,
+    expected: Option<&TypeModelHandle>,
+  ) -> Option<ExpressionModel> {
+    match expr {
+      Expression::Name(name_expr) => self.check_name_expr(name_expr),
+      Expression::IntLiteral(int_literal) =>
+        self.check_int_literal_expr(int_literal, expected),
+      Expression::FloatLiteral(float_literal) => {
+        let value = float_literal.value();
+        let expected_scalar = expected.and_then(|ty| ty.numeric_element());
+        let (scalar, literal) = match expected_scalar {
+          Some(ScalarNumericTypeModel::F16) =>
+            (ScalarNumericTypeModel::F16, LiteralModel::new_f16(value)),
+          _ => (ScalarNumericTypeModel::F32, LiteralModel::new_f32(value)),
+        };
+        let ty = self.intern_type(TypeModel::new_scalar(scalar));
+        Some(ExpressionModel::new(ty, ExpressionModelKind::Literal(literal)))
+      },
+      Expression::Paren(paren_expr) =>
+        self.check_expr(&paren_expr.subexpr, expected),
+      Expression::StructLiteral(struct_literal) =>
+        self.check_struct_literal_expr(struct_literal),
+      Expression::Dot(dot_expr) => self.check_dot_expr(dot_expr),
+      Expression::Index(index_expr) => self.check_index_expr(index_expr),
+      Expression::Call(call_expr) => self.check_call_expr(call_expr),
+      Expression::Unary(unary_expr) =>
+        self.check_unary_expr(unary_expr, expected),
+      Expression::Bit(bit_expr) => {
+        let op = match bit_expr.op {
+          BitExprOp::And => BinaryOpModel::BitAnd,
+          BitExprOp::Or => BinaryOpModel::BitOr,
+          BitExprOp::Xor => BinaryOpModel::BitXor,
+        };
+        self.check_binary_expr(&bit_expr.lhs, op, &bit_expr.rhs, expected)
+      },
+      Expression::Mul(mul_expr) => {
+        let op = match mul_expr.op {
+          MulExprOp::Mul => BinaryOpModel::Mul,
+          MulExprOp::Div => BinaryOpModel::Div,
+          MulExprOp::Mod => BinaryOpModel::Mod,
+        };
+        self.check_binary_expr(&mul_expr.lhs, op, &mul_expr.rhs, expected)
+      },
+      Expression::Add(add_expr) => {
+        let op = match add_expr.op {
+          AddExprOp::Add => BinaryOpModel::Add,
+          AddExprOp::Sub => BinaryOpModel::Sub,
+        };
+        self.check_binary_expr(&add_expr.lhs, op, &add_expr.rhs, expected)
+      },
+      Expression::Shift(shift_expr) => {
+        let op = match shift_expr.op {
+          ShiftExprOp::Shl => BinaryOpModel::Shl,
+          ShiftExprOp::Shr => BinaryOpModel::Shr,
+        };
+        self.check_binary_expr(&shift_expr.lhs, op, &shift_expr.rhs, expected)
+      },
+      Expression::Relational(relational_expr) => {
+        let op = match relational_expr.op {
+          RelationalExprOp::LessThan => BinaryOpModel::LessThan,
+          RelationalExprOp::LessThanOrEqual => BinaryOpModel::LessThanOrEqual,
+          RelationalExprOp::GreaterThan => BinaryOpModel::GreaterThan,
+          RelationalExprOp::GreaterThanOrEqual =>
+            BinaryOpModel::GreaterThanOrEqual,
+          RelationalExprOp::Equal => BinaryOpModel::Equal,
+          RelationalExprOp::NotEqual => BinaryOpModel::NotEqual,
+        };
+        self.check_binary_expr(
+          &relational_expr.lhs, op, &relational_expr.rhs, expected
+        )
+      },
+      Expression::Logical(logical_expr) => {
+        let op = match logical_expr.op {
+          LogicalExprOp::And => BinaryOpModel::LogicalAnd,
+          LogicalExprOp::Or => BinaryOpModel::LogicalOr,
+        };
+        self.check_binary_expr(&logical_expr.lhs, op, &logical_expr.rhs, expected)
+      },
+      Expression::Cast(cast_expr) => self.check_cast_expr(cast_expr),
+    }
+  }
+
+  /**
+   * Check an expression whose value is needed at compile time, e.g. an
+   * array length.  The expected type is a hint used to type literals.
+   */
+  pub(super) fn check_const_expr(&mut self,
+    const_expr: &ConstExpr,
+    expected: TypeModel,
+  ) -> Option<LiteralModel> {
+    let expected = self.intern_type(expected);
+    let expr = self.check_expr(&const_expr.expr, Some(&expected))?;
+    match expr.kind {
+      ExpressionModelKind::Literal(literal) => Some(literal),
+      _ => self.error(format!(
+        "Expression `{}` is not a constant.", const_expr
+      )),
+    }
+  }
+
+  /**
+   * Fold an operation on constants into a literal.  Errors such as
+   * constant division by zero are reported here, since they would fail
+   * at runtime on every invocation.  Results that are not finite stay
+   * as operations, since targets have no literals for them.
+   */
+  fn fold_const(&mut self, expr: ExpressionModel) -> Option<ExpressionModel> {
+    match const_eval(&expr) {
+      Ok(Some(literal)) if literal.is_finite() => Some(ExpressionModel::new(
+        expr.ty, ExpressionModelKind::Literal(literal)
+      )),
+      Ok(_) => Some(expr),
+      Err(err) => self.error(format!(
+        "{} in a constant expression.", err.to_string().trim_end_matches('.')
+      )),
+    }
+  }
+
+  /**
+   * Check an expression that is the target of a mutation.
+   */
+  pub(super) fn check_place(&mut self, expr: &Expression)
+    -> Option<ExpressionModel>
+  {
+    match expr {
+      Expression::Name(name_expr) => {
+        let name = Self::single_name(name_expr)?;
+        match self.lookup_local(name).cloned() {
+          Some(binding) if binding.mutable => {
+            let ty = binding.ty?;
+            let name = self.model_space.intern_name(name);
+            Some(ExpressionModel::new(ty, ExpressionModelKind::Local(name)))
+          },
+          Some(binding) => {
+            binding.ty?;
+            self.error(format!("Cannot mutate immutable binding `{}`.", name))
+          },
+          None if name == "uniforms" && self.uniforms.is_some() =>
+            self.error("Cannot mutate uniforms."),
+          None if self.buffers.contains_key(name) => {
+            let buffer = self.buffers[name].clone();
+            if !buffer.mode.is_writable() {
+              return self.error(format!(
+                "Cannot write to read-only buffer `{}`.", name
+              ));
+            }
+            let kind = ExpressionModelKind::Buffer(buffer.name);
+            Some(ExpressionModel::new(buffer.ty, kind))
+          },
+          None => self.check_name_expr(name_expr)
+            .and_then(|_| self.error(format!("Cannot mutate `{}`.", name))),
+        }
+      },
+      Expression::Paren(paren_expr) => self.check_place(&paren_expr.subexpr),
+      Expression::Index(index_expr) => {
+        let target = self.check_place(&index_expr.target)?;
+        self.check_array_element(target, &index_expr.index)
+      },
+      Expression::Dot(dot_expr) => {
+        let target = self.check_place(&dot_expr.target)?;
+        let place = self.check_component_access(target, &dot_expr.name)?;
+        match &place.kind {
+          ExpressionModelKind::Swizzle(swizzle) if swizzle.has_repeats() =>
+            self.error(format!(
+              "Cannot mutate swizzle `.{}` with repeated components.",
+              swizzle.component_letters()
+            )),
+          ExpressionModelKind::ArrayLength(_) |
+          ExpressionModelKind::Literal(_) =>
+            self.error("Cannot mutate the length of an array."),
+          _ => Some(place),
+        }
+      },
+      _ => self.error("Expression cannot be mutated."),
+    }
+  }
+
+  /**
+   * Check that a found type matches an expected one.
+   */
+  pub(super) fn check_type_matches(&mut self,
+    expected: &TypeModelHandle,
+    found: &TypeModelHandle,
+    context: &str,
+  ) -> Option<()> {
+    if expected != found {
+      return self.error(format!(
+        "Mismatched types in {}: expected `{}`, found `{}`.",
+        context, **expected, **found
+      ));
+    }
+    Some(())
+  }
+
+  /**
+   * Check that a value can be bound to a local name.
+   */
+  pub(super) fn check_bindable(&mut self, name: &str, value: ExpressionModel)
+    -> Option<ExpressionModel>
+  {
+    if value.ty.is_void() {
+      return self.error(format!("Cannot bind `void` value to `{}`.", name));
+    }
+    if value.ty.is_runtime_sized() {
+      return self.error(format!(
+        "Cannot bind a value of runtime-sized type `{}` to `{}`.",
+        *value.ty, name
+      ));
+    }
+    Some(value)
+  }
+
+  fn single_name<'x>(name_expr: &NameExpr<'x>) -> Option<&'x str> {
+    if name_expr.name.is_single() {
+      Some(name_expr.name.parts[0].contents)
+    } else {
+      None
+    }
+  }
+
+  /**
+   * Get the buffer an expression names, if it is a bare buffer name
+   * not shadowed by a local.
+   */
+  fn buffer_named(&self, expr: &Expression) -> Option<BufferModel> {
+    let Expression::Name(name_expr) = expr else {
+      return None;
+    };
+    let name = Self::single_name(name_expr)?;
+    if self.lookup_local(name).is_some() {
+      return None;
+    }
+    self.buffers.get(name).cloned()
+  }
+
+  fn check_name_expr(&mut self, name_expr: &NameExpr)
+    -> Option<ExpressionModel>
+  {
+    let Some(name) = Self::single_name(name_expr) else {
+      let path = name_expr.name.parts.iter()
+        .map(|part| part.contents)
+        .collect::<Vec<_>>()
+        .join("::");
+      return self.error(format!("Unknown name `{}`.", path));
+    };
+
+    if let Some(binding) = self.lookup_local(name).cloned() {
+      let ty = binding.ty?;
+      let name = self.model_space.intern_name(name);
+      return Some(ExpressionModel::new(ty, ExpressionModelKind::Local(name)));
+    }
+    if name == "true" || name == "false" {
+      let ty = self.intern_type(TypeModel::new_bool());
+      let literal = LiteralModel::Bool(name == "true");
+      return Some(ExpressionModel::new(ty, ExpressionModelKind::Literal(literal)));
+    }
+    if name == "uniforms" {
+      if let Some(uniforms) = &self.uniforms {
+        let ty = uniforms.ty.clone();
+        return Some(ExpressionModel::new(ty, ExpressionModelKind::Uniforms));
+      }
+    }
+    if let Some(buffer) = self.buffers.get(name) {
+      let ty = buffer.ty.clone();
+      let kind = ExpressionModelKind::Buffer(buffer.name.clone());
+      return Some(ExpressionModel::new(ty, kind));
+    }
+    if self.funcs.contains_key(name) {
+      return self.error(format!("Function `{}` must be called.", name));
+    }
+    if self.failed_decls.contains(name) {
+      return None;
+    }
+    self.error(format!("Unknown name `{}`.", name))
+  }
+
+  fn check_int_literal_expr(&mut self,
+    int_literal: &IntLiteralExpr,
+    expected: Option<&TypeModelHandle>,
+  ) -> Option<ExpressionModel> {
+    let context_ty = match expected.and_then(|ty| ty.numeric_element()) {
+      Some(ScalarNumericTypeModel::U32) => IntLiteralExprType::U32,
+      Some(ScalarNumericTypeModel::U64) => IntLiteralExprType::U64,
+      Some(ScalarNumericTypeModel::I64) => IntLiteralExprType::I64,
+      _ => IntLiteralExprType::I32,
+    };
+    let (scalar, literal) = match int_literal.value(context_ty) {
+      Ok(IntLiteralValue::U32(value)) =>
+        (ScalarNumericTypeModel::U32, LiteralModel::U32(value)),
+      Ok(IntLiteralValue::I32(value)) =>
+        (ScalarNumericTypeModel::I32, LiteralModel::I32(value)),
+      Ok(IntLiteralValue::U64(value)) =>
+        (ScalarNumericTypeModel::U64, LiteralModel::U64(value)),
+      Ok(IntLiteralValue::I64(value)) =>
+        (ScalarNumericTypeModel::I64, LiteralModel::I64(value)),
+      Err(IntLiteralRangeError { ty }) => {
+        let ty_name = match ty {
+          IntLiteralExprType::U32 => "u32",
+          IntLiteralExprType::I32 => "i32",
+          IntLiteralExprType::U64 => "u64",
+          IntLiteralExprType::I64 => "i64",
+        };
+        return self.error(format!(
+          "Integer literal `{}` is out of range for `{}`.", int_literal, ty_name
+        ));
+      },
+    };
+    let ty = TypeModel::new_scalar(scalar);
+    self.check_type_supported(&ty)?;
+    let ty = self.intern_type(ty);
+    Some(ExpressionModel::new(ty, ExpressionModelKind::Literal(literal)))
+  }
+
+  /**
+   * Whether an expression is built only from unsuffixed integer literals
+   * and float literals, and so takes its type from context.
+   */
+  fn is_untyped_literal(expr: &Expression) -> bool {
+    match expr {
+      Expression::IntLiteral(int_literal) => int_literal.ty.is_none(),
+      Expression::FloatLiteral(_) => true,
+      Expression::Paren(paren_expr) =>
+        Self::is_untyped_literal(&paren_expr.subexpr),
+      Expression::Unary(unary_expr) =>
+        Self::is_untyped_literal(&unary_expr.subexpr),
+      Expression::Bit(bit_expr) =>
+        Self::is_untyped_literal(&bit_expr.lhs) &&
+          Self::is_untyped_literal(&bit_expr.rhs),
+      Expression::Mul(mul_expr) =>
+        Self::is_untyped_literal(&mul_expr.lhs) &&
+          Self::is_untyped_literal(&mul_expr.rhs),
+      Expression::Add(add_expr) =>
+        Self::is_untyped_literal(&add_expr.lhs) &&
+          Self::is_untyped_literal(&add_expr.rhs),
+      _ => false,
+    }
+  }
+
+  /**
+   * Check the operands of a binary operator, using the type of one as
+   * the context for the other.  If only the left operand is an untyped
+   * literal, the right operand is checked first so `0 < i` types the
+   * literal from `i`.
+   */
+  fn check_operands(&mut self,
+    lhs: &Expression,
+    rhs: &Expression,
+    expected: Option<&TypeModelHandle>,
+  ) -> Option<(ExpressionModel, ExpressionModel)> {
+    if Self::is_untyped_literal(lhs) && !Self::is_untyped_literal(rhs) {
+      let rhs = self.check_expr(rhs, expected)?;
+      let lhs = self.check_expr(lhs, Some(&rhs.ty))?;
+      Some((lhs, rhs))
+    } else {
+      let lhs = self.check_expr(lhs, expected)?;
+      let rhs = self.check_expr(rhs, Some(&lhs.ty))?;
+      Some((lhs, rhs))
+    }
+  }
+
+  fn check_struct_literal_expr(&mut self, struct_literal: &StructLiteralExpr)
+    -> Option<ExpressionModel>
+  {
+    let struct_name = &struct_literal.name;
+    let ty = struct_name.is_single()
+      .then(|| self.structs.get(struct_name.parts[0].contents))
+      .flatten()
+      .cloned();
+    let ty = match ty {
+      Some(Some(ty)) => ty,
+      // The struct failed to resolve, and has already been reported.
+      Some(None) => return None,
+      None => return self.error(format!(
+        "Unknown struct `{}`.",
+        struct_name.parts.iter()
+          .map(|part| part.contents)
+          .collect::<Vec<_>>()
+          .join("::")
+      )),
+    };
+    let TypeModel::Struct(struct_ty) = &*ty else {
+      unreachable!("Struct name resolved to non-struct type");
+    };
+    if ty.is_runtime_sized() {
+      return self.error(format!(
+        "Cannot construct runtime-sized struct `{}`.", *ty
+      ));
+    }
+
+    let mut values: Vec<Option<ExpressionModel>> =
+      vec![None; struct_ty.fields.len()];
+    let mut ok = true;
+    for literal_field in &struct_literal.fields {
+      let field_name = literal_field.name.contents;
+      let Some((index, field)) = struct_ty.field(field_name) else {
+        self.error::<()>(format!(
+          "No field `{}` on struct `{}`.", field_name, *ty
+        ));
+        ok = false;
+        continue;
+      };
+      if values[index].is_some() {
+        self.error::<()>(format!(
+          "Field `{}` of struct `{}` is given more than once.", field_name, *ty
+        ));
+        ok = false;
+        continue;
+      }
+      let value = self.check_expr(&literal_field.value, Some(&field.ty))
+        .and_then(|value| {
+          self.check_type_matches(
+            &field.ty,
+            &value.ty,
+            &format!("field `{}` of `{}`", field_name, *ty),
+          )?;
+          Some(value)
+        });
+      match value {
+        Some(value) => values[index] = Some(value),
+        None => ok = false,
+      }
+    }
+
+    let mut args = Vec::new();
+    for (field, value) in struct_ty.fields.iter().zip(values) {
+      match value {
+        Some(value) => args.push(value),
+        None if ok => {
+          self.error::<()>(format!(
+            "Missing field `{}` in literal of struct `{}`.", field.name, *ty
+          ));
+          ok = false;
+        },
+        None => {},
+      }
+    }
+    if !ok {
+      return None;
+    }
+    let kind = ExpressionModelKind::Construct(ConstructExprModel { args });
+    Some(ExpressionModel::new(ty.clone(), kind))
+  }
+
+  /**
+   * Check a vector constructor, e.g. `vec3xf32(1.0, 0.0, 0.0)` or
+   * `vec4xf32(xyz, 1.0)`.
+   */
+  fn check_vector_constructor(&mut self,
+    ty: TypeModelHandle,
+    call_expr: &CallExpr,
+  ) -> Option<ExpressionModel> {
+    let TypeModel::Vector(vector_ty) = &*ty else {
+      unreachable!("Vector constructor of non-vector type");
+    };
+    let scalar_ty = self.intern_type(TypeModel::new_scalar(vector_ty.scalar));
+    let mut args = Vec::new();
+    let mut components = 0;
+    for arg in &call_expr.args {
+      let arg = self.check_expr(arg, Some(&scalar_ty))?;
+      match &*arg.ty {
+        TypeModel::Scalar(_) if arg.ty == scalar_ty => components += 1,
+        TypeModel::Vector(arg_vector_ty) if arg_vector_ty.scalar == vector_ty.scalar =>
+          components += arg_vector_ty.dims as u32,
+        _ => return self.error(format!(
+          "Cannot construct `{}` from a value of type `{}`.", *ty, *arg.ty
+        )),
+      }
+      args.push(arg);
+    }
+    if components != vector_ty.dims as u32 {
+      return self.error(format!(
+        "Constructor of `{}` needs {} components, but {} were given.",
+        *ty, vector_ty.dims as u32, components
+      ));
+    }
+    let kind = ExpressionModelKind::Construct(ConstructExprModel { args });
+    Some(ExpressionModel::new(ty, kind))
+  }
+
+  fn check_dot_expr(&mut self, dot_expr: &DotExpr) -> Option<ExpressionModel> {
+    let target = self.check_expr(&dot_expr.target, None)?;
+    let access = self.check_component_access(target, &dot_expr.name)?;
+    self.check_buffer_read(access)
+  }
+
+  /**
+   * Check that a value read from within a buffer comes from a readable
+   * buffer.  Runtime-sized parts of a buffer are not values, so e.g.
+   * `out.length` is allowed for a write-only buffer.
+   */
+  fn check_buffer_read(&mut self, expr: ExpressionModel)
+    -> Option<ExpressionModel>
+  {
+    if expr.ty.is_runtime_sized() {
+      return Some(expr);
+    }
+    let mut root = &expr;
+    loop {
+      root = match &root.kind {
+        ExpressionModelKind::Index(index) => &index.target,
+        ExpressionModelKind::Field(field) => &field.target,
+        ExpressionModelKind::Swizzle(swizzle) => &swizzle.target,
+        ExpressionModelKind::ArrayLength(_) => return Some(expr),
+        ExpressionModelKind::Buffer(name) => {
+          if !self.buffers[name.name.as_str()].mode.is_readable() {
+            return self.error(format!(
+              "Cannot read from write-only buffer `{}`.", name.name
+            ));
+          }
+          return Some(expr);
+        },
+        _ => return Some(expr),
+      };
+    }
+  }
+
+  /**
+   * Check a struct field, vector component or array length access on
+   * a value.
+   */
+  fn check_component_access(&mut self,
+    target: ExpressionModel,
+    suffix: &DotExprSuffix,
+  ) -> Option<ExpressionModel> {
+    match (&*target.ty, suffix) {
+      (TypeModel::Struct(struct_ty), DotExprSuffix::Name(name)) => {
+        let Some((index, field)) = struct_ty.field(name.contents) else {
+          return self.error(format!(
+            "No field `{}` on type `{}`.", name.contents, *target.ty
+          ));
+        };
+        let ty = field.ty.clone();
+        let kind = ExpressionModelKind::Field(FieldExprModel {
+          target: target.boxed(),
+          field: index as u32,
+        });
+        Some(ExpressionModel::new(ty, kind))
+      },
+      (TypeModel::Vector(vector_ty), DotExprSuffix::Number(component)) => {
+        let dims = vector_ty.dims;
+        self.check_swizzle(target, dims, vec![*component], &component.to_string())
+      },
+      (TypeModel::Vector(vector_ty), DotExprSuffix::Name(name)) => {
+        let dims = vector_ty.dims;
+        let Some(components) = SwizzleExprModel::parse_components(name.contents)
+        else {
+          return self.error(format!(
+            "No field `{}` on type `{}`; expected a swizzle of `xyzw`.",
+            name.contents, *target.ty
+          ));
+        };
+        self.check_swizzle(target, dims, components, name.contents)
+      },
+      (TypeModel::Array(array_ty), DotExprSuffix::Name(name))
+        if name.contents == "length" =>
+      {
+        let ty = self.intern_type(TypeModel::new_u32());
+        let kind = match array_ty.len {
+          Some(len) => ExpressionModelKind::Literal(LiteralModel::U32(len)),
+          None => ExpressionModelKind::ArrayLength(ArrayLengthExprModel {
+            target: target.boxed(),
+          }),
+        };
+        Some(ExpressionModel::new(ty, kind))
+      },
+      (_, DotExprSuffix::Name(name)) => self.error(format!(
+        "No field `{}` on type `{}`.", name.contents, *target.ty
+      )),
+      (_, DotExprSuffix::Number(component)) => self.error(format!(
+        "No component {} on type `{}`.", component, *target.ty
+      )),
+    }
+  }
+
+  /**
+   * Check a swizzle of a vector's components.
+   */
+  fn check_swizzle(&mut self,
+    target: ExpressionModel,
+    dims: VecDims,
+    components: Vec<u32>,
+    text: &str,
+  ) -> Option<ExpressionModel> {
+    if components.iter().any(|&component| component >= dims as u32) {
+      return self.error(format!(
+        "Swizzle `.{}` is out of range for type `{}`.", text, *target.ty
+      ));
+    }
+    let scalar = target.ty.numeric_element().expect("Non-numeric vector");
+    let ty = match VecDims::from_count(components.len()) {
+      Some(dims) => TypeModel::new_vector(scalar, dims),
+      None => TypeModel::new_scalar(scalar),
+    };
+    let ty = self.intern_type(ty);
+    let kind = ExpressionModelKind::Swizzle(SwizzleExprModel {
+      target: target.boxed(),
+      components,
+    });
+    Some(ExpressionModel::new(ty, kind))
+  }
+
+  fn check_index_expr(&mut self, index_expr: &IndexExpr)
+    -> Option<ExpressionModel>
+  {
+    let target = self.check_expr(&index_expr.target, None)?;
+    let element = self.check_array_element(target, &index_expr.index)?;
+    self.check_buffer_read(element)
+  }
+
+  /**
+   * Check an indexed access of an array, or of a buffer's storage array.
+   */
+  fn check_array_element(&mut self, target: ExpressionModel, index: &Expression)
+    -> Option<ExpressionModel>
+  {
+    let TypeModel::Array(array_ty) = &*target.ty else {
+      return self.error(format!(
+        "Cannot index into a value of type `{}`; expected an array or buffer.",
+        *target.ty
+      ));
+    };
+    let ty = array_ty.elem.clone();
+    let index = self.check_index(index)?;
+    // Constant indices are folded, so ones out of bounds of a sized
+    // array can be reported here rather than failing on every target.
+    if let (ExpressionModelKind::Literal(literal), Some(len)) = (&index.kind, array_ty.len) {
+      let value = const_eval::int_value(*literal);
+      if !(0..len as i128).contains(&value) {
+        return self.error(format!(
+          "Index {} is out of bounds for type `{}`.", value, *target.ty
+        ));
+      }
+    }
+    let kind = ExpressionModelKind::Index(IndexExprModel {
+      target: target.boxed(),
+      index: index.boxed(),
+    });
+    Some(ExpressionModel::new(ty, kind))
+  }
+
+  /**
+   * Check an index subexpression, which must be an integer scalar.
+   */
+  fn check_index(&mut self, index: &Expression) -> Option<ExpressionModel> {
+    let u32_ty = self.intern_type(TypeModel::new_u32());
+    let index = self.check_expr(index, Some(&u32_ty))?;
+    if !index.ty.is_integer_scalar() {
+      return self.error(format!(
+        "Index must be an integer, found `{}`.", *index.ty
+      ));
+    }
+    Some(index)
+  }
+
+  fn check_call_expr(&mut self, call_expr: &CallExpr)
+    -> Option<ExpressionModel>
+  {
+    if let Some(buffer) = self.buffer_named(&call_expr.callee) {
+      return self.error(format!(
+        "Buffer `{}` cannot be called; index it with `{}[...]`.",
+        buffer.name.name, buffer.name.name
+      ));
+    }
+    let callee_name = match &*call_expr.callee {
+      Expression::Name(name_expr) => Self::single_name(name_expr)
+        .filter(|name| self.lookup_local(name).is_none()),
+      _ => None,
+    };
+    if let Expression::Name(name_expr) = &*call_expr.callee {
+      let type_name = TypeName::Path(name_expr.name.clone());
+      if let TypeRefPartial::Model(model @ TypeModel::Vector(_)) =
+        TypeRefPartial::from_type_name(&type_name)
+      {
+        self.check_type_supported(&model)?;
+        let ty = self.intern_type(model);
+        return self.check_vector_constructor(ty, call_expr);
+      }
+    }
+    let Some(signature) = callee_name
+      .and_then(|name| self.funcs.get(name))
+      .cloned()
+    else {
+      if callee_name.is_some_and(|name| self.failed_decls.contains(name)) {
+        return None;
+      }
+      return self.error("Call target must be a function.");
+    };
+    let callee_name = callee_name.unwrap();
+
+    if call_expr.args.len() != signature.args.len() {
+      return self.error(format!(
+        "Function `{}` takes {} arguments, but {} were given.",
+        callee_name, signature.args.len(), call_expr.args.len()
+      ));
+    }
+    let mut args = Vec::new();
+    for (arg, arg_model) in call_expr.args.iter().zip(&signature.args) {
+      let arg = self.check_expr(arg, Some(&arg_model.ty))?;
+      self.check_type_matches(
+        &arg_model.ty,
+        &arg.ty,
+        &format!("argument `{}` of `{}`", arg_model.name.name, callee_name),
+      )?;
+      args.push(arg);
+    }
+    let func = self.model_space.intern_name(callee_name);
+    let kind = ExpressionModelKind::Call(CallExprModel { func, args });
+    Some(ExpressionModel::new(signature.return_ty, kind))
+  }
+
+  fn check_unary_expr(&mut self,
+    unary_expr: &UnaryExpr,
+    expected: Option<&TypeModelHandle>,
+  ) -> Option<ExpressionModel> {
+    // Fold negation into an unsigned literal, so `-2147483648` fits `i32`
+    // and `-1_u32` is reported as out of range.
+    if let (UnaryExprOp::Negate, Expression::IntLiteral(int_literal)) =
+      (&unary_expr.op, &*unary_expr.subexpr)
+    {
+      if int_literal.sign.is_none() {
+        let negated = IntLiteralExpr {
+          sign: Some(IntLiteralExprSign::Negative),
+          ..int_literal.clone()
+        };
+        return self.check_int_literal_expr(&negated, expected);
+      }
+    }
+
+    let subexpr = self.check_expr(&unary_expr.subexpr, expected)?;
+    let element = subexpr.ty.numeric_element();
+    let op = match &unary_expr.op {
+      UnaryExprOp::Positive if element.is_some() => return Some(subexpr),
+      UnaryExprOp::Negate if element.is_some_and(|e| e.is_signed()) =>
+        UnaryOpModel::Negate,
+      UnaryExprOp::Not if subexpr.ty.is_bool() => UnaryOpModel::Not,
+      UnaryExprOp::Complement if element.is_some_and(|e| e.is_integer()) =>
+        UnaryOpModel::Complement,
+      op => return self.error(format!(
+        "Cannot apply unary {:?} to a value of type `{}`.", op, *subexpr.ty
+      )),
+    };
+    let ty = subexpr.ty.clone();
+    let kind = ExpressionModelKind::Unary(UnaryExprModel {
+      op,
+      subexpr: subexpr.boxed(),
+    });
+    self.fold_const(ExpressionModel::new(ty, kind))
+  }
+
+  fn check_cast_expr(&mut self, cast_expr: &CastExpr)
+    -> Option<ExpressionModel>
+  {
+    let subexpr = self.check_expr(&cast_expr.subexpr, None)?;
+    let target = match TypeRefPartial::from_type_name(&cast_expr.ty) {
+      TypeRefPartial::Model(model) if model.numeric_element().is_some() => model,
+      _ => return self.error(format!(
+        "Cannot cast to `{}`; expected a numeric scalar or vector type.",
+        cast_expr.ty
+      )),
+    };
+    self.check_type_supported(&target)?;
+    let castable = match (&*subexpr.ty, &target) {
+      (TypeModel::Scalar(_), TypeModel::Scalar(_)) =>
+        subexpr.ty.numeric_element().is_some(),
+      (TypeModel::Vector(from), TypeModel::Vector(to)) => from.dims == to.dims,
+      _ => false,
+    };
+    if !castable {
+      return self.error(format!(
+        "Cannot cast a value of type `{}` to `{}`.", *subexpr.ty, target
+      ));
+    }
+    let ty = self.intern_type(target);
+    let kind = ExpressionModelKind::Cast(CastExprModel {
+      subexpr: subexpr.boxed(),
+    });
+    self.fold_const(ExpressionModel::new(ty, kind))
+  }
+
+  fn check_binary_expr(&mut self,
+    lhs: &Expression,
+    op: BinaryOpModel,
+    rhs: &Expression,
+    expected: Option<&TypeModelHandle>,
+  ) -> Option<ExpressionModel> {
+    let (lhs, rhs, ty) = match op {
+      BinaryOpModel::LogicalAnd | BinaryOpModel::LogicalOr => {
+        let bool_ty = self.intern_type(TypeModel::new_bool());
+        let lhs = self.check_expr(lhs, Some(&bool_ty));
+        let rhs = self.check_expr(rhs, Some(&bool_ty));
+        let (lhs, rhs) = (lhs?, rhs?);
+        self.check_type_matches(&bool_ty, &lhs.ty, "logical operand")?;
+        self.check_type_matches(&bool_ty, &rhs.ty, "logical operand")?;
+        (lhs, rhs, bool_ty)
+      },
+      _ if op.is_comparison() => {
+        let (lhs, rhs) = self.check_operands(lhs, rhs, None)?;
+        self.check_type_matches(&lhs.ty, &rhs.ty, "comparison")?;
+        let comparable = match op {
+          BinaryOpModel::Equal | BinaryOpModel::NotEqual =>
+            lhs.ty.is_bool() || lhs.ty.as_numeric_scalar().is_some(),
+          _ => lhs.ty.as_numeric_scalar().is_some(),
+        };
+        if !comparable {
+          return self.error(format!(
+            "Cannot compare values of type `{}`.", *lhs.ty
+          ));
+        }
+        let bool_ty = self.intern_type(TypeModel::new_bool());
+        (lhs, rhs, bool_ty)
+      },
+      BinaryOpModel::Shl | BinaryOpModel::Shr => {
+        let lhs = self.check_expr(lhs, expected)?;
+        if !lhs.ty.numeric_element().is_some_and(|e| e.is_integer()) {
+          return self.error(format!("Cannot shift a value of type `{}`.", *lhs.ty));
+        }
+        let amount_ty = match &*lhs.ty {
+          TypeModel::Vector(vector_ty) => TypeModel::new_vector(
+            ScalarNumericTypeModel::U32, vector_ty.dims
+          ),
+          _ => TypeModel::new_u32(),
+        };
+        let amount_ty = self.intern_type(amount_ty);
+        let rhs = self.check_expr(rhs, Some(&amount_ty))?;
+        self.check_type_matches(&amount_ty, &rhs.ty, "shift amount")?;
+        let ty = lhs.ty.clone();
+        (lhs, rhs, ty)
+      },
+      _ => {
+        let (lhs, rhs) = self.check_operands(lhs, rhs, expected)?;
+        let ty = self.arithmetic_result_type(op, &lhs.ty, &rhs.ty)?;
+        (lhs, rhs, ty)
+      },
+    };
+    let kind = ExpressionModelKind::Binary(BinaryExprModel {
+      lhs: lhs.boxed(),
+      op,
+      rhs: rhs.boxed(),
+    });
+    self.fold_const(ExpressionModel::new(ty, kind))
+  }
+
+  /**
+   * The result type of an arithmetic or bitwise operation.  Operands
+   * must have the same numeric type, or be a vector and a scalar of its
+   * element type.
+   */
+  fn arithmetic_result_type(&mut self,
+    op: BinaryOpModel,
+    lhs: &TypeModelHandle,
+    rhs: &TypeModelHandle,
+  ) -> Option<TypeModelHandle> {
+    let result = match (&**lhs, &**rhs) {
+      (TypeModel::Matrix(_), _) | (_, TypeModel::Matrix(_)) =>
+        self.matrix_result_type(op, lhs, rhs),
+      _ if lhs == rhs && lhs.numeric_element().is_some() => Some(lhs.clone()),
+      (TypeModel::Vector(vector_ty), TypeModel::Scalar(_))
+        if rhs.as_numeric_scalar() == Some(vector_ty.scalar) => Some(lhs.clone()),
+      (TypeModel::Scalar(_), TypeModel::Vector(vector_ty))
+        if lhs.as_numeric_scalar() == Some(vector_ty.scalar) => Some(rhs.clone()),
+      _ => None,
+    };
+    let is_bitwise = matches!(op,
+      BinaryOpModel::BitAnd | BinaryOpModel::BitOr | BinaryOpModel::BitXor
+    );
+    match result {
+      Some(ty) if !is_bitwise || ty.numeric_element().unwrap().is_integer() =>
+        Some(ty),
+      _ => self.error(format!(
+        "Cannot apply {:?} to values of types `{}` and `{}`.", op, **lhs, **rhs
+      )),
+    }
+  }
+
+  /**
+   * The result type of an arithmetic operation involving a matrix.
+   * Matrices of the same type can be added and subtracted, and scaled
+   * by their element type.  Multiplication follows the usual linear
+   * algebra rules:
+   *
+   * - `matCxR * matKxC` is `matKxR`
+   * - `matCxR * vecC` is `vecR`
+   * - `vecR * matCxR` is `vecC`
+   */
+  fn matrix_result_type(&mut self,
+    op: BinaryOpModel,
+    lhs: &TypeModelHandle,
+    rhs: &TypeModelHandle,
+  ) -> Option<TypeModelHandle> {
+    let ty = match (op, &**lhs, &**rhs) {
+      (BinaryOpModel::Add | BinaryOpModel::Sub, _, _) if lhs == rhs =>
+        return Some(lhs.clone()),
+      (BinaryOpModel::Mul, TypeModel::Matrix(a), TypeModel::Matrix(b))
+        if a.scalar == b.scalar && b.rows == a.cols =>
+        TypeModel::new_matrix(a.scalar, b.cols, a.rows),
+      (BinaryOpModel::Mul, TypeModel::Matrix(m), TypeModel::Vector(v))
+        if m.scalar == v.scalar && v.dims == m.cols =>
+        TypeModel::new_vector(m.scalar, m.rows),
+      (BinaryOpModel::Mul, TypeModel::Vector(v), TypeModel::Matrix(m))
+        if m.scalar == v.scalar && v.dims == m.rows =>
+        TypeModel::new_vector(m.scalar, m.cols),
+      (BinaryOpModel::Mul, TypeModel::Matrix(m), _)
+        if rhs.as_numeric_scalar() == Some(m.scalar) => return Some(lhs.clone()),
+      (BinaryOpModel::Mul, _, TypeModel::Matrix(m))
+        if lhs.as_numeric_scalar() == Some(m.scalar) => return Some(rhs.clone()),
+      _ => return None,
+    };
+    Some(self.intern_type(ty))
+  }
+}
@@ -0,0 +1,873 @@
+mod expr_checker;
+mod stmt_checker;
+
+use std::collections::{ HashMap, HashSet };
+use crate::{
+  model::{
+    BufferAccessMode,
+    BufferModel,
+    EntrypointDims,
+    EntrypointModel,
+    FuncArgModel,
+    FuncModel,
+    LayoutError,
+    LayoutRules,
+    LiteralModel,
+    ModelSpace,
+    NameModelHandle,
+    ResourceBindingModel,
+    ScalarNumericTypeModel,
+    ScalarTypeModel,
+    ShaderFileModel,
+    StatementModel,
+    StructFieldModel,
+    StructTypeModel,
+    TestModel,
+    TypeModel,
+    TypeModelHandle,
+    UniformsModel,
+    VecDims,
+  },
+  syntax::declaration::{ Attribute, StructDeclField },
+  transform::{
+    Diagnostic,
+    TargetCapabilities,
+    syntax_ingester::{
+      BufferDeclPartial,
+      EntrypointDeclPartial,
+      FuncDeclPartial,
+      ShaderFileDeclarationPartial,
+      ShaderFilePartial,
+      StatementBodyPartial,
+      TestDeclPartial,
+      TypeRefPartial,
+      UniformsDeclPartial,
+    },
+  },
+};
+
+/**
+ * The largest alignment a struct field can be given with `@align`.
+ */
+const MAX_FIELD_ALIGN: u32 = 1 << 16;
+
+/**
+ * The most invocations a workgroup can have, which every target
+ * supports.
+ */
+const MAX_WORKGROUP_INVOCATIONS: u32 = 256;
+
+/**
+ * Type-checks the ingested declarations of a shader file, producing
+ * the checked model.
+ */
+pub(crate) struct TypeChecker<'s> {
+  // The model space used to intern names and types.
+  model_space: &'s mut ModelSpace,
+
+  // The optional features of the compile target.
+  capabilities: TargetCapabilities,
+
+  // The diagnostics reported so far.
+  diagnostics: Vec<Diagnostic>,
+
+  // Resolved struct types.  A `None` entry marks a struct that failed
+  // to resolve, so that it is reported only once.
+  structs: HashMap<NameModelHandle, Option<TypeModelHandle>>,
+
+  // Structs currently being resolved, for cycle detection.
+  resolving_structs: HashSet<NameModelHandle>,
+
+  // The uniforms of the shader file, if any.
+  uniforms: Option<UniformsModel>,
+
+  // Buffers by name.
+  buffers: HashMap<NameModelHandle, BufferModel>,
+
+  // Function signatures by name.
+  funcs: HashMap<NameModelHandle, FuncSignature>,
+
+  // Names of the uniforms, buffers and functions that failed to check,
+  // so that uses of them are not reported again.
+  failed_decls: HashSet<NameModelHandle>,
+
+  // Lexical scopes of local bindings, innermost last.
+  scopes: Vec<HashMap<NameModelHandle, LocalBinding>>,
+
+  // The return type of the body being checked.
+  return_ty: Option<TypeModelHandle>,
+
+  // Whether the body being checked is a test's, where assertions are
+  // allowed.
+  in_test: bool,
+}
+
+/**
+ * The signature of a function, available before its body is checked.
+ */
+#[derive(Debug, Clone)]
+struct FuncSignature {
+  args: Vec<FuncArgModel>,
+  return_ty: TypeModelHandle,
+}
+
+/**
+ * The bindings assigned to the uniforms and buffers.  Resources whose
+ * binding could not be assigned are absent.
+ */
+#[derive(Debug, Clone, Default)]
+struct ResourceBindings {
+  uniforms: Option<ResourceBindingModel>,
+  buffers: HashMap<NameModelHandle, ResourceBindingModel>,
+}
+
+/**
+ * A local binding in a scope.  The type is `None` if the binding's
+ * initializer failed to check, in which case uses of it are not
+ * reported again.
+ */
+#[derive(Debug, Clone)]
+struct LocalBinding {
+  ty: Option<TypeModelHandle>,
+  mutable: bool,
+}
+
+impl<'s> TypeChecker<'s> {
+  fn new(model_space: &'s mut ModelSpace, capabilities: TargetCapabilities)
+    -> Self
+  {
+    TypeChecker {
+      model_space,
+      capabilities,
+      diagnostics: Vec::new(),
+      structs: HashMap::new(),
+      resolving_structs: HashSet::new(),
+      uniforms: None,
+      buffers: HashMap::new(),
+      funcs: HashMap::new(),
+      failed_decls: HashSet::new(),
+      scopes: Vec::new(),
+      return_ty: None,
+      in_test: false,
+    }
+  }
+
+  /**
+   * Check a shader file partial, filling in the given model.
+   */
+  pub(crate) fn check_shader_file(
+    model_space: &'s mut ModelSpace,
+    capabilities: TargetCapabilities,
+    mut model: ShaderFileModel,
+    partial: &ShaderFilePartial,
+  ) -> Result<ShaderFileModel, Vec<Diagnostic>> {
+    let mut checker = TypeChecker::new(model_space, capabilities);
+
+    // Types are resolved first, as everything else may refer to them.
+    for decl in partial.declarations_in_order() {
+      if let ShaderFileDeclarationPartial::Struct(struct_decl) = decl {
+        if let Some(ty) = checker.resolve_struct(partial, &struct_decl.name) {
+          model.structs.push(ty);
+        }
+      }
+    }
+    let bindings = checker.assign_bindings(partial);
+    if let Some(uniforms_decl) = &partial.uniforms {
+      checker.uniforms = checker.check_uniforms_decl(
+        partial,
+        uniforms_decl,
+        bindings.uniforms,
+      );
+      if checker.uniforms.is_none() {
+        let name = checker.model_space.intern_name("uniforms");
+        checker.failed_decls.insert(name);
+      }
+      model.uniforms = checker.uniforms.clone();
+    }
+
+    // Then the signatures of buffers and functions.
+    for decl in partial.declarations_in_order() {
+      match decl {
+        ShaderFileDeclarationPartial::Buffer(buffer_decl) => {
+          let binding = bindings.buffers.get(&buffer_decl.name).copied();
+          if let Some(buffer) = checker.check_buffer_decl(partial, buffer_decl, binding) {
+            checker.buffers.insert(buffer.name.clone(), buffer.clone());
+            model.buffers.push(buffer);
+          } else {
+            checker.failed_decls.insert(buffer_decl.name.clone());
+          }
+        },
+        ShaderFileDeclarationPartial::Func(func_decl) => {
+          if let Some(signature) = checker.check_func_signature(partial, func_decl) {
+            checker.funcs.insert(func_decl.name.clone(), signature);
+          } else {
+            checker.failed_decls.insert(func_decl.name.clone());
+          }
+        },
+        _ => {},
+      }
+    }
+
+    // Finally the bodies.
+    for decl in partial.declarations_in_order() {
+      match decl {
+        ShaderFileDeclarationPartial::Func(func_decl) => {
+          if let Some(func) = checker.check_func_decl(func_decl) {
+            model.funcs.push(func);
+          }
+        },
+        ShaderFileDeclarationPartial::Entrypoint(entrypoint_decl) => {
+          model.entrypoints.push(checker.check_entrypoint_decl(entrypoint_decl));
+        },
+        _ => {},
+      }
+    }
+    let mut test_names = HashSet::new();
+    for test_decl in &partial.tests {
+      if !test_names.insert(test_decl.name.clone()) {
+        checker.error::<()>(format!("Duplicate test `{}`.", test_decl.name.name));
+        continue;
+      }
+      model.tests.push(checker.check_test_decl(test_decl));
+    }
+
+    if checker.diagnostics.is_empty() {
+      Ok(model)
+    } else {
+      Err(checker.diagnostics)
+    }
+  }
+
+  /**
+   * Report an error.  Returns `None` for convenient propagation.
+   */
+  fn error<T>(&mut self, message: impl Into<String>) -> Option<T> {
+    self.diagnostics.push(Diagnostic::new(message));
+    None
+  }
+
+  /**
+   * Intern a type model.
+   */
+  fn intern_type(&mut self, ty: TypeModel) -> TypeModelHandle {
+    self.model_space.intern_type(ty)
+  }
+
+  /**
+   * Resolve a struct declaration into an interned struct type.
+   */
+  fn resolve_struct(&mut self,
+    partial: &ShaderFilePartial,
+    name: &NameModelHandle,
+  ) -> Option<TypeModelHandle> {
+    if let Some(resolved) = self.structs.get(name) {
+      return resolved.clone();
+    }
+    let struct_decl = match partial.declarations.get(name) {
+      Some(ShaderFileDeclarationPartial::Struct(struct_decl)) => struct_decl,
+      _ => return self.error(format!("Unknown struct `{}`.", name.name)),
+    };
+    if !self.resolving_structs.insert(name.clone()) {
+      return self.error(format!("Struct `{}` contains itself.", name.name));
+    }
+
+    let owner = format!("struct `{}`", name.name);
+    let attributes = self.check_attributes(
+      &struct_decl.syntax_decl.attributes,
+      &owner,
+      &[("packed", false)],
+    );
+    let fields = self.resolve_struct_fields(
+      partial,
+      &owner,
+      &struct_decl.syntax_decl.fields,
+    );
+    self.resolving_structs.remove(name);
+
+    let resolved = attributes.zip(fields).and_then(|(attributes, fields)| {
+      let path = self.model_space.intern_name_path(vec![name.clone()]);
+      let ty = self.intern_type(TypeModel::Struct(StructTypeModel {
+        name: (*path).clone(),
+        fields,
+        packed: attributes.contains_key("packed"),
+      }));
+      // Check layout attributes and sizes here, so that they are reported
+      // even for structs that are not in a buffer.  Types that can't be shared
+      // with the host are only reported where they are shared.
+      match ty.layout(LayoutRules::Storage) {
+        Err(err @ (LayoutError::AlignTooSmall { .. } |
+                   LayoutError::SizeTooSmall { .. } |
+                   LayoutError::TooLarge(_))) =>
+          self.error(err.to_string()),
+        _ => Some(ty),
+      }
+    });
+    self.structs.insert(name.clone(), resolved.clone());
+    resolved
+  }
+
+  /**
+   * Check a declaration's attributes against the allowed names, each
+   * either taking a `u32` argument or none.  Returns the argument of
+   * each attribute present, by name.
+   */
+  fn check_attributes<'x>(&mut self,
+    attributes: &[Attribute<'x>],
+    owner: &str,
+    allowed: &[(&str, bool)],
+  ) -> Option<HashMap<&'x str, Option<u32>>> {
+    let mut values = HashMap::new();
+    let mut ok = true;
+    for attribute in attributes {
+      let name = attribute.name.contents;
+      let Some(&(_, takes_arg)) = allowed.iter().find(|(n, _)| *n == name) else {
+        self.error::<()>(format!("Unknown attribute `@{}` on {}.", name, owner));
+        ok = false;
+        continue;
+      };
+      let value = match (&attribute.arg, takes_arg) {
+        (Some(arg), true) => match self.check_const_expr(arg, TypeModel::new_u32()) {
+          Some(LiteralModel::U32(value)) => Some(value),
+          Some(_) => {
+            self.error::<()>(format!(
+              "Attribute `@{}` on {} needs a `u32` argument, found `{}`.",
+              name, owner, arg
+            ));
+            ok = false;
+            continue;
+          },
+          None => {
+            ok = false;
+            continue;
+          },
+        },
+        (None, false) => None,
+        (Some(_), false) | (None, true) => {
+          self.error::<()>(format!(
+            "Attribute `@{}` on {} {}.",
+            name, owner,
+            if takes_arg { "needs an argument" } else { "takes no argument" }
+          ));
+          ok = false;
+          continue;
+        },
+      };
+      if values.insert(name, value).is_some() {
+        self.error::<()>(format!("Duplicate attribute `@{}` on {}.", name, owner));
+        ok = false;
+      }
+    }
+    ok.then_some(values)
+  }
+
+  /**
+   * Resolve the fields of a struct or uniforms declaration.
+   */
+  fn resolve_struct_fields(&mut self,
+    partial: &ShaderFilePartial,
+    owner: &str,
+    fields: &[StructDeclField],
+  ) -> Option<Vec<StructFieldModel>> {
+    let mut field_models: Vec<StructFieldModel> = Vec::new();
+    let mut ok = true;
+    for (position, field) in fields.iter().enumerate() {
+      let name = self.model_space.intern_name(field.name.contents);
+      if field_models.iter().any(|f| f.name == *name) {
+        self.error::<()>(format!(
+          "Duplicate field `{}` in {}.", field.name.contents, owner
+        ));
+        ok = false;
+        continue;
+      }
+      let ty_ref = TypeRefPartial::from_type_name(&field.ty);
+      let is_last = position + 1 == fields.len();
+      match self.resolve_type_ref(partial, &ty_ref) {
+        Some(ty) if ty.is_void() => {
+          self.error::<()>(format!(
+            "Field `{}` in {} cannot have type `void`.",
+            field.name.contents, owner
+          ));
+          ok = false;
+        },
+        Some(ty) if ty.is_runtime_sized() &&
+          !(is_last && matches!(*ty, TypeModel::Array(_))) =>
+        {
+          self.error::<()>(format!(
+            "Field `{}` in {} has runtime-sized type `{}`; runtime-sized \
+             arrays may only appear as the last member of a buffer type.",
+            field.name.contents, owner, *ty
+          ));
+          ok = false;
+        },
+        Some(ty) => {
+          let field_owner = format!("field `{}` in {}", field.name.contents, owner);
+          match self.check_field_attributes(field, &field_owner, &ty) {
+            Some((align, size)) => field_models.push(StructFieldModel {
+              name: (*name).clone(),
+              ty,
+              align,
+              size,
+            }),
+            None => { ok = false; },
+          }
+        },
+        None => { ok = false; },
+      }
+    }
+    ok.then_some(field_models)
+  }
+
+  /**
+   * Check the `@align` and `@size` attributes of a struct field.
+   */
+  fn check_field_attributes(&mut self,
+    field: &StructDeclField,
+    owner: &str,
+    ty: &TypeModelHandle,
+  ) -> Option<(Option<u32>, Option<u32>)> {
+    let attributes = self.check_attributes(
+      &field.attributes,
+      owner,
+      &[("align", true), ("size", true)],
+    )?;
+    let align = attributes.get("align").copied().flatten();
+    let size = attributes.get("size").copied().flatten();
+    if align.is_some_and(|align| !align.is_power_of_two()) {
+      return self.error(format!(
+        "`@align` on {} must be a power of two.", owner
+      ));
+    }
+    if align.is_some_and(|align| align > MAX_FIELD_ALIGN) {
+      return self.error(format!(
+        "`@align` on {} must be at most {}.", owner, MAX_FIELD_ALIGN
+      ));
+    }
+    if size == Some(0) {
+      return self.error(format!("`@size` on {} must be positive.", owner));
+    }
+    if size.is_some() && ty.is_runtime_sized() {
+      return self.error(format!(
+        "`@size` on {} is not allowed for runtime-sized type `{}`.", owner, **ty
+      ));
+    }
+    Some((align, size))
+  }
+
+  /**
+   * Resolve a type reference to an interned type.
+   */
+  fn resolve_type_ref(&mut self,
+    partial: &ShaderFilePartial,
+    ty_ref: &TypeRefPartial,
+  ) -> Option<TypeModelHandle> {
+    match ty_ref {
+      TypeRefPartial::Model(model) => {
+        self.check_type_supported(model)?;
+        Some(self.intern_type(model.clone()))
+      },
+      TypeRefPartial::Path(path) => {
+        let is_struct = path.is_single() && matches!(
+          partial.declarations.get(path.parts[0].contents),
+          Some(ShaderFileDeclarationPartial::Struct(_))
+        );
+        if !is_struct {
+          let path_str = path.parts.iter()
+            .map(|part| part.contents)
+            .collect::<Vec<_>>()
+            .join("::");
+          return self.error(format!("Unknown type `{}`.", path_str));
+        }
+        let name = self.model_space.intern_name(path.parts[0].contents);
+        self.resolve_struct(partial, &name)
+      },
+      TypeRefPartial::Array { elem, len } => {
+        let elem = self.resolve_type_ref(partial, elem)?;
+        if elem.is_void() || elem.is_runtime_sized() {
+          return self.error(format!(
+            "Arrays cannot have elements of type `{}`.", *elem
+          ));
+        }
+        let len = match len {
+          Some(len) => match self.check_const_expr(len, TypeModel::new_u32())? {
+            LiteralModel::U32(value) if value > 0 => Some(value),
+            _ => return self.error(format!(
+              "Array length `{}` must be a positive `u32`.", len
+            )),
+          },
+          None => None,
+        };
+        Some(self.intern_type(TypeModel::new_array(elem, len)))
+      },
+    }
+  }
+
+  /**
+   * Check that a built-in type is supported by the target's capabilities.
+   */
+  fn check_type_supported(&mut self, ty: &TypeModel) -> Option<()> {
+    let scalar = match ty {
+      TypeModel::Scalar(ScalarTypeModel::Numeric(scalar)) => *scalar,
+      TypeModel::Vector(vector_ty) => vector_ty.scalar,
+      TypeModel::Matrix(matrix_ty) => matrix_ty.scalar,
+      _ => return Some(()),
+    };
+    let (supported, capability) = match scalar {
+      ScalarNumericTypeModel::F16 => (self.capabilities.shader_f16, "shader_f16"),
+      ScalarNumericTypeModel::I64 | ScalarNumericTypeModel::U64 =>
+        (self.capabilities.shader_int64, "shader_int64"),
+      _ => return Some(()),
+    };
+    if !supported {
+      return self.error(format!(
+        "Type `{}` requires the `{}` capability, which the target does not \
+         support.",
+        ty, capability
+      ));
+    }
+    Some(())
+  }
+
+  /**
+   * Check that a type is not runtime-sized.
+   */
+  fn check_sized(&mut self, ty: TypeModelHandle, context: &str)
+    -> Option<TypeModelHandle>
+  {
+    if ty.is_runtime_sized() {
+      return self.error(format!(
+        "Runtime-sized type `{}` cannot be used as {}; runtime-sized arrays \
+         may only appear as the last member of a buffer type.",
+        *ty, context
+      ));
+    }
+    Some(ty)
+  }
+
+  /**
+   * Assign a group and binding to the uniforms and each buffer.
+   * Declared bindings are kept as given.  The rest take the lowest free
+   * binding in their `@group`, or group 0 if none is declared; the
+   * uniforms are assigned first, then buffers in declaration order.
+   */
+  fn assign_bindings(&mut self, partial: &ShaderFilePartial)
+    -> ResourceBindings
+  {
+    const ALLOWED: &[(&str, bool)] = &[("group", true), ("binding", true)];
+
+    // The requested group and binding of each resource whose
+    // attributes are valid, keyed by buffer name or `None` for the
+    // uniforms.
+    let mut requests = Vec::new();
+    if let Some(uniforms_decl) = &partial.uniforms {
+      let attributes = &uniforms_decl.syntax_decl.attributes;
+      if let Some(values) = self.check_attributes(attributes, "uniforms", ALLOWED) {
+        requests.push((None, "uniforms".to_string(), values));
+      }
+    }
+    for decl in partial.declarations_in_order() {
+      if let ShaderFileDeclarationPartial::Buffer(buffer_decl) = decl {
+        let owner = format!("buffer `{}`", buffer_decl.name.name);
+        if let Some(values) = self.check_attributes(&buffer_decl.attributes, &owner, ALLOWED) {
+          requests.push((Some(buffer_decl.name.clone()), owner, values));
+        }
+      }
+    }
+
+    let mut bindings = ResourceBindings::default();
+    let mut used: HashMap<ResourceBindingModel, String> = HashMap::new();
+    let mut assign = |key: Option<NameModelHandle>, binding| match key {
+      Some(name) => { bindings.buffers.insert(name, binding); },
+      None => { bindings.uniforms = Some(binding); },
+    };
+
+    for (key, owner, values) in &requests {
+      let group = values.get("group").copied().flatten().unwrap_or(0);
+      let Some(binding) = values.get("binding").copied().flatten() else {
+        continue;
+      };
+      let binding = ResourceBindingModel { group, binding };
+      if let Some(other) = used.get(&binding) {
+        self.error::<()>(format!(
+          "Binding `@group({}) @binding({})` of {} is already used by {}.",
+          group, binding.binding, owner, other
+        ));
+        continue;
+      }
+      used.insert(binding, owner.clone());
+      assign(key.clone(), binding);
+    }
+    for (key, owner, values) in requests {
+      if values.contains_key("binding") {
+        continue;
+      }
+      let group = values.get("group").copied().flatten().unwrap_or(0);
+      let binding = (0..)
+        .map(|binding| ResourceBindingModel { group, binding })
+        .find(|binding| !used.contains_key(binding))
+        .expect("a group has a free binding");
+      used.insert(binding, owner);
+      assign(key, binding);
+    }
+    bindings
+  }
+
+  /**
+   * Check the uniforms declaration.
+   */
+  fn check_uniforms_decl(&mut self,
+    partial: &ShaderFilePartial,
+    uniforms_decl: &UniformsDeclPartial,
+    binding: Option<ResourceBindingModel>,
+  ) -> Option<UniformsModel> {
+    let fields = self.resolve_struct_fields(
+      partial,
+      "uniforms",
+      &uniforms_decl.syntax_decl.fields,
+    )?;
+    if fields.last().is_some_and(|field| field.ty.is_runtime_sized()) {
+      return self.error(
+        "Uniforms cannot contain runtime-sized arrays; they may only appear \
+         as the last member of a buffer type."
+      );
+    }
+    let name = self.model_space.intern_name("Uniforms");
+    let path = self.model_space.intern_name_path(vec![name]);
+    let ty = self.intern_type(TypeModel::Struct(StructTypeModel {
+      name: (*path).clone(),
+      fields,
+      packed: false,
+    }));
+    if let Err(err) = ty.layout(LayoutRules::Uniform) {
+      return self.error(format!("Uniforms cannot be laid out: {}", err));
+    }
+    Some(UniformsModel { ty, binding: binding? })
+  }
+
+  /**
+   * Check a buffer declaration.
+   */
+  fn check_buffer_decl(&mut self,
+    partial: &ShaderFilePartial,
+    buffer_decl: &BufferDeclPartial,
+    binding: Option<ResourceBindingModel>,
+  ) -> Option<BufferModel> {
+    let declared_ty = self.resolve_type_ref(partial, &buffer_decl.ty)?;
+    if declared_ty.is_void() {
+      return self.error(format!(
+        "Buffer `{}` cannot have elements of type `void`.",
+        buffer_decl.name.name
+      ));
+    }
+    // A sized type declares the buffer's elements.
+    let ty = if declared_ty.is_runtime_sized() {
+      declared_ty
+    } else {
+      self.intern_type(TypeModel::new_array(declared_ty, None))
+    };
+    if let Err(err) = ty.layout(LayoutRules::Storage) {
+      return self.error(format!(
+        "Buffer `{}` cannot be laid out: {}", buffer_decl.name.name, err
+      ));
+    }
+    Some(BufferModel {
+      name: buffer_decl.name.clone(),
+      mode: BufferAccessMode::from_decl_mode(buffer_decl.mode),
+      ty,
+      binding: binding?,
+    })
+  }
+
+  /**
+   * Check the argument and return types of a function.
+   */
+  fn check_func_signature(&mut self,
+    partial: &ShaderFilePartial,
+    func_decl: &FuncDeclPartial,
+  ) -> Option<FuncSignature> {
+    let mut args = Vec::new();
+    for arg in &func_decl.args {
+      let ty = self.resolve_type_ref(partial, &arg.ty)?;
+      let ty = self.check_sized(ty, &format!("argument `{}`", arg.name.name))?;
+      args.push(FuncArgModel { name: arg.name.clone(), ty });
+    }
+    let return_ty = match &func_decl.return_ty {
+      Some(ty_ref) => {
+        let ty = self.resolve_type_ref(partial, ty_ref)?;
+        self.check_sized(ty, "a return type")?
+      },
+      None => self.intern_type(TypeModel::new_void()),
+    };
+    Some(FuncSignature { args, return_ty })
+  }
+
+  /**
+   * Check a function declaration's body, which must return a value on
+   * every path if the function has a return type.
+   */
+  fn check_func_decl(&mut self, func_decl: &FuncDeclPartial)
+    -> Option<FuncModel>
+  {
+    let signature = self.funcs.get(&func_decl.name)?.clone();
+    let locals = signature.args.iter()
+      .map(|arg| (arg.name.clone(), arg.ty.clone()))
+      .collect::<Vec<_>>();
+    let errors = self.diagnostics.len();
+    let body = self.check_body(
+      locals,
+      signature.return_ty.clone(),
+      &func_decl.body,
+    );
+    // Statements in error are left out of the body, so it is only known
+    // to end if it has none.
+    let checked = self.diagnostics.len() == errors;
+    if checked && !signature.return_ty.is_void() && !Self::always_returns(&body) {
+      self.error::<()>(format!(
+        "Function `{}` may end without returning a value.", func_decl.name.name
+      ));
+    }
+    Some(FuncModel {
+      name: func_decl.name.clone(),
+      args: signature.args,
+      return_ty: signature.return_ty,
+      body,
+    })
+  }
+
+  /**
+   * Check an entrypoint declaration's workgroup size and body.
+   */
+  fn check_entrypoint_decl(&mut self, entrypoint_decl: &EntrypointDeclPartial)
+    -> EntrypointModel
+  {
+    let arg_ty = self.intern_type(
+      Self::entrypoint_arg_type(entrypoint_decl.dims)
+    );
+    let void_ty = self.intern_type(TypeModel::new_void());
+    let workgroup_size = self.check_workgroup_size(entrypoint_decl)
+      .unwrap_or(entrypoint_decl.dims.workgroup_size());
+    let body = self.check_body(
+      vec![(entrypoint_decl.arg_name.clone(), arg_ty)],
+      void_ty,
+      &entrypoint_decl.body,
+    );
+    EntrypointModel {
+      name: entrypoint_decl.name.clone(),
+      dims: entrypoint_decl.dims,
+      workgroup_size,
+      arg_name: entrypoint_decl.arg_name.clone(),
+      body,
+    }
+  }
+
+  /**
+   * Check the `@workgroup_size_x`, `@workgroup_size_y` and
+   * `@workgroup_size_z` attributes of an entrypoint, each allowed for an
+   * axis its id has.  Axes without one keep the default size.
+   */
+  fn check_workgroup_size(&mut self, entrypoint_decl: &EntrypointDeclPartial)
+    -> Option<[u32; 3]>
+  {
+    const AXES: [&str; 3] = ["workgroup_size_x", "workgroup_size_y", "workgroup_size_z"];
+    let owner = format!("entrypoint `{}`", entrypoint_decl.name.name);
+    let axes = entrypoint_decl.dims as usize;
+    let allowed = AXES[..axes].iter().map(|&axis| (axis, true)).collect::<Vec<_>>();
+    let attributes = self.check_attributes(&entrypoint_decl.attributes, &owner, &allowed)?;
+
+    let mut size = entrypoint_decl.dims.workgroup_size();
+    for (axis, name) in AXES[..axes].iter().enumerate() {
+      if let Some(&Some(value)) = attributes.get(name) {
+        if value == 0 {
+          return self.error(format!("`@{}` on {} must be positive.", name, owner));
+        }
+        size[axis] = value;
+      }
+    }
+    let invocations = size.iter().try_fold(1_u32, |product, &n| product.checked_mul(n));
+    if invocations.is_none_or(|n| n > MAX_WORKGROUP_INVOCATIONS) {
+      return self.error(format!(
+        "The workgroups of {} have {}x{}x{} invocations, more than the {} allowed.",
+        owner, size[0], size[1], size[2], MAX_WORKGROUP_INVOCATIONS
+      ));
+    }
+    Some(size)
+  }
+
+  /**
+   * Check a test, whose body takes no arguments and returns nothing.
+   */
+  fn check_test_decl(&mut self, test_decl: &TestDeclPartial) -> TestModel {
+    let void_ty = self.intern_type(TypeModel::new_void());
+    self.in_test = true;
+    let body = self.check_body(Vec::new(), void_ty, &test_decl.body);
+    self.in_test = false;
+    TestModel { name: test_decl.name.clone(), body }
+  }
+
+  /**
+   * The type of an entrypoint's invocation id argument.
+   */
+  pub(crate) fn entrypoint_arg_type(dims: EntrypointDims) -> TypeModel {
+    match dims {
+      EntrypointDims::D1 => TypeModel::new_u32(),
+      EntrypointDims::D2 =>
+        TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec2),
+      EntrypointDims::D3 =>
+        TypeModel::new_vector(ScalarNumericTypeModel::U32, VecDims::Vec3),
+    }
+  }
+
+  /**
+   * Check a body with the given immutable arguments in scope.
+   */
+  fn check_body(&mut self,
+    args: Vec<(NameModelHandle, TypeModelHandle)>,
+    return_ty: TypeModelHandle,
+    body: &[StatementBodyPartial],
+  ) -> Vec<StatementModel> {
+    self.return_ty = Some(return_ty);
+    self.push_scope();
+    for (name, ty) in args {
+      self.bind_local(name, Some(ty), false);
+    }
+    let mut statements = Vec::new();
+    for stmt in body {
+      self.check_stmt(&stmt.syntax_stmt, &mut statements);
+    }
+    self.pop_scope();
+    self.return_ty = None;
+    statements
+  }
+
+  fn push_scope(&mut self) {
+    self.scopes.push(HashMap::new());
+  }
+
+  fn pop_scope(&mut self) {
+    self.scopes.pop().expect("Unbalanced scopes");
+  }
+
+  /**
+   * Bind a local name in the innermost scope.
+   */
+  fn bind_local(&mut self,
+    name: NameModelHandle,
+    ty: Option<TypeModelHandle>,
+    mutable: bool,
+  ) {
+    let scope = self.scopes.last_mut().expect("No scope");
+    if scope.contains_key(&name) {
+      self.error::<()>(format!(
+        "`{}` is already defined in this scope.", name.name
+      ));
+      return;
+    }
+    scope.insert(name, LocalBinding { ty, mutable });
+  }
+
+  /**
+   * Look up a local binding by name, innermost scope first.
+   */
+  fn lookup_local(&self, name: &str) -> Option<&LocalBinding> {
+    self.scopes.iter().rev().find_map(|scope| scope.get(name))
+  }
+}
@@ -0,0 +1,177 @@
+use crate::{
+  model::{
+    AssertStmtModel,
+    ExecStmtModel,
+    IfStmtModel,
+    LetStmtModel,
+    LoopStmtModel,
+    MutateStmtModel,
+    RetStmtModel,
+    StatementModel,
+    TypeModel,
+    VarStmtModel,
+  },
+  syntax::statement::{
+    AssertStmt,
+    IfStmt,
+    LetStmt,
+    MutateStmt,
+    RetStmt,
+    Statement,
+    StatementBlock,
+    VarStmt,
+  },
+  transform::type_checker::TypeChecker,
+};
+
+impl<'s> TypeChecker<'s> {
+  /**
+   * Check a statement, appending the checked statements to `out`.
+   */
+  pub(super) fn check_stmt(&mut self,
+    stmt: &Statement,
+    out: &mut Vec<StatementModel>,
+  ) {
+    match stmt {
+      Statement::Let(let_stmt) => self.check_let_stmt(let_stmt, out),
+      Statement::Var(var_stmt) => self.check_var_stmt(var_stmt, out),
+      Statement::Mutate(mutate_stmt) => {
+        out.extend(self.check_mutate_stmt(mutate_stmt));
+      },
+      Statement::Exec(exec_stmt) => {
+        out.extend(
+          self.check_expr(&exec_stmt.expr, None)
+            .map(|expr| StatementModel::Exec(ExecStmtModel { expr }))
+        );
+      },
+      Statement::Ret(ret_stmt) => {
+        out.extend(self.check_ret_stmt(ret_stmt));
+      },
+      Statement::If(if_stmt) => {
+        out.extend(self.check_if_stmt(if_stmt));
+      },
+      Statement::Loop(loop_stmt) => {
+        let block = self.check_block(&loop_stmt.block);
+        out.push(StatementModel::Loop(LoopStmtModel { block }));
+      },
+      Statement::Assert(assert_stmt) => {
+        out.extend(self.check_assert_stmt(assert_stmt));
+      },
+    }
+  }
+
+  /**
+   * Check a nested block in its own scope.
+   */
+  fn check_block(&mut self, block: &StatementBlock) -> Vec<StatementModel> {
+    self.push_scope();
+    let mut statements = Vec::new();
+    for stmt in &block.statements {
+      self.check_stmt(stmt, &mut statements);
+    }
+    self.pop_scope();
+    statements
+  }
+
+  fn check_let_stmt(&mut self,
+    let_stmt: &LetStmt,
+    out: &mut Vec<StatementModel>,
+  ) {
+    for piece in &let_stmt.pieces {
+      let name = self.model_space.intern_name(piece.name.contents);
+      let value = self.check_expr(&piece.value, None)
+        .and_then(|value| self.check_bindable(&name.name, value));
+      self.bind_local(name.clone(), value.as_ref().map(|v| v.ty.clone()), false);
+      if let Some(value) = value {
+        out.push(StatementModel::Let(LetStmtModel { name, value }));
+      }
+    }
+  }
+
+  fn check_var_stmt(&mut self,
+    var_stmt: &VarStmt,
+    out: &mut Vec<StatementModel>,
+  ) {
+    for piece in &var_stmt.pieces {
+      let name = self.model_space.intern_name(piece.name.contents);
+      let value = match &piece.value {
+        Some(value) => self.check_expr(value, None)
+          .and_then(|value| self.check_bindable(&name.name, value)),
+        None => self.error(format!(
+          "Variable `{}` requires an initial value.", name.name
+        )),
+      };
+      self.bind_local(name.clone(), value.as_ref().map(|v| v.ty.clone()), true);
+      if let Some(value) = value {
+        out.push(StatementModel::Var(VarStmtModel { name, value }));
+      }
+    }
+  }
+
+  fn check_mutate_stmt(&mut self, mutate_stmt: &MutateStmt)
+    -> Option<StatementModel>
+  {
+    let lvalue = self.check_place(&mutate_stmt.lvalue)?;
+    if lvalue.ty.is_runtime_sized() {
+      return self.error(format!(
+        "Cannot mutate a value of runtime-sized type `{}`.", *lvalue.ty
+      ));
+    }
+    let value = self.check_expr(&mutate_stmt.expr, Some(&lvalue.ty))?;
+    self.check_type_matches(&lvalue.ty, &value.ty, "assignment")?;
+    Some(StatementModel::Mutate(MutateStmtModel { lvalue, value }))
+  }
+
+  fn check_ret_stmt(&mut self, ret_stmt: &RetStmt) -> Option<StatementModel> {
+    let return_ty = self.return_ty.clone().expect("No return type");
+    let value = match &ret_stmt.value {
+      Some(value) => {
+        let value = self.check_expr(value, Some(&return_ty))?;
+        self.check_type_matches(&return_ty, &value.ty, "return value")?;
+        Some(value)
+      },
+      None if return_ty.is_void() => None,
+      None => return self.error(format!(
+        "Missing return value of type `{}`.", *return_ty
+      )),
+    };
+    Some(StatementModel::Ret(RetStmtModel { value }))
+  }
+
+  fn check_if_stmt(&mut self, if_stmt: &IfStmt) -> Option<StatementModel> {
+    let bool_ty = self.intern_type(TypeModel::new_bool());
+    let cond = self.check_expr(&if_stmt.cond, Some(&bool_ty))
+      .and_then(|cond| {
+        self.check_type_matches(&bool_ty, &cond.ty, "if condition")?;
+        Some(cond)
+      });
+    let if_block = self.check_block(&if_stmt.if_block);
+    let else_block = if_stmt.else_block.as_ref()
+      .map(|block| self.check_block(block));
+    Some(StatementModel::If(IfStmtModel { cond: cond?, if_block, else_block }))
+  }
+
+  fn check_assert_stmt(&mut self, assert_stmt: &AssertStmt) -> Option<StatementModel> {
+    if !self.in_test {
+      return self.error("`assert` is only allowed in tests.");
+    }
+    let bool_ty = self.intern_type(TypeModel::new_bool());
+    let cond = self.check_expr(&assert_stmt.cond, Some(&bool_ty))?;
+    self.check_type_matches(&bool_ty, &cond.ty, "assertion")?;
+    Some(StatementModel::Assert(AssertStmtModel { cond, span: assert_stmt.span.clone() }))
+  }
+
+  /**
+   * Whether control never reaches the end of a block.  Loops are only
+   * left by returning.
+   */
+  pub(super) fn always_returns(statements: &[StatementModel]) -> bool {
+    match statements.last() {
+      Some(StatementModel::Ret(_) | StatementModel::Loop(_)) => true,
+      Some(StatementModel::If(if_stmt)) =>
+        Self::always_returns(&if_stmt.if_block) &&
+          if_stmt.else_block.as_deref().is_some_and(Self::always_returns),
+      _ => false,
+    }
+  }
+}